@@ -0,0 +1,133 @@
+// Failures and startup time of recent boots, side by side, so that a unit which started failing after an update (or a
+// boot that got slower) stands out against the boots before it.
+
+use std::collections::HashMap;
+
+/// How many boots back the summary goes
+pub const RECENT_BOOTS: usize = 10;
+
+pub const STARTUP_FINISHED: &str = "b07a249cd024414a82dd00cd181378ff";
+pub const UNIT_FAILED: &str = "d9b373ed55a64feb8242e02dbe79a49c";
+
+/// The fields of the manager's messages the summary is built from
+pub const FIELDS: &[&str] = &[
+  "MESSAGE_ID",
+  "UNIT",
+  "UNIT_RESULT",
+  "JOB_RESULT",
+  "FIRMWARE_USEC",
+  "LOADER_USEC",
+  "KERNEL_USEC",
+  "INITRD_USEC",
+  "USERSPACE_USEC",
+];
+
+/// One line of `journalctl --list-boots`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Boot {
+  /// 0 for the current boot, -1 for the one before, and so on
+  pub index: i32,
+  pub id: String,
+  /// When the boot's first journal entry was written, as journalctl prints it
+  pub started: String,
+}
+
+/// Parse `journalctl --list-boots`. Older versions separate the first and last entry with an em dash, newer ones have
+/// a header line and columns
+pub fn parse_boots(output: &str) -> Vec<Boot> {
+  output
+    .lines()
+    .filter_map(|line| {
+      let mut words = line.split_whitespace();
+      let index = words.next()?.parse().ok()?;
+      let id = words.next().filter(|id| id.len() == 32 && id.chars().all(|c| c.is_ascii_hexdigit()))?;
+      let rest = words.collect::<Vec<_>>().join(" ");
+      let started = match rest.split_once('—') {
+        Some((first, _)) => first.trim().to_string(),
+        None => rest.split(' ').take(4).collect::<Vec<_>>().join(" "),
+      };
+      Some(Boot { index, id: id.to_string(), started })
+    })
+    .collect()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BootSummary {
+  /// From firmware to the default target being reached; `None` if the boot never got there
+  pub startup_usec: Option<u64>,
+  /// Units that failed before startup finished, with why: `timeout`, `exit-code`, `dependency`...
+  pub failures: Vec<(String, String)>,
+}
+
+impl BootSummary {
+  pub fn failed(&self, unit: &str) -> bool {
+    self.failures.iter().any(|(u, _)| u == unit)
+  }
+}
+
+/// Summarize one boot from the manager's startup-finished and unit-failed messages (`journalctl --output=export`)
+pub fn summarize(entries: &[HashMap<String, String>]) -> BootSummary {
+  let timestamp = |e: &HashMap<String, String>| e.get("__REALTIME_TIMESTAMP").and_then(|t| t.parse::<u64>().ok());
+  let finished = entries.iter().find(|e| e.get("MESSAGE_ID").is_some_and(|id| id == STARTUP_FINISHED));
+  let startup_usec = finished.map(|e| {
+    ["FIRMWARE_USEC", "LOADER_USEC", "KERNEL_USEC", "INITRD_USEC", "USERSPACE_USEC"]
+      .iter()
+      .filter_map(|f| e.get(*f).and_then(|v| v.parse::<u64>().ok()))
+      .sum()
+  });
+  let finished_at = finished.and_then(timestamp).unwrap_or(u64::MAX);
+
+  let mut failures: Vec<(String, String)> = vec![];
+  for entry in entries {
+    if entry.get("MESSAGE_ID").is_none_or(|id| id != UNIT_FAILED) || timestamp(entry).unwrap_or(0) > finished_at {
+      continue;
+    }
+    let Some(unit) = entry.get("UNIT") else { continue };
+    let result = entry.get("UNIT_RESULT").or(entry.get("JOB_RESULT")).cloned().unwrap_or_else(|| "failed".into());
+    if !failures.iter().any(|(u, _)| u == unit) {
+      failures.push((unit.clone(), result));
+    }
+  }
+  BootSummary { startup_usec, failures }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::systemd::parse_property_blocks;
+
+  #[test]
+  fn test_parse_boots() {
+    let old = "-1 0123456789abcdef0123456789abcdef Mon 2026-10-12 08:00:01 UTC—Mon 2026-10-12 22:10:00 UTC\n";
+    let new = "IDX BOOT ID                          FIRST ENTRY                 LAST ENTRY\n  \
+               0 fedcba9876543210fedcba9876543210 Tue 2026-10-13 08:00:05 UTC Tue 2026-10-13 09:00:00 UTC\n";
+    assert_eq!(
+      parse_boots(old),
+      vec![Boot {
+        index: -1,
+        id: "0123456789abcdef0123456789abcdef".into(),
+        started: "Mon 2026-10-12 08:00:01 UTC".into()
+      }]
+    );
+    let boots = parse_boots(new);
+    assert_eq!(boots.len(), 1);
+    assert_eq!(boots[0].started, "Tue 2026-10-13 08:00:05 UTC");
+  }
+
+  #[test]
+  fn test_summarize() {
+    let output = format!(
+      "__REALTIME_TIMESTAMP=100\nMESSAGE_ID={UNIT_FAILED}\nUNIT=nginx.service\nUNIT_RESULT=timeout\n\n\
+       __REALTIME_TIMESTAMP=150\nMESSAGE_ID={UNIT_FAILED}\nUNIT=web.target\nJOB_RESULT=dependency\n\n\
+       __REALTIME_TIMESTAMP=200\nMESSAGE_ID={STARTUP_FINISHED}\nKERNEL_USEC=2000000\nUSERSPACE_USEC=8000000\n\n\
+       __REALTIME_TIMESTAMP=300\nMESSAGE_ID={UNIT_FAILED}\nUNIT=backup.service\nUNIT_RESULT=exit-code\n"
+    );
+    let summary = summarize(&parse_property_blocks(&output));
+    assert_eq!(summary.startup_usec, Some(10_000_000));
+    assert_eq!(
+      summary.failures,
+      vec![("nginx.service".to_string(), "timeout".to_string()), ("web.target".into(), "dependency".into())]
+    );
+    assert!(!summary.failed("backup.service"));
+  }
+}
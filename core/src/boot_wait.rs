@@ -0,0 +1,212 @@
+// What a unit waited on during this boot: when each of its After= dependencies became ready compared to when the unit
+// got going, and which one actually held it up (the last to become ready before the unit started). Following that
+// one back the same way gives the chain of units the boot waited on, like `systemd-analyze critical-chain`. Every
+// unit's time to start, slowest first, is `systemd-analyze blame`.
+
+use std::collections::{HashMap, HashSet};
+
+/// The manager's "Starting ..." and "Started ..." (or "Reached target ...", "Mounted ...") messages
+pub const UNIT_STARTING: &str = "7d4958e842da4a758f6c1cdc7b36dcc5";
+pub const UNIT_STARTED: &str = "39f53479d3a045ac8e11786248231fbf";
+
+/// Read for every loaded unit. The timestamps are only used for units the journal has nothing on, since they're from
+/// the unit's latest start rather than the one during boot
+pub const PROPERTIES: &[&str] = &["After", "InactiveExitTimestampMonotonic", "ActiveEnterTimestampMonotonic"];
+
+/// Microseconds since the kernel started
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timing {
+  /// When the unit began activating; targets and some mounts never log this
+  pub started: Option<u64>,
+  /// When it became active
+  pub ready: Option<u64>,
+}
+
+impl Timing {
+  pub fn from_properties(properties: &HashMap<String, String>) -> Self {
+    let usec = |name: &str| properties.get(name).and_then(|v| v.parse().ok()).filter(|t| *t > 0);
+    Self { started: usec("InactiveExitTimestampMonotonic"), ready: usec("ActiveEnterTimestampMonotonic") }
+  }
+
+  /// When whatever was waiting for this unit could have gone ahead: as soon as it started if it never logged being
+  /// ready, and for a unit with no start, when it was ready
+  fn start(&self) -> Option<u64> {
+    self.started.or(self.ready)
+  }
+
+  /// How long activating took
+  pub fn took(&self) -> Option<u64> {
+    Some(self.ready?.saturating_sub(self.started?))
+  }
+}
+
+/// How to list the units that started during boot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Order {
+  /// The longest to activate first, like `systemd-analyze blame`
+  #[default]
+  Slowest,
+  /// The first to start first
+  Started,
+}
+
+/// The units that took any time to activate, with their timings
+pub fn startup_times(timings: &HashMap<String, Timing>, order: Order) -> Vec<(&str, Timing)> {
+  let mut times: Vec<(&str, Timing)> =
+    timings.iter().filter(|(_, t)| t.took().is_some()).map(|(unit, t)| (unit.as_str(), *t)).collect();
+  match order {
+    Order::Slowest => times.sort_by(|(a, ta), (b, tb)| tb.took().cmp(&ta.took()).then_with(|| a.cmp(b))),
+    Order::Started => times.sort_by(|(a, ta), (b, tb)| ta.start().cmp(&tb.start()).then_with(|| a.cmp(b))),
+  }
+  times
+}
+
+/// The first starting and started message of each unit, from `journalctl --output=export` entries. `unit_field` is
+/// `UNIT` for the system manager and `USER_UNIT` for a user manager
+pub fn from_journal(entries: &[HashMap<String, String>], unit_field: &str) -> HashMap<String, Timing> {
+  let mut timings: HashMap<String, Timing> = HashMap::new();
+  for entry in entries {
+    let (Some(unit), Some(at)) =
+      (entry.get(unit_field), entry.get("__MONOTONIC_TIMESTAMP").and_then(|t| t.parse::<u64>().ok()))
+    else {
+      continue;
+    };
+    let timing = timings.entry(unit.clone()).or_default();
+    match entry.get("MESSAGE_ID").map(String::as_str) {
+      Some(UNIT_STARTING) => {
+        timing.started.get_or_insert(at);
+      },
+      Some(UNIT_STARTED) => {
+        timing.ready.get_or_insert(at);
+      },
+      _ => {},
+    }
+  }
+  timings
+}
+
+/// One After= dependency and how long before the unit started it was ready
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wait {
+  pub unit: String,
+  pub ready: u64,
+  /// `None` when it was ready only after the unit started, so it didn't hold anything up
+  pub slack: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Analysis {
+  pub timing: Timing,
+  /// Dependencies that became ready during boot, the last to be ready first
+  pub waits: Vec<Wait>,
+  /// The unit, what held it up, what held that up, and so on
+  pub chain: Vec<(String, Timing)>,
+}
+
+/// `after` is each unit's After= list. `None` if the unit didn't start during this boot
+pub fn analyze(
+  unit: &str,
+  after: &HashMap<String, Vec<String>>,
+  timings: &HashMap<String, Timing>,
+) -> Option<Analysis> {
+  let timing = *timings.get(unit)?;
+  let start = timing.start()?;
+
+  let mut waits = after
+    .get(unit)
+    .into_iter()
+    .flatten()
+    .filter_map(|dependency| {
+      let ready = timings.get(dependency)?.ready?;
+      Some(Wait { unit: dependency.clone(), ready, slack: start.checked_sub(ready) })
+    })
+    .collect::<Vec<_>>();
+  waits.sort_by(|a, b| b.ready.cmp(&a.ready).then_with(|| a.unit.cmp(&b.unit)));
+
+  let mut chain = vec![(unit.to_string(), timing)];
+  let mut seen = HashSet::from([unit.to_string()]);
+  while let Some(blocker) = blocker(&chain.last()?.0, after, timings) {
+    if !seen.insert(blocker.clone()) {
+      break;
+    }
+    chain.push((blocker.clone(), timings[&blocker]));
+  }
+  Some(Analysis { timing, waits, chain })
+}
+
+/// The After= dependency of `unit` that was ready last before `unit` started
+fn blocker(unit: &str, after: &HashMap<String, Vec<String>>, timings: &HashMap<String, Timing>) -> Option<String> {
+  let start = timings.get(unit)?.start()?;
+  after
+    .get(unit)?
+    .iter()
+    .filter_map(|dependency| Some((dependency, timings.get(dependency)?.ready?)))
+    .filter(|(_, ready)| *ready <= start)
+    .max_by_key(|(_, ready)| *ready)
+    .map(|(dependency, _)| dependency.clone())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_from_journal() {
+    let entry = |unit: &str, id: &str, at: &str| {
+      HashMap::from([
+        ("UNIT".to_string(), unit.to_string()),
+        ("MESSAGE_ID".to_string(), id.to_string()),
+        ("__MONOTONIC_TIMESTAMP".to_string(), at.to_string()),
+      ])
+    };
+    let timings = from_journal(
+      &[
+        entry("nginx.service", UNIT_STARTING, "4000000"),
+        entry("nginx.service", UNIT_STARTED, "4300000"),
+        // a restart later on doesn't count
+        entry("nginx.service", UNIT_STARTING, "90000000"),
+        entry("network-online.target", UNIT_STARTED, "3900000"),
+      ],
+      "UNIT",
+    );
+    assert_eq!(timings["nginx.service"], Timing { started: Some(4_000_000), ready: Some(4_300_000) });
+    assert_eq!(timings["network-online.target"], Timing { started: None, ready: Some(3_900_000) });
+  }
+
+  #[test]
+  fn test_analyze() {
+    let timing = |started, ready| Timing { started, ready: Some(ready) };
+    let timings = HashMap::from([
+      ("nginx.service".to_string(), timing(Some(4_000_000), 4_300_000)),
+      ("network-online.target".to_string(), timing(None, 3_900_000)),
+      ("wait-online.service".to_string(), timing(Some(1_000_000), 3_800_000)),
+      ("local-fs.target".to_string(), timing(None, 1_000_000)),
+      ("late.service".to_string(), timing(Some(4_100_000), 5_000_000)),
+    ]);
+    let after = HashMap::from([
+      (
+        "nginx.service".to_string(),
+        vec!["local-fs.target".into(), "network-online.target".into(), "late.service".into(), "gone.service".into()],
+      ),
+      ("network-online.target".to_string(), vec!["wait-online.service".into()]),
+      ("wait-online.service".to_string(), vec!["local-fs.target".into()]),
+    ]);
+
+    let analysis = analyze("nginx.service", &after, &timings).unwrap();
+    assert_eq!(analysis.timing.took(), Some(300_000));
+    let waits = analysis.waits.iter().map(|w| (w.unit.as_str(), w.slack)).collect::<Vec<_>>();
+    assert_eq!(
+      waits,
+      [("late.service", None), ("network-online.target", Some(100_000)), ("local-fs.target", Some(3_000_000))]
+    );
+    let chain = analysis.chain.iter().map(|(unit, _)| unit.as_str()).collect::<Vec<_>>();
+    assert_eq!(chain, ["nginx.service", "network-online.target", "wait-online.service", "local-fs.target"]);
+
+    assert_eq!(analyze("gone.service", &after, &timings), None);
+
+    let slowest = startup_times(&timings, Order::Slowest).iter().map(|(unit, _)| *unit).collect::<Vec<_>>();
+    assert_eq!(slowest, ["wait-online.service", "late.service", "nginx.service"]);
+    let started = startup_times(&timings, Order::Started).iter().map(|(unit, _)| *unit).collect::<Vec<_>>();
+    assert_eq!(started, ["wait-online.service", "nginx.service", "late.service"]);
+  }
+}
@@ -0,0 +1,237 @@
+// Reading journal entries with their fields rather than as finished lines of text, so the log pane can tell how
+// important each one is. journalctl's export format is used: one `FIELD=value` per line and an empty line after each
+// entry, except that values with newlines or other control characters come as the field name on its own line, a
+// little-endian 64-bit length, and then that many bytes. Each line keeps the fields of the entry it's from, so they
+// can be looked at and filtered on.
+
+use std::{
+  collections::{BTreeMap, HashMap},
+  process::Stdio,
+  sync::Arc,
+};
+
+use anyhow::{Context, Result};
+use chrono::{Local, TimeZone};
+use tokio::{
+  io::AsyncReadExt,
+  process::{Child, ChildStdout},
+};
+
+use crate::{remote, systemd};
+
+/// syslog priority names, by number
+pub const PRIORITIES: &[&str] = &["emerg", "alert", "crit", "err", "warning", "notice", "info", "debug"];
+
+/// The priority filters the log pane cycles through: everything, then info, warning and err and above
+pub const PRIORITY_FILTERS: &[Option<u8>] = &[None, Some(6), Some(4), Some(3)];
+
+pub fn next_priority_filter(current: Option<u8>) -> Option<u8> {
+  let index = PRIORITY_FILTERS.iter().position(|p| *p == current).unwrap_or(0);
+  PRIORITY_FILTERS[(index + 1) % PRIORITY_FILTERS.len()]
+}
+
+/// `warning+`, for the log pane title
+pub fn priority_filter_label(priority: u8) -> String {
+  format!("{}+", PRIORITIES.get(priority as usize).unwrap_or(&"debug"))
+}
+
+/// One line of the log pane
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+  /// Laid out like journalctl's `short-iso` output: `2025-04-26T06:04:45-07:00 host nginx[812]: message`
+  pub text: String,
+  /// `None` for lines that aren't journal entries of their own, like notes about the logs
+  pub priority: Option<u8>,
+  /// Every field of the entry, shared by all of its lines. `None` like `priority`
+  pub fields: Option<Arc<BTreeMap<String, String>>>,
+}
+
+impl Entry {
+  pub fn plain(text: impl Into<String>) -> Self {
+    Self { text: text.into(), priority: None, fields: None }
+  }
+
+  /// The lines an entry takes up. Messages spanning several lines are continued on lines of their own, indented to
+  /// line up under the first
+  pub fn from_fields(fields: &HashMap<String, String>) -> Vec<Self> {
+    let priority = fields.get("PRIORITY").and_then(|p| p.parse().ok());
+    let timestamp = fields
+      .get("__REALTIME_TIMESTAMP")
+      .and_then(|us| us.parse().ok())
+      .and_then(|us| Local.timestamp_micros(us).single())
+      .map(|at| at.format("%Y-%m-%dT%H:%M:%S%:z").to_string())
+      .unwrap_or_default();
+    let host = fields.get("_HOSTNAME").map_or("localhost", String::as_str);
+    let identifier = fields.get("SYSLOG_IDENTIFIER").or(fields.get("_COMM")).map_or("unknown", String::as_str);
+    let header = match fields.get("SYSLOG_PID").or(fields.get("_PID")) {
+      Some(pid) => format!("{timestamp} {host} {identifier}[{pid}]: "),
+      None => format!("{timestamp} {host} {identifier}: "),
+    };
+    let message = fields.get("MESSAGE").map_or("", String::as_str);
+    let mut lines = message.trim_end_matches('\n').split('\n');
+    let kept = Some(Arc::new(fields.iter().map(|(k, v)| (k.clone(), v.clone())).collect()));
+    let line = |text| Self { text, priority, fields: kept.clone() };
+    let first = line(format!("{header}{}", lines.next().unwrap_or_default()));
+    let indent = " ".repeat(header.chars().count());
+    std::iter::once(first).chain(lines.map(|l| line(format!("{indent}{l}")))).collect()
+  }
+}
+
+impl AsRef<str> for Entry {
+  fn as_ref(&self) -> &str {
+    &self.text
+  }
+}
+
+/// Reads `journalctl --output=export` as it arrives, in chunks that can end anywhere
+#[derive(Debug, Default)]
+pub struct ExportParser {
+  buffer: Vec<u8>,
+  fields: HashMap<String, String>,
+}
+
+impl ExportParser {
+  /// The entries completed by `data`
+  pub fn feed(&mut self, data: &[u8]) -> Vec<HashMap<String, String>> {
+    self.buffer.extend_from_slice(data);
+    let mut entries = vec![];
+    let mut at = 0;
+    while let Some(newline) = self.buffer[at..].iter().position(|b| *b == b'\n').map(|i| at + i) {
+      let line = &self.buffer[at..newline];
+      if line.is_empty() {
+        if !self.fields.is_empty() {
+          entries.push(std::mem::take(&mut self.fields));
+        }
+        at = newline + 1;
+      } else if let Some(equals) = line.iter().position(|b| *b == b'=') {
+        let name = String::from_utf8_lossy(&line[..equals]).to_string();
+        self.fields.insert(name, String::from_utf8_lossy(&line[equals + 1..]).to_string());
+        at = newline + 1;
+      } else {
+        let start = newline + 1 + 8;
+        let Some(length) = self.buffer.get(newline + 1..start) else { break };
+        let end = start + u64::from_le_bytes(length.try_into().unwrap()) as usize;
+        // the value and the newline after it
+        if self.buffer.len() <= end {
+          break;
+        }
+        let name = String::from_utf8_lossy(line).to_string();
+        self.fields.insert(name, String::from_utf8_lossy(&self.buffer[start..end]).to_string());
+        at = end + 1;
+      }
+    }
+    self.buffer.drain(..at);
+    entries
+  }
+}
+
+/// The fields naming the unit an entry is about, most specific first: the manager's own messages about a unit (e.g.
+/// "Started nginx.service") carry `UNIT=` but come from PID 1, so they're checked before the sender's unit
+pub const UNIT_FIELDS: &[&str] = &["UNIT", "USER_UNIT", "_SYSTEMD_USER_UNIT", "_SYSTEMD_UNIT", "COREDUMP_UNIT"];
+
+/// The units an entry names, in the order of `UNIT_FIELDS`
+pub fn units_of(fields: &HashMap<String, String>) -> impl Iterator<Item = &str> {
+  UNIT_FIELDS.iter().filter_map(|f| fields.get(*f)).map(String::as_str)
+}
+
+/// The lines of all the entries in a complete export
+pub fn parse_export(data: &[u8]) -> Vec<Entry> {
+  ExportParser::default().feed(data).iter().flat_map(Entry::from_fields).collect()
+}
+
+/// New journal entries as journalctl writes them. Dropping it stops journalctl
+pub struct Follow {
+  // kept so journalctl (or the ssh running it) goes when this does
+  _child: Child,
+  stdout: ChildStdout,
+  parser: ExportParser,
+  chunk: Vec<u8>,
+}
+
+impl Follow {
+  /// Follow what `matches` selects under the `scope` manager, e.g. `["-u", "nginx.service"]`: entries from `since`
+  /// (in seconds since the epoch) on, or only those written from now on
+  pub fn spawn(scope: systemd::UnitScope, matches: &[String], since: Option<i64>) -> Result<Self> {
+    let mut journalctl = systemd::journalctl();
+    journalctl.args(matches).args(["--output=export", "--follow", "--quiet"]);
+    match since {
+      Some(since) => journalctl.arg(format!("--since=@{since}")),
+      None => journalctl.arg("--lines=0"),
+    };
+    systemd::in_scope(&mut journalctl, scope);
+    remote::on_host(&mut journalctl);
+
+    let mut command = tokio::process::Command::from(journalctl);
+    command.stdout(Stdio::piped()).stderr(Stdio::null()).kill_on_drop(true);
+    let mut child = command.spawn().context("Failed to run journalctl")?;
+    let stdout = child.stdout.take().context("journalctl has no output to read")?;
+    Ok(Self { _child: child, stdout, parser: ExportParser::default(), chunk: vec![0; 64 * 1024] })
+  }
+
+  /// The lines of the entries completed by the next read, which may be none; `None` once journalctl has exited.
+  /// Safe to cancel, e.g. in `tokio::select!`: nothing is lost
+  pub async fn next(&mut self) -> Option<Vec<Entry>> {
+    Some(self.next_fields().await?.iter().flat_map(Entry::from_fields).collect())
+  }
+
+  /// Like `next`, but the entries' fields rather than their lines, for telling apart where they came from
+  pub async fn next_fields(&mut self) -> Option<Vec<HashMap<String, String>>> {
+    match self.stdout.read(&mut self.chunk).await {
+      Ok(0) | Err(_) => None,
+      Ok(read) => Some(self.parser.feed(&self.chunk[..read])),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_export() {
+    let mut data = b"__REALTIME_TIMESTAMP=1745672685000000\n_HOSTNAME=web1\nSYSLOG_IDENTIFIER=nginx\n_PID=812\n\
+      PRIORITY=3\nMESSAGE=bind() failed\n\n"
+      .to_vec();
+    data.extend(b"__REALTIME_TIMESTAMP=1745672686000000\n_HOSTNAME=web1\n_COMM=nginx\nPRIORITY=6\nMESSAGE\n");
+    data.extend(12u64.to_le_bytes());
+    data.extend(b"first\nsecond\n\n");
+
+    // split anywhere, including inside the length of the binary field
+    let mut parser = ExportParser::default();
+    let split = data.len() - 20;
+    let mut entries = parser.feed(&data[..split]);
+    assert_eq!(entries.len(), 1);
+    entries.extend(parser.feed(&data[split..]));
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[1]["MESSAGE"], "first\nsecond");
+
+    let lines = parse_export(&data);
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].text.ends_with(" web1 nginx[812]: bind() failed"));
+    assert_eq!(lines[0].priority, Some(3));
+    assert!(lines[1].text.ends_with(" web1 nginx: first"));
+    assert_eq!(lines[2].text.trim_start(), "second");
+    assert_eq!(lines[2].text.len(), lines[1].text.len() + 1);
+    assert_eq!(lines[2].priority, Some(6));
+    // the continuation shares its entry's fields
+    assert_eq!(lines[0].fields.as_ref().unwrap()["_PID"], "812");
+    assert_eq!(lines[2].fields, lines[1].fields);
+    assert_eq!(lines[1].fields.as_ref().unwrap()["_COMM"], "nginx");
+  }
+
+  #[test]
+  fn test_units_of() {
+    let fields: HashMap<String, String> =
+      [("_SYSTEMD_UNIT", "init.scope"), ("UNIT", "nginx.service"), ("MESSAGE", "Started nginx.service")]
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .into();
+    assert_eq!(units_of(&fields).collect::<Vec<_>>(), ["nginx.service", "init.scope"]);
+  }
+
+  #[test]
+  fn test_priority_filters() {
+    assert_eq!(next_priority_filter(None), Some(6));
+    assert_eq!(next_priority_filter(Some(3)), None);
+    assert_eq!(priority_filter_label(4), "warning+");
+  }
+}
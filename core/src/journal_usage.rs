@@ -0,0 +1,79 @@
+// Estimating how much of the journal one unit takes up. journald doesn't track this, so it's the size of the unit's
+// entries as journalctl prints them, which tracks the on-disk share well enough to find the unit writing gigabytes.
+
+/// What one unit has in the journal
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Usage {
+  pub entries: u64,
+  pub bytes: u64,
+  /// Bytes in entries older than the configured retention
+  pub expired_bytes: u64,
+  /// Microseconds since the epoch of the oldest and newest entries
+  pub first_usec: Option<u64>,
+  pub last_usec: Option<u64>,
+}
+
+impl Usage {
+  /// Count one line of `journalctl --output=json`. `cutoff_usec` is when entries start counting as expired
+  pub fn add_entry(&mut self, line: &str, cutoff_usec: Option<u64>) {
+    self.entries += 1;
+    self.bytes += line.len() as u64;
+    let Some(timestamp) = realtime_timestamp(line) else { return };
+    self.first_usec = Some(self.first_usec.map_or(timestamp, |t| t.min(timestamp)));
+    self.last_usec = Some(self.last_usec.map_or(timestamp, |t| t.max(timestamp)));
+    if cutoff_usec.is_some_and(|cutoff| timestamp < cutoff) {
+      self.expired_bytes += line.len() as u64;
+    }
+  }
+
+  /// Average bytes written per day over the span the entries cover (at least an hour, so a single burst isn't
+  /// extrapolated into a flood)
+  pub fn bytes_per_day(&self) -> Option<u64> {
+    const DAY_USEC: u64 = 86_400 * 1_000_000;
+    let span = self.last_usec?.saturating_sub(self.first_usec?).max(DAY_USEC / 24);
+    Some((self.bytes as u128 * DAY_USEC as u128 / span as u128) as u64)
+  }
+}
+
+/// Pull `__REALTIME_TIMESTAMP` out of a JSON entry without parsing the whole thing
+fn realtime_timestamp(line: &str) -> Option<u64> {
+  let (_, rest) = line.split_once("\"__REALTIME_TIMESTAMP\":\"")?;
+  rest.split('"').next()?.parse().ok()
+}
+
+/// Parse `journalctl --disk-usage`, e.g. `Archived and active journals take up 3.5G in the file system.`
+pub fn parse_disk_usage(output: &str) -> Option<u64> {
+  let size = output.split(" take up ").nth(1)?.split_whitespace().next()?;
+  let (number, suffix) = size.split_at(size.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(size.len()));
+  let multiplier: u64 = match suffix {
+    "" | "B" => 1,
+    "K" => 1 << 10,
+    "M" => 1 << 20,
+    "G" => 1 << 30,
+    "T" => 1 << 40,
+    _ => return None,
+  };
+  Some((number.parse::<f64>().ok()? * multiplier as f64) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_usage() {
+    let mut usage = Usage::default();
+    usage.add_entry(r#"{"__REALTIME_TIMESTAMP":"1000","MESSAGE":"old"}"#, Some(2000));
+    usage.add_entry(r#"{"__REALTIME_TIMESTAMP":"3000","MESSAGE":"new"}"#, Some(2000));
+    assert_eq!(usage.entries, 2);
+    assert_eq!(usage.expired_bytes, usage.bytes / 2);
+    assert_eq!((usage.first_usec, usage.last_usec), (Some(1000), Some(3000)));
+  }
+
+  #[test]
+  fn test_parse_disk_usage() {
+    assert_eq!(parse_disk_usage("Archived and active journals take up 3.5G in the file system."), Some(3758096384));
+    assert_eq!(parse_disk_usage("No journal files were found."), None);
+    assert_eq!(parse_disk_usage("Archived and active journals take up 8.0M in the file system."), Some(8 << 20));
+  }
+}
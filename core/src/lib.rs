@@ -0,0 +1,48 @@
+//! The systemd and journal layer of systemctl-tui, for other tools (and tests) to build on.
+//!
+//! - Listing units: [`systemd::get_all_services`] for the units matching some patterns, and [`systemd::UnitWatch`]
+//!   to hear about them changing rather than polling.
+//! - Acting on them: [`systemd::start_service`], [`systemd::stop_service`], [`systemd::restart_service`] and the
+//!   rest, each cancellable through a `CancellationToken`, with [`systemd::run_systemctl`] for verbs without a
+//!   function of their own.
+//! - Their logs: [`journal::parse_export`] for a batch read with `journalctl --output=export`, and
+//!   [`journal::Follow`] for entries as they're written.
+//!
+//! Everything goes to the local managers unless [`remote::set_host`] points it at another machine over ssh,
+//! [`machine::set_machine`] at a local container's, or [`user_manager::set_user`] (as root) at another user's
+//! manager.
+//!
+//! ```no_run
+//! use systemctl_tui_core::systemd::{self, Scope};
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! for unit in systemd::get_all_services(Scope::Global, &["*.service".into()]).await? {
+//!   if unit.is_failed() {
+//!     println!("{} failed ({})", unit.name, unit.sub_state);
+//!   }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod boot_history;
+
+pub mod boot_wait;
+
+pub mod journal;
+
+pub mod journal_usage;
+
+pub mod machine;
+
+pub mod power;
+
+pub mod preset;
+
+pub mod remote;
+
+pub mod systemd;
+
+pub mod timers;
+
+pub mod user_manager;
@@ -0,0 +1,99 @@
+// Managing a local container's systemd the way `systemctl -M` does: containers registered with systemd-machined
+// (systemd-nspawn ones, and others that register) run their own manager and journal. Its D-Bus API is reached by
+// running systemd-stdio-bridge with `--machine`, and systemctl and journalctl take `--machine` themselves. Only system
+// units can be managed in a container, and only on this machine, not one reached with `--host`.
+
+use std::{path::PathBuf, process::Command, sync::RwLock};
+
+use anyhow::{bail, Context, Result};
+use zbus::address::{transport::Transport, transport::Unixexec, Address};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Machine {
+  pub name: String,
+  /// `container` or `vm`
+  pub class: String,
+  /// What runs it, e.g. `systemd-nspawn`
+  pub service: String,
+}
+
+static MACHINE: RwLock<Option<String>> = RwLock::new(None);
+
+/// The container everything runs against, `None` for this machine
+pub fn machine() -> Option<String> {
+  MACHINE.read().unwrap().clone()
+}
+
+pub fn set_machine(machine: Option<String>) {
+  *MACHINE.write().unwrap() = machine;
+}
+
+/// The containers machined knows of. Virtual machines are left out, since `-M` can't reach into them
+pub fn list() -> Result<Vec<Machine>> {
+  let output = Command::new("machinectl")
+    .args(["list", "--no-legend", "--no-pager"])
+    .output()
+    .context("Failed to run machinectl")?;
+  if !output.status.success() {
+    bail!("Failed to list containers: {}", String::from_utf8_lossy(&output.stderr).trim());
+  }
+  Ok(parse_machines(&String::from_utf8_lossy(&output.stdout)).into_iter().filter(|m| m.class == "container").collect())
+}
+
+/// `machinectl list --no-legend` lines: the name, class and service first, then columns that vary by systemd version
+pub fn parse_machines(output: &str) -> Vec<Machine> {
+  output
+    .lines()
+    .filter_map(|line| {
+      let mut columns = line.split_whitespace();
+      Some(Machine {
+        name: columns.next()?.to_string(),
+        class: columns.next()?.to_string(),
+        service: columns.next()?.to_string(),
+      })
+    })
+    .collect()
+}
+
+/// Where the container's root directory can be read from here: its leader process's view of `/`. Only root can
+/// read it, like the rest of the container
+pub fn root(name: &str) -> Result<PathBuf> {
+  let output = Command::new("machinectl")
+    .args(["show", "--property=Leader", "--value", "--", name])
+    .output()
+    .context("Failed to run machinectl")?;
+  let leader = String::from_utf8_lossy(&output.stdout).trim().parse::<u32>().ok().filter(|_| output.status.success());
+  match leader {
+    Some(leader) => Ok(PathBuf::from(format!("/proc/{leader}/root"))),
+    None => bail!("Failed to find {name}'s root directory: {}", String::from_utf8_lossy(&output.stderr).trim()),
+  }
+}
+
+/// The arguments pointing systemctl or journalctl at the container, if there is one
+pub fn args() -> Vec<String> {
+  machine().map(|name| vec![format!("--machine={name}")]).unwrap_or_default()
+}
+
+/// Where to reach the container's system bus
+pub fn bus_address(name: &str) -> Address {
+  let args = vec![format!("--machine={name}").into()];
+  Address::from(Transport::Unixexec(Unixexec::new("systemd-stdio-bridge".into(), None, args)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_machines() {
+    let output = "fedora container systemd-nspawn fedora 40 10.0.0.2…\nwin11  vm        libvirt-qemu -       -  -\n";
+    let machines = parse_machines(output);
+    assert_eq!(machines.len(), 2);
+    assert_eq!(
+      machines[0],
+      Machine { name: "fedora".into(), class: "container".into(), service: "systemd-nspawn".into() }
+    );
+    assert_eq!(machines[1].class, "vm");
+    assert!(parse_machines("").is_empty());
+  }
+}
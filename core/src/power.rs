@@ -0,0 +1,161 @@
+// Rebooting, powering off or suspending the machine through logind, like `systemctl reboot` does. logind checks the
+// caller is allowed, and since nothing here can answer polkit's questions, a caller that would have to authenticate
+// is turned down instead.
+
+use anyhow::{Context, Result};
+use zbus::proxy;
+
+use crate::{remote, systemd};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerAction {
+  Reboot,
+  PowerOff,
+  Halt,
+  Suspend,
+  Hibernate,
+}
+
+impl PowerAction {
+  pub const ALL: &[PowerAction] =
+    &[PowerAction::Reboot, PowerAction::PowerOff, PowerAction::Halt, PowerAction::Suspend, PowerAction::Hibernate];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      PowerAction::Reboot => "Reboot",
+      PowerAction::PowerOff => "Power off",
+      PowerAction::Halt => "Halt",
+      PowerAction::Suspend => "Suspend",
+      PowerAction::Hibernate => "Hibernate",
+    }
+  }
+
+  /// The `systemctl` verb doing the same
+  pub fn verb(self) -> &'static str {
+    match self {
+      PowerAction::Reboot => "reboot",
+      PowerAction::PowerOff => "poweroff",
+      PowerAction::Halt => "halt",
+      PowerAction::Suspend => "suspend",
+      PowerAction::Hibernate => "hibernate",
+    }
+  }
+
+  /// The logind method doing it
+  pub fn method(self) -> &'static str {
+    match self {
+      PowerAction::Reboot => "Reboot",
+      PowerAction::PowerOff => "PowerOff",
+      PowerAction::Halt => "Halt",
+      PowerAction::Suspend => "Suspend",
+      PowerAction::Hibernate => "Hibernate",
+    }
+  }
+}
+
+/// Whether logind would do `action`: `yes`, `challenge` (after authenticating), `no`, or `na` when the hardware
+/// can't, like hibernating without swap
+pub async fn availability(action: PowerAction) -> Result<String> {
+  let connection = systemd::get_connection(systemd::UnitScope::Global).await?;
+  let login = LoginProxy::new(&connection).await?;
+  let answer = match action {
+    PowerAction::Reboot => login.can_reboot().await,
+    PowerAction::PowerOff => login.can_power_off().await,
+    PowerAction::Halt => login.can_halt().await,
+    PowerAction::Suspend => login.can_suspend().await,
+    PowerAction::Hibernate => login.can_hibernate().await,
+  };
+  Ok(answer?)
+}
+
+pub async fn run(action: PowerAction) -> Result<()> {
+  let connection = systemd::get_connection(systemd::UnitScope::Global).await?;
+  let login = LoginProxy::new(&connection).await?;
+  let result = match action {
+    PowerAction::Reboot => login.reboot(false).await,
+    PowerAction::PowerOff => login.power_off(false).await,
+    PowerAction::Halt => login.halt(false).await,
+    PowerAction::Suspend => login.suspend(false).await,
+    PowerAction::Hibernate => login.hibernate(false).await,
+  };
+  result.with_context(|| format!("Failed to {}", action.verb()))
+}
+
+/// The name of the machine power actions go to, from hostnamed, or the kernel when that isn't running here
+pub async fn hostname() -> Result<String> {
+  let connection = systemd::get_connection(systemd::UnitScope::Global).await?;
+  let from_hostnamed = async { HostnameProxy::new(&connection).await?.hostname().await };
+  match from_hostnamed.await {
+    Ok(name) => Ok(name),
+    Err(_) if remote::host().is_none() => {
+      Ok(std::fs::read_to_string("/proc/sys/kernel/hostname").context("Failed to read the hostname")?.trim().into())
+    },
+    Err(e) => Err(e).context("Failed to get the hostname"),
+  }
+}
+
+/// Proxy object for `org.freedesktop.login1.Manager`.
+#[proxy(
+  interface = "org.freedesktop.login1.Manager",
+  default_service = "org.freedesktop.login1",
+  default_path = "/org/freedesktop/login1",
+  gen_blocking = false
+)]
+trait Login {
+  #[zbus(name = "Reboot")]
+  fn reboot(&self, interactive: bool) -> zbus::Result<()>;
+
+  #[zbus(name = "PowerOff")]
+  fn power_off(&self, interactive: bool) -> zbus::Result<()>;
+
+  #[zbus(name = "Halt")]
+  fn halt(&self, interactive: bool) -> zbus::Result<()>;
+
+  #[zbus(name = "Suspend")]
+  fn suspend(&self, interactive: bool) -> zbus::Result<()>;
+
+  #[zbus(name = "Hibernate")]
+  fn hibernate(&self, interactive: bool) -> zbus::Result<()>;
+
+  #[zbus(name = "CanReboot")]
+  fn can_reboot(&self) -> zbus::Result<String>;
+
+  #[zbus(name = "CanPowerOff")]
+  fn can_power_off(&self) -> zbus::Result<String>;
+
+  #[zbus(name = "CanHalt")]
+  fn can_halt(&self) -> zbus::Result<String>;
+
+  #[zbus(name = "CanSuspend")]
+  fn can_suspend(&self) -> zbus::Result<String>;
+
+  #[zbus(name = "CanHibernate")]
+  fn can_hibernate(&self) -> zbus::Result<String>;
+}
+
+/// Proxy object for `org.freedesktop.hostname1`.
+#[proxy(
+  interface = "org.freedesktop.hostname1",
+  default_service = "org.freedesktop.hostname1",
+  default_path = "/org/freedesktop/hostname1",
+  gen_blocking = false
+)]
+trait Hostname {
+  #[zbus(property)]
+  fn hostname(&self) -> zbus::Result<String>;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_power_action_names() {
+    let verbs = PowerAction::ALL.iter().map(|a| a.verb()).collect::<Vec<_>>();
+    assert_eq!(verbs, ["reboot", "poweroff", "halt", "suspend", "hibernate"]);
+    // logind's methods are the names without spaces
+    for action in PowerAction::ALL {
+      assert_eq!(action.method(), action.name().replace(' ', "").replace("off", "Off"));
+    }
+  }
+}
@@ -0,0 +1,223 @@
+// Reading preset files to explain where a unit's vendor preset comes from. The rules are the ones described in
+// systemd.preset(5): files are ordered by file name across all preset directories, a file in an earlier directory
+// masks one with the same name in a later directory, and the first rule matching a unit wins.
+
+use std::path::{Path, PathBuf};
+
+use crate::systemd::UnitScope;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresetAction {
+  Enable,
+  Disable,
+  Ignore,
+}
+
+impl PresetAction {
+  pub fn label(self) -> &'static str {
+    match self {
+      PresetAction::Enable => "enable",
+      PresetAction::Disable => "disable",
+      PresetAction::Ignore => "ignore",
+    }
+  }
+}
+
+/// Which of its decisions `systemctl preset` carries out, as in `--preset-mode=`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PresetMode {
+  #[default]
+  Full,
+  EnableOnly,
+  DisableOnly,
+}
+
+impl PresetMode {
+  pub const NAMES: &'static [&'static str] = &["full", "enable-only", "disable-only"];
+
+  pub fn parse(name: &str) -> Self {
+    match name {
+      "enable-only" => PresetMode::EnableOnly,
+      "disable-only" => PresetMode::DisableOnly,
+      _ => PresetMode::Full,
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      PresetMode::Full => "full",
+      PresetMode::EnableOnly => "enable-only",
+      PresetMode::DisableOnly => "disable-only",
+    }
+  }
+
+  /// Whether a rule saying `action` is carried out
+  pub fn allows(self, action: PresetAction) -> bool {
+    match self {
+      PresetMode::Full => action != PresetAction::Ignore,
+      PresetMode::EnableOnly => action == PresetAction::Enable,
+      PresetMode::DisableOnly => action == PresetAction::Disable,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresetRule {
+  pub file: PathBuf,
+  /// 1-based
+  pub line: usize,
+  pub action: PresetAction,
+  pub pattern: String,
+  /// For templates, the instances to enable, e.g. `enable getty@.service tty1 tty2`
+  pub instances: Vec<String>,
+}
+
+impl PresetRule {
+  pub fn matches(&self, unit: &str) -> bool {
+    if let (Some((prefix, suffix)), false) = (unit.split_once('@'), self.instances.is_empty()) {
+      let Some((instance, suffix)) = suffix.rsplit_once('.') else { return false };
+      return glob_match(&self.pattern, &format!("{prefix}@.{suffix}")) && self.instances.iter().any(|i| i == instance);
+    }
+    glob_match(&self.pattern, unit)
+  }
+
+  pub fn text(&self) -> String {
+    let mut text = format!("{} {}", self.action.label(), self.pattern);
+    for instance in &self.instances {
+      text.push(' ');
+      text.push_str(instance);
+    }
+    text
+  }
+}
+
+pub fn preset_dirs(scope: UnitScope) -> Vec<PathBuf> {
+  let kind = match scope {
+    UnitScope::Global => "system-preset",
+    UnitScope::User => "user-preset",
+  };
+  ["/etc/systemd", "/run/systemd", "/usr/local/lib/systemd", "/usr/lib/systemd"]
+    .iter()
+    .map(|d| Path::new(d).join(kind))
+    .collect()
+}
+
+/// All `.preset` files in evaluation order, skipping ones masked by a file of the same name in an earlier directory
+pub fn preset_files(dirs: &[PathBuf]) -> Vec<PathBuf> {
+  let mut files: Vec<PathBuf> = vec![];
+  for dir in dirs {
+    let Ok(entries) = std::fs::read_dir(dir) else { continue };
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+      if path.extension().is_some_and(|e| e == "preset") && !files.iter().any(|f| f.file_name() == path.file_name()) {
+        files.push(path);
+      }
+    }
+  }
+  files.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+  files
+}
+
+pub fn parse_preset(file: &Path, text: &str) -> Vec<PresetRule> {
+  text
+    .lines()
+    .enumerate()
+    .filter_map(|(i, line)| {
+      let mut words = line.split_whitespace();
+      let action = match words.next()? {
+        "enable" => PresetAction::Enable,
+        "disable" => PresetAction::Disable,
+        "ignore" => PresetAction::Ignore,
+        _ => return None,
+      };
+      let pattern = words.next()?.to_string();
+      let instances = words.map(str::to_string).collect();
+      Some(PresetRule { file: file.to_path_buf(), line: i + 1, action, pattern, instances })
+    })
+    .collect()
+}
+
+/// Every rule for a scope, in the order systemd evaluates them
+pub fn load_rules(scope: UnitScope) -> Vec<PresetRule> {
+  preset_files(&preset_dirs(scope))
+    .iter()
+    .flat_map(|file| parse_preset(file, &std::fs::read_to_string(file).unwrap_or_default()))
+    .collect()
+}
+
+/// The rule that decides `unit`'s preset; with no matching rule, systemd enables the unit
+pub fn decide<'a>(rules: &'a [PresetRule], unit: &str) -> Option<&'a PresetRule> {
+  rules.iter().find(|r| r.matches(unit))
+}
+
+/// What the presets do with `unit`
+pub fn action_for(rules: &[PresetRule], unit: &str) -> PresetAction {
+  decide(rules, unit).map_or(PresetAction::Enable, |r| r.action)
+}
+
+/// fnmatch(3)-style matching of `*`, `?` and `[...]`, which is what preset patterns use
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+  fn matches(p: &[char], n: &[char]) -> bool {
+    match p.first() {
+      None => n.is_empty(),
+      Some('*') => (0..=n.len()).any(|i| matches(&p[1..], &n[i..])),
+      Some('?') => !n.is_empty() && matches(&p[1..], &n[1..]),
+      Some('[') => {
+        let Some(end) = p.iter().skip(1).position(|c| *c == ']').map(|i| i + 1) else {
+          return n.first() == Some(&'[') && matches(&p[1..], &n[1..]);
+        };
+        let Some(c) = n.first() else { return false };
+        let (negate, set) = match p[1] {
+          '!' | '^' => (true, &p[2..end]),
+          _ => (false, &p[1..end]),
+        };
+        let mut found = false;
+        let mut i = 0;
+        while i < set.len() {
+          if i + 2 < set.len() && set[i + 1] == '-' {
+            found |= set[i] <= *c && *c <= set[i + 2];
+            i += 3;
+          } else {
+            found |= set[i] == *c;
+            i += 1;
+          }
+        }
+        found != negate && matches(&p[end + 1..], &n[1..])
+      },
+      Some(c) => n.first() == Some(c) && matches(&p[1..], &n[1..]),
+    }
+  }
+  matches(&pattern.chars().collect::<Vec<_>>(), &name.chars().collect::<Vec<_>>())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_glob_match() {
+    assert!(glob_match("*", "nginx.service"));
+    assert!(glob_match("systemd-*.service", "systemd-resolved.service"));
+    assert!(!glob_match("systemd-*.service", "systemd-resolved.socket"));
+    assert!(glob_match("getty@tty[1-6].service", "getty@tty3.service"));
+    assert!(!glob_match("getty@tty[!1-6].service", "getty@tty3.service"));
+    assert!(glob_match("?ginx.service", "nginx.service"));
+  }
+
+  #[test]
+  fn test_decide() {
+    let rules = parse_preset(
+      Path::new("/usr/lib/systemd/system-preset/90-systemd.preset"),
+      "# comment\nenable systemd-resolved.service\nenable getty@.service tty1\n\ndisable *\n",
+    );
+    assert_eq!(rules.len(), 3);
+    assert_eq!(decide(&rules, "systemd-resolved.service").unwrap().line, 2);
+    assert_eq!(decide(&rules, "getty@tty1.service").unwrap().action, PresetAction::Enable);
+    assert_eq!(decide(&rules, "getty@tty2.service").unwrap().action, PresetAction::Disable);
+    assert_eq!(decide(&rules, "nginx.service").unwrap().text(), "disable *");
+    assert_eq!(decide(&rules[..1], "nginx.service"), None);
+    // no rule means enable
+    assert_eq!(action_for(&rules[..1], "nginx.service"), PresetAction::Enable);
+    assert!(!PresetMode::EnableOnly.allows(action_for(&rules, "nginx.service")));
+    assert_eq!(PresetMode::parse("disable-only").name(), "disable-only");
+  }
+}
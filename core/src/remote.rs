@@ -0,0 +1,159 @@
+// Managing another machine's systemd the way `systemctl -H` does: the manager's D-Bus API is reached by running
+// systemd-stdio-bridge on the host over SSH, and the command-line tools either take `-H` themselves or are run there
+// through ssh. Only system units can be managed remotely.
+
+use std::{ffi::OsString, path::PathBuf, process::Command, sync::RwLock};
+
+use anyhow::{bail, Context, Result};
+use zbus::address::{transport::Transport, transport::Unixexec, Address};
+
+use crate::machine;
+
+static HOST: RwLock<Option<String>> = RwLock::new(None);
+
+/// The host everything runs against, `None` for this machine
+pub fn host() -> Option<String> {
+  HOST.read().unwrap().clone()
+}
+
+pub fn set_host(host: Option<String>) {
+  *HOST.write().unwrap() = host;
+}
+
+/// The host or container everything runs against, `None` for this machine
+pub fn elsewhere() -> Option<String> {
+  host().or_else(machine::machine)
+}
+
+/// Fail for things that only work on this machine, like editing unit files. A container counts as another machine
+pub fn ensure_local(what: &str) -> Result<()> {
+  match elsewhere() {
+    Some(host) => bail!("{what} isn't possible while managing {host}, only on this machine"),
+    None => Ok(()),
+  }
+}
+
+/// Check a host as given on the command line or in the switcher, `[user@]machine[:port]`. Empty means this machine
+pub fn parse_host(host: &str) -> Result<Option<String>> {
+  let host = host.trim();
+  if host.is_empty() || host == "localhost" {
+    return Ok(None);
+  }
+  if host.starts_with('-') || host.contains(char::is_whitespace) {
+    bail!("`{host}` isn't a host; enter it as [user@]machine[:port]");
+  }
+  Ok(Some(host.to_string()))
+}
+
+/// The arguments to ssh that connect to `host` and run a command there, like systemctl does for `-H`
+pub fn ssh_args(host: &str) -> Vec<String> {
+  let mut args = vec!["-xT".to_string()];
+  let destination = match host.rsplit_once(':') {
+    Some((destination, port)) if !port.is_empty() && port.chars().all(|c| c.is_ascii_digit()) => {
+      args.extend(["-p".to_string(), port.to_string()]);
+      destination
+    },
+    _ => host,
+  };
+  args.extend(["--".to_string(), destination.to_string()]);
+  args
+}
+
+/// Where to reach the host's system bus
+pub fn bus_address(host: &str) -> Address {
+  let mut args: Vec<OsString> = ssh_args(host).into_iter().map(OsString::from).collect();
+  args.push("systemd-stdio-bridge".into());
+  Address::from(Transport::Unixexec(Unixexec::new("ssh".into(), None, args)))
+}
+
+/// Run `command` on the host instead, if there is one. ssh hands the remote shell a single string, so every argument
+/// is quoted; environment variables set or removed on `command` are passed along with `env`, and a journalctl is
+/// deprioritized there the way `systemd::journalctl` does here. Its stdio, working directory and `pre_exec` can't be
+/// read back to carry over, so call this before setting them
+pub fn on_host(command: &mut Command) -> &mut Command {
+  let Some(host) = host() else { return command };
+  assert!(command.get_current_dir().is_none(), "remote::on_host called after setting a working directory");
+
+  let mut remote = Command::new("ssh");
+  remote.args(ssh_args(&host)).arg(command_line(command));
+  *command = remote;
+  command
+}
+
+/// `command` as a line for the remote shell
+fn command_line(command: &Command) -> String {
+  let mut words = vec![];
+  let envs = command.get_envs().collect::<Vec<_>>();
+  if !envs.is_empty() {
+    words.push("env".to_string());
+    // env stops reading options at the first assignment, so every -u goes before them
+    for (key, _) in envs.iter().filter(|(_, value)| value.is_none()) {
+      words.extend(["-u".to_string(), shell_quote(&key.to_string_lossy())]);
+    }
+    for (key, value) in envs.iter().filter_map(|(key, value)| Some((key, (*value)?))) {
+      words.push(shell_quote(&format!("{}={}", key.to_string_lossy(), value.to_string_lossy())));
+    }
+  }
+  if command.get_program() == "journalctl" {
+    words.extend(["ionice", "-t", "-c", "2", "-n", "7", "nice", "-n", "10"].map(String::from));
+  }
+  words.push(shell_quote(&command.get_program().to_string_lossy()));
+  words.extend(command.get_args().map(|a| shell_quote(&a.to_string_lossy())));
+  words.join(" ")
+}
+
+/// Read a file on the host or in the container everything runs against, or on this machine: through cat over ssh,
+/// or under the container's root directory
+pub fn read_file(path: &str) -> Result<String> {
+  if host().is_some() {
+    let output = on_host(Command::new("cat").arg(path)).output()?;
+    if !output.status.success() {
+      bail!("Failed to read {path}: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+  }
+  let local = match machine::machine() {
+    Some(name) => machine::root(&name)?.join(path.trim_start_matches('/')),
+    None => PathBuf::from(path),
+  };
+  let contents = std::fs::read(&local).with_context(|| format!("Failed to read {path}"))?;
+  Ok(String::from_utf8_lossy(&contents).into_owned())
+}
+
+/// Quote `arg` for a POSIX shell, leaving it bare when that's unambiguous
+pub fn shell_quote(arg: &str) -> String {
+  if arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_.=@/:".contains(c)) {
+    arg.to_string()
+  } else {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_ssh_args() {
+    assert_eq!(ssh_args("admin@web1"), ["-xT", "--", "admin@web1"]);
+    assert_eq!(ssh_args("web1:2222"), ["-xT", "-p", "2222", "--", "web1"]);
+    assert!(parse_host("-oProxyCommand=x").is_err());
+    assert_eq!(parse_host(" ").unwrap(), None);
+  }
+
+  #[test]
+  fn test_command_line() {
+    let mut command = Command::new("journalctl");
+    command.args(["-u", "my app.service"]).env("SYSTEMD_COLORS", "0").env_remove("PAGER");
+    assert_eq!(
+      command_line(&command),
+      "env -u PAGER SYSTEMD_COLORS=0 ionice -t -c 2 -n 7 nice -n 10 journalctl -u 'my app.service'"
+    );
+    assert_eq!(command_line(Command::new("cat").arg("/etc/hosts")), "cat /etc/hosts");
+
+    // a removed variable that sorts after a set one still comes first
+    let mut command = Command::new("systemctl");
+    command.arg("status").env("LANG", "C").env_remove("SYSTEMD_PAGER");
+    assert_eq!(command_line(&command), "env -u SYSTEMD_PAGER LANG=C systemctl status");
+  }
+}
@@ -0,0 +1,2531 @@
+// File initially taken from https://github.com/servicer-labs/servicer/blob/master/src/utils/systemd.rs, since modified
+
+use core::str;
+use std::{
+  collections::HashMap,
+  io::{BufRead, BufReader},
+  os::unix::process::CommandExt,
+  path::PathBuf,
+  process::{Command, Stdio},
+};
+
+use anyhow::{bail, Context, Result};
+use log::error;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+use zbus::{proxy, zvariant, Connection};
+
+use crate::{
+  boot_history, boot_wait, journal_usage, machine,
+  preset::{self, PresetMode},
+  remote, timers, user_manager,
+};
+
+#[derive(Debug, Clone)]
+pub struct UnitWithStatus {
+  pub name: String,                              // The primary unit name as string
+  pub scope: UnitScope,                          // System or user?
+  pub description: String,                       // The human readable description string
+  pub file_path: Option<Result<String, String>>, // The unit file path - populated later on demand
+
+  pub load_state: String, // The load state (i.e. whether the unit file has been loaded successfully)
+
+  // Some comments re: state from this helpful comment: https://www.reddit.com/r/linuxquestions/comments/r58dvz/comment/hmlemfk/
+  /// One state, called the "activation state", essentially describes what the unit is doing now. The two most common values for this state are active and inactive, though there are a few other possibilities. (Each unit type has its own set of "substates" that map to these activation states. For instance, service units can be running or stopped. Again, there's a variety of other substates, and the list differs for each unit type.)
+  pub activation_state: String,
+  /// The sub state (a more fine-grained version of the active state that is specific to the unit type, which the active state is not)
+  pub sub_state: String,
+
+  /// The other state all units have is called the "enablement state". It describes how the unit might be automatically started in the future. A unit is enabled if it has been added to the requirements list of any other unit though symlinks in the filesystem. The set of symlinks to be created when enabling a unit is described by the unit's [Install] section. A unit is disabled if no symlinks are present. Again there's a variety of other values other than these two (e.g. not all units even have [Install] sections).
+  /// Only populated when needed b/c this is much slower to get
+  pub enablement_state: Option<String>,
+  // We don't use any of these right now, might as well skip'em so there's less data to clone
+  // pub followed: String, // A unit that is being followed in its state by this unit, if there is any, otherwise the empty string.
+  // pub path: String,     // The unit object path
+  // pub job_id: u32,      // If there is a job queued for the job unit the numeric job id, 0 otherwise
+  // pub job_type: String, // The job type as string
+  // pub job_path: String, // The job object path
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnitScope {
+  Global,
+  User,
+}
+
+/// Just enough info to fully identify a unit
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UnitId {
+  pub name: String,
+  pub scope: UnitScope,
+}
+
+impl UnitWithStatus {
+  pub fn is_active(&self) -> bool {
+    self.activation_state == "active"
+  }
+
+  pub fn is_failed(&self) -> bool {
+    self.activation_state == "failed"
+  }
+
+  pub fn is_not_found(&self) -> bool {
+    self.load_state == "not-found"
+  }
+
+  pub fn is_enabled(&self) -> bool {
+    self.load_state == "loaded" && self.activation_state == "active"
+  }
+
+  pub fn short_name(&self) -> &str {
+    if self.name.ends_with(".service") {
+      &self.name[..self.name.len() - 8]
+    } else {
+      &self.name
+    }
+  }
+
+  // TODO: should we have a non-allocating version of this?
+  pub fn id(&self) -> UnitId {
+    UnitId { name: self.name.clone(), scope: self.scope }
+  }
+
+  // useful for updating without wiping out the file path
+  pub fn update(&mut self, other: UnitWithStatus) {
+    self.description = other.description;
+    self.load_state = other.load_state;
+    self.activation_state = other.activation_state;
+    self.sub_state = other.sub_state;
+  }
+}
+
+type RawUnit =
+  (String, String, String, String, String, String, zvariant::OwnedObjectPath, u32, String, zvariant::OwnedObjectPath);
+
+/// id, unit, type, state, job path, unit path
+type RawJob = (u32, String, String, String, zvariant::OwnedObjectPath, zvariant::OwnedObjectPath);
+
+fn to_unit_status(raw_unit: RawUnit, scope: UnitScope) -> UnitWithStatus {
+  let (name, description, load_state, active_state, sub_state, _followed, _path, _job_id, _job_type, _job_path) =
+    raw_unit;
+
+  UnitWithStatus {
+    name,
+    scope,
+    description,
+    file_path: None,
+    enablement_state: None,
+    load_state,
+    activation_state: active_state,
+    sub_state,
+  }
+}
+
+// Different from UnitScope in that this is not for 1 specific unit (i.e. it can include multiple scopes)
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum Scope {
+  Global,
+  User,
+  #[default]
+  All,
+}
+
+// this takes like 5-10 ms on 13th gen Intel i7 (scope=all)
+pub async fn get_all_services(scope: Scope, services: &[String]) -> Result<Vec<UnitWithStatus>> {
+  let start = std::time::Instant::now();
+
+  let mut units = vec![];
+
+  let is_root = nix::unistd::geteuid().is_root();
+
+  // there's no reaching a user manager on another machine or in a container
+  let scope = if remote::elsewhere().is_some() { Scope::Global } else { scope };
+  match scope {
+    Scope::Global => {
+      let system_units = get_services(UnitScope::Global, services).await?;
+      units.extend(system_units);
+    },
+    Scope::User => {
+      let user_units = get_services(UnitScope::User, services).await?;
+      units.extend(user_units);
+    },
+    Scope::All => {
+      let (system_units, user_units) =
+        tokio::join!(get_services(UnitScope::Global, services), get_services(UnitScope::User, services));
+      units.extend(system_units?);
+
+      // Should always be able to get user units, but it may fail when running as root
+      if let Ok(user_units) = user_units {
+        units.extend(user_units);
+      } else if is_root {
+        error!("Failed to get user units, ignoring because we're running as root")
+      } else {
+        user_units?;
+      }
+    },
+  }
+
+  // sort by name case-insensitive
+  units.sort_by_key(|a| a.name.to_lowercase());
+
+  info!("Loaded systemd services in {:?}", start.elapsed());
+
+  Ok(units)
+}
+
+async fn get_services(scope: UnitScope, services: &[String]) -> Result<Vec<UnitWithStatus>, anyhow::Error> {
+  let connection = get_connection(scope).await?;
+  let manager_proxy = ManagerProxy::new(&connection).await?;
+  let units = manager_proxy.list_units_by_patterns(vec![], services.to_vec()).await?;
+  let units: Vec<_> = units.into_iter().map(|u| to_unit_status(u, scope)).collect();
+  Ok(units)
+}
+
+pub fn get_unit_file_location(service: &UnitId) -> Result<String> {
+  // show -P FragmentPath reitunes.service
+  let output = in_scope(&mut systemctl(), service.scope)
+    .args(["--quiet", "show", "-P", "FragmentPath", &service.name])
+    .output()?;
+
+  if output.status.success() {
+    let path = str::from_utf8(&output.stdout)?.trim();
+    if path.is_empty() {
+      bail!("No unit file found for {}", service.name);
+    }
+    Ok(path.trim().to_string())
+  } else {
+    let stderr = String::from_utf8(output.stderr)?;
+    bail!(stderr);
+  }
+}
+
+/// How long to keep gathering signals after the first one. A state change comes as a burst: a job, then each property
+const SIGNAL_SETTLE: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// The manager's signals about units starting, stopping, appearing and going away, turned into the new state of each
+/// unit they were about
+pub struct UnitWatch {
+  scope: UnitScope,
+  patterns: Vec<String>,
+  manager: ManagerProxy<'static>,
+  stream: zbus::MessageStream,
+}
+
+/// What a burst of the manager's signals changed
+#[derive(Debug, Clone, Default)]
+pub struct UnitChanges {
+  /// The new state of units that appeared or changed
+  pub updated: Vec<UnitWithStatus>,
+  /// Units the manager unloaded, like transient ones that finished, which a full fetch wouldn't list any more
+  pub removed: Vec<UnitId>,
+}
+
+impl UnitWatch {
+  /// Subscribe to the manager's signals, for units matching `patterns` (as passed to `get_all_services`)
+  pub async fn new(scope: UnitScope, patterns: &[String]) -> Result<Self> {
+    let connection = get_connection(scope).await?;
+    let manager = ManagerProxy::new(&connection).await?;
+    let rule =
+      zbus::MatchRule::builder().msg_type(zbus::message::Type::Signal).sender("org.freedesktop.systemd1")?.build();
+    let stream = zbus::MessageStream::for_match_rule(rule, &connection, None).await?;
+    manager.subscribe().await?;
+    Ok(Self { scope, patterns: patterns.to_vec(), manager, stream })
+  }
+
+  /// Wait for units to change, then read their state. Fails once the connection goes away
+  pub async fn next(&mut self) -> Result<UnitChanges> {
+    use futures::StreamExt;
+
+    loop {
+      // the last signal about each unit says whether it's still loaded
+      let mut changed = std::collections::BTreeMap::new();
+      let message = self.stream.next().await.context("The manager's signals stopped")??;
+      changed.extend(changed_unit(&message));
+      let settle = tokio::time::sleep(SIGNAL_SETTLE);
+      tokio::pin!(settle);
+      loop {
+        tokio::select! {
+          _ = &mut settle => break,
+          message = self.stream.next() => {
+            let message = message.context("The manager's signals stopped")??;
+            changed.extend(changed_unit(&message));
+          }
+        }
+      }
+
+      let (removed, updated): (Vec<_>, Vec<_>) = changed
+        .into_iter()
+        .filter(|(name, _)| self.patterns.iter().any(|p| preset::glob_match(p, name)))
+        .partition(|(_, removed)| *removed);
+      if removed.is_empty() && updated.is_empty() {
+        continue;
+      }
+      // asking about a removed unit would load it again
+      let names = updated.into_iter().map(|(name, _)| name).collect::<Vec<_>>();
+      let updated = if names.is_empty() { vec![] } else { self.manager.list_units_by_names(names).await? };
+      return Ok(UnitChanges {
+        updated: updated.into_iter().map(|u| to_unit_status(u, self.scope)).collect(),
+        removed: removed.into_iter().map(|(name, _)| UnitId { name, scope: self.scope }).collect(),
+      });
+    }
+  }
+}
+
+/// The unit a manager signal is about, if it's about one, and whether it was removed
+fn changed_unit(message: &zbus::Message) -> Option<(String, bool)> {
+  let header = message.header();
+  let body = message.body();
+  let unit = match header.member()?.as_str() {
+    "UnitRemoved" => {
+      return body.deserialize::<(String, zvariant::OwnedObjectPath)>().ok().map(|(unit, _)| (unit, true));
+    },
+    "UnitNew" => body.deserialize::<(String, zvariant::OwnedObjectPath)>().ok().map(|(unit, _)| unit),
+    "JobNew" => body.deserialize::<(u32, zvariant::OwnedObjectPath, String)>().ok().map(|(_, _, unit)| unit),
+    "JobRemoved" => {
+      body.deserialize::<(u32, zvariant::OwnedObjectPath, String, String)>().ok().map(|(_, _, unit, _)| unit)
+    },
+    "PropertiesChanged" => unit_name_from_path(header.path()?.as_str()),
+    _ => None,
+  };
+  unit.map(|unit| (unit, false))
+}
+
+/// Get a handful of properties for a unit, like `systemctl show --property=A,B`
+pub fn get_unit_properties(unit: &UnitId, properties: &[&str]) -> Result<HashMap<String, String>> {
+  let property_arg = format!("--property={}", properties.join(","));
+  let output = in_scope(&mut systemctl(), unit.scope).args(["show", &property_arg, &unit.name]).output()?;
+  if !output.status.success() {
+    bail!("Failed to get properties of {}: {}", unit.name, String::from_utf8_lossy(&output.stderr));
+  }
+
+  Ok(parse_properties(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Every property of a unit, as `systemctl show` lists them
+pub fn get_all_properties(unit: &UnitId) -> Result<Vec<(String, String)>> {
+  let output = in_scope(&mut systemctl(), unit.scope).args(["show", &unit.name]).output()?;
+  if !output.status.success() {
+    bail!("Failed to get properties of {}: {}", unit.name, String::from_utf8_lossy(&output.stderr).trim());
+  }
+  Ok(
+    String::from_utf8_lossy(&output.stdout)
+      .lines()
+      .filter_map(|l| l.split_once('='))
+      .map(|(k, v)| (k.to_string(), v.to_string()))
+      .collect(),
+  )
+}
+
+/// One of the files a unit is loaded from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitFile {
+  pub path: String,
+  /// Applied on top of the unit file, from a `<unit>.d` directory
+  pub drop_in: bool,
+  pub contents: String,
+}
+
+/// The unit file and its drop-ins, in the order they apply, like `systemctl cat`
+pub fn get_unit_files(unit: &UnitId) -> Result<Vec<UnitFile>> {
+  let properties = get_unit_properties(unit, &["FragmentPath", "DropInPaths"])?;
+  let fragment = properties.get("FragmentPath").filter(|p| !p.is_empty()).map(|p| (p.as_str(), false));
+  let drop_ins = properties.get("DropInPaths").into_iter().flat_map(|p| p.split_whitespace()).map(|p| (p, true));
+  fragment
+    .into_iter()
+    .chain(drop_ins)
+    .map(|(path, drop_in)| Ok(UnitFile { path: path.to_string(), drop_in, contents: remote::read_file(path)? }))
+    .collect()
+}
+
+/// The `ActiveState` of each of `names`, read in one go
+pub fn get_active_states(scope: UnitScope, names: &[String]) -> Result<HashMap<String, String>> {
+  if names.is_empty() {
+    return Ok(HashMap::new());
+  }
+  let output = in_scope(&mut systemctl(), scope).args(["show", "--property=ActiveState"]).args(names).output()?;
+  if !output.status.success() {
+    bail!("Failed to get the state of {}: {}", names.join(", "), String::from_utf8_lossy(&output.stderr).trim());
+  }
+  // one block per unit, in the order asked for
+  let blocks = parse_property_blocks(&String::from_utf8_lossy(&output.stdout));
+  Ok(names.iter().cloned().zip(blocks.into_iter().map(|mut b| b.remove("ActiveState").unwrap_or_default())).collect())
+}
+
+/// Every value of a property that can appear more than once, like a socket's `Listen`
+pub fn get_property_values(unit: &UnitId, property: &str) -> Result<Vec<String>> {
+  let mut command = systemctl();
+  in_scope(&mut command, unit.scope);
+  let output = command.args(["show", &format!("--property={property}"), &unit.name]).output()?;
+  if !output.status.success() {
+    bail!("Failed to get {property} of {}: {}", unit.name, String::from_utf8_lossy(&output.stderr));
+  }
+  let prefix = format!("{property}=");
+  Ok(
+    String::from_utf8_lossy(&output.stdout).lines().filter_map(|l| l.strip_prefix(&prefix)).map(String::from).collect(),
+  )
+}
+
+/// Properties of a unit with every value of those that can appear more than once, like a socket's `Listen`
+pub fn get_unit_property_lists(unit: &UnitId, properties: &[&str]) -> Result<HashMap<String, Vec<String>>> {
+  let property_arg = format!("--property={}", properties.join(","));
+  let output = in_scope(&mut systemctl(), unit.scope).args(["show", &property_arg, &unit.name]).output()?;
+  if !output.status.success() {
+    bail!("Failed to get properties of {}: {}", unit.name, String::from_utf8_lossy(&output.stderr));
+  }
+  Ok(parse_property_lists(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Like `parse_properties`, keeping repeated keys' values in order
+pub fn parse_property_lists(output: &str) -> HashMap<String, Vec<String>> {
+  let mut properties: HashMap<String, Vec<String>> = HashMap::new();
+  for (key, value) in output.lines().filter_map(|l| l.split_once('=')) {
+    properties.entry(key.to_string()).or_default().push(value.to_string());
+  }
+  properties
+}
+
+/// Parse `Key=Value` lines as printed by `systemctl show`
+pub fn parse_properties(output: &str) -> HashMap<String, String> {
+  output.lines().filter_map(|l| l.split_once('=')).map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+/// A few properties of every loaded unit, one map per unit
+pub fn get_all_unit_properties(scope: UnitScope, properties: &[&str]) -> Result<Vec<HashMap<String, String>>> {
+  let mut command = systemctl();
+  in_scope(&mut command, scope);
+  let output = command.args(["show", &format!("--property=Id,{}", properties.join(",")), "*"]).output()?;
+  if !output.status.success() {
+    bail!("Failed to get unit properties: {}", String::from_utf8_lossy(&output.stderr).trim());
+  }
+  Ok(parse_property_blocks(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Every loaded unit's unit file state: `enabled`, `disabled`, `static`, `masked` and so on, or empty for units
+/// without a unit file
+pub fn get_all_unit_file_states(scope: UnitScope) -> Result<HashMap<String, String>> {
+  Ok(
+    get_all_unit_properties(scope, &["UnitFileState"])?
+      .into_iter()
+      .filter_map(|mut p| Some((p.remove("Id")?, p.remove("UnitFileState").unwrap_or_default())))
+      .collect(),
+  )
+}
+
+/// Every unit file with its state, whether or not it's loaded: `("nginx.service", "enabled")`
+pub fn get_unit_file_states(scope: UnitScope) -> Result<Vec<(String, String)>> {
+  let mut command = systemctl();
+  in_scope(&mut command, scope);
+  let output = command.args(["list-unit-files", "--no-legend", "--no-pager", "--plain"]).output()?;
+  if !output.status.success() {
+    bail!("Failed to list unit files: {}", String::from_utf8_lossy(&output.stderr).trim());
+  }
+  Ok(
+    String::from_utf8_lossy(&output.stdout)
+      .lines()
+      .filter_map(|l| {
+        let mut words = l.split_whitespace();
+        Some((words.next()?.to_string(), words.next()?.to_string()))
+      })
+      .collect(),
+  )
+}
+
+/// The names of the template unit files, e.g. `getty@.service`, whether or not any instance of them is loaded
+pub fn get_templates(scope: UnitScope) -> Result<Vec<String>> {
+  let mut command = systemctl();
+  in_scope(&mut command, scope);
+  let output = command.args(["list-unit-files", "--no-legend", "--plain", "*@.*"]).output()?;
+  if !output.status.success() {
+    bail!("Failed to list unit files: {}", String::from_utf8_lossy(&output.stderr).trim());
+  }
+  Ok(
+    String::from_utf8_lossy(&output.stdout)
+      .lines()
+      .filter_map(|l| l.split_whitespace().next())
+      .filter(|name| name.contains("@."))
+      .map(String::from)
+      .collect(),
+  )
+}
+
+/// When every loaded timer last fired and fires next
+pub fn get_all_timers(scope: UnitScope) -> Result<HashMap<String, timers::TimerTimes>> {
+  Ok(
+    show_unix_timestamps(scope, "*.timer", timers::TIMER_PROPERTIES)?
+      .into_iter()
+      .filter_map(|p| Some((p.get("Id")?.clone(), timers::TimerTimes::from_properties(&p))))
+      .collect(),
+  )
+}
+
+pub fn get_timer_times(unit: &UnitId) -> Result<timers::TimerTimes> {
+  let properties = show_unix_timestamps(unit.scope, &unit.name, timers::TIMER_PROPERTIES)?;
+  Ok(properties.first().map(timers::TimerTimes::from_properties).unwrap_or_default())
+}
+
+/// `systemctl show` with timestamps as `@<seconds>` instead of in the local format, which can't be parsed reliably
+fn show_unix_timestamps(scope: UnitScope, pattern: &str, properties: &[&str]) -> Result<Vec<HashMap<String, String>>> {
+  let mut command = systemctl();
+  in_scope(&mut command, scope);
+  let output =
+    command.args(["show", "--timestamp=unix", &format!("--property=Id,{}", properties.join(",")), pattern]).output()?;
+  if !output.status.success() {
+    bail!("Failed to get timer properties: {}", String::from_utf8_lossy(&output.stderr).trim());
+  }
+  Ok(parse_property_blocks(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// A timer's triggers, with the first calendar one expanded into its next few runs
+pub fn get_timer_schedule(unit: &UnitId, runs: usize) -> Result<timers::Schedule> {
+  let calendar =
+    get_property_values(unit, "TimersCalendar")?.iter().filter_map(|v| timers::parse_trigger(v)).collect::<Vec<_>>();
+  let monotonic =
+    get_property_values(unit, "TimersMonotonic")?.iter().filter_map(|v| timers::parse_trigger(v)).collect::<Vec<_>>();
+  let next_runs = match calendar.first().and_then(|t| t.strip_prefix("OnCalendar=")) {
+    Some(expression) => calendar_preview(expression, runs)?.elapses.into_iter().map(|(at, _)| at).collect(),
+    None => vec![],
+  };
+  Ok(timers::Schedule { triggers: calendar.into_iter().chain(monotonic).collect(), next_runs })
+}
+
+/// `systemctl show` output for several units: blank-line separated blocks of `Key=Value` lines
+pub fn parse_property_blocks(output: &str) -> Vec<HashMap<String, String>> {
+  output.split("\n\n").map(parse_properties).filter(|p| !p.is_empty()).collect()
+}
+
+/// The properties behind `Accounting`
+pub const ACCOUNTING_PROPERTIES: &[&str] = &[
+  "IOAccounting",
+  "IPAccounting",
+  "IOReadBytes",
+  "IOWriteBytes",
+  "IPIngressBytes",
+  "IPEgressBytes",
+  "CPUUsageNSec",
+  "MemoryCurrent",
+  "TasksCurrent",
+  "ActiveState",
+  "ActiveEnterTimestampMonotonic",
+  "StateChangeTimestampMonotonic",
+  "FreezerState",
+  "NRestarts",
+  "Restart",
+  "WatchdogUSec",
+  "Result",
+];
+
+/// Restarts it takes, with the unit not staying up for `CRASH_LOOP_UPTIME` since, to count as crash-looping
+pub const CRASH_LOOP_RESTARTS: u32 = 3;
+pub const CRASH_LOOP_UPTIME: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// A numeric property from `systemctl show`, or `None` when it's unavailable: those read as `[not set]` or u64::MAX
+pub fn parse_counter(properties: &HashMap<String, String>, name: &str) -> Option<u64> {
+  properties.get(name).and_then(|v| v.parse::<u64>().ok()).filter(|v| *v != u64::MAX)
+}
+
+/// I/O, network and CPU totals for a unit's current run, its memory and task counts, whether it's frozen, and for
+/// services how often they've been restarted. The counters are `None` when accounting is off or the unit isn't
+/// running
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Accounting {
+  pub io_accounting: bool,
+  pub ip_accounting: bool,
+  pub io_read_bytes: Option<u64>,
+  pub io_write_bytes: Option<u64>,
+  pub ip_ingress_bytes: Option<u64>,
+  pub ip_egress_bytes: Option<u64>,
+  pub cpu_usage_nsec: Option<u64>,
+  pub memory_current: Option<u64>,
+  pub tasks_current: Option<u64>,
+  /// When these were read, in microseconds of `CLOCK_MONOTONIC`
+  pub sampled_usec: u64,
+  /// When the unit last became active, in microseconds of `CLOCK_MONOTONIC`. Only set while it's active
+  pub active_since_usec: Option<u64>,
+  /// When the unit last changed active state, in microseconds of `CLOCK_MONOTONIC`. Unset for units that haven't
+  /// since boot
+  pub state_changed_usec: Option<u64>,
+  /// Its processes are paused by the cgroup freezer, or being paused
+  pub frozen: bool,
+  /// Automatic restarts since the service was last started by hand. The rest are also only set for services
+  pub restarts: Option<u32>,
+  /// `Restart=`, e.g. `on-failure`
+  pub restart_policy: Option<String>,
+  /// `WatchdogSec=`, unset when there's no watchdog
+  pub watchdog_usec: Option<u64>,
+  /// How the last run went, e.g. `success`, `oom-kill` or `watchdog`
+  pub result: Option<String>,
+}
+
+impl Accounting {
+  pub fn from_properties(properties: &HashMap<String, String>) -> Self {
+    let counter = |name: &str| parse_counter(properties, name);
+    let flag = |name: &str| properties.get(name).is_some_and(|v| v == "yes");
+    Accounting {
+      io_accounting: flag("IOAccounting"),
+      ip_accounting: flag("IPAccounting"),
+      io_read_bytes: counter("IOReadBytes"),
+      io_write_bytes: counter("IOWriteBytes"),
+      ip_ingress_bytes: counter("IPIngressBytes"),
+      ip_egress_bytes: counter("IPEgressBytes"),
+      cpu_usage_nsec: counter("CPUUsageNSec"),
+      memory_current: counter("MemoryCurrent"),
+      tasks_current: counter("TasksCurrent"),
+      sampled_usec: monotonic_now_usec(),
+      active_since_usec: counter("ActiveEnterTimestampMonotonic")
+        .filter(|t| *t != 0 && properties.get("ActiveState").is_some_and(|s| s == "active")),
+      state_changed_usec: counter("StateChangeTimestampMonotonic").filter(|t| *t != 0),
+      frozen: properties.get("FreezerState").is_some_and(|s| s == "frozen" || s == "freezing"),
+      restarts: counter("NRestarts").and_then(|n| u32::try_from(n).ok()),
+      restart_policy: properties.get("Restart").filter(|p| !p.is_empty()).cloned(),
+      watchdog_usec: counter("WatchdogUSec").filter(|w| *w != 0),
+      result: properties.get("Result").filter(|r| !r.is_empty()).cloned(),
+    }
+  }
+
+  /// Restarted again and again without staying up: waiting for yet another restart, or up only briefly since the
+  /// last. `sub_state` is the unit's, like `auto-restart`
+  pub fn crash_looping(&self, sub_state: &str) -> bool {
+    self.restarts.is_some_and(|n| n >= CRASH_LOOP_RESTARTS)
+      && (sub_state == "auto-restart" || self.uptime().is_some_and(|up| up < CRASH_LOOP_UPTIME))
+  }
+
+  /// CPU use between `earlier` and this reading, as a percentage of one CPU like top shows it
+  pub fn cpu_percent(&self, earlier: &Accounting) -> Option<f64> {
+    let used = self.cpu_usage_nsec?.checked_sub(earlier.cpu_usage_nsec?)?;
+    // readings much closer together than that say little
+    let elapsed = self.sampled_usec.checked_sub(earlier.sampled_usec).filter(|e| *e >= 1_000_000)?;
+    Some(used as f64 / 1000.0 / elapsed as f64 * 100.0)
+  }
+
+  /// CPU, I/O and network use between `earlier` and this reading, or `None` if they're too close together to tell
+  pub fn rates(&self, earlier: &Accounting) -> Option<Rates> {
+    let elapsed = self.sampled_usec.checked_sub(earlier.sampled_usec).filter(|e| *e >= 1_000_000)?;
+    let per_second = |counter: fn(&Accounting) -> Option<u64>| {
+      let bytes = counter(self)?.checked_sub(counter(earlier)?)?;
+      Some(bytes as f64 * 1_000_000.0 / elapsed as f64)
+    };
+    Some(Rates {
+      cpu_percent: self.cpu_percent(earlier),
+      io_read: per_second(|a| a.io_read_bytes),
+      io_write: per_second(|a| a.io_write_bytes),
+      ip_ingress: per_second(|a| a.ip_ingress_bytes),
+      ip_egress: per_second(|a| a.ip_egress_bytes),
+    })
+  }
+
+  /// How long the unit has been active, if it is
+  pub fn uptime(&self) -> Option<std::time::Duration> {
+    let since = self.active_since_usec?;
+    Some(std::time::Duration::from_micros(monotonic_now_usec().saturating_sub(since)))
+  }
+
+  /// How long ago the unit last changed active state
+  pub fn since_state_change(&self) -> Option<std::time::Duration> {
+    let changed = self.state_changed_usec?;
+    Some(std::time::Duration::from_micros(monotonic_now_usec().saturating_sub(changed)))
+  }
+}
+
+/// How fast a unit has been using CPU, disk and network between two readings. Each is `None` when its accounting is off
+/// or the counter went backwards, e.g. the unit restarted in between
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Rates {
+  /// Percentage of one CPU
+  pub cpu_percent: Option<f64>,
+  /// The rest are in bytes per second
+  pub io_read: Option<f64>,
+  pub io_write: Option<f64>,
+  pub ip_ingress: Option<f64>,
+  pub ip_egress: Option<f64>,
+}
+
+impl Rates {
+  /// Read and written together, for sorting by
+  pub fn io(&self) -> Option<f64> {
+    sum(self.io_read, self.io_write)
+  }
+
+  /// Received and sent together
+  pub fn network(&self) -> Option<f64> {
+    sum(self.ip_ingress, self.ip_egress)
+  }
+}
+
+fn sum(a: Option<f64>, b: Option<f64>) -> Option<f64> {
+  match (a, b) {
+    (None, None) => None,
+    (a, b) => Some(a.unwrap_or(0.0) + b.unwrap_or(0.0)),
+  }
+}
+
+pub const TRIGGER_PROPERTIES: &[&str] = &["Triggers", "TriggeredBy"];
+
+/// Activation between units: the service a timer, socket or path unit starts, and what starts a service. Often the
+/// trigger is what needs fixing, e.g. a timer firing too often
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Triggers {
+  /// What the unit starts
+  pub triggers: Vec<UnitId>,
+  /// What starts the unit
+  pub triggered_by: Vec<UnitId>,
+}
+
+impl Triggers {
+  /// Units trigger others under the same manager
+  pub fn from_properties(scope: UnitScope, properties: &HashMap<String, String>) -> Self {
+    let units = |name: &str| {
+      properties
+        .get(name)
+        .map(|v| v.split_whitespace().map(|name| UnitId { name: name.to_string(), scope }).collect())
+        .unwrap_or_default()
+    };
+    Triggers { triggers: units("Triggers"), triggered_by: units("TriggeredBy") }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.triggers.is_empty() && self.triggered_by.is_empty()
+  }
+}
+
+/// Now on the clock systemd's `*TimestampMonotonic` properties use
+pub fn monotonic_now_usec() -> u64 {
+  let mut now = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+  // SAFETY: `now` is a valid timespec to write to, and CLOCK_MONOTONIC is always available on Linux
+  unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) };
+  now.tv_sec as u64 * 1_000_000 + now.tv_nsec as u64 / 1_000
+}
+
+/// Accounting for every loaded unit in `scope`, by unit name
+pub fn get_all_accounting(scope: UnitScope) -> Result<HashMap<String, Accounting>> {
+  let properties = [&["Id"], ACCOUNTING_PROPERTIES].concat();
+  Ok(
+    get_all_unit_properties(scope, &properties)?
+      .into_iter()
+      .filter_map(|p| Some((p.get("Id")?.clone(), Accounting::from_properties(&p))))
+      .collect(),
+  )
+}
+
+/// Turn on I/O and IP accounting for a unit until the next reboot
+pub async fn enable_accounting(unit: UnitId, cancel_token: CancellationToken) -> Result<()> {
+  let assignments = vec!["IOAccounting=yes".to_string(), "IPAccounting=yes".to_string()];
+  set_properties(unit, assignments, true, cancel_token).await
+}
+
+/// `systemctl set-property`: change resource settings of a running unit, until the next reboot if `runtime`
+pub async fn set_properties(
+  unit: UnitId,
+  assignments: Vec<String>,
+  runtime: bool,
+  cancel_token: CancellationToken,
+) -> Result<()> {
+  async fn set(unit: UnitId, assignments: Vec<String>, runtime: bool) -> Result<()> {
+    let mut command = systemctl();
+    in_scope(&mut command, unit.scope).arg("set-property");
+    if runtime {
+      command.arg("--runtime");
+    }
+    command.arg(&unit.name).args(&assignments);
+    let output = tokio::process::Command::from(command).output().await?;
+    if !output.status.success() {
+      bail!("Failed to set properties of {}: {}", unit.name, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    info!("Set {} on {}", assignments.join(" "), unit.name);
+    Ok(())
+  }
+
+  tokio::select! {
+      _ = cancel_token.cancelled() => {
+          bail!("cancelled");
+      }
+      result = set(unit, assignments, runtime) => {
+          result
+      }
+  }
+}
+
+/// The target the manager boots into, e.g. `graphical.target`
+pub fn get_default_target(scope: UnitScope) -> Result<String> {
+  let mut command = systemctl();
+  in_scope(&mut command, scope);
+  let output = command.arg("get-default").output()?;
+  if !output.status.success() {
+    bail!("Failed to get the default target: {}", String::from_utf8_lossy(&output.stderr).trim());
+  }
+  Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Targets that shut the machine down or restart it. They can be isolated too, but that's what the power menu is for
+const SHUTDOWN_TARGETS: &[&str] = &[
+  "poweroff.target",
+  "reboot.target",
+  "halt.target",
+  "kexec.target",
+  "soft-reboot.target",
+  "ctrl-alt-del.target",
+  "exit.target",
+];
+
+/// A target the machine can boot into or be switched to with `systemctl isolate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BootTarget {
+  pub name: String,
+  pub description: String,
+  pub active: bool,
+}
+
+/// The targets that allow being isolated, like multi-user, graphical and rescue, leaving out the shutdown ones
+pub fn get_boot_targets(scope: UnitScope) -> Result<Vec<BootTarget>> {
+  let output = in_scope(&mut systemctl(), scope)
+    .args(["list-unit-files", "--type=target", "--no-legend", "--no-pager"])
+    .output()?;
+  if !output.status.success() {
+    bail!("Failed to list targets: {}", String::from_utf8_lossy(&output.stderr).trim());
+  }
+  let names = String::from_utf8_lossy(&output.stdout)
+    .lines()
+    .filter_map(|l| l.split_whitespace().next())
+    .filter(|name| !name.contains("@."))
+    .map(String::from)
+    .collect::<Vec<_>>();
+  if names.is_empty() {
+    return Ok(vec![]);
+  }
+  let output = in_scope(&mut systemctl(), scope)
+    .args(["show", "--property=Id,Description,AllowIsolate,ActiveState"])
+    .args(&names)
+    .output()?;
+  if !output.status.success() {
+    bail!("Failed to get the targets' properties: {}", String::from_utf8_lossy(&output.stderr).trim());
+  }
+  Ok(boot_targets(parse_property_blocks(&String::from_utf8_lossy(&output.stdout))))
+}
+
+/// The isolatable targets among `systemctl show` blocks of targets, by name
+pub fn boot_targets(blocks: Vec<HashMap<String, String>>) -> Vec<BootTarget> {
+  let mut targets = blocks
+    .into_iter()
+    .filter(|b| b.get("AllowIsolate").is_some_and(|v| v == "yes"))
+    .filter_map(|mut b| {
+      Some(BootTarget {
+        name: b.remove("Id")?,
+        description: b.remove("Description").unwrap_or_default(),
+        active: b.get("ActiveState").is_some_and(|s| s == "active"),
+      })
+    })
+    .filter(|t| !SHUTDOWN_TARGETS.contains(&t.name.as_str()))
+    .collect::<Vec<_>>();
+  targets.sort_by(|a, b| a.name.cmp(&b.name));
+  targets
+}
+
+/// `systemctl`, pointed at the remote host or container if there is one
+fn systemctl() -> Command {
+  let mut command = Command::new("systemctl");
+  if let Some(host) = remote::host() {
+    command.args(["-H", &host]);
+  }
+  command.args(machine::args());
+  command
+}
+
+/// Point `command`, a systemctl, journalctl or systemd-analyze, at `scope`'s manager: `--user` for user units, run as the user whose
+/// manager it is when that's someone else's
+pub fn in_scope(command: &mut Command, scope: UnitScope) -> &mut Command {
+  if scope == UnitScope::User {
+    if let Some(user) = user_manager::user() {
+      let lower = command.get_program() == "journalctl";
+      user.wrap(command);
+      if lower {
+        // SAFETY: as in `journalctl`
+        unsafe {
+          command.pre_exec(|| {
+            lower_priority();
+            Ok(())
+          });
+        }
+      }
+    }
+    command.arg("--user");
+  }
+  command
+}
+
+/// A `journalctl` that runs at the lowest best-effort I/O priority and a raised nice value. Reading a multi-gigabyte
+/// journal otherwise competes for the disk with the very services being investigated. journalctl has no `-H`, so pass
+/// it through `remote::on_host` once its arguments are in
+pub fn journalctl() -> Command {
+  let mut command = Command::new("journalctl");
+  // SAFETY: only async-signal-safe syscalls run between fork and exec
+  unsafe {
+    command.pre_exec(|| {
+      lower_priority();
+      Ok(())
+    });
+  }
+  command.args(machine::args());
+  command
+}
+
+fn lower_priority() {
+  const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+  const IOPRIO_CLASS_BE: libc::c_long = 2;
+  const IOPRIO_CLASS_SHIFT: libc::c_long = 13;
+  const IOPRIO_LOWEST: libc::c_long = 7;
+  // failing to deprioritize isn't worth refusing to read logs over, so errors are ignored
+  unsafe {
+    libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT | IOPRIO_LOWEST);
+    libc::setpriority(libc::PRIO_PROCESS, 0, 10);
+  }
+}
+
+/// The system manager's own log messages from the current boot
+pub fn get_manager_messages() -> Result<Vec<String>> {
+  let output = remote::on_host(journalctl().args(["--boot=0", "--output=cat", "--no-pager", "_PID=1"])).output()?;
+  if !output.status.success() {
+    bail!("Failed to read the manager's messages: {}", String::from_utf8_lossy(&output.stderr).trim());
+  }
+  Ok(String::from_utf8_lossy(&output.stdout).lines().map(str::to_string).collect())
+}
+
+/// Add up a unit's journal entries, in `namespace` if it logs to one. This reads every entry, so it takes a while for
+/// chatty units
+pub fn get_journal_usage(
+  unit: &UnitId,
+  namespace: Option<&str>,
+  cutoff_usec: Option<u64>,
+) -> Result<journal_usage::Usage> {
+  let mut command = journalctl();
+  in_scope(&mut command, unit.scope).args(["--quiet", "--no-pager", "--output=json", "-u", &unit.name]);
+  if let Some(namespace) = namespace {
+    command.arg(format!("--namespace={namespace}"));
+  }
+  let mut child = remote::on_host(&mut command).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+  let mut usage = journal_usage::Usage::default();
+  for line in BufReader::new(child.stdout.take().context("journalctl has no stdout")?).split(b'\n') {
+    usage.add_entry(&String::from_utf8_lossy(&line?), cutoff_usec);
+  }
+  let output = child.wait_with_output()?;
+  if !output.status.success() {
+    bail!("Failed to read the journal of {}: {}", unit.name, String::from_utf8_lossy(&output.stderr).trim());
+  }
+  Ok(usage)
+}
+
+/// The size of all journal files, or of one namespace's
+pub fn get_journal_disk_usage(namespace: Option<&str>) -> Result<u64> {
+  let mut command = journalctl();
+  command.arg("--disk-usage");
+  if let Some(namespace) = namespace {
+    command.arg(format!("--namespace={namespace}"));
+  }
+  let output = remote::on_host(&mut command).output()?;
+  if !output.status.success() {
+    bail!("Failed to get the journal's disk usage: {}", String::from_utf8_lossy(&output.stderr).trim());
+  }
+  journal_usage::parse_disk_usage(&String::from_utf8_lossy(&output.stdout)).context("Unexpected output from journalctl")
+}
+
+/// Delete archived journal files older than `days`, only from `namespace` if given. Returns what journalctl reports
+pub fn vacuum_journal(namespace: Option<&str>, days: u32) -> Result<Vec<String>> {
+  let mut command = journalctl();
+  command.arg(format!("--vacuum-time={days}d"));
+  if let Some(namespace) = namespace {
+    command.arg(format!("--namespace={namespace}"));
+  }
+  let output = remote::on_host(&mut command).output()?;
+  if !output.status.success() {
+    bail!("Failed to vacuum the journal: {}", String::from_utf8_lossy(&output.stderr).trim());
+  }
+  // journalctl reports what it deleted on stderr
+  Ok(String::from_utf8_lossy(&output.stderr).lines().map(str::to_string).collect())
+}
+
+/// The boots the journal has entries for, oldest first
+pub fn get_boots() -> Result<Vec<boot_history::Boot>> {
+  let output = remote::on_host(journalctl().args(["--list-boots", "--no-pager"])).output()?;
+  if !output.status.success() {
+    bail!("Failed to list boots: {}", String::from_utf8_lossy(&output.stderr).trim());
+  }
+  Ok(boot_history::parse_boots(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// The system manager's startup-finished and unit-failed messages from one boot
+pub fn get_boot_summary(boot_id: &str) -> Result<boot_history::BootSummary> {
+  let mut command = journalctl();
+  command
+    .args(["--quiet", "--output=export", "_PID=1"])
+    .arg(format!("--boot={boot_id}"))
+    .arg(format!("--output-fields={}", boot_history::FIELDS.join(",")))
+    .arg(format!("MESSAGE_ID={}", boot_history::STARTUP_FINISHED))
+    .arg(format!("MESSAGE_ID={}", boot_history::UNIT_FAILED));
+  let output = remote::on_host(&mut command).output()?;
+  if !output.status.success() {
+    bail!("Failed to read boot {boot_id}: {}", String::from_utf8_lossy(&output.stderr).trim());
+  }
+  Ok(boot_history::summarize(&parse_property_blocks(&String::from_utf8_lossy(&output.stdout))))
+}
+
+/// When each unit first started and became ready during this boot, going by the manager's messages
+pub fn get_boot_timings(scope: UnitScope) -> Result<HashMap<String, boot_wait::Timing>> {
+  let unit_field = match scope {
+    UnitScope::Global => "UNIT",
+    UnitScope::User => "USER_UNIT",
+  };
+  let mut command = journalctl();
+  command
+    .args(["--quiet", "--boot=0", "--output=export"])
+    .arg(format!("--output-fields={unit_field},MESSAGE_ID"))
+    .arg(format!("MESSAGE_ID={}", boot_wait::UNIT_STARTING))
+    .arg(format!("MESSAGE_ID={}", boot_wait::UNIT_STARTED));
+  match scope {
+    UnitScope::Global => command.arg("_PID=1"),
+    UnitScope::User => in_scope(&mut command, scope),
+  };
+  let output = remote::on_host(&mut command).output()?;
+  if !output.status.success() {
+    bail!("Failed to read this boot's start messages: {}", String::from_utf8_lossy(&output.stderr).trim());
+  }
+  Ok(boot_wait::from_journal(&parse_property_blocks(&String::from_utf8_lossy(&output.stdout)), unit_field))
+}
+
+/// One run of a unit, pieced together from what the manager logged about it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+  pub id: String,
+  /// Microseconds since the epoch of the first message about this run
+  pub started: i64,
+  /// When the manager logged the run ending, if it has
+  pub ended: Option<i64>,
+  /// `success`, or the failure result such as `exit-code`, `signal` or `oom-kill`
+  pub result: Option<String>,
+  /// The main process's exit status or signal, e.g. `1` or `SEGV`
+  pub exit_status: Option<String>,
+  /// When the manager logged the unit being reloaded during this run
+  pub reloads: Vec<i64>,
+}
+
+const UNIT_SUCCESS: &str = "7ad2d189f7e94e70a38c781354912448";
+const UNIT_FAILED: &str = "d9b373ed55a64feb8242e02dbe79a49c";
+const UNIT_STOPPED: &str = "9d1aaa27d60140bd96365438aad20286";
+const UNIT_RESOURCES: &str = "ae8f7b866b0347b9af31fe1c80b127c0";
+const UNIT_RELOADED: &str = "7b05ebc668384222baa8881179cfda54";
+
+/// Past runs of a unit, oldest first
+pub fn get_invocations(unit: &UnitId) -> Result<Vec<Invocation>> {
+  let (unit_match, invocation_field) = match unit.scope {
+    UnitScope::Global => (format!("UNIT={}", unit.name), "INVOCATION_ID"),
+    UnitScope::User => (format!("USER_UNIT={}", unit.name), "USER_INVOCATION_ID"),
+  };
+  let mut command = journalctl();
+  command
+    .args(["--quiet", "--output=export"])
+    .arg(format!("--output-fields={invocation_field},MESSAGE_ID,UNIT_RESULT,EXIT_STATUS"))
+    .arg(unit_match);
+  match unit.scope {
+    UnitScope::Global => command.arg("_PID=1"),
+    UnitScope::User => in_scope(&mut command, unit.scope),
+  };
+  let output = remote::on_host(&mut command).output()?;
+  if !output.status.success() {
+    bail!("Failed to read the runs of {}: {}", unit.name, String::from_utf8_lossy(&output.stderr).trim());
+  }
+  Ok(parse_invocations(&parse_property_blocks(&String::from_utf8_lossy(&output.stdout)), invocation_field))
+}
+
+/// Group `journalctl --output=export` entries by invocation ID
+pub fn parse_invocations(entries: &[HashMap<String, String>], invocation_field: &str) -> Vec<Invocation> {
+  let mut invocations: Vec<Invocation> = vec![];
+  for entry in entries {
+    let (Some(id), Some(timestamp)) = (
+      entry.get(invocation_field).filter(|id| !id.is_empty()),
+      entry.get("__REALTIME_TIMESTAMP").and_then(|t| t.parse::<i64>().ok()),
+    ) else {
+      continue;
+    };
+    let invocation = match invocations.iter_mut().find(|i| i.id == *id) {
+      Some(invocation) => invocation,
+      None => {
+        invocations.push(Invocation {
+          id: id.clone(),
+          started: timestamp,
+          ended: None,
+          result: None,
+          exit_status: None,
+          reloads: vec![],
+        });
+        invocations.last_mut().unwrap()
+      },
+    };
+
+    let message_id = entry.get("MESSAGE_ID").map(String::as_str);
+    if matches!(message_id, Some(UNIT_SUCCESS | UNIT_FAILED | UNIT_STOPPED | UNIT_RESOURCES)) {
+      invocation.ended = Some(timestamp);
+    }
+    if message_id == Some(UNIT_RELOADED) {
+      invocation.reloads.push(timestamp);
+    }
+    if message_id == Some(UNIT_SUCCESS) {
+      invocation.result.get_or_insert_with(|| "success".into());
+    }
+    if let Some(result) = entry.get("UNIT_RESULT") {
+      invocation.result = Some(result.clone());
+    }
+    if let Some(status) = entry.get("EXIT_STATUS") {
+      invocation.exit_status = Some(status.clone());
+    }
+  }
+  invocations
+}
+
+/// Variables that user services commonly need from the graphical or SSH session but that the user manager often lacks
+pub const SESSION_VARIABLES: &[&str] =
+  &["DISPLAY", "WAYLAND_DISPLAY", "XAUTHORITY", "SSH_AUTH_SOCK", "DBUS_SESSION_BUS_ADDRESS", "XDG_RUNTIME_DIR"];
+
+fn systemctl_environment(scope: UnitScope, verb: &str, args: &[String]) -> Result<String> {
+  let mut command = systemctl();
+  in_scope(&mut command, scope);
+  // assignments and names are the user's, and one starting with a dash mustn't be taken for an option
+  let output = command.arg(verb).arg("--").args(args).output()?;
+  if !output.status.success() {
+    bail!("systemctl {verb} failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+  }
+  Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// The service manager's environment, as `NAME=value` lines
+pub fn get_manager_environment(scope: UnitScope) -> Result<Vec<String>> {
+  Ok(systemctl_environment(scope, "show-environment", &[])?.lines().map(str::to_string).collect())
+}
+
+/// Set `NAME=value` assignments in the service manager's environment
+pub fn set_manager_environment(scope: UnitScope, assignments: &[String]) -> Result<()> {
+  systemctl_environment(scope, "set-environment", assignments).map(|_| ())
+}
+
+pub fn unset_manager_environment(scope: UnitScope, names: &[String]) -> Result<()> {
+  systemctl_environment(scope, "unset-environment", names).map(|_| ())
+}
+
+/// Session variables that are set here (according to `session`) but missing from, or different in, the manager
+pub fn missing_session_variables(manager: &[String], session: impl Fn(&str) -> Option<String>) -> Vec<String> {
+  SESSION_VARIABLES
+    .iter()
+    .filter_map(|name| {
+      let value = session(name)?;
+      let assignment = format!("{name}={value}");
+      (!manager.contains(&assignment)).then_some(assignment)
+    })
+    .collect()
+}
+
+/// Executable paths from an `ExecStart`-style property, e.g. `{ path=/usr/sbin/nginx ; argv[]=... }`
+pub fn parse_exec_paths(value: &str) -> Vec<String> {
+  value
+    .split("{ path=")
+    .skip(1)
+    .filter_map(|s| s.split(' ').next())
+    .filter(|p| !p.is_empty())
+    .map(String::from)
+    .collect()
+}
+
+pub async fn start_service(service: UnitId, cancel_token: CancellationToken) -> Result<()> {
+  async fn start_service(service: UnitId) -> Result<()> {
+    let connection = get_connection(service.scope).await?;
+    let manager_proxy = ManagerProxy::new(&connection).await?;
+    manager_proxy.start_unit(service.name.clone(), "replace".into()).await?;
+    Ok(())
+  }
+
+  // god these select macros are ugly, is there really no better way to select?
+  tokio::select! {
+    _ = cancel_token.cancelled() => {
+        anyhow::bail!("cancelled");
+    }
+    result = start_service(service) => {
+        result
+    }
+  }
+}
+
+pub async fn stop_service(service: UnitId, cancel_token: CancellationToken) -> Result<()> {
+  async fn stop_service(service: UnitId) -> Result<()> {
+    let connection = get_connection(service.scope).await?;
+    let manager_proxy = ManagerProxy::new(&connection).await?;
+    manager_proxy.stop_unit(service.name, "replace".into()).await?;
+    Ok(())
+  }
+
+  // god these select macros are ugly, is there really no better way to select?
+  tokio::select! {
+    _ = cancel_token.cancelled() => {
+        anyhow::bail!("cancelled");
+    }
+    result = stop_service(service) => {
+        result
+    }
+  }
+}
+
+/// What can be done to all the marked units at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchVerb {
+  Start,
+  Stop,
+  Restart,
+}
+
+impl BatchVerb {
+  pub fn name(self) -> &'static str {
+    match self {
+      BatchVerb::Start => "Start",
+      BatchVerb::Stop => "Stop",
+      BatchVerb::Restart => "Restart",
+    }
+  }
+
+  /// Queue the job for `unit`
+  pub async fn run(self, unit: UnitId) -> Result<()> {
+    let cancel_token = CancellationToken::new();
+    match self {
+      BatchVerb::Start => start_service(unit, cancel_token).await,
+      BatchVerb::Stop => stop_service(unit, cancel_token).await,
+      BatchVerb::Restart => restart_service(unit, cancel_token).await,
+    }
+  }
+
+  /// Whether a unit that's settled in `active_state` got where this was taking it
+  pub fn succeeded(self, active_state: &str) -> bool {
+    match self {
+      BatchVerb::Start | BatchVerb::Restart => active_state == "active",
+      BatchVerb::Stop => active_state != "active",
+    }
+  }
+}
+
+/// A unit's active state once it's done activating or deactivating, or whatever it is after `timeout`
+pub async fn wait_until_settled(unit: &UnitId, timeout: std::time::Duration) -> Result<String> {
+  let connection = get_connection(unit.scope).await?;
+  let deadline = tokio::time::Instant::now() + timeout;
+  loop {
+    // give the job a moment to get going, or a restart looks done before it's begun
+    tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+    let state = get_active_state(&connection, &unit.name).await;
+    if !matches!(state.as_str(), "activating" | "deactivating" | "reloading") || tokio::time::Instant::now() >= deadline
+    {
+      return Ok(state);
+    }
+  }
+}
+
+/// A job in a manager's queue, as `systemctl list-jobs` lists them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Job {
+  pub id: u32,
+  pub unit: UnitId,
+  /// start, stop, restart, reload...
+  pub job_type: String,
+  /// waiting or running
+  pub state: String,
+  /// The units whose jobs have to finish before this one can run
+  pub waiting_for: Vec<String>,
+}
+
+impl Job {
+  /// e.g. "start job 42 waiting for network-online.target"
+  pub fn describe(&self) -> String {
+    let mut text = format!("{} job {} {}", self.job_type, self.id, self.state);
+    if !self.waiting_for.is_empty() {
+      text.push_str(&format!(" for {}", self.waiting_for.join(", ")));
+    }
+    text
+  }
+}
+
+/// The jobs queued in `scope`'s manager, oldest first
+pub async fn get_jobs(scope: UnitScope) -> Result<Vec<Job>> {
+  let connection = get_connection(scope).await?;
+  let manager_proxy = ManagerProxy::new(&connection).await?;
+  let mut jobs = vec![];
+  for (id, unit, job_type, state, path, _) in manager_proxy.list_jobs().await? {
+    let mut waiting_for = vec![];
+    if state == "waiting" {
+      // the job may be gone by now, in which case it isn't waiting on anything
+      for (_, unit, ..) in JobProxy::new(&connection, path).await?.get_after().await.unwrap_or_default() {
+        if !waiting_for.contains(&unit) {
+          waiting_for.push(unit);
+        }
+      }
+    }
+    jobs.push(Job { id, unit: UnitId { name: unit, scope }, job_type, state, waiting_for });
+  }
+  jobs.sort_by_key(|j| j.id);
+  Ok(jobs)
+}
+
+pub async fn cancel_job(scope: UnitScope, id: u32) -> Result<()> {
+  let connection = get_connection(scope).await?;
+  let manager_proxy = ManagerProxy::new(&connection).await?;
+  manager_proxy.cancel_job(id).await?;
+  Ok(())
+}
+
+pub async fn enable_service(service: UnitId, cancel_token: CancellationToken) -> Result<()> {
+  async fn enable(service: UnitId) -> Result<()> {
+    let connection = get_connection(service.scope).await?;
+    let manager_proxy = ManagerProxy::new(&connection).await?;
+    manager_proxy.enable_unit_files(vec![service.name], false, false).await?;
+    // like `systemctl enable`, reload so the new symlinks take effect
+    manager_proxy.reload().await?;
+    Ok(())
+  }
+
+  tokio::select! {
+    _ = cancel_token.cancelled() => {
+        anyhow::bail!("cancelled");
+    }
+    result = enable(service) => {
+        result
+    }
+  }
+}
+
+/// What can be done to unit files' enablement, to one unit or many at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnablementVerb {
+  Enable,
+  Disable,
+  /// Disable then enable, putting the symlinks back the way [Install] says
+  Reenable,
+  /// Enable or disable, as the preset files say
+  Preset(PresetMode),
+}
+
+impl EnablementVerb {
+  pub fn name(self) -> &'static str {
+    match self {
+      EnablementVerb::Enable => "Enable",
+      EnablementVerb::Disable => "Disable",
+      EnablementVerb::Reenable => "Reenable",
+      EnablementVerb::Preset(_) => "Preset",
+    }
+  }
+}
+
+/// `created /etc/systemd/system/multi-user.target.wants/foo.service → ...` for each change the manager reports
+fn describe_unit_file_changes(changes: Vec<(String, String, String)>) -> Vec<String> {
+  changes
+    .into_iter()
+    .map(|(kind, file, destination)| match kind.as_str() {
+      "symlink" => format!("created {file} → {destination}"),
+      "unlink" => format!("removed {file}"),
+      _ => format!("{kind} {file}"),
+    })
+    .collect()
+}
+
+/// Change a unit file's enablement without reloading the manager, so several can be changed before one reload.
+/// Returns what changed on disk
+pub async fn set_unit_file_enablement(unit: &UnitId, verb: EnablementVerb) -> Result<Vec<String>> {
+  let connection = get_connection(unit.scope).await?;
+  let manager_proxy = ManagerProxy::new(&connection).await?;
+  let files = vec![unit.name.clone()];
+  let changes = match verb {
+    EnablementVerb::Enable => manager_proxy.enable_unit_files(files, false, false).await?.1,
+    EnablementVerb::Disable => manager_proxy.disable_unit_files(files, false).await?,
+    EnablementVerb::Reenable => manager_proxy.reenable_unit_files(files, false, false).await?.1,
+    EnablementVerb::Preset(mode) => {
+      manager_proxy.preset_unit_files_with_mode(files, mode.name().into(), false, false).await?.1
+    },
+  };
+  Ok(describe_unit_file_changes(changes))
+}
+
+/// Apply the presets to every unit file of the `scope` manager, like `systemctl preset-all`, without reloading it.
+/// Returns what changed on disk
+pub async fn preset_all_unit_files(scope: UnitScope, mode: PresetMode) -> Result<Vec<String>> {
+  let connection = get_connection(scope).await?;
+  let manager_proxy = ManagerProxy::new(&connection).await?;
+  let changes = manager_proxy.preset_all_unit_files(mode.name().into(), false, false).await?;
+  Ok(describe_unit_file_changes(changes))
+}
+
+/// The next few times an `OnCalendar=` expression elapses, according to `systemd-analyze calendar`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CalendarPreview {
+  pub normalized: String,
+  /// (timestamp, time from now)
+  pub elapses: Vec<(String, String)>,
+}
+
+pub fn calendar_preview(expression: &str, iterations: usize) -> Result<CalendarPreview> {
+  // what's typed into the wizard could start with a dash, and mustn't be taken for an option
+  let output = Command::new("systemd-analyze")
+    .args(["calendar", &format!("--iterations={iterations}"), "--", expression])
+    .output()?;
+  if !output.status.success() {
+    bail!(String::from_utf8_lossy(&output.stderr).trim().to_string());
+  }
+  Ok(parse_calendar_preview(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn parse_calendar_preview(output: &str) -> CalendarPreview {
+  let mut preview = CalendarPreview::default();
+  for line in output.lines() {
+    let Some((key, value)) = line.trim().split_once(": ") else { continue };
+    match key {
+      "Normalized form" => preview.normalized = value.to_string(),
+      "Next elapse" => preview.elapses.push((value.to_string(), String::new())),
+      k if k.starts_with("Iter. #") => preview.elapses.push((value.to_string(), String::new())),
+      "From now" => {
+        if let Some(last) = preview.elapses.last_mut() {
+          last.1 = value.to_string();
+        }
+      },
+      _ => {},
+    }
+  }
+  preview
+}
+
+/// Run `systemd-analyze verify` on a unit file, failing with its complaints if there are any
+pub fn verify_unit_file(path: &str, scope: UnitScope) -> Result<()> {
+  let verification = verify(path, scope)?;
+  if !verification.passed {
+    let complaints = verification.diagnostics.iter().map(|d| d.to_string()).collect::<Vec<_>>().join("\n");
+    bail!("{} failed verification:\n{}", path, complaints);
+  }
+  Ok(())
+}
+
+/// What `systemd-analyze verify` made of a unit file: whether it would load, and what it warned about on the way
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verification {
+  pub passed: bool,
+  pub diagnostics: Vec<Diagnostic>,
+}
+
+/// One of `systemd-analyze verify`'s complaints, with the line it's about when it names one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+  pub path: Option<String>,
+  pub line: Option<usize>,
+  pub message: String,
+  /// What's on that line of the file
+  pub source: Option<String>,
+}
+
+impl std::fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match (&self.path, self.line) {
+      (Some(path), Some(line)) => write!(f, "{path}:{line}: {}", self.message),
+      _ => write!(f, "{}", self.message),
+    }
+  }
+}
+
+/// Run `systemd-analyze verify` on a unit file here, which also loads the drop-ins that apply to it
+pub fn verify(path: &str, scope: UnitScope) -> Result<Verification> {
+  remote::ensure_local("Verifying unit files")?;
+  let output = in_scope(&mut Command::new("systemd-analyze"), scope)
+    .args(["verify", path])
+    .output()
+    .context("Failed to run systemd-analyze")?;
+  let mut diagnostics = parse_verify_output(&String::from_utf8_lossy(&output.stderr));
+  for diagnostic in &mut diagnostics {
+    let (Some(path), Some(line)) = (&diagnostic.path, diagnostic.line) else { continue };
+    let contents = std::fs::read_to_string(path).unwrap_or_default();
+    diagnostic.source = contents.lines().nth(line.saturating_sub(1)).map(String::from);
+  }
+  Ok(Verification { passed: output.status.success(), diagnostics })
+}
+
+/// `systemd-analyze verify`'s complaints, one per line. The ones about a particular line start with `path:line: `
+pub fn parse_verify_output(output: &str) -> Vec<Diagnostic> {
+  output
+    .lines()
+    .map(str::trim)
+    .filter(|line| !line.is_empty())
+    .map(|line| {
+      let located = line.split_once(": ").and_then(|(location, message)| {
+        let (path, number) = location.rsplit_once(':')?;
+        Some((path, number.parse().ok()?, message))
+      });
+      match located {
+        Some((path, number, message)) => {
+          Diagnostic { path: Some(path.into()), line: Some(number), message: message.into(), source: None }
+        },
+        None => Diagnostic { path: None, line: None, message: line.into(), source: None },
+      }
+    })
+    .collect()
+}
+
+pub async fn reload(scope: UnitScope, cancel_token: CancellationToken) -> Result<()> {
+  async fn reload_(scope: UnitScope) -> Result<()> {
+    let connection = get_connection(scope).await?;
+    let manager_proxy: ManagerProxy<'_> = ManagerProxy::new(&connection).await?;
+    let error_message = match scope {
+      UnitScope::Global => "Failed to reload units, probably because superuser permissions are needed. Try running `sudo systemctl daemon-reload`",
+      UnitScope::User => "Failed to reload units. Try running `systemctl --user daemon-reload`",
+    };
+    manager_proxy.reload().await.context(error_message)?;
+    Ok(())
+  }
+
+  // god these select macros are ugly, is there really no better way to select?
+  tokio::select! {
+    _ = cancel_token.cancelled() => {
+        anyhow::bail!("cancelled");
+    }
+    result = reload_(scope) => {
+        result
+    }
+  }
+}
+
+/// A command to start as a transient unit, like `systemd-run`: in a service the manager runs it in, or (for a unit
+/// named `*.scope`) in a scope around a process started here, like `systemd-run --scope`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transient {
+  pub unit: UnitId,
+  /// The program and its arguments. The program needs a full path unless it's on this machine's `PATH`
+  pub command: Vec<String>,
+  pub description: String,
+  /// Services only
+  pub user: Option<String>,
+  pub working_directory: Option<String>,
+  pub memory_max: Option<u64>,
+}
+
+impl Transient {
+  pub fn is_scope(&self) -> bool {
+    self.unit.name.ends_with(".scope")
+  }
+
+  /// Check what can be checked before asking the manager
+  pub fn validate(&self) -> Result<()> {
+    if !self.unit.name.ends_with(".service") && !self.is_scope() {
+      bail!("A transient unit is a .service or a .scope");
+    }
+    if self.command.is_empty() {
+      bail!("Enter a command to run");
+    }
+    if self.is_scope() && self.user.is_some() {
+      bail!("Only services can run as another user; a scope's process is started here, as you");
+    }
+    Ok(())
+  }
+
+  /// The transient unit's properties, as `StartTransientUnit` takes them. `pids` are the processes a scope starts with
+  fn properties(&self, program: String, pids: Vec<u32>) -> Vec<(String, zvariant::Value<'static>)> {
+    let mut properties = vec![("Description".to_string(), zvariant::Value::from(self.description.clone()))];
+    if self.is_scope() {
+      properties.push(("PIDs".into(), zvariant::Value::from(pids)));
+    } else {
+      // (path, argv, ignore failure), as ExecStart= is on the bus
+      properties.push(("ExecStart".into(), zvariant::Value::from(vec![(program, self.command.clone(), false)])));
+      if let Some(directory) = &self.working_directory {
+        properties.push(("WorkingDirectory".into(), zvariant::Value::from(directory.clone())));
+      }
+      if let Some(user) = &self.user {
+        properties.push(("User".into(), zvariant::Value::from(user.clone())));
+      }
+    }
+    if let Some(bytes) = self.memory_max {
+      properties.push(("MemoryMax".into(), zvariant::Value::from(bytes)));
+    }
+    properties
+  }
+}
+
+/// The full path of `program`, looked up on `PATH` if it isn't one already. Only this machine's `PATH` can be searched
+fn executable(program: &str) -> Result<String> {
+  if program.starts_with('/') {
+    return Ok(program.to_string());
+  }
+  remote::ensure_local(&format!("Finding {program} on the PATH"))?;
+  find_on_path(program)
+    .map(|found| found.to_string_lossy().to_string())
+    .with_context(|| format!("{program} isn't on the PATH"))
+}
+
+/// Where `program` is on this machine's `PATH`, if it's there
+pub fn find_on_path(program: &str) -> Option<PathBuf> {
+  let path = std::env::var_os("PATH")?;
+  std::env::split_paths(&path).map(|dir| dir.join(program)).find(|candidate| candidate.is_file())
+}
+
+/// Start `transient` with `StartTransientUnit`
+pub async fn start_transient(transient: Transient, cancel_token: CancellationToken) -> Result<()> {
+  async fn start(transient: Transient) -> Result<()> {
+    transient.validate()?;
+    let program = executable(&transient.command[0])?;
+    let connection = get_connection(transient.unit.scope).await?;
+    let manager_proxy = ManagerProxy::new(&connection).await?;
+
+    let mut pids = vec![];
+    if transient.is_scope() {
+      remote::ensure_local("Running a command in a scope")?;
+      let mut command = tokio::process::Command::new(&program);
+      command.args(&transient.command[1..]).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+      // in its own process group, so it isn't sent the terminal's signals
+      command.process_group(0);
+      if let Some(directory) = &transient.working_directory {
+        command.current_dir(directory);
+      }
+      let mut child = command.spawn().with_context(|| format!("Failed to run {program}"))?;
+      pids.push(child.id().context("The command exited straight away")?);
+      // reaped when it exits, however long that takes
+      tokio::spawn(async move { child.wait().await });
+    }
+
+    let properties = transient.properties(program, pids.clone());
+    let started =
+      manager_proxy.start_transient_unit(transient.unit.name.clone(), "fail".into(), properties, vec![]).await;
+    if let Err(e) = started {
+      // a process the manager didn't take in would be left running outside of any scope
+      for pid in pids {
+        // SAFETY: kill has no memory safety requirements
+        unsafe { libc::kill(pid as i32, libc::SIGTERM) };
+      }
+      return Err(e).with_context(|| format!("Failed to start {}", transient.unit.name));
+    }
+    info!("Started transient unit {}", transient.unit.name);
+    Ok(())
+  }
+
+  tokio::select! {
+      _ = cancel_token.cancelled() => {
+          bail!("cancelled");
+      }
+      result = start(transient) => {
+          result
+      }
+  }
+}
+
+/// The bus `scope`'s manager is on, here or on the remote host
+pub async fn get_connection(scope: UnitScope) -> Result<Connection, anyhow::Error> {
+  if let Some(host) = remote::host() {
+    if scope == UnitScope::User {
+      bail!("User units can't be managed on a remote host");
+    }
+    return Ok(zbus::connection::Builder::address(remote::bus_address(&host))?.build().await?);
+  }
+  if let Some(machine) = machine::machine() {
+    if scope == UnitScope::User {
+      bail!("User units can't be managed in a container");
+    }
+    return Ok(zbus::connection::Builder::address(machine::bus_address(&machine))?.build().await?);
+  }
+  match (scope, user_manager::user()) {
+    (UnitScope::Global, _) => Ok(Connection::system().await?),
+    (UnitScope::User, Some(user)) => Ok(zbus::connection::Builder::address(user.bus_address())?.build().await?),
+    (UnitScope::User, None) => Ok(Connection::session().await?),
+  }
+}
+
+pub async fn restart_service(service: UnitId, cancel_token: CancellationToken) -> Result<()> {
+  async fn restart(service: UnitId) -> Result<()> {
+    let connection = get_connection(service.scope).await?;
+    let manager_proxy = ManagerProxy::new(&connection).await?;
+    manager_proxy.restart_unit(service.name, "replace".into()).await?;
+    Ok(())
+  }
+
+  // god these select macros are ugly, is there really no better way to select?
+  tokio::select! {
+    _ = cancel_token.cancelled() => {
+        // The token was cancelled
+        anyhow::bail!("cancelled");
+    }
+    result = restart(service) => {
+        result
+    }
+  }
+}
+
+// useless function only added to test that cancellation works
+pub async fn sleep_test(_service: String, cancel_token: CancellationToken) -> Result<()> {
+  // god these select macros are ugly, is there really no better way to select?
+  tokio::select! {
+      _ = cancel_token.cancelled() => {
+          // The token was cancelled
+          anyhow::bail!("cancelled");
+      }
+      _ = tokio::time::sleep(std::time::Duration::from_secs(2)) => {
+          Ok(())
+      }
+  }
+}
+
+/// Which of a unit's processes a signal goes to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KillWhom {
+  #[default]
+  All,
+  Main,
+  Control,
+}
+
+impl KillWhom {
+  /// As `KillUnit` and `systemctl kill --kill-whom=` take it
+  pub fn as_str(self) -> &'static str {
+    match self {
+      KillWhom::All => "all",
+      KillWhom::Main => "main",
+      KillWhom::Control => "control",
+    }
+  }
+
+  pub fn parse(whom: &str) -> Result<Self> {
+    Ok(match whom {
+      "all" => KillWhom::All,
+      "main" => KillWhom::Main,
+      "control" => KillWhom::Control,
+      _ => bail!("`{whom}` isn't main, control or all"),
+    })
+  }
+}
+
+/// The number of a signal given as a name (`SIGHUP`, `hup`) or a number (`1`, or up to `SIGRTMAX` for real-time ones)
+pub fn signal_number(signal: &str) -> Result<i32> {
+  let signal = signal.trim();
+  if let Ok(number) = signal.parse::<i32>() {
+    if !(1..=libc::SIGRTMAX()).contains(&number) {
+      bail!("{number} isn't a signal number");
+    }
+    return Ok(number);
+  }
+  let upper = signal.to_uppercase();
+  let name = if upper.starts_with("SIG") { upper } else { format!("SIG{upper}") };
+  match name.parse::<nix::sys::signal::Signal>() {
+    Ok(signal) => Ok(signal as i32),
+    Err(_) => bail!("`{signal}` isn't a signal, e.g. SIGTERM, HUP or 10"),
+  }
+}
+
+/// Send `signal` to the unit's processes through the manager's `KillUnit`
+pub async fn kill_service(
+  service: UnitId,
+  signal: String,
+  whom: KillWhom,
+  cancel_token: CancellationToken,
+) -> Result<()> {
+  async fn kill(service: UnitId, signal: String, whom: KillWhom) -> Result<()> {
+    let number = signal_number(&signal)?;
+    let connection = get_connection(service.scope).await?;
+    let manager_proxy = ManagerProxy::new(&connection).await?;
+    manager_proxy
+      .kill_unit(service.name.clone(), whom.as_str().into(), number)
+      .await
+      .with_context(|| format!("Failed to send {signal} to {}", service.name))?;
+    info!("Sent {} to the {} processes of {}", signal, whom.as_str(), service.name);
+    Ok(())
+  }
+
+  tokio::select! {
+      _ = cancel_token.cancelled() => {
+          bail!("cancelled");
+      }
+      result = kill(service, signal, whom) => {
+          result
+      }
+  }
+}
+
+/// Forget that a unit failed, and its restart counter, as `systemctl reset-failed <unit>` does
+pub async fn reset_failed_unit(service: UnitId, cancel_token: CancellationToken) -> Result<()> {
+  async fn reset(service: UnitId) -> Result<()> {
+    let connection = get_connection(service.scope).await?;
+    let manager_proxy = ManagerProxy::new(&connection).await?;
+    manager_proxy
+      .reset_failed_unit(service.name.clone())
+      .await
+      .with_context(|| format!("Failed to reset the failed state of {}", service.name))?;
+    info!("Reset the failed state of {}", service.name);
+    Ok(())
+  }
+
+  tokio::select! {
+      _ = cancel_token.cancelled() => {
+          bail!("cancelled");
+      }
+      result = reset(service) => {
+          result
+      }
+  }
+}
+
+/// Pause every process of the unit with the cgroup freezer, or resume them with `thaw`
+pub async fn freeze_unit(service: UnitId, thaw: bool, cancel_token: CancellationToken) -> Result<()> {
+  async fn freeze(service: UnitId, thaw: bool) -> Result<()> {
+    let connection = get_connection(service.scope).await?;
+    let manager_proxy = ManagerProxy::new(&connection).await?;
+    if thaw {
+      manager_proxy
+        .thaw_unit(service.name.clone())
+        .await
+        .with_context(|| format!("Failed to thaw {}", service.name))?;
+    } else {
+      manager_proxy
+        .freeze_unit(service.name.clone())
+        .await
+        .with_context(|| format!("Failed to freeze {}", service.name))?;
+    }
+    Ok(())
+  }
+
+  tokio::select! {
+      _ = cancel_token.cancelled() => {
+          bail!("cancelled");
+      }
+      result = freeze(service, thaw) => {
+          result
+      }
+  }
+}
+
+/// Forget every failed unit of `scope`'s manager, as `systemctl reset-failed` does
+pub async fn reset_failed(scope: UnitScope, cancel_token: CancellationToken) -> Result<()> {
+  async fn reset(scope: UnitScope) -> Result<()> {
+    let connection = get_connection(scope).await?;
+    let manager_proxy = ManagerProxy::new(&connection).await?;
+    manager_proxy.reset_failed().await.context("Failed to reset failed units")?;
+    info!("Reset all failed {:?} units", scope);
+    Ok(())
+  }
+
+  tokio::select! {
+      _ = cancel_token.cancelled() => {
+          bail!("cancelled");
+      }
+      result = reset(scope) => {
+          result
+      }
+  }
+}
+
+/// `systemctl` with `args` against `scope`'s manager, e.g. a command typed in the command bar
+pub fn run_systemctl(scope: UnitScope, args: &[String]) -> Result<std::process::Output> {
+  Ok(in_scope(&mut systemctl(), scope).args(args).output()?)
+}
+
+/// `run_systemctl` as a job, failing with what systemctl said when it fails
+pub async fn run_systemctl_job(scope: UnitScope, args: Vec<String>, cancel_token: CancellationToken) -> Result<()> {
+  async fn run(scope: UnitScope, args: Vec<String>) -> Result<()> {
+    let output = run_systemctl(scope, &args)?;
+    if !output.status.success() {
+      bail!("systemctl {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+  }
+
+  tokio::select! {
+    _ = cancel_token.cancelled() => {
+      bail!("cancelled");
+    }
+    result = run(scope, args) => {
+      result
+    }
+  }
+}
+
+/// The kinds of change `systemd-delta` reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DeltaKind {
+  Masked,
+  Equivalent,
+  Redirected,
+  Overridden,
+  Extended,
+  Unchanged,
+}
+
+impl DeltaKind {
+  fn parse(s: &str) -> Option<Self> {
+    match s {
+      "MASKED" => Some(DeltaKind::Masked),
+      "EQUIVALENT" => Some(DeltaKind::Equivalent),
+      "REDIRECTED" => Some(DeltaKind::Redirected),
+      "OVERRIDDEN" => Some(DeltaKind::Overridden),
+      "EXTENDED" => Some(DeltaKind::Extended),
+      "UNCHANGED" => Some(DeltaKind::Unchanged),
+      _ => None,
+    }
+  }
+
+  pub fn label(&self) -> &'static str {
+    match self {
+      DeltaKind::Masked => "Masked",
+      DeltaKind::Equivalent => "Equivalent",
+      DeltaKind::Redirected => "Redirected",
+      DeltaKind::Overridden => "Overridden",
+      DeltaKind::Extended => "Extended",
+      DeltaKind::Unchanged => "Unchanged",
+    }
+  }
+}
+
+/// One entry of `systemd-delta` output, e.g. `[OVERRIDDEN] /etc/systemd/system/foo.service → /usr/lib/systemd/system/foo.service`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeltaEntry {
+  pub kind: DeltaKind,
+  /// The overriding file (or for `Extended`, the unit file being extended)
+  pub path: String,
+  /// The overridden vendor file (or for `Extended`, the drop-in)
+  pub target: String,
+  /// Unified diff, only present for `Overridden`
+  pub diff: Vec<String>,
+}
+
+impl DeltaEntry {
+  /// Whether this entry concerns the given unit (or the template it was instantiated from)
+  pub fn is_for_unit(&self, unit_name: &str) -> bool {
+    let template = template_name(unit_name);
+    let file_name = |p: &str| p.rsplit('/').next().unwrap_or(p).to_string();
+    [file_name(&self.path), file_name(&self.target)].iter().any(|f| f == unit_name || Some(f) == template.as_ref())
+      || self.target.contains(&format!("/{unit_name}.d/"))
+  }
+}
+
+/// `foo@bar.service` -> `foo@.service`
+pub fn template_name(unit_name: &str) -> Option<String> {
+  let (prefix, rest) = unit_name.split_once('@')?;
+  let (_instance, suffix) = rest.rsplit_once('.')?;
+  Some(format!("{prefix}@.{suffix}"))
+}
+
+pub fn parse_delta(output: &str) -> Vec<DeltaEntry> {
+  let mut entries: Vec<DeltaEntry> = vec![];
+  for line in output.lines() {
+    let header = line
+      .strip_prefix('[')
+      .and_then(|l| l.split_once(']'))
+      .and_then(|(kind, rest)| Some((DeltaKind::parse(kind)?, rest.trim().split_once(" → ")?)));
+
+    match header {
+      Some((kind, (path, target))) => {
+        entries.push(DeltaEntry { kind, path: path.to_string(), target: target.to_string(), diff: vec![] })
+      },
+      None => {
+        if let Some(entry) = entries.last_mut() {
+          // skip the blank line between the header and the diff, and the summary line at the end
+          if (entry.diff.is_empty() && line.is_empty()) || line.ends_with("overridden configuration files found.") {
+            continue;
+          }
+          entry.diff.push(line.to_string());
+        }
+      },
+    }
+  }
+
+  for entry in entries.iter_mut() {
+    while entry.diff.last().is_some_and(|l| l.is_empty()) {
+      entry.diff.pop();
+    }
+  }
+  entries
+}
+
+/// Run `systemd-delta` for system or user unit directories
+pub fn get_delta(scope: UnitScope) -> Result<Vec<DeltaEntry>> {
+  // it only looks at the directories of the machine it runs on
+  if let Some(machine) = machine::machine() {
+    bail!("Comparing unit files with the vendor's isn't possible in a container ({machine}), only on this machine");
+  }
+  let prefix = match scope {
+    UnitScope::Global => "systemd/system",
+    UnitScope::User => "systemd/user",
+  };
+  let output = remote::on_host(Command::new("systemd-delta").args(["--no-pager", prefix])).output()?;
+  if !output.status.success() {
+    bail!("systemd-delta failed: {}", String::from_utf8_lossy(&output.stderr));
+  }
+  Ok(parse_delta(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Proxy object for `org.freedesktop.systemd1.Manager`.
+/// Partially taken from https://github.com/lucab/zbus_systemd/blob/main/src/systemd1/generated.rs
+#[proxy(
+  interface = "org.freedesktop.systemd1.Manager",
+  default_service = "org.freedesktop.systemd1",
+  default_path = "/org/freedesktop/systemd1",
+  gen_blocking = false
+)]
+pub trait Manager {
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#StartUnit()) Call interface method `StartUnit`.
+  #[zbus(name = "StartUnit")]
+  fn start_unit(&self, name: String, mode: String) -> zbus::Result<zvariant::OwnedObjectPath>;
+
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#StopUnit()) Call interface method `StopUnit`.
+  #[zbus(name = "StopUnit")]
+  fn stop_unit(&self, name: String, mode: String) -> zbus::Result<zvariant::OwnedObjectPath>;
+
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#ReloadUnit()) Call interface method `ReloadUnit`.
+  #[zbus(name = "ReloadUnit")]
+  fn reload_unit(&self, name: String, mode: String) -> zbus::Result<zvariant::OwnedObjectPath>;
+
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#RestartUnit()) Call interface method `RestartUnit`.
+  #[zbus(name = "RestartUnit")]
+  fn restart_unit(&self, name: String, mode: String) -> zbus::Result<zvariant::OwnedObjectPath>;
+
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#StartTransientUnit()) Call interface method `StartTransientUnit`.
+  #[zbus(name = "StartTransientUnit")]
+  fn start_transient_unit(
+    &self,
+    name: String,
+    mode: String,
+    properties: Vec<(String, zvariant::Value<'_>)>,
+    aux: Vec<(String, Vec<(String, zvariant::Value<'_>)>)>,
+  ) -> zbus::Result<zvariant::OwnedObjectPath>;
+
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#KillUnit()) Call interface method `KillUnit`.
+  #[zbus(name = "KillUnit")]
+  fn kill_unit(&self, name: String, whom: String, signal: i32) -> zbus::Result<()>;
+
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#ResetFailedUnit()) Call interface method `ResetFailedUnit`.
+  #[zbus(name = "ResetFailedUnit")]
+  fn reset_failed_unit(&self, name: String) -> zbus::Result<()>;
+
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#FreezeUnit()) Call interface method `FreezeUnit`.
+  #[zbus(name = "FreezeUnit")]
+  fn freeze_unit(&self, name: String) -> zbus::Result<()>;
+
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#ThawUnit()) Call interface method `ThawUnit`.
+  #[zbus(name = "ThawUnit")]
+  fn thaw_unit(&self, name: String) -> zbus::Result<()>;
+
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#ResetFailed()) Call interface method `ResetFailed`.
+  #[zbus(name = "ResetFailed")]
+  fn reset_failed(&self) -> zbus::Result<()>;
+
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#EnableUnitFiles()) Call interface method `EnableUnitFiles`.
+  #[zbus(name = "EnableUnitFiles")]
+  fn enable_unit_files(
+    &self,
+    files: Vec<String>,
+    runtime: bool,
+    force: bool,
+  ) -> zbus::Result<(bool, Vec<(String, String, String)>)>;
+
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#DisableUnitFiles()) Call interface method `DisableUnitFiles`.
+  #[zbus(name = "DisableUnitFiles")]
+  fn disable_unit_files(&self, files: Vec<String>, runtime: bool) -> zbus::Result<Vec<(String, String, String)>>;
+
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#ReenableUnitFiles()) Call interface method `ReenableUnitFiles`.
+  #[zbus(name = "ReenableUnitFiles")]
+  fn reenable_unit_files(
+    &self,
+    files: Vec<String>,
+    runtime: bool,
+    force: bool,
+  ) -> zbus::Result<(bool, Vec<(String, String, String)>)>;
+
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#PresetUnitFilesWithMode()) Call interface method `PresetUnitFilesWithMode`.
+  #[zbus(name = "PresetUnitFilesWithMode")]
+  fn preset_unit_files_with_mode(
+    &self,
+    files: Vec<String>,
+    mode: String,
+    runtime: bool,
+    force: bool,
+  ) -> zbus::Result<(bool, Vec<(String, String, String)>)>;
+
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#PresetAllUnitFiles()) Call interface method `PresetAllUnitFiles`.
+  #[zbus(name = "PresetAllUnitFiles")]
+  fn preset_all_unit_files(
+    &self,
+    mode: String,
+    runtime: bool,
+    force: bool,
+  ) -> zbus::Result<Vec<(String, String, String)>>;
+
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#ListUnits()) Call interface method `ListUnits`.
+  #[zbus(name = "ListUnits")]
+  fn list_units(
+    &self,
+  ) -> zbus::Result<
+    Vec<(
+      String,
+      String,
+      String,
+      String,
+      String,
+      String,
+      zvariant::OwnedObjectPath,
+      u32,
+      String,
+      zvariant::OwnedObjectPath,
+    )>,
+  >;
+
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#ListUnitsByPatterns()) Call interface method `ListUnitsByPatterns`.
+  #[zbus(name = "ListUnitsByPatterns")]
+  fn list_units_by_patterns(
+    &self,
+    states: Vec<String>,
+    patterns: Vec<String>,
+  ) -> zbus::Result<
+    Vec<(
+      String,
+      String,
+      String,
+      String,
+      String,
+      String,
+      zvariant::OwnedObjectPath,
+      u32,
+      String,
+      zvariant::OwnedObjectPath,
+    )>,
+  >;
+
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#ListUnitsByNames()) Call interface method `ListUnitsByNames`.
+  #[zbus(name = "ListUnitsByNames")]
+  fn list_units_by_names(&self, names: Vec<String>) -> zbus::Result<Vec<RawUnit>>;
+
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#ListJobs()) Call interface method `ListJobs`.
+  #[zbus(name = "ListJobs")]
+  fn list_jobs(&self) -> zbus::Result<Vec<RawJob>>;
+
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#CancelJob()) Call interface method `CancelJob`.
+  #[zbus(name = "CancelJob")]
+  fn cancel_job(&self, id: u32) -> zbus::Result<()>;
+
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#Reload()) Call interface method `Reload`.
+  #[zbus(name = "Reload")]
+  fn reload(&self) -> zbus::Result<()>;
+
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#Subscribe()) Call interface method `Subscribe`.
+  /// The manager only sends signals once someone has subscribed
+  #[zbus(name = "Subscribe")]
+  fn subscribe(&self) -> zbus::Result<()>;
+}
+
+/// Proxy object for `org.freedesktop.systemd1.Job`.
+#[proxy(
+  interface = "org.freedesktop.systemd1.Job",
+  default_service = "org.freedesktop.systemd1",
+  assume_defaults = false,
+  gen_blocking = false
+)]
+pub trait Job {
+  /// [📖](https://www.freedesktop.org/software/systemd/man/systemd.directives.html#GetAfter()) Call interface method `GetAfter`.
+  /// The jobs this one is waiting for
+  #[zbus(name = "GetAfter")]
+  fn get_after(&self) -> zbus::Result<Vec<RawJob>>;
+}
+
+/// Proxy object for `org.freedesktop.systemd1.Unit`.
+/// Taken from https://github.com/lucab/zbus_systemd/blob/main/src/systemd1/generated.rs
+#[proxy(
+  interface = "org.freedesktop.systemd1.Unit",
+  default_service = "org.freedesktop.systemd1",
+  assume_defaults = false,
+  gen_blocking = false
+)]
+pub trait Unit {
+  /// Get property `ActiveState`.
+  #[zbus(property)]
+  fn active_state(&self) -> zbus::Result<String>;
+
+  /// Get property `LoadState`.
+  #[zbus(property)]
+  fn load_state(&self) -> zbus::Result<String>;
+
+  /// Get property `UnitFileState`.
+  #[zbus(property)]
+  fn unit_file_state(&self) -> zbus::Result<String>;
+}
+
+/// Proxy object for `org.freedesktop.systemd1.Service`.
+/// Taken from https://github.com/lucab/zbus_systemd/blob/main/src/systemd1/generated.rs
+#[proxy(
+  interface = "org.freedesktop.systemd1.Service",
+  default_service = "org.freedesktop.systemd1",
+  assume_defaults = false,
+  gen_blocking = false
+)]
+trait Service {
+  /// Get property `MainPID`.
+  #[zbus(property, name = "MainPID")]
+  fn main_pid(&self) -> zbus::Result<u32>;
+}
+
+/// Returns the load state of a systemd unit
+///
+/// Returns `invalid-unit-path` if the path is invalid
+///
+/// # Arguments
+///
+/// * `connection`: zbus connection
+/// * `full_service_name`: Full name of the service name with '.service' in the end
+///
+pub async fn get_active_state(connection: &Connection, full_service_name: &str) -> String {
+  let object_path = get_unit_path(full_service_name);
+
+  match zvariant::ObjectPath::try_from(object_path) {
+    Ok(path) => {
+      let unit_proxy = UnitProxy::new(connection, path).await.unwrap();
+      unit_proxy.active_state().await.unwrap_or("invalid-unit-path".into())
+    },
+    Err(_) => "invalid-unit-path".to_string(),
+  }
+}
+
+/// Returns the unit file state of a systemd unit. If the state is `enabled`, the unit loads on every boot
+///
+/// Returns `invalid-unit-path` if the path is invalid
+///
+/// # Arguments
+///
+/// * `connection`: zbus connection
+/// * `full_service_name`: Full name of the service name with '.service' in the end
+///
+pub async fn get_unit_file_state(connection: &Connection, full_service_name: &str) -> String {
+  let object_path = get_unit_path(full_service_name);
+
+  match zvariant::ObjectPath::try_from(object_path) {
+    Ok(path) => {
+      let unit_proxy = UnitProxy::new(connection, path).await.unwrap();
+      unit_proxy.unit_file_state().await.unwrap_or("invalid-unit-path".into())
+    },
+    Err(_) => "invalid-unit-path".to_string(),
+  }
+}
+
+/// Returns the PID of a systemd service
+///
+/// # Arguments
+///
+/// * `connection`: zbus connection
+/// * `full_service_name`: Full name of the service name with '.service' in the end
+///
+pub async fn get_main_pid(connection: &Connection, full_service_name: &str) -> Result<u32, zbus::Error> {
+  let object_path = get_unit_path(full_service_name);
+
+  let validated_object_path = zvariant::ObjectPath::try_from(object_path).unwrap();
+
+  let service_proxy = ServiceProxy::new(connection, validated_object_path).await.unwrap();
+  service_proxy.main_pid().await
+}
+
+/// Encode into a valid dbus string
+///
+/// # Arguments
+///
+/// * `input_string`
+///
+fn encode_as_dbus_object_path(input_string: &str) -> String {
+  input_string
+    .chars()
+    .map(|c| if c.is_ascii_alphanumeric() || c == '/' || c == '_' { c.to_string() } else { format!("_{:x}", c as u32) })
+    .collect()
+}
+
+/// Unit file path for a service
+///
+/// # Arguments
+///
+/// * `full_service_name`
+///
+pub fn get_unit_path(full_service_name: &str) -> String {
+  format!("/org/freedesktop/systemd1/unit/{}", encode_as_dbus_object_path(full_service_name))
+}
+
+/// The unit a D-Bus object path is for, the other way around from `get_unit_path`. `None` for anything but a unit
+pub fn unit_name_from_path(path: &str) -> Option<String> {
+  let encoded = path.strip_prefix("/org/freedesktop/systemd1/unit/")?.as_bytes();
+  let mut name = Vec::with_capacity(encoded.len());
+  let mut i = 0;
+  while i < encoded.len() {
+    let escaped = encoded.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(str::from_utf8(hex).ok()?, 16).ok());
+    match (encoded[i], escaped) {
+      (b'_', Some(byte)) => {
+        name.push(byte);
+        i += 3;
+      },
+      (byte, _) => {
+        name.push(byte);
+        i += 1;
+      },
+    }
+  }
+  String::from_utf8(name).ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_triggers() {
+    let properties = HashMap::from([
+      ("TriggeredBy".to_string(), "backup.timer backup.path".to_string()),
+      ("Triggers".into(), "".into()),
+    ]);
+    let triggers = Triggers::from_properties(UnitScope::User, &properties);
+    assert!(triggers.triggers.is_empty());
+    assert_eq!(triggers.triggered_by[1], UnitId { name: "backup.path".into(), scope: UnitScope::User });
+    assert!(Triggers::default().is_empty());
+  }
+
+  #[test]
+  fn test_signal_number() {
+    assert_eq!(signal_number("SIGTERM").unwrap(), 15);
+    assert_eq!(signal_number("hup").unwrap(), 1);
+    assert_eq!(signal_number(" 10 ").unwrap(), 10);
+    assert!(signal_number("0").is_err());
+    assert!(signal_number("SIGNOPE").is_err());
+    assert_eq!(KillWhom::parse("main").unwrap(), KillWhom::Main);
+    assert!(KillWhom::parse("everyone").is_err());
+  }
+
+  #[test]
+  fn test_get_unit_path() {
+    assert_eq!(get_unit_path("test.service"), "/org/freedesktop/systemd1/unit/test_2eservice");
+  }
+
+  #[test]
+  fn test_unit_name_from_path() {
+    assert_eq!(unit_name_from_path(&get_unit_path("getty@tty1.service")).as_deref(), Some("getty@tty1.service"));
+    assert_eq!(
+      unit_name_from_path("/org/freedesktop/systemd1/unit/systemd_2dfsck_40dev_2ddisk_5cx2dby_5cx2duuid_2eservice")
+        .as_deref(),
+      Some("systemd-fsck@dev-disk\\x2dby\\x2duuid.service")
+    );
+    assert_eq!(unit_name_from_path("/org/freedesktop/systemd1/job/42"), None);
+  }
+
+  #[test]
+  fn test_parse_delta() {
+    let output = "\
+[OVERRIDDEN] /etc/systemd/system/apt-daily.service → /usr/lib/systemd/system/apt-daily.service
+
+--- /usr/lib/systemd/system/apt-daily.service\t2023-05-25 14:11:37.000000000 +0000
++++ /etc/systemd/system/apt-daily.service\t2026-10-14 18:33:49.207082676 +0000
+@@ -1,2 +1,2 @@
+ [Unit]
+-Description=Daily apt download activities
++Description=changed
+
+[MASKED]     /etc/systemd/system/apt-daily.timer → /usr/lib/systemd/system/apt-daily.timer
+[EXTENDED]   /usr/lib/systemd/system/user@.service → /usr/lib/systemd/system/user@.service.d/10-login-barrier.conf
+
+3 overridden configuration files found.
+";
+    let entries = parse_delta(output);
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].kind, DeltaKind::Overridden);
+    assert_eq!(entries[0].path, "/etc/systemd/system/apt-daily.service");
+    assert_eq!(entries[0].diff.len(), 6);
+    assert_eq!(entries[1].kind, DeltaKind::Masked);
+    assert!(entries[1].diff.is_empty());
+    assert!(entries[2].diff.is_empty());
+
+    assert!(entries[0].is_for_unit("apt-daily.service"));
+    assert!(!entries[0].is_for_unit("apt-daily.timer"));
+    assert!(entries[2].is_for_unit("user@1000.service"));
+  }
+
+  #[test]
+  fn test_parse_exec_paths() {
+    let value = "{ path=/usr/sbin/nginx ; argv[]=/usr/sbin/nginx -g daemon on; ; ignore_errors=no ; pid=0 }";
+    assert_eq!(parse_exec_paths(value), vec!["/usr/sbin/nginx"]);
+    assert!(parse_exec_paths("").is_empty());
+  }
+
+  #[test]
+  fn test_parse_calendar_preview() {
+    let output = "  Original form: daily
+Normalized form: *-*-* 00:00:00
+    Next elapse: Thu 2026-10-15 00:00:00 UTC
+       From now: 5h 19min left
+       Iter. #2: Fri 2026-10-16 00:00:00 UTC
+       From now: 1 day 5h left
+";
+    let preview = parse_calendar_preview(output);
+    assert_eq!(preview.normalized, "*-*-* 00:00:00");
+    assert_eq!(
+      preview.elapses,
+      vec![
+        ("Thu 2026-10-15 00:00:00 UTC".to_string(), "5h 19min left".to_string()),
+        ("Fri 2026-10-16 00:00:00 UTC".to_string(), "1 day 5h left".to_string())
+      ]
+    );
+  }
+
+  #[test]
+  fn test_missing_session_variables() {
+    let manager = vec!["DISPLAY=:0".to_string(), "SSH_AUTH_SOCK=/old".to_string()];
+    let session = |name: &str| match name {
+      "DISPLAY" => Some(":0".to_string()),
+      "SSH_AUTH_SOCK" => Some("/new".to_string()),
+      "XDG_RUNTIME_DIR" => Some("/run/user/1000".to_string()),
+      _ => None,
+    };
+    assert_eq!(
+      missing_session_variables(&manager, session),
+      vec!["SSH_AUTH_SOCK=/new".to_string(), "XDG_RUNTIME_DIR=/run/user/1000".to_string()]
+    );
+  }
+
+  #[test]
+  fn test_parse_invocations() {
+    let output = "__REALTIME_TIMESTAMP=100\nINVOCATION_ID=aaa\nMESSAGE_ID=39f53479d3a045ac8e11786248231fbf\n\n\
+                  __REALTIME_TIMESTAMP=200\nINVOCATION_ID=aaa\nMESSAGE_ID=98e322203f7a4ed290d09fe03c09fe15\n\
+                  EXIT_STATUS=1\n\n\
+                  __REALTIME_TIMESTAMP=201\nINVOCATION_ID=aaa\nMESSAGE_ID=d9b373ed55a64feb8242e02dbe79a49c\n\
+                  UNIT_RESULT=exit-code\n\n\
+                  __REALTIME_TIMESTAMP=300\nINVOCATION_ID=bbb\nMESSAGE_ID=39f53479d3a045ac8e11786248231fbf\n\n\
+                  __REALTIME_TIMESTAMP=350\nINVOCATION_ID=bbb\nMESSAGE_ID=7b05ebc668384222baa8881179cfda54\n\n\
+                  __REALTIME_TIMESTAMP=400\nMESSAGE_ID=7ad2d189f7e94e70a38c781354912448\n";
+    let invocations = parse_invocations(&parse_property_blocks(output), "INVOCATION_ID");
+    assert_eq!(invocations.len(), 2);
+    assert_eq!(invocations[0].started, 100);
+    assert_eq!(invocations[0].ended, Some(201));
+    assert_eq!(invocations[0].result.as_deref(), Some("exit-code"));
+    assert_eq!(invocations[0].exit_status.as_deref(), Some("1"));
+    assert_eq!(
+      invocations[1],
+      Invocation { id: "bbb".into(), started: 300, ended: None, result: None, exit_status: None, reloads: vec![350] }
+    );
+  }
+
+  #[test]
+  fn test_accounting_from_properties() {
+    let properties = parse_properties(
+      "IOAccounting=yes\nIPAccounting=no\nIOReadBytes=4096\nIOWriteBytes=18446744073709551615\n\
+       IPIngressBytes=[no data]\nIPEgressBytes=[no data]\nCPUUsageNSec=1500000000\nActiveState=inactive\n\
+       ActiveEnterTimestampMonotonic=12345\n",
+    );
+    let accounting = Accounting::from_properties(&properties);
+    assert!(accounting.io_accounting);
+    assert!(!accounting.ip_accounting);
+    assert_eq!(accounting.io_read_bytes, Some(4096));
+    assert_eq!(accounting.io_write_bytes, None);
+    assert_eq!(accounting.ip_ingress_bytes, None);
+    assert_eq!(accounting.cpu_usage_nsec, Some(1_500_000_000));
+    assert_eq!(accounting.active_since_usec, None);
+    assert_eq!(accounting.memory_current, None);
+    assert_eq!(accounting.restarts, None);
+
+    let properties =
+      parse_properties("NRestarts=47\nRestart=on-failure\nWatchdogUSec=0\nResult=oom-kill\nActiveState=activating\n");
+    let accounting = Accounting::from_properties(&properties);
+    assert_eq!(accounting.restarts, Some(47));
+    assert_eq!(accounting.restart_policy.as_deref(), Some("on-failure"));
+    assert_eq!(accounting.watchdog_usec, None);
+    assert_eq!(accounting.result.as_deref(), Some("oom-kill"));
+    assert!(accounting.crash_looping("auto-restart"));
+    // not up, so not up briefly either
+    assert!(!accounting.crash_looping("start"));
+    let steady = Accounting { restarts: Some(47), active_since_usec: Some(1), ..Default::default() };
+    assert!(!steady.crash_looping("running"));
+
+    let earlier = Accounting { cpu_usage_nsec: Some(1_000_000_000), sampled_usec: 1_000_000, ..Default::default() };
+    let later = Accounting { cpu_usage_nsec: Some(1_500_000_000), sampled_usec: 3_000_000, ..Default::default() };
+    assert_eq!(later.cpu_percent(&earlier), Some(25.0));
+    // restarted in between
+    assert_eq!(earlier.cpu_percent(&later), None);
+
+    let earlier = Accounting { io_read_bytes: Some(1 << 20), io_write_bytes: None, ..earlier };
+    let later = Accounting { io_read_bytes: Some(5 << 20), io_write_bytes: Some(4096), ..later };
+    let rates = later.rates(&earlier).unwrap();
+    assert_eq!((rates.cpu_percent, rates.io_read, rates.io_write), (Some(25.0), Some(2097152.0), None));
+    assert_eq!((rates.io(), rates.network()), (Some(2097152.0), None));
+    assert_eq!(later.rates(&Accounting { sampled_usec: 2_500_000, ..earlier }), None);
+  }
+
+  #[test]
+  fn test_template_name() {
+    assert_eq!(template_name("user@1000.service"), Some("user@.service".into()));
+    assert_eq!(template_name("nginx.service"), None);
+  }
+
+  #[test]
+  fn test_encode_as_dbus_object_path() {
+    assert_eq!(encode_as_dbus_object_path("test.service"), "test_2eservice");
+    assert_eq!(encode_as_dbus_object_path("test-with-hyphen.service"), "test_2dwith_2dhyphen_2eservice");
+  }
+
+  #[test]
+  fn test_job_describe() {
+    let mut job = Job {
+      id: 42,
+      unit: UnitId { name: "nginx.service".into(), scope: UnitScope::Global },
+      job_type: "start".into(),
+      state: "running".into(),
+      waiting_for: vec![],
+    };
+    assert_eq!(job.describe(), "start job 42 running");
+    job.state = "waiting".into();
+    job.waiting_for = vec!["network-online.target".into(), "postgresql.service".into()];
+    assert_eq!(job.describe(), "start job 42 waiting for network-online.target, postgresql.service");
+  }
+
+  #[test]
+  fn test_boot_targets() {
+    let block = |id: &str, isolate: &str, state: &str| -> HashMap<String, String> {
+      [("Id", id), ("Description", "A target"), ("AllowIsolate", isolate), ("ActiveState", state)]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+    };
+    let targets = boot_targets(vec![
+      block("multi-user.target", "yes", "active"),
+      block("network.target", "no", "active"),
+      block("reboot.target", "yes", "inactive"),
+      block("graphical.target", "yes", "inactive"),
+    ]);
+    let names = targets.iter().map(|t| t.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["graphical.target", "multi-user.target"]);
+    assert!(targets[1].active);
+  }
+
+  #[test]
+  fn test_parse_verify_output() {
+    let output = "/etc/systemd/system/web.service:7: Unknown key name 'ExecStat' in section 'Service', ignoring.\n\
+                  web.service: Service has no ExecStart=, ExecStop=, or SuccessAction=. Refusing.\n\n";
+    let diagnostics = parse_verify_output(output);
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].path.as_deref(), Some("/etc/systemd/system/web.service"));
+    assert_eq!(diagnostics[0].line, Some(7));
+    assert_eq!(diagnostics[0].message, "Unknown key name 'ExecStat' in section 'Service', ignoring.");
+    // about the unit as a whole, not a line of it
+    assert_eq!(diagnostics[1].line, None);
+    assert_eq!(
+      diagnostics[1].to_string(),
+      "web.service: Service has no ExecStart=, ExecStop=, or SuccessAction=. Refusing."
+    );
+  }
+
+  #[test]
+  fn test_transient() {
+    let mut transient = Transient {
+      unit: UnitId { name: "run-backup.service".into(), scope: UnitScope::Global },
+      command: ["rsync", "-a", "/srv", "/backup"].map(String::from).to_vec(),
+      description: "rsync -a /srv /backup".into(),
+      user: Some("backup".into()),
+      working_directory: None,
+      memory_max: Some(512 << 20),
+    };
+    assert!(transient.validate().is_ok());
+    let properties = transient.properties("/usr/bin/rsync".into(), vec![]);
+    let names = properties.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["Description", "ExecStart", "User", "MemoryMax"]);
+
+    // a scope's process is started here, so it can't be someone else's
+    transient.unit.name = "run-backup.scope".into();
+    assert!(transient.validate().is_err());
+    transient.user = None;
+    let properties = transient.properties("/usr/bin/rsync".into(), vec![812]);
+    let names = properties.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["Description", "PIDs", "MemoryMax"]);
+    transient.unit.name = "run-backup.timer".into();
+    assert!(transient.validate().is_err());
+  }
+}
@@ -0,0 +1,70 @@
+// Timer units: when they last fired, when they fire next, and what their schedule expands to.
+
+use std::collections::HashMap;
+
+/// Read with `--timestamp=unix`, so they come back as `@<seconds>`
+pub const TIMER_PROPERTIES: &[&str] = &["NextElapseUSecRealtime", "LastTriggerUSec"];
+
+/// Seconds since the epoch, `None` when the timer has never fired or has nothing scheduled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimerTimes {
+  pub next_elapse: Option<i64>,
+  pub last_trigger: Option<i64>,
+}
+
+impl TimerTimes {
+  pub fn from_properties(properties: &HashMap<String, String>) -> Self {
+    let timestamp = |name: &str| properties.get(name).and_then(|v| parse_unix_timestamp(v));
+    Self { next_elapse: timestamp("NextElapseUSecRealtime"), last_trigger: timestamp("LastTriggerUSec") }
+  }
+}
+
+/// `@1760486400` -> 1760486400. Unset timestamps show up as `n/a`, empty, or `@0`
+pub fn parse_unix_timestamp(value: &str) -> Option<i64> {
+  value.strip_prefix('@')?.parse().ok().filter(|t| *t > 0)
+}
+
+/// A timer's triggers and where its calendar ones go next
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Schedule {
+  /// e.g. `OnCalendar=*-*-* 00:00:00` or `OnUnitActiveSec=1h`
+  pub triggers: Vec<String>,
+  /// The next few elapses of the first calendar trigger, as `systemd-analyze calendar` prints them
+  pub next_runs: Vec<String>,
+}
+
+/// The trigger out of a `TimersCalendar=`/`TimersMonotonic=` value, e.g.
+/// `{ OnCalendar=*-*-* 00:00:00 ; next_elapse=Thu 2026-10-16 00:00:00 UTC }` -> `OnCalendar=*-*-* 00:00:00`
+pub fn parse_trigger(value: &str) -> Option<String> {
+  let inner = value.trim().strip_prefix('{')?.strip_suffix('}')?;
+  let trigger = inner.split(" ; ").next()?.trim();
+  (!trigger.is_empty()).then(|| trigger.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_timer_times() {
+    let properties = HashMap::from([
+      ("NextElapseUSecRealtime".to_string(), "@1760486400".to_string()),
+      ("LastTriggerUSec".to_string(), "n/a".to_string()),
+    ]);
+    assert_eq!(
+      TimerTimes::from_properties(&properties),
+      TimerTimes { next_elapse: Some(1760486400), last_trigger: None }
+    );
+    assert_eq!(parse_unix_timestamp("@0"), None);
+  }
+
+  #[test]
+  fn test_parse_trigger() {
+    assert_eq!(
+      parse_trigger("{ OnCalendar=*-*-* 00:00:00 ; next_elapse=Thu 2026-10-16 00:00:00 UTC }").as_deref(),
+      Some("OnCalendar=*-*-* 00:00:00")
+    );
+    assert_eq!(parse_trigger("{ OnUnitActiveSec=1d ; next_elapse=2d 3h }").as_deref(), Some("OnUnitActiveSec=1d"));
+    assert_eq!(parse_trigger(""), None);
+  }
+}
@@ -0,0 +1,157 @@
+// Other users' `systemd --user` managers, for root to debug user services without logging in as the user. User units
+// then come from that user's manager instead of our own: systemctl and journalctl run as the user through runuser,
+// with the user's runtime directory so they find its manager and bus, and the manager's D-Bus API is reached by
+// running systemd-stdio-bridge the same way.
+
+use std::{ffi::OsString, process::Command, sync::RwLock};
+
+use anyhow::{bail, Context, Result};
+use zbus::address::{transport::Transport, transport::Unixexec, Address};
+
+use crate::systemd::UnitScope;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct User {
+  pub name: String,
+  pub uid: u32,
+}
+
+static USER: RwLock<Option<User>> = RwLock::new(None);
+
+/// Whose manager user units come from, `None` for our own
+pub fn user() -> Option<User> {
+  USER.read().unwrap().clone()
+}
+
+pub fn set_user(user: Option<User>) {
+  *USER.write().unwrap() = user;
+}
+
+pub fn ensure_root() -> Result<()> {
+  // SAFETY: geteuid can't fail
+  if unsafe { libc::geteuid() } != 0 {
+    bail!("Only root can look into other users' service managers");
+  }
+  Ok(())
+}
+
+/// Fail for things that only work on our own user units, like editing unit files in ~/.config
+pub fn ensure_own(what: &str, scope: UnitScope) -> Result<()> {
+  match user() {
+    Some(user) if scope == UnitScope::User => bail!("{what} isn't possible for {}'s user units", user.name),
+    _ => Ok(()),
+  }
+}
+
+/// Users whose manager is running, i.e. who are logged in or have lingering enabled
+pub fn list() -> Result<Vec<User>> {
+  let output = Command::new("loginctl")
+    .args(["list-users", "--no-legend", "--no-pager"])
+    .output()
+    .context("Failed to run loginctl")?;
+  if !output.status.success() {
+    bail!("Failed to list users: {}", String::from_utf8_lossy(&output.stderr).trim());
+  }
+  Ok(parse_users(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// `loginctl list-users --no-legend` lines: the uid and name first, then columns that vary by systemd version
+pub fn parse_users(output: &str) -> Vec<User> {
+  output
+    .lines()
+    .filter_map(|line| {
+      let mut columns = line.split_whitespace();
+      let uid = columns.next()?.parse().ok()?;
+      Some(User { name: columns.next()?.to_string(), uid })
+    })
+    .collect()
+}
+
+/// A user as given on the command line, by name or uid
+pub fn find(user: &str) -> Result<User> {
+  ensure_root()?;
+  list()?.into_iter().find(|u| u.name == user || u.uid.to_string() == user).with_context(|| {
+    format!("{user} has no service manager running. It needs to be logged in, or `loginctl enable-linger {user}`")
+  })
+}
+
+impl User {
+  fn runtime_directory(&self) -> String {
+    format!("/run/user/{}", self.uid)
+  }
+
+  /// `program` run as the user, pointed at its manager and bus
+  pub fn argv(&self, program: &str) -> Vec<String> {
+    let directory = self.runtime_directory();
+    vec![
+      "runuser".into(),
+      "-u".into(),
+      self.name.clone(),
+      "--".into(),
+      "env".into(),
+      format!("XDG_RUNTIME_DIR={directory}"),
+      format!("DBUS_SESSION_BUS_ADDRESS=unix:path={directory}/bus"),
+      program.into(),
+    ]
+  }
+
+  pub fn command(&self, program: &str) -> Command {
+    let argv = self.argv(program);
+    let mut command = Command::new(&argv[0]);
+    command.args(&argv[1..]);
+    command
+  }
+
+  /// Where to reach the user's bus
+  pub fn bus_address(&self) -> Address {
+    let argv = self.argv("systemd-stdio-bridge");
+    let mut args = argv[1..].iter().map(OsString::from).collect::<Vec<_>>();
+    args.push("--user".into());
+    Address::from(Transport::Unixexec(Unixexec::new(argv[0].clone().into(), None, args)))
+  }
+
+  /// Run `command` as the user instead, keeping its arguments and environment
+  pub fn wrap(&self, command: &mut Command) {
+    let mut wrapped = self.command(&command.get_program().to_string_lossy());
+    wrapped.args(command.get_args());
+    for (key, value) in command.get_envs() {
+      match value {
+        Some(value) => wrapped.env(key, value),
+        None => wrapped.env_remove(key),
+      };
+    }
+    *command = wrapped;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_users() {
+    let users = parse_users("   0 root      no  active\n1000 alice    yes lingering\n\n");
+    assert_eq!(users, [User { name: "root".into(), uid: 0 }, User { name: "alice".into(), uid: 1000 }]);
+
+    let mut command = Command::new("systemctl");
+    command.args(["--user", "restart", "app.service"]);
+    users[1].wrap(&mut command);
+    assert_eq!(command.get_program(), "runuser");
+    let args = command.get_args().map(|a| a.to_string_lossy().into_owned()).collect::<Vec<_>>();
+    assert_eq!(
+      args,
+      [
+        "-u",
+        "alice",
+        "--",
+        "env",
+        "XDG_RUNTIME_DIR=/run/user/1000",
+        "DBUS_SESSION_BUS_ADDRESS=unix:path=/run/user/1000/bus",
+        "systemctl",
+        "--user",
+        "restart",
+        "app.service"
+      ]
+    );
+  }
+}
@@ -1,6 +1,27 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use ratatui::text::Line;
+
 use crate::{
-  components::home::Mode,
-  systemd::{UnitId, UnitWithStatus},
+  boot_history::Boot,
+  boot_wait, command_bar,
+  components::home::{BootFilter, Mode},
+  crash, health,
+  journal::Entry,
+  journal_usage::Usage,
+  log_filter::Filter,
+  mounts,
+  power::PowerAction,
+  pressure::Pressure,
+  process_tree,
+  running_set::{Plan, RunningSet},
+  search::SearchResult,
+  systemd::{
+    Accounting, BatchVerb, BootTarget, EnablementVerb, Invocation, Job, KillWhom, Transient, Triggers, UnitChanges,
+    UnitId, UnitScope, UnitWithStatus,
+  },
+  timers::{Schedule, TimerTimes},
+  user_manager::User,
 };
 
 #[derive(Debug, Clone)]
@@ -14,26 +35,502 @@ pub enum Action {
   Resize(u16, u16),
   ToggleShowLogger,
   RefreshServices,
+  /// The periodic refresh; the unit list itself is only re-read while the manager's signals aren't being watched
+  RefreshTick,
   SetServices(Vec<UnitWithStatus>),
+  /// A unit fetch that didn't work out, e.g. because the remote host went away
+  RefreshFailed(String),
+  /// A search of the unit list run in the background
+  SetSearchResults(SearchResult),
+  /// Units the manager signalled a change for, with their new state, or that it unloaded
+  UnitsUpdated(UnitChanges),
+  /// Whether the manager's signals are being watched for a scope
+  SetLiveUpdates {
+    scope: UnitScope,
+    live: bool,
+  },
   EnterMode(Mode),
   EnterError(String),
   CancelTask,
   ToggleHelp,
-  SetUnitFilePath { unit: UnitId, path: Result<String, String> },
+  SetUnitFilePath {
+    unit: UnitId,
+    path: Result<String, String>,
+  },
   CopyUnitFilePath,
-  SetLogs { unit: UnitId, logs: Vec<String> },
-  AppendLogLine { unit: UnitId, line: String },
+  /// Put plain text on the clipboard; `what` is for the log message
+  CopyText {
+    what: String,
+    text: String,
+  },
+  SetLogs {
+    unit: UnitId,
+    logs: Vec<Entry>,
+  },
+  AppendLogLines {
+    unit: UnitId,
+    lines: Vec<Entry>,
+  },
+  /// A page of lines logged before the oldest one in the log pane, oldest first; none once the start is reached
+  PrependLogLines {
+    unit: UnitId,
+    lines: Vec<Entry>,
+  },
+  /// Ask for an instance of a template, e.g. `getty@.service`, to start or enable. Any template can be picked if it's
+  /// `None`
+  OpenInstantiate(Option<UnitId>),
+  /// The template unit files, to complete the instantiate form's template from
+  SetTemplates(Vec<String>),
+  /// The manager refused an action on `unit`; offer to run `command`, its systemctl equivalent, with more privileges
+  OfferEscalation {
+    unit: UnitId,
+    error: String,
+    command: Vec<String>,
+  },
+  /// Run a command in the terminal, with the TUI out of the way so it can ask for a password
+  RunEscalated {
+    unit: UnitId,
+    command: Vec<String>,
+  },
+  EscalationDone {
+    unit: UnitId,
+    command: Vec<String>,
+    error: Option<String>,
+  },
+  /// Stream the logs of these units together
+  FollowMergedLogs(Vec<UnitId>),
+  /// Lines for the merged logs of `units`, each with the index in `units` of the unit it's from
+  AppendMergedLogs {
+    units: Vec<UnitId>,
+    lines: Vec<(usize, Entry)>,
+  },
+  /// A unit's log stream ended on its own, e.g. journalctl was killed while the terminal was detached
+  FollowStopped(UnitId),
+  /// A start, stop, restart or the like of a unit finished, whether or not it worked
+  ServiceActionDone(UnitId),
   StartService(UnitId),
   StopService(UnitId),
   RestartService(UnitId),
   ReloadService(UnitId),
+  /// Ask whether to start it as well, then enable it
   EnableService(UnitId),
+  /// Ask whether to stop it as well, then disable it
   DisableService(UnitId),
-  KillService(UnitId, String),
+  /// Preview putting its symlinks back the way [Install] says, then do it
+  ReenableService(UnitId),
+  /// Preview what the preset files do with it, then apply them
+  PresetService(UnitId),
+  KillService {
+    unit: UnitId,
+    signal: String,
+    whom: KillWhom,
+  },
+  /// Pick any signal, by name or number, and which of the unit's processes get it
+  OpenKill(UnitId),
+  /// Clear a unit's failed state and restart counter
+  ResetFailed(UnitId),
+  /// Pause a unit's processes with the cgroup freezer, or resume them
+  FreezeUnit {
+    unit: UnitId,
+    thaw: bool,
+  },
+  /// Ask which of a unit's cache, state, logs, runtime and configuration directories to remove
+  OpenClean(UnitId),
+  /// What `OpenClean` can remove: each kind of directory the unit has, with the directories
+  SetCleanTargets {
+    unit: UnitId,
+    targets: Result<Vec<(&'static str, String)>, String>,
+  },
+  /// Ask before clearing every failed unit
+  ConfirmResetAllFailed,
+  ResetAllFailed,
+  /// Star a unit, or unstar it
+  ToggleFavorite(UnitId),
+  /// List only failed units, or everything again
+  ToggleFailedView,
+  /// `systemctl` with these arguments, from the command bar or for verbs without an action of their own. The unit is
+  /// the first one they name, if any
+  RunSystemctl(UnitId, Vec<String>),
   ScrollUp(u16),
   ScrollDown(u16),
   ScrollToTop,
   ScrollToBottom,
-  EditUnitFile { unit: UnitId, path: String },
+  /// Open a drop-in override of the unit in the editor, creating it if needed, like `systemctl edit`
+  EditDropIn {
+    unit: UnitId,
+    /// The unit file, shown commented out in a new override for reference
+    fragment_path: Option<String>,
+  },
+  /// A unit file or drop-in was saved with changes: offer to reload the manager and restart the unit
+  PromptReload {
+    unit: UnitId,
+    path: String,
+  },
+  /// The saved file's been checked with `systemd-analyze verify`: offer the reload, or to fix it if it didn't pass
+  OfferReload {
+    unit: UnitId,
+    path: String,
+    passed: bool,
+    lines: Vec<Line<'static>>,
+  },
+  /// Check a unit file and its drop-ins with `systemd-analyze verify`
+  VerifyUnitFile {
+    unit: UnitId,
+    path: String,
+  },
+  ReloadAndRestart(UnitId),
+  /// What a saved unit file or drop-in changes compared to what's in effect, shown in the reload prompt
+  SetPromptLines(Vec<Line<'static>>),
+  OpenSetProperties(UnitId),
+  /// `systemctl set-property`, e.g. `CPUQuota=50%`; until the next reboot if `runtime`
+  SetProperties {
+    unit: UnitId,
+    assignments: Vec<String>,
+    runtime: bool,
+  },
+  EditUnitFile {
+    unit: UnitId,
+    path: String,
+  },
+  ShowViewer {
+    title: String,
+    lines: Vec<Line<'static>>,
+  },
+  ShowUnitDelta(UnitId),
+  /// The environment a service's processes get, and where each variable comes from
+  ShowEnvironment(UnitId),
+  OpenSetEnvironment(UnitId),
+  /// Change a service's environment overrides drop-in, then reload (and restart it if `restart`)
+  SetEnvironment {
+    unit: UnitId,
+    changes: Vec<String>,
+    restart: bool,
+  },
+  /// Restart a service whenever files it runs from change
+  OpenWatchFiles(UnitId),
+  UnwatchFiles(UnitId),
+  /// A watched unit was restarted for `path` changing, and why it failed if it did
+  WatchRestarted {
+    unit: UnitId,
+    path: PathBuf,
+    error: Option<String>,
+  },
+  ShowDeltaAudit,
+  ShowPresets,
+  ShowOrderingCycles,
+  ShowBootPlan,
+  ShowBootFailures,
+  ShowSliceTree,
+  ShowSessionStats,
+  /// Units whose state or enablement changed between refreshes lately, newest first
+  ShowChanges,
+  /// The actions taken through the TUI, on one unit or all of them
+  ShowActionLog(Option<UnitId>),
+  /// What works on this machine; `first_run` when shown on launch, so it isn't again
+  ShowSelfCheck {
+    first_run: bool,
+  },
+  ShowSandboxing(UnitId),
+  /// The processes in a unit's cgroup, kept up to date while shown
+  ShowProcesses(UnitId),
+  /// The unit file and its drop-ins, like `systemctl cat`
+  ShowUnitFiles(UnitId),
+  /// The managers' job queues
+  ShowJobs,
+  SetJobs(Result<Vec<Job>, String>),
+  /// Cancel a queued job, which is for `unit`
+  CancelJob {
+    unit: UnitId,
+    id: u32,
+  },
+  /// Reboot, power off or suspend the machine
+  OpenPowerMenu,
+  /// What logind would do, and the machine's name to type before it does
+  SetPowerMenu(Result<(Vec<PowerAction>, String), String>),
+  /// Ask for the hostname before `action`
+  ConfirmPower {
+    action: PowerAction,
+    hostname: String,
+  },
+  RunPowerAction(PowerAction),
+  /// List the targets the machine can boot into, to change the default or switch to one now
+  OpenBootTargets,
+  /// The targets and the current default
+  SetBootTargets(Result<(Vec<BootTarget>, String), String>),
+  /// Offer `set-default` and `isolate` for a target
+  PickBootTarget {
+    target: String,
+    is_default: bool,
+  },
+  /// Ask for the target's name before `set-default`, or `isolate` if `isolate`
+  ConfirmBootTarget {
+    target: String,
+    isolate: bool,
+  },
+  /// Every property of the unit, like `systemctl show`
+  ShowProperties(UnitId),
+  SetInspectedProperties {
+    unit: UnitId,
+    properties: Result<Vec<(String, String)>, String>,
+  },
+  SetProcesses {
+    unit: UnitId,
+    processes: Result<Vec<process_tree::Process>, String>,
+  },
+  ShowBootContext(UnitId),
+  /// Which of a unit's dependencies it waited on during this boot
+  ShowBootWait(UnitId),
+  /// Pick how to look at this boot's start times
+  OpenBootTimes,
+  /// Every unit's time to activate this boot, as a bar chart
+  ShowBootTimes(boot_wait::Order),
+  /// The chain of units the default target waited on
+  ShowCriticalChain,
+  ShowRunHistory(UnitId),
+  ShowTimeline(UnitId),
+  /// Open the collapsible dependency tree of a unit
+  ShowDependencyTree(UnitId),
+  /// The properties of every unit in the tree's scope, by name, once they've been read
+  SetDependencyUnits(HashMap<String, HashMap<String, String>>),
+  /// Select a unit from the dependency tree and open its action menu
+  OpenDependencyMenu(UnitId),
+  /// Connect to a socket unit's listen address and report whether its service came up
+  PokeSocket(UnitId),
+  /// The ports and sockets a unit listens on, from its socket units and its processes
+  ShowListening(UnitId),
+  /// Measure how much of the journal a unit takes up
+  ShowJournalUsage(UnitId),
+  SetJournalUsage {
+    unit: UnitId,
+    usage: Usage,
+    /// Size of the whole journal (or the unit's namespace)
+    journal_bytes: Option<u64>,
+  },
+  /// Delete journal entries older than the configured retention
+  VacuumJournal(UnitId),
+  ShowMountDiagnostics(UnitId),
+  /// Reload the manager so fstab/unit file fixes are picked up, then start the mount again
+  RetryMount(UnitId),
+  FollowLogsInPane(UnitId),
+  /// Select a unit in the list, clearing the search if it hides the unit
+  SelectUnit(UnitId),
+  /// Select the pinned unit in `slot` (0-based), then open its logs in the pager if `logs`
+  JumpToPinned {
+    slot: usize,
+    logs: bool,
+  },
+  SetSearch(String),
+  /// Run the plugin at this index in the config
+  RunPlugin {
+    unit: UnitId,
+    plugin: usize,
+  },
+  /// What a plugin printed for a unit, for its tab in the details pane
+  SetPluginOutput {
+    unit: UnitId,
+    plugin: usize,
+    lines: Vec<Line<'static>>,
+  },
+  OpenLogsInPager,
+  OpenPipeLogs,
+  OpenExportLogs,
+  /// Add a filter to the log pane, or take one off
+  OpenLogFilters,
+  OpenAddLogFilter,
+  AddLogFilter(Filter),
+  /// Show the fields of the journal entry at the top of the log pane
+  InspectLogEntry,
+  /// The filter at this index, or all of them
+  RemoveLogFilter(Option<usize>),
+  OpenExportGraph(UnitId),
+  OpenReplayMacro,
+  /// Preview enabling, disabling, reenabling or presetting the marked units
+  OpenBulkEnablement {
+    verb: EnablementVerb,
+  },
+  /// Preview applying the presets to every unit file, like `systemctl preset-all`
+  OpenPresetAll(UnitScope),
+  /// Ask before starting, stopping or restarting all the marked units
+  ConfirmBatch(BatchVerb),
+  RunBatch(BatchVerb),
+  /// Offer to save what's running now, or restore a saved snapshot of it
+  OpenRunningSets,
+  SaveRunningSet,
+  PreviewRestore(RunningSet),
+  RestoreRunningSet(Plan),
+  /// Press the next key of the macro being replayed
+  ReplayStep,
+  SetAccounting(Vec<(UnitId, Accounting)>),
+  /// A unit's `Documentation=` URIs
+  SetDocumentation {
+    unit: UnitId,
+    uris: Vec<String>,
+  },
+  SetTriggers {
+    unit: UnitId,
+    triggers: Triggers,
+  },
+  /// The details rows of a socket, mount or other unit that doesn't run processes, see `unit_types::fields`
+  SetTypeDetails {
+    unit: UnitId,
+    values: Vec<String>,
+  },
+  OpenLogBootPicker,
+  PickLogBoot(Vec<Boot>),
+  SetLogBoot(BootFilter),
+  /// Jump to the unit that triggers this one, or that it triggers, asking which if there are several
+  GoToTrigger(UnitId),
+  SetMountRequirements {
+    unit: UnitId,
+    requirements: Vec<mounts::Requirement>,
+  },
+  SetCrashEvidence {
+    unit: UnitId,
+    evidence: crash::Evidence,
+  },
+  /// How the health check at this index in the config went for `unit`
+  SetHealth {
+    check: usize,
+    unit: UnitId,
+    outcome: health::Outcome,
+  },
+  /// The unit's OOM kills and core dumps, with the backtrace of the latest dump
+  ShowCrash(UnitId),
+  /// Jump to a mount or device the unit needs, the missing ones first
+  GoToMount(UnitId),
+  /// Open the unit's documentation, asking which link if it has several
+  ShowDocumentation(UnitId),
+  /// Open one `Documentation=` URI: man and info pages in the terminal, anything else in the browser
+  OpenDocumentation(String),
+  SetTimers(Vec<(UnitId, TimerTimes)>),
+  SetFileStates(Vec<(UnitId, String)>),
+  SetSchedule {
+    unit: UnitId,
+    schedule: Schedule,
+  },
+  EnableAccounting(UnitId),
+  SetPressure(Vec<(UnitId, Pressure)>),
+  /// The units whose files changed on disk since the manager loaded them (`NeedDaemonReload`)
+  SetStaleUnits(Vec<UnitId>),
+  /// Start, stop or restart a unit whose file changed on disk: ask whether to reload first
+  ConfirmStale(BatchVerb, UnitId),
+  /// A stop, restart, kill or mask of a unit in `confirm-units` that's been confirmed, to run without asking again
+  Confirmed(Box<Action>),
+  /// daemon-reload, then start, stop or restart the unit
+  ReloadAndRun(BatchVerb, UnitId),
+  /// Start, stop or restart the unit with the configuration the manager has loaded, changed file or not
+  RunStale(BatchVerb, UnitId),
+  SetPropertyValues(HashMap<String, HashMap<UnitId, String>>),
+  SetRunHistory {
+    unit: UnitId,
+    runs: Vec<Invocation>,
+  },
+  ShowRunLogs {
+    unit: UnitId,
+    run: Invocation,
+  },
+  OpenNewServiceWizard,
+  /// Ask for a command to run as a transient unit, like `systemd-run`
+  OpenRunTransient,
+  RunTransient(Transient),
+  OpenNewTimerWizard,
+  OpenCrontabImport,
+  OpenManagerEnvironment,
+  /// Ask for the host to manage, or this machine
+  OpenHostSwitcher,
+  /// As root, pick whose user manager to show user units from
+  OpenUserSwitcher,
+  /// Pick a local container to manage, or this machine
+  OpenMachineSwitcher,
+  SwitchMachine(Option<String>),
+  /// Show system and user units, only system units or only user units, in turn
+  CycleScope,
+  SwitchUser(Option<User>),
+  /// Pick one of the built-in or configured themes for the rest of the session
+  OpenThemePicker,
+  SetTheme(String),
+  OpenDuplicateUnit {
+    unit: UnitId,
+    path: String,
+  },
+  FormChanged,
+  SubmitForm,
+  /// Replace the form's preview, if the field it was computed from still has the same value
+  SetFormPreview {
+    field: String,
+    value: String,
+    lines: Vec<Line<'static>>,
+  },
   Noop,
 }
+
+impl Action {
+  /// Whether it changes units, unit files or the machine, so read-only mode refuses it. Forms and prompts that only
+  /// lead to such changes count too, so they don't open just to fail on submit
+  pub fn changes_system(&self) -> bool {
+    match self {
+      // e.g. `status` from the command bar only looks
+      Action::RunSystemctl(_, args) => !args.first().is_some_and(|verb| command_bar::is_read_only_verb(verb)),
+      Action::Confirmed(action) => action.changes_system(),
+      _ => matches!(
+        self,
+        Action::OpenInstantiate(_)
+          | Action::OfferEscalation { .. }
+          | Action::RunEscalated { .. }
+          | Action::StartService(_)
+          | Action::StopService(_)
+          | Action::RestartService(_)
+          | Action::ReloadService(_)
+          | Action::EnableService(_)
+          | Action::DisableService(_)
+          | Action::ReenableService(_)
+          | Action::PresetService(_)
+          | Action::KillService { .. }
+          | Action::OpenKill(_)
+          | Action::ResetFailed(_)
+          | Action::FreezeUnit { .. }
+          | Action::OpenClean(_)
+          | Action::ConfirmResetAllFailed
+          | Action::ResetAllFailed
+          | Action::EditDropIn { .. }
+          | Action::PromptReload { .. }
+          | Action::OfferReload { .. }
+          | Action::ReloadAndRestart(_)
+          | Action::OpenSetProperties(_)
+          | Action::SetProperties { .. }
+          | Action::EditUnitFile { .. }
+          | Action::OpenSetEnvironment(_)
+          | Action::SetEnvironment { .. }
+          | Action::OpenWatchFiles(_)
+          | Action::CancelJob { .. }
+          | Action::OpenPowerMenu
+          | Action::ConfirmPower { .. }
+          | Action::RunPowerAction(_)
+          | Action::OpenBootTargets
+          | Action::PickBootTarget { .. }
+          | Action::ConfirmBootTarget { .. }
+          | Action::PokeSocket(_)
+          | Action::VacuumJournal(_)
+          | Action::RetryMount(_)
+          | Action::OpenBulkEnablement { .. }
+          | Action::OpenPresetAll(_)
+          | Action::ConfirmBatch(_)
+          | Action::RunBatch(_)
+          | Action::RestoreRunningSet(_)
+          | Action::EnableAccounting(_)
+          | Action::ConfirmStale(..)
+          | Action::ReloadAndRun(..)
+          | Action::RunStale(..)
+          | Action::OpenNewServiceWizard
+          | Action::OpenRunTransient
+          | Action::RunTransient(_)
+          | Action::OpenNewTimerWizard
+          | Action::OpenCrontabImport
+          | Action::OpenManagerEnvironment
+          | Action::OpenDuplicateUnit { .. }
+          // a shell pipeline could do anything
+          | Action::OpenPipeLogs
+      ),
+    }
+  }
+}
@@ -0,0 +1,144 @@
+// An append-only record of every state-changing action taken through the TUI, for shared root machines and
+// post-incident review. It lives in the data directory as tab-separated lines so it can be grepped, or loaded into a
+// spreadsheet, without this tool:
+//
+//   timestamp  host  user  scope  unit  action  result
+//
+// The user is whoever ran sudo when there was one, since that's who a reviewer is looking for. Admins who each log in
+// as themselves can share one log by pointing `action-log` in config.toml at a file they can all append to.
+
+use std::{fs::OpenOptions, io::Write, os::unix::fs::OpenOptionsExt, path::PathBuf, sync::RwLock};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, Local};
+
+use crate::{
+  systemd::{UnitId, UnitScope},
+  utils::get_data_dir,
+};
+
+/// `action-log` from config.toml, when it's set
+static PATH: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+  pub timestamp: DateTime<FixedOffset>,
+  pub host: String,
+  pub user: String,
+  pub scope: String,
+  pub unit: String,
+  pub action: String,
+  /// `ok`, `cancelled` or `failed: <error>`
+  pub result: String,
+}
+
+impl Entry {
+  /// `unit` is empty for actions on the manager itself, like changing its environment
+  pub fn new(scope: UnitScope, unit: &str, action: &str, result: &str) -> Self {
+    let scope = match scope {
+      UnitScope::Global => "system",
+      UnitScope::User => "user",
+    };
+    Self {
+      timestamp: Local::now().fixed_offset(),
+      host: std::fs::read_to_string("/proc/sys/kernel/hostname").map(|h| h.trim().to_string()).unwrap_or_default(),
+      user: ["SUDO_USER", "USER"].iter().find_map(|v| std::env::var(v).ok()).unwrap_or_default(),
+      scope: scope.into(),
+      unit: unit.into(),
+      action: action.into(),
+      result: result.into(),
+    }
+  }
+
+  pub fn is_ok(&self) -> bool {
+    self.result == "ok"
+  }
+
+  pub fn is_for(&self, unit: &UnitId) -> bool {
+    let scope = match unit.scope {
+      UnitScope::Global => "system",
+      UnitScope::User => "user",
+    };
+    self.unit == unit.name && self.scope == scope
+  }
+
+  fn to_line(&self) -> String {
+    let fields = [&self.host, &self.user, &self.scope, &self.unit, &self.action, &self.result];
+    let mut line = self.timestamp.to_rfc3339();
+    for field in fields {
+      line.push('\t');
+      line.push_str(&field.replace(['\t', '\n'], " "));
+    }
+    line
+  }
+
+  fn parse(line: &str) -> Option<Self> {
+    let mut fields = line.splitn(7, '\t');
+    Some(Self {
+      timestamp: DateTime::parse_from_rfc3339(fields.next()?).ok()?,
+      host: fields.next()?.into(),
+      user: fields.next()?.into(),
+      scope: fields.next()?.into(),
+      unit: fields.next()?.into(),
+      action: fields.next()?.into(),
+      result: fields.next()?.into(),
+    })
+  }
+}
+
+/// Keep the log at `path` instead of in the data directory
+pub fn set_path(path: Option<PathBuf>) {
+  *PATH.write().unwrap() = path;
+}
+
+pub fn path() -> Result<PathBuf> {
+  if let Some(path) = PATH.read().unwrap().clone() {
+    return Ok(path);
+  }
+  Ok(get_data_dir()?.join("actions.log"))
+}
+
+pub fn record(entry: &Entry) -> Result<()> {
+  let path = path()?;
+  if let Some(dir) = path.parent() {
+    std::fs::create_dir_all(dir)?;
+  }
+  let mut file = OpenOptions::new()
+    .create(true)
+    .append(true)
+    .mode(0o600)
+    .open(&path)
+    .with_context(|| format!("Failed to open {}", path.display()))?;
+  writeln!(file, "{}", entry.to_line())?;
+  Ok(())
+}
+
+/// Every entry in the log, oldest first. Lines that don't parse are skipped
+pub fn load() -> Result<Vec<Entry>> {
+  let path = path()?;
+  if !path.exists() {
+    return Ok(vec![]);
+  }
+  let text = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+  Ok(text.lines().filter_map(Entry::parse).collect())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_entry_round_trip() {
+    let entry = Entry::new(UnitScope::Global, "nginx.service", "Restart", "failed: Access denied\tby polkit");
+    let line = entry.to_line();
+    assert_eq!(line.matches('\t').count(), 6);
+    let parsed = Entry::parse(&line).unwrap();
+    assert_eq!(parsed.result, "failed: Access denied by polkit");
+    assert_eq!((parsed.unit.as_str(), parsed.scope.as_str()), ("nginx.service", "system"));
+    assert_eq!(parsed.timestamp.timestamp(), entry.timestamp.timestamp());
+    assert!(!parsed.is_ok());
+    assert!(parsed.is_for(&UnitId { name: "nginx.service".into(), scope: UnitScope::Global }));
+    assert!(!parsed.is_for(&UnitId { name: "nginx.service".into(), scope: UnitScope::User }));
+    assert_eq!(Entry::parse("garbage"), None);
+  }
+}
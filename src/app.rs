@@ -1,19 +1,32 @@
-use std::{process::Command, sync::Arc};
+use std::{
+  io::Write,
+  path::Path,
+  process::{Command, Stdio},
+  sync::Arc,
+  time::Instant,
+};
 
 use anyhow::{Context, Result};
 use log::error;
 use tokio::sync::{mpsc, Mutex};
-use tracing::debug;
+use tracing::{debug, info_span, warn, Instrument};
 
 use crate::{
   action::Action,
+  action_log,
   components::{
-    home::{Home, Mode},
+    home::{record_action, Home, Mode},
     Component,
   },
+  config::Config,
+  control, docs, dropin, escalation,
   event::EventHandler,
-  systemd::{get_all_services, Scope},
+  metrics, remote,
+  saved_view::{self, SavedView},
+  systemd::{find_on_path, get_all_services, Scope},
   terminal::TerminalHandler,
+  user_manager,
+  utils::{log_perf_event, STARTUP_TARGET},
 };
 
 pub struct App {
@@ -25,8 +38,8 @@ pub struct App {
 }
 
 impl App {
-  pub fn new(scope: Scope, limit_units: Vec<String>) -> Result<Self> {
-    let home = Home::new(scope, &limit_units);
+  pub fn new(scope: Scope, limit_units: Vec<String>, config: Config) -> Result<Self> {
+    let home = Home::new(scope, &limit_units, config);
     let home = Arc::new(Mutex::new(home));
     Ok(Self { scope, home, limit_units, should_quit: false, should_suspend: false })
   }
@@ -60,17 +73,30 @@ impl App {
       }
     });
 
-    self.home.lock().await.init(action_tx.clone())?;
+    let metrics_address = self.home.lock().await.config.metrics_address;
+    if let Some(address) = metrics_address {
+      metrics::serve(address, self.home.clone()).await?;
+    }
+    let control_socket = self.home.lock().await.config.control_socket.clone();
+    if let Some(path) = &control_socket {
+      control::serve(path, self.home.clone(), action_tx.clone()).await?;
+    }
+
+    let mut home = self.home.lock().await;
+    info_span!(target: STARTUP_TARGET, "init").in_scope(|| home.init(action_tx.clone()))?;
+    drop(home);
 
     let units = get_all_services(self.scope, &self.limit_units)
+      .instrument(info_span!(target: STARTUP_TARGET, "first_unit_fetch"))
       .await
       .context("Unable to get services. Check that systemd is running and try running this tool with sudo.")?;
     self.home.lock().await.set_units(units);
 
-    let mut terminal = TerminalHandler::new(self.home.clone());
+    let mouse = !self.home.lock().await.config.no_mouse;
+    let mut terminal = TerminalHandler::new(self.home.clone(), mouse);
     let mut event = EventHandler::new(self.home.clone(), action_tx.clone());
 
-    terminal.render().await;
+    terminal.render().instrument(info_span!(target: STARTUP_TARGET, "first_render")).await;
 
     loop {
       if let Some(action) = action_rx.recv().await {
@@ -88,13 +114,27 @@ impl App {
           Action::Quit => self.should_quit = true,
           Action::Suspend => self.should_suspend = true,
           Action::Resume => self.should_suspend = false,
-          Action::Resize(_, _) => terminal.render().await,
+          Action::Resize(width, height) => terminal.resize(width, height).await,
+          // handled here rather than in home.rs, so read-only mode has to be checked here too
+          Action::EditUnitFile { .. } | Action::EditDropIn { .. } | Action::RunEscalated { .. }
+            if self.home.lock().await.refuses(&action) =>
+          {
+            if let Some(next) = self.home.lock().await.dispatch(action) {
+              action_tx.send(next)?
+            }
+          },
+          Action::EditUnitFile { .. } | Action::EditDropIn { .. } if remote::elsewhere().is_some() => {
+            let e = remote::ensure_local("Editing unit files").unwrap_err();
+            action_tx.send(Action::EnterError(format!("{e:#}")))?;
+          },
+          Action::EditUnitFile { unit, .. } | Action::EditDropIn { unit, .. }
+            if user_manager::ensure_own("Editing unit files", unit.scope).is_err() =>
+          {
+            let e = user_manager::ensure_own("Editing unit files", unit.scope).unwrap_err();
+            action_tx.send(Action::EnterError(format!("{e:#}")))?;
+          },
           // This would normally be in home.rs, but it needs to do some terminal and event handling stuff that's easier here
           Action::EditUnitFile { unit, path } => {
-            event.stop();
-            let mut tui = terminal.tui.lock().await;
-            tui.exit()?;
-
             let read_unit_file_contents = || match std::fs::read_to_string(&path) {
               Ok(contents) => contents,
               Err(e) => {
@@ -104,31 +144,91 @@ impl App {
             };
 
             let unit_file_contents = read_unit_file_contents();
-            let editor = std::env::var("EDITOR").unwrap_or_else(|_| "nano".to_string());
-            match Command::new(&editor).arg(&path).status() {
-              Ok(_) => {
-                tui.enter()?;
-                tui.clear()?;
-                event = EventHandler::new(self.home.clone(), action_tx.clone());
-
-                let new_unit_file_contents = read_unit_file_contents();
-                if unit_file_contents != new_unit_file_contents {
-                  action_tx.send(Action::ReloadService(unit))?;
-                }
-
-                action_tx.send(Action::EnterMode(Mode::ServiceList))?;
+            event.stop();
+            let result = terminal.run_outside(|| edit(Path::new(&path))).await?;
+            event = EventHandler::new(self.home.clone(), action_tx.clone());
+            match result {
+              Ok(()) if read_unit_file_contents() != unit_file_contents => {
+                record_action(action_log::Entry::new(unit.scope, &unit.name, "Edit unit file", "ok"));
+                action_tx.send(Action::PromptReload { unit, path })?;
               },
+              Ok(()) => action_tx.send(Action::EnterMode(Mode::ServiceList))?,
+              Err(e) => action_tx.send(Action::EnterError(format!("{e:#}")))?,
+            }
+          },
+          // Like `systemctl edit`: a drop-in that's only applied on top of the unit file
+          Action::EditDropIn { unit, fragment_path } => {
+            let prepared = dropin::override_path(&unit)
+              .and_then(|path| Ok((dropin::prepare(&path, &unit.name, fragment_path.as_deref())?, path)));
+            let (before, path) = match prepared {
+              Ok(prepared) => prepared,
               Err(e) => {
-                tui.enter()?;
-                tui.clear()?;
-                event = EventHandler::new(self.home.clone(), action_tx.clone());
-                action_tx.send(Action::EnterError(format!("Failed to open editor `{editor}`: {e}")))?;
+                let mut message = format!("{e:#}");
+                if message.contains("Permission denied") {
+                  message.push_str("\n\nTry running this tool with sudo.");
+                }
+                action_tx.send(Action::EnterError(message))?;
+                continue;
+              },
+            };
+            event.stop();
+            let result = terminal.run_outside(|| edit(&path)).await?;
+            event = EventHandler::new(self.home.clone(), action_tx.clone());
+            match result.and_then(|()| dropin::finish(&path, &before)) {
+              Ok(true) => {
+                record_action(action_log::Entry::new(unit.scope, &unit.name, "Edit drop-in", "ok"));
+                action_tx.send(Action::PromptReload { unit, path: path.display().to_string() })?
               },
+              Ok(false) => action_tx.send(Action::EnterMode(Mode::ServiceList))?,
+              Err(e) => action_tx.send(Action::EnterError(format!("{e:#}")))?,
+            }
+          },
+          // man and info need the terminal to themselves too; a browser doesn't
+          Action::OpenDocumentation(uri) => {
+            let Some(link) = docs::Link::parse(&uri) else {
+              action_tx.send(Action::EnterError(format!("Don't know how to open {uri}")))?;
+              continue;
+            };
+            let result = if link.in_terminal() {
+              event.stop();
+              let result = terminal.run_outside(|| docs::open(&link)).await?;
+              event = EventHandler::new(self.home.clone(), action_tx.clone());
+              result
+            } else {
+              docs::open(&link)
+            };
+            match result {
+              Ok(()) => action_tx.send(Action::EnterMode(Mode::ServiceList))?,
+              Err(e) => action_tx.send(Action::EnterError(format!("{e:#}")))?,
+            }
+          },
+          // sudo and polkit's agent ask for the password in the terminal
+          Action::RunEscalated { unit, command } => {
+            event.stop();
+            let result = terminal.run_outside(|| escalation::run(&command)).await?;
+            event = EventHandler::new(self.home.clone(), action_tx.clone());
+            action_tx.send(Action::EscalationDone { unit, command, error: result.err().map(|e| format!("{e:#}")) })?;
+          },
+          // Same deal as editing: the pager needs the terminal to itself
+          Action::OpenLogsInPager => {
+            let logs = self.home.lock().await.logs_text();
+            event.stop();
+            let result = terminal.run_outside(|| page(&logs)).await?;
+            event = EventHandler::new(self.home.clone(), action_tx.clone());
+            match result {
+              Ok(()) => action_tx.send(Action::Render)?,
+              Err(e) => action_tx.send(Action::EnterError(format!("{e:#}")))?,
             }
           },
           _ => {
-            if let Some(_action) = self.home.lock().await.dispatch(action) {
-              action_tx.send(_action)?
+            // frames wait for this to let go of the lock, so a slow action is a stalled frame
+            let mut home = self.home.lock().await;
+            let started = Instant::now();
+            let next = home.dispatch(action);
+            drop(home);
+            log_perf_event("dispatch", started.elapsed());
+            if let Some(next) = next {
+              action_tx.send(next)?
             };
           },
         }
@@ -138,11 +238,22 @@ impl App {
         event.stop();
         terminal.task.await?;
         event.task.await?;
-        terminal = TerminalHandler::new(self.home.clone());
+        terminal = TerminalHandler::new(self.home.clone(), mouse);
         event = EventHandler::new(self.home.clone(), action_tx.clone());
         action_tx.send(Action::Resume)?;
         action_tx.send(Action::Render)?;
       } else if self.should_quit {
+        let home = self.home.lock().await;
+        // the sort order is kept either way, for launches that don't set one
+        let view = if home.config.restore_session {
+          home.saved_view()
+        } else {
+          SavedView { sort: Some(home.sort), ..Default::default() }
+        };
+        if let Err(e) = saved_view::save(&view) {
+          warn!("Error saving the view for next time: {e:#}");
+        }
+        drop(home);
         terminal.stop()?;
         event.stop();
         terminal.task.await?;
@@ -153,3 +264,34 @@ impl App {
     Ok(())
   }
 }
+
+/// Open `path` in `$EDITOR`, or nano if that isn't set
+fn edit(path: &Path) -> Result<()> {
+  let editor = std::env::var("EDITOR").unwrap_or_else(|_| "nano".to_string());
+  Command::new(&editor).arg(path).status().with_context(|| format!("Failed to open editor `{editor}`"))?;
+  Ok(())
+}
+
+/// `$PAGER` if it's set, otherwise bat if it's installed (Debian calls it batcat), then less
+fn pager_command() -> Vec<String> {
+  if let Some(pager) = std::env::var("PAGER").ok().filter(|p| !p.trim().is_empty()) {
+    return pager.split_whitespace().map(String::from).collect();
+  }
+  match ["bat", "batcat"].into_iter().find(|b| find_on_path(b).is_some()) {
+    Some(bat) => vec![bat.into(), "--paging=always".into(), "--language=log".into()],
+    None => vec!["less".into(), "-R".into()],
+  }
+}
+
+fn page(text: &str) -> Result<()> {
+  let command = pager_command();
+  let mut child = Command::new(&command[0])
+    .args(&command[1..])
+    .stdin(Stdio::piped())
+    .spawn()
+    .with_context(|| format!("Failed to open pager `{}`", command.join(" ")))?;
+  // the pager closing early (e.g. quitting less before it's read everything) isn't an error
+  let _ = child.stdin.take().unwrap().write_all(text.as_bytes());
+  child.wait()?;
+  Ok(())
+}
@@ -0,0 +1,77 @@
+// Working out what the next boot will start: everything the default target pulls in through Wants=/Requires= and
+// friends, compared against what's running right now.
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+/// The dependency properties that pull units into a boot transaction
+pub const PULL_PROPERTIES: &[&str] = &["Wants", "Requires", "Requisite", "BindsTo", "Upholds"];
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct BootPlan {
+  /// Every unit the default target pulls in, including itself
+  pub will_start: BTreeSet<String>,
+  /// Services that will start at boot but aren't running now
+  pub not_running: Vec<String>,
+  /// Services running now that nothing at boot pulls in (started by hand, or activated by a socket, D-Bus, etc.)
+  pub not_at_boot: Vec<String>,
+}
+
+/// `units` needs `Id`, `ActiveState` and the `PULL_PROPERTIES` of every loaded unit
+pub fn plan(units: &[HashMap<String, String>], default_target: &str) -> BootPlan {
+  let by_id: HashMap<&str, &HashMap<String, String>> =
+    units.iter().filter_map(|u| u.get("Id").map(|id| (id.as_str(), u))).collect();
+
+  let mut will_start = BTreeSet::from([default_target.to_string()]);
+  let mut queue = VecDeque::from([default_target.to_string()]);
+  while let Some(unit) = queue.pop_front() {
+    let Some(properties) = by_id.get(unit.as_str()) else { continue };
+    for dependency in PULL_PROPERTIES.iter().filter_map(|p| properties.get(*p)).flat_map(|v| v.split_whitespace()) {
+      if will_start.insert(dependency.to_string()) {
+        queue.push_back(dependency.to_string());
+      }
+    }
+  }
+
+  let is_active = |id: &str| by_id.get(id).and_then(|u| u.get("ActiveState")).is_some_and(|s| s == "active");
+  let not_running =
+    will_start.iter().filter(|u| u.ends_with(".service") && !is_active(u.as_str())).cloned().collect::<Vec<_>>();
+  let mut not_at_boot = by_id
+    .keys()
+    .filter(|id| id.ends_with(".service") && is_active(id) && !will_start.contains(**id))
+    .map(|id| id.to_string())
+    .collect::<Vec<_>>();
+  not_at_boot.sort();
+
+  BootPlan { will_start, not_running, not_at_boot }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn unit(id: &str, active: &str, wants: &str) -> HashMap<String, String> {
+    HashMap::from([
+      ("Id".to_string(), id.to_string()),
+      ("ActiveState".to_string(), active.to_string()),
+      ("Wants".to_string(), wants.to_string()),
+    ])
+  }
+
+  #[test]
+  fn test_plan() {
+    let units = vec![
+      unit("graphical.target", "active", "multi-user.target gdm.service"),
+      unit("multi-user.target", "active", "nginx.service cron.service"),
+      unit("nginx.service", "active", ""),
+      unit("cron.service", "failed", ""),
+      unit("gdm.service", "active", ""),
+      unit("cups.service", "active", ""),
+      unit("unrelated.target", "active", "other.service"),
+    ];
+    let plan = plan(&units, "graphical.target");
+    assert!(plan.will_start.contains("nginx.service"));
+    assert!(!plan.will_start.contains("other.service"));
+    assert_eq!(plan.not_running, vec!["cron.service"]);
+    assert_eq!(plan.not_at_boot, vec!["cups.service"]);
+  }
+}
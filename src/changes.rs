@@ -0,0 +1,111 @@
+// What's changed between refreshes: each time a unit's state or enablement comes back different from the last
+// refresh, the change is kept, newest last, so the overlay can say e.g. "nginx.service: active → failed 40s ago". A
+// unit changing again and again within `session::FLAP_WINDOW` stands out as flapping.
+
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Local};
+
+use crate::{
+  session::{FLAP_TRANSITIONS, FLAP_WINDOW},
+  systemd::UnitId,
+};
+
+/// Older changes are dropped past this many
+const MAX_CHANGES: usize = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aspect {
+  /// `ActiveState/SubState`, e.g. `active/running`
+  State,
+  /// The unit file state, e.g. `enabled`
+  Enablement,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+  pub unit: UnitId,
+  pub aspect: Aspect,
+  pub from: String,
+  pub to: String,
+  pub at: DateTime<Local>,
+}
+
+#[derive(Debug, Default)]
+pub struct ChangeLog {
+  changes: VecDeque<Change>,
+}
+
+impl ChangeLog {
+  /// Keep the change if `from`, what the last refresh saw, differs from `to`
+  pub fn record(&mut self, unit: &UnitId, aspect: Aspect, from: &str, to: &str, at: DateTime<Local>) {
+    if from == to {
+      return;
+    }
+    let change = Change { unit: unit.clone(), aspect, from: from.into(), to: to.into(), at };
+    self.changes.push_back(change);
+    if self.changes.len() > MAX_CHANGES {
+      self.changes.pop_front();
+    }
+  }
+
+  /// Changes since `since`, newest first
+  pub fn since(&self, since: DateTime<Local>) -> Vec<&Change> {
+    self.changes.iter().rev().take_while(|c| c.at >= since).collect()
+  }
+
+  /// Whether `unit` has changed state often enough lately to be flapping
+  pub fn is_flapping(&self, unit: &UnitId, now: DateTime<Local>) -> bool {
+    let recent = self.changes.iter().rev().take_while(|c| now - c.at <= FLAP_WINDOW);
+    recent.filter(|c| &c.unit == unit && c.aspect == Aspect::State).count() >= FLAP_TRANSITIONS
+  }
+}
+
+/// `active/running`, or just `inactive` when the sub state says the same
+pub fn state(active: &str, sub: &str) -> String {
+  if sub.is_empty() || sub == active || (active == "inactive" && sub == "dead") {
+    active.to_string()
+  } else {
+    format!("{active}/{sub}")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::Duration;
+
+  use super::*;
+  use crate::systemd::UnitScope;
+
+  #[test]
+  fn test_change_log() {
+    let start = Local::now();
+    let at = |seconds| start + Duration::seconds(seconds);
+    let nginx = UnitId { name: "nginx.service".into(), scope: UnitScope::Global };
+    let cron = UnitId { name: "cron.service".into(), scope: UnitScope::Global };
+    let mut log = ChangeLog::default();
+
+    log.record(&nginx, Aspect::State, "active/running", "active/running", at(0));
+    log.record(&cron, Aspect::Enablement, "enabled", "disabled", at(1));
+    for (i, (from, to)) in
+      [("active/running", "failed"), ("failed", "activating/start"), ("activating/start", "failed")]
+        .into_iter()
+        .enumerate()
+    {
+      log.record(&nginx, Aspect::State, from, to, at(10 + i as i64));
+    }
+    let recent = log.since(at(5));
+    assert_eq!(recent.len(), 3);
+    assert_eq!(recent[0].to, "failed");
+    assert_eq!(log.since(at(0)).last().map(|c| c.aspect), Some(Aspect::Enablement));
+    assert!(!log.is_flapping(&nginx, at(20)));
+    log.record(&nginx, Aspect::State, "failed", "activating/start", at(14));
+    assert!(log.is_flapping(&nginx, at(20)));
+    // long enough after, it's settled
+    assert!(!log.is_flapping(&nginx, at(20) + FLAP_WINDOW));
+
+    assert_eq!(state("active", "running"), "active/running");
+    assert_eq!(state("inactive", "dead"), "inactive");
+    assert_eq!(state("failed", "failed"), "failed");
+  }
+}
@@ -0,0 +1,140 @@
+// Subcommands that print to stdout and exit instead of starting the TUI, for scripts and CI. They list units over
+// D-Bus and read logs with journalctl the same way the TUI does, so `--host` and `--user-manager` apply to them too.
+
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+
+use crate::{
+  remote,
+  systemd::{self, Scope, UnitId, UnitScope, UnitWithStatus},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Output {
+  /// Tab-separated columns
+  #[default]
+  Text,
+  /// One JSON array of objects for `list`, one object per line for `logs`
+  Json,
+}
+
+fn scope_name(scope: UnitScope) -> &'static str {
+  match scope {
+    UnitScope::Global => "system",
+    UnitScope::User => "user",
+  }
+}
+
+/// Whether a unit is in `state`, which like `systemctl --state` can be a load, active or sub state
+pub fn in_state(unit: &UnitWithStatus, state: &str) -> bool {
+  [&unit.load_state, &unit.activation_state, &unit.sub_state].iter().any(|s| s.as_str() == state)
+}
+
+/// The units matching `patterns`, narrowed to those in any of `states` if there are some
+pub async fn list(scope: Scope, patterns: &[String], states: &[String], output: Output) -> Result<String> {
+  let units = systemd::get_all_services(scope, patterns).await?;
+  let units = units.iter().filter(|u| states.is_empty() || states.iter().any(|s| in_state(u, s))).collect::<Vec<_>>();
+  Ok(format_list(&units, output))
+}
+
+fn format_list(units: &[&UnitWithStatus], output: Output) -> String {
+  match output {
+    Output::Text => units
+      .iter()
+      .map(|u| {
+        format!(
+          "{}\t{}\t{}\t{}\t{}\t{}\n",
+          u.name,
+          scope_name(u.scope),
+          u.load_state,
+          u.activation_state,
+          u.sub_state,
+          u.description
+        )
+      })
+      .collect(),
+    Output::Json => {
+      let objects = units
+        .iter()
+        .map(|u| {
+          format!(
+            r#"{{"name":{},"scope":"{}","load_state":{},"active_state":{},"sub_state":{},"description":{}}}"#,
+            json_string(&u.name),
+            scope_name(u.scope),
+            json_string(&u.load_state),
+            json_string(&u.activation_state),
+            json_string(&u.sub_state),
+            json_string(&u.description)
+          )
+        })
+        .collect::<Vec<_>>();
+      format!("[{}]\n", objects.join(","))
+    },
+  }
+}
+
+/// `value` as a JSON string literal
+fn json_string(value: &str) -> String {
+  let mut quoted = String::with_capacity(value.len() + 2);
+  quoted.push('"');
+  for c in value.chars() {
+    match c {
+      '"' => quoted.push_str("\\\""),
+      '\\' => quoted.push_str("\\\\"),
+      '\n' => quoted.push_str("\\n"),
+      '\r' => quoted.push_str("\\r"),
+      '\t' => quoted.push_str("\\t"),
+      c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+      c => quoted.push(c),
+    }
+  }
+  quoted.push('"');
+  quoted
+}
+
+/// The last `lines` lines of a unit's logs, oldest first
+pub fn logs(unit: &UnitId, lines: usize, output: Output) -> Result<String> {
+  let format = match output {
+    Output::Text => "--output=short-iso",
+    Output::Json => "--output=json",
+  };
+  let mut command = systemd::journalctl();
+  command.args(["--quiet", "--no-pager", format, "--lines", &lines.to_string(), "-u", &unit.name]);
+  systemd::in_scope(&mut command, unit.scope);
+  let output = remote::on_host(&mut command).output()?;
+  if !output.status.success() {
+    bail!("journalctl failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+  }
+  Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_format_list() {
+    let unit = UnitWithStatus {
+      name: "nginx.service".into(),
+      scope: UnitScope::Global,
+      description: "A \"fast\" web server".into(),
+      file_path: None,
+      load_state: "loaded".into(),
+      activation_state: "failed".into(),
+      sub_state: "failed".into(),
+      enablement_state: None,
+    };
+    assert!(in_state(&unit, "failed") && in_state(&unit, "loaded") && !in_state(&unit, "active"));
+    assert_eq!(
+      format_list(&[&unit], Output::Text),
+      "nginx.service\tsystem\tloaded\tfailed\tfailed\tA \"fast\" web server\n"
+    );
+    assert_eq!(
+      format_list(&[&unit], Output::Json),
+      r#"[{"name":"nginx.service","scope":"system","load_state":"loaded","active_state":"failed","sub_state":"failed","description":"A \"fast\" web server"}]"#
+        .to_string()
+        + "\n"
+    );
+    assert_eq!(json_string("a\\b\n\u{1}"), r#""a\\b\n\u0001""#);
+  }
+}
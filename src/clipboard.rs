@@ -0,0 +1,121 @@
+// Clipboard support that also works over SSH and inside terminal multiplexers.
+// Native backends (via clipboard-anywhere) only reach the machine the TUI runs on, so for remote sessions we fall back
+// to OSC 52: an escape sequence that asks the *local* terminal emulator to set its clipboard.
+
+use std::io::Write;
+
+use anyhow::{bail, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use tracing::{info, warn};
+
+/// Most terminals cap OSC 52 payloads somewhere around 100KB (xterm/hterm/kitty all differ a bit), stay under that
+const MAX_OSC52_PAYLOAD: usize = 74_994;
+
+/// GNU screen truncates DCS strings longer than this, so the sequence has to be sent in pieces
+const SCREEN_CHUNK_SIZE: usize = 76;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Multiplexer {
+  None,
+  Tmux,
+  Screen,
+}
+
+impl Multiplexer {
+  fn detect() -> Self {
+    if std::env::var_os("TMUX").is_some() {
+      Multiplexer::Tmux
+    } else if std::env::var("TERM").is_ok_and(|t| t.starts_with("screen")) || std::env::var_os("STY").is_some() {
+      Multiplexer::Screen
+    } else {
+      Multiplexer::None
+    }
+  }
+}
+
+fn is_remote_session() -> bool {
+  ["SSH_CLIENT", "SSH_CONNECTION", "SSH_TTY"].iter().any(|v| std::env::var_os(v).is_some())
+}
+
+/// Copy text to the clipboard, preferring OSC 52 when a native clipboard can't possibly be the right one
+pub fn set_clipboard(text: &str) -> Result<()> {
+  let force_osc52 = std::env::var_os("SYSTEMCTL_TUI_OSC52").is_some();
+  if force_osc52 || is_remote_session() || Multiplexer::detect() != Multiplexer::None {
+    return set_clipboard_osc52(text);
+  }
+
+  match clipboard_anywhere::set_clipboard(text) {
+    Ok(()) => Ok(()),
+    Err(e) => {
+      warn!("Native clipboard failed ({e}), falling back to OSC 52");
+      set_clipboard_osc52(text)
+    },
+  }
+}
+
+fn set_clipboard_osc52(text: &str) -> Result<()> {
+  let sequences = osc52_sequences(text, Multiplexer::detect())?;
+  // the TUI draws to stderr, so that's where the terminal is listening
+  let mut stderr = std::io::stderr().lock();
+  for sequence in sequences {
+    stderr.write_all(sequence.as_bytes())?;
+  }
+  stderr.flush()?;
+  info!("Copied {} bytes to the clipboard with OSC 52", text.len());
+  Ok(())
+}
+
+/// Build the escape sequence(s) needed to set the clipboard, wrapped for whatever multiplexer we're inside
+fn osc52_sequences(text: &str, multiplexer: Multiplexer) -> Result<Vec<String>> {
+  let payload = STANDARD.encode(text);
+  if payload.len() > MAX_OSC52_PAYLOAD {
+    bail!("Too much text to copy over OSC 52 ({} bytes encoded, limit is {MAX_OSC52_PAYLOAD})", payload.len());
+  }
+
+  let osc = format!("\x1b]52;c;{payload}\x07");
+  let sequences = match multiplexer {
+    Multiplexer::None => vec![osc],
+    // tmux passthrough: wrap in a DCS and double every ESC in the inner sequence
+    Multiplexer::Tmux => vec![format!("\x1bPtmux;{}\x1b\\", osc.replace('\x1b', "\x1b\x1b"))],
+    // screen: split into DCS chunks, each forwarded to the outer terminal as-is
+    Multiplexer::Screen => osc
+      .as_bytes()
+      .chunks(SCREEN_CHUNK_SIZE)
+      .map(|chunk| format!("\x1bP{}\x1b\\", String::from_utf8_lossy(chunk)))
+      .collect(),
+  };
+  Ok(sequences)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_osc52_plain() {
+    let sequences = osc52_sequences("hello", Multiplexer::None).unwrap();
+    assert_eq!(sequences, vec!["\x1b]52;c;aGVsbG8=\x07".to_string()]);
+  }
+
+  #[test]
+  fn test_osc52_tmux_passthrough() {
+    let sequences = osc52_sequences("hello", Multiplexer::Tmux).unwrap();
+    assert_eq!(sequences, vec!["\x1bPtmux;\x1b\x1b]52;c;aGVsbG8=\x07\x1b\\".to_string()]);
+  }
+
+  #[test]
+  fn test_osc52_screen_chunks() {
+    let text = "a".repeat(200);
+    let sequences = osc52_sequences(&text, Multiplexer::Screen).unwrap();
+    assert!(sequences.len() > 1);
+    assert!(sequences.iter().all(|s| s.starts_with("\x1bP") && s.ends_with("\x1b\\")));
+    let rejoined: String = sequences.iter().map(|s| &s[2..s.len() - 2]).collect();
+    assert_eq!(rejoined, format!("\x1b]52;c;{}\x07", STANDARD.encode(&text)));
+  }
+
+  #[test]
+  fn test_osc52_too_large() {
+    let text = "a".repeat(MAX_OSC52_PAYLOAD);
+    assert!(osc52_sequences(&text, Multiplexer::None).is_err());
+  }
+}
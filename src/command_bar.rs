@@ -0,0 +1,331 @@
+// The `:` command bar, for systemctl verbs typed the way they would be in a shell: `:restart nginx.service`,
+// `:mask foo.timer`, `:status --user pipewire`. Unit names and verbs complete with Tab, and earlier commands come back
+// with the arrow keys, across sessions. What runs is up to the unit list: verbs it has actions for go through them,
+// the rest run `systemctl` after asking.
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+
+use crate::{systemd::UnitScope, utils::get_data_dir};
+
+/// The verbs the bar accepts. Interactive ones like `edit` would need the terminal to themselves
+pub const VERBS: &[&str] = &[
+  "start",
+  "stop",
+  "restart",
+  "reload",
+  "try-restart",
+  "reload-or-restart",
+  "try-reload-or-restart",
+  "kill",
+  "clean",
+  "freeze",
+  "thaw",
+  "reset-failed",
+  "isolate",
+  "enable",
+  "disable",
+  "reenable",
+  "preset",
+  "preset-all",
+  "mask",
+  "unmask",
+  "revert",
+  "set-property",
+  "set-default",
+  "daemon-reload",
+  "daemon-reexec",
+  "status",
+  "show",
+  "cat",
+  "help",
+  "list-units",
+  "list-unit-files",
+  "list-dependencies",
+  "list-sockets",
+  "list-timers",
+  "list-jobs",
+  "is-active",
+  "is-enabled",
+  "is-failed",
+  "is-system-running",
+  "get-default",
+];
+
+/// Verbs that only look, so they run without asking and show what they print
+const READ_ONLY: &[&str] = &["status", "show", "cat", "help", "get-default", "is-system-running"];
+
+/// Options whose value is the next word, so it isn't taken for a unit
+const OPTIONS_WITH_VALUES: &[&str] =
+  &["-s", "--signal", "--kill-whom", "--kill-value", "-p", "--property", "--what", "-n", "--lines", "-o", "--output"];
+
+const HISTORY_LIMIT: usize = 200;
+
+/// Whether `systemctl <verb>` only looks, so it runs without asking, and in read-only sessions
+pub fn is_read_only_verb(verb: &str) -> bool {
+  READ_ONLY.contains(&verb) || verb.starts_with("list-") || verb.starts_with("is-")
+}
+
+/// A typed command, split into words
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+  pub verb: String,
+  /// `--user` or `--system` when given, otherwise `None`: the scope of the units named, or the system's
+  pub scope: Option<UnitScope>,
+  /// Everything after the verb except the scope flag
+  pub args: Vec<String>,
+}
+
+impl Invocation {
+  pub fn parse(line: &str) -> Result<Self> {
+    let mut words = split(line)?.into_iter();
+    let Some(verb) = words.next() else { bail!("Type a systemctl verb, e.g. restart nginx.service") };
+    if !VERBS.contains(&verb.as_str()) {
+      bail!("{verb} isn't a verb the command bar runs");
+    }
+    let mut scope = None;
+    let mut args = vec![];
+    for word in words {
+      match word.as_str() {
+        "--user" => scope = Some(UnitScope::User),
+        "--system" => scope = Some(UnitScope::Global),
+        _ => args.push(word),
+      }
+    }
+    Ok(Self { verb, scope, args })
+  }
+
+  /// The arguments that name units, with `.service` added where systemctl would assume it
+  pub fn units(&self) -> Vec<String> {
+    if self.verb == "set-property" {
+      return self.args.iter().find(|a| !a.starts_with('-')).map(|a| unit_name(a)).into_iter().collect();
+    }
+    let mut units = vec![];
+    let mut args = self.args.iter();
+    while let Some(arg) = args.next() {
+      if OPTIONS_WITH_VALUES.contains(&arg.as_str()) {
+        args.next();
+      } else if !arg.starts_with('-') && !arg.contains('=') {
+        units.push(unit_name(arg));
+      }
+    }
+    units
+  }
+
+  pub fn is_read_only(&self) -> bool {
+    is_read_only_verb(&self.verb)
+  }
+
+  /// The signal of a `kill`, from `-s SIGNAL` or `--signal=SIGNAL`
+  pub fn signal(&self) -> Option<&str> {
+    let mut args = self.args.iter();
+    while let Some(arg) = args.next() {
+      if arg == "-s" || arg == "--signal" {
+        return args.next().map(String::as_str);
+      }
+      if let Some(signal) = arg.strip_prefix("--signal=") {
+        return Some(signal);
+      }
+    }
+    None
+  }
+
+  /// Which processes a `kill` signals, from `--kill-whom WHOM` or `--kill-whom=WHOM`
+  pub fn kill_whom(&self) -> Option<&str> {
+    let mut args = self.args.iter();
+    while let Some(arg) = args.next() {
+      if arg == "--kill-whom" {
+        return args.next().map(String::as_str);
+      }
+      if let Some(whom) = arg.strip_prefix("--kill-whom=") {
+        return Some(whom);
+      }
+    }
+    None
+  }
+
+  /// Whether there's an option other than a signal and who gets it, which the unit list's own actions have no way
+  /// to pass on
+  pub fn has_other_options(&self) -> bool {
+    let mut args = self.args.iter();
+    while let Some(arg) = args.next() {
+      if arg == "-s" || arg == "--signal" || arg == "--kill-whom" {
+        args.next();
+      } else if arg.starts_with('-') && !arg.starts_with("--signal=") && !arg.starts_with("--kill-whom=") {
+        return true;
+      }
+    }
+    false
+  }
+
+  /// The arguments to pass to `systemctl`, scope aside
+  pub fn systemctl_args(&self) -> Vec<String> {
+    std::iter::once(self.verb.clone()).chain(self.args.iter().cloned()).collect()
+  }
+}
+
+/// `nginx` is `nginx.service`, as in systemctl
+fn unit_name(arg: &str) -> String {
+  if arg.contains('.') || arg.contains('*') {
+    arg.to_string()
+  } else {
+    format!("{arg}.service")
+  }
+}
+
+/// Words separated by spaces, where single or double quotes keep spaces in a word
+pub fn split(line: &str) -> Result<Vec<String>> {
+  let mut words = vec![];
+  let mut word: Option<String> = None;
+  let mut quote = None;
+  for c in line.chars() {
+    match (quote, c) {
+      (Some(q), c) if c == q => quote = None,
+      (Some(_), c) => word.get_or_insert_default().push(c),
+      (None, '\'' | '"') => {
+        quote = Some(c);
+        word.get_or_insert_default();
+      },
+      (None, c) if c.is_whitespace() => words.extend(word.take()),
+      (None, c) => word.get_or_insert_default().push(c),
+    }
+  }
+  if quote.is_some() {
+    bail!("A quote isn't closed");
+  }
+  words.extend(word);
+  Ok(words)
+}
+
+/// What the word being typed at the end of `line` could be: a verb first, unit names after it
+pub fn suggestions(line: &str, unit_names: &[String], limit: usize) -> Vec<String> {
+  let word = line.rsplit(' ').next().unwrap_or_default();
+  if !line.trim_start().contains(' ') {
+    if word.is_empty() || VERBS.contains(&word) {
+      return vec![];
+    }
+    return VERBS.iter().filter(|v| v.starts_with(word)).take(limit).map(|v| v.to_string()).collect();
+  }
+  if word.starts_with('-') || word.contains('=') {
+    return vec![];
+  }
+  crate::search::complete(word, unit_names, limit).into_iter().map(String::from).collect()
+}
+
+/// `line` with its last word replaced by `suggestion`, ready for the next word
+pub fn complete(line: &str, suggestion: &str) -> String {
+  match line.rsplit_once(' ') {
+    Some((before, _)) => format!("{before} {suggestion} "),
+    None => format!("{suggestion} "),
+  }
+}
+
+/// Commands run from the bar, oldest first, and where browsing them with the arrow keys has got to
+#[derive(Debug, Default)]
+pub struct History {
+  entries: Vec<String>,
+  browsing: Option<usize>,
+}
+
+impl History {
+  fn path() -> Result<PathBuf> {
+    Ok(get_data_dir()?.join("command-history"))
+  }
+
+  /// The history saved by earlier sessions, if any
+  pub fn load() -> Self {
+    let entries = Self::path()
+      .and_then(|path| Ok(std::fs::read_to_string(path)?))
+      .map(|text| text.lines().filter(|l| !l.is_empty()).map(String::from).collect())
+      .unwrap_or_default();
+    Self { entries, browsing: None }
+  }
+
+  /// Remember `line`, moving it to the end if it's there already, and save the history
+  pub fn push(&mut self, line: &str) -> Result<()> {
+    self.browsing = None;
+    self.entries.retain(|e| e != line);
+    self.entries.push(line.to_string());
+    let excess = self.entries.len().saturating_sub(HISTORY_LIMIT);
+    self.entries.drain(..excess);
+    let path = Self::path()?;
+    if let Some(directory) = path.parent() {
+      std::fs::create_dir_all(directory)?;
+    }
+    std::fs::write(path, self.entries.join("\n") + "\n")?;
+    Ok(())
+  }
+
+  /// Stop browsing, for a fresh command
+  pub fn reset(&mut self) {
+    self.browsing = None;
+  }
+
+  /// One command further back
+  pub fn older(&mut self) -> Option<&str> {
+    let index = match self.browsing {
+      Some(0) => 0,
+      Some(index) => index - 1,
+      None => self.entries.len().checked_sub(1)?,
+    };
+    self.browsing = Some(index);
+    self.entries.get(index).map(String::as_str)
+  }
+
+  /// One command forward, or `Some("")` past the newest
+  pub fn newer(&mut self) -> Option<&str> {
+    let index = self.browsing? + 1;
+    if index >= self.entries.len() {
+      self.browsing = None;
+      return Some("");
+    }
+    self.browsing = Some(index);
+    self.entries.get(index).map(String::as_str)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse() {
+    let invocation = Invocation::parse("kill --user -s HUP pipewire").unwrap();
+    assert_eq!(invocation.scope, Some(UnitScope::User));
+    assert_eq!(invocation.signal(), Some("HUP"));
+    assert_eq!(invocation.units(), ["pipewire.service"]);
+    assert_eq!(invocation.systemctl_args(), ["kill", "-s", "HUP", "pipewire"]);
+    assert!(!invocation.has_other_options());
+    let invocation = Invocation::parse("kill --kill-whom=main nginx").unwrap();
+    assert_eq!(invocation.kill_whom(), Some("main"));
+    assert!(!invocation.has_other_options());
+    assert!(Invocation::parse("kill --kill-value=3 nginx").unwrap().has_other_options());
+
+    let invocation = Invocation::parse("set-property nginx 'Description=a b' CPUQuota=50%").unwrap();
+    assert_eq!(invocation.args, ["nginx", "Description=a b", "CPUQuota=50%"]);
+    assert_eq!(invocation.units(), ["nginx.service"]);
+    assert!(!invocation.is_read_only());
+    assert!(Invocation::parse("list-timers --all").unwrap().is_read_only());
+    assert!(Invocation::parse("edit nginx").is_err());
+    assert!(Invocation::parse("  ").is_err());
+    assert!(Invocation::parse("mask 'foo").is_err());
+  }
+
+  #[test]
+  fn test_suggestions() {
+    let names = vec!["nginx.service".to_string(), "foo.timer".to_string()];
+    assert_eq!(suggestions("res", &names, 5), ["restart", "reset-failed"]);
+    assert_eq!(suggestions("mask fo", &names, 5), ["foo.timer"]);
+    assert!(suggestions("kill --sig", &names, 5).is_empty());
+    assert_eq!(complete("mask fo", "foo.timer"), "mask foo.timer ");
+
+    let mut history = History { entries: vec!["start a".into(), "stop b".into()], browsing: None };
+    assert_eq!(history.older(), Some("stop b"));
+    assert_eq!(history.older(), Some("start a"));
+    assert_eq!(history.older(), Some("start a"));
+    assert_eq!(history.newer(), Some("stop b"));
+    assert_eq!(history.newer(), Some(""));
+    assert_eq!(history.newer(), None);
+  }
+}
@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+  layout::{Margin, Rect},
+  style::{Color, Modifier, Style},
+  text::{Line, Span},
+  widgets::{Clear, List, ListItem, ListState},
+};
+
+use super::{home::Mode, pane_block, pane_title, Component, Frame};
+use crate::{
+  action::Action,
+  states::UnitState,
+  systemd::{UnitId, UnitScope},
+  theme::Theme,
+};
+
+/// The relationships shown in the tree, in the order a unit's children are listed
+pub const RELATIONSHIPS: &[&str] = &["Requires", "Wants", "After", "Before"];
+
+/// Read for every loaded unit when the tree opens, along with `RELATIONSHIPS`
+pub const STATE_PROPERTIES: &[&str] = &["LoadState", "ActiveState"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Node {
+  name: String,
+  /// How the parent relates to this unit, e.g. `Wants`; empty for the root
+  relationship: &'static str,
+  /// `None` until the node is first expanded
+  children: Option<Vec<Node>>,
+  expanded: bool,
+}
+
+impl Node {
+  fn new(name: &str, relationship: &'static str) -> Self {
+    Self { name: name.to_string(), relationship, children: None, expanded: false }
+  }
+}
+
+/// A visible row: the path of child indexes from the root, and whether the unit already appears above it in its branch
+struct Row<'a> {
+  path: Vec<usize>,
+  node: &'a Node,
+  repeated: bool,
+}
+
+/// A unit's dependencies as a collapsible tree, like `systemctl list-dependencies` but with After=/Before= too. Any
+/// unit in it can be jumped to, or started, stopped and restarted without closing the tree
+#[derive(Default)]
+pub struct DepTree {
+  pub scope: Option<UnitScope>,
+  /// The properties of every loaded unit in the scope, by name; empty while they're being read
+  pub units: HashMap<String, HashMap<String, String>>,
+  pub theme: Theme,
+  pub accessible: bool,
+  root: Option<Node>,
+  state: ListState,
+}
+
+impl DepTree {
+  pub fn new(unit: &UnitId, theme: Theme, accessible: bool) -> Self {
+    let mut state = ListState::default();
+    state.select(Some(0));
+    Self {
+      scope: Some(unit.scope),
+      theme,
+      accessible,
+      root: Some(Node::new(&unit.name, "")),
+      state,
+      ..Default::default()
+    }
+  }
+
+  pub fn root_name(&self) -> Option<&str> {
+    self.root.as_ref().map(|r| r.name.as_str())
+  }
+
+  /// Fill in the units once they've been read, and open the root's first level
+  pub fn set_units(&mut self, units: HashMap<String, HashMap<String, String>>) {
+    self.units = units;
+    if let Some(root) = self.root.as_mut() {
+      root.children = None;
+      root.expanded = false;
+    }
+    self.state.select(Some(0));
+    self.expand();
+  }
+
+  /// Keep states current after the unit list refreshes, e.g. once something started from the tree comes up
+  pub fn update_state(&mut self, name: &str, load_state: &str, active_state: &str) {
+    if let Some(properties) = self.units.get_mut(name) {
+      properties.insert("LoadState".into(), load_state.into());
+      properties.insert("ActiveState".into(), active_state.into());
+    }
+  }
+
+  /// What's selected, for screen readers
+  pub fn selected_description(&self) -> String {
+    let rows = self.rows();
+    let Some(row) = self.state.selected().and_then(|i| rows.get(i)) else { return "Nothing selected".into() };
+    let state = self.units.get(&row.node.name).and_then(|p| p.get("ActiveState")).map_or("not loaded", |s| s.as_str());
+    let relationship =
+      if row.node.relationship.is_empty() { String::new() } else { format!(" ({}=)", row.node.relationship) };
+    format!(
+      "{}{relationship}, {state}, {}level {}, {} of {}",
+      row.node.name,
+      if row.node.expanded { "expanded, " } else { "" },
+      row.path.len() + 1,
+      self.state.selected().unwrap_or(0) + 1,
+      rows.len()
+    )
+  }
+
+  fn children_of(&self, name: &str) -> Vec<Node> {
+    let Some(properties) = self.units.get(name) else { return vec![] };
+    RELATIONSHIPS
+      .iter()
+      .flat_map(|rel| {
+        properties.get(*rel).into_iter().flat_map(|v| v.split_whitespace()).map(|dep| Node::new(dep, rel))
+      })
+      .collect()
+  }
+
+  fn rows(&self) -> Vec<Row<'_>> {
+    fn walk<'a>(node: &'a Node, path: Vec<usize>, ancestors: &mut Vec<&'a str>, rows: &mut Vec<Row<'a>>) {
+      let repeated = ancestors.contains(&node.name.as_str());
+      rows.push(Row { path: path.clone(), node, repeated });
+      if repeated || !node.expanded {
+        return;
+      }
+      ancestors.push(&node.name);
+      for (i, child) in node.children.iter().flatten().enumerate() {
+        let mut child_path = path.clone();
+        child_path.push(i);
+        walk(child, child_path, ancestors, rows);
+      }
+      ancestors.pop();
+    }
+    let mut rows = vec![];
+    if let Some(root) = &self.root {
+      walk(root, vec![], &mut vec![], &mut rows);
+    }
+    rows
+  }
+
+  fn node_mut(&mut self, path: &[usize]) -> Option<&mut Node> {
+    let mut node = self.root.as_mut()?;
+    for i in path {
+      node = node.children.as_mut()?.get_mut(*i)?;
+    }
+    Some(node)
+  }
+
+  fn selected_row(&self) -> Option<(Vec<usize>, String, bool)> {
+    let rows = self.rows();
+    let row = rows.get(self.state.selected()?)?;
+    Some((row.path.clone(), row.node.name.clone(), row.repeated))
+  }
+
+  fn selected_unit(&self) -> Option<UnitId> {
+    let (_, name, _) = self.selected_row()?;
+    Some(UnitId { name, scope: self.scope? })
+  }
+
+  fn move_by(&mut self, delta: isize) {
+    let len = self.rows().len();
+    if len == 0 {
+      return;
+    }
+    let current = self.state.selected().unwrap_or(0) as isize;
+    self.state.select(Some((current + delta).clamp(0, len as isize - 1) as usize));
+  }
+
+  fn expand(&mut self) {
+    // a unit that's already further up the branch would expand forever (After=/Before= loops are common)
+    let Some((path, name, false)) = self.selected_row() else { return };
+    let children = self.children_of(&name);
+    if let Some(node) = self.node_mut(&path) {
+      node.children.get_or_insert(children);
+      node.expanded = true;
+    }
+  }
+
+  fn collapse(&mut self) {
+    let Some((path, _, _)) = self.selected_row() else { return };
+    if let Some(node) = self.node_mut(&path).filter(|n| n.expanded) {
+      node.expanded = false;
+    } else if let Some((_, parent)) = path.split_last() {
+      // already collapsed: go up to the parent instead
+      if let Some(index) = self.rows().iter().position(|r| r.path == parent) {
+        self.state.select(Some(index));
+      }
+    }
+  }
+}
+
+impl Component for DepTree {
+  fn handle_key_events(&mut self, key: KeyEvent) -> Vec<Action> {
+    match key.code {
+      KeyCode::Esc | KeyCode::Char('q') => return vec![Action::EnterMode(Mode::ServiceList)],
+      KeyCode::Down | KeyCode::Char('j') => self.move_by(1),
+      KeyCode::Up | KeyCode::Char('k') => self.move_by(-1),
+      KeyCode::PageDown => self.move_by(10),
+      KeyCode::PageUp => self.move_by(-10),
+      KeyCode::Right | KeyCode::Char('l') | KeyCode::Enter => self.expand(),
+      KeyCode::Left | KeyCode::Char('h') => self.collapse(),
+      code => {
+        let Some(unit) = self.selected_unit() else { return vec![] };
+        return match code {
+          KeyCode::Char('g') => vec![Action::SelectUnit(unit)],
+          KeyCode::Char(' ') => vec![Action::OpenDependencyMenu(unit)],
+          KeyCode::Char('s') => vec![Action::StartService(unit)],
+          KeyCode::Char('t') => vec![Action::StopService(unit)],
+          KeyCode::Char('r') => vec![Action::RestartService(unit)],
+          _ => vec![],
+        };
+      },
+    }
+    vec![Action::Render]
+  }
+
+  fn render(&mut self, f: &mut Frame<'_>, rect: Rect) {
+    let popup = rect.inner(Margin::new(rect.width / 10, rect.height / 10));
+    let dim = Style::default().add_modifier(Modifier::DIM);
+    let rows = self.rows();
+    let items = rows
+      .iter()
+      .map(|row| {
+        let node = row.node;
+        let properties = self.units.get(&node.name);
+        let marker = match &node.children {
+          _ if row.repeated => "↺",
+          Some(children) if children.is_empty() => "·",
+          None if self.children_of(&node.name).is_empty() && !self.units.is_empty() => "·",
+          _ if node.expanded => "▾",
+          _ => "▸",
+        };
+        let state = |p: &str| properties.and_then(|ps| ps.get(p)).map_or("", |s| s.as_str());
+        let active_state = properties.map_or("not loaded", |_| state("ActiveState"));
+        let color = match properties {
+          Some(_) => UnitState::of(state("LoadState"), state("ActiveState")).color(&self.theme),
+          None => Color::Reset,
+        };
+
+        let mut spans = vec![
+          Span::raw(format!("{}{marker} ", "  ".repeat(row.path.len()))),
+          Span::styled(node.name.clone(), Style::default().fg(color)),
+        ];
+        if !node.relationship.is_empty() {
+          spans.push(Span::styled(format!("  {}=", node.relationship), dim));
+        }
+        if self.accessible || properties.is_none() {
+          spans.push(Span::styled(format!(", {active_state}"), dim));
+        }
+        if row.repeated {
+          spans.push(Span::styled("  (already above)", Style::default().fg(self.theme.warning)));
+        }
+        ListItem::new(Line::from(spans))
+      })
+      .collect::<Vec<_>>();
+
+    let mut title = format!("Dependencies of {}", self.root_name().unwrap_or_default());
+    if self.units.is_empty() {
+      title.push_str(" (loading...)");
+    }
+    let block = pane_block(self.accessible).title(pane_title(&title, self.accessible)).title_bottom(
+      Line::from(" l/h expand/collapse, g go to, s/t/r start/stop/restart, space actions, Esc close ")
+        .right_aligned()
+        .style(dim),
+    );
+    let mut list = List::new(items).block(block).highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    if self.accessible {
+      list = list.highlight_symbol("> ");
+    }
+    f.render_widget(Clear, popup);
+    f.render_stateful_widget(list, popup, &mut self.state);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_tree() {
+    let unit = UnitId { name: "a.service".into(), scope: UnitScope::Global };
+    let mut tree = DepTree::new(&unit, Theme::DARK, false);
+    let properties = |deps: &[(&str, &str)]| deps.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    tree.set_units(HashMap::from([
+      ("a.service".to_string(), properties(&[("Requires", "b.service"), ("After", "c.target b.service")])),
+      ("b.service".to_string(), properties(&[("Before", "a.service")])),
+    ]));
+    let names = |tree: &DepTree| tree.rows().iter().map(|r| r.node.name.clone()).collect::<Vec<_>>();
+    assert_eq!(names(&tree), ["a.service", "b.service", "c.target", "b.service"]);
+
+    tree.move_by(1);
+    tree.expand();
+    assert_eq!(names(&tree), ["a.service", "b.service", "a.service", "c.target", "b.service"]);
+    // the unit above can't be expanded again
+    tree.move_by(1);
+    assert!(tree.rows()[2].repeated);
+    tree.expand();
+    assert_eq!(tree.rows().len(), 5);
+
+    tree.collapse();
+    assert_eq!(tree.state.selected(), Some(1));
+    tree.collapse();
+    assert_eq!(names(&tree), ["a.service", "b.service", "c.target", "b.service"]);
+    assert_eq!(tree.selected_unit(), Some(UnitId { name: "b.service".into(), scope: UnitScope::Global }));
+  }
+}
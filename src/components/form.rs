@@ -0,0 +1,279 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+  layout::{Constraint, Direction, Layout, Rect},
+  style::{Color, Modifier, Style},
+  text::{Line, Span},
+  widgets::{Clear, Paragraph},
+};
+use tui_input::{backend::crossterm::EventHandler, Input};
+
+use super::{home::Mode, pane_block, pane_title, Component, Frame};
+use crate::{
+  action::Action,
+  power::PowerAction,
+  search,
+  systemd::{EnablementVerb, UnitId, UnitScope},
+};
+
+/// What a form is for, so whoever handles `Action::SubmitForm` knows what to do with the values
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum FormPurpose {
+  #[default]
+  NewService,
+  RunTransient,
+  NewTimer,
+  ImportCrontab,
+  ManagerEnvironment,
+  DuplicateUnit {
+    source: UnitId,
+    path: String,
+  },
+  PipeLogs(UnitId),
+  ExportGraph(UnitId),
+  ExportLogs,
+  LogFilter,
+  ReplayMacro,
+  BulkEnablement {
+    units: Vec<UnitId>,
+    verb: EnablementVerb,
+  },
+  PresetAll(UnitScope),
+  SetProperties(UnitId),
+  SetEnvironment(UnitId),
+  WatchFiles(UnitId),
+  Kill(UnitId),
+  SwitchHost,
+  Instantiate,
+  /// Going ahead once the machine's name is typed
+  Power {
+    action: PowerAction,
+    hostname: String,
+  },
+  /// Going ahead once the target's name is typed
+  BootTarget {
+    target: String,
+    isolate: bool,
+  },
+}
+
+pub enum FieldKind {
+  Text(Input),
+  Choice { options: Vec<String>, selected: usize },
+}
+
+pub struct Field {
+  pub label: String,
+  pub kind: FieldKind,
+  /// Unit names to complete the last word from, for text fields that take them
+  pub completions: Vec<String>,
+}
+
+impl Field {
+  pub fn text(label: &str, initial: &str) -> Self {
+    Self { label: label.to_string(), kind: FieldKind::Text(Input::new(initial.to_string())), completions: vec![] }
+  }
+
+  /// A text field for space-separated unit names, completed from `names` with Tab
+  pub fn units(label: &str, initial: &str, names: Vec<String>) -> Self {
+    Self { completions: names, ..Self::text(label, initial) }
+  }
+
+  pub fn choice(label: &str, options: &[&str]) -> Self {
+    let options = options.iter().map(|o| o.to_string()).collect();
+    Self { label: label.to_string(), kind: FieldKind::Choice { options, selected: 0 }, completions: vec![] }
+  }
+
+  pub fn value(&self) -> &str {
+    match &self.kind {
+      FieldKind::Text(input) => input.value(),
+      FieldKind::Choice { options, selected } => &options[*selected],
+    }
+  }
+
+  /// Unit names the word being typed could be, best first
+  pub fn suggestions(&self) -> Vec<&str> {
+    if self.completions.is_empty() || self.value().ends_with(' ') {
+      return vec![];
+    }
+    let word = self.value().rsplit(' ').next().unwrap_or_default();
+    search::complete(word, &self.completions, MAX_SUGGESTIONS)
+  }
+
+  /// Replace the word being typed with the best suggestion. Returns whether there was one
+  fn complete(&mut self) -> bool {
+    let Some(suggestion) = self.suggestions().first().map(|s| s.to_string()) else { return false };
+    let FieldKind::Text(input) = &mut self.kind else { return false };
+    let value = match input.value().rsplit_once(' ') {
+      Some((before, _)) => format!("{before} {suggestion}"),
+      None => suggestion,
+    };
+    *input = Input::new(value);
+    true
+  }
+}
+
+const MAX_SUGGESTIONS: usize = 5;
+
+/// A small multi-field dialog: text inputs and choices, Tab between them, Enter to submit
+#[derive(Default)]
+pub struct Form {
+  pub title: String,
+  pub purpose: FormPurpose,
+  pub fields: Vec<Field>,
+  pub focused: usize,
+  /// Optional text shown under the fields, e.g. a preview of what submitting will do
+  pub preview: Vec<Line<'static>>,
+  pub accessible: bool,
+  pub accent: Color,
+}
+
+impl Form {
+  pub fn new(title: &str, purpose: FormPurpose, fields: Vec<Field>) -> Self {
+    Self { title: title.to_string(), purpose, fields, ..Default::default() }
+  }
+
+  /// Value of the field with the given label, or "" if there's no such field
+  pub fn value(&self, label: &str) -> &str {
+    self.fields.iter().find(|f| f.label == label).map(|f| f.value()).unwrap_or("")
+  }
+
+  pub fn focused_label(&self) -> &str {
+    self.fields.get(self.focused).map(|f| f.label.as_str()).unwrap_or("")
+  }
+
+  /// Put `field` right after the field labelled `after` if `shown`, otherwise take it out, for fields that only apply
+  /// to some of another field's choices. The focus stays on whichever field had it
+  pub fn show_field(&mut self, field: Field, after: &str, shown: bool) {
+    let index = self.fields.iter().position(|f| f.label == field.label);
+    match index {
+      Some(index) if !shown => {
+        self.fields.remove(index);
+        if self.focused > index {
+          self.focused -= 1;
+        }
+      },
+      None if shown => {
+        let index = self.fields.iter().position(|f| f.label == after).map_or(self.fields.len(), |i| i + 1);
+        self.fields.insert(index, field);
+        if self.focused >= index {
+          self.focused += 1;
+        }
+      },
+      _ => {},
+    }
+  }
+}
+
+impl Component for Form {
+  fn handle_key_events(&mut self, key: KeyEvent) -> Vec<Action> {
+    let field_count = self.fields.len();
+    match key.code {
+      KeyCode::Esc => return vec![Action::EnterMode(Mode::ServiceList)],
+      KeyCode::Enter => return vec![Action::SubmitForm],
+      KeyCode::Tab if self.fields.get_mut(self.focused).is_some_and(Field::complete) => {
+        return vec![Action::FormChanged, Action::Render];
+      },
+      KeyCode::Tab | KeyCode::Down => self.focused = (self.focused + 1) % field_count.max(1),
+      KeyCode::BackTab | KeyCode::Up => self.focused = (self.focused + field_count.max(1) - 1) % field_count.max(1),
+      _ => {
+        let Some(field) = self.fields.get_mut(self.focused) else { return vec![] };
+        match &mut field.kind {
+          FieldKind::Text(input) => {
+            input.handle_event(&crossterm::event::Event::Key(key));
+          },
+          FieldKind::Choice { options, selected } => match key.code {
+            KeyCode::Left => *selected = (*selected + options.len() - 1) % options.len(),
+            KeyCode::Right | KeyCode::Char(' ') => *selected = (*selected + 1) % options.len(),
+            _ => return vec![],
+          },
+        }
+        return vec![Action::FormChanged, Action::Render];
+      },
+    }
+    vec![Action::Render]
+  }
+
+  fn render(&mut self, f: &mut Frame<'_>, rect: Rect) {
+    let label_width = self.fields.iter().map(|f| f.label.len()).max().unwrap_or(0) as u16 + 2;
+    let suggestions = self.fields.get(self.focused).map(Field::suggestions).unwrap_or_default();
+    let height = (self.fields.len() + self.preview.len() + 4 + usize::from(!suggestions.is_empty())) as u16;
+    let width = 80.min(rect.width);
+    let popup = Rect::new(
+      rect.x + (rect.width.saturating_sub(width)) / 2,
+      rect.y + (rect.height.saturating_sub(height)) / 2,
+      width,
+      height.min(rect.height),
+    );
+
+    let block = pane_block(self.accessible)
+      .title(pane_title(&self.title, self.accessible))
+      .border_style(Style::default().fg(self.accent))
+      .title_bottom(
+        Line::from(" Tab next field, ←/→ change choice, Enter submit, Esc cancel ")
+          .right_aligned()
+          .style(Style::default().add_modifier(Modifier::DIM)),
+      );
+    let inner = block.inner(popup);
+    f.render_widget(Clear, popup);
+    f.render_widget(block, popup);
+
+    let rows = Layout::new(
+      Direction::Vertical,
+      self.fields.iter().map(|_| Constraint::Length(1)).chain([
+        Constraint::Length(u16::from(!suggestions.is_empty())),
+        Constraint::Length(1),
+        Constraint::Fill(1),
+      ]),
+    )
+    .split(inner);
+
+    for (i, field) in self.fields.iter().enumerate() {
+      let focused = i == self.focused;
+      let cols =
+        Layout::new(Direction::Horizontal, [Constraint::Length(label_width), Constraint::Fill(1)]).split(rows[i]);
+      let label_style = if focused { Style::default().add_modifier(Modifier::BOLD) } else { Style::default() };
+      let marker = if focused && self.accessible { "> " } else { "" };
+      f.render_widget(
+        Paragraph::new(format!("{marker}{}: ", field.label))
+          .style(label_style)
+          .alignment(ratatui::layout::Alignment::Right),
+        cols[0],
+      );
+
+      let value_style = if focused { Style::default().fg(self.accent) } else { Style::default() };
+      match &field.kind {
+        FieldKind::Text(input) => {
+          let scroll = input.visual_scroll(cols[1].width.saturating_sub(1) as usize);
+          f.render_widget(Paragraph::new(input.value()).style(value_style).scroll((0, scroll as u16)), cols[1]);
+          if focused {
+            f.set_cursor_position((cols[1].x + (input.visual_cursor().saturating_sub(scroll)) as u16, cols[1].y));
+          }
+        },
+        FieldKind::Choice { options, selected } => {
+          let line = Line::from(vec![
+            Span::styled("< ", Style::default().add_modifier(Modifier::DIM)),
+            Span::styled(options[*selected].clone(), value_style),
+            Span::styled(" >", Style::default().add_modifier(Modifier::DIM)),
+          ]);
+          f.render_widget(line, cols[1]);
+        },
+      }
+    }
+
+    if !suggestions.is_empty() {
+      let mut spans = vec![Span::styled("Tab: ", Style::default().add_modifier(Modifier::DIM))];
+      for (i, suggestion) in suggestions.iter().enumerate() {
+        let style =
+          if i == 0 { Style::default().fg(self.accent) } else { Style::default().add_modifier(Modifier::DIM) };
+        spans.push(Span::styled(suggestion.to_string(), style));
+        spans.push(Span::raw("  "));
+      }
+      let cols = Layout::new(Direction::Horizontal, [Constraint::Length(label_width), Constraint::Fill(1)])
+        .split(rows[self.fields.len()]);
+      f.render_widget(Line::from(spans), cols[1]);
+    }
+
+    let preview_rect = rows[self.fields.len() + 2];
+    f.render_widget(Paragraph::new(self.preview.clone()), preview_rect);
+  }
+}
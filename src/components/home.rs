@@ -1,16 +1,15 @@
-use chrono::DateTime;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use futures::Future;
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
 use itertools::Itertools;
 use ratatui::{
   layout::{Constraint, Direction, Layout, Rect},
   style::{Color, Modifier, Style},
   text::{Line, Span},
-  widgets::{Block, BorderType, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
+  widgets::{Clear, List, ListItem, ListState, Paragraph, Wrap},
 };
 use tokio::{
-  io::AsyncBufReadExt,
   sync::mpsc::{self, UnboundedSender},
   task::JoinHandle,
 };
@@ -19,14 +18,66 @@ use tracing::{error, info, warn};
 use tui_input::{backend::crossterm::EventHandler, Input};
 
 use std::{
-  process::{Command, Stdio},
-  time::Duration,
+  collections::{BTreeMap, HashMap, HashSet, VecDeque},
+  path::{Path, PathBuf},
+  time::{Duration, Instant},
 };
 
-use super::{logger::Logger, Component, Frame};
+use super::{
+  deptree::{self, DepTree},
+  form::{Field, Form, FormPurpose},
+  jobs::JobQueue,
+  logger::Logger,
+  merged_logs::{self, MergedLogs},
+  pane_block, pane_title,
+  properties::PropertyInspector,
+  viewer::{diff_lines, unit_file_lines, Viewer},
+  Component, Frame,
+};
 use crate::{
   action::Action,
-  systemd::{self, Scope, UnitId, UnitScope, UnitWithStatus},
+  action_log, boot, boot_history, boot_wait,
+  changes::{self, Aspect, ChangeLog},
+  cli, clipboard, command_bar,
+  config::{self, Config, ListColumn, LogTimestamps, SortOrder, StartupView},
+  crash, cron, depgraph, docs, enablement,
+  equivalent::{self, Equivalent},
+  escalation, failure_hooks, favorites,
+  file_watch::{self, Watch},
+  graph, hardening, health, help, humanize,
+  journal::{self, Entry},
+  journal_usage,
+  keybindings::{self, Chord, Command, Context, Keymap, Lookup},
+  keymacro::{self, Replay, Step},
+  log_filter::{Filter, LogFilters},
+  log_search::LogSearch,
+  machine, monochrome, mounts, mouse,
+  multiplexer::Multiplexer,
+  ordering,
+  pane_layout::PaneLayout,
+  plugin::{self, OutputLine},
+  power::{self, PowerAction},
+  preset,
+  pressure::{self, Pressure},
+  process_tree::{self, Process},
+  property_diff, remote,
+  running_set::{self, RunningSet},
+  saved_view::{self, SavedView},
+  scaffold::{self, HardeningPreset, NewService, NewTimer},
+  search::{self, parse_property_query, SearchIndex},
+  self_check,
+  session::{self, SessionStats},
+  slices, sockets,
+  states::{self, UnitState},
+  subprocess::{run_with_timeout, Finished},
+  systemd::{
+    self, Accounting, BatchVerb, EnablementVerb, Invocation, Job, KillWhom, Rates, Scope, Transient, Triggers, UnitId,
+    UnitScope, UnitWithStatus,
+  },
+  theme::{self, Theme},
+  timeline::{self, Slot},
+  timers::{Schedule, TimerTimes},
+  unit_environment, unit_types, user_manager,
 };
 
 #[derive(Debug, Default, Copy, Clone, PartialEq)]
@@ -39,45 +90,135 @@ pub enum Mode {
   Processing,
   Error,
   SignalMenu,
+  RunMenu,
+  Viewer,
+  Form,
+  DepTree,
+  /// Every property of a unit, filtered by name
+  Properties,
+  /// The logs of several units streamed together
+  MergedLogs,
+  /// The managers' job queues
+  Jobs,
+  /// A question with a few answers, shown as a menu
+  Prompt,
+  /// The log pane has focus, for scrolling and searching it
+  Logs,
+  /// Typing a search of the log pane
+  LogSearch,
+  /// Typing a systemctl command after `:`
+  CommandBar,
 }
 
-#[derive(Clone, Copy)]
-pub struct Theme {
-  pub primary: Color,   // Cyan (dark) / Blue (light) - used in help popup
-  pub accent: Color,    // LightGreen (dark) / Green (light) - borders
-  pub kbd: Color,       // Gray (dark, appears white-ish) / Blue (light) - keyboard shortcuts
-  pub muted: Color,     // Gray (dark) / DarkGray (light)
-  pub muted_alt: Color, // DarkGray (dark) / Reset (light)
+/// Which boot(s) the log pane shows
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum BootFilter {
+  #[default]
+  All,
+  Current,
+  Previous,
+  /// A boot before the previous one, by its index in `journalctl --list-boots`: -2, -3...
+  Earlier(i32),
 }
 
-impl Default for Theme {
-  fn default() -> Self {
-    Self::detect()
+impl BootFilter {
+  /// The boot at `index` in `journalctl --list-boots`
+  pub fn at(index: i32) -> Self {
+    match index {
+      0 => BootFilter::Current,
+      -1 => BootFilter::Previous,
+      index => BootFilter::Earlier(index),
+    }
   }
-}
 
-impl Theme {
-  pub fn detect() -> Self {
-    let is_light = terminal_light::luma().is_ok_and(|luma| luma > 0.5);
+  pub fn next(self) -> Self {
+    match self {
+      BootFilter::All => BootFilter::Current,
+      BootFilter::Current => BootFilter::Previous,
+      BootFilter::Previous | BootFilter::Earlier(_) => BootFilter::All,
+    }
+  }
 
-    if is_light {
-      Self {
-        primary: Color::Blue,
-        accent: Color::Green,
-        kbd: Color::Blue,
-        muted: Color::DarkGray,
-        muted_alt: Color::Reset,
-      }
-    } else {
-      Self {
-        primary: Color::Cyan,
-        accent: Color::LightGreen,
-        kbd: Color::Gray, // appears white-ish when bold on dark terminals
-        muted: Color::Gray,
-        muted_alt: Color::DarkGray,
-      }
+  pub fn label(self) -> String {
+    match self {
+      BootFilter::All => "all boots".into(),
+      BootFilter::Current => "current boot".into(),
+      BootFilter::Previous => "previous boot".into(),
+      BootFilter::Earlier(index) => format!("boot {index}"),
+    }
+  }
+
+  fn journalctl_arg(self) -> Option<String> {
+    match self {
+      BootFilter::All => None,
+      BootFilter::Current => Some("--boot=0".into()),
+      BootFilter::Previous => Some("--boot=-1".into()),
+      BootFilter::Earlier(index) => Some(format!("--boot={index}")),
     }
   }
+
+  /// Only the current boot can produce new log lines
+  fn can_follow(self) -> bool {
+    matches!(self, BootFilter::All | BootFilter::Current)
+  }
+}
+
+/// Everything the journal thread needs to know to fetch logs for the selected unit
+#[derive(Debug, Clone)]
+pub struct LogQuery {
+  pub unit: UnitId,
+  pub boot: BootFilter,
+  /// Merge in SELinux/AppArmor denials involving the unit's executables or main PID
+  pub include_audit: bool,
+  /// Only show the unit's current (or most recent) run, by its invocation ID
+  pub current_run: bool,
+  /// Only show entries at this syslog priority or more important
+  pub priority: Option<u8>,
+  /// Restart a follow stream that stopped on its own from where the buffered logs end, instead of reading the last
+  /// batch again and replacing them
+  pub resume: Option<ResumePoint>,
+  /// Read a page of the lines logged before where the buffered logs start, to put before them
+  pub older: Option<ResumePoint>,
+}
+
+/// Where the buffered logs end (or start): the second of the last (or first) line, and the lines logged in that
+/// second, which a read covering that second will send again
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResumePoint {
+  pub since: i64,
+  pub seen: Vec<Entry>,
+}
+
+impl ResumePoint {
+  /// `None` when the last line has no timestamp, e.g. when there were no logs
+  pub fn after(logs: &[Entry]) -> Option<Self> {
+    let timestamp = |entry: &Entry| entry.text.split_whitespace().next().map(str::to_string);
+    let last = timestamp(logs.last()?)?;
+    let since = parse_journalctl_datetime(&last)?.timestamp();
+    let count = logs.iter().rev().take_while(|l| timestamp(l).as_ref() == Some(&last)).count();
+    let seen = logs[logs.len() - count..].to_vec();
+    Some(Self { since, seen })
+  }
+
+  /// `None` when the first line has no timestamp. Continuation lines of the first second's entries are seen too
+  pub fn before(logs: &[Entry]) -> Option<Self> {
+    let first = logs.first()?.text.split_whitespace().next()?;
+    let since = parse_journalctl_datetime(first)?.timestamp();
+    let count = logs.iter().take_while(|l| l.text.starts_with(first) || l.text.starts_with(' ')).count();
+    Some(Self { since, seen: logs[..count].to_vec() })
+  }
+
+  /// `lines` without the ones already seen
+  pub fn unseen(mut self, mut lines: Vec<Entry>) -> Vec<Entry> {
+    lines.retain(|line| match self.seen.iter().position(|s| s == line) {
+      Some(index) => {
+        self.seen.remove(index);
+        false
+      },
+      None => true,
+    });
+    lines
+  }
 }
 
 #[derive(Default)]
@@ -85,21 +226,170 @@ pub struct Home {
   pub scope: Scope,
   pub limit_units: Vec<String>,
   pub theme: Theme,
+  pub config: Config,
   pub logger: Logger,
   pub show_logger: bool,
+  pub viewer: Viewer,
+  pub form: Form,
+  pub dep_tree: DepTree,
+  pub property_inspector: PropertyInspector,
+  pub merged_logs: MergedLogs,
+  /// Streams the merged logs while they're open
+  merged_logs_task: Option<JoinHandle<()>>,
+  pub job_queue: JobQueue,
+  /// The queued jobs, as of the last poll
+  jobs: Vec<Job>,
+  /// Polls the job queues after an action until they're empty, or for as long as they're open
+  job_watch: Option<JoinHandle<()>>,
+  /// Set while dispatching a confirmed action, so it isn't asked about again
+  confirmed: bool,
+  /// The question asked in prompt mode; its answers are the menu items
+  prompt_title: String,
+  /// Shown above the answers
+  prompt_lines: Vec<Line<'static>>,
   pub all_units: IndexMap<UnitId, UnitWithStatus>,
+  search_index: SearchIndex,
+  /// Watches the manager's signals to keep the unit list current
+  unit_watch: Option<JoinHandle<()>>,
+  /// Scopes whose manager signals are being watched, so their units don't need polling
+  live_scopes: HashSet<UnitScope>,
+  /// I/O and network totals, for the selected unit or for every unit when list columns need them
+  accounting: HashMap<UnitId, Accounting>,
+  /// CPU, I/O and network use between the last two accounting refreshes
+  rates: HashMap<UnitId, Rates>,
+  /// CPU and memory use at each accounting refresh, oldest first, for the details pane's graphs
+  history: HashMap<UnitId, VecDeque<ResourceSample>>,
+  /// What units are listed by; resource columns go highest first
+  pub sort: SortOrder,
+  /// Where to go once the units are first loaded
+  startup_view: Option<StartupView>,
+  /// A unit just started, like a transient one, to select once it shows up in the list
+  select_when_listed: Option<UnitId>,
+  /// The rest of the view restored from the last session, applied once the units are listed
+  restoring: Option<SavedView>,
+  /// Where to scroll the logs restored from the last session once they're read
+  restore_logs_scroll: Option<u16>,
+  /// Units restarted when their files change
+  watches: HashMap<UnitId, Watch>,
+  /// The unit whose processes the viewer is showing, and their CPU time at the last refresh
+  process_view: Option<(UnitId, Option<process_tree::Sample>)>,
+  timers: HashMap<UnitId, TimerTimes>,
+  /// Units that fit in the unit list at its last render, for paging
+  list_page: usize,
+  /// Where the last render drew what can be clicked
+  mouse_areas: mouse::Areas,
+  /// `UnitFileState`, while the list shows it
+  file_states: HashMap<UnitId, String>,
+  /// `Documentation=` URIs, for units that have been selected
+  documentation: HashMap<UnitId, Vec<String>>,
+  triggers: HashMap<UnitId, Triggers>,
+  /// Mounts and devices each service needs, for services that have been selected
+  mount_requirements: HashMap<UnitId, Vec<mounts::Requirement>>,
+  /// OOM kills and core dumps of services whose last run ended in one, for those that have been selected
+  crash_evidence: HashMap<UnitId, crash::Evidence>,
+  /// What each plugin printed for the units it was run for, by the plugin's index in the config
+  plugin_output: HashMap<(usize, UnitId), Vec<Line<'static>>>,
+  /// The plugin whose output the details pane shows in place of the selected unit's details
+  details_tab: Option<usize>,
+  /// The last outcome of each health check for each unit it covers, by the check's index in the config
+  health: HashMap<(usize, UnitId), health::Outcome>,
+  /// When each health check last started for each unit
+  health_started: HashMap<(usize, UnitId), Instant>,
+  /// The rows of `unit_types::fields`, for units that have been selected
+  type_details: HashMap<UnitId, Vec<String>>,
+  /// Only units of this type are listed, e.g. `socket`
+  pub type_filter: Option<&'static str>,
+  /// Only failed units are listed
+  pub failed_only: bool,
+  /// Only units whose names match this are listed, from `--filter`
+  pub name_filter: Option<String>,
+  /// Only units in one of these states are listed, from `--state`
+  pub state_filter: Vec<String>,
+  /// For the selected timer's details
+  schedules: HashMap<UnitId, Schedule>,
+  /// PSI of every unit with a cgroup, refreshed with the unit list
+  pressure: HashMap<UnitId, Pressure>,
+  /// Units whose files changed on disk since the manager loaded them
+  stale_units: HashSet<UnitId>,
+  /// Property values fetched for a property query, by property name. Kept while the query stays a property query
+  property_values: HashMap<String, HashMap<UnitId, String>>,
+  /// State changes seen since startup
+  session: SessionStats,
+  /// State and enablement changes seen between refreshes
+  changes: ChangeLog,
+  /// When the changes were last looked at, so newer ones stand out
+  changes_seen: Option<DateTime<Local>>,
+  /// Offered again the next time logs are piped
+  last_pipe_command: String,
+  /// What the log pane keeps and drops, for every unit
+  log_filters: LogFilters,
+  /// Keys pressed since recording started, or in the last recording
+  macro_keys: Vec<KeyEvent>,
+  recording_macro: bool,
+  keymap: Keymap,
+  /// The start of a multi-key sequence in the unit list
+  pending_keys: Vec<Chord>,
+  /// Show what actions would do instead of doing them
+  dry_run: bool,
+  /// Refuse whatever changes units or the machine, keeping browsing and logs
+  read_only: bool,
+  /// The last state-changing action and whether it was a dry run, for the command echo
+  last_equivalent: Option<(Equivalent, bool)>,
+  replay: Option<Replay>,
+  /// Units picked for bulk actions, in the order they were marked
+  marked: IndexSet<UnitId>,
+  /// Starred units, listed first and kept across sessions
+  favorites: IndexSet<UnitId>,
+  /// A pinned unit jumped to with alt: its logs open in the pager once they've loaded
+  page_logs_for: Option<UnitId>,
+  /// Set while a page of older log lines is being read
+  loading_older_logs: bool,
+  /// Reading further back found nothing older
+  logs_start_reached: bool,
+  /// The newest log lines were pushed out by older ones, so new ones aren't added until the pane is back at the top
+  logs_detached: bool,
+  /// Measured on request, since it means reading all of a unit's logs. With the size of the journal it's part of
+  journal_usage: HashMap<UnitId, (journal_usage::Usage, Option<u64>)>,
+  /// Draw without color, per NO_COLOR/CLICOLOR or the monochrome theme
+  pub monochrome: bool,
   pub filtered_units: StatefulList<UnitWithStatus>,
-  pub logs: Vec<String>,
+  pub logs: Vec<Entry>,
+  /// How many of `logs` were logged in each minute, by minutes since the epoch, for the log rate graph
+  log_minutes: BTreeMap<i64, u64>,
   pub logs_scroll_offset: u16,
+  /// Long log lines wrap, otherwise they run off the right edge and Left/Right scroll sideways. Kept from unit to unit
+  pub log_wrap: bool,
+  /// Columns scrolled right, while not wrapping
+  pub log_hscroll: u16,
+  pub log_timestamps: LogTimestamps,
+  /// The log pane's width inside its borders, as last drawn
+  log_page_width: u16,
+  /// Where the unit list, details and logs go
+  pub pane_layout: PaneLayout,
+  pub log_boot: BootFilter,
+  pub log_include_audit: bool,
+  pub log_current_run: bool,
+  /// Hide log entries less important than this syslog priority
+  pub log_priority: Option<u8>,
+  pub log_search: LogSearch,
+  /// What's typed while searching the logs; the search itself follows along
+  pub log_search_input: Input,
+  /// What's typed in the command bar
+  pub command_input: Input,
+  command_history: command_bar::History,
   pub mode: Mode,
   pub previous_mode: Option<Mode>,
   pub input: Input,
   pub menu_items: StatefulList<MenuItem>,
   pub cancel_token: Option<CancellationToken>,
   pub spinner_tick: u8,
+  /// When the unit fetch in flight started; the list keeps showing the last one's units meanwhile
+  refreshing_since: Option<Instant>,
+  /// Whether another fetch was asked for while one was in flight, to run once it's done
+  refresh_again: bool,
   pub error_message: String,
   pub action_tx: Option<mpsc::UnboundedSender<Action>>,
-  pub journalctl_tx: Option<std::sync::mpsc::Sender<UnitId>>,
+  pub journalctl_tx: Option<std::sync::mpsc::Sender<LogQuery>>,
 }
 
 pub struct MenuItem {
@@ -197,17 +487,76 @@ impl<T> StatefulList<T> {
 }
 
 impl Home {
-  pub fn new(scope: Scope, limit_units: &[String]) -> Self {
+  pub fn new(scope: Scope, limit_units: &[String], config: Config) -> Self {
     let limit_units = limit_units.to_vec();
-    Self { scope, limit_units, ..Default::default() }
+    let mut home = Self { scope, limit_units, ..Default::default() };
+    home.logger.accessible = config.accessible;
+    home.dry_run = config.dry_run;
+    home.read_only = config.read_only;
+    // a sort set in the config wins over the one the last session was closed with
+    home.sort = config.sort.or_else(|| saved_view::load()?.sort).unwrap_or_default();
+    home.log_wrap = true;
+    home.log_timestamps = config.log_timestamps;
+    home.pane_layout = PaneLayout::new(config.split, config.list_size);
+    // already checked when the config was loaded
+    home.keymap = Keymap::new(&config.keybindings).unwrap_or_default();
+    home.command_history = command_bar::History::load();
+    home.favorites = favorites::load();
+    home.startup_view = config.startup_view.clone();
+    home.name_filter = config.startup_filter.pattern.clone();
+    home.state_filter = config.startup_filter.states.clone();
+    home.type_filter = config.startup_filter.unit_type;
+    if let Some(view) = config.restored_view.clone() {
+      home.input = Input::new(view.search.clone());
+      home.type_filter = view.unit_type;
+      home.failed_only = view.failed_only;
+      home.restoring = Some(view);
+    }
+    home.set_theme(config.theme().unwrap_or_else(|_| Theme::detect()));
+    humanize::set_style(humanize::Style::new(config.locale.as_deref(), config.precision));
+    action_log::set_path(config.action_log.clone());
+    home.config = config;
+    home
   }
 
   pub fn set_units(&mut self, units: Vec<UnitWithStatus>) {
     self.all_units.clear();
+    self.search_index.clear();
     for unit_status in units.into_iter() {
+      self.search_index.insert(unit_status.id(), unit_status.short_name(), &unit_status.description);
       self.all_units.insert(unit_status.id(), unit_status);
     }
     self.refresh_filtered_units();
+    if let Some(view) = self.restoring.take() {
+      self.finish_restoring(view);
+    }
+  }
+
+  /// Select the unit the last session had selected, scrolled as it was, if it's still listed
+  fn finish_restoring(&mut self, view: SavedView) {
+    let selected = view.selected.and_then(|unit| self.filtered_units.items.iter().position(|u| u.id() == unit));
+    let Some(index) = selected else { return };
+    *self.filtered_units.state.offset_mut() = view.list_offset.min(index);
+    self.select(Some(index), true);
+    self.restore_logs_scroll = Some(view.logs_scroll);
+    if view.logs_focused {
+      self.mode = Mode::Logs;
+    }
+  }
+
+  /// What's shown now, for the next session to pick up
+  pub fn saved_view(&self) -> SavedView {
+    SavedView {
+      search: self.input.value().to_string(),
+      selected: self.selected_service(),
+      scope: Some(self.scope),
+      sort: Some(self.sort),
+      unit_type: self.type_filter,
+      failed_only: self.failed_only,
+      list_offset: self.filtered_units.state.offset(),
+      logs_scroll: self.logs_scroll_offset,
+      logs_focused: matches!(self.mode, Mode::Logs | Mode::LogSearch),
+    }
   }
 
   // Update units in-place, then filter the list
@@ -218,7 +567,22 @@ impl Home {
     let now = std::time::Instant::now();
 
     for unit in units {
+      self.search_index.insert(unit.id(), unit.short_name(), &unit.description);
       if let Some(existing) = self.all_units.get_mut(&unit.id()) {
+        self.session.record(unit.id(), &existing.activation_state, &unit.activation_state, Local::now());
+        let (before, after) = (
+          changes::state(&existing.activation_state, &existing.sub_state),
+          changes::state(&unit.activation_state, &unit.sub_state),
+        );
+        self.changes.record(&unit.id(), Aspect::State, &before, &after, Local::now());
+        if unit.is_failed() && !existing.is_failed() {
+          for hook in self.config.failure_hooks_for(&unit.name) {
+            info!("{} failed, firing its failure hook", unit.name);
+            if let Err(e) = failure_hooks::fire(hook, &unit.id(), &unit.description, !self.read_only) {
+              error!("Failure hook for {} didn't fire: {e:#}", unit.name);
+            }
+          }
+        }
         existing.update(unit);
       } else {
         self.all_units.insert(unit.id(), unit);
@@ -229,35 +593,65 @@ impl Home {
     let now = std::time::Instant::now();
     self.refresh_filtered_units();
     info!("Filtered units in {:?}", now.elapsed());
+
+    if let Some(unit) = self.select_when_listed.take_if(|unit| self.all_units.contains_key(unit)) {
+      if !self.filtered_units.items.iter().any(|u| u.id() == unit) {
+        self.input = Input::default();
+        self.refresh_filtered_units();
+      }
+      if let Some(index) = self.filtered_units.items.iter().position(|u| u.id() == unit) {
+        self.select(Some(index), true);
+      }
+    }
   }
 
   pub fn next(&mut self) {
-    self.logs = vec![];
-    self.filtered_units.next();
-    self.get_logs();
-    self.logs_scroll_offset = 0;
+    self.move_selection(1);
   }
 
   pub fn previous(&mut self) {
-    self.logs = vec![];
-    self.filtered_units.previous();
-    self.get_logs();
-    self.logs_scroll_offset = 0;
+    self.move_selection(-1);
+  }
+
+  /// Move the selection `by` units, down for positive, wrapping around at the ends unless `stop-at-ends` is set
+  fn move_selection(&mut self, by: isize) {
+    let index = moved(self.filtered_units.state.selected(), self.filtered_units.items.len(), by, self.wraps());
+    if index.is_some() && index != self.filtered_units.state.selected() {
+      self.select(index, true);
+    }
+  }
+
+  /// Select the next unit below the selection that `matches`, wrapping around unless `stop-at-ends` is set
+  fn jump_to_next(&mut self, matches: impl Fn(&UnitWithStatus) -> bool) {
+    let units = &self.filtered_units.items;
+    let index = next_match(self.filtered_units.state.selected(), units.len(), self.wraps(), |i| matches(&units[i]));
+    if index.is_some() {
+      self.select(index, true);
+    }
+  }
+
+  fn wraps(&self) -> bool {
+    !self.config.stop_at_ends
   }
 
   pub fn select(&mut self, index: Option<usize>, refresh_logs: bool) {
     if refresh_logs {
       self.logs = vec![];
+      self.log_minutes.clear();
     }
     self.filtered_units.select(index);
     if refresh_logs {
+      // a plugin's tab is about the unit it ran for
+      self.details_tab = None;
       self.get_logs();
       self.logs_scroll_offset = 0;
+      self.log_hscroll = 0;
     }
   }
 
   pub fn unselect(&mut self) {
     self.logs = vec![];
+    self.log_minutes.clear();
     self.filtered_units.unselect();
   }
 
@@ -265,28 +659,753 @@ impl Home {
     self.filtered_units.selected().map(|u| u.id())
   }
 
+  /// Read the config file again and put what it says into effect. What's only read at launch, or was given on the
+  /// command line, stays as it was
+  pub fn reload_config(&mut self) -> anyhow::Result<()> {
+    let mut config = Config::load()?;
+    config.keep_launch_settings(&self.config);
+    self.keymap = Keymap::new(&config.keybindings)?;
+    // with no theme set, the one matching the terminal's background stays
+    if let Some(name) = &config.theme {
+      self.set_theme(theme::resolve(name, &config.themes)?.with_palette(config.palette));
+    }
+    let layout = PaneLayout::new(config.split, config.list_size);
+    (self.pane_layout.split, self.pane_layout.list_share) = (layout.split, layout.list_share);
+    humanize::set_style(humanize::Style::new(config.locale.as_deref(), config.precision));
+    action_log::set_path(config.action_log.clone());
+    // the checks may have been reordered or changed, so their outcomes don't carry over
+    self.health.clear();
+    self.health_started.clear();
+    // and so might the plugins
+    self.plugin_output.clear();
+    self.details_tab = None;
+    self.config = config;
+    info!("Reloaded the config");
+    Ok(())
+  }
+
+  /// Whether read-only mode refuses `action`: whatever changes the system, and plugins not marked as only looking
+  pub fn refuses(&self, action: &Action) -> bool {
+    let changes_system = match action {
+      Action::RunPlugin { plugin, .. } => !self.config.plugins.get(*plugin).is_some_and(|p| p.read_only),
+      action => action.changes_system(),
+    };
+    self.read_only && changes_system
+  }
+
+  /// What a command bound in the unit list does
+  pub fn run_command(&mut self, command: Command) -> Vec<Action> {
+    let selected = self.filtered_units.selected().cloned();
+    let unit = selected.as_ref().map(|u| u.id());
+    let for_unit = |f: fn(UnitId) -> Action| unit.clone().map(f).into_iter().collect_vec();
+    if self.read_only && command.changes_system() {
+      return vec![Action::EnterError(READ_ONLY_REFUSAL.into())];
+    }
+    match command {
+      Command::Quit => vec![Action::Quit],
+      Command::Previous => {
+        // if we're filtering the list, and we're at the top, and there's text in the search box, go to search mode
+        if self.filtered_units.state.selected() == Some(0) {
+          return vec![Action::EnterMode(Mode::Search)];
+        }
+
+        self.previous();
+        vec![Action::Render]
+      },
+      Command::Next => {
+        self.next();
+        vec![Action::Render]
+      },
+      Command::Search => vec![Action::EnterMode(Mode::Search)],
+      Command::HalfPageDown => {
+        self.move_selection((self.list_page / 2).max(1) as isize);
+        vec![Action::Render]
+      },
+      Command::HalfPageUp => {
+        self.move_selection(-((self.list_page / 2).max(1) as isize));
+        vec![Action::Render]
+      },
+      Command::PageDown => {
+        self.move_selection(self.list_page.max(1) as isize);
+        vec![Action::Render]
+      },
+      Command::PageUp => {
+        self.move_selection(-(self.list_page.max(1) as isize));
+        vec![Action::Render]
+      },
+      Command::NextFailed => {
+        self.jump_to_next(|u| u.is_failed());
+        vec![Action::Render]
+      },
+      Command::FailedView => vec![Action::ToggleFailedView],
+      Command::ToggleFavorite => for_unit(Action::ToggleFavorite),
+      Command::ResetFailed => for_unit(Action::ResetFailed),
+      Command::ResetAllFailed => vec![Action::ConfirmResetAllFailed],
+      Command::NextInstance => {
+        if let Some(template) = selected.and_then(|u| unit_types::template(&u.name)) {
+          self.jump_to_next(|u| unit_types::template(&u.name).as_ref() == Some(&template));
+        }
+        vec![Action::Render]
+      },
+      Command::Instantiate => {
+        let template =
+          selected.as_ref().and_then(|u| Some(UnitId { name: unit_types::template(&u.name)?, scope: u.scope }));
+        vec![Action::OpenInstantiate(template)]
+      },
+      Command::CycleUnitType => {
+        self.cycle_unit_type();
+        vec![Action::Render]
+      },
+      Command::CycleSort => {
+        self.cycle_sort();
+        vec![Action::Render]
+      },
+      Command::ActionMenu => vec![Action::EnterMode(Mode::ActionMenu)],
+      Command::Start => for_unit(Action::StartService),
+      Command::Stop => for_unit(Action::StopService),
+      Command::Restart => for_unit(Action::RestartService),
+      Command::Reload => for_unit(Action::ReloadService),
+      Command::Kill if unit.is_some() => vec![Action::EnterMode(Mode::SignalMenu)],
+      Command::Enable => for_unit(Action::EnableService),
+      Command::Disable => for_unit(Action::DisableService),
+      Command::Reenable => for_unit(Action::ReenableService),
+      Command::ApplyPreset => for_unit(Action::PresetService),
+      Command::PresetAll => {
+        vec![Action::OpenPresetAll(if matches!(self.scope, Scope::User) { UnitScope::User } else { UnitScope::Global })]
+      },
+      Command::ToggleMask => selected.iter().map(mask_action).collect(),
+      Command::ToggleFreeze => match &unit {
+        Some(unit) => vec![Action::FreezeUnit { unit: unit.clone(), thaw: self.is_frozen(unit) }],
+        None => vec![],
+      },
+      Command::Clean => for_unit(Action::OpenClean),
+      Command::EditUnitFile => match selected.and_then(|u| u.file_path.clone().and_then(Result::ok).map(|p| (u, p))) {
+        Some((selected, path)) => vec![Action::EditUnitFile { unit: selected.id(), path }],
+        None => vec![],
+      },
+      Command::EditDropIn => match selected {
+        Some(selected) => {
+          vec![Action::EditDropIn { unit: selected.id(), fragment_path: selected.file_path.and_then(Result::ok) }]
+        },
+        None => vec![],
+      },
+      Command::CopyUnitFilePath if unit.is_some() => vec![Action::CopyUnitFilePath],
+      Command::SetProperties => for_unit(Action::OpenSetProperties),
+      Command::ShowUnitDelta => for_unit(Action::ShowUnitDelta),
+      Command::ShowEnvironment => for_unit(Action::ShowEnvironment),
+      Command::SetEnvironment => for_unit(Action::OpenSetEnvironment),
+      Command::WatchFiles => match unit {
+        Some(unit) if self.watches.contains_key(&unit) => vec![Action::UnwatchFiles(unit)],
+        _ => for_unit(Action::OpenWatchFiles),
+      },
+      Command::VerifyUnitFile => {
+        match selected.and_then(|u| u.file_path.clone().and_then(Result::ok).map(|p| (u, p))) {
+          Some((selected, path)) => vec![Action::VerifyUnitFile { unit: selected.id(), path }],
+          None => vec![],
+        }
+      },
+      Command::ShowSandboxing => for_unit(Action::ShowSandboxing),
+      Command::ShowProcesses => for_unit(Action::ShowProcesses),
+      Command::ShowListening => for_unit(Action::ShowListening),
+      Command::ShowProperties => for_unit(Action::ShowProperties),
+      Command::ShowJobs => vec![Action::ShowJobs],
+      Command::ShowUnitFiles => for_unit(Action::ShowUnitFiles),
+      Command::FollowMergedLogs => {
+        // the marked units, or just the selected one
+        let units = if self.marked.is_empty() {
+          unit.clone().into_iter().collect()
+        } else {
+          self.marked.iter().cloned().collect()
+        };
+        vec![Action::FollowMergedLogs(units)]
+      },
+      Command::ShowRunHistory => for_unit(Action::ShowRunHistory),
+      Command::ShowTimeline => for_unit(Action::ShowTimeline),
+      Command::ShowBootWait => for_unit(Action::ShowBootWait),
+      Command::ShowBootContext => for_unit(Action::ShowBootContext),
+      Command::ShowCrash => for_unit(Action::ShowCrash),
+      Command::PluginTab => {
+        let next = self.details_tab.map_or(0, |i| i + 1);
+        match unit.clone().filter(|_| next < self.config.plugins.len()) {
+          Some(unit) => vec![Action::RunPlugin { unit, plugin: next }],
+          None => {
+            self.details_tab = None;
+            vec![Action::Render]
+          },
+        }
+      },
+      Command::ShowJournalUsage => for_unit(Action::ShowJournalUsage),
+      Command::ExportGraph => for_unit(Action::OpenExportGraph),
+      Command::FollowLogsInPane => for_unit(Action::FollowLogsInPane),
+      Command::ShowDocumentation => for_unit(Action::ShowDocumentation),
+      Command::GoToTrigger => for_unit(Action::GoToTrigger),
+      Command::GoToMount => for_unit(Action::GoToMount),
+      Command::EnableAccounting => for_unit(Action::EnableAccounting),
+      Command::DependencyTree => for_unit(Action::ShowDependencyTree),
+      Command::DeltaAudit => vec![Action::ShowDeltaAudit],
+      Command::Presets => vec![Action::ShowPresets],
+      Command::OrderingCycles => vec![Action::ShowOrderingCycles],
+      Command::BootPlan => vec![Action::ShowBootPlan],
+      Command::BootFailures => vec![Action::ShowBootFailures],
+      Command::BootTimes => vec![Action::OpenBootTimes],
+      Command::SliceTree => vec![Action::ShowSliceTree],
+      Command::SessionStats => vec![Action::ShowSessionStats],
+      Command::WhatChanged => vec![Action::ShowChanges],
+      Command::ActionLog => vec![Action::ShowActionLog(None)],
+      Command::SelfCheck => vec![Action::ShowSelfCheck { first_run: false }],
+      Command::PowerMenu => vec![Action::OpenPowerMenu],
+      Command::BootTargets => vec![Action::OpenBootTargets],
+      Command::NewService => vec![Action::OpenNewServiceWizard],
+      Command::RunTransient => vec![Action::OpenRunTransient],
+      Command::NewTimer => vec![Action::OpenNewTimerWizard],
+      Command::CrontabImport => vec![Action::OpenCrontabImport],
+      Command::ManagerEnvironment => vec![Action::OpenManagerEnvironment],
+      Command::SwitchHost => vec![Action::OpenHostSwitcher],
+      Command::SwitchUser => vec![Action::OpenUserSwitcher],
+      Command::SwitchMachine => vec![Action::OpenMachineSwitcher],
+      Command::CycleScope => vec![Action::CycleScope],
+      Command::CycleLogBoot => {
+        self.cycle_log_boot();
+        vec![Action::Render]
+      },
+      Command::PickLogBoot => vec![Action::OpenLogBootPicker],
+      Command::ToggleLogAudit => {
+        self.toggle_log_audit();
+        vec![Action::Render]
+      },
+      Command::ToggleCurrentRun => {
+        self.toggle_log_current_run();
+        vec![Action::Render]
+      },
+      Command::CycleLogPriority => {
+        self.cycle_log_priority();
+        vec![Action::Render]
+      },
+      Command::CycleLogTimestamps => {
+        self.log_timestamps = self.log_timestamps.next();
+        vec![Action::Render]
+      },
+      Command::LogsInPager => vec![Action::OpenLogsInPager],
+      Command::CopyLogs => vec![Action::CopyText { what: "logs".into(), text: self.plain_logs() }],
+      Command::PipeLogs => vec![Action::OpenPipeLogs],
+      Command::RecordMacro => {
+        self.macro_keys.clear();
+        self.recording_macro = true;
+        vec![Action::Render]
+      },
+      Command::ReplayMacro => vec![Action::OpenReplayMacro],
+      Command::Mark => {
+        if let Some(unit) = unit {
+          if !self.marked.shift_remove(&unit) {
+            self.marked.insert(unit);
+          }
+          self.next();
+        }
+        vec![Action::Render]
+      },
+      Command::ClearMarks => {
+        self.marked.clear();
+        vec![Action::Render]
+      },
+      Command::RunningSets => vec![Action::OpenRunningSets],
+      Command::Themes => vec![Action::OpenThemePicker],
+      Command::FocusLogs => vec![Action::EnterMode(Mode::Logs)],
+      Command::MaximizeLogs => {
+        self.pane_layout.logs_maximized = true;
+        vec![Action::EnterMode(Mode::Logs)]
+      },
+      Command::ToggleSplit => {
+        self.pane_layout.split = self.pane_layout.split.toggled();
+        info!("Layout: {}", self.pane_layout.describe());
+        vec![Action::Render]
+      },
+      Command::GrowList | Command::ShrinkList => {
+        self.pane_layout.resize(command == Command::GrowList);
+        info!("Layout: {}", self.pane_layout.describe());
+        vec![Action::Render]
+      },
+      Command::DryRun => {
+        self.dry_run = !self.dry_run;
+        info!("Dry run {}", if self.dry_run { "on" } else { "off" });
+        vec![Action::Render]
+      },
+      Command::CommandBar => {
+        self.command_input = Input::default();
+        self.command_history.reset();
+        vec![Action::EnterMode(Mode::CommandBar)]
+      },
+      Command::ForceQuit => vec![Action::Quit],
+      Command::Suspend => vec![Action::Suspend],
+      Command::JumpToSearch => vec![Action::EnterMode(Mode::Search)],
+      Command::ToggleDebugLog => vec![Action::ToggleShowLogger],
+      Command::Help => vec![Action::ToggleHelp, Action::Render],
+      Command::ScrollLogsDown => vec![Action::ScrollDown(1), Action::Render],
+      Command::ScrollLogsUp => vec![Action::ScrollUp(1), Action::Render],
+      Command::LogsTop => vec![Action::ScrollToTop, Action::Render],
+      Command::LogsBottom => vec![Action::ScrollToBottom, Action::Render],
+      Command::LogDown => vec![Action::ScrollDown(1), Action::Render],
+      Command::LogUp => vec![Action::ScrollUp(1), Action::Render],
+      Command::LeaveLogs => {
+        // the list has to be on screen to go back to it
+        self.pane_layout.logs_maximized = false;
+        vec![Action::EnterMode(Mode::ServiceList)]
+      },
+      Command::LogMaximize => {
+        self.pane_layout.logs_maximized = !self.pane_layout.logs_maximized;
+        vec![Action::Render]
+      },
+      Command::LogSearch => {
+        self.log_search_input = Input::new(self.log_search.query.clone());
+        vec![Action::EnterMode(Mode::LogSearch)]
+      },
+      Command::LogNextMatch | Command::LogPreviousMatch => {
+        let line = if command == Command::LogNextMatch {
+          self.log_search.next_match()
+        } else {
+          self.log_search.previous_match()
+        };
+        self.scroll_to_log_line(line);
+        vec![Action::Render]
+      },
+      Command::LogCopy => vec![Action::CopyText { what: "logs".into(), text: self.plain_logs() }],
+      Command::LogCopyLine => match self.selected_log_line() {
+        Some(line) => vec![Action::CopyText { what: "log line".into(), text: monochrome::strip_ansi(line) }],
+        None => vec![],
+      },
+      Command::LogSave => vec![Action::OpenExportLogs],
+      Command::LogFilters => vec![Action::OpenLogFilters],
+      Command::LogInspect => vec![Action::InspectLogEntry],
+      Command::LogWrap => {
+        self.toggle_log_wrap();
+        vec![Action::Render]
+      },
+      Command::LogTimestamps => {
+        self.log_timestamps = self.log_timestamps.next();
+        vec![Action::Render]
+      },
+      // wrapped lines have nothing off to the side
+      Command::LogLeft | Command::LogRight | Command::LogLineStart if self.log_wrap => vec![],
+      Command::LogLeft => {
+        self.log_hscroll = self.log_hscroll.saturating_sub(LOG_HSCROLL_STEP);
+        vec![Action::Render]
+      },
+      Command::LogRight => {
+        self.log_hscroll = self.log_hscroll.saturating_add(LOG_HSCROLL_STEP);
+        vec![Action::Render]
+      },
+      Command::LogLineStart => {
+        self.log_hscroll = 0;
+        vec![Action::Render]
+      },
+      Command::Kill | Command::CopyUnitFilePath => vec![],
+    }
+  }
+
+  /// The log pane's footer hints, with the keys as bound
+  fn log_pane_hints(&self) -> String {
+    let key = |command| {
+      self.keymap.describe(command).map(|keys| match keys.as_str() {
+        "enter" | "esc" | "tab" => format!("<{keys}>"),
+        "left" => "←".into(),
+        "right" => "→".into(),
+        _ => keys,
+      })
+    };
+    let pair = |a, b| Some(format!("{}/{}", key(a)?, key(b)?));
+    let hints = if self.log_wrap {
+      vec![
+        ("Scroll", pair(Command::LogDown, Command::LogUp)),
+        ("Search", key(Command::LogSearch)),
+        ("Next/previous match", pair(Command::LogNextMatch, Command::LogPreviousMatch)),
+        ("Filter", key(Command::LogFilters)),
+        ("Fields", key(Command::LogInspect)),
+        ("Copy", pair(Command::LogCopy, Command::LogCopyLine)),
+        ("Save", key(Command::LogSave)),
+        ("Unwrap", key(Command::LogWrap)),
+        ("Times", key(Command::LogTimestamps)),
+        ("Maximize", key(Command::LogMaximize)),
+        ("Back", key(Command::LeaveLogs)),
+      ]
+    } else {
+      vec![
+        ("Scroll", pair(Command::LogDown, Command::LogUp)),
+        ("Sideways", pair(Command::LogLeft, Command::LogRight)),
+        ("Start of line", key(Command::LogLineStart)),
+        ("Wrap", key(Command::LogWrap)),
+        ("Times", key(Command::LogTimestamps)),
+        ("Search", key(Command::LogSearch)),
+        ("Next/previous match", pair(Command::LogNextMatch, Command::LogPreviousMatch)),
+        ("Filter", key(Command::LogFilters)),
+        ("Back", key(Command::LeaveLogs)),
+      ]
+    };
+    hints.into_iter().filter_map(|(label, keys)| Some(format!("{label}: {}", keys?))).join(" | ")
+  }
+
+  /// Look up the keys typed so far, plus `key`, among the commands for `context`, and run the one they make up
+  fn run_keys(&mut self, context: Context, key: KeyEvent) -> Vec<Action> {
+    self.pending_keys.push(Chord::from(key));
+    match self.keymap.lookup(context, &self.pending_keys) {
+      Lookup::Found(command) => {
+        self.pending_keys.clear();
+        self.run_command(command)
+      },
+      Lookup::Prefix => vec![Action::Render],
+      Lookup::None => {
+        let in_sequence = self.pending_keys.len() > 1;
+        self.pending_keys.clear();
+        match pinned_slot(key.code) {
+          Some(slot) if !in_sequence && context == Context::UnitList => {
+            vec![Action::JumpToPinned { slot, logs: false }]
+          },
+          _ => vec![Action::Render],
+        }
+      },
+    }
+  }
+
+  /// Every key, grouped by where it works, for the help overlay
+  fn help_lines(&self) -> Vec<Line<'static>> {
+    let dim = Style::default().add_modifier(Modifier::DIM);
+    let sections = help::sections(&self.keymap);
+    let width = sections.iter().flat_map(|s| &s.entries).map(|e| e.keys.join(", ").chars().count()).max().unwrap_or(0);
+    let mut lines = vec![
+      Line::from(Span::styled(
+        "Keys with a name on the right can be remapped in the [keybindings] table of config.toml, by that name",
+        dim,
+      )),
+      Line::from(vec![
+        Span::styled("Mouse: ", dim),
+        Span::raw("click a unit or the log pane, scroll with the wheel, click a hint in the footer"),
+      ]),
+    ];
+    for section in sections {
+      lines.push(Line::from(""));
+      lines.push(Line::from(Span::styled(section.title, Style::default().add_modifier(Modifier::UNDERLINED))));
+      for entry in section.entries {
+        let refused = self.read_only
+          && entry
+            .command
+            .and_then(|name| Command::try_from(name.to_string()).ok())
+            .is_some_and(Command::changes_system);
+        let keys = match entry.keys.as_slice() {
+          [] => Span::styled(format!("{:width$}", "unbound"), dim),
+          keys if refused => Span::styled(format!("{:width$}", keys.join(", ")), Style::default().fg(self.theme.muted)),
+          keys => Span::styled(format!("{:width$}", keys.join(", ")), Style::default().fg(self.theme.primary)),
+        };
+        let description = if refused { Span::styled(entry.description, dim) } else { Span::raw(entry.description) };
+        let mut spans = vec![keys, Span::raw("  "), description];
+        spans.extend(entry.command.map(|name| Span::styled(format!("  {name}"), dim)));
+        lines.push(Line::from(spans));
+      }
+    }
+    lines
+  }
+
+  /// What's searched for in the log pane and how it's going, for the pane's bottom border
+  fn log_search_title(&self, theme: Theme) -> Line<'static> {
+    let search = &self.log_search;
+    let query = match self.mode {
+      Mode::LogSearch => self.log_search_input.value(),
+      _ if search.is_active() => &search.query,
+      _ => return Line::default(),
+    };
+    let mut spans = vec![
+      Span::raw("─"),
+      Span::styled(format!("/{query}"), Style::default().add_modifier(Modifier::BOLD).fg(theme.kbd)),
+    ];
+    if search.regex {
+      spans.push(Span::styled(" (regex)", Style::default().fg(theme.muted)));
+    }
+    spans.push(match (&search.error, search.position()) {
+      (Some(error), _) => Span::styled(format!(" {error}"), Style::default().fg(theme.failed)),
+      (None, Some(position)) => Span::raw(format!(" {position}/{}", search.match_count())),
+      (None, None) if search.is_active() => Span::raw(format!(" {} matches", search.match_count())),
+      (None, None) => Span::raw(""),
+    });
+    spans.push(Span::raw("─"));
+    Line::from(spans)
+  }
+
+  /// What the command echo shows: the highlighted menu action, else the last action taken on the selected unit, else
+  /// how to get the logs being shown. Labelled so it's clear which one it is
+  fn echoed_command(&self) -> Option<(&'static str, String)> {
+    if !self.config.command_echo {
+      return None;
+    }
+    if matches!(self.mode, Mode::ActionMenu | Mode::SignalMenu) {
+      if let Some(equivalent) = self.menu_items.selected().and_then(|item| equivalent::for_action(&item.action)) {
+        return Some((if self.dry_run { "would run" } else { "will run" }, equivalent.shell()));
+      }
+    }
+    let unit = self.selected_service()?;
+    match &self.last_equivalent {
+      Some((equivalent, dry_run)) if equivalent.unit == unit => {
+        Some((if *dry_run { "would have run" } else { "ran" }, equivalent.shell()))
+      },
+      _ => Some((
+        "logs",
+        equivalent::journalctl(
+          &unit,
+          self.log_boot.journalctl_arg().as_deref(),
+          self.log_priority,
+          self.log_current_run,
+        ),
+      )),
+    }
+  }
+
   pub fn get_logs(&mut self) {
     if let Some(selected) = self.filtered_units.selected() {
-      let unit_id = selected.id();
-      if let Err(e) = self.journalctl_tx.as_ref().unwrap().send(unit_id) {
+      let query = LogQuery {
+        unit: selected.id(),
+        boot: self.log_boot,
+        include_audit: self.log_include_audit,
+        current_run: self.log_current_run,
+        priority: self.log_priority,
+        resume: None,
+        older: None,
+      };
+      if let Err(e) = self.journalctl_tx.as_ref().unwrap().send(query) {
         warn!("Error sending unit name to journalctl thread: {}", e);
       }
     } else {
       self.logs = vec![];
+      self.log_minutes.clear();
+    }
+  }
+
+  /// Read a page of older lines once the log pane is scrolled close to the oldest one it has
+  fn get_older_logs_if_needed(&mut self) {
+    let below = self.logs.len().saturating_sub(self.logs_scroll_offset as usize);
+    if below > OLDER_LOGS_MARGIN || self.loading_older_logs || self.logs_start_reached {
+      return;
+    }
+    let Some(unit) = self.selected_service() else { return };
+    let Some(older) = ResumePoint::before(&self.logs) else {
+      self.logs_start_reached = true;
+      return;
+    };
+    self.loading_older_logs = true;
+    let query = LogQuery {
+      unit,
+      boot: self.log_boot,
+      include_audit: self.log_include_audit,
+      current_run: self.log_current_run,
+      priority: self.log_priority,
+      resume: None,
+      older: Some(older),
+    };
+    if let Err(e) = self.journalctl_tx.as_ref().unwrap().send(query) {
+      warn!("Error sending unit name to journalctl thread: {}", e);
+    }
+  }
+
+  pub fn cycle_log_boot(&mut self) {
+    self.set_log_boot(self.log_boot.next());
+  }
+
+  pub fn set_log_boot(&mut self, boot: BootFilter) {
+    self.log_boot = boot;
+    // keep showing the old lines until the new batch arrives instead of flashing an empty pane
+    self.logs_scroll_offset = 0;
+    self.get_logs();
+  }
+
+  pub fn toggle_log_audit(&mut self) {
+    self.log_include_audit = !self.log_include_audit;
+    if self.log_include_audit {
+      self.logs_scroll_offset = 0;
+      self.get_logs();
+    } else {
+      // the audit lines are tagged, so turning them off doesn't need another trip to journalctl
+      self.logs.retain(|l| !l.text.contains(AUDIT_TAG));
+      self.log_minutes = count_log_minutes(&self.logs);
+    }
+  }
+
+  pub fn cycle_log_priority(&mut self) {
+    self.log_priority = journal::next_priority_filter(self.log_priority);
+    self.logs_scroll_offset = 0;
+    self.get_logs();
+  }
+
+  /// The logs as plain text, for the pager and other programs
+  pub fn logs_text(&self) -> String {
+    self.logs.iter().map(|l| l.text.as_str()).join("\n")
+  }
+
+  /// The logs without their colors, to copy or save
+  fn plain_logs(&self) -> String {
+    self.logs.iter().map(|l| monochrome::strip_ansi(&l.text)).join("\n")
+  }
+
+  /// The log line that `Y` copies: the search match jumped to last, otherwise the one at the top of the pane
+  fn selected_log_line(&self) -> Option<&str> {
+    self.selected_log_entry().map(|l| l.text.as_str())
+  }
+
+  fn selected_log_entry(&self) -> Option<&Entry> {
+    let index = match self.log_search.current {
+      Some(index) if self.log_search.is_active() => index,
+      _ => self.logs.len().checked_sub(self.logs_scroll_offset as usize + 1)?,
+    };
+    self.logs.get(index)
+  }
+
+  /// Scroll the log pane so `line` of the logs is at the top. Wrapped lines above it push it down a bit. Unwrapped,
+  /// it's also scrolled sideways if that's what it takes to show the line's first search match
+  fn scroll_to_log_line(&mut self, line: Option<usize>) {
+    let Some(line) = line else { return };
+    self.logs_scroll_offset = self.logs.len().saturating_sub(line + 1) as u16;
+    if self.log_wrap {
+      return;
+    }
+    let text = self.logs[line].text.as_str();
+    let message = log_message(text);
+    let Some(&(start, _)) = self.log_search.ranges(message).first() else { return };
+    // the timestamp is shown differently from how it's written
+    let shown_timestamp = text
+      .split_once(' ')
+      .and_then(|(timestamp, _)| format_log_timestamp(timestamp, self.log_timestamps, Local::now()))
+      .filter(|t| !t.is_empty());
+    let prefix = shown_timestamp.map_or(0, |t| t.chars().count() + 1);
+    let column = (prefix + message[..start].chars().count()) as u16;
+    if column < self.log_hscroll || column >= self.log_hscroll + self.log_page_width {
+      self.log_hscroll = column.saturating_sub(LOG_HSCROLL_STEP);
+    }
+  }
+
+  fn toggle_log_wrap(&mut self) {
+    self.log_wrap = !self.log_wrap;
+    self.log_hscroll = 0;
+  }
+
+  pub fn toggle_log_current_run(&mut self) {
+    self.log_current_run = !self.log_current_run;
+    self.logs_scroll_offset = 0;
+    self.get_logs();
+  }
+
+  pub fn cycle_unit_type(&mut self) {
+    let loaded = self.all_units.values().map(|u| unit_types::unit_type(&u.name));
+    self.type_filter = unit_types::next_type_filter(self.type_filter, loaded);
+    self.refresh_filtered_units();
+  }
+
+  /// By name, then by state, then by each resource column in turn
+  pub fn cycle_sort(&mut self) {
+    self.sort = self.sort.next();
+    self.refresh_filtered_units();
+    self.refresh_accounting();
+  }
+
+  /// Go to the view picked with `startup-view` or `--view`
+  fn open_startup_view(&mut self, view: StartupView) -> Option<Action> {
+    match view {
+      StartupView::List => {},
+      StartupView::Failed => self.failed_only = true,
+      StartupView::Search(query) => self.input = Input::new(query),
+      StartupView::Top => {
+        self.sort = SortOrder::CpuPercent;
+        self.refresh_accounting();
+      },
+      StartupView::Logs(name) => {
+        // prefer the system unit when both managers have one by this name
+        let Some(unit) =
+          self.all_units.keys().filter(|id| id.name == name).min_by_key(|id| id.scope != UnitScope::Global).cloned()
+        else {
+          return Some(Action::EnterError(format!("{name} isn't in the unit list")));
+        };
+        let _ = self.dispatch(Action::SelectUnit(unit));
+        return Some(Action::EnterMode(Mode::Logs));
+      },
+    }
+    self.refresh_filtered_units();
+    Some(Action::Render)
+  }
+
+  /// `matching docker* in state failed`, while `--filter` or `--state` narrow the list
+  fn command_line_filter(&self) -> Option<String> {
+    let mut parts = vec![];
+    if let Some(pattern) = &self.name_filter {
+      parts.push(format!("matching {pattern}"));
     }
+    if !self.state_filter.is_empty() {
+      parts.push(format!("in state {}", self.state_filter.join(" or ")));
+    }
+    (!parts.is_empty()).then(|| parts.join(" "))
+  }
+
+  /// The configured list columns, and the one being sorted by if it isn't one of them
+  fn list_columns(&self) -> Vec<ListColumn> {
+    let mut columns = self.config.list_columns.clone();
+    columns.extend(self.sort.column().filter(|c| !columns.contains(c)));
+    if !self.config.health_checks.is_empty() && !columns.contains(&ListColumn::Health) {
+      columns.push(ListColumn::Health);
+    }
+    columns
   }
 
   fn refresh_filtered_units(&mut self) {
     let previously_selected = self.selected_service();
-    let search_value_lower = self.input.value().to_lowercase();
-    // TODO: use fuzzy find
-    let matching = self
-      .all_units
-      .values()
-      .filter(|u| u.short_name().to_lowercase().contains(&search_value_lower))
-      .cloned()
-      .collect_vec();
-    self.filtered_units.items = matching;
+    let matching = match parse_property_query(self.input.value()) {
+      Some(filters) => {
+        let missing = filters
+          .iter()
+          .map(|f| f.property.clone())
+          .filter(|p| !self.property_values.contains_key(p))
+          .unique()
+          .collect_vec();
+        if !missing.is_empty() {
+          self.fetch_property_values(missing);
+        }
+        self
+          .all_units
+          .values()
+          .filter(|u| {
+            filters.iter().all(|f| {
+              let value = self.property_values.get(&f.property).and_then(|values| values.get(&u.id()));
+              value.is_some_and(|v| f.matches(v))
+            })
+          })
+          .cloned()
+          .collect_vec()
+      },
+      None => {
+        // half-typed terms like `Restart=always U` don't need everything fetched again
+        if !self.input.value().contains('=') {
+          self.property_values.clear();
+        }
+        let ids = match self.search_index.cached(self.input.value()) {
+          Some(ids) => ids,
+          None if self.search_index.len() >= search::BACKGROUND_UNITS && self.action_tx.is_some() => {
+            // the list stays as it is until the results come back
+            self.search_in_background();
+            return;
+          },
+          None => self.search_index.search(self.input.value()),
+        };
+        ids.into_iter().filter_map(|id| self.all_units.get(id)).cloned().collect_vec()
+      },
+    };
+    let (type_filter, failed_only) = (self.type_filter, self.failed_only);
+    self.filtered_units.items = matching
+      .into_iter()
+      .filter(|u| type_filter.is_none_or(|t| unit_types::unit_type(&u.name) == t))
+      .filter(|u| !failed_only || u.is_failed())
+      .filter(|u| self.name_filter.as_ref().is_none_or(|p| search::name_matches(p, &u.name)))
+      .filter(|u| self.state_filter.is_empty() || self.state_filter.iter().any(|s| cli::in_state(u, s)))
+      .collect();
+    if let Some(column) = self.sort.column() {
+      let key =
+        |u: &UnitWithStatus| sort_value(column, self.accounting.get(&u.id()), self.rates.get(&u.id())).unwrap_or(-1.0);
+      self.filtered_units.items.sort_by(|a, b| key(b).total_cmp(&key(a)));
+    } else if self.sort != SortOrder::Name {
+      self.filtered_units.items.sort_by_key(|u| state_rank(self.sort, u));
+    }
+    // a stable sort, so favorites keep the order they'd otherwise be in
+    self.filtered_units.items.sort_by_key(|u| !self.favorites.contains(&u.id()));
 
     // try to select the same item we had selected before
     // TODO: this is horrible, clean it up
@@ -311,6 +1430,84 @@ impl Home {
     }
   }
 
+  /// Search the unit list on a blocking task, so typing doesn't wait on thousands of units being checked
+  fn search_in_background(&mut self) {
+    let Some(job) = self.search_index.job(self.input.value()) else { return };
+    let tx = self.action_tx.clone().unwrap();
+    tokio::task::spawn_blocking(move || {
+      if let Some(result) = job.run() {
+        let _ = tx.send(Action::SetSearchResults(result));
+        let _ = tx.send(Action::Render);
+      }
+    });
+  }
+
+  /// A plain-text description of what currently has focus, for screen readers to pick up in accessible mode
+  fn accessible_status(&self) -> String {
+    let selected = self.filtered_units.selected().map(|u| {
+      let position = self.filtered_units.state.selected().unwrap_or(0) + 1;
+      let looping = self.accounting.get(&u.id()).is_some_and(|a| a.crash_looping(&u.sub_state));
+      format!(
+        "{}: {}, {} ({}){}, {} of {}",
+        u.name,
+        u.load_state,
+        u.activation_state,
+        u.sub_state,
+        if looping { ", crash-looping" } else { "" },
+        position,
+        self.filtered_units.items.len()
+      )
+    });
+    let selected = selected.unwrap_or_else(|| "No unit selected".into());
+
+    match self.mode {
+      Mode::Search => format!("Search \"{}\". {selected}", self.input.value()),
+      Mode::ServiceList => selected,
+      Mode::Logs => match self.log_search.position() {
+        Some(position) => format!("Logs, match {position} of {}", self.log_search.match_count()),
+        None => {
+          let key = |command| self.keymap.describe(command).unwrap_or_else(|| "unbound".into());
+          format!(
+            "Logs. Press {} to search, {} to copy them, {} to save them to a file, {} to go back",
+            key(Command::LogSearch),
+            key(Command::LogCopy),
+            key(Command::LogSave),
+            key(Command::LeaveLogs)
+          )
+        },
+      },
+      Mode::LogSearch => {
+        format!("Search logs \"{}\", {} matches", self.log_search.query, self.log_search.match_count())
+      },
+      Mode::CommandBar => format!("Command: {}", self.command_input.value()),
+      Mode::Help => "Help open. Press Escape to close".into(),
+      Mode::ActionMenu | Mode::SignalMenu | Mode::RunMenu | Mode::Prompt => match self.menu_items.selected() {
+        Some(item) => format!("Menu item: {}, key {}", item.name, item.key_string()),
+        None => "Menu open".into(),
+      },
+      Mode::Processing => "Working. Press Escape to cancel".into(),
+      Mode::Form => format!(
+        "{}, editing {}: {}",
+        self.form.title,
+        self.form.focused_label(),
+        self.form.value(self.form.focused_label())
+      ),
+      Mode::Viewer => format!(
+        "{}, line {} of {}. Press Escape to close",
+        self.viewer.title,
+        self.viewer.scroll + 1,
+        self.viewer.lines.len()
+      ),
+      Mode::DepTree => format!("Dependency tree: {}. Press Escape to close", self.dep_tree.selected_description()),
+      Mode::MergedLogs => format!("{}. Press Escape to close", self.merged_logs.description()),
+      Mode::Jobs => format!("Job queue: {}. Press c to cancel, Escape to close", self.job_queue.selected_description()),
+      Mode::Properties => {
+        format!("Properties: {}. Press Escape to close", self.property_inspector.selected_description())
+      },
+      Mode::Error => format!("Error: {}", self.error_message.replace('\n', " ")),
+    }
+  }
+
   fn start_service(&mut self, service: UnitId) {
     let cancel_token = CancellationToken::new();
     let future = systemd::start_service(service.clone(), cancel_token.clone());
@@ -335,17 +1532,42 @@ impl Home {
     self.service_action(service, "Restart".into(), cancel_token, future);
   }
 
+  /// daemon-reload, then restart the unit so it runs with the new configuration
+  fn reload_and_restart(&mut self, service: UnitId) {
+    let cancel_token = CancellationToken::new();
+    let (unit, token) = (service.clone(), cancel_token.clone());
+    let future = async move {
+      systemd::reload(unit.scope, token.clone()).await?;
+      systemd::restart_service(unit, token).await
+    };
+    self.service_action(service, "Reload and restart".into(), cancel_token, future);
+  }
+
   fn service_action<Fut>(&mut self, service: UnitId, action_name: String, cancel_token: CancellationToken, action: Fut)
   where
     Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
   {
     let tx = self.action_tx.clone().unwrap();
+    // acting on a unit in the dependency tree goes back to the tree, and cancelling a job to the queue
+    let done_mode = match self.mode {
+      Mode::DepTree | Mode::Jobs => self.mode,
+      _ => Mode::ServiceList,
+    };
+    // the job just queued may take a while, and the details pane says what it's waiting for
+    self.watch_jobs(self.mode == Mode::Jobs);
 
     self.cancel_token = Some(cancel_token.clone());
+    // what to offer to run with more privileges if the manager refuses
+    let retry = self
+      .last_equivalent
+      .as_ref()
+      .filter(|(equivalent, dry_run)| !dry_run && equivalent.unit == service)
+      .map(|(equivalent, _)| equivalent.command.clone())
+      .filter(|_| !escalation::methods(service.scope).is_empty());
 
     let tx_clone = tx.clone();
     let spinner_task = tokio::spawn(async move {
-      let mut interval = tokio::time::interval(Duration::from_millis(200));
+      let mut interval = tokio::time::interval(SPINNER_INTERVAL);
       loop {
         interval.tick().await;
         tx_clone.send(Action::SpinnerTick).unwrap();
@@ -354,10 +1576,20 @@ impl Home {
 
     tokio::spawn(async move {
       tx.send(Action::EnterMode(Mode::Processing)).unwrap();
-      match action.await {
+      let result = action.await;
+      let outcome = match &result {
+        Ok(_) => "ok".to_string(),
+        Err(_) if cancel_token.is_cancelled() => "cancelled".to_string(),
+        Err(e) => format!("failed: {e}"),
+      };
+      record_action(action_log::Entry::new(service.scope, &service.name, &action_name, &outcome));
+      if !cancel_token.is_cancelled() {
+        let _ = tx.send(Action::ServiceActionDone(service.clone()));
+      }
+      match result {
         Ok(_) => {
           info!("{} of {:?} service {} succeeded", action_name, service.scope, service.name);
-          tx.send(Action::EnterMode(Mode::ServiceList)).unwrap();
+          tx.send(Action::EnterMode(done_mode)).unwrap();
         },
         // would be nicer to check the error type here, but this is easier
         Err(_) if cancel_token.is_cancelled() => {
@@ -367,13 +1599,18 @@ impl Home {
           error!("{} of {:?} service {} failed: {}", action_name, service.scope, service.name, e);
           let mut error_string = e.to_string();
 
-          if error_string.contains("AccessDenied") {
-            error_string.push('\n');
-            error_string.push('\n');
-            error_string.push_str("Try running this tool with sudo.");
+          if escalation::is_permission_error(&error_string) {
+            if let Some(command) = retry {
+              tx.send(Action::OfferEscalation { unit: service.clone(), error: error_string, command }).unwrap();
+            } else {
+              error_string.push('\n');
+              error_string.push('\n');
+              error_string.push_str("Try running this tool with sudo.");
+              tx.send(Action::EnterError(error_string)).unwrap();
+            }
+          } else {
+            tx.send(Action::EnterError(error_string)).unwrap();
           }
-
-          tx.send(Action::EnterError(error_string)).unwrap();
         },
       }
       spinner_task.abort();
@@ -387,792 +1624,7749 @@ impl Home {
     });
   }
 
-  fn kill_service(&mut self, service: UnitId, signal: String) {
-    let cancel_token = CancellationToken::new();
-    let future = systemd::kill_service(service.clone(), signal.clone(), cancel_token.clone());
-    self.service_action(service, format!("Kill with {}", signal), cancel_token, future);
+  /// Whether the unit's processes are paused, as of the last accounting refresh
+  fn is_frozen(&self, unit: &UnitId) -> bool {
+    self.accounting.get(unit).is_some_and(|a| a.frozen)
   }
-}
-
-impl Component for Home {
-  fn init(&mut self, tx: UnboundedSender<Action>) -> anyhow::Result<()> {
-    self.action_tx = Some(tx.clone());
-    // TODO find a better name for these. They're used to run any async data loading that needs to happen after the selection is changed,
-    // not just journalctl stuff
-    let (journalctl_tx, journalctl_rx) = std::sync::mpsc::channel::<UnitId>();
-    self.journalctl_tx = Some(journalctl_tx);
 
-    // TODO: move into function
-    tokio::task::spawn_blocking(move || {
-      let mut last_follow_handle: Option<JoinHandle<()>> = None;
+  /// What `action` does and the units it does it to that are in `confirm-units`, each with the pattern it matched, if
+  /// it stops, restarts, kills or masks any
+  fn protected_units(&self, action: &Action) -> Option<(String, Vec<(String, String)>)> {
+    let (verb, names) = destructive_verb(action)?;
+    let units = names
+      .into_iter()
+      .filter_map(|name| Some((name.to_string(), self.config.confirm_pattern(name)?.to_string())))
+      .collect_vec();
+    (!units.is_empty()).then_some((verb, units))
+  }
 
+  /// Poll the job queues until they're empty, or for as long as `keep_polling`
+  fn watch_jobs(&mut self, keep_polling: bool) {
+    if let Some(watch) = self.job_watch.take() {
+      watch.abort();
+    }
+    let tx = self.action_tx.clone().unwrap();
+    let scopes = self.unit_scopes();
+    self.job_watch = Some(tokio::spawn(async move {
+      // an action's job is only queued once the call goes through, so an empty queue at first doesn't mean it's done
+      let mut polls = 0;
       loop {
-        let mut unit: UnitId = match journalctl_rx.recv() {
-          Ok(unit) => unit,
-          Err(_) => return,
-        };
-
-        // drain the channel, use the last value
-        while let Ok(service) = journalctl_rx.try_recv() {
-          info!("Skipping logs for {}...", unit.name);
-          unit = service;
+        let mut jobs = vec![];
+        let mut errors = vec![];
+        for scope in &scopes {
+          match systemd::get_jobs(*scope).await {
+            Ok(queue) => jobs.extend(queue),
+            Err(e) => errors.push(format!("{e:#}")),
+          }
         }
-
-        if let Some(handle) = last_follow_handle.take() {
-          info!("Cancelling previous journalctl task");
-          handle.abort();
+        let done = !keep_polling && jobs.is_empty() && polls >= 2;
+        // e.g. root has no user manager to ask, which doesn't matter if the system manager answered
+        let jobs = if errors.len() == scopes.len() { Err(errors.join("\n")) } else { Ok(jobs) };
+        if tx.send(Action::SetJobs(jobs)).is_err() || done {
+          break;
         }
+        polls += 1;
+        tokio::time::sleep(JOB_POLL_INTERVAL).await;
+      }
+    }));
+  }
 
-        // lazy debounce to avoid spamming journalctl on slow connections/systems
-        std::thread::sleep(Duration::from_millis(100));
+  fn open_form(&mut self, mut form: Form) -> Option<Action> {
+    form.accessible = self.config.accessible;
+    form.accent = self.theme.accent;
+    self.form = form;
+    self.mode = Mode::Form;
+    Some(Action::Render)
+  }
 
-        // get the unit file path
-        match systemd::get_unit_file_location(&unit) {
-          Ok(path) => {
-            let _ = tx.send(Action::SetUnitFilePath { unit: unit.clone(), path: Ok(path) });
-            let _ = tx.send(Action::Render);
-          },
-          Err(e) => {
-            // Fix this!!! Set the path to an error enum variant instead of a string
-            let _ =
-              tx.send(Action::SetUnitFilePath { unit: unit.clone(), path: Err("could not be determined".into()) });
-            let _ = tx.send(Action::Render);
-            error!("Error getting unit file path for {}: {}", unit.name, e);
-          },
+  /// Keep the unit list current from the manager's signals. Until that's working (or if it stops), the list is polled
+  fn watch_units(&mut self) {
+    if let Some(watch) = self.unit_watch.take() {
+      watch.abort();
+    }
+    self.live_scopes.clear();
+    let tx = self.action_tx.clone().unwrap();
+    let scopes = self.unit_scopes();
+    let patterns = self.limit_units.clone();
+    self.unit_watch = Some(tokio::spawn(async move {
+      let watches = scopes.into_iter().map(|scope| {
+        let (tx, patterns) = (tx.clone(), patterns.clone());
+        async move {
+          let mut watch = match systemd::UnitWatch::new(scope, &patterns).await {
+            Ok(watch) => watch,
+            Err(e) => return warn!("Not watching {scope:?} units for changes, polling instead: {e:#}"),
+          };
+          let _ = tx.send(Action::SetLiveUpdates { scope, live: true });
+          loop {
+            match watch.next().await {
+              Ok(units) => {
+                let _ = tx.send(Action::UnitsUpdated(units));
+              },
+              Err(e) => {
+                warn!("Stopped watching {scope:?} units for changes, polling instead: {e:#}");
+                break;
+              },
+            }
+          }
+          let _ = tx.send(Action::SetLiveUpdates { scope, live: false });
         }
+      });
+      futures::future::join_all(watches).await;
+    }));
+  }
 
-        // First, get the N lines in a batch
-        info!("Getting logs for {}", unit.name);
-        let start = std::time::Instant::now();
+  /// Point everything at the host in the switcher and start over with its units
+  fn switch_host(&mut self) -> Option<Action> {
+    let host = match remote::parse_host(self.form.value("Host")) {
+      Ok(host) => host,
+      Err(e) => return Some(Action::EnterError(format!("{e:#}"))),
+    };
+    self.mode = Mode::ServiceList;
+    if host == remote::host() {
+      return Some(Action::Render);
+    }
+    info!("Switching to {}", host.as_deref().unwrap_or("this machine"));
+    // a container is only reachable from the machine it runs on
+    if host.is_some() {
+      machine::set_machine(None);
+    }
+    remote::set_host(host);
+    // nothing known about the previous machine's units applies to this one
+    self.forget_units()
+  }
 
-        let mut args = vec!["--quiet", "--output=short-iso", "--lines=500", "-u"];
+  /// Point everything at a container's manager, or back at this machine's, and start over with its units
+  fn switch_machine(&mut self, name: Option<String>) -> Option<Action> {
+    self.mode = Mode::ServiceList;
+    if name == machine::machine() {
+      return Some(Action::Render);
+    }
+    info!("Switching to {}", name.as_deref().unwrap_or("this machine"));
+    machine::set_machine(name);
+    self.forget_units()
+  }
 
-        args.push(&unit.name);
+  fn set_theme(&mut self, theme: Theme) {
+    self.theme = theme;
+    self.logger.theme = theme;
+    self.monochrome = !monochrome::color_enabled() || theme == Theme::MONOCHROME;
+  }
 
-        if unit.scope == UnitScope::User {
-          args.push("--user");
-        }
+  fn switch_user(&mut self, user: Option<user_manager::User>) -> Option<Action> {
+    self.mode = Mode::ServiceList;
+    if user == user_manager::user() {
+      return Some(Action::Render);
+    }
+    info!("Showing user units of {}", user.as_ref().map_or("ourselves", |u| u.name.as_str()));
+    user_manager::set_user(user);
+    if matches!(self.scope, Scope::Global) {
+      self.scope = Scope::All;
+    }
+    self.forget_units()
+  }
 
-        match Command::new("journalctl").args(&args).output() {
-          Ok(output) => {
-            if output.status.success() {
-              info!("Got logs for {} in {:?}", unit.name, start.elapsed());
-              if let Ok(stdout) = std::str::from_utf8(&output.stdout) {
-                let mut logs = stdout.trim().split('\n').map(String::from).collect_vec();
+  /// Show system and user units, then only system units, then only user units, and start over with them
+  fn cycle_scope(&mut self) -> Option<Action> {
+    if remote::elsewhere().is_some() {
+      return Some(Action::EnterError("Only system units can be shown on another machine or in a container".into()));
+    }
+    self.scope = match self.scope {
+      Scope::All => Scope::Global,
+      Scope::Global => Scope::User,
+      Scope::User => Scope::All,
+    };
+    info!("Showing {} units", scope_label(self.scope));
+    self.forget_units()
+  }
 
-                if logs.is_empty() || logs[0].is_empty() {
-                  logs.push(String::from("No logs found/available. Maybe try relaunching with `sudo systemctl-tui`"));
-                }
-                let _ = tx.send(Action::SetLogs { unit: unit.clone(), logs });
-                let _ = tx.send(Action::Render);
-              } else {
-                warn!("Error parsing stdout for {}", unit.name);
-              }
-            } else {
-              warn!("Error getting logs for {}: {}", unit.name, String::from_utf8_lossy(&output.stderr));
-            }
-          },
-          Err(e) => warn!("Error getting logs for {}: {}", unit.name, e),
-        }
+  /// Drop everything about the units being shown and start over, after switching to another machine or user
+  fn forget_units(&mut self) -> Option<Action> {
+    self.all_units.clear();
+    self.health.clear();
+    self.health_started.clear();
+    self.search_index.clear();
+    self.accounting.clear();
+    self.timers.clear();
+    self.documentation.clear();
+    self.schedules.clear();
+    self.pressure.clear();
+    self.property_values.clear();
+    self.journal_usage.clear();
+    self.marked.clear();
+    self.session = SessionStats::default();
+    self.logs = vec![];
+    self.log_minutes.clear();
+    self.rates.clear();
+    self.history.clear();
+    self.refresh_filtered_units();
+    self.watch_units();
+    Some(Action::RefreshServices)
+  }
 
-        // Then follow the logs
-        // Splitting this into two commands is a bit of a hack that makes it easier to get the initial batch of logs
-        // This does mean that we'll miss any logs that are written between the two commands, low enough risk for now
-        let tx = tx.clone();
-        last_follow_handle = Some(tokio::spawn(async move {
-          let mut command = tokio::process::Command::new("journalctl");
-          command.arg("-u");
-          command.arg(unit.name.clone());
-          command.arg("--output=short-iso");
-          command.arg("--follow");
-          command.arg("--lines=0");
-          command.arg("--quiet");
-          command.stdout(Stdio::piped());
-          command.stderr(Stdio::piped());
-
-          if unit.scope == UnitScope::User {
-            command.arg("--user");
-          }
+  /// Start the health checks that are due, for every listed unit they cover
+  fn run_health_checks(&mut self) {
+    let Some(tx) = self.action_tx.clone() else { return };
+    for unit in self.all_units.keys() {
+      for (index, check) in self.config.health_checks_for(&unit.name) {
+        // fetching a URL only looks, but a command could do anything
+        if self.read_only && check.url.is_none() {
+          continue;
+        }
+        let key = (index, unit.clone());
+        // a check still running when the next is due isn't started twice
+        let wait = Duration::from_secs(check.interval.max(check.timeout));
+        if self.health_started.get(&key).is_some_and(|started| started.elapsed() < wait) {
+          continue;
+        }
+        self.health_started.insert(key, Instant::now());
+        let (check, unit, tx) = (check.clone(), unit.clone(), tx.clone());
+        tokio::task::spawn_blocking(move || {
+          let outcome = health::probe(&check, &unit);
+          let _ = tx.send(Action::SetHealth { check: index, unit, outcome });
+        });
+      }
+    }
+  }
 
-          let mut child = command.spawn().expect("failed to execute process");
+  fn spinner_char(&self) -> char {
+    SPINNER_CHARS[self.spinner_tick as usize % SPINNER_CHARS.len()]
+  }
 
-          let stdout = child.stdout.take().unwrap();
+  /// How `unit`'s health checks went: the failing one if any, else the latest. `None` before any have finished
+  fn health_of(&self, unit: &UnitId) -> Option<&health::Outcome> {
+    health::worst(self.health.iter().filter(|((_, u), _)| u == unit).map(|(_, outcome)| outcome))
+  }
 
-          let reader = tokio::io::BufReader::new(stdout);
-          let mut lines = reader.lines();
-          while let Some(line) = lines.next_line().await.unwrap() {
-            let _ = tx.send(Action::AppendLogLine { unit: unit.clone(), line });
-            let _ = tx.send(Action::Render);
-          }
-        }));
-      }
+  /// Re-read the processes the viewer is showing, if it's showing them
+  fn refresh_processes(&self) {
+    let Some((unit, _)) = self.process_view.as_ref().filter(|_| self.mode == Mode::Viewer) else { return };
+    let unit = unit.clone();
+    let tx = self.action_tx.clone().unwrap();
+    tokio::task::spawn_blocking(move || {
+      let processes = systemd::get_unit_properties(&unit, &["ControlGroup"])
+        .and_then(|properties| match properties.get("ControlGroup").filter(|c| !c.is_empty()) {
+          Some(control_group) => process_tree::read(control_group),
+          None => anyhow::bail!("{} isn't running, so it has no processes", unit.name),
+        })
+        .map_err(|e| format!("{e:#}"));
+      let _ = tx.send(Action::SetProcesses { unit, processes });
     });
-    Ok(())
   }
 
-  fn handle_key_events(&mut self, key: KeyEvent) -> Vec<Action> {
-    if key.modifiers.contains(KeyModifiers::CONTROL) {
-      match key.code {
-        KeyCode::Char('c') => return vec![Action::Quit],
-        KeyCode::Char('q') => return vec![Action::Quit],
-        KeyCode::Char('z') => return vec![Action::Suspend],
-        KeyCode::Char('f') => return vec![Action::EnterMode(Mode::Search)],
-        KeyCode::Char('l') => return vec![Action::ToggleShowLogger],
-        // vim keybindings, apparently
-        KeyCode::Char('d') => return vec![Action::ScrollDown(1), Action::Render],
-        KeyCode::Char('u') => return vec![Action::ScrollUp(1), Action::Render],
-        _ => (),
+  /// Re-read accounting for every unit if the list shows it, otherwise just for the selected unit
+  fn refresh_accounting(&self) {
+    let tx = self.action_tx.clone().unwrap();
+    // the health column comes from the checks, not accounting
+    let needs_all = self.list_columns().iter().any(|c| *c != ListColumn::Health);
+    let scopes = if needs_all { self.unit_scopes() } else { vec![] };
+    let shows_timers =
+      self.config.list_columns.iter().any(|c| matches!(c, ListColumn::NextElapse | ListColumn::LastTrigger));
+    let timer_scopes = if shows_timers { self.unit_scopes() } else { vec![] };
+    let file_state_scopes =
+      if self.list_columns().contains(&ListColumn::FileState) { self.unit_scopes() } else { vec![] };
+    let selected = self.selected_service();
+    tokio::task::spawn_blocking(move || {
+      let mut accounting = vec![];
+      for scope in scopes {
+        match systemd::get_all_accounting(scope) {
+          Ok(all) => accounting.extend(all.into_iter().map(|(name, a)| (UnitId { name, scope }, a))),
+          Err(e) => warn!("Error getting accounting: {}", e),
+        }
       }
-    }
-
-    if matches!(key.code, KeyCode::Char('?')) || matches!(key.code, KeyCode::F(1)) {
-      return vec![Action::ToggleHelp, Action::Render];
-    }
+      if let Some(unit) = selected.filter(|u| !accounting.iter().any(|(id, _)| id == u)) {
+        if let Ok(properties) = systemd::get_unit_properties(&unit, systemd::ACCOUNTING_PROPERTIES) {
+          accounting.push((unit, Accounting::from_properties(&properties)));
+        }
+      }
+      let _ = tx.send(Action::SetAccounting(accounting));
 
-    // TODO: seems like terminals can't recognize shift or ctrl at the same time as page up/down
-    // Is there another way we could scroll in large increments?
-    match key.code {
-      KeyCode::PageDown => return vec![Action::ScrollDown(1), Action::Render],
-      KeyCode::PageUp => return vec![Action::ScrollUp(1), Action::Render],
-      KeyCode::Home => return vec![Action::ScrollToTop, Action::Render],
-      KeyCode::End => return vec![Action::ScrollToBottom, Action::Render],
-      _ => (),
-    }
+      let mut timers = vec![];
+      for scope in timer_scopes {
+        match systemd::get_all_timers(scope) {
+          Ok(all) => timers.extend(all.into_iter().map(|(name, t)| (UnitId { name, scope }, t))),
+          Err(e) => warn!("Error getting timers: {}", e),
+        }
+      }
+      if !timers.is_empty() {
+        let _ = tx.send(Action::SetTimers(timers));
+      }
 
-    match self.mode {
-      Mode::ServiceList => {
-        match key.code {
-          KeyCode::Char('q') => vec![Action::Quit],
-          KeyCode::Up | KeyCode::Char('k') => {
-            // if we're filtering the list, and we're at the top, and there's text in the search box, go to search mode
-            if self.filtered_units.state.selected() == Some(0) {
-              return vec![Action::EnterMode(Mode::Search)];
-            }
+      let mut file_states = vec![];
+      for scope in file_state_scopes {
+        match systemd::get_all_unit_file_states(scope) {
+          Ok(all) => file_states.extend(all.into_iter().map(|(name, s)| (UnitId { name, scope }, s))),
+          Err(e) => warn!("Error getting unit file states: {}", e),
+        }
+      }
+      if !file_states.is_empty() {
+        let _ = tx.send(Action::SetFileStates(file_states));
+      }
+    });
+  }
 
-            self.previous();
-            vec![Action::Render]
-          },
-          KeyCode::Down | KeyCode::Char('j') => {
-            self.next();
-            vec![Action::Render]
-          },
-          KeyCode::Char('/') => vec![Action::EnterMode(Mode::Search)],
-          KeyCode::Char('e') => {
-            if let Some(selected) = self.filtered_units.selected() {
-              if let Some(Ok(file_path)) = &selected.file_path {
-                return vec![Action::EditUnitFile { unit: selected.id(), path: file_path.clone() }];
+  /// Read `properties` of every unit for a property query; the list refreshes when they arrive
+  fn fetch_property_values(&mut self, properties: Vec<String>) {
+    // an empty entry marks the fetch as in flight so each keystroke doesn't start another
+    for property in &properties {
+      self.property_values.insert(property.clone(), HashMap::new());
+    }
+    let tx = self.action_tx.clone().unwrap();
+    let scopes = self.unit_scopes();
+    tokio::task::spawn_blocking(move || {
+      let mut names = vec!["Id"];
+      names.extend(properties.iter().flat_map(|p| search::source_properties(p)).unique());
+      let mut values: HashMap<String, HashMap<UnitId, String>> = HashMap::new();
+      for scope in scopes {
+        match systemd::get_all_unit_properties(scope, &names) {
+          Ok(units) => {
+            for unit in units {
+              let Some(id) = unit.get("Id") else { continue };
+              for property in &properties {
+                let value = search::property_value(property, &unit);
+                values.entry(property.clone()).or_default().insert(UnitId { name: id.clone(), scope }, value);
               }
             }
-            vec![]
           },
-          KeyCode::Enter | KeyCode::Char(' ') => vec![Action::EnterMode(Mode::ActionMenu)],
-          _ => vec![],
+          Err(e) => warn!("Error getting {} for a property search: {}", properties.join(", "), e),
         }
-      },
-      Mode::Help => match key.code {
-        KeyCode::Esc | KeyCode::Enter => vec![Action::ToggleHelp],
-        _ => vec![],
-      },
-      Mode::Error => match key.code {
-        KeyCode::Esc | KeyCode::Enter => vec![Action::EnterMode(Mode::ServiceList)],
-        _ => vec![],
-      },
-      Mode::Search => match key.code {
-        KeyCode::Esc => vec![Action::EnterMode(Mode::ServiceList)],
-        KeyCode::Enter => vec![Action::EnterMode(Mode::ActionMenu)],
-        KeyCode::Down | KeyCode::Tab => {
-          self.next();
-          vec![Action::EnterMode(Mode::ServiceList)]
-        },
-        KeyCode::Up => {
-          self.previous();
-          vec![Action::EnterMode(Mode::ServiceList)]
-        },
-        _ => {
-          let prev_search_value = self.input.value().to_owned();
-          self.input.handle_event(&crossterm::event::Event::Key(key));
+      }
+      let _ = tx.send(Action::SetPropertyValues(values));
+    });
+  }
 
-          // if the search value changed, filter the list
-          if prev_search_value != self.input.value() {
-            self.refresh_filtered_units();
-          }
-          vec![Action::Render]
-        },
-      },
-      Mode::ActionMenu => match key.code {
-        KeyCode::Esc => vec![Action::EnterMode(Mode::ServiceList)],
-        KeyCode::Down | KeyCode::Char('j') => {
-          self.menu_items.next();
-          vec![Action::Render]
-        },
-        KeyCode::Up | KeyCode::Char('k') => {
-          self.menu_items.previous();
-          vec![Action::Render]
-        },
-        KeyCode::Enter | KeyCode::Char(' ') => match self.menu_items.selected() {
-          Some(i) => vec![i.action.clone()],
-          None => vec![Action::EnterMode(Mode::ServiceList)],
-        },
-        _ => {
-          for item in self.menu_items.items.iter() {
-            if let Some(key_code) = item.key {
-              if key_code == key.code {
-                return vec![item.action.clone()];
+  /// Re-read every unit's pressure, and while at it which units need a daemon-reload
+  fn refresh_pressure(&self) {
+    let tx = self.action_tx.clone().unwrap();
+    let scopes = self.unit_scopes();
+    tokio::task::spawn_blocking(move || {
+      let mut pressure = vec![];
+      let mut stale = vec![];
+      for scope in scopes {
+        match systemd::get_all_unit_properties(scope, &["ControlGroup", "NeedDaemonReload"]) {
+          Ok(units) => {
+            for properties in units {
+              let Some(name) = properties.get("Id") else { continue };
+              let unit = UnitId { name: name.clone(), scope };
+              if properties.get("NeedDaemonReload").is_some_and(|n| n == "yes") {
+                stale.push(unit.clone());
               }
-            }
-          }
-          vec![]
-        },
-      },
-      Mode::Processing => match key.code {
-        KeyCode::Esc => vec![Action::CancelTask],
-        _ => vec![],
-      },
-      Mode::SignalMenu => match key.code {
-        KeyCode::Esc => vec![Action::EnterMode(Mode::ServiceList)],
-        KeyCode::Down | KeyCode::Char('j') => {
-          self.menu_items.next();
-          vec![Action::Render]
-        },
-        KeyCode::Up | KeyCode::Char('k') => {
-          self.menu_items.previous();
-          vec![Action::Render]
-        },
-        KeyCode::Enter | KeyCode::Char(' ') => match self.menu_items.selected() {
-          Some(i) => vec![i.action.clone()],
-          None => vec![Action::EnterMode(Mode::ServiceList)],
-        },
-        _ => {
-          for item in self.menu_items.items.iter() {
-            if let Some(key_code) = item.key {
-              if key_code == key.code {
-                return vec![item.action.clone()];
+              if let Some(p) = properties.get("ControlGroup").and_then(|c| pressure::read_pressure(c)) {
+                pressure.push((unit, p));
               }
             }
-          }
-          vec![]
-        },
-      },
+          },
+          Err(e) => warn!("Error getting control groups: {}", e),
+        }
+      }
+      let _ = tx.send(Action::SetPressure(pressure));
+      let _ = tx.send(Action::SetStaleUnits(stale));
+    });
+  }
+
+  fn unit_scopes(&self) -> Vec<UnitScope> {
+    // there's no reaching a user manager on another machine or in a container
+    if remote::elsewhere().is_some() {
+      return vec![UnitScope::Global];
+    }
+    match self.scope {
+      Scope::Global => vec![UnitScope::Global],
+      Scope::User => vec![UnitScope::User],
+      Scope::All => vec![UnitScope::Global, UnitScope::User],
     }
   }
 
-  fn dispatch(&mut self, action: Action) -> Option<Action> {
-    match action {
-      Action::ToggleShowLogger => {
-        self.show_logger = !self.show_logger;
-        return Some(Action::Render);
-      },
-      Action::EnterMode(mode) => {
-        if mode == Mode::ActionMenu {
-          if let Some(selected) = self.filtered_units.selected() {
-            let mut menu_items = vec![
-              MenuItem::new("Start", Action::StartService(selected.id()), Some(KeyCode::Char('s'))),
-              MenuItem::new("Stop", Action::StopService(selected.id()), Some(KeyCode::Char('t'))),
-              MenuItem::new("Restart", Action::RestartService(selected.id()), Some(KeyCode::Char('r'))),
-              MenuItem::new("Reload", Action::ReloadService(selected.id()), Some(KeyCode::Char('l'))),
-              MenuItem::new("Kill", Action::EnterMode(Mode::SignalMenu), Some(KeyCode::Char('k'))),
-              // TODO add these
-              // MenuItem::new("Enable", Action::EnableService(selected.clone())),
-              // MenuItem::new("Disable", Action::DisableService(selected.clone())),
-            ];
+  /// Every loaded unit's name, for completing unit name fields
+  fn unit_names(&self) -> Vec<String> {
+    self.all_units.keys().map(|id| id.name.clone()).unique().collect()
+  }
 
-            if let Some(Ok(file_path)) = &selected.file_path {
-              menu_items.push(MenuItem::new("Copy unit file path", Action::CopyUnitFilePath, Some(KeyCode::Char('c'))));
-              menu_items.push(MenuItem::new(
-                "Edit unit file",
-                Action::EditUnitFile { unit: selected.id(), path: file_path.clone() },
-                Some(KeyCode::Char('e')),
-              ));
-            }
+  /// Scopes the wizards can create units in, given the scope we were started with
+  fn creatable_scopes(&self) -> &'static [&'static str] {
+    // another user's unit files aren't ours to write
+    match (self.scope, user_manager::user().is_some()) {
+      (Scope::Global, _) | (Scope::All, true) => &["system"],
+      (Scope::User, true) => &[],
+      (Scope::User, false) => &["user"],
+      (Scope::All, false) => &["system", "user"],
+    }
+  }
 
-            self.menu_items = StatefulList::with_items(menu_items);
-            self.menu_items.state.select(Some(0));
-          } else {
-            return None;
-          }
-        } else if mode == Mode::SignalMenu {
-          if let Some(selected) = self.filtered_units.selected() {
-            let signals = vec![
-              ("SIGTERM", KeyCode::Char('t')),
-              ("SIGHUP", KeyCode::Char('h')),
-              ("SIGINT", KeyCode::Char('i')),
-              ("SIGQUIT", KeyCode::Char('q')),
-              ("SIGKILL", KeyCode::Char('k')),
-              ("SIGUSR1", KeyCode::Char('1')),
-              ("SIGUSR2", KeyCode::Char('2')),
-            ];
+  /// Only ask the service wizard for a user to run as when it's making a system unit; the user manager runs
+  /// everything as its own user
+  fn show_user_field(&mut self) {
+    let shown = self.form.value("Scope") == "system";
+    self.form.show_field(Field::text("User", ""), "ExecStart", shown);
+  }
 
-            let menu_items: Vec<MenuItem> = signals
-              .into_iter()
-              .map(|(name, key_code)| {
-                MenuItem::new(name, Action::KillService(selected.id(), name.to_string()), Some(key_code))
-              })
-              .collect();
+  /// Show upcoming runs for the timer wizard's OnCalendar expression as it's typed
+  fn preview_calendar(&self) {
+    let value = self.form.value("OnCalendar").to_string();
+    let tx = self.action_tx.clone().unwrap();
+    tokio::task::spawn_blocking(move || {
+      let lines = match systemd::calendar_preview(&value, 5) {
+        Ok(preview) => {
+          let mut lines = vec![Line::from(Span::styled(
+            format!("Next runs ({})", preview.normalized),
+            Style::default().add_modifier(Modifier::BOLD),
+          ))];
+          lines.extend(preview.elapses.into_iter().map(|(when, from_now)| {
+            Line::from(vec![
+              Span::raw(format!("  {when}")),
+              Span::styled(format!("  {from_now}"), Style::default().add_modifier(Modifier::DIM)),
+            ])
+          }));
+          lines
+        },
+        Err(e) => vec![Line::from(Span::styled(e.to_string(), Style::default().fg(Color::Red)))],
+      };
+      let _ = tx.send(Action::SetFormPreview { field: "OnCalendar".into(), value, lines });
+    });
+  }
 
-            self.menu_items = StatefulList::with_items(menu_items);
-            self.menu_items.state.select(Some(0));
-          } else {
-            return None;
-          }
-        }
+  /// Run `change` against the manager picked in the environment form, then show the manager's environment along with
+  /// what `change` did
+  fn refresh_environment<F>(&self, change: F)
+  where
+    F: FnOnce(UnitScope) -> anyhow::Result<Option<String>> + Send + 'static,
+  {
+    let scope_name = self.form.value("Scope").to_string();
+    let scope = if scope_name == "user" { UnitScope::User } else { UnitScope::Global };
+    let tx = self.action_tx.clone().unwrap();
+    tokio::task::spawn_blocking(move || {
+      let mut lines = match change(scope) {
+        Ok(Some(status)) => vec![Line::from(Span::styled(status, Style::default().fg(Color::Green))), Line::from("")],
+        Ok(None) => vec![],
+        Err(e) => vec![Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red))), Line::from("")],
+      };
+      lines.extend(manager_environment_lines(scope));
+      let _ = tx.send(Action::SetFormPreview { field: "Scope".into(), value: scope_name, lines });
+    });
+  }
 
-        self.mode = mode;
-        return Some(Action::Render);
-      },
-      Action::EnterError(err) => {
-        tracing::error!(err);
-        self.error_message = err;
-        return Some(Action::EnterMode(Mode::Error));
-      },
-      Action::ToggleHelp => {
-        if self.mode != Mode::Help {
-          self.previous_mode = Some(self.mode);
-          self.mode = Mode::Help;
+  fn submit_environment(&mut self) {
+    let input = self.form.value("Variable").trim().to_string();
+    let names: Vec<String> = input.split_whitespace().map(str::to_string).collect();
+    match self.form.value("Action") {
+      "set" => self.refresh_environment(move |scope| {
+        match input.split_once('=') {
+          Some((name, _)) if !name.is_empty() && !name.contains(char::is_whitespace) => {},
+          _ => anyhow::bail!("Enter the variable as NAME=value"),
+        }
+        let result = systemd::set_manager_environment(scope, std::slice::from_ref(&input));
+        record_environment_change(scope, &format!("Set {}", input.split('=').next().unwrap_or_default()), &result);
+        result?;
+        Ok(Some(format!("Set {input}")))
+      }),
+      "unset" => self.refresh_environment(move |scope| {
+        if names.is_empty() {
+          anyhow::bail!("Enter the names of the variables to unset");
+        }
+        let result = systemd::unset_manager_environment(scope, &names);
+        record_environment_change(scope, &format!("Unset {}", names.join(" ")), &result);
+        result?;
+        Ok(Some(format!("Unset {}", names.join(" "))))
+      }),
+      _ => self.refresh_environment(move |scope| {
+        // with no names, bring over whichever of the usual session variables the manager is missing
+        let assignments = if names.is_empty() {
+          let manager = systemd::get_manager_environment(scope)?;
+          systemd::missing_session_variables(&manager, |name| std::env::var(name).ok())
         } else {
-          self.mode = self.previous_mode.unwrap_or(Mode::Search);
+          names
+            .iter()
+            .map(|name| match std::env::var(name) {
+              Ok(value) => Ok(format!("{name}={value}")),
+              Err(_) => anyhow::bail!("{name} isn't set in this session"),
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?
+        };
+        if assignments.is_empty() {
+          return Ok(Some("Nothing to import, the manager already has this session's variables".into()));
         }
-        return Some(Action::Render);
+        let result = systemd::set_manager_environment(scope, &assignments);
+        let names: Vec<&str> = assignments.iter().filter_map(|a| a.split_once('=')).map(|(name, _)| name).collect();
+        record_environment_change(scope, &format!("Import {}", names.join(" ")), &result);
+        result?;
+        Ok(Some(format!("Imported {}", names.join(" "))))
+      }),
+    }
+  }
+
+  fn preview_duplicate(&mut self) {
+    let FormPurpose::DuplicateUnit { source, path } = &self.form.purpose else { return };
+    let target = scaffold::duplicate_file_name(&source.name, self.form.value("Name"))
+      .and_then(|file_name| Ok(scaffold::unit_directory(source.scope)?.join(file_name)));
+    self.form.preview = match target {
+      Ok(target) => vec![
+        Line::from(format!("Copies {path}")),
+        Line::from(format!("    to {}", target.display())),
+        Line::from(Span::styled("and opens the copy in $EDITOR", Style::default().add_modifier(Modifier::DIM))),
+      ],
+      Err(e) => vec![Line::from(Span::styled(e.to_string(), Style::default().fg(Color::Red)))],
+    };
+  }
+
+  fn submit_duplicate(&mut self, source: UnitId, source_path: String) {
+    let new_name = self.form.value("Name").trim().to_string();
+    let file_name = match scaffold::duplicate_file_name(&source.name, &new_name) {
+      Ok(file_name) => file_name,
+      Err(e) => {
+        self.form.preview = vec![Line::from(Span::styled(e.to_string(), Style::default().fg(Color::Red)))];
+        return;
       },
-      Action::CopyUnitFilePath => {
-        if let Some(selected) = self.filtered_units.selected() {
-          if let Some(Ok(file_path)) = &selected.file_path {
-            match clipboard_anywhere::set_clipboard(file_path) {
-              Ok(_) => return Some(Action::EnterMode(Mode::ServiceList)),
-              Err(e) => return Some(Action::EnterError(format!("Error copying to clipboard: {e}"))),
-            }
-          } else {
-            return Some(Action::EnterError("No unit file path available".into()));
+    };
+    let scope = source.scope;
+    let unit = UnitId { name: file_name.clone(), scope };
+
+    let tx = self.action_tx.clone().unwrap();
+    let cancel_token = CancellationToken::new();
+    let token = cancel_token.clone();
+    let future = {
+      let unit = unit.clone();
+      async move {
+        let path =
+          tokio::task::spawn_blocking(move || scaffold::copy_unit_file(scope, &source_path, &file_name)).await??;
+        info!("Copied {} to {}", source.name, path.display());
+        systemd::reload(scope, token).await?;
+        tx.send(Action::EditUnitFile { unit, path: path.to_string_lossy().to_string() })?;
+        Ok(())
+      }
+    };
+    self.service_action(unit, "Duplicate".into(), cancel_token, future);
+  }
+
+  fn crontab_timers(&self) -> anyhow::Result<(Vec<cron::CronJob>, Vec<cron::Translation>)> {
+    let form = &self.form;
+    let scope = if form.value("Scope") == "user" { UnitScope::User } else { UnitScope::Global };
+    let (text, system) = cron::load_crontab(form.value("Crontab"))?;
+    let jobs = cron::parse_crontab(&text, system);
+    let timers = cron::to_timers(&jobs, form.value("Name prefix").trim(), form.value("Persistent") == "yes", scope);
+    Ok((jobs, timers))
+  }
+
+  /// Show how each crontab entry will be translated, so nothing gets written without being reviewed
+  fn preview_crontab(&mut self) {
+    let error = |e: String| Line::from(Span::styled(e, Style::default().fg(Color::Red)));
+    self.form.preview = match self.crontab_timers() {
+      Ok((jobs, _)) if jobs.is_empty() => {
+        vec![Line::from("Enter a crontab line, a crontab path, or leave empty to import your own crontab")]
+      },
+      Ok((jobs, timers)) => jobs
+        .iter()
+        .zip(timers)
+        .flat_map(|(job, timer)| {
+          let mut lines =
+            vec![Line::from(Span::styled(job.line.clone(), Style::default().add_modifier(Modifier::DIM)))];
+          match timer {
+            Ok(timer) => {
+              lines.push(Line::from(vec![
+                Span::raw("  → "),
+                Span::styled(timer.timer_name(), Style::default().fg(Color::Green)),
+                Span::raw(format!(" OnCalendar={}", timer.on_calendar.join(" + "))),
+              ]));
+              lines.push(Line::from(format!("    ExecStart={}", timer.command)));
+            },
+            Err(e) => lines.push(error(format!("  ✗ {e}"))),
           }
-        }
+          lines
+        })
+        .collect(),
+      Err(e) => vec![error(format!("{e:#}"))],
+    };
+  }
+
+  /// The instance the form describes, with how to bring it up
+  fn instantiate_form(&self) -> anyhow::Result<(UnitId, Vec<String>)> {
+    let form = &self.form;
+    let template = form.value("Template").trim();
+    let instance = form.value("Instance").trim();
+    let Some(name) = unit_types::instance(template, instance) else {
+      anyhow::bail!("Pick a template, a unit whose name ends in @.service or similar");
+    };
+    if instance.is_empty() {
+      anyhow::bail!("Enter an instance name, e.g. what goes after the @");
+    }
+    let scope = match form.value("Scope") {
+      "" => self.unit_scopes()[0],
+      "user" => UnitScope::User,
+      _ => UnitScope::Global,
+    };
+    let args = match form.value("Afterwards") {
+      "enable and start" => vec!["enable".to_string(), "--now".into(), name.clone()],
+      "enable" => vec!["enable".to_string(), name.clone()],
+      _ => vec!["start".to_string(), name.clone()],
+    };
+    Ok((UnitId { name, scope }, args))
+  }
+
+  fn preview_instantiate(&mut self) {
+    self.form.preview = match self.instantiate_form() {
+      Ok((unit, args)) => vec![
+        Line::from(format!("Runs systemctl {}", args.join(" "))),
+        Line::from(Span::styled(
+          format!(
+            "Instances of {} are grouped together in the list",
+            unit_types::template(&unit.name).unwrap_or_default()
+          ),
+          Style::default().add_modifier(Modifier::DIM),
+        )),
+      ],
+      Err(e) => vec![Line::from(Span::styled(e.to_string(), Style::default().add_modifier(Modifier::DIM)))],
+    };
+  }
+
+  fn submit_instantiate(&mut self) -> Option<Action> {
+    match self.instantiate_form() {
+      Ok((unit, args)) => Some(Action::RunSystemctl(unit, args)),
+      Err(e) => {
+        self.form.preview = vec![Line::from(Span::styled(e.to_string(), Style::default().fg(Color::Red)))];
+        Some(Action::Render)
       },
-      Action::SetUnitFilePath { unit, path } => {
-        if let Some(unit) = self.all_units.get_mut(&unit) {
-          unit.file_path = Some(path.clone());
+    }
+  }
+
+  fn submit_pipe_logs(&mut self, unit: UnitId) -> Option<Action> {
+    let command = self.form.value("Command").trim().to_string();
+    if command.is_empty() {
+      self.form.preview = vec![Line::from(Span::styled("Enter a command", Style::default().fg(Color::Red)))];
+      return Some(Action::Render);
+    }
+    self.last_pipe_command = command.clone();
+
+    let title = format!("{} | {command}", unit.name);
+    let logs = self.logs_text();
+    let tx = self.action_tx.clone().unwrap();
+    let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Running...")] };
+    tokio::task::spawn_blocking(move || {
+      let lines = match pipe_through(&command, logs) {
+        Ok(output) if output.is_empty() => vec![Line::from("No output")],
+        Ok(output) => output.lines().map(|l| Line::from(l.to_string())).collect(),
+        Err(e) => vec![Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red)))],
+      };
+      let _ = tx.send(Action::ShowViewer { title, lines });
+    });
+    Some(loading)
+  }
+
+  /// Count the files the paths cover as they're typed, to catch a typo before anything's watched
+  fn preview_watch_files(&mut self) {
+    let value = self.form.value("Paths").to_string();
+    let patterns = value.split_whitespace().map(String::from).collect_vec();
+    if patterns.is_empty() {
+      self.form.preview = watch_files_hint();
+      return;
+    }
+    let tx = self.action_tx.clone().unwrap();
+    tokio::task::spawn_blocking(move || {
+      let files = file_watch::scan(&patterns).len();
+      let lines = match files {
+        0 => vec![Line::from(Span::styled("No files there yet", Style::default().fg(Color::Yellow)))],
+        1 => vec![Line::from("Watches 1 file")],
+        files => vec![Line::from(format!("Watches {files} files"))],
+      };
+      let _ = tx.send(Action::SetFormPreview { field: "Paths".into(), value, lines });
+    });
+  }
+
+  fn submit_watch_files(&mut self, unit: UnitId) -> Option<Action> {
+    let patterns = self.form.value("Paths").split_whitespace().map(String::from).collect_vec();
+    if patterns.is_empty() {
+      self.form.preview = vec![Line::from(Span::styled("Enter a path or a glob", Style::default().fg(Color::Red)))];
+      return Some(Action::Render);
+    }
+    info!("Restarting {} when {} change", unit.name, patterns.join(" "));
+    let watch = Watch::new(patterns.clone());
+    let (tx, cancel) = (self.action_tx.clone().unwrap(), watch.cancel.clone());
+    self.watches.insert(unit.clone(), watch);
+    tokio::spawn(async move {
+      let scan = |patterns: Vec<String>| async move {
+        tokio::task::spawn_blocking(move || file_watch::scan(&patterns)).await.unwrap_or_default()
+      };
+      let mut files = scan(patterns.clone()).await;
+      loop {
+        tokio::select! {
+          _ = cancel.cancelled() => return,
+          _ = tokio::time::sleep(file_watch::POLL) => {},
         }
-        self.refresh_filtered_units(); // copy the updated unit file path to the filtered list
-      },
-      Action::SetLogs { unit, logs } => {
-        if let Some(selected) = self.filtered_units.selected() {
-          if selected.id() == unit {
-            self.logs = logs;
+        let now = scan(patterns.clone()).await;
+        let Some(path) = file_watch::changed(&files, &now) else { continue };
+        files = now;
+        // saving several files, or a build writing them, restarts once they've all landed
+        loop {
+          tokio::time::sleep(file_watch::SETTLE).await;
+          let now = scan(patterns.clone()).await;
+          if now == files {
+            break;
           }
+          files = now;
         }
-      },
-      Action::AppendLogLine { unit, line } => {
-        if let Some(selected) = self.filtered_units.selected() {
-          if selected.id() == unit {
-            self.logs.push(line);
-          }
+        if cancel.is_cancelled() {
+          return;
         }
+        let result = systemd::restart_service(unit.clone(), cancel.clone()).await;
+        let outcome = result.as_ref().map_or_else(|e| format!("failed: {e}"), |_| "ok".into());
+        record_action(action_log::Entry::new(unit.scope, &unit.name, "Restart on file change", &outcome));
+        let error = result.err().map(|e| e.to_string());
+        let _ = tx.send(Action::WatchRestarted { unit: unit.clone(), path, error });
+        let _ = tx.send(Action::RefreshServices);
+      }
+    });
+    Some(Action::EnterMode(Mode::ServiceList))
+  }
+
+  fn submit_log_filter(&mut self) -> Option<Action> {
+    match Filter::parse(self.form.value("Pattern")) {
+      Ok(filter) => self.add_log_filter(filter),
+      Err(e) => {
+        self.form.preview = vec![Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red)))];
+        Some(Action::Render)
       },
-      Action::ScrollUp(offset) => {
-        self.logs_scroll_offset = self.logs_scroll_offset.saturating_sub(offset);
-        info!("scroll offset: {}", self.logs_scroll_offset);
-      },
-      Action::ScrollDown(offset) => {
-        self.logs_scroll_offset = self.logs_scroll_offset.saturating_add(offset);
-        info!("scroll offset: {}", self.logs_scroll_offset);
-      },
-      Action::ScrollToTop => {
-        self.logs_scroll_offset = 0;
+    }
+  }
+
+  fn add_log_filter(&mut self, filter: Filter) -> Option<Action> {
+    self.log_filters.filters.push(filter);
+    self.logs = self.log_filters.apply(std::mem::take(&mut self.logs));
+    self.log_minutes = count_log_minutes(&self.logs);
+    self.logs_scroll_offset = 0;
+    self.log_search.rescan(&self.logs, log_message);
+    Some(Action::EnterMode(Mode::Logs))
+  }
+
+  fn submit_export_logs(&mut self) -> Option<Action> {
+    let path = PathBuf::from(self.form.value("File").trim());
+    let mut text = self.plain_logs();
+    text.push('\n');
+    let count = self.logs.len();
+    let value = self.form.value("File").to_string();
+    let tx = self.action_tx.clone().unwrap();
+    tokio::task::spawn_blocking(move || {
+      if let Err(e) = std::fs::write(&path, text) {
+        let error = format!("Failed to write {}: {e}", path.display());
+        let lines = vec![Line::from(Span::styled(error, Style::default().fg(Color::Red)))];
+        let _ = tx.send(Action::SetFormPreview { field: "File".into(), value, lines });
+        return;
+      }
+      info!("Wrote {count} log lines to {}", path.display());
+      let _ = tx.send(Action::EnterMode(Mode::Logs));
+    });
+    None
+  }
+
+  fn submit_export_graph(&mut self, unit: UnitId) -> Option<Action> {
+    let Ok(depth) = self.form.value("Depth").trim().parse::<usize>() else {
+      self.form.preview = vec![Line::from(Span::styled("Depth must be a number", Style::default().fg(Color::Red)))];
+      return Some(Action::Render);
+    };
+    let path = PathBuf::from(self.form.value("File").trim());
+    let direction = if self.form.value("Direction") == depgraph::Direction::Dependents.label() {
+      depgraph::Direction::Dependents
+    } else {
+      depgraph::Direction::Dependencies
+    };
+
+    let title = format!("Dependency graph of {} ({})", unit.name, direction.label());
+    let tx = self.action_tx.clone().unwrap();
+    let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Exporting...")] };
+    tokio::task::spawn_blocking(move || {
+      let lines = export_graph_lines(&unit, direction, depth, &path);
+      let _ = tx.send(Action::ShowViewer { title, lines });
+    });
+    Some(loading)
+  }
+
+  fn submit_crontab_import(&mut self) {
+    let timers: Vec<NewTimer> = match self.crontab_timers() {
+      Ok((_, timers)) => timers.into_iter().flatten().collect(),
+      Err(_) => vec![],
+    };
+    let Some(first) = timers.first() else {
+      self.preview_crontab();
+      self.form.preview.push(Line::from(Span::styled("Nothing to import", Style::default().fg(Color::Red))));
+      return;
+    };
+    let enable_and_start = self.form.value("Afterwards") == "enable and start";
+
+    let scope = first.scope;
+    let unit = UnitId { name: first.timer_name(), scope };
+    let cancel_token = CancellationToken::new();
+    let token = cancel_token.clone();
+    let future = async move {
+      let units: Vec<UnitId> = timers.iter().map(|t| UnitId { name: t.timer_name(), scope: t.scope }).collect();
+      tokio::task::spawn_blocking(move || scaffold::install_timers(&timers)).await??;
+      systemd::reload(scope, token.clone()).await?;
+      if enable_and_start {
+        for unit in units {
+          systemd::enable_service(unit.clone(), token.clone()).await?;
+          systemd::start_service(unit, token.clone()).await?;
+        }
+      }
+      Ok(())
+    };
+    self.service_action(unit, "Import crontab".into(), cancel_token, future);
+  }
+
+  fn submit_new_timer(&mut self) {
+    let form = &self.form;
+    let scope = if form.value("Scope") == "user" { UnitScope::User } else { UnitScope::Global };
+    let timer = NewTimer {
+      name: form.value("Name").trim().trim_end_matches(".timer").to_string(),
+      description: form.value("Description").trim().to_string(),
+      command: form.value("Command").trim().to_string(),
+      user: String::new(),
+      environment: vec![],
+      on_calendar: vec![form.value("OnCalendar").trim().to_string()],
+      persistent: form.value("Persistent") == "yes",
+      scope,
+    };
+    let enable_and_start = form.value("Afterwards") == "enable and start";
+
+    if let Err(e) = timer.validate() {
+      self.form.preview = vec![Line::from(Span::styled(e.to_string(), Style::default().fg(Color::Red)))];
+      return;
+    }
+
+    let unit = UnitId { name: timer.timer_name(), scope };
+    let cancel_token = CancellationToken::new();
+    let token = cancel_token.clone();
+    let future = {
+      let unit = unit.clone();
+      async move {
+        tokio::task::spawn_blocking(move || scaffold::install_timer(&timer)).await??;
+        systemd::reload(scope, token.clone()).await?;
+        if enable_and_start {
+          systemd::enable_service(unit.clone(), token.clone()).await?;
+          systemd::start_service(unit, token).await?;
+        }
+        Ok(())
+      }
+    };
+    self.service_action(unit, "Create".into(), cancel_token, future);
+  }
+
+  fn submit_new_service(&mut self) {
+    let form = &self.form;
+    let scope = if form.value("Scope") == "user" { UnitScope::User } else { UnitScope::Global };
+    let service = NewService {
+      name: form.value("Name").trim().trim_end_matches(".service").to_string(),
+      description: form.value("Description").trim().to_string(),
+      exec_start: form.value("ExecStart").trim().to_string(),
+      user: form.value("User").trim().to_string(),
+      working_directory: form.value("WorkingDirectory").trim().to_string(),
+      restart: form.value("Restart").to_string(),
+      hardening: HardeningPreset::parse(form.value("Hardening")),
+      scope,
+      after: form.value("After").split_whitespace().map(String::from).collect(),
+    };
+    let enable_and_start = form.value("Afterwards") == "enable and start";
+
+    // keep the form open so mistakes can be fixed
+    if let Err(e) = service.validate() {
+      self.form.preview = vec![Line::from(Span::styled(e.to_string(), Style::default().fg(Color::Red)))];
+      return;
+    }
+
+    let unit = UnitId { name: service.unit_name(), scope };
+    let cancel_token = CancellationToken::new();
+    let token = cancel_token.clone();
+    let future = {
+      let unit = unit.clone();
+      async move {
+        let contents = service.render();
+        let unit_name = unit.name.clone();
+        let path =
+          tokio::task::spawn_blocking(move || scaffold::install_unit_file(scope, &unit_name, &contents)).await??;
+        info!("Created {}", path.display());
+        systemd::reload(scope, token.clone()).await?;
+        if enable_and_start {
+          systemd::enable_service(unit.clone(), token.clone()).await?;
+          systemd::start_service(unit, token).await?;
+        }
+        Ok(())
+      }
+    };
+    self.service_action(unit, "Create".into(), cancel_token, future);
+  }
+
+  fn submit_run_transient(&mut self) -> Option<Action> {
+    let error = |message: String| vec![Line::from(Span::styled(message, Style::default().fg(Color::Red)))];
+    let command = match command_bar::split(self.form.value("Command")) {
+      Ok(command) => command,
+      Err(e) => {
+        self.form.preview = error(format!("{e:#}"));
+        return Some(Action::Render);
       },
-      Action::ScrollToBottom => {
-        // TODO: this is partially broken, figure out a better way to scroll to end
-        // problem: we don't actually know the height of the paragraph before it's rendered
-        // because it's wrapped based on the width of the widget
-        // A proper fix might need to wait until ratatui improves scrolling: https://github.com/ratatui-org/ratatui/issues/174
-        self.logs_scroll_offset = self.logs.len() as u16;
+    };
+    let memory_max = match self.form.value("MemoryMax").trim() {
+      "" => None,
+      value => match property_diff::parse_bytes(value, property_diff::ram_bytes()) {
+        Some(bytes) => Some(bytes),
+        None => {
+          self.form.preview = error(format!("{value} isn't a size, like 512M or 2G"));
+          return Some(Action::Render);
+        },
       },
+    };
+    let optional = |value: &str| Some(value.trim().to_string()).filter(|v| !v.is_empty());
+    let suffix = if self.form.value("Run in") == "scope" { ".scope" } else { ".service" };
+    let name = optional(self.form.value("Name")).unwrap_or_else(|| format!("run-{}", Local::now().timestamp()));
+    let name = format!("{}{suffix}", name.trim_end_matches(suffix));
+    let scope = if self.form.value("Manager") == "user" { UnitScope::User } else { UnitScope::Global };
+    let transient = Transient {
+      unit: UnitId { name, scope },
+      description: command.join(" "),
+      command,
+      user: optional(self.form.value("User")),
+      working_directory: optional(self.form.value("WorkingDirectory")),
+      memory_max,
+    };
 
-      Action::StartService(service_name) => self.start_service(service_name),
-      Action::StopService(service_name) => self.stop_service(service_name),
-      Action::ReloadService(service_name) => self.reload_service(service_name),
-      Action::RestartService(service_name) => self.restart_service(service_name),
-      Action::RefreshServices => {
-        let tx = self.action_tx.clone().unwrap();
-        let scope = self.scope;
-        let limit_units = self.limit_units.to_vec();
-        tokio::spawn(async move {
-          let units = systemd::get_all_services(scope, &limit_units)
-            .await
-            .expect("Failed to get services. Check that systemd is running and try running this tool with sudo.");
-          tx.send(Action::SetServices(units)).unwrap();
-        });
-      },
-      Action::SetServices(units) => {
-        self.update_units(units);
+    // keep the form open so mistakes can be fixed
+    if let Err(e) = transient.validate() {
+      self.form.preview = error(e.to_string());
+      return Some(Action::Render);
+    }
+    Some(Action::RunTransient(transient))
+  }
+
+  fn run_transient(&mut self, transient: Transient) {
+    let unit = transient.unit.clone();
+    self.select_when_listed = Some(unit.clone());
+    let cancel_token = CancellationToken::new();
+    let future = systemd::start_transient(transient, cancel_token.clone());
+    self.service_action(unit, "Run".into(), cancel_token, future);
+  }
+
+  fn enable_accounting(&mut self, service: UnitId) {
+    let cancel_token = CancellationToken::new();
+    let future = systemd::enable_accounting(service.clone(), cancel_token.clone());
+    self.service_action(service, "Enable accounting".into(), cancel_token, future);
+  }
+
+  fn set_properties(&mut self, unit: UnitId, assignments: Vec<String>, runtime: bool) {
+    let cancel_token = CancellationToken::new();
+    let future = systemd::set_properties(unit.clone(), assignments, runtime, cancel_token.clone());
+    self.service_action(unit, "Set properties".into(), cancel_token, future);
+  }
+
+  /// Compare the assignments with what's in effect as they're typed
+  fn preview_set_properties(&self) {
+    let FormPurpose::SetProperties(unit) = self.form.purpose.clone() else { return };
+    let assignments = self.form.value("Assignments").to_string();
+    let tx = self.action_tx.clone().unwrap();
+    tokio::task::spawn_blocking(move || {
+      let settings = property_diff::parse_arguments(&assignments);
+      let lines = match settings_diff(&unit, &settings) {
+        Ok(changes) => property_diff_lines(&changes, false),
+        Err(e) => vec![Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red)))],
+      };
+      let _ = tx.send(Action::SetFormPreview { field: "Assignments".into(), value: assignments, lines });
+    });
+  }
+
+  fn submit_set_properties(&mut self, unit: UnitId) -> Option<Action> {
+    let settings = property_diff::parse_arguments(self.form.value("Assignments"));
+    if settings.is_empty() {
+      self.form.preview =
+        vec![Line::from(Span::styled("Enter at least one NAME=value", Style::default().fg(Color::Red)))];
+      return Some(Action::Render);
+    }
+    let assignments = settings.iter().map(|(k, v)| format!("{k}={v}")).collect();
+    let action = Action::SetProperties { unit, assignments, runtime: self.form.value("Lifetime") == "until reboot" };
+    if self.dry_run {
+      let mut lines = self.form.preview.clone();
+      lines.push(Line::from(""));
+      lines.extend(equivalent::for_action(&action).map(|e| Line::from(format!("$ {}", e.shell()))));
+      lines.push(Line::from(Span::styled(
+        "Dry run: nothing was changed. Press d in the unit list to turn dry run off",
+        Style::default().add_modifier(Modifier::DIM),
+      )));
+      return Some(Action::ShowViewer { title: "Dry run: set properties".into(), lines });
+    }
+    Some(action)
+  }
+
+  fn submit_set_environment(&mut self, unit: UnitId) -> Option<Action> {
+    let changes = match command_bar::split(self.form.value("Variables")) {
+      Ok(changes) if !changes.is_empty() => changes,
+      Ok(_) => {
+        self.form.preview =
+          vec![Line::from(Span::styled("Enter at least one NAME=value", Style::default().fg(Color::Red)))];
         return Some(Action::Render);
       },
-      Action::KillService(service_name, signal) => self.kill_service(service_name, signal),
-      Action::SpinnerTick => {
-        self.spinner_tick = self.spinner_tick.wrapping_add(1);
+      Err(e) => {
+        self.form.preview = vec![Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red)))];
         return Some(Action::Render);
       },
-      Action::CancelTask => {
-        if let Some(cancel_token) = self.cancel_token.take() {
-          cancel_token.cancel();
+    };
+    let restart = self.form.value("Afterwards") == "reload and restart";
+    if self.dry_run {
+      let dim = Style::default().add_modifier(Modifier::DIM);
+      let path = unit_environment::drop_in_path(&unit).map(|p| p.display().to_string()).unwrap_or_default();
+      let mut lines = vec![Line::from(format!("Would change {path}:"))];
+      lines.extend(changes.iter().map(|change| match change.split_once('=') {
+        Some(_) => Line::from(format!("  set {change}")),
+        None => Line::from(format!("  drop the override of {change}")),
+      }));
+      lines.push(Line::from(""));
+      let mut commands = vec![equivalent::for_action(&Action::ReloadService(unit.clone()))];
+      if restart {
+        commands.push(equivalent::for_action(&Action::RestartService(unit)));
+      }
+      lines.extend(commands.into_iter().flatten().map(|e| Line::from(format!("$ {}", e.shell()))));
+      lines.push(Line::from(Span::styled(
+        "Dry run: nothing was changed. Press d in the unit list to turn dry run off",
+        dim,
+      )));
+      return Some(Action::ShowViewer { title: "Dry run: override environment".into(), lines });
+    }
+    Some(Action::SetEnvironment { unit, changes, restart })
+  }
+
+  fn set_environment(&mut self, unit: UnitId, changes: Vec<String>, restart: bool) {
+    let cancel_token = CancellationToken::new();
+    let token = cancel_token.clone();
+    let future = {
+      let unit = unit.clone();
+      async move {
+        let written = unit.clone();
+        let path = tokio::task::spawn_blocking(move || unit_environment::set_overrides(&written, &changes)).await??;
+        info!("Updated {}", path.display());
+        systemd::reload(unit.scope, token.clone()).await?;
+        if restart {
+          systemd::restart_service(unit, token).await?;
         }
-        self.mode = Mode::ServiceList;
-        return Some(Action::Render);
-      },
-      _ => (),
+        Ok(())
+      }
+    };
+    self.service_action(unit, "Override environment".into(), cancel_token, future);
+  }
+
+  /// The form for changing units' enablement, with the choice of starting or stopping them too, or for presets, of
+  /// the preset mode
+  fn open_enablement(&mut self, units: Vec<UnitId>, verb: EnablementVerb) -> Option<Action> {
+    let field = match verb {
+      EnablementVerb::Enable => Field::choice("Afterwards", &["just enable", "enable and start"]),
+      EnablementVerb::Disable => Field::choice("Afterwards", &["just disable", "disable and stop"]),
+      EnablementVerb::Reenable => Field::choice("Afterwards", &["just reenable", "reenable and start"]),
+      EnablementVerb::Preset(_) => Field::choice("Mode", preset::PresetMode::NAMES),
+    };
+    let title = format!("{} {}", verb.name(), units_label(&units));
+    let form = Form::new(&title, FormPurpose::BulkEnablement { units, verb }, vec![field]);
+    let action = self.open_form(form);
+    self.preview_bulk_enablement();
+    action
+  }
+
+  /// The verb with the preset mode picked in the form
+  fn enablement_verb(&self, verb: EnablementVerb) -> EnablementVerb {
+    match verb {
+      EnablementVerb::Preset(_) => EnablementVerb::Preset(preset::PresetMode::parse(self.form.value("Mode"))),
+      verb => verb,
+    }
+  }
+
+  fn preview_bulk_enablement(&self) {
+    let FormPurpose::BulkEnablement { units, verb } = self.form.purpose.clone() else { return };
+    let verb = self.enablement_verb(verb);
+    let field = if matches!(verb, EnablementVerb::Preset(_)) { "Mode" } else { "Afterwards" };
+    let value = self.form.value(field).to_string();
+    let tx = self.action_tx.clone().unwrap();
+    tokio::task::spawn_blocking(move || {
+      let changes = enablement_changes(&units, verb);
+      let mut lines = enablement_preview_lines(&changes);
+      if value.contains(" and ") {
+        lines.push(Line::from(""));
+        lines.push(Line::from(format!(
+          "Then {} each of them",
+          if verb == EnablementVerb::Disable { "stop" } else { "start" }
+        )));
+      }
+      let _ = tx.send(Action::SetFormPreview { field: field.into(), value, lines });
+    });
+  }
+
+  /// The form for `preset-all`, previewing the unit files whose enablement the presets would change
+  fn open_preset_all(&mut self, scope: UnitScope) -> Option<Action> {
+    let title =
+      format!("Apply the presets to every {} unit file", if scope == UnitScope::User { "user" } else { "system" });
+    let form = Form::new(&title, FormPurpose::PresetAll(scope), vec![Field::choice("Mode", preset::PresetMode::NAMES)]);
+    let action = self.open_form(form);
+    self.preview_preset_all();
+    action
+  }
+
+  fn preview_preset_all(&self) {
+    let FormPurpose::PresetAll(scope) = self.form.purpose.clone() else { return };
+    let value = self.form.value("Mode").to_string();
+    let mode = preset::PresetMode::parse(&value);
+    let tx = self.action_tx.clone().unwrap();
+    tokio::task::spawn_blocking(move || {
+      let lines = match systemd::get_unit_file_states(scope) {
+        Ok(files) => preset_all_lines(scope, &files, mode),
+        Err(e) => vec![Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red)))],
+      };
+      let _ = tx.send(Action::SetFormPreview { field: "Mode".into(), value, lines });
+    });
+  }
+
+  fn submit_preset_all(&mut self, scope: UnitScope) -> Option<Action> {
+    let mode = preset::PresetMode::parse(self.form.value("Mode"));
+    let title = format!("Preset all {} unit files", if scope == UnitScope::User { "user" } else { "system" });
+    if self.dry_run {
+      let mut lines = self.form.preview.clone();
+      lines.push(Line::from(""));
+      lines.push(Line::from(Span::styled(
+        "Dry run: nothing was changed. Press d in the unit list to turn dry run off",
+        Style::default().add_modifier(Modifier::DIM),
+      )));
+      return Some(Action::ShowViewer { title: format!("Dry run: {title}"), lines });
+    }
+
+    let tx = self.action_tx.clone().unwrap();
+    let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Applying...")] };
+    tokio::spawn(async move {
+      let result = systemd::preset_all_unit_files(scope, mode).await;
+      let outcome = match &result {
+        Ok(_) => "ok".to_string(),
+        Err(e) => format!("failed: {e}"),
+      };
+      record_action(action_log::Entry::new(scope, "", &format!("Preset all unit files ({})", mode.name()), &outcome));
+      let mut lines = match result {
+        Ok(changes) if changes.is_empty() => vec![Line::from("Nothing to change")],
+        Ok(changes) => changes.into_iter().map(Line::from).collect(),
+        Err(e) => vec![Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red)))],
+      };
+      if let Err(e) = systemd::reload(scope, CancellationToken::new()).await {
+        lines.push(Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red))));
+      }
+      let _ = tx.send(Action::ShowViewer { title, lines });
+      let _ = tx.send(Action::RefreshServices);
+    });
+    Some(loading)
+  }
+
+  /// Queue the job for every marked unit, wait for them to settle, then report how each one went
+  fn run_batch(&mut self, verb: BatchVerb) -> Option<Action> {
+    let jobs = self.marked.iter().map(|unit| (verb, unit.clone())).collect_vec();
+    let title = format!("{} {} units", verb.name(), jobs.len());
+    if !self.dry_run {
+      self.marked.clear();
+    }
+    Some(self.run_jobs(title, jobs))
+  }
+
+  /// Start or stop each unit, all at once, and show how each one ended up. In dry run, just show the commands
+  fn run_jobs(&self, title: String, jobs: Vec<(BatchVerb, UnitId)>) -> Action {
+    if self.dry_run {
+      let mut lines = jobs
+        .iter()
+        .filter_map(|(verb, unit)| equivalent::for_action(&batch_action(*verb, unit)))
+        .map(|equivalent| Line::from(equivalent.shell()))
+        .collect_vec();
+      lines.push(Line::from(""));
+      lines.push(Line::from(Span::styled(
+        "Dry run: nothing was changed. Press d in the unit list to turn dry run off",
+        Style::default().add_modifier(Modifier::DIM),
+      )));
+      return Action::ShowViewer { title: format!("Dry run: {title}"), lines };
+    }
+
+    let tx = self.action_tx.clone().unwrap();
+    let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Waiting for the jobs...")] };
+    tokio::spawn(async move {
+      // queue everything first so the units start (or stop) together rather than one after another
+      let queued = futures::future::join_all(jobs.iter().map(|(verb, unit)| verb.run(unit.clone()))).await;
+      let settled = futures::future::join_all(jobs.iter().zip(&queued).map(|((_, unit), queued)| async move {
+        match queued {
+          Ok(()) => systemd::wait_until_settled(unit, Duration::from_secs(30)).await,
+          Err(e) => Err(anyhow::anyhow!("{e}")),
+        }
+      }))
+      .await;
+
+      let mut lines = vec![];
+      for ((verb, unit), result) in jobs.iter().zip(settled) {
+        let (ok, detail) = match result {
+          Ok(state) => (verb.succeeded(&state), format!("now {state}")),
+          Err(e) => (false, e.to_string()),
+        };
+        let outcome = if ok { "ok".to_string() } else { format!("failed: {detail}") };
+        record_action(action_log::Entry::new(unit.scope, &unit.name, verb.name(), &outcome));
+        let (mark, color) = if ok { ("✓ ", Color::Green) } else { ("✗ ", Color::Red) };
+        lines.push(Line::from(vec![
+          Span::styled(mark, Style::default().fg(color)),
+          Span::raw(format!("{} {} ", verb.name(), unit.name)),
+          Span::styled(
+            detail,
+            if ok { Style::default().add_modifier(Modifier::DIM) } else { Style::default().fg(color) },
+          ),
+        ]));
+      }
+      let _ = tx.send(Action::ShowViewer { title, lines });
+      let _ = tx.send(Action::RefreshServices);
+    });
+    loading
+  }
+
+  /// Change each unit's enablement, reload once per manager, then report how each one went
+  fn submit_bulk_enablement(&mut self, units: Vec<UnitId>, verb: EnablementVerb) -> Option<Action> {
+    let verb = self.enablement_verb(verb);
+    let and_then = self.form.value("Afterwards").contains(" and ");
+    let title = format!("{} {}", verb.name(), units_label(&units));
+    if self.dry_run {
+      let mut lines = self.form.preview.clone();
+      lines.push(Line::from(""));
+      lines.push(Line::from(Span::styled(
+        "Dry run: nothing was changed. Press d in the unit list to turn dry run off",
+        Style::default().add_modifier(Modifier::DIM),
+      )));
+      return Some(Action::ShowViewer { title: format!("Dry run: {title}"), lines });
+    }
+    self.marked.retain(|u| !units.contains(u));
+
+    let tx = self.action_tx.clone().unwrap();
+    let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Applying...")] };
+    tokio::spawn(async move {
+      let mut lines = vec![];
+      let mut scopes = vec![];
+      for unit in &units {
+        let result = systemd::set_unit_file_enablement(unit, verb).await;
+        let outcome = match &result {
+          Ok(_) => "ok".to_string(),
+          Err(e) => format!("failed: {e}"),
+        };
+        record_action(action_log::Entry::new(unit.scope, &unit.name, verb.name(), &outcome));
+        match result {
+          Ok(changes) => {
+            lines.push(Line::from(vec![
+              Span::styled("✓ ", Style::default().fg(Color::Green)),
+              Span::raw(unit.name.clone()),
+            ]));
+            if changes.is_empty() {
+              lines
+                .push(Line::from(Span::styled("    nothing to change", Style::default().add_modifier(Modifier::DIM))));
+            }
+            lines.extend(changes.into_iter().map(|c| Line::from(format!("    {c}"))));
+            if !scopes.contains(&unit.scope) {
+              scopes.push(unit.scope);
+            }
+          },
+          Err(e) => lines.push(Line::from(vec![
+            Span::styled("✗ ", Style::default().fg(Color::Red)),
+            Span::raw(format!("{} ", unit.name)),
+            Span::styled(e.to_string(), Style::default().fg(Color::Red)),
+          ])),
+        }
+      }
+      for scope in scopes {
+        if let Err(e) = systemd::reload(scope, CancellationToken::new()).await {
+          lines.push(Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red))));
+        }
+      }
+      if and_then {
+        lines.push(Line::from(""));
+        for unit in units {
+          let (name, result) = if verb == EnablementVerb::Disable {
+            ("Stop", systemd::stop_service(unit.clone(), CancellationToken::new()).await)
+          } else {
+            ("Start", systemd::start_service(unit.clone(), CancellationToken::new()).await)
+          };
+          let outcome = match &result {
+            Ok(_) => "ok".to_string(),
+            Err(e) => format!("failed: {e}"),
+          };
+          record_action(action_log::Entry::new(unit.scope, &unit.name, name, &outcome));
+          lines.push(match result {
+            Ok(_) => Line::from(vec![
+              Span::styled("✓ ", Style::default().fg(Color::Green)),
+              Span::raw(format!("{name} {}", unit.name)),
+            ]),
+            Err(e) => Line::from(vec![
+              Span::styled("✗ ", Style::default().fg(Color::Red)),
+              Span::raw(format!("{name} {} ", unit.name)),
+              Span::styled(e.to_string(), Style::default().fg(Color::Red)),
+            ]),
+          });
+        }
+      }
+      let _ = tx.send(Action::ShowViewer { title, lines });
+      let _ = tx.send(Action::RefreshServices);
+    });
+    Some(loading)
+  }
+
+  fn retry_mount(&mut self, unit: UnitId) {
+    let cancel_token = CancellationToken::new();
+    let token = cancel_token.clone();
+    let future = {
+      let unit = unit.clone();
+      async move {
+        systemd::reload(unit.scope, token.clone()).await?;
+        systemd::start_service(unit, token).await
+      }
+    };
+    self.service_action(unit, "Retry mount".into(), cancel_token, future);
+  }
+
+  fn kill_service(&mut self, service: UnitId, signal: String, whom: KillWhom) {
+    let cancel_token = CancellationToken::new();
+    let future = systemd::kill_service(service.clone(), signal.clone(), whom, cancel_token.clone());
+    let action_name = match whom {
+      KillWhom::All => format!("Kill with {signal}"),
+      whom => format!("Kill {} process with {signal}", whom.as_str()),
+    };
+    self.service_action(service, action_name, cancel_token, future);
+  }
+
+  fn submit_kill(&mut self, unit: UnitId) -> Option<Action> {
+    let signal = self.form.value("Signal").trim().to_string();
+    if let Err(e) = systemd::signal_number(&signal) {
+      self.form.preview = vec![Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red)))];
+      return Some(Action::Render);
+    }
+    let whom = match self.form.value("Send to") {
+      "main process" => KillWhom::Main,
+      "control process" => KillWhom::Control,
+      _ => KillWhom::All,
+    };
+    Some(Action::KillService { unit, signal, whom })
+  }
+
+  /// Power actions only go ahead once the machine's name is typed, so it's the machine meant, not whichever this
+  /// happens to be connected to
+  fn submit_power(&mut self, action: PowerAction, hostname: String) -> Option<Action> {
+    let typed = self.form.value("Hostname").trim();
+    if typed != hostname {
+      let message = if typed.is_empty() {
+        "Type the hostname to go ahead".to_string()
+      } else {
+        format!("This is {hostname}, not {typed}")
+      };
+      self.form.preview = vec![Line::from(Span::styled(message, Style::default().fg(Color::Red)))];
+      return Some(Action::Render);
+    }
+    self.mode = Mode::ServiceList;
+    Some(Action::RunPowerAction(action))
+  }
+
+  fn submit_boot_target(&mut self, target: String, isolate: bool) -> Option<Action> {
+    let typed = self.form.value("Target").trim();
+    // the name can be typed without .target
+    if typed != target && format!("{typed}.target") != target {
+      let message =
+        if typed.is_empty() { format!("Type {target} to go ahead") } else { format!("{typed} isn't {target}") };
+      self.form.preview = vec![Line::from(Span::styled(message, Style::default().fg(Color::Red)))];
+      return Some(Action::Render);
+    }
+    let verb = if isolate { "isolate" } else { "set-default" };
+    let unit = UnitId { name: target.clone(), scope: UnitScope::Global };
+    Some(Action::RunSystemctl(unit, vec![verb.into(), target]))
+  }
+
+  /// `reset-failed` for each manager with failed units. In dry run, just show the commands
+  fn reset_all_failed(&mut self) -> Option<Action> {
+    let scopes = self.all_units.values().filter(|u| u.is_failed()).map(|u| u.scope).unique().collect_vec();
+    let Some(&first) = scopes.first() else {
+      return Some(Action::EnterMode(Mode::ServiceList));
+    };
+    if self.dry_run {
+      let lines = scopes.iter().map(|scope| Line::from(equivalent::reset_all_failed(*scope))).collect();
+      return Some(Action::ShowViewer { title: "Dry run: reset failed units".into(), lines });
+    }
+    let cancel_token = CancellationToken::new();
+    let token = cancel_token.clone();
+    let future = async move {
+      for scope in scopes {
+        systemd::reset_failed(scope, token.clone()).await?;
+      }
+      Ok(())
+    };
+    // not about any one unit, so logged against the manager
+    self.service_action(
+      UnitId { name: String::new(), scope: first },
+      "Reset all failed units".into(),
+      cancel_token,
+      future,
+    );
+    None
+  }
+
+  /// The scope of a unit named in the command bar: the system's, unless only the user manager has it
+  fn scope_of_unit(&self, name: Option<&String>) -> UnitScope {
+    let loaded = |scope| name.is_some_and(|name| self.all_units.contains_key(&UnitId { name: name.clone(), scope }));
+    if loaded(UnitScope::Global) {
+      UnitScope::Global
+    } else if loaded(UnitScope::User) || matches!(self.scope, Scope::User) {
+      UnitScope::User
+    } else {
+      UnitScope::Global
+    }
+  }
+
+  /// Run a line from the command bar. The verbs the unit list has actions for go through those, so they're tracked
+  /// and dry runs cover them; other read-only verbs just show what they print, and the rest ask first
+  fn submit_command_line(&mut self, line: &str) -> Option<Action> {
+    let invocation = match command_bar::Invocation::parse(line) {
+      Ok(invocation) => invocation,
+      Err(e) => return Some(Action::EnterError(format!("{e:#}"))),
+    };
+    if let Err(e) = self.command_history.push(line.trim()) {
+      warn!("Error saving the command history: {e:#}");
+    }
+    let units = invocation.units();
+    let scope = invocation.scope.unwrap_or_else(|| self.scope_of_unit(units.first()));
+    // an empty name is an action on the manager itself
+    let unit = UnitId { name: units.first().cloned().unwrap_or_default(), scope };
+    let one_unit = units.len() == 1 && !invocation.has_other_options();
+    match invocation.verb.as_str() {
+      "start" if one_unit => return Some(Action::StartService(unit)),
+      "stop" if one_unit => return Some(Action::StopService(unit)),
+      "restart" if one_unit => return Some(Action::RestartService(unit)),
+      "kill" if one_unit => {
+        let signal = invocation.signal().unwrap_or("SIGTERM").to_string();
+        let whom = match invocation.kill_whom().map(KillWhom::parse).transpose() {
+          Ok(whom) => whom.unwrap_or_default(),
+          Err(e) => return Some(Action::EnterError(format!("{e:#}"))),
+        };
+        return Some(Action::KillService { unit, signal, whom });
+      },
+      "reenable" | "preset" if !units.is_empty() && !invocation.has_other_options() => {
+        let verb = match invocation.verb.as_str() {
+          "reenable" => EnablementVerb::Reenable,
+          _ => EnablementVerb::Preset(preset::PresetMode::Full),
+        };
+        let units = units.into_iter().map(|name| UnitId { name, scope }).collect();
+        return self.open_enablement(units, verb);
+      },
+      "preset-all" if !invocation.has_other_options() => return Some(Action::OpenPresetAll(scope)),
+      _ => {},
+    }
+
+    let args = invocation.systemctl_args();
+    if invocation.is_read_only() {
+      let title = format!("systemctl {}", args.join(" "));
+      let tx = self.action_tx.clone().unwrap();
+      tokio::task::spawn_blocking(move || {
+        let lines = match systemd::run_systemctl(scope, &args) {
+          Ok(output) => {
+            let text =
+              format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+            let lines = text.lines().map(|l| Line::from(monochrome::strip_ansi(l))).collect_vec();
+            if lines.is_empty() {
+              vec![Line::from("No output")]
+            } else {
+              lines
+            }
+          },
+          Err(e) => vec![Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red)))],
+        };
+        let _ = tx.send(Action::ShowViewer { title, lines });
+      });
+      return Some(Action::ShowViewer { title: line.trim().to_string(), lines: vec![Line::from("Running...")] });
+    }
+
+    let action = Action::RunSystemctl(unit, args);
+    self.prompt_title = "Run this?".into();
+    self.prompt_lines = equivalent::for_action(&action).map(|e| Line::from(e.shell())).into_iter().collect();
+    let items = vec![
+      MenuItem::new("Run it", action, Some(KeyCode::Char('y'))),
+      MenuItem::new("Cancel", Action::EnterMode(Mode::ServiceList), Some(KeyCode::Char('n'))),
+    ];
+    self.menu_items = StatefulList::with_items(items);
+    self.menu_items.state.select(Some(0));
+    self.mode = Mode::Prompt;
+    Some(Action::Render)
+  }
+
+  fn run_systemctl(&mut self, unit: UnitId, args: Vec<String>) {
+    let cancel_token = CancellationToken::new();
+    let action_name = format!("systemctl {}", args.join(" "));
+    let future = systemd::run_systemctl_job(unit.scope, args, cancel_token.clone());
+    self.service_action(unit, action_name, cancel_token, future);
+  }
+}
+
+impl Component for Home {
+  fn init(&mut self, tx: UnboundedSender<Action>) -> anyhow::Result<()> {
+    self.action_tx = Some(tx.clone());
+    // TODO find a better name for these. They're used to run any async data loading that needs to happen after the selection is changed,
+    // not just journalctl stuff
+    let (journalctl_tx, journalctl_rx) = std::sync::mpsc::channel::<LogQuery>();
+    self.journalctl_tx = Some(journalctl_tx);
+
+    // TODO: move into function
+    tokio::task::spawn_blocking(move || {
+      // One follow stream per selected unit. It only ever yields new lines, so it stays valid across boot and audit
+      // filter changes and only needs replacing when the selection moves to another unit
+      let mut follow: Option<(Vec<String>, JoinHandle<()>)> = None;
+      let mut last_read: Option<Instant> = None;
+      let mut audit_cache = AuditCache::default();
+
+      loop {
+        let mut query: LogQuery = match journalctl_rx.recv() {
+          Ok(query) => query,
+          Err(_) => return,
+        };
+
+        // lazy debounce to avoid spamming journalctl on slow connections/systems, and a floor on how often we read so
+        // scrolling through the list doesn't keep the disk busy
+        let since_last = last_read.map_or(JOURNAL_READ_INTERVAL, |t| t.elapsed());
+        std::thread::sleep(Duration::from_millis(100).max(JOURNAL_READ_INTERVAL.saturating_sub(since_last)));
+
+        // drain the channel, use the last value
+        while let Ok(next) = journalctl_rx.try_recv() {
+          // older lines are moot when the logs are about to be read afresh
+          if next.older.is_some() && query.older.is_none() && query.resume.is_none() {
+            continue;
+          }
+          info!("Skipping logs for {}...", query.unit.name);
+          query = next;
+        }
+        last_read = Some(Instant::now());
+        let LogQuery { unit, boot, include_audit, current_run, priority, resume, older } = query;
+
+        let mut matches = if current_run {
+          match systemd::get_unit_properties(&unit, &["InvocationID"]) {
+            Ok(properties) => match properties.get("InvocationID").filter(|id| !id.is_empty()) {
+              Some(invocation) => invocation_matches(&unit, invocation),
+              None => {
+                let logs =
+                  vec![Entry::plain(format!("{} hasn't run yet, so it has no current run to show", unit.name))];
+                let _ = tx.send(Action::SetLogs { unit: unit.clone(), logs });
+                let _ = tx.send(Action::Render);
+                continue;
+              },
+            },
+            Err(e) => {
+              warn!("Error getting the invocation ID of {}, showing all runs: {}", unit.name, e);
+              vec!["-u".into(), unit.name.clone()]
+            },
+          }
+        } else {
+          vec!["-u".into(), unit.name.clone()]
+        };
+        matches.extend(priority.map(|p| format!("--priority=0..{p}")));
+
+        if let Some(older) = older {
+          let mut journalctl = systemd::journalctl();
+          systemd::in_scope(&mut journalctl, unit.scope);
+          // the rest of the first second, whose lines already on screen are then left out
+          journalctl
+            .args(["--quiet", "--output=export", &format!("--until=@{}.999999", older.since)])
+            .arg(format!("--lines={}", LOG_PAGE_LINES + older.seen.len()))
+            .args(&matches)
+            .args(boot.journalctl_arg());
+          let lines = match remote::on_host(&mut journalctl).output() {
+            Ok(output) if output.status.success() => older.unseen(journal::parse_export(&output.stdout)),
+            Ok(output) => {
+              warn!("Error getting older logs for {}: {}", unit.name, String::from_utf8_lossy(&output.stderr));
+              vec![]
+            },
+            Err(e) => {
+              warn!("Error getting older logs for {}: {}", unit.name, e);
+              vec![]
+            },
+          };
+          let _ = tx.send(Action::PrependLogLines { unit, lines });
+          let _ = tx.send(Action::Render);
+          continue;
+        }
+
+        // the stream only depends on which lines match, so it survives boot and audit filter changes
+        let same_unit = follow.as_ref().is_some_and(|(followed, handle)| *followed == matches && !handle.is_finished());
+        if let Some(resume) = resume {
+          if !same_unit {
+            info!("Resuming the log stream of {}", unit.name);
+            follow = Some((matches.clone(), follow_logs(tx.clone(), unit, matches, Some(resume))));
+          }
+          continue;
+        }
+        if !same_unit {
+          if let Some((_, handle)) = follow.take() {
+            info!("Cancelling previous journalctl task");
+            handle.abort();
+          }
+        }
+
+        // get the unit file path
+        match systemd::get_unit_file_location(&unit) {
+          Ok(path) => {
+            let _ = tx.send(Action::SetUnitFilePath { unit: unit.clone(), path: Ok(path) });
+            let _ = tx.send(Action::Render);
+          },
+          Err(e) => {
+            // Fix this!!! Set the path to an error enum variant instead of a string
+            let _ =
+              tx.send(Action::SetUnitFilePath { unit: unit.clone(), path: Err("could not be determined".into()) });
+            let _ = tx.send(Action::Render);
+            error!("Error getting unit file path for {}: {}", unit.name, e);
+          },
+        }
+
+        let properties = [systemd::ACCOUNTING_PROPERTIES, systemd::TRIGGER_PROPERTIES, &["Documentation"]].concat();
+        let mut crashed = false;
+        match systemd::get_unit_properties(&unit, &properties) {
+          Ok(properties) => {
+            let accounting = Accounting::from_properties(&properties);
+            crashed = accounting.result.as_deref().is_some_and(crash::is_crash);
+            let _ = tx.send(Action::SetAccounting(vec![(unit.clone(), accounting)]));
+            let uris = docs::parse_documentation(properties.get("Documentation").map_or("", |d| d.as_str()));
+            let _ = tx.send(Action::SetDocumentation { unit: unit.clone(), uris });
+            let triggers = Triggers::from_properties(unit.scope, &properties);
+            let _ = tx.send(Action::SetTriggers { unit: unit.clone(), triggers });
+            let _ = tx.send(Action::Render);
+          },
+          Err(e) => warn!("Error getting accounting for {}: {}", unit.name, e),
+        }
+
+        if unit.name.ends_with(".service") {
+          let requirements = systemd::get_unit_properties(&unit, mounts::REQUIREMENT_PROPERTIES).and_then(|p| {
+            let mut requirements = mounts::requirements(&p);
+            let names = requirements.iter().map(|r| r.unit.clone()).collect_vec();
+            let states = systemd::get_active_states(unit.scope, &names)?;
+            for requirement in &mut requirements {
+              requirement.active_state = states.get(&requirement.unit).cloned().unwrap_or_default();
+            }
+            Ok(requirements)
+          });
+          match requirements {
+            Ok(requirements) => {
+              let _ = tx.send(Action::SetMountRequirements { unit: unit.clone(), requirements });
+              let _ = tx.send(Action::Render);
+            },
+            Err(e) => warn!("Error getting the mounts {} needs: {}", unit.name, e),
+          }
+        }
+
+        // a unit that's since run fine has its old crashes cleared
+        let evidence = if crashed { get_crash_evidence(&unit) } else { Ok(crash::Evidence::default()) };
+        match evidence {
+          Ok(evidence) => {
+            let _ = tx.send(Action::SetCrashEvidence { unit: unit.clone(), evidence });
+            let _ = tx.send(Action::Render);
+          },
+          Err(e) => warn!("Error getting OOM kills and core dumps of {}: {}", unit.name, e),
+        }
+
+        let unit_type = unit_types::unit_type(&unit.name);
+        if !unit_types::fields(unit_type).is_empty() {
+          match systemd::get_unit_property_lists(&unit, &unit_types::properties(unit_type)) {
+            Ok(lists) => {
+              let values = unit_types::values(unit_type, &lists);
+              let _ = tx.send(Action::SetTypeDetails { unit: unit.clone(), values });
+              let _ = tx.send(Action::Render);
+            },
+            Err(e) => warn!("Error getting the details of {}: {}", unit.name, e),
+          }
+        }
+
+        if unit.name.ends_with(".timer") {
+          if let Ok(times) = systemd::get_timer_times(&unit) {
+            let _ = tx.send(Action::SetTimers(vec![(unit.clone(), times)]));
+          }
+          match systemd::get_timer_schedule(&unit, 3) {
+            Ok(schedule) => {
+              let _ = tx.send(Action::SetSchedule { unit: unit.clone(), schedule });
+              let _ = tx.send(Action::Render);
+            },
+            Err(e) => warn!("Error getting the schedule of {}: {}", unit.name, e),
+          }
+        }
+
+        // First, get the N lines in a batch
+        info!("Getting logs for {}", unit.name);
+        let start = std::time::Instant::now();
+
+        let lines_arg = format!("--lines={LOG_PAGE_LINES}");
+        let mut args = vec!["--quiet", "--output=export", &lines_arg];
+
+        args.extend(matches.iter().map(String::as_str));
+
+        let boot_arg = boot.journalctl_arg();
+        args.extend(boot_arg.as_deref());
+
+        let mut journalctl = systemd::journalctl();
+        systemd::in_scope(&mut journalctl, unit.scope);
+        match remote::on_host(journalctl.args(&args)).output() {
+          Ok(output) => {
+            if output.status.success() {
+              info!("Got logs for {} in {:?}", unit.name, start.elapsed());
+              let mut logs = journal::parse_export(&output.stdout);
+
+              if include_audit {
+                let since = ResumePoint::before(&logs).map(|start| start.since);
+                match get_related_audit_lines(&unit, boot, since, &mut audit_cache, !same_unit) {
+                  Ok(audit_lines) => logs = merge_log_lines(logs, audit_lines.into_iter().map(Entry::plain).collect()),
+                  Err(e) => warn!("Error getting audit messages for {}: {}", unit.name, e),
+                }
+              }
+
+              if logs.is_empty() {
+                logs.push(Entry::plain("No logs found/available. Maybe try relaunching with `sudo systemctl-tui`"));
+              }
+              let _ = tx.send(Action::SetLogs { unit: unit.clone(), logs });
+              let _ = tx.send(Action::Render);
+            } else {
+              warn!("Error getting logs for {}: {}", unit.name, String::from_utf8_lossy(&output.stderr));
+            }
+          },
+          Err(e) => warn!("Error getting logs for {}: {}", unit.name, e),
+        }
+
+        if same_unit {
+          continue;
+        }
+
+        // Then follow the logs
+        // Splitting this into two commands is a bit of a hack that makes it easier to get the initial batch of logs
+        // This does mean that we'll miss any logs that are written between the two commands, low enough risk for now
+        follow = Some((matches.clone(), follow_logs(tx.clone(), unit, matches, None)));
+      }
+    });
+    self.watch_units();
+    // the checks are about this machine, not one reached over ssh or a container
+    if remote::elsewhere().is_none() && self_check::is_first_run() {
+      let _ = self.action_tx.as_ref().unwrap().send(Action::ShowSelfCheck { first_run: true });
+    }
+    Ok(())
+  }
+
+  fn handle_key_events(&mut self, key: KeyEvent) -> Vec<Action> {
+    // any key stops a replay rather than getting mixed in with it
+    if self.replay.take().is_some() {
+      info!("Stopped replaying the macro");
+      return vec![Action::Render];
+    }
+    if self.recording_macro {
+      let keys = [self.pending_keys.as_slice(), &[Chord::from(key)]].concat();
+      if self.mode == Mode::ServiceList
+        && self.keymap.lookup(Context::UnitList, &keys) == Lookup::Found(Command::RecordMacro)
+      {
+        self.pending_keys.clear();
+        self.recording_macro = false;
+        info!("Recorded a macro of {} keys", self.macro_keys.len());
+        return vec![Action::Render];
+      }
+      self.macro_keys.push(key);
+    }
+
+    let chord = [Chord::from(key)];
+    if let Lookup::Found(command) = self.keymap.lookup(Context::Anywhere, &chord) {
+      self.pending_keys.clear();
+      return self.run_command(command);
+    }
+
+    // pinned units are reachable from anywhere, but not in the middle of an action
+    if key.modifiers.contains(KeyModifiers::ALT) && self.mode != Mode::Processing {
+      if let Some(slot) = pinned_slot(key.code) {
+        return vec![Action::JumpToPinned { slot, logs: true }];
+      }
+    }
+
+    if self.mode == Mode::Viewer {
+      return self.viewer.handle_key_events(key);
+    }
+
+    if self.mode == Mode::Form {
+      return self.form.handle_key_events(key);
+    }
+
+    if self.mode == Mode::DepTree {
+      return self.dep_tree.handle_key_events(key);
+    }
+
+    if self.mode == Mode::Properties {
+      return self.property_inspector.handle_key_events(key);
+    }
+
+    if self.mode == Mode::MergedLogs {
+      return self.merged_logs.handle_key_events(key);
+    }
+
+    if self.mode == Mode::Jobs {
+      return self.job_queue.handle_key_events(key);
+    }
+
+    // TODO: seems like terminals can't recognize shift or ctrl at the same time as page up/down
+    // Is there another way we could scroll in large increments?
+    if let Lookup::Found(command) = self.keymap.lookup(Context::MainScreen, &chord) {
+      self.pending_keys.clear();
+      return self.run_command(command);
+    }
+
+    match self.mode {
+      Mode::ServiceList if key.code == KeyCode::Esc && self.command_line_filter().is_some() => {
+        self.name_filter = None;
+        self.state_filter.clear();
+        self.refresh_filtered_units();
+        vec![Action::Render]
+      },
+      Mode::ServiceList => self.run_keys(Context::UnitList, key),
+      Mode::Help => match key.code {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => vec![Action::ToggleHelp],
+        _ => self.viewer.handle_key_events(key),
+      },
+      Mode::Error => match key.code {
+        KeyCode::Esc | KeyCode::Enter => vec![Action::EnterMode(Mode::ServiceList)],
+        _ => vec![],
+      },
+      Mode::Search => match key.code {
+        KeyCode::Esc => vec![Action::EnterMode(Mode::ServiceList)],
+        KeyCode::Enter => vec![Action::EnterMode(Mode::ActionMenu)],
+        KeyCode::Down | KeyCode::Tab => {
+          self.next();
+          vec![Action::EnterMode(Mode::ServiceList)]
+        },
+        KeyCode::Up => {
+          self.previous();
+          vec![Action::EnterMode(Mode::ServiceList)]
+        },
+        _ => {
+          let prev_search_value = self.input.value().to_owned();
+          self.input.handle_event(&crossterm::event::Event::Key(key));
+
+          // if the search value changed, filter the list
+          if prev_search_value != self.input.value() {
+            self.refresh_filtered_units();
+          }
+          vec![Action::Render]
+        },
+      },
+      Mode::ActionMenu => match key.code {
+        KeyCode::Esc => vec![Action::EnterMode(Mode::ServiceList)],
+        KeyCode::Down | KeyCode::Char('j') => {
+          self.menu_items.next();
+          vec![Action::Render]
+        },
+        KeyCode::Up | KeyCode::Char('k') => {
+          self.menu_items.previous();
+          vec![Action::Render]
+        },
+        KeyCode::Enter | KeyCode::Char(' ') => match self.menu_items.selected() {
+          Some(i) if self.refuses(&i.action) => vec![Action::EnterError(READ_ONLY_REFUSAL.into())],
+          Some(i) => vec![i.action.clone()],
+          None => vec![Action::EnterMode(Mode::ServiceList)],
+        },
+        _ => {
+          for item in self.menu_items.items.iter() {
+            if let Some(key_code) = item.key {
+              if key_code == key.code {
+                // grayed-out items don't run, e.g. editing, which app.rs handles before anything here could refuse it
+                if self.refuses(&item.action) {
+                  return vec![Action::EnterError(READ_ONLY_REFUSAL.into())];
+                }
+                return vec![item.action.clone()];
+              }
+            }
+          }
+          vec![]
+        },
+      },
+      Mode::Processing => match key.code {
+        KeyCode::Esc => vec![Action::CancelTask],
+        _ => vec![],
+      },
+      Mode::Logs if key.code == KeyCode::Esc && self.log_search.is_active() => {
+        self.log_search.clear();
+        vec![Action::Render]
+      },
+      Mode::Logs => self.run_keys(Context::LogPane, key),
+      Mode::LogSearch => match key.code {
+        KeyCode::Esc => {
+          self.log_search.clear();
+          vec![Action::EnterMode(Mode::Logs)]
+        },
+        KeyCode::Enter => {
+          let line = self.log_search.next_match();
+          self.scroll_to_log_line(line);
+          vec![Action::EnterMode(Mode::Logs)]
+        },
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+          self.log_search.set_query(self.log_search_input.value(), !self.log_search.regex);
+          vec![Action::Render]
+        },
+        _ => {
+          self.log_search_input.handle_event(&crossterm::event::Event::Key(key));
+          if self.log_search_input.value() != self.log_search.query {
+            self.log_search.set_query(self.log_search_input.value(), self.log_search.regex);
+          }
+          vec![Action::Render]
+        },
+      },
+      Mode::CommandBar => match key.code {
+        KeyCode::Esc => vec![Action::EnterMode(Mode::ServiceList)],
+        KeyCode::Enter => {
+          let line = self.command_input.value().to_string();
+          if line.trim().is_empty() {
+            return vec![Action::EnterMode(Mode::ServiceList)];
+          }
+          self.mode = Mode::ServiceList;
+          self.submit_command_line(&line).into_iter().collect()
+        },
+        KeyCode::Tab => {
+          let line = self.command_input.value();
+          if let Some(suggestion) = command_bar::suggestions(line, &self.unit_names(), 1).first() {
+            self.command_input = Input::new(command_bar::complete(line, suggestion));
+          }
+          vec![Action::Render]
+        },
+        KeyCode::Up | KeyCode::Down => {
+          let line = if key.code == KeyCode::Up { self.command_history.older() } else { self.command_history.newer() };
+          if let Some(line) = line {
+            self.command_input = Input::new(line.to_string());
+          }
+          vec![Action::Render]
+        },
+        _ => {
+          self.command_input.handle_event(&crossterm::event::Event::Key(key));
+          vec![Action::Render]
+        },
+      },
+      Mode::Viewer | Mode::Form | Mode::DepTree | Mode::Properties | Mode::MergedLogs | Mode::Jobs => vec![],
+      Mode::SignalMenu | Mode::RunMenu | Mode::Prompt => match key.code {
+        KeyCode::Esc => vec![Action::EnterMode(Mode::ServiceList)],
+        KeyCode::Down | KeyCode::Char('j') => {
+          self.menu_items.next();
+          vec![Action::Render]
+        },
+        KeyCode::Up | KeyCode::Char('k') => {
+          self.menu_items.previous();
+          vec![Action::Render]
+        },
+        KeyCode::Enter | KeyCode::Char(' ') => match self.menu_items.selected() {
+          Some(i) => vec![i.action.clone()],
+          None => vec![Action::EnterMode(Mode::ServiceList)],
+        },
+        _ => {
+          for item in self.menu_items.items.iter() {
+            if let Some(key_code) = item.key {
+              if key_code == key.code {
+                return vec![item.action.clone()];
+              }
+            }
+          }
+          vec![]
+        },
+      },
+    }
+  }
+
+  fn handle_mouse_events(&mut self, event: MouseEvent) -> Vec<Action> {
+    let (column, row) = (event.column, event.row);
+    // the unit list and the log pane, as opposed to popups over them
+    let on_panes = matches!(self.mode, Mode::ServiceList | Mode::Search | Mode::Logs);
+    match event.kind {
+      MouseEventKind::ScrollDown | MouseEventKind::ScrollUp => {
+        let down = event.kind == MouseEventKind::ScrollDown;
+        if !on_panes {
+          // popups scroll, or move their selection, like with the arrow keys
+          return self.handle_key_events(KeyEvent::from(if down { KeyCode::Down } else { KeyCode::Up }));
+        }
+        if self.mouse_areas.in_logs(column, row) {
+          let lines = mouse::WHEEL_LINES;
+          vec![if down { Action::ScrollDown(lines) } else { Action::ScrollUp(lines) }, Action::Render]
+        } else if self.mouse_areas.in_list(column, row) {
+          let by = if down { mouse::WHEEL_LINES as isize } else { -(mouse::WHEEL_LINES as isize) };
+          let selected = self.filtered_units.state.selected();
+          // the wheel stops at the ends rather than wrapping around
+          let index = moved(selected, self.filtered_units.items.len(), by, false);
+          if index.is_some() && index != selected {
+            self.select(index, true);
+          }
+          vec![Action::Render]
+        } else {
+          vec![]
+        }
+      },
+      MouseEventKind::Down(MouseButton::Left) => {
+        if let Some(key) = self.mouse_areas.hint_at(column, row) {
+          return self.handle_key_events(KeyEvent::from(key));
+        }
+        if !on_panes {
+          return vec![];
+        }
+        let (offset, units) = (self.filtered_units.state.offset(), self.filtered_units.items.len());
+        if let Some(index) = self.mouse_areas.unit_at(column, row, offset, units) {
+          if self.mode == Mode::Logs {
+            self.mode = Mode::ServiceList;
+          }
+          if self.filtered_units.state.selected() != Some(index) {
+            self.select(Some(index), true);
+          }
+          vec![Action::Render]
+        } else if self.mouse_areas.in_logs(column, row) && self.mode != Mode::Logs {
+          vec![Action::EnterMode(Mode::Logs)]
+        } else {
+          vec![]
+        }
+      },
+      _ => vec![],
+    }
+  }
+
+  fn dispatch(&mut self, action: Action) -> Option<Action> {
+    if self.refuses(&action) {
+      return Some(Action::EnterError(READ_ONLY_REFUSAL.into()));
+    }
+    if let Some(equivalent) = equivalent::for_action(&action) {
+      self.last_equivalent = Some((equivalent, self.dry_run));
+    }
+    match action {
+      Action::ToggleShowLogger => {
+        self.show_logger = !self.show_logger;
+        return Some(Action::Render);
+      },
+      Action::EnterMode(mode) => {
+        if mode == Mode::ActionMenu {
+          if let Some(selected) = self.filtered_units.selected() {
+            let mut menu_items = vec![
+              MenuItem::new("Start", Action::StartService(selected.id()), Some(KeyCode::Char('s'))),
+              MenuItem::new("Stop", Action::StopService(selected.id()), Some(KeyCode::Char('t'))),
+              MenuItem::new("Restart", Action::RestartService(selected.id()), Some(KeyCode::Char('r'))),
+              MenuItem::new("Reload", Action::ReloadService(selected.id()), Some(KeyCode::Char('l'))),
+              MenuItem::new("Kill", Action::EnterMode(Mode::SignalMenu), Some(KeyCode::Char('k'))),
+              MenuItem::new("Show local changes", Action::ShowUnitDelta(selected.id()), Some(KeyCode::Char('d'))),
+              MenuItem::new("Show sandboxing", Action::ShowSandboxing(selected.id()), Some(KeyCode::Char('h'))),
+              MenuItem::new("Show processes", Action::ShowProcesses(selected.id()), Some(KeyCode::Char('x'))),
+              MenuItem::new(
+                "Show listening ports and sockets",
+                Action::ShowListening(selected.id()),
+                Some(KeyCode::Char('O')),
+              ),
+              MenuItem::new("Show all properties", Action::ShowProperties(selected.id()), Some(KeyCode::Char('A'))),
+              MenuItem::new(
+                "Show unit file and drop-ins",
+                Action::ShowUnitFiles(selected.id()),
+                Some(KeyCode::Char('C')),
+              ),
+              MenuItem::new("Show past runs", Action::ShowRunHistory(selected.id()), Some(KeyCode::Char('p'))),
+              MenuItem::new("Show activity timeline", Action::ShowTimeline(selected.id()), Some(KeyCode::Char('a'))),
+              MenuItem::new(
+                "Show actions taken on it",
+                Action::ShowActionLog(Some(selected.id())),
+                Some(KeyCode::Char('H')),
+              ),
+              MenuItem::new(
+                "Show what it waited on at boot",
+                Action::ShowBootWait(selected.id()),
+                Some(KeyCode::Char('w')),
+              ),
+              MenuItem::new(
+                "Export dependency graph",
+                Action::OpenExportGraph(selected.id()),
+                Some(KeyCode::Char('g')),
+              ),
+              MenuItem::new("Enable", Action::EnableService(selected.id()), Some(KeyCode::Char('n'))),
+              MenuItem::new("Disable", Action::DisableService(selected.id()), Some(KeyCode::Char('i'))),
+              MenuItem::new(
+                "Reenable: put its symlinks back",
+                Action::ReenableService(selected.id()),
+                Some(KeyCode::Char('I')),
+              ),
+              MenuItem::new(
+                "Apply its preset: enable or disable it as the presets say",
+                Action::PresetService(selected.id()),
+                Some(KeyCode::Char('U')),
+              ),
+              MenuItem::new(
+                if self.favorites.contains(&selected.id()) { "Unstar" } else { "Star, to list it first" },
+                Action::ToggleFavorite(selected.id()),
+                Some(KeyCode::Char('*')),
+              ),
+              MenuItem::new(
+                if selected.load_state == "masked" { "Unmask" } else { "Mask" },
+                mask_action(selected),
+                Some(KeyCode::Char('z')),
+              ),
+            ];
+            if selected.is_failed() {
+              menu_items.push(MenuItem::new(
+                "Reset failed state",
+                Action::ResetFailed(selected.id()),
+                Some(KeyCode::Char('F')),
+              ));
+            }
+            if self.is_frozen(&selected.id()) {
+              let action = Action::FreezeUnit { unit: selected.id(), thaw: true };
+              menu_items.push(MenuItem::new("Thaw: resume its processes", action, Some(KeyCode::Char('Z'))));
+            } else if selected.is_active() {
+              let action = Action::FreezeUnit { unit: selected.id(), thaw: false };
+              menu_items.push(MenuItem::new("Freeze: pause its processes", action, Some(KeyCode::Char('Z'))));
+            }
+            if unit_types::can_clean(&selected.name) {
+              menu_items.push(MenuItem::new(
+                "Clean cache, state, logs...",
+                Action::OpenClean(selected.id()),
+                Some(KeyCode::Char('W')),
+              ));
+            }
+
+            if !self.marked.is_empty() {
+              let count = self.marked.len();
+              menu_items.push(MenuItem::new(
+                &format!("Enable {count} marked units"),
+                Action::OpenBulkEnablement { verb: EnablementVerb::Enable },
+                Some(KeyCode::Char('N')),
+              ));
+              menu_items.push(MenuItem::new(
+                &format!("Disable {count} marked units"),
+                Action::OpenBulkEnablement { verb: EnablementVerb::Disable },
+                Some(KeyCode::Char('D')),
+              ));
+              menu_items.push(MenuItem::new(
+                &format!("Reenable {count} marked units"),
+                Action::OpenBulkEnablement { verb: EnablementVerb::Reenable },
+                Some(KeyCode::Char('X')),
+              ));
+              menu_items.push(MenuItem::new(
+                &format!("Apply the presets of {count} marked units"),
+                Action::OpenBulkEnablement { verb: EnablementVerb::Preset(preset::PresetMode::Full) },
+                Some(KeyCode::Char('Q')),
+              ));
+              for (verb, key) in [(BatchVerb::Start, 'S'), (BatchVerb::Stop, 'T'), (BatchVerb::Restart, 'R')] {
+                menu_items.push(MenuItem::new(
+                  &format!("{} {count} marked units", verb.name()),
+                  Action::ConfirmBatch(verb),
+                  Some(KeyCode::Char(key)),
+                ));
+              }
+              menu_items.push(MenuItem::new(
+                &format!("Follow the logs of {count} marked units together"),
+                Action::FollowMergedLogs(self.marked.iter().cloned().collect()),
+                Some(KeyCode::Char('L')),
+              ));
+            }
+
+            menu_items.push(MenuItem::new(
+              "Show journal usage",
+              Action::ShowJournalUsage(selected.id()),
+              Some(KeyCode::Char('J')),
+            ));
+            if let Some(days) = self.config.journal_retention_days {
+              menu_items.push(MenuItem::new(
+                &format!("Vacuum journal older than {days} days"),
+                Action::VacuumJournal(selected.id()),
+                Some(KeyCode::Char('v')),
+              ));
+            }
+
+            if selected.name.ends_with(".socket") {
+              menu_items.push(MenuItem::new(
+                "Poke: test socket activation",
+                Action::PokeSocket(selected.id()),
+                Some(KeyCode::Char('o')),
+              ));
+            }
+
+            if let Some(multiplexer) = Multiplexer::detect() {
+              menu_items.push(MenuItem::new(
+                &format!("Follow logs in a {} pane", multiplexer.label()),
+                Action::FollowLogsInPane(selected.id()),
+                Some(KeyCode::Char('f')),
+              ));
+            }
+
+            for (i, plugin) in self.config.plugins.iter().enumerate() {
+              let key = char::from_digit(i as u32 + 1, 10).filter(|_| i < 9).map(KeyCode::Char);
+              menu_items.push(MenuItem::new(&plugin.name, Action::RunPlugin { unit: selected.id(), plugin: i }, key));
+            }
+
+            if selected.activation_state == "failed" {
+              menu_items.push(MenuItem::new(
+                "Show boot context",
+                Action::ShowBootContext(selected.id()),
+                Some(KeyCode::Char('b')),
+              ));
+              menu_items.push(MenuItem::new(
+                "Show OOM kills and core dumps",
+                Action::ShowCrash(selected.id()),
+                Some(KeyCode::Char('K')),
+              ));
+              if mounts::is_mount(&selected.name) {
+                menu_items.push(MenuItem::new(
+                  "Diagnose mount failure",
+                  Action::ShowMountDiagnostics(selected.id()),
+                  Some(KeyCode::Char('m')),
+                ));
+                menu_items.push(MenuItem::new(
+                  "Reload and retry mount",
+                  Action::RetryMount(selected.id()),
+                  Some(KeyCode::Char('M')),
+                ));
+              }
+            }
+
+            if selected.name.ends_with(".service") {
+              menu_items.push(MenuItem::new(
+                "Show environment",
+                Action::ShowEnvironment(selected.id()),
+                Some(KeyCode::Char('y')),
+              ));
+              menu_items.push(MenuItem::new(
+                "Override environment variables",
+                Action::OpenSetEnvironment(selected.id()),
+                Some(KeyCode::Char('Y')),
+              ));
+              menu_items.push(if self.watches.contains_key(&selected.id()) {
+                MenuItem::new(
+                  "Stop restarting on file changes",
+                  Action::UnwatchFiles(selected.id()),
+                  Some(KeyCode::Char('B')),
+                )
+              } else {
+                MenuItem::new(
+                  "Restart on file changes...",
+                  Action::OpenWatchFiles(selected.id()),
+                  Some(KeyCode::Char('B')),
+                )
+              });
+            }
+            menu_items.push(MenuItem::new(
+              "Set resource properties",
+              Action::OpenSetProperties(selected.id()),
+              Some(KeyCode::Char('P')),
+            ));
+            let fragment_path = selected.file_path.clone().and_then(Result::ok);
+            menu_items.push(MenuItem::new(
+              "Edit drop-in override",
+              Action::EditDropIn { unit: selected.id(), fragment_path },
+              Some(KeyCode::Char('E')),
+            ));
+            if let Some(Ok(file_path)) = &selected.file_path {
+              menu_items.push(MenuItem::new("Copy unit file path", Action::CopyUnitFilePath, Some(KeyCode::Char('c'))));
+              menu_items.push(MenuItem::new(
+                "Edit unit file",
+                Action::EditUnitFile { unit: selected.id(), path: file_path.clone() },
+                Some(KeyCode::Char('e')),
+              ));
+              menu_items.push(MenuItem::new(
+                "Verify unit file",
+                Action::VerifyUnitFile { unit: selected.id(), path: file_path.clone() },
+                Some(KeyCode::Char('V')),
+              ));
+              menu_items.push(MenuItem::new(
+                "Duplicate unit file",
+                Action::OpenDuplicateUnit { unit: selected.id(), path: file_path.clone() },
+                Some(KeyCode::Char('u')),
+              ));
+            }
+
+            self.menu_items = StatefulList::with_items(menu_items);
+            self.menu_items.state.select(Some(0));
+          } else {
+            return None;
+          }
+        } else if mode == Mode::SignalMenu {
+          if let Some(selected) = self.filtered_units.selected() {
+            let signals = vec![
+              ("SIGTERM", KeyCode::Char('t')),
+              ("SIGHUP", KeyCode::Char('h')),
+              ("SIGINT", KeyCode::Char('i')),
+              ("SIGQUIT", KeyCode::Char('q')),
+              ("SIGKILL", KeyCode::Char('k')),
+              ("SIGUSR1", KeyCode::Char('1')),
+              ("SIGUSR2", KeyCode::Char('2')),
+            ];
+
+            let mut menu_items: Vec<MenuItem> = signals
+              .into_iter()
+              .map(|(name, key_code)| {
+                let action = Action::KillService { unit: selected.id(), signal: name.into(), whom: KillWhom::All };
+                MenuItem::new(name, action, Some(key_code))
+              })
+              .collect();
+            menu_items.push(MenuItem::new(
+              "Other signal, or only some processes...",
+              Action::OpenKill(selected.id()),
+              Some(KeyCode::Char('o')),
+            ));
+
+            self.menu_items = StatefulList::with_items(menu_items);
+            self.menu_items.state.select(Some(0));
+          } else {
+            return None;
+          }
+        }
+
+        if mode != Mode::MergedLogs {
+          if let Some(task) = self.merged_logs_task.take() {
+            task.abort();
+          }
+        }
+        // closing the queue stops polling it, once whatever's in it is done
+        if self.mode == Mode::Jobs && !matches!(mode, Mode::Jobs | Mode::Processing) {
+          self.watch_jobs(false);
+        }
+        self.mode = mode;
+        return Some(Action::Render);
+      },
+      Action::EnterError(err) => {
+        tracing::error!(err);
+        self.error_message = err;
+        return Some(Action::EnterMode(Mode::Error));
+      },
+      Action::SelectUnit(unit) => {
+        if !self.all_units.contains_key(&unit) {
+          return Some(Action::EnterError(format!("{} isn't in the unit list", unit.name)));
+        }
+        self.mode = Mode::ServiceList;
+        if !self.filtered_units.items.iter().any(|u| u.id() == unit) {
+          self.input = Input::default();
+          self.refresh_filtered_units();
+        }
+        if let Some(index) = self.filtered_units.items.iter().position(|u| u.id() == unit) {
+          self.select(Some(index), true);
+        }
+        return Some(Action::Render);
+      },
+      Action::JumpToPinned { slot, logs } => {
+        let Some(name) = self.config.pinned.get(slot) else {
+          return Some(Action::EnterError(format!(
+            "Nothing is pinned to {}; add units to `pinned` in the config",
+            slot + 1
+          )));
+        };
+        // prefer the system unit when both managers have one by this name
+        let Some(unit) =
+          self.all_units.keys().filter(|id| id.name == *name).min_by_key(|id| id.scope != UnitScope::Global).cloned()
+        else {
+          return Some(Action::EnterError(format!("{name} is pinned to {} but isn't loaded", slot + 1)));
+        };
+        if logs {
+          self.page_logs_for = Some(unit.clone());
+        }
+        return Some(Action::SelectUnit(unit));
+      },
+      Action::SetSearch(text) => {
+        self.mode = Mode::Search;
+        self.input = Input::new(text);
+        self.refresh_filtered_units();
+        return Some(Action::Render);
+      },
+      Action::ToggleHelp => {
+        if self.mode != Mode::Help {
+          self.previous_mode = Some(self.mode);
+          self.mode = Mode::Help;
+          let title = format!("Help for {} v{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+          self.viewer = Viewer::new(title, self.help_lines(), self.config.accessible);
+        } else {
+          self.mode = self.previous_mode.unwrap_or(Mode::Search);
+        }
+        return Some(Action::Render);
+      },
+      Action::CopyUnitFilePath => {
+        if let Some(selected) = self.filtered_units.selected() {
+          if let Some(Ok(file_path)) = &selected.file_path {
+            match clipboard::set_clipboard(file_path) {
+              Ok(_) => return Some(Action::EnterMode(Mode::ServiceList)),
+              Err(e) => return Some(Action::EnterError(format!("Error copying to clipboard: {e}"))),
+            }
+          } else {
+            return Some(Action::EnterError("No unit file path available".into()));
+          }
+        }
+      },
+      Action::CopyText { what, text } => match clipboard::set_clipboard(&text) {
+        Ok(_) => {
+          info!("Copied {what} to the clipboard");
+          return Some(Action::Render);
+        },
+        Err(e) => return Some(Action::EnterError(format!("Error copying to clipboard: {e}"))),
+      },
+      Action::FollowLogsInPane(unit) => {
+        let Some(multiplexer) = Multiplexer::detect() else {
+          return Some(Action::EnterError("Not running inside tmux or zellij".into()));
+        };
+        return match multiplexer.follow_logs(&unit) {
+          Ok(()) => Some(Action::EnterMode(Mode::ServiceList)),
+          Err(e) => Some(Action::EnterError(e.to_string())),
+        };
+      },
+      Action::ShowViewer { title, lines } => {
+        self.process_view = None;
+        self.viewer = Viewer::new(title, lines, self.config.accessible);
+        self.mode = Mode::Viewer;
+        return Some(Action::Render);
+      },
+      Action::ShowUnitDelta(unit) => {
+        let title = format!("Local changes to {}", unit.name);
+        let tx = self.action_tx.clone().unwrap();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = unit_delta_lines(&unit);
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::ShowEnvironment(unit) => {
+        let title = format!("Environment of {}", unit.name);
+        let tx = self.action_tx.clone().unwrap();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = match unit_environment::effective(&unit) {
+            Ok(effective) => environment_lines(&effective),
+            Err(e) => vec![Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red)))],
+          };
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::OpenWatchFiles(unit) => {
+        if let Err(e) = remote::ensure_local("Watching files") {
+          return Some(Action::EnterError(format!("{e:#}")));
+        }
+        let fields = vec![Field::text("Paths", "")];
+        let mut form =
+          Form::new(&format!("Restart {} on file changes", unit.name), FormPurpose::WatchFiles(unit), fields);
+        form.preview = watch_files_hint();
+        return self.open_form(form);
+      },
+      Action::UnwatchFiles(unit) => {
+        if let Some(watch) = self.watches.remove(&unit) {
+          info!("No longer restarting {} on changes, after {} restarts", unit.name, watch.restarts);
+        }
+        return Some(Action::EnterMode(Mode::ServiceList));
+      },
+      Action::WatchRestarted { unit, path, error } => {
+        let watch = self.watches.get_mut(&unit)?;
+        match &error {
+          Some(e) => error!("Restarting {} for {} changing failed: {e}", unit.name, path.display()),
+          None => info!("Restarted {} for {} changing", unit.name, path.display()),
+        }
+        watch.restarts += 1;
+        watch.last = Some((path, Local::now(), error));
+        return Some(Action::Render);
+      },
+      Action::OpenSetEnvironment(unit) => {
+        if let Err(e) = remote::ensure_local("Overriding the environment")
+          .and_then(|_| user_manager::ensure_own("Overriding the environment", unit.scope))
+        {
+          return Some(Action::EnterError(format!("{e:#}")));
+        }
+        let fields =
+          vec![Field::text("Variables", ""), Field::choice("Afterwards", &["reload and restart", "just reload"])];
+        let title = format!("Override the environment of {}", unit.name);
+        let mut form = Form::new(&title, FormPurpose::SetEnvironment(unit.clone()), fields);
+        let dim = Style::default().add_modifier(Modifier::DIM);
+        let mut lines = vec![Line::from(Span::styled(
+          "NAME=value to set one, a bare NAME to drop an override set here. Quote values with spaces",
+          dim,
+        ))];
+        form.preview = lines.clone();
+        let tx = self.action_tx.clone().unwrap();
+        tokio::task::spawn_blocking(move || {
+          let Ok(path) = unit_environment::drop_in_path(&unit) else { return };
+          let overrides = unit_environment::parse_drop_in(&std::fs::read_to_string(&path).unwrap_or_default());
+          lines.push(Line::from(Span::styled(format!("Written to {}", path.display()), dim)));
+          if !overrides.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from("Overridden so far"));
+            lines.extend(overrides.iter().map(|(name, value)| Line::from(format!("  {name}={value}"))));
+          }
+          let _ = tx.send(Action::SetFormPreview { field: "Variables".into(), value: String::new(), lines });
+        });
+        return self.open_form(form);
+      },
+      Action::SetEnvironment { unit, changes, restart } => self.set_environment(unit, changes, restart),
+      Action::ShowDeltaAudit => {
+        let title = "Local changes to unit files".to_string();
+        let tx = self.action_tx.clone().unwrap();
+        let scopes = self.unit_scopes();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = delta_audit_lines(&scopes);
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::ShowOrderingCycles => {
+        let title = "Ordering cycles".to_string();
+        let tx = self.action_tx.clone().unwrap();
+        let scopes = self.unit_scopes();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = ordering_cycle_lines(&scopes);
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::ShowBootPlan => {
+        let title = "Next boot".to_string();
+        let tx = self.action_tx.clone().unwrap();
+        let scopes = self.unit_scopes();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = boot_plan_lines(&scopes);
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::ShowBootFailures => {
+        let title = "Failures by boot".to_string();
+        let tx = self.action_tx.clone().unwrap();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = boot_failures_lines();
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::RunPlugin { unit, plugin: index } => {
+        let plugin = self.config.plugins.get(index)?.clone();
+        // the last output stays up until the new one's in
+        self.plugin_output.entry((index, unit.clone())).or_insert_with(|| vec![Line::from("Running...")]);
+        self.details_tab = Some(index);
+        let tx = self.action_tx.clone().unwrap();
+        tokio::task::spawn_blocking(move || {
+          let lines = plugin_lines(&plugin, &unit);
+          let _ = tx.send(Action::SetPluginOutput { unit, plugin: index, lines });
+        });
+        return Some(Action::EnterMode(Mode::ServiceList));
+      },
+      Action::SetPluginOutput { unit, plugin, lines } => {
+        self.plugin_output.insert((plugin, unit), lines);
+        return Some(Action::Render);
+      },
+      Action::ShowActionLog(unit) => {
+        let title = match &unit {
+          Some(unit) => format!("Actions taken on {} through systemctl-tui", unit.name),
+          None => "Actions taken through systemctl-tui".into(),
+        };
+        let tx = self.action_tx.clone().unwrap();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let _ = tx.send(Action::ShowViewer { title, lines: action_log_lines(unit.as_ref()) });
+        });
+        return Some(loading);
+      },
+      Action::ShowSelfCheck { first_run } => {
+        let title = "What works here".to_string();
+        let tx = self.action_tx.clone().unwrap();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Checking...")] };
+        tokio::spawn(async move {
+          let checks = self_check::run().await;
+          if first_run {
+            if let Err(e) = self_check::mark_done() {
+              warn!("Error remembering the first-run check: {e:#}");
+            }
+          }
+          let _ = tx.send(Action::ShowViewer { title, lines: self_check_lines(&checks, first_run) });
+        });
+        return Some(loading);
+      },
+      Action::ShowSessionStats => {
+        let lines = session_stats_lines(&self.session, self.config.graph_style);
+        return Some(Action::ShowViewer { title: "State changes this session".into(), lines });
+      },
+      Action::ShowChanges => {
+        let now = Local::now();
+        let lines = changes_lines(&self.changes, self.changes_seen, now);
+        self.changes_seen = Some(now);
+        return Some(Action::ShowViewer { title: "What changed in the last hour".into(), lines });
+      },
+      Action::ShowSliceTree => {
+        let title = "Slices".to_string();
+        let tx = self.action_tx.clone().unwrap();
+        let scopes = self.unit_scopes();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = slice_tree_lines(&scopes);
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::ShowPresets => {
+        let title = "Preset rules".to_string();
+        let tx = self.action_tx.clone().unwrap();
+        let units: Vec<UnitId> = self.all_units.keys().cloned().collect();
+        let selected = self.filtered_units.selected().map(|u| u.id());
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = preset_lines(&units, selected.as_ref());
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::ShowBootContext(unit) => {
+        let title = format!("Boot context for {}", unit.name);
+        let tx = self.action_tx.clone().unwrap();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = match get_boot_context(&unit) {
+            Ok(logs) => boot_context_lines(&unit, logs),
+            Err(e) => vec![Line::from(Span::styled(e.to_string(), Style::default().fg(Color::Red)))],
+          };
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::OpenBootTimes => {
+        self.prompt_title = "Boot times".into();
+        self.prompt_lines = vec![];
+        let items = vec![
+          MenuItem::new(
+            "Slowest first (blame)",
+            Action::ShowBootTimes(boot_wait::Order::Slowest),
+            Some(KeyCode::Char('b')),
+          ),
+          MenuItem::new("In start order", Action::ShowBootTimes(boot_wait::Order::Started), Some(KeyCode::Char('o'))),
+          MenuItem::new(
+            "What the boot waited on (critical chain)",
+            Action::ShowCriticalChain,
+            Some(KeyCode::Char('c')),
+          ),
+        ];
+        self.menu_items = StatefulList::with_items(items);
+        self.menu_items.state.select(Some(0));
+        self.mode = Mode::Prompt;
+        return Some(Action::Render);
+      },
+      Action::ShowBootTimes(order) => {
+        let title = "Boot times".to_string();
+        let tx = self.action_tx.clone().unwrap();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = boot_times_lines(order);
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::ShowCriticalChain => {
+        let title = "Critical chain".to_string();
+        let tx = self.action_tx.clone().unwrap();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = critical_chain_lines();
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::ShowUnitFiles(unit) => {
+        let title = format!("Unit file of {}", unit.name);
+        let tx = self.action_tx.clone().unwrap();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = match systemd::get_unit_files(&unit) {
+            Ok(files) if files.is_empty() => vec![Line::from(format!("{} has no unit file", unit.name))],
+            Ok(files) => unit_file_lines(&files),
+            Err(e) => vec![Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red)))],
+          };
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::ShowBootWait(unit) => {
+        let title = format!("Boot wait for {}", unit.name);
+        let tx = self.action_tx.clone().unwrap();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = boot_wait_lines(&unit);
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::ShowRunHistory(unit) => {
+        let tx = self.action_tx.clone().unwrap();
+        tokio::task::spawn_blocking(move || {
+          let action = match systemd::get_invocations(&unit) {
+            Ok(runs) if runs.is_empty() => {
+              Action::EnterError(format!("The journal has no record of {} running", unit.name))
+            },
+            Ok(runs) => Action::SetRunHistory { unit, runs },
+            Err(e) => Action::EnterError(e.to_string()),
+          };
+          let _ = tx.send(action);
+        });
+        return Some(Action::EnterMode(Mode::ServiceList));
+      },
+      Action::SetRunHistory { unit, runs } => {
+        let menu_items = runs
+          .into_iter()
+          .rev()
+          .enumerate()
+          .map(|(i, run)| {
+            // the nine most recent runs get number keys
+            let key = char::from_digit(i as u32 + 1, 10).filter(|_| i < 9).map(KeyCode::Char);
+            MenuItem::new(&run_label(&run), Action::ShowRunLogs { unit: unit.clone(), run }, key)
+          })
+          .collect();
+        self.menu_items = StatefulList::with_items(menu_items);
+        self.menu_items.state.select(Some(0));
+        return Some(Action::EnterMode(Mode::RunMenu));
+      },
+      Action::ShowRunLogs { unit, run } => {
+        let title = format!("{} run {}", unit.name, run_label(&run));
+        let tx = self.action_tx.clone().unwrap();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = match get_run_logs(&unit, &run) {
+            Ok(logs) if logs.is_empty() => vec![Line::from("Nothing was logged during this run")],
+            Ok(logs) => logs.into_iter().map(Line::from).collect(),
+            Err(e) => vec![Line::from(Span::styled(e.to_string(), Style::default().fg(Color::Red)))],
+          };
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::ShowDependencyTree(unit) => {
+        self.dep_tree = DepTree::new(&unit, self.theme, self.config.accessible);
+        self.mode = Mode::DepTree;
+        let tx = self.action_tx.clone().unwrap();
+        tokio::task::spawn_blocking(move || {
+          let properties = deptree::STATE_PROPERTIES.iter().chain(deptree::RELATIONSHIPS).copied().collect_vec();
+          match systemd::get_all_unit_properties(unit.scope, &properties) {
+            Ok(units) => {
+              let units = units.into_iter().filter_map(|p| Some((p.get("Id")?.clone(), p))).collect();
+              let _ = tx.send(Action::SetDependencyUnits(units));
+            },
+            Err(e) => {
+              let _ = tx.send(Action::EnterError(format!("Error reading dependencies of {}: {e}", unit.name)));
+            },
+          }
+        });
+        return Some(Action::Render);
+      },
+      Action::ShowProperties(unit) => {
+        self.property_inspector = PropertyInspector::new(&unit, self.theme, self.config.accessible);
+        self.mode = Mode::Properties;
+        let tx = self.action_tx.clone().unwrap();
+        tokio::task::spawn_blocking(move || {
+          let properties = systemd::get_all_properties(&unit).map_err(|e| format!("{e:#}"));
+          let _ = tx.send(Action::SetInspectedProperties { unit, properties });
+        });
+        return Some(Action::Render);
+      },
+      Action::ShowJobs => {
+        self.job_queue = JobQueue::new(self.theme, self.config.accessible);
+        self.mode = Mode::Jobs;
+        self.watch_jobs(true);
+        return Some(Action::Render);
+      },
+      Action::SetJobs(jobs) => {
+        if let Ok(jobs) = &jobs {
+          self.jobs = jobs.clone();
+        }
+        self.job_queue.set_jobs(jobs);
+        return Some(Action::Render);
+      },
+      Action::CancelJob { unit, id } => {
+        let cancel_token = CancellationToken::new();
+        let future = systemd::cancel_job(unit.scope, id);
+        self.service_action(unit, format!("Cancel job {id}"), cancel_token, future);
+      },
+      Action::OpenPowerMenu => {
+        let tx = self.action_tx.clone().unwrap();
+        tokio::spawn(async move {
+          let menu = async {
+            let mut available = vec![];
+            for &action in PowerAction::ALL {
+              // "challenge" needs a password we can't ask for, so only what logind would just do is offered
+              if power::availability(action).await? == "yes" {
+                available.push(action);
+              }
+            }
+            anyhow::Ok((available, power::hostname().await?))
+          };
+          let _ = tx.send(Action::SetPowerMenu(menu.await.map_err(|e| format!("{e:#}"))));
+        });
+      },
+      Action::SetPowerMenu(menu) => {
+        let (available, hostname) = match menu {
+          Ok(menu) => menu,
+          Err(e) => return Some(Action::EnterError(format!("Can't tell what logind allows: {e}"))),
+        };
+        if available.is_empty() {
+          return Some(Action::EnterError(format!("logind won't reboot, power off or suspend {hostname} for you")));
+        }
+        self.prompt_title = format!("Power: {hostname}");
+        self.prompt_lines = vec![Line::from(Span::styled(
+          "Each asks for the hostname before going ahead",
+          Style::default().add_modifier(Modifier::DIM),
+        ))];
+        let keys = |action| match action {
+          PowerAction::Reboot => 'r',
+          PowerAction::PowerOff => 'p',
+          PowerAction::Halt => 'h',
+          PowerAction::Suspend => 's',
+          PowerAction::Hibernate => 'H',
+        };
+        let mut items = available
+          .into_iter()
+          .map(|action| {
+            let confirm = Action::ConfirmPower { action, hostname: hostname.clone() };
+            MenuItem::new(action.name(), confirm, Some(KeyCode::Char(keys(action))))
+          })
+          .collect_vec();
+        items.push(MenuItem::new("Cancel", Action::EnterMode(Mode::ServiceList), Some(KeyCode::Char('n'))));
+        let cancel = items.len() - 1;
+        self.menu_items = StatefulList::with_items(items);
+        self.menu_items.state.select(Some(cancel));
+        self.mode = Mode::Prompt;
+        return Some(Action::Render);
+      },
+      Action::ConfirmPower { action, hostname } => {
+        let title = format!("{} {hostname}", action.name());
+        let purpose = FormPurpose::Power { action, hostname: hostname.clone() };
+        let mut form = Form::new(&title, purpose, vec![Field::text("Hostname", "")]);
+        let consequence = match action {
+          PowerAction::Suspend | PowerAction::Hibernate => "It stays unreachable until something wakes it up",
+          _ => "Every unit on it stops, along with everything you're running there",
+        };
+        form.preview = vec![
+          Line::from(format!("Type {hostname} to {} it", action.verb())),
+          Line::from(Span::styled(consequence, Style::default().add_modifier(Modifier::DIM))),
+        ];
+        return self.open_form(form);
+      },
+      Action::RunPowerAction(action) if self.dry_run => {
+        let dim = Style::default().add_modifier(Modifier::DIM);
+        let lines = vec![
+          Line::from(Span::styled("Would run", Style::default().add_modifier(Modifier::BOLD))),
+          Line::from(format!("  systemctl {}", action.verb())),
+          Line::from(Span::styled("  which is the D-Bus call", dim)),
+          Line::from(format!("  org.freedesktop.login1.Manager.{}(false)", action.method())),
+          Line::from(""),
+          Line::from(Span::styled("Nothing was changed. Press d in the unit list to turn dry run off", dim)),
+        ];
+        return Some(Action::ShowViewer { title: format!("Dry run: systemctl {}", action.verb()), lines });
+      },
+      Action::RunPowerAction(action) => {
+        let tx = self.action_tx.clone().unwrap();
+        info!("{}", action.name());
+        tokio::spawn(async move {
+          let result = power::run(action).await;
+          let outcome = match &result {
+            Ok(_) => "ok".to_string(),
+            Err(e) => format!("failed: {e:#}"),
+          };
+          // about the whole machine, so logged against the system manager
+          record_action(action_log::Entry::new(UnitScope::Global, "", action.name(), &outcome));
+          if let Err(e) = result {
+            let _ = tx.send(Action::EnterError(format!("{e:#}")));
+          }
+        });
+      },
+      Action::OpenBootTargets => {
+        let tx = self.action_tx.clone().unwrap();
+        tokio::task::spawn_blocking(move || {
+          let targets = systemd::get_boot_targets(UnitScope::Global)
+            .and_then(|targets| Ok((targets, systemd::get_default_target(UnitScope::Global)?)));
+          let _ = tx.send(Action::SetBootTargets(targets.map_err(|e| format!("{e:#}"))));
+        });
+      },
+      Action::SetBootTargets(targets) => {
+        let (targets, default) = match targets {
+          Ok(targets) => targets,
+          Err(e) => return Some(Action::EnterError(e)),
+        };
+        self.prompt_title = "Boot targets".into();
+        self.prompt_lines = vec![
+          Line::from(format!("Boots into {default}")),
+          Line::from(Span::styled(
+            "Pick one to make it the default or switch to it now; either asks for its name first",
+            Style::default().add_modifier(Modifier::DIM),
+          )),
+        ];
+        let width = targets.iter().map(|t| t.name.chars().count()).max().unwrap_or(0);
+        let mut items = targets
+          .iter()
+          .map(|target| {
+            let mut label = format!("{:width$}  {}", target.name, target.description);
+            if target.name == default {
+              label.push_str(" (default)");
+            }
+            if target.active {
+              label.push_str(" (active)");
+            }
+            let action = Action::PickBootTarget { target: target.name.clone(), is_default: target.name == default };
+            MenuItem::new(&label, action, None)
+          })
+          .collect_vec();
+        items.push(MenuItem::new("Cancel", Action::EnterMode(Mode::ServiceList), Some(KeyCode::Char('n'))));
+        let selected = targets.iter().position(|t| t.name == default).unwrap_or(items.len() - 1);
+        self.menu_items = StatefulList::with_items(items);
+        self.menu_items.state.select(Some(selected));
+        self.mode = Mode::Prompt;
+        return Some(Action::Render);
+      },
+      Action::PickBootTarget { target, is_default } => {
+        self.prompt_title = target.clone();
+        self.prompt_lines = vec![Line::from(Span::styled(
+          "Isolating starts it and stops every unit it doesn't need, like a desktop session when going to multi-user",
+          Style::default().add_modifier(Modifier::DIM),
+        ))];
+        let mut items = vec![];
+        if !is_default {
+          let confirm = Action::ConfirmBootTarget { target: target.clone(), isolate: false };
+          items.push(MenuItem::new("Boot into it from now on (set-default)", confirm, Some(KeyCode::Char('d'))));
+        }
+        let confirm = Action::ConfirmBootTarget { target, isolate: true };
+        items.push(MenuItem::new("Switch to it now (isolate)", confirm, Some(KeyCode::Char('i'))));
+        items.push(MenuItem::new("Cancel", Action::EnterMode(Mode::ServiceList), Some(KeyCode::Char('n'))));
+        self.menu_items = StatefulList::with_items(items);
+        self.menu_items.state.select(Some(0));
+        return Some(Action::Render);
+      },
+      Action::ConfirmBootTarget { target, isolate } => {
+        let (title, consequence) = if isolate {
+          (format!("Isolate {target}"), "Every unit it doesn't need stops now, along with what's running in it")
+        } else {
+          (format!("Make {target} the default"), "The machine boots into it from the next boot on")
+        };
+        let purpose = FormPurpose::BootTarget { target: target.clone(), isolate };
+        let mut form = Form::new(&title, purpose, vec![Field::text("Target", "")]);
+        form.preview = vec![
+          Line::from(format!("Type {target} to go ahead")),
+          Line::from(Span::styled(consequence, Style::default().add_modifier(Modifier::DIM))),
+        ];
+        return self.open_form(form);
+      },
+      Action::OfferEscalation { unit, error, command } => {
+        self.prompt_title = format!("Not allowed to change {}", unit.name);
+        self.prompt_lines = vec![
+          Line::from(error),
+          Line::from(Span::styled(
+            format!("Retrying runs {} in the terminal, which asks for a password", command.join(" ")),
+            Style::default().add_modifier(Modifier::DIM),
+          )),
+        ];
+        let mut items = escalation::methods(unit.scope)
+          .into_iter()
+          .map(|method| {
+            let key = match method {
+              escalation::Method::Polkit => 'p',
+              escalation::Method::Sudo => 's',
+            };
+            let action = Action::RunEscalated { unit: unit.clone(), command: method.command(&command) };
+            MenuItem::new(method.label(), action, Some(KeyCode::Char(key)))
+          })
+          .collect_vec();
+        items.push(MenuItem::new("Cancel", Action::EnterMode(Mode::ServiceList), Some(KeyCode::Char('n'))));
+        self.menu_items = StatefulList::with_items(items);
+        self.menu_items.state.select(Some(0));
+        self.mode = Mode::Prompt;
+        return Some(Action::Render);
+      },
+      Action::EscalationDone { unit, command, error } => {
+        let outcome = error.as_ref().map_or("ok".to_string(), |e| format!("failed: {e}"));
+        record_action(action_log::Entry::new(unit.scope, &unit.name, &command.join(" "), &outcome));
+        if let Some(error) = error {
+          return Some(Action::EnterError(error));
+        }
+        self.mode = Mode::ServiceList;
+        return Some(Action::RefreshServices);
+      },
+      Action::FollowMergedLogs(units) => {
+        if let Some(task) = self.merged_logs_task.take() {
+          task.abort();
+        }
+        let tx = self.action_tx.clone().unwrap();
+        self.merged_logs_task = Some(follow_merged_logs(tx, units.clone()));
+        self.merged_logs = MergedLogs::new(units, self.theme, self.config.accessible);
+        self.merged_logs.timestamps = self.log_timestamps;
+        self.mode = Mode::MergedLogs;
+        return Some(Action::Render);
+      },
+      Action::AppendMergedLogs { units, lines } if self.merged_logs.units == units => self.merged_logs.append(lines),
+      Action::AppendMergedLogs { .. } => {},
+      Action::SetInspectedProperties { unit, properties } => {
+        if self.property_inspector.unit.as_ref() == Some(&unit) {
+          self.property_inspector.set_properties(properties);
+        }
+        return Some(Action::Render);
+      },
+      Action::SetDependencyUnits(units) => {
+        self.dep_tree.set_units(units);
+        return Some(Action::Render);
+      },
+      Action::OpenDependencyMenu(unit) => {
+        if !self.all_units.contains_key(&unit) {
+          return Some(Action::EnterError(format!(
+            "{} isn't in the unit list, so there's no action menu for it. Start, stop and restart work from the tree",
+            unit.name
+          )));
+        }
+        let _ = self.dispatch(Action::SelectUnit(unit));
+        return Some(Action::EnterMode(Mode::ActionMenu));
+      },
+      Action::ShowTimeline(unit) => {
+        let title = format!("Timeline of {}", unit.name);
+        let tx = self.action_tx.clone().unwrap();
+        let theme = self.theme;
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = timeline_lines(&unit, &theme);
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::ShowJournalUsage(unit) => {
+        let title = format!("Journal usage of {}", unit.name);
+        let tx = self.action_tx.clone().unwrap();
+        let retention_days = self.config.journal_retention_days;
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Reading the journal...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = match measure_journal_usage(&unit, retention_days) {
+            Ok((usage, journal_bytes, namespace)) => {
+              let lines = journal_usage_lines(&usage, journal_bytes, namespace.as_deref(), retention_days);
+              let _ = tx.send(Action::SetJournalUsage { unit, usage, journal_bytes });
+              lines
+            },
+            Err(e) => vec![Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red)))],
+          };
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::SetJournalUsage { unit, usage, journal_bytes } => {
+        self.journal_usage.insert(unit, (usage, journal_bytes));
+      },
+      Action::VacuumJournal(unit) => {
+        let days = self.config.journal_retention_days?;
+        let title = format!("Vacuuming logs older than {days} days");
+        if self.dry_run {
+          let lines = vec![
+            Line::from(format!(
+              "Would run journalctl --vacuum-time={days}d, with --namespace if {} has one",
+              unit.name
+            )),
+            Line::from(Span::styled("Dry run: nothing was deleted", Style::default().add_modifier(Modifier::DIM))),
+          ];
+          return Some(Action::ShowViewer { title, lines });
+        }
+        let tx = self.action_tx.clone().unwrap();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Vacuuming...")] };
+        tokio::task::spawn_blocking(move || {
+          let namespace = log_namespace(&unit);
+          let result = systemd::vacuum_journal(namespace.as_deref(), days);
+          let outcome = match &result {
+            Ok(_) => "ok".to_string(),
+            Err(e) => format!("failed: {e}"),
+          };
+          record_action(action_log::Entry::new(unit.scope, &unit.name, "Vacuum journal", &outcome));
+          let mut lines = vec![Line::from(match &namespace {
+            Some(namespace) => format!("Only the {namespace} namespace, which {} logs to, was vacuumed", unit.name),
+            None => format!("{} logs to the main journal, so all units' old entries went", unit.name),
+          })];
+          lines.push(Line::from(""));
+          match result {
+            Ok(output) => lines.extend(output.into_iter().map(Line::from)),
+            Err(e) => lines.push(Line::from(Span::styled(e.to_string(), Style::default().fg(Color::Red)))),
+          }
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::PokeSocket(unit) => {
+        let title = format!("Poking {}", unit.name);
+        let tx = self.action_tx.clone().unwrap();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Connecting...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = socket_poke_lines(&unit);
+          let _ = tx.send(Action::ShowViewer { title, lines });
+          let _ = tx.send(Action::RefreshServices);
+        });
+        return Some(loading);
+      },
+      Action::ShowListening(unit) => {
+        let title = format!("What {} listens on", unit.name);
+        let tx = self.action_tx.clone().unwrap();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = listening_lines(&unit);
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::ShowMountDiagnostics(unit) => {
+        let title = format!("Why {} failed", unit.name);
+        let tx = self.action_tx.clone().unwrap();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = mount_diagnostics_lines(&unit);
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::RetryMount(unit) => self.retry_mount(unit),
+      Action::ShowProcesses(unit) => {
+        if remote::elsewhere().is_some() {
+          return Some(Action::EnterError("Processes can only be listed on this machine".into()));
+        }
+        let title = format!("Processes of {}", unit.name);
+        self.viewer = Viewer::new(title, vec![Line::from("Loading...")], self.config.accessible);
+        self.mode = Mode::Viewer;
+        self.process_view = Some((unit, None));
+        self.refresh_processes();
+        return Some(Action::Render);
+      },
+      Action::SetProcesses { unit, processes } => {
+        let showing = self.mode == Mode::Viewer;
+        let (_, sample) = self.process_view.as_mut().filter(|(shown, _)| showing && *shown == unit)?;
+        self.viewer.lines = match &processes {
+          Ok(processes) => process_lines(processes, sample.as_ref()),
+          Err(e) => vec![Line::from(Span::styled(e.clone(), Style::default().fg(Color::Red)))],
+        };
+        *sample = processes.ok().map(|p| process_tree::Sample::of(&p));
+        return Some(Action::Render);
+      },
+      Action::ShowSandboxing(unit) => {
+        let title = format!("Sandboxing for {}", unit.name);
+        let tx = self.action_tx.clone().unwrap();
+        let accessible = self.config.accessible;
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = sandboxing_lines(&unit, accessible);
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      // these write unit files on this machine
+      Action::OpenNewServiceWizard
+      | Action::OpenNewTimerWizard
+      | Action::OpenCrontabImport
+      | Action::OpenDuplicateUnit { .. }
+        if remote::elsewhere().is_some() || self.creatable_scopes().is_empty() =>
+      {
+        let e = remote::ensure_local("Creating units")
+          .and_then(|_| user_manager::ensure_own("Creating units", UnitScope::User))
+          .unwrap_err();
+        return Some(Action::EnterError(format!("{e:#}")));
+      },
+      Action::OpenNewServiceWizard => {
+        let fields = vec![
+          Field::text("Name", ""),
+          Field::text("Description", ""),
+          Field::text("ExecStart", ""),
+          Field::text("User", ""),
+          Field::text("WorkingDirectory", ""),
+          Field::units("After", "", self.unit_names()),
+          Field::choice("Restart", &["on-failure", "always", "no"]),
+          Field::choice("Hardening", HardeningPreset::NAMES),
+          Field::choice("Scope", self.creatable_scopes()),
+          Field::choice("Afterwards", &["enable and start", "just create"]),
+        ];
+        let action = self.open_form(Form::new("New service", FormPurpose::NewService, fields));
+        self.show_user_field();
+        return action;
+      },
+      Action::FormChanged if self.form.purpose == FormPurpose::NewService && self.form.focused_label() == "Scope" => {
+        self.show_user_field();
+      },
+      Action::OpenRunTransient => {
+        let scopes = self.unit_scopes();
+        let managers = scopes.iter().map(|s| if *s == UnitScope::User { "user" } else { "system" }).collect_vec();
+        let fields = vec![
+          Field::text("Command", ""),
+          Field::choice("Run in", &["service", "scope"]),
+          Field::choice("Manager", &managers),
+          Field::text("Name", ""),
+          Field::text("User", ""),
+          Field::text("WorkingDirectory", ""),
+          Field::text("MemoryMax", ""),
+        ];
+        let mut form = Form::new("Run a command", FormPurpose::RunTransient, fields);
+        let dim = Style::default().add_modifier(Modifier::DIM);
+        form.preview = vec![
+          Line::from("Started as a transient unit, which goes away once it stops, like systemd-run does"),
+          Line::from(Span::styled(
+            "A service is run by the manager; a scope keeps a command started here. The name defaults to run-<time>",
+            dim,
+          )),
+        ];
+        return self.open_form(form);
+      },
+      Action::OpenNewTimerWizard => {
+        let fields = vec![
+          Field::text("Name", ""),
+          Field::text("Description", ""),
+          Field::text("Command", ""),
+          Field::text("OnCalendar", "daily"),
+          Field::choice("Persistent", &["yes", "no"]),
+          Field::choice("Scope", self.creatable_scopes()),
+          Field::choice("Afterwards", &["enable and start", "just create"]),
+        ];
+        let action = self.open_form(Form::new("New timer", FormPurpose::NewTimer, fields));
+        self.preview_calendar();
+        return action;
+      },
+      Action::FormChanged
+        if self.form.purpose == FormPurpose::NewTimer && self.form.focused_label() == "OnCalendar" =>
+      {
+        self.preview_calendar();
+      },
+      Action::OpenCrontabImport => {
+        let fields = vec![
+          Field::text("Crontab", ""),
+          Field::text("Name prefix", "cron"),
+          Field::choice("Persistent", &["yes", "no"]),
+          Field::choice("Scope", self.creatable_scopes()),
+          Field::choice("Afterwards", &["enable and start", "just create"]),
+        ];
+        let action = self.open_form(Form::new("Import crontab", FormPurpose::ImportCrontab, fields));
+        self.preview_crontab();
+        return action;
+      },
+      Action::FormChanged if self.form.purpose == FormPurpose::ImportCrontab => self.preview_crontab(),
+      Action::OpenDuplicateUnit { unit, path } => {
+        // foo.service -> foo-copy.service, getty@tty1.service -> getty-copy@tty1.service
+        let split = unit.name.find(['@', '.']).unwrap_or(unit.name.len());
+        let suggestion = format!("{}-copy{}", &unit.name[..split], &unit.name[split..]);
+        let title = format!("Duplicate {}", unit.name);
+        let form =
+          Form::new(&title, FormPurpose::DuplicateUnit { source: unit, path }, vec![Field::text("Name", &suggestion)]);
+        let action = self.open_form(form);
+        self.preview_duplicate();
+        return action;
+      },
+      Action::FormChanged if matches!(self.form.purpose, FormPurpose::DuplicateUnit { .. }) => self.preview_duplicate(),
+      Action::OpenInstantiate(template) => {
+        let scopes = self.unit_scopes();
+        let mut scope_names = scopes
+          .iter()
+          .map(|scope| match scope {
+            UnitScope::Global => "system",
+            UnitScope::User => "user",
+          })
+          .collect_vec();
+        // the template's own scope first
+        if template.as_ref().is_some_and(|t| t.scope == UnitScope::User) {
+          scope_names.reverse();
+        }
+        let mut fields = vec![
+          Field::units("Template", template.as_ref().map_or("", |t| t.name.as_str()), vec![]),
+          Field::text("Instance", ""),
+          Field::choice("Afterwards", &["start", "enable and start", "enable"]),
+        ];
+        if scope_names.len() > 1 {
+          fields.push(Field::choice("Scope", &scope_names));
+        }
+        let mut form = Form::new("New instance of a template", FormPurpose::Instantiate, fields);
+        // straight to the instance name when the template's already known
+        form.focused = usize::from(template.is_some());
+        let action = self.open_form(form);
+        self.preview_instantiate();
+
+        let tx = self.action_tx.clone().unwrap();
+        tokio::task::spawn_blocking(move || {
+          let templates =
+            scopes.into_iter().flat_map(|scope| systemd::get_templates(scope).unwrap_or_default()).unique().collect();
+          let _ = tx.send(Action::SetTemplates(templates));
+        });
+        return action;
+      },
+      Action::SetTemplates(templates) if self.form.purpose == FormPurpose::Instantiate => {
+        if let Some(field) = self.form.fields.first_mut() {
+          field.completions = templates;
+        }
+        return Some(Action::Render);
+      },
+      Action::SetTemplates(_) => {},
+      Action::FormChanged if self.form.purpose == FormPurpose::Instantiate => self.preview_instantiate(),
+      Action::OpenPipeLogs => {
+        let unit = self.selected_service()?;
+        let fields = vec![Field::text("Command", &self.last_pipe_command)];
+        let mut form = Form::new(&format!("Pipe logs of {}", unit.name), FormPurpose::PipeLogs(unit), fields);
+        let dim = Style::default().add_modifier(Modifier::DIM);
+        form.preview = vec![
+          Line::from(Span::styled("Runs with `sh -c`, with the log lines shown now on stdin", dim)),
+          Line::from(Span::styled("e.g. grep -i error | cut -c1-80", dim)),
+        ];
+        return self.open_form(form);
+      },
+      Action::OpenExportLogs => {
+        let unit = self.selected_service()?;
+        let fields = vec![Field::text("File", &format!("{}.log", unit.name))];
+        let mut form = Form::new(&format!("Save logs of {}", unit.name), FormPurpose::ExportLogs, fields);
+        let dim = Style::default().add_modifier(Modifier::DIM);
+        form.preview = vec![Line::from(Span::styled(
+          format!("Saves the {} lines loaded now, relative to the current directory", self.logs.len()),
+          dim,
+        ))];
+        return self.open_form(form);
+      },
+      Action::OpenLogFilters if self.log_filters.is_empty() => return Some(Action::OpenAddLogFilter),
+      Action::OpenLogFilters => {
+        self.prompt_title = "Log filters".into();
+        self.prompt_lines = vec![Line::from("Lines have to pass every filter to be shown")];
+        let mut items = vec![MenuItem::new("Add a filter", Action::OpenAddLogFilter, Some(KeyCode::Char('a')))];
+        for (i, filter) in self.log_filters.filters.iter().enumerate() {
+          let key = char::from_digit(i as u32 + 1, 10).map(KeyCode::Char);
+          items.push(MenuItem::new(&format!("Remove {}", filter.spec), Action::RemoveLogFilter(Some(i)), key));
+        }
+        items.push(MenuItem::new("Remove all of them", Action::RemoveLogFilter(None), Some(KeyCode::Char('c'))));
+        self.menu_items = StatefulList::with_items(items);
+        self.menu_items.state.select(Some(0));
+        self.mode = Mode::Prompt;
+        return Some(Action::Render);
+      },
+      Action::OpenAddLogFilter => {
+        let fields = vec![Field::text("Pattern", "")];
+        let mut form = Form::new("Filter the logs", FormPurpose::LogFilter, fields);
+        let dim = Style::default().add_modifier(Modifier::DIM);
+        form.preview = vec![
+          Line::from(Span::styled("Only lines matching it are shown, or with a leading ! only lines that don't", dim)),
+          Line::from(Span::styled("A regex, e.g. timed out|refused or !healthcheck", dim)),
+          Line::from(Span::styled("Or a journal field's exact value, e.g. _PID=812", dim)),
+        ];
+        return self.open_form(form);
+      },
+      Action::AddLogFilter(filter) => return self.add_log_filter(filter),
+      Action::InspectLogEntry => {
+        let line = self.selected_log_entry()?;
+        let Some(fields) = line.fields.clone() else {
+          return Some(Action::EnterError(
+            "The line at the top of the logs isn't a journal entry, so it has no fields".into(),
+          ));
+        };
+        let (theme, accessible) = (self.theme, self.config.accessible);
+        self.property_inspector = PropertyInspector::journal_entry(&line.text, &fields, theme, accessible);
+        self.mode = Mode::Properties;
+        return Some(Action::Render);
+      },
+      Action::RemoveLogFilter(index) => {
+        match index {
+          Some(i) if i < self.log_filters.filters.len() => {
+            self.log_filters.filters.remove(i);
+          },
+          Some(_) => {},
+          None => self.log_filters.filters.clear(),
+        }
+        // the lines filtered out are gone, so read them again
+        self.logs_scroll_offset = 0;
+        self.get_logs();
+        return Some(Action::EnterMode(Mode::Logs));
+      },
+      Action::OpenExportGraph(unit) => {
+        let fields = vec![
+          Field::choice(
+            "Direction",
+            &[depgraph::Direction::Dependencies.label(), depgraph::Direction::Dependents.label()],
+          ),
+          Field::text("Depth", "3"),
+          Field::text("File", &format!("{}.dot", unit.name)),
+        ];
+        let mut form =
+          Form::new(&format!("Export dependency graph of {}", unit.name), FormPurpose::ExportGraph(unit), fields);
+        let dim = Style::default().add_modifier(Modifier::DIM);
+        form.preview = vec![Line::from(Span::styled("Name the file .svg to render it with Graphviz", dim))];
+        return self.open_form(form);
+      },
+      Action::OpenReplayMacro => {
+        if self.macro_keys.is_empty() {
+          return Some(Action::EnterError(
+            "No macro recorded yet. Press m to start recording and m again to stop".into(),
+          ));
+        }
+        let each = format!("each of the {} listed units", self.filtered_units.items.len());
+        let fields = vec![Field::choice("Replay on", &["selected unit", &each])];
+        let mut form = Form::new("Replay macro", FormPurpose::ReplayMacro, fields);
+        let keys = self.macro_keys.iter().map(keymacro::key_label).join(" ");
+        form.preview =
+          vec![Line::from(Span::styled(format!("Keys: {keys}"), Style::default().add_modifier(Modifier::DIM)))];
+        return self.open_form(form);
+      },
+      Action::ReplayStep => {
+        let mut replay = self.replay.take()?;
+        match replay.step() {
+          Step::Select(unit) => {
+            self.mode = Mode::ServiceList;
+            match self.filtered_units.items.iter().position(|u| u.id() == unit) {
+              Some(index) => self.select(Some(index), true),
+              // gone from the list since the replay started
+              None => replay.next = replay.keys.len(),
+            }
+          },
+          Step::Press(key) => {
+            let tx = self.action_tx.clone().unwrap();
+            for action in self.handle_key_events(key) {
+              let _ = tx.send(action);
+            }
+          },
+          Step::Done => {
+            info!("Finished replaying the macro");
+            return Some(Action::Render);
+          },
+        }
+        self.replay = Some(replay);
+        let tx = self.action_tx.clone().unwrap();
+        tokio::spawn(async move {
+          tokio::time::sleep(keymacro::REPLAY_KEY_INTERVAL).await;
+          let _ = tx.send(Action::ReplayStep);
+        });
+        return Some(Action::Render);
+      },
+      Action::OpenBulkEnablement { verb } => {
+        let units = self.marked.iter().cloned().collect_vec();
+        return self.open_enablement(units, verb);
+      },
+      Action::EnableService(unit) => return self.open_enablement(vec![unit], EnablementVerb::Enable),
+      Action::DisableService(unit) => return self.open_enablement(vec![unit], EnablementVerb::Disable),
+      Action::ReenableService(unit) => return self.open_enablement(vec![unit], EnablementVerb::Reenable),
+      Action::PresetService(unit) => {
+        return self.open_enablement(vec![unit], EnablementVerb::Preset(preset::PresetMode::Full))
+      },
+      Action::OpenPresetAll(scope) => return self.open_preset_all(scope),
+      Action::ConfirmBatch(verb) => {
+        let units = self.marked.iter().cloned().collect_vec();
+        self.prompt_title = format!("{} {} marked units?", verb.name(), units.len());
+        self.prompt_lines = batch_lines(verb, &units, &self.all_units);
+        let protected =
+          units.iter().filter(|u| self.config.confirm_pattern(&u.name).is_some()).map(|u| &u.name).join(", ");
+        let protected = verb != BatchVerb::Start && !protected.is_empty();
+        if protected {
+          let warning = format!("Includes {protected}, from confirm-units");
+          self.prompt_lines.insert(0, Line::from(Span::styled(warning, Style::default().fg(Color::Red))));
+        }
+        let items = vec![
+          MenuItem::new(&format!("{} them", verb.name()), Action::RunBatch(verb), Some(KeyCode::Char('y'))),
+          MenuItem::new("Cancel", Action::EnterMode(Mode::ServiceList), Some(KeyCode::Char('n'))),
+        ];
+        self.menu_items = StatefulList::with_items(items);
+        self.menu_items.state.select(Some(if protected { 1 } else { 0 }));
+        self.mode = Mode::Prompt;
+        return Some(Action::Render);
+      },
+      Action::RunBatch(verb) => return self.run_batch(verb),
+      Action::OpenRunningSets => {
+        let sets = match running_set::list(remote::elsewhere().as_deref()) {
+          Ok(sets) => sets,
+          Err(e) => return Some(Action::EnterError(format!("{e:#}"))),
+        };
+        self.prompt_title = "Running units".into();
+        self.prompt_lines = vec![];
+        let mut items =
+          vec![MenuItem::new("Save what's running now", Action::SaveRunningSet, Some(KeyCode::Char('s')))];
+        for (set, key) in sets.into_iter().zip('1'..='9') {
+          let name = format!("Restore {} ({} units)", set.taken.format("%Y-%m-%d %H:%M"), set.units.len());
+          items.push(MenuItem::new(&name, Action::PreviewRestore(set), Some(KeyCode::Char(key))));
+        }
+        self.menu_items = StatefulList::with_items(items);
+        self.menu_items.state.select(Some(0));
+        self.mode = Mode::Prompt;
+        return Some(Action::Render);
+      },
+      Action::SaveRunningSet => {
+        let set = RunningSet::capture(self.all_units.values(), &self.unit_scopes(), remote::elsewhere());
+        let lines = match set.save() {
+          Ok(path) => {
+            let mut lines = vec![
+              Line::from(format!("Saved {} running units to {}", set.units.len(), path.display())),
+              Line::from(Span::styled(
+                format!(
+                  "Press {} in the unit list to restore them",
+                  self.keymap.describe(Command::RunningSets).unwrap_or_default()
+                ),
+                Style::default().add_modifier(Modifier::DIM),
+              )),
+              Line::from(""),
+            ];
+            lines.extend(set.units.iter().map(|u| Line::from(format!("  {}", u.name))));
+            lines
+          },
+          Err(e) => vec![Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red)))],
+        };
+        return Some(Action::ShowViewer { title: "Save running units".into(), lines });
+      },
+      Action::PreviewRestore(set) => {
+        let plan = set.plan(&self.all_units.values().cloned().collect_vec());
+        self.prompt_title = format!("Restore running units from {}", set.taken.format("%Y-%m-%d %H:%M"));
+        self.prompt_lines = restore_lines(&plan);
+        let items = if plan.is_empty() {
+          vec![MenuItem::new("Close", Action::EnterMode(Mode::ServiceList), Some(KeyCode::Char('n')))]
+        } else {
+          vec![
+            MenuItem::new("Start and stop them", Action::RestoreRunningSet(plan), Some(KeyCode::Char('y'))),
+            MenuItem::new("Cancel", Action::EnterMode(Mode::ServiceList), Some(KeyCode::Char('n'))),
+          ]
+        };
+        self.menu_items = StatefulList::with_items(items);
+        self.menu_items.state.select(Some(0));
+        self.mode = Mode::Prompt;
+        return Some(Action::Render);
+      },
+      Action::RestoreRunningSet(plan) => {
+        let jobs = plan
+          .stop
+          .into_iter()
+          .map(|unit| (BatchVerb::Stop, unit))
+          .chain(plan.start.into_iter().map(|unit| (BatchVerb::Start, unit)))
+          .collect_vec();
+        return Some(self.run_jobs("Restore running units".into(), jobs));
+      },
+      Action::FormChanged if matches!(self.form.purpose, FormPurpose::BulkEnablement { .. }) => {
+        self.preview_bulk_enablement()
+      },
+      Action::FormChanged if matches!(self.form.purpose, FormPurpose::PresetAll(_)) => self.preview_preset_all(),
+      Action::OpenManagerEnvironment => {
+        let fields = vec![
+          Field::text("Variable", ""),
+          Field::choice("Action", &["set", "unset", "import from session"]),
+          Field::choice("Scope", self.creatable_scopes()),
+        ];
+        let action = self.open_form(Form::new("Manager environment", FormPurpose::ManagerEnvironment, fields));
+        self.refresh_environment(|_| Ok(None));
+        return action;
+      },
+      Action::OpenUserSwitcher => {
+        let users = user_manager::ensure_root()
+          .and_then(|_| remote::ensure_local("Showing other users' units"))
+          .and_then(|_| user_manager::list());
+        let users = match users {
+          Ok(users) => users,
+          Err(e) => return Some(Action::EnterError(format!("{e:#}"))),
+        };
+        self.prompt_title = "Show user units of".into();
+        self.prompt_lines = vec![Line::from(Span::styled(
+          "Users that are logged in or have lingering enabled",
+          Style::default().add_modifier(Modifier::DIM),
+        ))];
+        let current = user_manager::user();
+        let mark = |user: Option<&user_manager::User>| if user == current.as_ref() { " (shown)" } else { "" };
+        let mut items =
+          vec![MenuItem::new(&format!("Yourself{}", mark(None)), Action::SwitchUser(None), Some(KeyCode::Char('0')))];
+        // root's own manager is "yourself"
+        for (user, key) in users.into_iter().filter(|u| u.uid != 0).zip('1'..='9') {
+          let name = format!("{} ({}){}", user.name, user.uid, mark(Some(&user)));
+          items.push(MenuItem::new(&name, Action::SwitchUser(Some(user)), Some(KeyCode::Char(key))));
+        }
+        self.menu_items = StatefulList::with_items(items);
+        self.menu_items.state.select(Some(0));
+        self.mode = Mode::Prompt;
+        return Some(Action::Render);
+      },
+      Action::SwitchUser(user) => return self.switch_user(user),
+      Action::OpenMachineSwitcher => {
+        if let Some(host) = remote::host() {
+          return Some(Action::EnterError(format!("Only containers on this machine can be managed, not on {host}")));
+        }
+        let machines = match machine::list() {
+          Ok(machines) => machines,
+          Err(e) => return Some(Action::EnterError(format!("{e:#}"))),
+        };
+        self.prompt_title = "Manage the units of".into();
+        self.prompt_lines = vec![Line::from(Span::styled(
+          "Containers registered with systemd-machined, like systemctl -M. Only their system units are shown",
+          Style::default().add_modifier(Modifier::DIM),
+        ))];
+        let current = machine::machine();
+        let mark = |name: Option<&String>| if name == current.as_ref() { " (shown)" } else { "" };
+        let mut items = vec![MenuItem::new(
+          &format!("This machine{}", mark(None)),
+          Action::SwitchMachine(None),
+          Some(KeyCode::Char('0')),
+        )];
+        for (found, key) in machines.into_iter().zip('1'..='9') {
+          let name = format!("{} ({}){}", found.name, found.service, mark(Some(&found.name)));
+          items.push(MenuItem::new(&name, Action::SwitchMachine(Some(found.name)), Some(KeyCode::Char(key))));
+        }
+        self.menu_items = StatefulList::with_items(items);
+        self.menu_items.state.select(Some(0));
+        self.mode = Mode::Prompt;
+        return Some(Action::Render);
+      },
+      Action::SwitchMachine(name) => return self.switch_machine(name),
+      Action::CycleScope => return self.cycle_scope(),
+      Action::OpenThemePicker => {
+        self.prompt_title = "Theme".into();
+        self.prompt_lines = vec![Line::from(Span::styled(
+          "Only for now. Set `theme` in config.toml to keep one",
+          Style::default().add_modifier(Modifier::DIM),
+        ))];
+        let items = theme::names(&self.config.themes)
+          .into_iter()
+          .zip('1'..='9')
+          .map(|(name, key)| {
+            let current = theme::resolve(&name, &self.config.themes)
+              .is_ok_and(|t| t.with_palette(self.config.palette) == self.theme);
+            let label = if current { format!("{name} (current)") } else { name.clone() };
+            MenuItem::new(&label, Action::SetTheme(name), Some(KeyCode::Char(key)))
+          })
+          .collect();
+        self.menu_items = StatefulList::with_items(items);
+        self.menu_items.state.select(Some(0));
+        self.mode = Mode::Prompt;
+        return Some(Action::Render);
+      },
+      Action::SetTheme(name) => {
+        self.mode = Mode::ServiceList;
+        match theme::resolve(&name, &self.config.themes) {
+          Ok(theme) => self.set_theme(theme.with_palette(self.config.palette)),
+          Err(e) => return Some(Action::EnterError(format!("{e:#}"))),
+        }
+        return Some(Action::Render);
+      },
+      Action::OpenHostSwitcher => {
+        let fields = vec![Field::text("Host", &remote::host().unwrap_or_default())];
+        let mut form = Form::new("Switch host", FormPurpose::SwitchHost, fields);
+        form.preview = vec![
+          Line::from("[user@]machine[:port], reached with ssh like systemctl -H. Leave empty for this machine"),
+          Line::from(Span::styled(
+            "Only system units can be managed remotely, and unit files can't be edited there",
+            Style::default().add_modifier(Modifier::DIM),
+          )),
+        ];
+        return self.open_form(form);
+      },
+      Action::FormChanged
+        if self.form.purpose == FormPurpose::ManagerEnvironment && self.form.focused_label() == "Scope" =>
+      {
+        self.refresh_environment(|_| Ok(None));
+      },
+      Action::FormChanged if matches!(self.form.purpose, FormPurpose::SetProperties(_)) => {
+        self.preview_set_properties()
+      },
+      Action::FormChanged if matches!(self.form.purpose, FormPurpose::WatchFiles(_)) => self.preview_watch_files(),
+      Action::FormChanged => {},
+      Action::SetFormPreview { field, value, lines } if self.mode == Mode::Form && self.form.value(&field) == value => {
+        self.form.preview = lines;
+        return Some(Action::Render);
+      },
+      Action::SetFormPreview { .. } => {},
+      Action::SubmitForm => {
+        match self.form.purpose.clone() {
+          FormPurpose::DuplicateUnit { source, path } => self.submit_duplicate(source, path),
+          FormPurpose::ManagerEnvironment => self.submit_environment(),
+          FormPurpose::NewService => self.submit_new_service(),
+          FormPurpose::RunTransient => return self.submit_run_transient(),
+          FormPurpose::NewTimer => self.submit_new_timer(),
+          FormPurpose::ImportCrontab => self.submit_crontab_import(),
+          FormPurpose::PipeLogs(unit) => return self.submit_pipe_logs(unit),
+          FormPurpose::ExportGraph(unit) => return self.submit_export_graph(unit),
+          FormPurpose::ExportLogs => return self.submit_export_logs(),
+          FormPurpose::LogFilter => return self.submit_log_filter(),
+          FormPurpose::BulkEnablement { units, verb } => return self.submit_bulk_enablement(units, verb),
+          FormPurpose::PresetAll(scope) => return self.submit_preset_all(scope),
+          FormPurpose::SetProperties(unit) => return self.submit_set_properties(unit),
+          FormPurpose::SetEnvironment(unit) => return self.submit_set_environment(unit),
+          FormPurpose::WatchFiles(unit) => return self.submit_watch_files(unit),
+          FormPurpose::Kill(unit) => return self.submit_kill(unit),
+          FormPurpose::SwitchHost => return self.switch_host(),
+          FormPurpose::Instantiate => return self.submit_instantiate(),
+          FormPurpose::Power { action, hostname } => return self.submit_power(action, hostname),
+          FormPurpose::BootTarget { target, isolate } => return self.submit_boot_target(target, isolate),
+          FormPurpose::ReplayMacro => {
+            let units = if self.form.value("Replay on") == "selected unit" {
+              vec![]
+            } else {
+              self.filtered_units.items.iter().map(|u| u.id()).collect()
+            };
+            self.mode = Mode::ServiceList;
+            self.replay = Some(Replay::new(self.macro_keys.clone(), units));
+            return Some(Action::ReplayStep);
+          },
+        }
+        return Some(Action::Render);
+      },
+      Action::SetUnitFilePath { unit, path } => {
+        if let Some(unit) = self.all_units.get_mut(&unit) {
+          unit.file_path = Some(path.clone());
+        }
+        self.refresh_filtered_units(); // copy the updated unit file path to the filtered list
+      },
+      Action::SetLogs { unit, logs } => {
+        if let Some(selected) = self.filtered_units.selected() {
+          if selected.id() == unit {
+            // fewer than a page means there's nothing older to read
+            self.logs_start_reached = logs.len() < LOG_PAGE_LINES;
+            self.logs = self.log_filters.apply(logs);
+            self.log_minutes = count_log_minutes(&self.logs);
+            self.loading_older_logs = false;
+            self.logs_detached = false;
+            self.log_search.rescan(&self.logs, log_message);
+            if let Some(offset) = self.restore_logs_scroll.take() {
+              self.logs_scroll_offset = offset.min(self.logs.len().saturating_sub(1) as u16);
+            }
+            if self.page_logs_for.as_ref() == Some(&unit) {
+              self.page_logs_for = None;
+              return Some(Action::OpenLogsInPager);
+            }
+          }
+        }
+      },
+      // new lines are from the current boot, so they don't belong in a view of the previous one, nor after older
+      // lines that pushed out the newest ones
+      Action::AppendLogLines { .. } if !self.log_boot.can_follow() || self.logs_detached => {},
+      // a denial that made it fail, or that it ran into right after starting, isn't in the log stream
+      Action::ServiceActionDone(unit) if self.log_include_audit && self.selected_service().as_ref() == Some(&unit) => {
+        self.get_logs()
+      },
+      Action::FollowStopped(unit) => {
+        if self.selected_service().as_ref() != Some(&unit) || !self.log_boot.can_follow() {
+          return None;
+        }
+        let Some(resume) = ResumePoint::after(&self.logs) else {
+          self.get_logs();
+          return None;
+        };
+        let query = LogQuery {
+          unit,
+          boot: self.log_boot,
+          include_audit: self.log_include_audit,
+          current_run: self.log_current_run,
+          priority: self.log_priority,
+          resume: Some(resume),
+          older: None,
+        };
+        if let Err(e) = self.journalctl_tx.as_ref().unwrap().send(query) {
+          warn!("Error sending unit name to journalctl thread: {}", e);
+        }
+      },
+      Action::AppendLogLines { unit, lines } => {
+        if let Some(selected) = self.filtered_units.selected() {
+          if selected.id() == unit {
+            let lines = self.log_filters.apply(lines);
+            // following a busy unit appends often, so the counts are kept up to date rather than redone
+            let excess = (self.logs.len() + lines.len()).saturating_sub(MAX_LOG_LINES).min(self.logs.len());
+            tally_log_minutes(&mut self.log_minutes, &self.logs[..excess], false);
+            tally_log_minutes(&mut self.log_minutes, &lines, true);
+            let dropped = append_log_lines(&mut self.logs, lines);
+            if dropped > excess {
+              self.log_minutes = count_log_minutes(&self.logs);
+            }
+            if dropped > 0 {
+              self.log_search.drop_front(dropped);
+              self.logs_start_reached = false;
+            }
+          }
+        }
+      },
+      Action::PrependLogLines { unit, lines } => {
+        if self.selected_service().as_ref() != Some(&unit) {
+          return None;
+        }
+        self.loading_older_logs = false;
+        if lines.is_empty() {
+          self.logs_start_reached = true;
+          return None;
+        }
+        let lines = self.log_filters.apply(lines);
+        let count = lines.len();
+        let dropped = prepend_log_lines(&mut self.logs, lines);
+        self.log_minutes = count_log_minutes(&self.logs);
+        if dropped > 0 {
+          // the offset counts from the newest line
+          self.logs_scroll_offset = self.logs_scroll_offset.saturating_sub(dropped as u16);
+          self.logs_detached = true;
+        }
+        self.log_search.prepended(&self.logs, count, log_message);
+      },
+      Action::ScrollUp(offset) => {
+        self.logs_scroll_offset = self.logs_scroll_offset.saturating_sub(offset);
+        info!("scroll offset: {}", self.logs_scroll_offset);
+        if self.logs_scroll_offset == 0 && self.logs_detached {
+          self.get_logs();
+        }
+      },
+      Action::ScrollDown(offset) => {
+        self.logs_scroll_offset = self.logs_scroll_offset.saturating_add(offset);
+        info!("scroll offset: {}", self.logs_scroll_offset);
+        self.get_older_logs_if_needed();
+      },
+      Action::ScrollToTop => {
+        self.logs_scroll_offset = 0;
+        if self.logs_detached {
+          self.get_logs();
+        }
+      },
+      Action::ScrollToBottom => {
+        // TODO: this is partially broken, figure out a better way to scroll to end
+        // problem: we don't actually know the height of the paragraph before it's rendered
+        // because it's wrapped based on the width of the widget
+        // A proper fix might need to wait until ratatui improves scrolling: https://github.com/ratatui-org/ratatui/issues/174
+        self.logs_scroll_offset = self.logs.len() as u16;
+        self.get_older_logs_if_needed();
+      },
+
+      action if !self.dry_run && !self.confirmed && self.protected_units(&action).is_some() => {
+        let (verb, units) = self.protected_units(&action)?;
+        let names = units.iter().map(|(name, _)| name).join(", ");
+        self.prompt_title = format!("Really {verb} {names}?");
+        self.prompt_lines = units
+          .iter()
+          .map(|(name, pattern)| match name == pattern {
+            true => Line::from(format!("{name} is in confirm-units")),
+            false => Line::from(format!("{name} is in confirm-units, as {pattern}")),
+          })
+          .collect();
+        let items = vec![
+          MenuItem::new(&format!("Yes, {verb} {names}"), Action::Confirmed(Box::new(action)), Some(KeyCode::Char('y'))),
+          MenuItem::new("Cancel", Action::EnterMode(Mode::ServiceList), Some(KeyCode::Char('n'))),
+        ];
+        self.menu_items = StatefulList::with_items(items);
+        // a stray enter cancels
+        self.menu_items.state.select(Some(1));
+        self.mode = Mode::Prompt;
+        return Some(Action::Render);
+      },
+      Action::Confirmed(action) => {
+        self.confirmed = true;
+        let next = self.dispatch(*action);
+        self.confirmed = false;
+        return next;
+      },
+      Action::StartService(ref unit) | Action::StopService(ref unit) | Action::RestartService(ref unit)
+        if !self.dry_run && self.stale_units.contains(unit) =>
+      {
+        let verb = match action {
+          Action::StartService(_) => BatchVerb::Start,
+          Action::StopService(_) => BatchVerb::Stop,
+          _ => BatchVerb::Restart,
+        };
+        return Some(Action::ConfirmStale(verb, unit.clone()));
+      },
+      action @ (Action::StartService(_)
+      | Action::StopService(_)
+      | Action::RestartService(_)
+      | Action::ReloadService(_)
+      | Action::KillService { .. }
+      | Action::ResetFailed(_)
+      | Action::FreezeUnit { .. }
+      | Action::RunSystemctl(..)
+      | Action::EnableAccounting(_)
+      | Action::RunTransient(_))
+        if self.dry_run =>
+      {
+        let equivalent = equivalent::for_action(&action)?;
+        let title = format!("Dry run: {}", equivalent.shell());
+        let tx = self.action_tx.clone().unwrap();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = dry_run_lines(&equivalent);
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::ReloadAndRestart(unit) if self.dry_run => {
+        let commands = [Action::ReloadService(unit.clone()), Action::RestartService(unit)]
+          .iter()
+          .filter_map(equivalent::for_action)
+          .map(|e| Line::from(e.shell()))
+          .collect();
+        return Some(Action::ShowViewer { title: "Dry run: reload and restart".into(), lines: commands });
+      },
+      Action::ReloadAndRestart(unit) => self.reload_and_restart(unit),
+      Action::SetPromptLines(lines) if self.mode == Mode::Prompt => self.prompt_lines = lines,
+      Action::SetPromptLines(_) => {},
+      Action::OpenSetProperties(unit) => {
+        let fields = vec![Field::text("Assignments", ""), Field::choice("Lifetime", &["until reboot", "persistent"])];
+        let title = format!("Set properties of {}", unit.name);
+        let mut form = Form::new(&title, FormPurpose::SetProperties(unit), fields);
+        form.preview = vec![Line::from(Span::styled(
+          "e.g. CPUQuota=50% MemoryMax=1G TasksMax=200",
+          Style::default().add_modifier(Modifier::DIM),
+        ))];
+        return self.open_form(form);
+      },
+      Action::SetProperties { unit, assignments, runtime } => self.set_properties(unit, assignments, runtime),
+      Action::PromptReload { unit, path } => {
+        self.prompt_title = format!("Saved {path}");
+        self.prompt_lines = vec![Line::from("Checking it with systemd-analyze verify...")];
+        // a drop-in is checked along with the unit file it applies to
+        let drop_in = Path::new(&path).parent().is_some_and(|d| d.extension().is_some_and(|e| e == "d"));
+        let unit_file = match self.all_units.get(&unit).and_then(|u| u.file_path.clone()) {
+          Some(Ok(unit_file)) if drop_in => unit_file,
+          _ => path.clone(),
+        };
+        let tx = self.action_tx.clone().unwrap();
+        tokio::task::spawn_blocking(move || {
+          let verification = systemd::verify(&unit_file, unit.scope);
+          let passed = verification.as_ref().map_or(true, |v| v.passed);
+          let mut lines = match verification {
+            Ok(verification) if verification.diagnostics.is_empty() => vec![],
+            Ok(verification) => verification_lines(&verification),
+            Err(e) => vec![Line::from(Span::styled(
+              format!("Couldn't verify it: {e:#}"),
+              Style::default().add_modifier(Modifier::DIM),
+            ))],
+          };
+          if !lines.is_empty() {
+            lines.push(Line::from(""));
+          }
+          lines.extend(saved_settings_lines(&unit, &path));
+          let _ = tx.send(Action::OfferReload { unit, path, passed, lines });
+          let _ = tx.send(Action::Render);
+        });
+        let items = vec![MenuItem::new("Not now", Action::EnterMode(Mode::ServiceList), Some(KeyCode::Char('n')))];
+        self.menu_items = StatefulList::with_items(items);
+        self.menu_items.state.select(Some(0));
+        self.mode = Mode::Prompt;
+        return Some(Action::Render);
+      },
+      Action::OfferReload { unit, path, passed, lines }
+        if self.mode == Mode::Prompt && self.prompt_title == format!("Saved {path}") =>
+      {
+        self.prompt_lines = lines;
+        let anyway = if passed { "" } else { " anyway" };
+        let mut items = vec![
+          MenuItem::new(
+            &format!("Reload and restart {}{anyway}", unit.name),
+            Action::ReloadAndRestart(unit.clone()),
+            Some(KeyCode::Char('r')),
+          ),
+          MenuItem::new(
+            &format!("Reload only (daemon-reload){anyway}"),
+            Action::ReloadService(unit.clone()),
+            Some(KeyCode::Char('l')),
+          ),
+          MenuItem::new("Not now", Action::EnterMode(Mode::ServiceList), Some(KeyCode::Char('n'))),
+        ];
+        // fixing it comes first, since reloading would load the unit broken
+        if !passed {
+          items.rotate_right(1);
+          items
+            .insert(0, MenuItem::new("Edit it again", Action::EditUnitFile { unit, path }, Some(KeyCode::Char('e'))));
+        }
+        self.menu_items = StatefulList::with_items(items);
+        self.menu_items.state.select(Some(0));
+      },
+      Action::OfferReload { .. } => {},
+      Action::VerifyUnitFile { unit, path } => {
+        let title = format!("systemd-analyze verify {}", unit.name);
+        let tx = self.action_tx.clone().unwrap();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = match systemd::verify(&path, unit.scope) {
+            Ok(verification) => verification_lines(&verification),
+            Err(e) => vec![Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red)))],
+          };
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::StartService(service_name) => self.start_service(service_name),
+      Action::StopService(service_name) => self.stop_service(service_name),
+      Action::ReloadService(service_name) => self.reload_service(service_name),
+      Action::RestartService(service_name) => self.restart_service(service_name),
+      Action::RefreshTick if self.unit_scopes().iter().all(|s| self.live_scopes.contains(s)) => {
+        self.refresh_accounting();
+        self.refresh_pressure();
+        self.refresh_processes();
+        self.run_health_checks();
+      },
+      Action::RefreshTick => {
+        self.refresh_processes();
+        self.run_health_checks();
+        return Some(Action::RefreshServices);
+      },
+      Action::UnitsUpdated(changes) => {
+        // with the signals watched there's no full fetch to notice these are gone
+        let removed: HashSet<UnitId> = changes.removed.into_iter().collect();
+        self.all_units.retain(|id, _| !removed.contains(id));
+        self.search_index.retain(|id| !removed.contains(id));
+        self.update_units(changes.updated);
+        return Some(Action::Render);
+      },
+      Action::SetLiveUpdates { scope, live } => {
+        info!("{} {scope:?} units from the manager's signals", if live { "Updating" } else { "No longer updating" });
+        if live {
+          self.live_scopes.insert(scope);
+        } else {
+          self.live_scopes.remove(&scope);
+        }
+      },
+      Action::RefreshServices => {
+        // a slow manager shouldn't pile up fetches; one more once this one's done picks up whatever changed meanwhile
+        if self.refreshing_since.is_some() {
+          self.refresh_again = true;
+          return None;
+        }
+        self.refreshing_since = Some(Instant::now());
+        let tx = self.action_tx.clone().unwrap();
+        let scope = self.scope;
+        let limit_units = self.limit_units.to_vec();
+        tokio::spawn(async move {
+          let fetch = systemd::get_all_services(scope, &limit_units);
+          tokio::pin!(fetch);
+          // ticks keep the spinner in the list's title turning while the fetch is slow
+          let mut ticks = tokio::time::interval_at(tokio::time::Instant::now() + SLOW_REFRESH, SPINNER_INTERVAL);
+          let result = loop {
+            tokio::select! {
+              result = &mut fetch => break result,
+              _ = ticks.tick() => {
+                let _ = tx.send(Action::SpinnerTick);
+              },
+            }
+          };
+          // an error ends this fetch rather than the program, so the next refresh can try again: a host or container
+          // can come back, and so can a manager that was restarting
+          let _ = tx.send(match result {
+            Ok(units) => Action::SetServices(units),
+            Err(e) if remote::elsewhere().is_some() => Action::RefreshFailed(format!("Failed to get services: {e:#}")),
+            Err(e) => Action::RefreshFailed(format!(
+              "Failed to get services. Check that systemd is running and try running this tool with sudo: {e:#}"
+            )),
+          });
+        });
+        self.refresh_accounting();
+        self.refresh_pressure();
+      },
+      Action::RefreshFailed(error) => {
+        self.refreshing_since = None;
+        self.refresh_again = false;
+        return Some(Action::EnterError(error));
+      },
+      Action::SetPropertyValues(values) => {
+        // ignore results for a query that's since been cleared
+        if self.input.value().contains('=') {
+          self.property_values.extend(values);
+          self.refresh_filtered_units();
+        }
+        return Some(Action::Render);
+      },
+      Action::SetPressure(pressure) => {
+        self.pressure = pressure.into_iter().collect();
+        return Some(Action::Render);
+      },
+      Action::SetStaleUnits(units) => {
+        self.stale_units = units.into_iter().collect();
+        return Some(Action::Render);
+      },
+      Action::ConfirmStale(verb, unit) => {
+        self.prompt_title = format!("{} changed on disk", unit.name);
+        self.prompt_lines = vec![
+          Line::from("systemd is still running the configuration it loaded before the unit file changed."),
+          Line::from(Span::styled(
+            "A daemon-reload picks up the change, for every unit of this manager",
+            Style::default().add_modifier(Modifier::DIM),
+          )),
+        ];
+        let verb_name = verb.name().to_lowercase();
+        let items = vec![
+          MenuItem::new(
+            &format!("Reload, then {verb_name}"),
+            Action::ReloadAndRun(verb, unit.clone()),
+            Some(KeyCode::Char('r')),
+          ),
+          MenuItem::new(
+            &format!("{} with the old configuration", verb.name()),
+            Action::RunStale(verb, unit),
+            Some(KeyCode::Char('o')),
+          ),
+          MenuItem::new("Cancel", Action::EnterMode(Mode::ServiceList), Some(KeyCode::Char('n'))),
+        ];
+        self.menu_items = StatefulList::with_items(items);
+        self.menu_items.state.select(Some(0));
+        self.mode = Mode::Prompt;
+        return Some(Action::Render);
+      },
+      Action::ReloadAndRun(verb, unit) => {
+        let cancel_token = CancellationToken::new();
+        let (service, token) = (unit.clone(), cancel_token.clone());
+        let future = async move {
+          systemd::reload(service.scope, token).await?;
+          verb.run(service).await
+        };
+        self.stale_units.retain(|u| u.scope != unit.scope);
+        self.service_action(unit, format!("Reload and {}", verb.name().to_lowercase()), cancel_token, future);
+      },
+      Action::RunStale(verb, unit) => {
+        // until the next refresh, so the action goes through
+        self.stale_units.remove(&unit);
+        return Some(batch_action(verb, &unit));
+      },
+      Action::SetAccounting(accounting) => {
+        for (unit, a) in accounting {
+          match self.accounting.get(&unit).and_then(|earlier| a.rates(earlier)) {
+            Some(rates) => {
+              let history = self.history.entry(unit.clone()).or_default();
+              history.push_back(ResourceSample {
+                at: Instant::now(),
+                cpu_percent: rates.cpu_percent,
+                memory: a.memory_current,
+              });
+              if history.len() > HISTORY_SAMPLES {
+                history.pop_front();
+              }
+              self.rates.insert(unit.clone(), rates);
+            },
+            None if a.cpu_usage_nsec.is_none() => {
+              self.rates.remove(&unit);
+              self.history.remove(&unit);
+            },
+            // too soon after the last reading to tell; the next one compares with this
+            None => {},
+          }
+          self.accounting.insert(unit, a);
+        }
+        if self.sort.column().is_some() {
+          self.refresh_filtered_units();
+        }
+        return Some(Action::Render);
+      },
+      Action::SetDocumentation { unit, uris } => {
+        self.documentation.insert(unit, uris);
+      },
+      Action::OpenLogBootPicker => {
+        let tx = self.action_tx.clone().unwrap();
+        tokio::task::spawn_blocking(move || {
+          let _ = match systemd::get_boots() {
+            Ok(boots) => tx.send(Action::PickLogBoot(boots)),
+            Err(e) => tx.send(Action::EnterError(format!("{e:#}"))),
+          };
+        });
+      },
+      Action::PickLogBoot(boots) => {
+        self.prompt_title = "Show logs from".into();
+        self.prompt_lines = vec![];
+        let mut items = vec![MenuItem::new("All boots", Action::SetLogBoot(BootFilter::All), Some(KeyCode::Char('a')))];
+        for boot in boots.iter().rev() {
+          let current = if boot.index == 0 { " (current)" } else { "" };
+          let name = format!("{:>3}  started {}{current}", boot.index, boot.started);
+          let key = char::from_digit(boot.index.unsigned_abs(), 10).map(KeyCode::Char);
+          items.push(MenuItem::new(&name, Action::SetLogBoot(BootFilter::at(boot.index)), key));
+        }
+        let selected =
+          items.iter().position(|item| matches!(&item.action, Action::SetLogBoot(b) if *b == self.log_boot));
+        self.menu_items = StatefulList::with_items(items);
+        self.menu_items.state.select(Some(selected.unwrap_or(0)));
+        self.mode = Mode::Prompt;
+        return Some(Action::Render);
+      },
+      Action::SetLogBoot(boot) => {
+        self.set_log_boot(boot);
+        return Some(Action::EnterMode(Mode::ServiceList));
+      },
+      Action::SetTriggers { unit, triggers } => {
+        self.triggers.insert(unit, triggers);
+      },
+      Action::SetTypeDetails { unit, values } => {
+        self.type_details.insert(unit, values);
+      },
+      Action::SetMountRequirements { unit, requirements } => {
+        self.mount_requirements.insert(unit, requirements);
+      },
+      Action::SetCrashEvidence { unit, evidence } => {
+        self.crash_evidence.insert(unit, evidence);
+      },
+      Action::SetHealth { check, unit, outcome } => {
+        self.health.insert((check, unit), outcome);
+        return Some(Action::Render);
+      },
+      Action::ShowCrash(unit) => {
+        let title = format!("OOM kills and core dumps of {}", unit.name);
+        let tx = self.action_tx.clone().unwrap();
+        let loading = Action::ShowViewer { title: title.clone(), lines: vec![Line::from("Loading...")] };
+        tokio::task::spawn_blocking(move || {
+          let lines = match get_crash_evidence(&unit) {
+            Ok(evidence) => crash_lines(&unit, &evidence),
+            Err(e) => vec![Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red)))],
+          };
+          let _ = tx.send(Action::ShowViewer { title, lines });
+        });
+        return Some(loading);
+      },
+      Action::GoToMount(unit) => {
+        let requirements = self.mount_requirements.get(&unit).cloned().unwrap_or_default();
+        let missing = requirements.iter().filter(|r| !r.is_available()).cloned().collect_vec();
+        let candidates = if missing.is_empty() { requirements } else { missing };
+        let id = |r: &mounts::Requirement| UnitId { name: r.unit.clone(), scope: unit.scope };
+        match candidates.as_slice() {
+          [] => return Some(Action::EnterError(format!("{} doesn't need any mounts or devices", unit.name))),
+          [requirement] => return Some(Action::SelectUnit(id(requirement))),
+          _ => {},
+        }
+        self.prompt_title = format!("Go to a mount {} needs", unit.name);
+        self.prompt_lines = vec![];
+        let items = candidates
+          .iter()
+          .zip('1'..='9')
+          .map(|(r, key)| {
+            let name = format!("{} ({})", r.unit, r.active_state);
+            MenuItem::new(&name, Action::SelectUnit(id(r)), Some(KeyCode::Char(key)))
+          })
+          .collect();
+        self.menu_items = StatefulList::with_items(items);
+        self.menu_items.state.select(Some(0));
+        self.mode = Mode::Prompt;
+        return Some(Action::Render);
+      },
+      Action::GoToTrigger(unit) => {
+        let triggers = self.triggers.get(&unit).cloned().unwrap_or_default();
+        let related = triggers
+          .triggered_by
+          .into_iter()
+          .map(|u| (format!("{} (triggers {})", u.name, unit.name), u))
+          .chain(triggers.triggers.into_iter().map(|u| (format!("{} (triggered by {})", u.name, unit.name), u)))
+          .collect_vec();
+        match related.as_slice() {
+          [] => {
+            return Some(Action::EnterError(format!("Nothing triggers {}, and it doesn't trigger anything", unit.name)))
+          },
+          [(_, other)] => return Some(Action::SelectUnit(other.clone())),
+          _ => {},
+        }
+        self.prompt_title = format!("Go to a trigger of {}", unit.name);
+        self.prompt_lines = vec![];
+        let items = related
+          .into_iter()
+          .zip('1'..='9')
+          .map(|((name, other), key)| MenuItem::new(&name, Action::SelectUnit(other), Some(KeyCode::Char(key))))
+          .collect();
+        self.menu_items = StatefulList::with_items(items);
+        self.menu_items.state.select(Some(0));
+        self.mode = Mode::Prompt;
+        return Some(Action::Render);
+      },
+      Action::ShowDocumentation(unit) => {
+        let uris = self.documentation.get(&unit).cloned().unwrap_or_default();
+        match uris.as_slice() {
+          [] => return Some(Action::EnterError(format!("{} has no Documentation= links", unit.name))),
+          [uri] => return Some(Action::OpenDocumentation(uri.clone())),
+          _ => {},
+        }
+        self.prompt_title = format!("Documentation for {}", unit.name);
+        self.prompt_lines = vec![];
+        let items = uris
+          .iter()
+          .zip('1'..='9')
+          .map(|(uri, key)| MenuItem::new(uri, Action::OpenDocumentation(uri.clone()), Some(KeyCode::Char(key))))
+          .collect();
+        self.menu_items = StatefulList::with_items(items);
+        self.menu_items.state.select(Some(0));
+        self.mode = Mode::Prompt;
+        return Some(Action::Render);
+      },
+      Action::SetTimers(timers) => {
+        self.timers.extend(timers);
+        return Some(Action::Render);
+      },
+      Action::SetFileStates(states) => {
+        let now = Local::now();
+        for (unit, state) in &states {
+          if let Some(before) = self.file_states.get(unit) {
+            self.changes.record(unit, Aspect::Enablement, before, state, now);
+          }
+        }
+        self.file_states.extend(states);
+        return Some(Action::Render);
+      },
+      Action::SetSchedule { unit, schedule } => {
+        self.schedules.insert(unit, schedule);
+      },
+      Action::EnableAccounting(unit) => self.enable_accounting(unit),
+      Action::RunTransient(transient) => self.run_transient(transient),
+      Action::SetServices(units) => {
+        self.refreshing_since = None;
+        if std::mem::take(&mut self.refresh_again) {
+          if let Some(tx) = &self.action_tx {
+            let _ = tx.send(Action::RefreshServices);
+          }
+        }
+        // a full fetch, so units missing from it are gone, like transient ones that finished
+        let listed: HashSet<UnitId> = units.iter().map(|u| u.id()).collect();
+        self.all_units.retain(|id, _| listed.contains(id));
+        self.search_index.retain(|id| listed.contains(id));
+        self.update_units(units);
+        if let Some(view) = self.startup_view.take() {
+          return self.open_startup_view(view);
+        }
+        return Some(Action::Render);
+      },
+      Action::SetSearchResults(result) => {
+        // stale ones are dropped
+        if !self.search_index.finish(result) {
+          return None;
+        }
+        self.refresh_filtered_units();
+      },
+      Action::KillService { unit, signal, whom } => self.kill_service(unit, signal, whom),
+      Action::FreezeUnit { unit, thaw } => {
+        let cancel_token = CancellationToken::new();
+        let future = systemd::freeze_unit(unit.clone(), thaw, cancel_token.clone());
+        self.service_action(unit, if thaw { "Thaw" } else { "Freeze" }.into(), cancel_token, future);
+      },
+      Action::OpenClean(unit) => {
+        let tx = self.action_tx.clone().unwrap();
+        tokio::task::spawn_blocking(move || {
+          let targets = systemd::get_unit_properties(&unit, &unit_types::clean_properties())
+            .map(|properties| unit_types::clean_targets(&properties))
+            .map_err(|e| format!("{e:#}"));
+          let _ = tx.send(Action::SetCleanTargets { unit, targets });
+        });
+      },
+      Action::SetCleanTargets { unit, targets } => {
+        let targets = match targets {
+          Ok(targets) if targets.is_empty() => {
+            return Some(Action::EnterError(format!(
+              "{} has no cache, state, logs, runtime or configuration directory to clean",
+              unit.name
+            )));
+          },
+          Ok(targets) => targets,
+          Err(e) => return Some(Action::EnterError(e)),
+        };
+        self.prompt_title = format!("Clean what of {}?", unit.name);
+        self.prompt_lines =
+          targets.iter().map(|(what, directories)| Line::from(format!("{what}: {directories}"))).collect();
+        if self.all_units.get(&unit).is_some_and(|u| u.activation_state != "inactive" && !u.is_failed()) {
+          let warning = "It has to be stopped first";
+          self.prompt_lines.push(Line::from(Span::styled(warning, Style::default().fg(Color::Yellow))));
+        }
+        let clean = |whats: &[&str]| {
+          let mut args = vec!["clean".to_string()];
+          args.extend(whats.iter().map(|what| format!("--what={what}")));
+          args.push(unit.name.clone());
+          Action::RunSystemctl(unit.clone(), args)
+        };
+        let mut items = targets
+          .iter()
+          .map(|(what, _)| {
+            let key =
+              unit_types::CLEAN_KINDS.iter().find(|(kind, ..)| kind == what).map(|(.., key)| KeyCode::Char(*key));
+            MenuItem::new(&format!("Remove its {what} directories"), clean(&[what]), key)
+          })
+          .collect_vec();
+        if targets.len() > 1 {
+          let all = targets.iter().map(|(what, _)| *what).collect_vec();
+          items.push(MenuItem::new("Remove all of them", clean(&all), Some(KeyCode::Char('a'))));
+        }
+        items.push(MenuItem::new("Cancel", Action::EnterMode(Mode::ServiceList), Some(KeyCode::Char('n'))));
+        self.menu_items = StatefulList::with_items(items);
+        // there's no undoing it, so a stray enter cancels
+        self.menu_items.state.select(Some(self.menu_items.items.len() - 1));
+        self.mode = Mode::Prompt;
+        return Some(Action::Render);
+      },
+      Action::ResetFailed(unit) => {
+        let cancel_token = CancellationToken::new();
+        let future = systemd::reset_failed_unit(unit.clone(), cancel_token.clone());
+        self.service_action(unit, "Reset failed state".into(), cancel_token, future);
+      },
+      Action::ToggleFavorite(unit) => {
+        if !self.favorites.shift_remove(&unit) {
+          self.favorites.insert(unit);
+        }
+        self.refresh_filtered_units();
+        if let Err(e) = favorites::save(&self.favorites) {
+          return Some(Action::EnterError(format!("Error saving favorites: {e:#}")));
+        }
+        return Some(Action::Render);
+      },
+      Action::ToggleFailedView => {
+        self.failed_only = !self.failed_only;
+        self.refresh_filtered_units();
+        return Some(Action::Render);
+      },
+      Action::ConfirmResetAllFailed => {
+        let failed = self.all_units.values().filter(|u| u.is_failed()).collect_vec();
+        if failed.is_empty() {
+          return Some(Action::EnterError("No units have failed".into()));
+        }
+        self.prompt_title = format!("Reset {} failed units?", failed.len());
+        self.prompt_lines = failed_lines(&failed);
+        let items = vec![
+          MenuItem::new("Reset them", Action::ResetAllFailed, Some(KeyCode::Char('y'))),
+          MenuItem::new("Cancel", Action::EnterMode(Mode::ServiceList), Some(KeyCode::Char('n'))),
+        ];
+        self.menu_items = StatefulList::with_items(items);
+        self.menu_items.state.select(Some(0));
+        self.mode = Mode::Prompt;
+        return Some(Action::Render);
+      },
+      Action::ResetAllFailed => return self.reset_all_failed(),
+      Action::OpenKill(unit) => {
+        let fields = vec![
+          Field::text("Signal", "SIGTERM"),
+          Field::choice("Send to", &["all processes", "main process", "control process"]),
+        ];
+        let title = format!("Send a signal to {}", unit.name);
+        let mut form = Form::new(&title, FormPurpose::Kill(unit), fields);
+        form.preview = vec![Line::from(Span::styled(
+          "A name like SIGUSR1 or HUP, or a number",
+          Style::default().add_modifier(Modifier::DIM),
+        ))];
+        return self.open_form(form);
+      },
+      Action::RunSystemctl(unit, args) => self.run_systemctl(unit, args),
+      Action::SpinnerTick => {
+        self.spinner_tick = self.spinner_tick.wrapping_add(1);
+        return Some(Action::Render);
+      },
+      Action::CancelTask => {
+        if let Some(cancel_token) = self.cancel_token.take() {
+          cancel_token.cancel();
+        }
+        self.mode = Mode::ServiceList;
+        return Some(Action::Render);
+      },
+      _ => (),
+    }
+    None
+  }
+
+  fn render(&mut self, f: &mut Frame<'_>, rect: Rect) {
+    let theme = self.theme;
+    // whatever isn't drawn this time can't be clicked
+    self.mouse_areas = mouse::Areas::default();
+    // Screen-reader friendly rendering: no box-drawing, state spelled out in text, focus announced on the bottom line
+    let accessible = self.config.accessible;
+
+    fn span(s: &str, color: Color) -> Span<'_> {
+      Span::styled(s, Style::default().fg(color))
+    }
+
+    fn colored_line(value: &str, color: Color) -> Line<'_> {
+      Line::from(vec![Span::styled(value, Style::default().fg(color))])
+    }
+
+    let rect = if self.show_logger {
+      let chunks = Layout::new(Direction::Vertical, Constraint::from_percentages([50, 50])).split(rect);
+
+      self.logger.render(f, chunks[1]);
+      chunks[0]
+    } else {
+      rect
+    };
+
+    let status_height = if accessible { 1 } else { 0 };
+    let echo_height = if self.config.command_echo { 1 } else { 0 };
+    let rects = Layout::new(
+      Direction::Vertical,
+      [
+        Constraint::Min(3),
+        Constraint::Percentage(100),
+        Constraint::Length(echo_height),
+        Constraint::Length(1),
+        Constraint::Length(status_height),
+      ],
+    )
+    .split(rect);
+    let search_panel = rects[0];
+    let main_panel = rects[1];
+    let echo_rect = rects[2];
+    let help_line_rect = rects[3];
+    let status_rect = rects[4];
+
+    // Colouring based on the same logic as sysz
+    // https://github.com/joehillen/sysz/blob/8da8e0dcbfde8d68fbdb22382671e395bd370d69/sysz#L69C1-L72C24
+    //    Some units are colored based on state:
+    //    green       active
+    //    red         failed
+    //    yellow      not-found
+    // with the colors taken from the theme
+    // without color, the symbols are all that's left to tell states apart
+    let state_symbols = (self.config.show_state_symbols() || self.monochrome) && !accessible;
+
+    let columns = &self.list_columns();
+    let now = Local::now().timestamp();
+    let list_width = 30 + LIST_COLUMN_WIDTH * columns.len() as u16;
+    let items: Vec<ListItem> = self
+      .filtered_units
+      .items
+      .iter()
+      .enumerate()
+      .map(|(index, i)| {
+        let unit_state = UnitState::of(&i.load_state, &i.activation_state);
+        let color = unit_state.color(&theme);
+        // instances of the template the row above is an instance of too are indented under it, by their instance name
+        let previous = index.checked_sub(1).and_then(|p| self.filtered_units.items.get(p));
+        let template = unit_types::template(&i.name);
+        let name = match i.short_name().find('@') {
+          Some(at) if template.is_some() && previous.is_some_and(|p| unit_types::template(&p.name) == template) => {
+            format!("{}{}", " ".repeat(at), &i.short_name()[at..])
+          },
+          _ => i.short_name().to_string(),
+        };
+        let mut line = if accessible {
+          // color alone doesn't reach a screen reader, so spell the state out
+          let state = if i.is_not_found() { &i.load_state } else { &i.activation_state };
+          Line::from(Span::styled(format!("{} {}", i.short_name(), state), Style::default().fg(color)))
+        } else if state_symbols {
+          Line::from(vec![
+            span(unit_state.symbol(), color),
+            Span::raw(" "),
+            Span::styled(name, Style::default().fg(color)),
+          ])
+        } else {
+          Line::from(Span::styled(name, Style::default().fg(color)))
+        };
+        if self.marked.contains(&i.id()) {
+          if accessible {
+            line.push_span(Span::raw(", marked"));
+          } else {
+            line.spans.insert(0, Span::styled("+ ", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)));
+          }
+        }
+        if self.favorites.contains(&i.id()) {
+          line.push_span(if accessible {
+            Span::raw(", starred")
+          } else {
+            Span::styled(" ★", Style::default().fg(theme.accent))
+          });
+        }
+        if let Some(slot) = self.config.pinned.iter().position(|p| *p == i.name).filter(|s| *s < 9) {
+          line.push_span(if accessible {
+            Span::raw(format!(", pinned to {}", slot + 1))
+          } else {
+            Span::styled(format!(" [{}]", slot + 1), Style::default().fg(theme.muted_alt))
+          });
+        }
+        if self.pressure.get(&i.id()).is_some_and(|p| p.is_sustained()) {
+          line.push_span(Span::styled(" pressure", Style::default().fg(Color::Yellow)));
+        }
+        if self.watches.contains_key(&i.id()) {
+          line.push_span(Span::styled(
+            if accessible { ", restarted on file changes" } else { " watched" },
+            Style::default().fg(theme.muted_alt),
+          ));
+        }
+        if self.stale_units.contains(&i.id()) {
+          line.push_span(Span::styled(
+            if accessible { ", needs daemon-reload" } else { " needs reload" },
+            Style::default().fg(Color::Yellow),
+          ));
+        }
+        if !columns.is_empty() {
+          let accounting = self.accounting.get(&i.id());
+          let timer = self.timers.get(&i.id());
+          let rates = self.rates.get(&i.id());
+          let file_state = self.file_states.get(&i.id()).map(String::as_str);
+          let has_checks = self.config.health_checks_for(&i.name).next().is_some();
+          let cells = columns
+            .iter()
+            .map(|c| match c {
+              ListColumn::Health => health_cell(has_checks.then(|| self.health_of(&i.id())), &theme),
+              c => Span::styled(
+                list_column_cell(*c, accounting, rates, timer, file_state, i.scope, now),
+                Style::default().fg(theme.muted_alt),
+              ),
+            })
+            .collect_vec();
+          // borders, plus the highlight symbol in accessible mode
+          let available = list_width.saturating_sub(if accessible { 4 } else { 2 }) as usize;
+          let padding = available.saturating_sub(line.width() + cells.iter().map(Span::width).sum::<usize>());
+          line.push_span(Span::raw(" ".repeat(padding)));
+          line.spans.extend(cells);
+        }
+        ListItem::new(line)
+      })
+      .collect();
+
+    // Create a List from all list items and highlight the currently selected one
+    let mut items = List::new(items)
+      .block(
+        pane_block(accessible)
+          .border_style(if self.mode == Mode::ServiceList {
+            Style::default().fg(theme.accent)
+          } else {
+            Style::default()
+          })
+          .title(pane_title(
+            &{
+              let units = self.type_filter.and_then(unit_types::plural).unwrap_or("Services");
+              let mut title = match (remote::host(), machine::machine(), user_manager::user()) {
+                (Some(host), ..) => format!("{units} on {host}"),
+                (None, Some(machine), _) => format!("{units} in {machine}"),
+                (None, None, user) => {
+                  let mut title = format!("{units} ({}", scope_label(self.scope));
+                  if let Some(user) = user.filter(|_| !matches!(self.scope, Scope::Global)) {
+                    title.push_str(&format!(", user units of {}", user.name));
+                  }
+                  if let Some(key) = self.keymap.describe(Command::CycleScope) {
+                    title.push_str(&format!(", {key} to switch"));
+                  }
+                  title + ")"
+                },
+              };
+              if self.failed_only {
+                title = format!("Failed {}{}", title[..1].to_lowercase(), &title[1..]);
+              }
+              if let Some(narrowed) = self.command_line_filter() {
+                title.push_str(&format!(" {narrowed}, Esc to clear"));
+              }
+              if self.sort != SortOrder::Name {
+                title.push_str(&format!(" by {}", self.sort.label()));
+              }
+              let failed = self.all_units.values().filter(|u| u.is_failed()).count();
+              if failed > 0 && !self.failed_only {
+                let hint = self.keymap.describe(Command::FailedView).map(|k| format!(", {k} to list them"));
+                title.push_str(&format!(" ({failed} failed{})", hint.unwrap_or_default()));
+              }
+              if !self.marked.is_empty() {
+                title.push_str(&format!(" ({} marked)", self.marked.len()));
+              }
+              if self.refreshing_since.is_some_and(|since| since.elapsed() >= SLOW_REFRESH) {
+                title.push_str(&if accessible {
+                  " (refreshing)".to_string()
+                } else {
+                  format!(" {}", self.spinner_char())
+                });
+              }
+              title
+            },
+            accessible,
+          )),
+      )
+      .highlight_style(Style::default().bg(theme.selection).add_modifier(Modifier::BOLD));
+    if accessible {
+      items = items.highlight_symbol("> ");
+    }
+
+    let selected_item = self.filtered_units.selected();
+    let needs = selected_item.and_then(|i| self.mount_requirements.get(&i.id())).filter(|r| !r.is_empty());
+
+    let is_service = selected_item.is_some_and(|i| i.name.ends_with(".service"));
+    let watch = selected_item.and_then(|i| self.watches.get(&i.id()));
+    let crash = selected_item.and_then(|i| self.crash_evidence.get(&i.id())).filter(|e| !e.is_empty());
+    let health = selected_item.and_then(|i| self.health_of(&i.id()));
+    let trend = selected_item.and_then(|i| self.history.get(&i.id())).filter(|h| h.len() >= 2);
+    let details_height = 13
+      + trend.is_some() as u16
+      + needs.is_some() as u16
+      + is_service as u16
+      + watch.is_some() as u16
+      + crash.is_some() as u16
+      + health.is_some() as u16;
+    let panes = self.pane_layout.panes(main_panel, list_width, details_height);
+    let details_panel = panes.details;
+    let logs_panel = panes.logs;
+
+    if !panes.list.is_empty() {
+      f.render_stateful_widget(items, panes.list, &mut self.filtered_units.state);
+      self.mouse_areas.list = pane_block(accessible).inner(panes.list);
+      // less the borders
+      self.list_page = panes.list.height.saturating_sub(2) as usize;
+    }
+    let selected_item = self.filtered_units.selected();
+    let needs = selected_item.and_then(|i| self.mount_requirements.get(&i.id())).filter(|r| !r.is_empty());
+    let watch = selected_item.and_then(|i| self.watches.get(&i.id()));
+    let crash = selected_item.and_then(|i| self.crash_evidence.get(&i.id())).filter(|e| !e.is_empty());
+    let health = selected_item.and_then(|i| self.health_of(&i.id()));
+    let trend = selected_item.and_then(|i| self.history.get(&i.id())).filter(|h| h.len() >= 2);
+
+    // with plugins configured, the details pane has a tab for each, showing what it printed for the selected unit
+    let plugin_tab = self.details_tab.zip(selected_item).and_then(|(i, u)| self.plugin_output.get(&(i, u.id())));
+    let mut details_title = vec![Span::raw(pane_title("Details", accessible))];
+    if !self.config.plugins.is_empty() {
+      let tab = |active: bool| {
+        if active {
+          Style::default().add_modifier(Modifier::BOLD).fg(theme.accent)
+        } else {
+          Style::default().fg(theme.muted_alt)
+        }
+      };
+      details_title[0].style = tab(plugin_tab.is_none());
+      for (i, plugin) in self.config.plugins.iter().enumerate() {
+        details_title.push(Span::styled(" · ", Style::default().fg(theme.muted_alt)));
+        details_title.push(Span::styled(plugin.name.clone(), tab(plugin_tab.is_some() && self.details_tab == Some(i))));
+      }
+      if let Some(key) = self.keymap.describe(Command::PluginTab) {
+        details_title.push(Span::styled(format!(" ({key} to switch)"), Style::default().fg(theme.muted_alt)));
+      }
+    }
+    let details_block = pane_block(accessible).title(Line::from(details_title));
+    let details_panel_panes = Layout::new(Direction::Horizontal, [Constraint::Min(14), Constraint::Percentage(100)])
+      .split(details_block.inner(details_panel));
+    let props_pane = details_panel_panes[0];
+    let values_pane = details_panel_panes[1];
+
+    // resource use means little for a timer, so its rows show the schedule instead
+    let is_timer = selected_item.is_some_and(|i| i.name.ends_with(".timer"));
+    // and sockets, mounts and the like show what they're about instead
+    let type_fields = selected_item.map_or(&[][..], |i| unit_types::fields(unit_types::unit_type(&i.name)));
+    let extra_props = if is_timer {
+      vec!["Next: ", "Last: ", "Schedule: ", "Upcoming: "]
+    } else if !type_fields.is_empty() {
+      type_fields.iter().map(|f| f.label()).collect()
+    } else {
+      let mut props = vec!["I/O: ", "Network: ", "Pressure: ", "Journal: "];
+      if trend.is_some() {
+        props.push("Trend: ");
+      }
+      if is_service {
+        props.push("Restarts: ");
+      }
+      if needs.is_some() {
+        props.push("Needs: ");
+      }
+      if watch.is_some() {
+        props.push("Watch: ");
+      }
+      if crash.is_some() {
+        props.push("Crash: ");
+      }
+      if health.is_some() {
+        props.push("Health: ");
+      }
+      props
+    };
+    let props_lines = ["Description: ", "Scope: ", "Loaded: ", "Active: ", "Unit file: ", "Docs: ", "Triggers: "]
+      .into_iter()
+      .chain(extra_props)
+      .map(Line::from)
+      .collect_vec();
+
+    let details_text = if let Some(i) = selected_item {
+      fn line_color_string<'a>(value: String, color: Color) -> Line<'a> {
+        Line::from(vec![Span::styled(value, Style::default().fg(color))])
+      }
+
+      let load_color = match i.load_state.as_str() {
+        "loaded" => UnitState::Active.color(&theme),
+        "not-found" => UnitState::NotFound.color(&theme),
+        "error" => UnitState::Failed.color(&theme),
+        _ => Color::Reset,
+      };
+
+      let unit_state = UnitState::of(&i.load_state, &i.activation_state);
+      let active_color = match unit_state {
+        UnitState::NotFound => Color::Reset,
+        state => state.color(&theme),
+      };
+
+      let sub_state = if self.is_frozen(&i.id()) { format!("{}, frozen", i.sub_state) } else { i.sub_state.clone() };
+      let active_state_value = if state_symbols {
+        format!("{} {} ({sub_state})", unit_state.symbol(), i.activation_state)
+      } else {
+        format!("{} ({sub_state})", i.activation_state)
+      };
+
+      let scope = match i.scope {
+        UnitScope::Global => "Global",
+        UnitScope::User => "User",
+      };
+
+      let mut lines = vec![
+        colored_line(&i.description, Color::Reset),
+        colored_line(scope, Color::Reset),
+        if self.stale_units.contains(&i.id()) {
+          let hint = self.keymap.describe(Command::Reload).map_or(String::new(), |k| format!(", {k} to reload"));
+          Line::from(vec![
+            Span::styled(i.load_state.clone(), Style::default().fg(load_color)),
+            Span::styled(format!(" (file changed on disk{hint})"), Style::default().fg(Color::Yellow)),
+          ])
+        } else {
+          colored_line(&i.load_state, load_color)
+        },
+        match self.jobs.iter().find(|j| j.unit == i.id()) {
+          Some(job) => {
+            let hint =
+              self.keymap.describe(Command::ShowJobs).map_or(String::new(), |k| format!(", {k} for the queue"));
+            Line::from(vec![
+              Span::styled(active_state_value, Style::default().fg(active_color)),
+              Span::styled(format!(" ({}{hint})", job.describe()), Style::default().fg(Color::Yellow)),
+            ])
+          },
+          None => line_color_string(active_state_value, active_color),
+        },
+        match &i.file_path {
+          Some(Ok(file_path)) => Line::from(file_path.as_str()),
+          Some(Err(e)) => colored_line(e, Color::Red),
+          None => Line::from(""),
+        },
+      ];
+      let muted = |s: &str| Line::from(Span::styled(s.to_string(), Style::default().fg(theme.muted_alt)));
+      lines.push(match self.documentation.get(&i.id()) {
+        None => Line::from(""),
+        Some(uris) if uris.is_empty() => muted("none"),
+        Some(uris) => Line::from(vec![
+          Span::raw(uris.join(" ")),
+          Span::styled(" (M to open)", Style::default().fg(theme.muted_alt)),
+        ]),
+      });
+      lines.push(match self.triggers.get(&i.id()) {
+        None => Line::from(""),
+        Some(triggers) if triggers.is_empty() => muted("none"),
+        Some(triggers) => {
+          let names = |units: &[UnitId]| units.iter().map(|u| u.name.as_str()).join(", ");
+          let mut spans = vec![];
+          if !triggers.triggered_by.is_empty() {
+            spans.push(Span::raw(format!("by {}", names(&triggers.triggered_by))));
+          }
+          if !triggers.triggers.is_empty() {
+            let separator = if spans.is_empty() { "" } else { "; " };
+            spans.push(Span::raw(format!("{separator}starts {}", names(&triggers.triggers))));
+          }
+          let hint = self.keymap.describe(Command::GoToTrigger).map(|k| format!(" ({k} to go there)"));
+          spans.extend(hint.map(|hint| Span::styled(hint, Style::default().fg(theme.muted_alt))));
+          Line::from(spans)
+        },
+      });
+      let accounting = self.accounting.get(&i.id());
+      let rates = self.rates.get(&i.id());
+      // what it's doing right now, once there are two readings to compare
+      let rate = |pair: Option<(Option<f64>, Option<f64>)>| match pair {
+        Some((a, b)) if a.is_some() || b.is_some() => Span::styled(
+          format!(" (now {}/{}/s)", format_rate_or_dash(a), format_rate_or_dash(b)),
+          Style::default().fg(theme.muted_alt),
+        ),
+        _ => Span::raw(""),
+      };
+      let io_line = match accounting {
+        None => Line::from(""),
+        Some(a) if !a.io_accounting => muted("accounting off (A to turn on)"),
+        Some(a) => Line::from(vec![
+          Span::raw(format!(
+            "read {}, written {}",
+            format_bytes_or_dash(a.io_read_bytes),
+            format_bytes_or_dash(a.io_write_bytes)
+          )),
+          rate(rates.map(|r| (r.io_read, r.io_write))),
+        ]),
+      };
+      let network_line = match accounting {
+        None => Line::from(""),
+        Some(a) if !a.ip_accounting => muted("accounting off (A to turn on)"),
+        Some(a) => Line::from(vec![
+          Span::raw(format!(
+            "received {}, sent {}",
+            format_bytes_or_dash(a.ip_ingress_bytes),
+            format_bytes_or_dash(a.ip_egress_bytes)
+          )),
+          rate(rates.map(|r| (r.ip_ingress, r.ip_egress))),
+        ]),
+      };
+      let pressure_line = match self.pressure.get(&i.id()) {
+        None => Line::from(""),
+        Some(p) => {
+          let stall = |name: &str, stall: pressure::Stall| {
+            let color = if stall.avg60 >= pressure::SUSTAINED_PRESSURE { Color::Yellow } else { Color::Reset };
+            Span::styled(format!("{name} {:.1}%", stall.avg60), Style::default().fg(color))
+          };
+          Line::from(vec![
+            stall("cpu", p.cpu),
+            Span::raw(", "),
+            stall("memory", p.memory),
+            Span::raw(", "),
+            stall("io", p.io),
+            Span::styled(" stalled over the last minute", Style::default().fg(theme.muted_alt)),
+          ])
+        },
+      };
+      let journal_line = match self.journal_usage.get(&i.id()) {
+        None => Line::from(""),
+        Some((usage, journal_bytes)) => {
+          let mut text = format!("~{}", humanize::size(usage.bytes));
+          if let Some(share) = journal_bytes.filter(|b| *b > 0).map(|b| usage.bytes as f64 / b as f64 * 100.0) {
+            text.push_str(&format!(", {share:.0}% of the journal"));
+          }
+          if let Some(per_day) = usage.bytes_per_day() {
+            text.push_str(&format!(" ({})", humanize::rate(per_day, "day")));
+          }
+          Line::from(text)
+        },
+      };
+      if !type_fields.is_empty() {
+        match self.type_details.get(&i.id()) {
+          Some(values) => {
+            lines.extend(values.iter().map(|v| if v.is_empty() { muted("none") } else { Line::from(v.clone()) }))
+          },
+          None => lines.extend(type_fields.iter().map(|_| Line::from(""))),
+        }
+      } else if is_timer {
+        lines.extend(timer_detail_lines(self.timers.get(&i.id()), self.schedules.get(&i.id()), theme.muted_alt));
+      } else {
+        lines.extend([io_line, network_line, pressure_line, journal_line]);
+        if let Some(trend) = trend {
+          lines.push(trend_line(trend, self.config.graph_style, accessible, &theme));
+        }
+        if is_service {
+          lines.push(accounting.map_or(Line::from(""), |a| restart_line(a, &i.sub_state, &theme)));
+        }
+        if let Some(needs) = needs {
+          let mut spans = vec![];
+          for (i, requirement) in needs.iter().enumerate() {
+            if i > 0 {
+              spans.push(Span::raw(", "));
+            }
+            spans.push(Span::raw(match &requirement.path {
+              Some(path) => format!("{path} ({})", requirement.unit),
+              None => requirement.unit.clone(),
+            }));
+            spans.push(if requirement.is_available() {
+              Span::styled(" ✓", Style::default().fg(UnitState::Active.color(&theme)))
+            } else {
+              Span::styled(format!(" {}", requirement.active_state), Style::default().fg(Color::Red))
+            });
+          }
+          if needs.iter().any(|r| !r.is_available()) {
+            let hint = self.keymap.describe(Command::GoToMount).map(|k| format!(" ({k} to go there)"));
+            spans.extend(hint.map(|hint| Span::styled(hint, Style::default().fg(theme.muted_alt))));
+          }
+          lines.push(Line::from(spans));
+        }
+        if let Some(watch) = watch {
+          lines.push(watch_line(watch, &theme));
+        }
+        if let Some(crash) = crash {
+          let hint = self.keymap.describe(Command::ShowCrash).map(|k| format!(" ({k} for more)"));
+          lines.push(crash_line(crash, hint, &theme));
+        }
+        if let Some(health) = health {
+          lines.push(health_line(health, &theme));
+        }
+      }
+
+      lines
+    } else {
+      vec![]
+    };
+
+    let paragraph = Paragraph::new(details_text).style(Style::default());
+
+    if let Some(lines) = plugin_tab {
+      f.render_widget(Paragraph::new(lines.clone()).wrap(Wrap { trim: false }), details_block.inner(details_panel));
+    } else {
+      let props_widget = Paragraph::new(props_lines).alignment(ratatui::layout::Alignment::Right);
+      f.render_widget(props_widget, props_pane);
+      f.render_widget(paragraph, values_pane);
+    }
+    f.render_widget(details_block, details_panel);
+
+    self.log_search.scan(&self.logs, log_message);
+    let log_search = &self.log_search;
+    let now = Local::now();
+    let log_lines = self
+      .logs
+      .iter()
+      .enumerate()
+      .rev()
+      .map(|(index, entry)| {
+        let current = log_search.current == Some(index);
+        let style = entry.priority.map_or(Style::default(), |p| Style::default().fg(theme.log_priority(p)));
+        let marker = entry
+          .priority
+          .filter(|_| state_symbols)
+          .map(|p| Span::styled(format!("{} ", states::log_priority_symbol(p)), style));
+        let l = entry.text.as_str();
+        if let Some((timestamp, rest)) = l.split_once(' ') {
+          if let Some(formatted_date) = format_log_timestamp(timestamp, self.log_timestamps, now) {
+            let rest_style = if rest.starts_with(AUDIT_TAG) { Style::default().fg(Color::Magenta) } else { style };
+            let mut spans = vec![];
+            if !formatted_date.is_empty() {
+              spans
+                .extend([Span::styled(formatted_date, Style::default().add_modifier(Modifier::DIM)), Span::raw(" ")]);
+            }
+            spans.extend(marker);
+            spans.extend(highlight_matches(rest, rest_style, &log_search.ranges(rest), current));
+            return Line::from(spans);
+          }
+        }
+
+        Line::from(marker.into_iter().chain(highlight_matches(l, style, &log_search.ranges(l), current)).collect_vec())
+      })
+      .collect_vec();
+
+    let log_rate_cells = (logs_panel.width as usize / 3).min(30);
+    let log_rate_minutes = log_rate_cells * graph::samples_per_cell(self.config.graph_style);
+    let log_rate = log_rate(&self.log_minutes, now.timestamp(), log_rate_minutes);
+    let log_rate_title = if accessible {
+      let peak = log_rate.iter().max().copied().unwrap_or(0);
+      let total: u64 = log_rate.iter().sum();
+      Line::from(format!("{total} lines in the last {log_rate_minutes} minutes, peak {peak} per minute"))
+    } else {
+      Line::from(vec![
+        Span::raw("─"),
+        Span::styled(
+          graph::sparkline(&log_rate, log_rate_cells, self.config.graph_style),
+          Style::default().fg(theme.accent),
+        ),
+        Span::styled(format!(" lines/min, last {log_rate_minutes}m─"), Style::default().fg(theme.muted_alt)),
+      ])
+    };
+
+    let paragraph = Paragraph::new(log_lines)
+      .block(
+        pane_block(accessible)
+          .title(pane_title(
+            &format!(
+              "Service Logs ({}{}{}{}{}{}{}{})",
+              self.log_boot.label(),
+              if self.log_timestamps == LogTimestamps::Short {
+                String::new()
+              } else {
+                format!(", {}", self.log_timestamps.label())
+              },
+              if self.log_current_run { ", current run" } else { "" },
+              self.log_priority.map(|p| format!(", {}", journal::priority_filter_label(p))).unwrap_or_default(),
+              if self.log_include_audit { ", +audit" } else { "" },
+              if self.log_filters.is_empty() {
+                String::new()
+              } else {
+                format!(", filtered: {}", self.log_filters.label())
+              },
+              if self.logs_detached { ", not following" } else { "" },
+              match (self.log_wrap, self.log_hscroll) {
+                (true, _) => String::new(),
+                (false, 0) => ", unwrapped".into(),
+                (false, columns) => format!(", unwrapped, {columns} columns in"),
+              }
+            ),
+            accessible,
+          ))
+          .title_bottom(log_rate_title.right_aligned())
+          .title_bottom(self.log_search_title(theme))
+          .border_style(if matches!(self.mode, Mode::Logs | Mode::LogSearch) {
+            Style::default().fg(theme.accent)
+          } else {
+            Style::default()
+          }),
+      )
+      .style(Style::default());
+    let paragraph = if self.log_wrap {
+      paragraph.wrap(Wrap { trim: true }).scroll((self.logs_scroll_offset, 0))
+    } else {
+      paragraph.scroll((self.logs_scroll_offset, self.log_hscroll))
+    };
+    // less the borders
+    self.log_page_width = logs_panel.width.saturating_sub(2);
+    f.render_widget(paragraph, logs_panel);
+    self.mouse_areas.logs = pane_block(accessible).inner(logs_panel);
+
+    let width = search_panel.width.max(3) - 3; // keep 2 for borders and 1 for cursor
+    let scroll = self.input.visual_scroll(width as usize);
+    let input = Paragraph::new(self.input.value())
+      .style(match self.mode {
+        Mode::Search => Style::default().fg(theme.accent),
+        _ => Style::default(),
+      })
+      .scroll((0, scroll as u16))
+      .block(pane_block(accessible).title(Line::from(vec![
+        Span::raw(pane_title(
+          if parse_property_query(self.input.value()).is_some() { "Property search " } else { "Search " },
+          accessible,
+        )),
+        Span::styled("(", Style::default().fg(theme.muted_alt)),
+        Span::styled("ctrl+f", Style::default().add_modifier(Modifier::BOLD).fg(theme.kbd)),
+        Span::styled(" or ", Style::default().fg(theme.muted_alt)),
+        Span::styled("/", Style::default().add_modifier(Modifier::BOLD).fg(theme.kbd)),
+        Span::styled(")", Style::default().fg(theme.muted_alt)),
+      ])));
+    f.render_widget(input, search_panel);
+    // clear top right of search panel so we can put help instructions there
+    let kbd = |s| Span::styled(s, Style::default().add_modifier(Modifier::BOLD).fg(theme.kbd));
+    let help_line = if self.read_only && !self.recording_macro && self.replay.is_none() {
+      Line::from(Span::styled(" read-only ", Style::default().fg(Color::Yellow)))
+    } else if self.dry_run && !self.recording_macro && self.replay.is_none() {
+      Line::from(vec![
+        Span::styled(" dry run, ", Style::default().fg(Color::Yellow)),
+        kbd("d"),
+        Span::raw(" to turn off "),
+      ])
+    } else if self.recording_macro {
+      Line::from(vec![
+        Span::styled(" ● recording macro, ", Style::default().fg(Color::Red)),
+        kbd("m"),
+        Span::raw(" to stop "),
+      ])
+    } else if self.replay.is_some() {
+      Line::from(Span::styled(" replaying macro, any key stops ", Style::default().fg(Color::Yellow)))
+    } else if !self.pending_keys.is_empty() {
+      let pending = keybindings::format_sequence(&self.pending_keys);
+      Line::from(vec![
+        Span::styled(format!(" {pending}"), Style::default().add_modifier(Modifier::BOLD).fg(theme.kbd)),
+        Span::raw(" ... "),
+      ])
+    } else {
+      Line::from(vec![Span::raw(" Press "), kbd("?"), Span::raw(" or "), kbd("F1"), Span::raw(" for help ")])
+    };
+    let help_width = (help_line.width() as u16).min(search_panel.width.saturating_sub(4));
+    let help_area = Rect::new(search_panel.x + search_panel.width - help_width - 2, search_panel.y, help_width, 1);
+    f.render_widget(Clear, help_area);
+    f.render_widget(Paragraph::new(help_line).style(Style::default().fg(theme.muted_alt)), help_area);
+
+    if self.mode == Mode::LogSearch {
+      // after "─/" in the bottom border
+      let x = logs_panel.x + 3 + self.log_search_input.visual_cursor() as u16;
+      f.set_cursor_position((
+        x.min(logs_panel.x + logs_panel.width.saturating_sub(2)),
+        logs_panel.y + logs_panel.height - 1,
+      ));
+    }
+
+    if self.mode == Mode::Search {
+      f.set_cursor_position((
+        (search_panel.x + 1 + self.input.cursor() as u16).min(search_panel.x + search_panel.width - 2),
+        search_panel.y + 1,
+      ));
+    }
+
+    if self.mode == Mode::Help {
+      self.viewer.render(f, f.area());
+    }
+
+    if self.mode == Mode::Error {
+      let popup = centered_rect_abs(50, 12, f.area());
+      let error_lines = self.error_message.split('\n').map(Line::from).collect_vec();
+      let paragraph = Paragraph::new(error_lines)
+        .block(
+          pane_block(accessible).title(pane_title("Error", accessible)).border_style(Style::default().fg(Color::Red)),
+        )
+        .wrap(Wrap { trim: true });
+
+      f.render_widget(Clear, popup);
+      f.render_widget(paragraph, popup);
+    }
+
+    if accessible {
+      let status = self.accessible_status();
+      f.render_widget(Line::from(status), status_rect);
+      // screen readers generally follow the cursor, so park it on the announcement
+      if self.mode != Mode::Search {
+        f.set_cursor_position((status_rect.x, status_rect.y));
+      }
+    }
+
+    let selected_item = match self.filtered_units.selected() {
+      Some(s) => s,
+      None => return,
+    };
+
+    // Help line at the bottom
+
+    let version = format!("v{}", env!("CARGO_PKG_VERSION"));
+
+    let help_line_rects =
+      Layout::new(Direction::Horizontal, [Constraint::Fill(1), Constraint::Length(version.len() as u16)])
+        .split(help_line_rect);
+    let help_rect = help_line_rects[0];
+    let version_rect = help_line_rects[1];
+
+    let help_line = match self.mode {
+      Mode::Search => Line::from(span("Show actions: <enter>", theme.primary)),
+      Mode::ServiceList if self.read_only => Line::from(vec![
+        span("Show actions: <enter> | ", theme.primary),
+        span("Open unit file: e", theme.muted),
+        span(" | Switch boot: b | Quit: q", theme.primary),
+      ]),
+      Mode::ServiceList => {
+        Line::from(span("Show actions: <enter> | Open unit file: e | Switch boot: b | Quit: q", theme.primary))
+      },
+      Mode::Help => Line::from(span("Close menu: <esc>", theme.primary)),
+      Mode::ActionMenu => Line::from(span("Execute action: <enter> | Close menu: <esc>", theme.primary)),
+      Mode::Processing => Line::from(span("Cancel task: <esc>", theme.primary)),
+      Mode::Error => Line::from(span("Close menu: <esc>", theme.primary)),
+      Mode::SignalMenu => Line::from(span("Send signal: <enter> | Close menu: <esc>", theme.primary)),
+      Mode::RunMenu => Line::from(span("Show logs of run: <enter> | Close menu: <esc>", theme.primary)),
+      Mode::Prompt => Line::from(span("Choose: <enter> | Dismiss: <esc>", theme.primary)),
+      Mode::Viewer => Line::from(span("Scroll: j/k | Close: <esc>", theme.primary)),
+      Mode::Form => Line::from(span("Next field: <tab> | Submit: <enter> | Cancel: <esc>", theme.primary)),
+      Mode::DepTree => Line::from(span("Expand: l | Collapse: h | Go to unit: g | Close: <esc>", theme.primary)),
+      Mode::Properties if self.property_inspector.unit.is_none() => Line::from(span(
+        "Filter: type | Only entries with it: <enter> | Hide them: ctrl+x | Copy value: ctrl+y | Close: <esc>",
+        theme.primary,
+      )),
+      Mode::Properties => Line::from(span("Filter: type | Copy value: <enter> | Close: <esc>", theme.primary)),
+      Mode::MergedLogs => Line::from(span("Scroll: j/k | Newest: g | Times: t | Close: <esc>", theme.primary)),
+      Mode::Jobs if self.read_only => Line::from(vec![
+        span("Move: j/k | ", theme.primary),
+        span("Cancel job: c", theme.muted),
+        span(" | Close: <esc>", theme.primary),
+      ]),
+      Mode::Jobs => Line::from(span("Move: j/k | Cancel job: c | Close: <esc>", theme.primary)),
+      Mode::Logs => Line::from(Span::styled(self.log_pane_hints(), Style::default().fg(theme.primary))),
+      Mode::LogSearch => Line::from(span("Go to match: <enter> | Regex on/off: ctrl+r | Cancel: <esc>", theme.primary)),
+      Mode::CommandBar => {
+        let mut spans = vec![
+          Span::styled(":", Style::default().fg(theme.primary)),
+          Span::raw(self.command_input.value().to_string()),
+        ];
+        let suggestions = command_bar::suggestions(self.command_input.value(), &self.unit_names(), 5);
+        if !suggestions.is_empty() {
+          spans.push(Span::styled(format!("  Tab: {}", suggestions.join("  ")), Style::default().fg(theme.muted_alt)));
+        }
+        f.set_cursor_position((help_rect.x + 1 + self.command_input.visual_cursor() as u16, help_rect.y));
+        Line::from(spans)
+      },
+    };
+
+    self.mouse_areas.hints = help_rect;
+    self.mouse_areas.hints_text = match self.mode {
+      Mode::CommandBar => String::new(),
+      _ => help_line.spans.iter().map(|s| s.content.as_ref()).collect(),
+    };
+    f.render_widget(help_line, help_rect);
+    f.render_widget(Line::from(version), version_rect);
+
+    if let Some((label, command)) = self.echoed_command() {
+      let echo = Line::from(vec![
+        Span::styled(format!("{label}: "), Style::default().fg(theme.muted)),
+        Span::styled("$ ", Style::default().fg(theme.muted_alt)),
+        Span::styled(command, Style::default().fg(theme.kbd)),
+      ]);
+      f.render_widget(echo, echo_rect);
+    }
+
+    let title = format!("Actions for {}", selected_item.name);
+    let mut min_width = title.len() as u16 + 2; // title plus corners
+    min_width = min_width.max(24); // hack: the width of the longest action name + 2
+
+    let popup_width = min_width.min(f.area().width);
+
+    if matches!(self.mode, Mode::ActionMenu | Mode::SignalMenu | Mode::RunMenu | Mode::Prompt) {
+      let title = match self.mode {
+        Mode::ActionMenu => format!("Actions for {}", selected_item.name),
+        Mode::SignalMenu => format!("Signals for {}", selected_item.name),
+        Mode::Prompt => self.prompt_title.clone(),
+        _ => format!("Past runs for {}", selected_item.name),
+      };
+      let prompt_lines = if self.mode == Mode::Prompt { self.prompt_lines.clone() } else { vec![] };
+      // the prompt's details go above the answers, with a blank line between
+      let details_height = if prompt_lines.is_empty() { 0 } else { prompt_lines.len() as u16 + 1 };
+      let height = self.menu_items.items.len() as u16 + 2 + details_height;
+      // key, name and borders
+      let widest = self.menu_items.items.iter().map(|i| i.name.len() as u16 + 6).max().unwrap_or_default();
+      let widest = prompt_lines.iter().map(|l| l.width() as u16 + 4).fold(widest, u16::max);
+      let popup_width = widest.max(title.len() as u16 + 2).max(popup_width).min(f.area().width);
+      let popup = centered_rect_abs(popup_width, height.min(f.area().height), f.area());
+
+      let items: Vec<ListItem> = self
+        .menu_items
+        .items
+        .iter()
+        .map(|i| {
+          let line = if self.refuses(&i.action) {
+            Line::styled(format!(" {:1} {}", i.key_string(), i.name), Style::default().fg(theme.muted))
+          } else {
+            let key_string = Span::styled(format!(" {:1} ", i.key_string()), Style::default().fg(theme.primary));
+            Line::from(vec![key_string, Span::raw(&i.name)])
+          };
+          ListItem::new(line)
+        })
+        .collect();
+      let block = pane_block(accessible).border_style(Style::default().fg(theme.accent)).title(title);
+      let mut items =
+        List::new(items).highlight_style(Style::default().bg(theme.selection).add_modifier(Modifier::BOLD));
+      if accessible {
+        items = items.highlight_symbol("> ");
+      }
+
+      f.render_widget(Clear, popup);
+      let inner = block.inner(popup);
+      f.render_widget(block, popup);
+      let [details, answers] = Layout::vertical([Constraint::Length(details_height), Constraint::Min(0)]).areas(inner);
+      f.render_widget(Paragraph::new(prompt_lines), details);
+      f.render_stateful_widget(items, answers, &mut self.menu_items.state);
+    }
+
+    if self.mode == Mode::Viewer {
+      self.viewer.render(f, f.area());
+    }
+
+    if self.mode == Mode::Form {
+      self.form.render(f, f.area());
+    }
+
+    if self.mode == Mode::DepTree {
+      let scope = self.dep_tree.scope;
+      for unit in self.all_units.values().filter(|u| Some(u.scope) == scope) {
+        self.dep_tree.update_state(&unit.name, &unit.load_state, &unit.activation_state);
+      }
+      self.dep_tree.render(f, f.area());
+    }
+
+    if self.mode == Mode::MergedLogs {
+      self.merged_logs.render(f, f.area());
+    }
+
+    if self.mode == Mode::Properties {
+      self.property_inspector.render(f, f.area());
+    }
+
+    if self.mode == Mode::Jobs {
+      self.job_queue.render(f, f.area());
+    }
+
+    if self.mode == Mode::Processing {
+      let height = self.menu_items.items.len() as u16 + 2;
+      let popup = centered_rect_abs(popup_width, height, f.area());
+
+      // braille spinners come out as gibberish through a screen reader
+      let spinner = if accessible { "Working...".to_string() } else { format!("{}", self.spinner_char()) };
+      let paragraph = Paragraph::new(vec![Line::from(spinner)])
+        .block(pane_block(accessible).title("Processing").border_style(Style::default().fg(theme.accent)))
+        .style(Style::default())
+        .wrap(Wrap { trim: true });
+
+      f.render_widget(Clear, popup);
+      f.render_widget(paragraph, popup);
+    }
+
+    theme::fill_foreground(f.buffer_mut(), theme.foreground);
+  }
+}
+
+/// journalctl matches for one run of a unit: what the unit itself logged and what the manager logged about it
+fn invocation_matches(unit: &UnitId, invocation: &str) -> Vec<String> {
+  let manager_field = match unit.scope {
+    UnitScope::Global => "INVOCATION_ID",
+    UnitScope::User => "USER_INVOCATION_ID",
+  };
+  vec![format!("_SYSTEMD_INVOCATION_ID={invocation}"), "+".into(), format!("{manager_field}={invocation}")]
+}
+
+fn get_run_logs(unit: &UnitId, run: &Invocation) -> anyhow::Result<Vec<String>> {
+  let mut command = systemd::journalctl();
+  command.args(["--quiet", "--output=short-iso", "--no-pager"]).args(invocation_matches(unit, &run.id));
+  systemd::in_scope(&mut command, unit.scope);
+  let output = remote::on_host(&mut command).output()?;
+  if !output.status.success() {
+    anyhow::bail!(String::from_utf8_lossy(&output.stderr).to_string());
+  }
+  Ok(String::from_utf8_lossy(&output.stdout).lines().filter(|l| !l.is_empty()).map(String::from).collect())
+}
+
+/// e.g. `2026-10-14 03:00:12 (4m 10s) failed: exit-code, status 1`
+fn run_label(run: &Invocation) -> String {
+  let local = |micros: i64| DateTime::from_timestamp_micros(micros).map(|t| t.with_timezone(&Local));
+  let started = local(run.started).map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_default();
+  let mut label = match run.ended {
+    Some(ended) => format!("{started} ({})", humanize::duration(Duration::from_micros((ended - run.started) as u64))),
+    None => format!("{started} (no end recorded)"),
+  };
+  match run.result.as_deref() {
+    Some("success") => label.push_str(" succeeded"),
+    Some(result) => label.push_str(&format!(" failed: {result}")),
+    None => {},
+  }
+  if let Some(status) = run.exit_status.as_deref().filter(|s| *s != "0") {
+    label.push_str(&format!(", status {status}"));
+  }
+  label
+}
+
+/// Each extra list column is right-aligned in this many cells
+const LIST_COLUMN_WIDTH: u16 = 14;
+
+/// e.g. `  1.2M/340K` for the I/O column: in/out, or a dash when accounting is off or the unit isn't running. Timer
+/// columns are relative to `now`, in seconds since the epoch
+fn list_column_cell(
+  column: ListColumn,
+  accounting: Option<&Accounting>,
+  rates: Option<&Rates>,
+  timer: Option<&TimerTimes>,
+  file_state: Option<&str>,
+  scope: UnitScope,
+  now: i64,
+) -> String {
+  let text = match (column, accounting) {
+    (ListColumn::Scope, _) => match scope {
+      UnitScope::Global => "system".into(),
+      UnitScope::User => "user".into(),
+    },
+    (ListColumn::FileState, _) => file_state.filter(|s| !s.is_empty()).unwrap_or("-").to_string(),
+    (ListColumn::NextElapse, _) => timer.and_then(|t| t.next_elapse).map_or("-".into(), |t| humanize::relative(t, now)),
+    (ListColumn::LastTrigger, _) => {
+      timer.and_then(|t| t.last_trigger).map_or("-".into(), |t| humanize::relative(t, now))
+    },
+    (_, None) => "-".into(),
+    (ListColumn::Io, Some(a)) if a.io_accounting => {
+      format!("{}/{}", format_bytes_or_dash(a.io_read_bytes), format_bytes_or_dash(a.io_write_bytes))
+    },
+    (ListColumn::Network, Some(a)) if a.ip_accounting => {
+      format!("{}/{}", format_bytes_or_dash(a.ip_ingress_bytes), format_bytes_or_dash(a.ip_egress_bytes))
+    },
+    (ListColumn::IoRate, Some(a)) if a.io_accounting => match rates {
+      Some(r) => format!("{}/{}/s", format_rate_or_dash(r.io_read), format_rate_or_dash(r.io_write)),
+      None => "-".into(),
+    },
+    (ListColumn::NetworkRate, Some(a)) if a.ip_accounting => match rates {
+      Some(r) => format!("{}/{}/s", format_rate_or_dash(r.ip_ingress), format_rate_or_dash(r.ip_egress)),
+      None => "-".into(),
+    },
+    (ListColumn::Cpu, Some(a)) => {
+      a.cpu_usage_nsec.map(|nsec| humanize::duration(Duration::from_nanos(nsec))).unwrap_or_else(|| "-".into())
+    },
+    (ListColumn::Uptime, Some(a)) => a.uptime().map(humanize::duration).unwrap_or_else(|| "-".into()),
+    (ListColumn::StateChanged, Some(a)) => {
+      a.since_state_change().map_or("-".into(), |d| format!("{} ago", humanize::duration(d)))
+    },
+    (ListColumn::CpuPercent, Some(_)) => rates.and_then(|r| r.cpu_percent).map_or("-".into(), humanize::percent),
+    (ListColumn::Memory, Some(a)) => a.memory_current.map_or("-".into(), humanize::size),
+    (ListColumn::Tasks, Some(a)) => a.tasks_current.map_or("-".into(), |t| t.to_string()),
+    _ => "-".into(),
+  };
+  format!("{text:>width$}", width = LIST_COLUMN_WIDTH as usize)
+}
+
+/// Where the selection ends up after moving `by` units in a list of `len`. Moving past an end stops there, unless
+/// the selection is at that end already and `wrap` is set, in which case it goes to the other end
+fn moved(selected: Option<usize>, len: usize, by: isize, wrap: bool) -> Option<usize> {
+  let last = len.checked_sub(1)?;
+  let Some(index) = selected else { return Some(0) };
+  let target = index as isize + by;
+  Some(if target > last as isize {
+    if wrap && index == last {
+      0
+    } else {
+      last
+    }
+  } else if target < 0 {
+    if wrap && index == 0 {
+      last
+    } else {
+      0
+    }
+  } else {
+    target as usize
+  })
+}
+
+/// The first index after `selected` that `matches`, going on from the top if `wrap` is set
+fn next_match(selected: Option<usize>, len: usize, wrap: bool, matches: impl Fn(usize) -> bool) -> Option<usize> {
+  let start = selected.map_or(0, |i| i + 1);
+  let wrapped = if wrap { 0..start.min(len) } else { 0..0 };
+  (start..len).chain(wrapped).find(|i| matches(*i))
+}
+
+/// `systemctl mask`, or `unmask` for a unit that's masked already
+fn mask_action(unit: &UnitWithStatus) -> Action {
+  let verb = if unit.load_state == "masked" { "unmask" } else { "mask" };
+  Action::RunSystemctl(unit.id(), vec![verb.into(), unit.name.clone()])
+}
+
+/// What a stop, restart, kill or mask does and the units it does it to
+fn destructive_verb(action: &Action) -> Option<(String, Vec<&str>)> {
+  match action {
+    Action::StopService(unit) => Some(("stop".into(), vec![&unit.name])),
+    Action::RestartService(unit) | Action::ReloadAndRestart(unit) => Some(("restart".into(), vec![&unit.name])),
+    Action::KillService { unit, signal, .. } => Some((format!("send {signal} to"), vec![&unit.name])),
+    // e.g. from the command bar
+    Action::RunSystemctl(_, args) => match args.split_first() {
+      Some((verb, rest))
+        if ["stop", "restart", "try-restart", "reload-or-restart", "kill", "mask"].contains(&verb.as_str()) =>
+      {
+        Some((verb.clone(), rest.iter().filter(|a| !a.starts_with('-')).map(String::as_str).collect()))
+      },
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+/// `nginx.service` for one unit, `3 units` for more
+fn units_label(units: &[UnitId]) -> String {
+  match units {
+    [unit] => unit.name.clone(),
+    _ => format!("{} units", units.len()),
+  }
+}
+
+/// What a unit is sorted by in `column`; `None` for units without a value, which go last
+fn sort_value(column: ListColumn, accounting: Option<&Accounting>, rates: Option<&Rates>) -> Option<f64> {
+  match column {
+    ListColumn::CpuPercent => rates?.cpu_percent,
+    ListColumn::IoRate => rates?.io(),
+    ListColumn::NetworkRate => rates?.network(),
+    ListColumn::Memory => accounting?.memory_current.map(|m| m as f64),
+    ListColumn::Tasks => accounting?.tasks_current.map(|t| t as f64),
+    // the latest change is the highest timestamp
+    ListColumn::StateChanged => accounting?.state_changed_usec.map(|t| t as f64),
+    _ => None,
+  }
+}
+
+/// Where a unit goes when sorting by active or load state, lowest first: the states worth a look come first
+fn state_rank(sort: SortOrder, unit: &UnitWithStatus) -> u8 {
+  match sort {
+    SortOrder::ActiveState => match unit.activation_state.as_str() {
+      "failed" => 0,
+      "activating" | "deactivating" | "reloading" | "refreshing" => 1,
+      "active" => 2,
+      _ => 3,
+    },
+    SortOrder::LoadState => match unit.load_state.as_str() {
+      "not-found" | "error" | "bad-setting" => 0,
+      "masked" => 1,
+      "loaded" => 2,
+      _ => 3,
+    },
+    _ => 0,
+  }
+}
+
+/// `system and user`, for the unit list title
+fn scope_label(scope: Scope) -> &'static str {
+  match scope {
+    Scope::Global => "system",
+    Scope::User => "user",
+    Scope::All => "system and user",
+  }
+}
+
+/// Next, Last, Schedule and Upcoming rows of a timer's details
+fn timer_detail_lines(times: Option<&TimerTimes>, schedule: Option<&Schedule>, muted: Color) -> Vec<Line<'static>> {
+  let now = Local::now();
+  let at = |time: Option<i64>, none: &str| match time.and_then(|t| DateTime::from_timestamp(t, 0)) {
+    Some(t) => Line::from(format!(
+      "{} ({})",
+      humanize::relative(t.timestamp(), now.timestamp()),
+      t.with_timezone(&Local).format("%a %Y-%m-%d %H:%M")
+    )),
+    None => Line::from(Span::styled(none.to_string(), Style::default().fg(muted))),
+  };
+  let Some(times) = times else { return vec![] };
+  let mut lines = vec![at(times.next_elapse, "nothing scheduled"), at(times.last_trigger, "never")];
+  if let Some(schedule) = schedule {
+    lines.push(Line::from(schedule.triggers.join(", ")));
+    lines.push(Line::from(schedule.next_runs.join(", ")));
+  }
+  lines
+}
+
+fn format_bytes_or_dash(bytes: Option<u64>) -> String {
+  bytes.map(humanize::size).unwrap_or_else(|| "-".into())
+}
+
+/// A rate in bytes per second without the `/s`, which goes after the pair
+fn format_rate_or_dash(bytes_per_second: Option<f64>) -> String {
+  format_bytes_or_dash(bytes_per_second.map(|b| b.round() as u64))
+}
+
+/// Shown for keys and actions refused in read-only mode
+const READ_ONLY_REFUSAL: &str = "Read-only session: units and the machine can't be changed (started with --read-only)";
+
+/// CPU and memory use at one accounting refresh
+#[derive(Debug, Clone, Copy)]
+struct ResourceSample {
+  at: Instant,
+  cpu_percent: Option<f64>,
+  memory: Option<u64>,
+}
+
+/// Refreshes of CPU and memory use kept for a unit's graphs, ten minutes' worth
+const HISTORY_SAMPLES: usize = 120;
+
+/// Width of each of the details pane's resource graphs, in cells
+const TREND_CELLS: usize = 12;
+
+/// How long a unit fetch runs before the list's title shows a spinner, so the usual quick ones don't flicker
+const SLOW_REFRESH: Duration = Duration::from_millis(500);
+
+/// How often spinners turn
+const SPINNER_INTERVAL: Duration = Duration::from_millis(200);
+
+const SPINNER_CHARS: &[char] = &['⣷', '⣯', '⣟', '⡿', '⢿', '⣻', '⣽', '⣾'];
+
+/// How often the job queues are polled while there's something in them
+const JOB_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long a piped command gets before it's killed, so something like `tail -f` can't hang the viewer
+const PIPE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Run `command` through the shell with `input` on stdin; returns stdout, followed by stderr if there was any
+fn pipe_through(command: &str, input: String) -> anyhow::Result<String> {
+  let finished = run_with_timeout(std::process::Command::new("sh").args(["-c", command]), Some(input), PIPE_TIMEOUT)?;
+  let Some(Finished { status, stdout: mut output, stderr }) = finished else {
+    anyhow::bail!("`{command}` was still running after {}s, so it was stopped", PIPE_TIMEOUT.as_secs());
+  };
+  output.push_str(&stderr);
+  // grep exits 1 when nothing matched, which isn't worth an error
+  if !status.success() && status.code() != Some(1) {
+    anyhow::bail!("`{command}` failed ({status}): {}", output.trim());
+  }
+  Ok(output)
+}
+
+/// Marks audit lines merged into a unit's logs so they stand out
+const AUDIT_TAG: &str = "[audit]";
+
+/// At most this often the journal thread reads a unit's logs, however quickly the selection moves
+const JOURNAL_READ_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Log lines read at a time: the latest when a unit is selected, then older ones as the pane is scrolled back
+const LOG_PAGE_LINES: usize = 500;
+
+/// Older lines are read once the pane is scrolled back to within this many of the oldest one
+const OLDER_LOGS_MARGIN: usize = 100;
+
+/// The log pane keeps at most this many lines. New ones push out the oldest, and older ones read while scrolling back
+/// push out the newest, which stops following until the pane is scrolled back to the top
+const MAX_LOG_LINES: usize = 10_000;
+
+/// Add newer lines after `logs`, returning how many of the oldest were dropped to stay within `MAX_LOG_LINES`
+fn append_log_lines(logs: &mut Vec<Entry>, lines: Vec<Entry>) -> usize {
+  logs.extend(lines);
+  let excess = logs.len().saturating_sub(MAX_LOG_LINES);
+  logs.drain(..excess);
+  excess
+}
+
+/// Put older lines before `logs`, returning how many of the newest were dropped to stay within `MAX_LOG_LINES`
+fn prepend_log_lines(logs: &mut Vec<Entry>, mut lines: Vec<Entry>) -> usize {
+  lines.append(logs);
+  let excess = lines.len().saturating_sub(MAX_LOG_LINES);
+  lines.truncate(MAX_LOG_LINES);
+  *logs = lines;
+  excess
+}
+
+/// How long followed log lines are collected before being appended as one batch
+const FOLLOW_BATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A follow stream that ends after running at least this long is restarted. One that ends sooner is probably failing
+/// outright (bad permissions, say), and restarting it would only loop
+const FOLLOW_RESTART_AFTER: Duration = Duration::from_secs(5);
+
+/// Stream new log lines of `unit` as they're written, from now or from where the buffered logs end
+fn follow_logs(
+  tx: UnboundedSender<Action>,
+  unit: UnitId,
+  matches: Vec<String>,
+  resume: Option<ResumePoint>,
+) -> JoinHandle<()> {
+  tokio::spawn(async move {
+    let started = Instant::now();
+    let mut follow = match journal::Follow::spawn(unit.scope, &matches, resume.as_ref().map(|r| r.since)) {
+      Ok(follow) => follow,
+      Err(e) => {
+        error!("Error following the logs of {}: {e:#}", unit.name);
+        return;
+      },
+    };
+    // lines from the resumed second that are already on screen
+    let mut seen = resume.map(|r| r.seen).unwrap_or_default();
+    // a chatty unit can log thousands of lines a second; hand them over in batches so each one doesn't cost
+    // a render
+    let mut pending = vec![];
+    let mut flush = tokio::time::interval(FOLLOW_BATCH_INTERVAL);
+    loop {
+      tokio::select! {
+        read = follow.next() => match read {
+          None => break,
+          Some(lines) => {
+            for line in lines {
+              match seen.iter().position(|s| *s == line) {
+                Some(index) => {
+                  seen.remove(index);
+                },
+                None => {
+                  seen.clear();
+                  pending.push(line);
+                },
+              }
+            }
+          },
+        },
+        _ = flush.tick(), if !pending.is_empty() => {
+          let _ = tx.send(Action::AppendLogLines { unit: unit.clone(), lines: std::mem::take(&mut pending) });
+          let _ = tx.send(Action::Render);
+        },
+      }
+    }
+    if !pending.is_empty() {
+      let _ = tx.send(Action::AppendLogLines { unit: unit.clone(), lines: pending });
+      let _ = tx.send(Action::Render);
+    }
+    // journalctl exited without being asked to (it's aborted when the selection moves on)
+    if started.elapsed() >= FOLLOW_RESTART_AFTER {
+      warn!("The log stream of {} stopped", unit.name);
+      let _ = tx.send(Action::FollowStopped(unit));
+    }
+  })
+}
+
+/// Show the last lines logged by any of `units`, then stream new ones as they're written, each with the unit it's from.
+/// System and user units are read from their own journalctl, one per manager
+fn follow_merged_logs(tx: UnboundedSender<Action>, units: Vec<UnitId>) -> JoinHandle<()> {
+  tokio::spawn(async move {
+    let scopes = units.iter().map(|u| u.scope).unique().collect_vec();
+    let matches = |scope: UnitScope| {
+      units.iter().filter(|u| u.scope == scope).flat_map(|u| ["-u".to_string(), u.name.clone()]).collect_vec()
+    };
+
+    // like the log pane: a batch of the latest lines, then a stream, accepting that lines written in between are missed
+    let mut entries = vec![];
+    for &scope in &scopes {
+      let mut journalctl = systemd::journalctl();
+      journalctl.args(["--quiet", "--output=export", "--lines=500"]).args(matches(scope));
+      systemd::in_scope(&mut journalctl, scope);
+      remote::on_host(&mut journalctl);
+      let output = tokio::process::Command::from(journalctl).output().await;
+      match output {
+        Ok(output) => {
+          let read = journal::ExportParser::default().feed(&output.stdout);
+          entries.extend(read.into_iter().map(|fields| (scope, fields)));
+        },
+        Err(e) => warn!("Error getting the logs of {} units: {e}", units.len()),
+      }
+    }
+    let timestamp = |fields: &HashMap<String, String>| {
+      fields.get("__REALTIME_TIMESTAMP").and_then(|t| t.parse::<u64>().ok()).unwrap_or_default()
+    };
+    entries.sort_by_key(|(_, fields)| timestamp(fields));
+    let lines = entries
+      .iter()
+      .flat_map(|(scope, fields)| merged_logs::attribute(&units, *scope, std::slice::from_ref(fields)))
+      .collect_vec();
+    let _ = tx.send(Action::AppendMergedLogs { units: units.clone(), lines });
+    let _ = tx.send(Action::Render);
+
+    let (lines_tx, mut lines_rx) = tokio::sync::mpsc::unbounded_channel();
+    // dropped, and so stopped along with their journalctl, when this task is aborted
+    let mut readers = tokio::task::JoinSet::new();
+    for scope in scopes {
+      let mut follow = match journal::Follow::spawn(scope, &matches(scope), None) {
+        Ok(follow) => follow,
+        Err(e) => {
+          error!("Error following the logs of {} units: {e:#}", units.len());
+          continue;
+        },
+      };
+      let (units, lines_tx) = (units.clone(), lines_tx.clone());
+      readers.spawn(async move {
+        while let Some(entries) = follow.next_fields().await {
+          let _ = lines_tx.send(merged_logs::attribute(&units, scope, &entries));
+        }
+      });
+    }
+    drop(lines_tx);
+
+    let mut pending = vec![];
+    let mut flush = tokio::time::interval(FOLLOW_BATCH_INTERVAL);
+    loop {
+      tokio::select! {
+        read = lines_rx.recv() => match read {
+          None => break,
+          Some(lines) => pending.extend(lines),
+        },
+        _ = flush.tick(), if !pending.is_empty() => {
+          let _ = tx.send(Action::AppendMergedLogs { units: units.clone(), lines: std::mem::take(&mut pending) });
+          let _ = tx.send(Action::Render);
+        },
+      }
+    }
+    if !pending.is_empty() {
+      let _ = tx.send(Action::AppendMergedLogs { units: units.clone(), lines: pending });
+      let _ = tx.send(Action::Render);
+    }
+  })
+}
+
+/// How long the audit and kernel messages read for one unit are reused when moving back to it
+const AUDIT_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Reading audit and kernel messages scans more of the journal than a unit's own logs, so moving the selection back
+/// and forth reuses the last read. Reading the logs of the unit already shown again, e.g. after it's restarted, reads
+/// them afresh
+#[derive(Default)]
+struct AuditCache {
+  read: Option<(AuditQuery, Instant, Vec<String>)>,
+}
+
+/// What the audit and kernel messages were read for
+#[derive(PartialEq)]
+struct AuditQuery {
+  unit: UnitId,
+  boot: BootFilter,
+  since: Option<i64>,
+  matches: Vec<String>,
+}
+
+impl AuditCache {
+  fn lines(&mut self, query: AuditQuery, reuse: bool) -> anyhow::Result<&[String]> {
+    let fresh = reuse && self.read.as_ref().is_some_and(|(q, at, _)| *q == query && at.elapsed() < AUDIT_CACHE_TTL);
+    if !fresh {
+      let mut args = vec!["--quiet".to_string(), "--output=short-iso".into(), "--lines=2000".into()];
+      args.extend(query.boot.journalctl_arg());
+      args.extend(query.since.map(|since| format!("--since=@{since}")));
+      args.extend(query.matches.iter().cloned());
+      let output = remote::on_host(systemd::journalctl().args(&args)).output()?;
+      if !output.status.success() {
+        anyhow::bail!(String::from_utf8_lossy(&output.stderr).to_string());
+      }
+      let lines = String::from_utf8_lossy(&output.stdout).lines().map(String::from).collect();
+      self.read = Some((query, Instant::now(), lines));
+    }
+    Ok(self.read.as_ref().map(|(_, _, lines)| lines.as_slice()).unwrap_or_default())
+  }
+}
+
+/// SELinux AVC and AppArmor denials from the journal that mention the unit's main PID or executables, from `since`
+/// (when the unit's logs start) on. These often show up as a vague "permission denied" or exec failure in the unit's
+/// own logs. `reuse` allows a recent read of the same messages instead of reading them again
+fn get_related_audit_lines(
+  unit: &UnitId,
+  boot: BootFilter,
+  since: Option<i64>,
+  cache: &mut AuditCache,
+  reuse: bool,
+) -> anyhow::Result<Vec<String>> {
+  let properties = systemd::get_unit_properties(unit, &["MainPID", "ExecStart", "ExecStartPre"])?;
+  let pids = properties.get("MainPID").filter(|p| *p != "0").cloned().into_iter().collect_vec();
+  let exes = ["ExecStart", "ExecStartPre"]
+    .iter()
+    .filter_map(|p| properties.get(*p))
+    .flat_map(|v| systemd::parse_exec_paths(v))
+    .collect_vec();
+
+  let query = AuditQuery { unit: unit.clone(), boot, since, matches: audit_matches(&pids, &exes) };
+  Ok(
+    cache
+      .lines(query, reuse)?
+      .iter()
+      .filter(|l| is_related_denial(l, &pids, &exes))
+      .filter_map(|l| l.split_once(' ').map(|(timestamp, rest)| format!("{timestamp} {AUDIT_TAG} {rest}")))
+      .collect(),
+  )
+}
+
+/// journalctl matches for the audit messages about `pids` or `exes`. journald records the process an audit message
+/// is about in `_PID` and `_COMM`; denials that only reach the kernel log carry no such fields, so all of those are
+/// read and filtered afterwards
+fn audit_matches(pids: &[String], exes: &[String]) -> Vec<String> {
+  let mut matches = vec![];
+  if !pids.is_empty() {
+    matches.push("_TRANSPORT=audit".to_string());
+    matches.extend(pids.iter().map(|p| format!("_PID={p}")));
+    matches.push("+".into());
+  }
+  if !exes.is_empty() {
+    matches.push("_TRANSPORT=audit".to_string());
+    matches.extend(exes.iter().map(|e| comm(e)).unique().map(|c| format!("_COMM={c}")));
+    matches.push("+".into());
+  }
+  matches.push("_TRANSPORT=kernel".into());
+  matches
+}
+
+/// The name the kernel knows the process running `exe` by: its file name, cut to the 15 bytes a task's name holds
+fn comm(exe: &str) -> &str {
+  let name = exe.rsplit('/').next().unwrap_or(exe);
+  let mut end = name.len().min(15);
+  while !name.is_char_boundary(end) {
+    end -= 1;
+  }
+  &name[..end]
+}
+
+fn is_related_denial(line: &str, pids: &[String], exes: &[String]) -> bool {
+  let is_denial = line.contains("avc:") || line.contains("apparmor=\"DENIED\"");
+  is_denial
+    && (pids.iter().any(|p| line.contains(&format!("pid={p} ")))
+      || exes.iter().any(|e| line.contains(&format!("exe=\"{e}\"")) || line.contains(&format!("comm=\"{}\"", comm(e)))))
+}
+
+/// The unit's log lines from this boot, merged with what the service manager logged around the same time
+fn get_boot_context(unit: &UnitId) -> anyhow::Result<Vec<String>> {
+  let journalctl = |args: &[&str]| -> anyhow::Result<Vec<String>> {
+    let mut command = systemd::journalctl();
+    command.args(["--quiet", "--output=short-iso", "--boot=0", "--no-pager"]).args(args);
+    systemd::in_scope(&mut command, unit.scope);
+    let output = remote::on_host(&mut command).output()?;
+    if !output.status.success() {
+      anyhow::bail!(String::from_utf8_lossy(&output.stderr).to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).lines().filter(|l| !l.is_empty()).map(String::from).collect())
+  };
+
+  let unit_logs = journalctl(&["-u", &unit.name])?;
+  let timestamp = |l: &String| l.split_once(' ').and_then(|(t, _)| parse_journalctl_datetime(t));
+  let (Some(first), Some(last)) = (unit_logs.first().and_then(timestamp), unit_logs.last().and_then(timestamp)) else {
+    anyhow::bail!("{} hasn't logged anything during this boot", unit.name);
+  };
+
+  // a minute before the unit's first line catches the dependencies it was waiting on
+  let since = format!("--since=@{}", first.timestamp() - 60);
+  let until = format!("--until=@{}", last.timestamp() + 10);
+  let manager = if unit.scope == UnitScope::User { "_COMM=systemd" } else { "_PID=1" };
+  let manager_logs = journalctl(&[&since, &until, manager])?
+    .into_iter()
+    .filter(|l| is_boot_context_message(l, &unit.name))
+    .filter_map(|l| l.split_once(' ').map(|(timestamp, rest)| format!("{timestamp} {MANAGER_TAG} {rest}")))
+    .collect();
+  Ok(merge_log_lines(unit_logs, manager_logs))
+}
+
+/// Each unit's After= list
+type AfterLists = HashMap<String, Vec<String>>;
+
+/// When each unit started and was ready this boot, and each unit's After= list
+fn boot_timings(scope: UnitScope) -> anyhow::Result<(HashMap<String, boot_wait::Timing>, AfterLists)> {
+  let mut timings = systemd::get_boot_timings(scope)?;
+  let units = systemd::get_all_unit_properties(scope, boot_wait::PROPERTIES)?;
+  let mut after = HashMap::new();
+  for properties in &units {
+    let Some(id) = properties.get("Id") else { continue };
+    // the journal can have been rotated or not be persistent; what the manager remembers is the next best thing
+    timings.entry(id.clone()).or_insert_with(|| boot_wait::Timing::from_properties(properties));
+    let dependencies = properties.get("After").map_or(vec![], |a| a.split_whitespace().map(String::from).collect());
+    after.insert(id.clone(), dependencies);
+  }
+  Ok((timings, after))
+}
+
+/// How long the unit waited on each of its After= dependencies this boot, and the chain of units that held it up
+fn boot_wait_lines(unit: &UnitId) -> Vec<Line<'static>> {
+  let error = |e: anyhow::Error| vec![Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red)))];
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let (timings, after) = match boot_timings(unit.scope) {
+    Ok(timings) => timings,
+    Err(e) => return error(e),
+  };
+  let Some(analysis) = boot_wait::analyze(&unit.name, &after, &timings) else {
+    return vec![Line::from(format!("{} didn't start during this boot", unit.name))];
+  };
+
+  let at =
+    |usec: Option<u64>| usec.map_or("?".into(), |u| format!("@{}", humanize::duration(Duration::from_micros(u))));
+  let took = |timing: &boot_wait::Timing| {
+    timing.took().map_or(String::new(), |t| format!(" (took {})", humanize::duration(Duration::from_micros(t))))
+  };
+  let mut lines = vec![
+    Line::from(format!(
+      "{} started at {} and was active at {}{}",
+      unit.name,
+      at(analysis.timing.started.or(analysis.timing.ready)),
+      at(analysis.timing.ready),
+      took(&analysis.timing)
+    )),
+    Line::from(Span::styled("Times are since the kernel started", dim)),
+    Line::from(""),
+  ];
+  let blocker = analysis.chain.get(1).map(|(name, _)| name.clone());
+  match analysis.waits.iter().find(|w| Some(&w.unit) == blocker.as_ref()) {
+    Some(wait) => lines.push(Line::from(Span::styled(
+      format!(
+        "Held up by {}, ready {} before {} started",
+        wait.unit,
+        humanize::duration(Duration::from_micros(wait.slack.unwrap_or(0))),
+        unit.name
+      ),
+      Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+    ))),
+    None => lines.push(Line::from("Nothing it's ordered after held it up")),
+  }
+
+  if !analysis.waits.is_empty() {
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+      "After= dependencies, last ready first",
+      Style::default().add_modifier(Modifier::BOLD),
+    )));
+    let width = analysis.waits.iter().map(|w| w.unit.len()).max().unwrap_or(0);
+    for wait in &analysis.waits {
+      let mut spans = vec![Span::raw(format!("  {:width$}  ready {:>10}  ", wait.unit, at(Some(wait.ready))))];
+      spans.push(match wait.slack {
+        Some(slack) => Span::raw(format!("{} before", humanize::duration(Duration::from_micros(slack)))),
+        None => Span::styled("after it started", dim),
+      });
+      if Some(&wait.unit) == blocker.as_ref() {
+        spans.push(Span::styled("  ← held it up", Style::default().fg(Color::Yellow)));
+      }
+      lines.push(Line::from(spans));
+    }
+  }
+
+  if analysis.chain.len() > 1 {
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+      format!("What the boot waited on, back from {}", unit.name),
+      Style::default().add_modifier(Modifier::BOLD),
+    )));
+    for (depth, (name, timing)) in analysis.chain.iter().enumerate() {
+      let branch = if depth == 0 { String::new() } else { format!("{}└ ", "  ".repeat(depth - 1)) };
+      lines.push(Line::from(vec![
+        Span::raw(format!("  {branch}{name} ")),
+        Span::styled(format!("{}{}", at(timing.ready), took(timing)), dim),
+      ]));
     }
-    None
   }
+  lines
+}
 
-  fn render(&mut self, f: &mut Frame<'_>, rect: Rect) {
-    // Theme colors for adaptive light/dark support
-    let theme = self.theme;
+/// Every unit's time to activate this boot as a bar chart, like `systemd-analyze blame`
+fn boot_times_lines(order: boot_wait::Order) -> Vec<Line<'static>> {
+  const BAR_WIDTH: usize = 30;
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let (timings, _) = match boot_timings(UnitScope::Global) {
+    Ok(timings) => timings,
+    Err(e) => return vec![Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red)))],
+  };
+  let times = boot_wait::startup_times(&timings, order);
+  if times.is_empty() {
+    return vec![Line::from("Nothing is known about this boot's start times")];
+  }
 
-    fn span(s: &str, color: Color) -> Span<'_> {
-      Span::styled(s, Style::default().fg(color))
+  let slowest = times.iter().filter_map(|(_, t)| t.took()).max().unwrap_or(1).max(1);
+  let mut lines = vec![
+    Line::from(Span::styled(
+      match order {
+        boot_wait::Order::Slowest => "Slowest to activate first, with the time each took",
+        boot_wait::Order::Started => "In the order they started, at the time since the kernel started",
+      },
+      dim,
+    )),
+    Line::from(""),
+  ];
+  for (unit, timing) in times {
+    let took = timing.took().unwrap_or(0);
+    // quick ones still get a sliver, so each row has a bar
+    let bar = ((took as f64 / slowest as f64 * BAR_WIDTH as f64).round() as usize).clamp(1, BAR_WIDTH);
+    let color = if took >= 5_000_000 {
+      Color::Red
+    } else if took >= 1_000_000 {
+      Color::Yellow
+    } else {
+      Color::Green
+    };
+    let mut spans = vec![
+      Span::raw(format!("{:>9} ", humanize::duration(Duration::from_micros(took)))),
+      Span::styled(format!("{:BAR_WIDTH$}", "█".repeat(bar)), Style::default().fg(color)),
+      Span::raw(format!(" {unit}")),
+    ];
+    if order == boot_wait::Order::Started {
+      let at = timing.started.unwrap_or(0);
+      spans.push(Span::styled(format!(" @{}", humanize::duration(Duration::from_micros(at))), dim));
     }
+    lines.push(Line::from(spans));
+  }
+  lines
+}
 
-    fn colored_line(value: &str, color: Color) -> Line<'_> {
-      Line::from(vec![Span::styled(value, Style::default().fg(color))])
+/// The units the default target waited on, one behind the other, like `systemd-analyze critical-chain`
+fn critical_chain_lines() -> Vec<Line<'static>> {
+  let error = |e: anyhow::Error| vec![Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red)))];
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let target = match systemd::get_default_target(UnitScope::Global) {
+    Ok(target) => target,
+    Err(e) => return error(e),
+  };
+  let (timings, after) = match boot_timings(UnitScope::Global) {
+    Ok(timings) => timings,
+    Err(e) => return error(e),
+  };
+  let Some(analysis) = boot_wait::analyze(&target, &after, &timings) else {
+    return vec![Line::from(format!("{target} wasn't reached during this boot"))];
+  };
+
+  let at =
+    |usec: Option<u64>| usec.map_or("?".into(), |u| format!("@{}", humanize::duration(Duration::from_micros(u))));
+  let mut lines = vec![
+    Line::from(Span::styled(
+      "Each unit waited on the one below it; @ is when it was ready, + how long it took to activate",
+      dim,
+    )),
+    Line::from(""),
+  ];
+  for (depth, (name, timing)) in analysis.chain.iter().enumerate() {
+    let mut spans = vec![Span::raw(format!("{}{name} ", "  ".repeat(depth))), Span::raw(at(timing.ready))];
+    if let Some(took) = timing.took() {
+      let style = if took >= 1_000_000 { Style::default().fg(Color::Yellow) } else { dim };
+      spans.push(Span::styled(format!(" +{}", humanize::duration(Duration::from_micros(took))), style));
     }
+    lines.push(Line::from(spans));
+  }
+  lines
+}
 
-    let rect = if self.show_logger {
-      let chunks = Layout::new(Direction::Vertical, Constraint::from_percentages([50, 50])).split(rect);
+const MANAGER_TAG: &str = "[manager]";
 
-      self.logger.render(f, chunks[1]);
-      chunks[0]
+/// Manager messages worth showing next to a failed unit: anything about the unit itself, plus ordering, dependency and
+/// timeout trouble elsewhere that might explain it
+fn is_boot_context_message(line: &str, unit: &str) -> bool {
+  let lower = line.to_lowercase();
+  line.contains(unit)
+    || ["ordering cycle", "dependency", "timed out", "timeout", "failed"].iter().any(|k| lower.contains(k))
+}
+
+fn boot_context_lines(unit: &UnitId, logs: Vec<String>) -> Vec<Line<'static>> {
+  let mut lines = vec![
+    Line::from(Span::styled(
+      format!("{}'s log lines from this boot, with the service manager's messages from around then", unit.name),
+      Style::default().add_modifier(Modifier::DIM),
+    )),
+    Line::from(""),
+  ];
+  lines.extend(logs.into_iter().map(|line| {
+    let style = if !line.contains(MANAGER_TAG) {
+      Style::default()
+    } else if line.contains(&unit.name) {
+      Style::default().fg(Color::Cyan)
     } else {
-      rect
+      Style::default().fg(Color::Yellow)
     };
+    Line::from(Span::styled(line, style))
+  }));
+  lines
+}
 
-    let rects =
-      Layout::new(Direction::Vertical, [Constraint::Min(3), Constraint::Percentage(100), Constraint::Length(1)])
-        .split(rect);
-    let search_panel = rects[0];
-    let main_panel = rects[1];
-    let help_line_rect = rects[2];
+/// The unit's OOM kills this boot and its latest core dumps, from the kernel, systemd-oomd and systemd-coredump
+fn get_crash_evidence(unit: &UnitId) -> anyhow::Result<crash::Evidence> {
+  let journalctl = |args: &[&str], scope: Option<UnitScope>| -> anyhow::Result<Vec<u8>> {
+    let mut command = systemd::journalctl();
+    command.args(["--quiet", "--no-pager"]).args(args);
+    if let Some(scope) = scope {
+      systemd::in_scope(&mut command, scope);
+    }
+    let output = remote::on_host(&mut command).output()?;
+    if !output.status.success() {
+      anyhow::bail!(String::from_utf8_lossy(&output.stderr).to_string());
+    }
+    Ok(output.stdout)
+  };
+  let lines = |data: Vec<u8>| String::from_utf8_lossy(&data).lines().map(String::from).collect_vec();
+
+  // the kernel's and oomd's lines are in the system journal, whoever's unit it is
+  let kernel = lines(journalctl(&["--dmesg", "--output=short-iso"], None)?);
+  let oomd = lines(journalctl(&["--boot=0", "--output=short-iso", "_COMM=systemd-oomd"], None).unwrap_or_default());
+  let oom_kills = crash::oom_kills(&merge_log_lines(kernel, oomd), &unit.name);
+
+  let field = format!("{}={}", crash::unit_field(unit.scope), unit.name);
+  let lines = format!("--lines={}", crash::MAX_CORE_DUMPS);
+  let dumps = journalctl(&["--output=export", &lines, &field], Some(unit.scope))?;
+  let mut core_dumps =
+    journal::ExportParser::default().feed(&dumps).iter().filter_map(crash::CoreDump::from_fields).collect_vec();
+  core_dumps.reverse();
+  Ok(crash::Evidence { oom_kills, core_dumps })
+}
 
-    // Helper for colouring based on the same logic as sysz
-    // https://github.com/joehillen/sysz/blob/8da8e0dcbfde8d68fbdb22382671e395bd370d69/sysz#L69C1-L72C24
-    //    Some units are colored based on state:
-    //    green       active
-    //    red         failed
-    //    yellow      not-found
-    fn unit_color(unit: &UnitWithStatus) -> Color {
-      if unit.is_active() {
-        Color::Green
-      } else if unit.is_failed() {
-        Color::Red
-      } else if unit.is_not_found() {
-        Color::Yellow
-      } else {
-        Color::Reset
+/// `coredumpctl info` of a core dump, which has the stack trace and where the core is kept
+fn core_dump_info(dump: &crash::CoreDump) -> anyhow::Result<Vec<String>> {
+  let mut command = std::process::Command::new("coredumpctl");
+  command.args(["info", "--no-pager", &dump.pid]);
+  let output = remote::on_host(&mut command).output()?;
+  if !output.status.success() {
+    anyhow::bail!(String::from_utf8_lossy(&output.stderr).trim().to_string());
+  }
+  Ok(String::from_utf8_lossy(&output.stdout).lines().map(String::from).collect())
+}
+
+fn crash_lines(unit: &UnitId, evidence: &crash::Evidence) -> Vec<Line<'static>> {
+  let bold = Style::default().add_modifier(Modifier::BOLD);
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  if evidence.is_empty() {
+    return vec![Line::from(format!("The journal has no OOM kills or core dumps of {}", unit.name))];
+  }
+  let mut lines = vec![];
+  if !evidence.oom_kills.is_empty() {
+    lines.push(Line::from(Span::styled("OOM kills this boot", bold)));
+    lines.extend(evidence.oom_kills.iter().map(|l| Line::from(l.clone())));
+    lines.push(Line::from(""));
+  }
+  let Some(latest) = evidence.core_dumps.first() else { return lines };
+  lines.push(Line::from(Span::styled("Core dumps, newest first", bold)));
+  let now = Local::now().timestamp();
+  for dump in &evidence.core_dumps {
+    let at = humanize::relative(dump.at, now);
+    lines.push(Line::from(format!("  {at}  PID {}  {}  {}", dump.pid, dump.signal, dump.exe)));
+  }
+  lines.push(Line::from(""));
+  // coredumpctl only knows this machine's dumps, where the container's PID means some other process, if any
+  if let Some(name) = machine::machine() {
+    lines.push(Line::from(Span::styled("What systemd-coredump logged", bold)));
+    lines.push(Line::from(Span::styled(format!("coredumpctl here can't see {name}'s core dumps"), dim)));
+    lines.extend(latest.message.lines().map(|l| Line::from(l.to_string())));
+    return lines;
+  }
+  lines.push(Line::from(Span::styled(format!("coredumpctl info {}", latest.pid), bold)));
+  match core_dump_info(latest) {
+    Ok(info) => lines.extend(info.into_iter().map(Line::from)),
+    // without coredumpctl, systemd-coredump's message has the same stack trace
+    Err(e) => {
+      lines.push(Line::from(Span::styled(format!("{e:#}, so here's what systemd-coredump logged"), dim)));
+      lines.extend(latest.message.lines().map(|l| Line::from(l.to_string())));
+    },
+  }
+  lines
+}
+
+/// Merge two sets of journal lines by timestamp. Both inputs are assumed to be in order already
+fn merge_log_lines<T: AsRef<str>>(a: Vec<T>, b: Vec<T>) -> Vec<T> {
+  let timestamp = |l: &T| l.as_ref().split_once(' ').and_then(|(t, _)| parse_journalctl_datetime(t));
+  a.into_iter().merge_by(b, |x, y| timestamp(x) <= timestamp(y)).collect()
+}
+
+/// What `systemd-delta` has to say about one unit, as a colored diff
+fn unit_delta_lines(unit: &UnitId) -> Vec<Line<'static>> {
+  let entries = match systemd::get_delta(unit.scope) {
+    Ok(entries) => entries.into_iter().filter(|e| e.is_for_unit(&unit.name)).collect_vec(),
+    Err(e) => {
+      return vec![Line::from(Span::styled(
+        format!("Failed to run systemd-delta: {e}"),
+        Style::default().fg(Color::Red),
+      ))]
+    },
+  };
+
+  if entries.is_empty() {
+    return vec![Line::from(format!("No local changes: {} is the same as the vendor unit file", unit.name))];
+  }
+
+  let mut lines = vec![];
+  for entry in entries {
+    let header = format!("[{}] {} → {}", entry.kind.label(), entry.path, entry.target);
+    lines.push(Line::from(Span::styled(header, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))));
+    match entry.kind {
+      systemd::DeltaKind::Extended => {
+        // systemd-delta doesn't diff drop-ins, the whole file is an addition
+        lines.push(Line::from(Span::styled(
+          format!("+++ {}", entry.target),
+          Style::default().add_modifier(Modifier::BOLD),
+        )));
+        match std::fs::read_to_string(&entry.target) {
+          Ok(contents) => lines.extend(diff_lines(&contents.lines().map(|l| format!("+{l}")).collect_vec())),
+          Err(e) => lines
+            .push(Line::from(Span::styled(format!("Could not read drop-in: {e}"), Style::default().fg(Color::Red)))),
+        }
+      },
+      systemd::DeltaKind::Masked => {
+        lines.push(Line::from("The unit is masked (linked to /dev/null) and cannot be started"))
+      },
+      _ => lines.extend(diff_lines(&entry.diff)),
+    }
+    lines.push(Line::from(""));
+  }
+  lines
+}
+
+/// The last hour's changes, newest first, with those since `seen` in bold and flapping units called out
+fn changes_lines(log: &ChangeLog, seen: Option<DateTime<Local>>, now: DateTime<Local>) -> Vec<Line<'static>> {
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let changes = log.since(now - chrono::Duration::hours(1));
+  if changes.is_empty() {
+    return vec![Line::from(Span::styled("Nothing has changed in the last hour", dim))];
+  }
+  let width = changes.iter().map(|c| c.unit.name.chars().count()).max().unwrap_or(0);
+  changes
+    .into_iter()
+    .map(|change| {
+      let new = seen.is_none_or(|seen| change.at > seen);
+      let style = if new { Style::default().add_modifier(Modifier::BOLD) } else { Style::default() };
+      let to_style = match change.to.as_str() {
+        "failed" => Style::default().fg(Color::Red),
+        to if to.starts_with("active") => Style::default().fg(Color::Green),
+        _ => Style::default(),
+      };
+      let mut spans = vec![
+        Span::styled(format!("{} ", change.at.format("%H:%M:%S")), dim),
+        Span::styled(format!("{:width$}  ", change.unit.name), style),
+      ];
+      if change.aspect == Aspect::Enablement {
+        spans.push(Span::raw("unit file "));
+      }
+      spans.extend([
+        Span::raw(change.from.clone()),
+        Span::raw(" → "),
+        Span::styled(change.to.clone(), to_style.patch(style)),
+        Span::styled(format!("  {}", humanize::relative(change.at.timestamp(), now.timestamp())), dim),
+      ]);
+      if log.is_flapping(&change.unit, now) {
+        spans.push(Span::styled("  flapping", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)));
       }
+      Line::from(spans)
+    })
+    .collect()
+}
+
+/// Restarts, failures and flapping for each unit that changed state since startup
+fn session_stats_lines(session: &SessionStats, graph_style: config::GraphStyle) -> Vec<Line<'static>> {
+  let bold = Style::default().add_modifier(Modifier::BOLD);
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let now = Local::now();
+  let mut lines = vec![Line::from(Span::styled(
+    format!(
+      "Watching since {} ({} ago)",
+      session.started.format("%H:%M:%S"),
+      humanize::duration((now - session.started).to_std().unwrap_or_default())
+    ),
+    dim,
+  ))];
+
+  let failures = session.failures_over_time(chrono::Duration::minutes(10), now);
+  lines.push(Line::from(vec![
+    Span::raw("Failures per 10 minutes: "),
+    Span::styled(graph::sparkline(&failures, 40, graph_style), Style::default().fg(Color::Red)),
+    Span::styled(format!(" {} in total", failures.iter().sum::<u64>()), dim),
+  ]));
+  lines.push(Line::from(""));
+
+  let units = session.units(now);
+  if units.is_empty() {
+    lines.push(Line::from(Span::styled("No unit has changed state yet", dim)));
+    return lines;
+  }
+  lines.push(Line::from(Span::styled(
+    format!("{:<40} {:>7} {:>8} {:>8}  last change", "unit", "changes", "restarts", "failures"),
+    bold,
+  )));
+  for unit in units {
+    let mut spans = vec![Span::raw(format!(
+      "{:<40} {:>7} {:>8} {:>8}  {} {} → {}",
+      unit.unit.name,
+      unit.transitions,
+      unit.restarts,
+      unit.failures,
+      unit.last.at.format("%H:%M:%S"),
+      unit.last.from,
+      unit.last.to
+    ))];
+    if unit.flapping {
+      spans.push(Span::styled("  flapping", Style::default().fg(Color::Yellow)));
     }
+    lines.push(Line::from(spans));
+  }
+  lines.push(Line::from(""));
+  lines.push(Line::from(Span::styled(
+    format!(
+      "Units are polled every few seconds, so quick restarts can be missed. Flapping means {} or more changes in {} \
+       minutes",
+      session::FLAP_TRANSITIONS,
+      session::FLAP_WINDOW.num_minutes()
+    ),
+    dim,
+  )));
+  lines
+}
 
-    let items: Vec<ListItem> = self
-      .filtered_units
-      .items
-      .iter()
-      .map(|i| {
-        let color = unit_color(i);
-        let line = colored_line(i.short_name(), color);
-        ListItem::new(line)
-      })
-      .collect();
+/// Running units grouped by slice, with each slice's share of memory, CPU and tasks
+fn slice_tree_lines(scopes: &[UnitScope]) -> Vec<Line<'static>> {
+  let bold = Style::default().add_modifier(Modifier::BOLD);
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let mut lines = vec![];
+
+  fn usage_text(usage: slices::Usage) -> String {
+    format!(
+      "{:>6} mem  {:>8} cpu  {:>5} tasks",
+      humanize::size(usage.memory_bytes),
+      humanize::duration(Duration::from_nanos(usage.cpu_nsec)),
+      usage.tasks
+    )
+  }
 
-    // Create a List from all list items and highlight the currently selected one
-    let items = List::new(items)
-      .block(
-        Block::default()
-          .borders(Borders::ALL)
-          .border_type(BorderType::Rounded)
-          .border_style(if self.mode == Mode::ServiceList {
-            Style::default().fg(theme.accent)
-          } else {
-            Style::default()
-          })
-          .title("─Services"),
-      )
-      .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD));
+  fn push_node(lines: &mut Vec<Line<'static>>, node: &slices::SliceNode, depth: usize, bold: Style, dim: Style) {
+    let name = format!("{}{}", "  ".repeat(depth), node.name);
+    lines.push(Line::from(vec![Span::styled(format!("{name:<48}"), bold), Span::styled(usage_text(node.total), bold)]));
+    for child in &node.slices {
+      push_node(lines, child, depth + 1, bold, dim);
+    }
+    for (unit, usage) in &node.units {
+      let name = format!("{}{unit}", "  ".repeat(depth + 1));
+      lines.push(Line::from(vec![Span::raw(format!("{name:<48}")), Span::styled(usage_text(*usage), dim)]));
+    }
+  }
+
+  for scope in scopes {
+    let heading = match scope {
+      UnitScope::Global => "System manager",
+      UnitScope::User => "User manager",
+    };
+    lines.push(Line::from(Span::styled(heading, bold.add_modifier(Modifier::UNDERLINED))));
+    match systemd::get_all_unit_properties(*scope, slices::SLICE_PROPERTIES) {
+      Ok(units) => {
+        let root = slices::build(&units);
+        if root.total == slices::Usage::default() {
+          lines.push(Line::from(Span::styled("No running units report memory, CPU or task counts", dim)));
+        } else {
+          push_node(&mut lines, &root, 0, bold, dim);
+        }
+      },
+      Err(e) => lines.push(Line::from(Span::styled(e.to_string(), Style::default().fg(Color::Red)))),
+    }
+    lines.push(Line::from(""));
+  }
+  lines.push(Line::from(Span::styled(
+    "Totals are summed from the running units in each slice; cpu is time used since each unit started",
+    dim,
+  )));
+  lines
+}
 
-    let chunks =
-      Layout::new(Direction::Horizontal, [Constraint::Min(30), Constraint::Percentage(100)]).split(main_panel);
-    let right_panel = chunks[1];
+/// What running `equivalent`'s action would do, without doing it
+fn dry_run_lines(equivalent: &Equivalent) -> Vec<Line<'static>> {
+  let bold = Style::default().add_modifier(Modifier::BOLD);
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let mut lines = vec![Line::from(Span::styled("Would run", bold)), Line::from(format!("  {}", equivalent.shell()))];
+  if let Some(dbus) = &equivalent.dbus {
+    lines.push(Line::from(Span::styled("  which is the D-Bus call", dim)));
+    lines.push(Line::from(format!("  {dbus}")));
+  }
+  lines.push(Line::from(""));
+
+  if let Some(propagation) = equivalent.propagation {
+    let unit = &equivalent.unit;
+    match systemd::get_all_unit_properties(unit.scope, equivalent.propagates_through) {
+      Ok(units) => {
+        let units = units.into_iter().filter_map(|p| Some((p.get("Id")?.clone(), p))).collect();
+        let affected = equivalent::affected_units(&units, &unit.name, equivalent.propagates_through);
+        if affected.is_empty() {
+          lines.push(Line::from(Span::styled("No other loaded unit is affected", dim)));
+        } else {
+          lines.push(Line::from(Span::styled(propagation, bold)));
+          lines.extend(affected.into_iter().map(|u| Line::from(format!("  {u}"))));
+        }
+        lines.push(Line::from(Span::styled(
+          format!("(through {}, as far as it goes)", equivalent.propagates_through.join(", ")),
+          dim,
+        )));
+      },
+      Err(e) => lines.push(Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red)))),
+    }
+    lines.push(Line::from(""));
+  }
+  lines.push(Line::from(Span::styled("Nothing was changed. Press d in the unit list to turn dry run off", dim)));
+  lines
+}
 
-    f.render_stateful_widget(items, chunks[0], &mut self.filtered_units.state);
+/// An action's outcome failing to make it into the action log shouldn't stop the action, but it should be noticed
+/// What to type in the watch form, before anything's typed
+fn watch_files_hint() -> Vec<Line<'static>> {
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  vec![
+    Line::from(Span::styled("Space-separated paths or globs, e.g. ~/src/app/**/*.py ~/src/app/config.toml", dim)),
+    Line::from(Span::styled("Checked every second; a directory covers everything in it", dim)),
+  ]
+}
 
-    let selected_item = self.filtered_units.selected();
+pub fn record_action(entry: action_log::Entry) {
+  if let Err(e) = action_log::record(&entry) {
+    error!("Failed to record {} of {} in the action log: {:#}", entry.action, entry.unit, e);
+  }
+}
 
-    let right_panel =
-      Layout::new(Direction::Vertical, [Constraint::Min(7), Constraint::Percentage(100)]).split(right_panel);
-    let details_panel = right_panel[0];
-    let logs_panel = right_panel[1];
+fn record_environment_change<T>(scope: UnitScope, action: &str, result: &anyhow::Result<T>) {
+  let outcome = match result {
+    Ok(_) => "ok".to_string(),
+    Err(e) => format!("failed: {e}"),
+  };
+  record_action(action_log::Entry::new(scope, "", &format!("{action} in the manager environment"), &outcome));
+}
 
-    let details_block = Block::default().title("─Details").borders(Borders::ALL).border_type(BorderType::Rounded);
-    let details_panel_panes = Layout::new(Direction::Horizontal, [Constraint::Min(14), Constraint::Percentage(100)])
-      .split(details_block.inner(details_panel));
-    let props_pane = details_panel_panes[0];
-    let values_pane = details_panel_panes[1];
+/// The action log, newest first, narrowed to the actions on `unit` if there is one
+fn action_log_lines(unit: Option<&UnitId>) -> Vec<Line<'static>> {
+  /// Enough to cover a long incident without making the viewer slow to open
+  const MAX_ENTRIES: usize = 1000;
+
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let entries = match action_log::load() {
+    Ok(entries) => entries.into_iter().filter(|e| unit.is_none_or(|unit| e.is_for(unit))).collect_vec(),
+    Err(e) => return vec![Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red)))],
+  };
+  let mut lines = vec![];
+  if let Ok(path) = action_log::path() {
+    lines.push(Line::from(Span::styled(format!("Recorded in {} (tab-separated)", path.display()), dim)));
+    lines.push(Line::from(""));
+  }
+  if entries.is_empty() {
+    let nothing = match unit {
+      Some(unit) => format!("Nothing has been done to {} through systemctl-tui yet", unit.name),
+      None => "Nothing has been done through systemctl-tui yet".into(),
+    };
+    lines.push(Line::from(Span::styled(nothing, dim)));
+  }
+  for entry in entries.iter().rev().take(MAX_ENTRIES) {
+    let color = if entry.is_ok() { Color::Green } else { Color::Red };
+    let unit = if entry.unit.is_empty() { String::new() } else { format!("{} ", entry.unit) };
+    lines.push(Line::from(vec![
+      Span::styled(format!("{} ", entry.timestamp.format("%Y-%m-%d %H:%M:%S")), dim),
+      Span::styled(format!("{}@{} {} ", entry.user, entry.host, entry.scope), dim),
+      Span::raw(format!("{}{} ", entry.action, unit)),
+      Span::styled(entry.result.clone(), Style::default().fg(color)),
+    ]));
+  }
+  lines
+}
 
-    let props_lines = vec![
-      Line::from("Description: "),
-      Line::from("Scope: "),
-      Line::from("Loaded: "),
-      Line::from("Active: "),
-      Line::from("Unit file: "),
+/// A unit's processes as a tree with their PID, CPU use since `earlier` and memory
+fn process_lines(processes: &[Process], earlier: Option<&process_tree::Sample>) -> Vec<Line<'static>> {
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let bold = Style::default().add_modifier(Modifier::BOLD);
+  if processes.is_empty() {
+    return vec![Line::from(Span::styled("No processes", dim))];
+  }
+  let now = Instant::now();
+  let tree = process_tree::tree(processes);
+  let branches = process_tree::branches(&tree);
+  let total_rss = processes.iter().map(|p| p.rss_bytes).sum();
+  let mut lines = vec![
+    Line::from(Span::styled(format!("{} processes, {} resident", processes.len(), humanize::size(total_rss)), dim)),
+    Line::from(""),
+    Line::from(Span::styled(format!("{:>8} {:>7} {:>10}  COMMAND", "PID", "CPU", "RSS"), bold)),
+  ];
+  for ((_, process), branch) in tree.iter().zip(branches) {
+    let cpu = earlier.and_then(|s| s.cpu_percent(process, now)).map_or("-".into(), humanize::percent);
+    let mut spans = vec![
+      Span::raw(format!("{:>8} {cpu:>7} {:>10}  {branch}", process.pid, humanize::size(process.rss_bytes))),
+      Span::styled(process.comm.clone(), bold),
     ];
+    if !process.cmdline.is_empty() {
+      spans.push(Span::styled(format!(" {}", process.cmdline), dim));
+    }
+    lines.push(Line::from(spans));
+  }
+  lines
+}
 
-    let details_text = if let Some(i) = selected_item {
-      fn line_color_string<'a>(value: String, color: Color) -> Line<'a> {
-        Line::from(vec![Span::styled(value, Style::default().fg(color))])
-      }
-
-      let load_color = match i.load_state.as_str() {
-        "loaded" => Color::Green,
-        "not-found" => Color::Yellow,
-        "error" => Color::Red,
-        _ => Color::Reset,
-      };
+fn self_check_lines(checks: &[self_check::Check], first_run: bool) -> Vec<Line<'static>> {
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let mut lines = vec![];
+  if first_run {
+    lines
+      .push(Line::from(Span::styled("Shown on the first launch only; the help (?) lists the key to check again", dim)));
+    lines.push(Line::from(""));
+  }
+  for check in checks {
+    let color = match check.status {
+      self_check::Status::Works => Color::Green,
+      self_check::Status::Limited => Color::Yellow,
+      self_check::Status::Missing => Color::Red,
+    };
+    lines.push(Line::from(vec![
+      Span::styled(format!("{} ", check.status.symbol()), Style::default().fg(color)),
+      Span::styled(format!("{}: ", check.name), Style::default().add_modifier(Modifier::BOLD)),
+      Span::raw(check.detail.clone()),
+    ]));
+    if let Some(hint) = &check.hint {
+      lines.push(Line::from(Span::styled(format!("  {hint}"), dim)));
+    }
+  }
+  lines
+}
 
-      let active_color = match i.activation_state.as_str() {
-        "active" => Color::Green,
-        "inactive" => Color::Reset,
-        "failed" => Color::Red,
-        _ => Color::Reset,
-      };
+fn plugin_lines(plugin: &crate::config::Plugin, unit: &UnitId) -> Vec<Line<'static>> {
+  let lines = match plugin::run(plugin, unit) {
+    Ok(lines) => lines,
+    Err(e) => return vec![Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red)))],
+  };
+  if lines.is_empty() {
+    return vec![Line::from(Span::styled("No output", Style::default().add_modifier(Modifier::DIM)))];
+  }
+  lines
+    .into_iter()
+    .map(|line| match line {
+      OutputLine::Heading(text) => Line::from(Span::styled(text, Style::default().add_modifier(Modifier::BOLD))),
+      OutputLine::Status(status, text) => {
+        let (marker, color) = match status {
+          plugin::Status::Ok => ("✓", Color::Green),
+          plugin::Status::Warn => ("!", Color::Yellow),
+          plugin::Status::Error => ("✗", Color::Red),
+        };
+        Line::from(vec![Span::styled(format!("{marker} "), Style::default().fg(color)), Span::raw(text)])
+      },
+      OutputLine::Text(text) => Line::from(text),
+    })
+    .collect()
+}
 
-      let active_state_value = format!("{} ({})", i.activation_state, i.sub_state);
+/// Compare settings with the unit's properties in effect
+fn settings_diff(unit: &UnitId, settings: &[(String, String)]) -> anyhow::Result<Vec<property_diff::Change>> {
+  let properties = property_diff::properties(settings);
+  let live = if properties.is_empty() { HashMap::new() } else { systemd::get_unit_properties(unit, &properties)? };
+  Ok(property_diff::diff(settings, &live, property_diff::ram_bytes()))
+}
 
-      let scope = match i.scope {
-        UnitScope::Global => "Global",
-        UnitScope::User => "User",
-      };
+/// What a saved unit file or drop-in will change once the manager reloads it. A whole unit file repeats every setting,
+/// so only the compared settings that differ are listed; a drop-in lists everything in it
+/// Each variable with where it's set, and how the running process differs from what a restart would give it
+fn environment_lines(effective: &unit_environment::Effective) -> Vec<Line<'static>> {
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let mut lines =
+    effective.errors.iter().map(|e| Line::from(Span::styled(e.clone(), Style::default().fg(Color::Red)))).collect_vec();
+  for variable in &effective.variables {
+    let mut spans = vec![
+      Span::styled(variable.name.clone(), Style::default().fg(Color::Cyan)),
+      Span::styled("=", dim),
+      Span::raw(variable.value.clone()),
+      Span::styled(format!("  ← {}", variable.source.label()), dim),
+    ];
+    if !variable.overrides.is_empty() {
+      let overridden = variable.overrides.iter().map(|s| s.label()).join(", ");
+      spans.push(Span::styled(format!(", over {overridden}"), dim));
+    }
+    lines.push(Line::from(spans));
+    let running = effective.process.as_ref().map(|process| process.get(&variable.name));
+    match running {
+      Some(Some(value)) if *value != variable.value => lines.push(Line::from(Span::styled(
+        format!("  the running process has {value}, until it's restarted"),
+        Style::default().fg(Color::Yellow),
+      ))),
+      Some(None) => lines.push(Line::from(Span::styled(
+        "  the running process doesn't have it, until it's restarted",
+        Style::default().fg(Color::Yellow),
+      ))),
+      _ => {},
+    }
+  }
+  if effective.variables.is_empty() {
+    lines.push(Line::from(Span::styled("No variables set", dim)));
+  }
 
-      let lines = vec![
-        colored_line(&i.description, Color::Reset),
-        colored_line(scope, Color::Reset),
-        colored_line(&i.load_state, load_color),
-        line_color_string(active_state_value, active_color),
-        match &i.file_path {
-          Some(Ok(file_path)) => Line::from(file_path.as_str()),
-          Some(Err(e)) => colored_line(e, Color::Red),
-          None => Line::from(""),
-        },
-      ];
+  if let Some(process) = &effective.process {
+    let extra = process
+      .iter()
+      .filter(|(name, _)| !effective.variables.iter().any(|v| &v.name == *name))
+      .sorted()
+      .map(|(name, value)| Line::from(format!("  {name}={value}")))
+      .collect_vec();
+    if !extra.is_empty() {
+      lines.push(Line::from(""));
+      lines.push(Line::from(Span::styled(
+        "Also in the running process, set by systemd itself (like INVOCATION_ID) or User=, PAM or the service",
+        Style::default().add_modifier(Modifier::BOLD),
+      )));
+      lines.extend(extra);
+    }
+  } else {
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+      "The running process's own environment can't be read (it isn't running, or it takes root)",
+      dim,
+    )));
+  }
+  lines
+}
 
-      lines
+/// What `systemd-analyze verify` said, with the line of the unit file each complaint is about
+fn verification_lines(verification: &systemd::Verification) -> Vec<Line<'static>> {
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let (color, summary) = match (verification.passed, verification.diagnostics.is_empty()) {
+    (true, true) => (Color::Green, "systemd-analyze verify found no problems"),
+    (true, false) => (Color::Yellow, "systemd-analyze verify has warnings"),
+    (false, _) => (Color::Red, "systemd-analyze verify failed: the unit won't load like this"),
+  };
+  let mut lines = vec![Line::from(Span::styled(summary, Style::default().fg(color).add_modifier(Modifier::BOLD)))];
+  for diagnostic in &verification.diagnostics {
+    let location = match (&diagnostic.path, diagnostic.line) {
+      (Some(path), Some(line)) => format!("{path}:{line}"),
+      _ => String::new(),
+    };
+    if location.is_empty() {
+      lines.push(Line::from(format!("  {}", diagnostic.message)));
     } else {
-      vec![]
+      lines.push(Line::from(vec![Span::styled(format!("  {location}: "), dim), Span::raw(diagnostic.message.clone())]));
+    }
+    if let Some(source) = &diagnostic.source {
+      lines.push(Line::from(vec![
+        Span::raw("    "),
+        Span::styled(source.clone(), Style::default().fg(Color::Black).bg(color)),
+      ]));
+    }
+  }
+  lines
+}
+
+fn saved_settings_lines(unit: &UnitId, path: &str) -> Vec<Line<'static>> {
+  let error = |e: String| vec![Line::from(Span::styled(e, Style::default().fg(Color::Red)))];
+  let contents = match std::fs::read_to_string(path) {
+    Ok(contents) => contents,
+    Err(e) => return error(format!("Could not read {path}: {e}")),
+  };
+  let mut settings = property_diff::parse_unit_file(&contents);
+  let drop_in = Path::new(path).parent().is_some_and(|d| d.extension().is_some_and(|e| e == "d"));
+  if !drop_in {
+    settings.retain(|(name, _)| !property_diff::properties(&[(name.clone(), String::new())]).is_empty());
+  }
+  match settings_diff(unit, &settings) {
+    Ok(changes) => property_diff_lines(&changes, !drop_in),
+    Err(e) => error(format!("{e:#}")),
+  }
+}
+
+/// Each setting as `before → after`, with what limits work out to
+fn property_diff_lines(changes: &[property_diff::Change], only_changed: bool) -> Vec<Line<'static>> {
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let width = changes.iter().map(|c| c.setting.len()).max().unwrap_or(0);
+  let mut lines = vec![];
+  for change in changes.iter().filter(|c| c.changed || !only_changed) {
+    let name = Span::raw(format!("{:width$}  ", change.setting));
+    lines.push(match (&change.before, change.changed) {
+      (Some(before), true) => Line::from(vec![
+        name,
+        Span::styled(before.clone(), Style::default().fg(Color::Red)),
+        Span::raw(" → "),
+        Span::styled(change.after.clone(), Style::default().fg(Color::Green)),
+      ]),
+      (Some(before), false) => Line::from(vec![name, Span::styled(format!("{before} (unchanged)"), dim)]),
+      (None, _) => Line::from(vec![name, Span::raw(change.after.clone()), Span::styled(" (not compared)", dim)]),
+    });
+  }
+  if lines.is_empty() {
+    lines.push(Line::from(Span::styled("No resource or timing settings change", dim)));
+  }
+  lines
+}
+
+/// Write the dependency graph of `unit` to `path`, and show the DOT that was written
+fn export_graph_lines(unit: &UnitId, direction: depgraph::Direction, depth: usize, path: &Path) -> Vec<Line<'static>> {
+  let error = |e: anyhow::Error| vec![Line::from(Span::styled(format!("{e:#}"), Style::default().fg(Color::Red)))];
+  let units = match systemd::get_all_unit_properties(unit.scope, &direction.properties()) {
+    Ok(units) => units.into_iter().filter_map(|p| Some((p.get("Id")?.clone(), p))).collect(),
+    Err(e) => return error(e),
+  };
+  let edges = depgraph::collect(&units, &unit.name, direction, depth);
+  let dot = depgraph::to_dot(&unit.name, &edges);
+  if let Err(e) = depgraph::write(&dot, path) {
+    return error(e);
+  }
+  info!("Wrote the {} of {} to {}", direction.label(), unit.name, path.display());
+
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let mut lines =
+    vec![Line::from(Span::styled(format!("Wrote {} edges to {}", edges.len(), path.display()), dim)), Line::from("")];
+  lines.extend(dot.lines().map(|l| Line::from(l.to_string())));
+  lines
+}
+
+/// Recent boots, newest first: how long startup took and what failed, with failures the boot before didn't have and
+/// noticeably slower startups called out
+fn boot_failures_lines() -> Vec<Line<'static>> {
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let error = |e: String| Line::from(Span::styled(e, Style::default().fg(Color::Red)));
+  let boots = match systemd::get_boots() {
+    Ok(boots) if boots.is_empty() => return vec![Line::from("The journal has no boots recorded")],
+    Ok(boots) => boots,
+    Err(e) => return vec![error(e.to_string())],
+  };
+  let recent = &boots[boots.len().saturating_sub(boot_history::RECENT_BOOTS)..];
+  let summaries = recent.iter().map(|b| systemd::get_boot_summary(&b.id)).collect_vec();
+
+  let seconds = |usec: u64| humanize::duration(Duration::from_micros(usec));
+  let mut lines = vec![];
+  for (i, (boot, summary)) in recent.iter().zip(&summaries).enumerate().rev() {
+    let summary = match summary {
+      Ok(summary) => summary,
+      Err(e) => {
+        lines.push(Line::from(format!("Boot {} ({})", boot.index, boot.started)));
+        lines.push(error(format!("    {e}")));
+        continue;
+      },
     };
+    let previous = i.checked_sub(1).and_then(|p| summaries[p].as_ref().ok());
+
+    let mut heading =
+      vec![Span::styled(format!("Boot {:>3}", boot.index), Style::default().add_modifier(Modifier::BOLD))];
+    heading.push(Span::styled(format!("  {}  ", boot.started), dim));
+    match (summary.startup_usec, previous.and_then(|p| p.startup_usec)) {
+      (None, _) => heading.push(Span::styled("never finished starting", Style::default().fg(Color::Red))),
+      (Some(usec), Some(before)) if usec > before + before / 5 => {
+        heading.push(Span::raw(format!("startup {} ", seconds(usec))));
+        heading.push(Span::styled(format!("(+{} slower)", seconds(usec - before)), Style::default().fg(Color::Yellow)));
+      },
+      (Some(usec), _) => heading.push(Span::raw(format!("startup {}", seconds(usec)))),
+    }
+    lines.push(Line::from(heading));
 
-    let paragraph = Paragraph::new(details_text).style(Style::default());
+    if summary.failures.is_empty() {
+      lines.push(Line::from(Span::styled("    no failures", dim)));
+    }
+    for (unit, result) in &summary.failures {
+      let mut line =
+        vec![Span::styled("    ✗ ", Style::default().fg(Color::Red)), Span::raw(format!("{unit:<40} {result:<12}"))];
+      if previous.is_some_and(|p| !p.failed(unit)) {
+        line.push(Span::styled("new", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)));
+      }
+      lines.push(Line::from(line));
+    }
+    lines.push(Line::from(""));
+  }
+  lines.push(Line::from(Span::styled(
+    "Only failures before startup finished are listed; \"new\" means the boot before didn't have it",
+    dim,
+  )));
+  lines
+}
 
-    let props_widget = Paragraph::new(props_lines).alignment(ratatui::layout::Alignment::Right);
-    f.render_widget(props_widget, props_pane);
+/// What the default target will pull in at the next boot, compared with what's running now
+fn boot_plan_lines(scopes: &[UnitScope]) -> Vec<Line<'static>> {
+  let bold = Style::default().add_modifier(Modifier::BOLD);
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let mut lines = vec![];
+
+  for scope in scopes {
+    let mut properties = vec!["ActiveState"];
+    properties.extend(boot::PULL_PROPERTIES);
+    let result = systemd::get_default_target(*scope)
+      .and_then(|target| Ok((systemd::get_all_unit_properties(*scope, &properties)?, target)));
+    let (units, target) = match result {
+      Ok(result) => result,
+      Err(e) => {
+        lines.push(Line::from(Span::styled(e.to_string(), Style::default().fg(Color::Red))));
+        lines.push(Line::from(""));
+        continue;
+      },
+    };
+    let plan = boot::plan(&units, &target);
+    let services = plan.will_start.iter().filter(|u| u.ends_with(".service")).collect::<Vec<_>>();
 
-    f.render_widget(paragraph, values_pane);
-    f.render_widget(details_block, details_panel);
+    let heading = match scope {
+      UnitScope::Global => "System manager",
+      UnitScope::User => "User manager",
+    };
+    lines.push(Line::from(Span::styled(heading, bold.add_modifier(Modifier::UNDERLINED))));
+    lines.push(Line::from(format!(
+      "{target} pulls in {} units, {} of them services",
+      plan.will_start.len(),
+      services.len()
+    )));
+    lines.push(Line::from(""));
+
+    lines.push(Line::from(Span::styled("Will start at boot, but not running now", bold)));
+    if plan.not_running.is_empty() {
+      lines.push(Line::from(Span::styled("  None", dim)));
+    }
+    let state_of = |name: &str| {
+      units
+        .iter()
+        .find(|u| u.get("Id").is_some_and(|id| id == name))
+        .and_then(|u| u.get("ActiveState").cloned())
+        .unwrap_or_else(|| "not loaded".into())
+    };
+    for name in &plan.not_running {
+      lines.push(Line::from(vec![
+        Span::styled(format!("  {name}"), Style::default().fg(Color::Yellow)),
+        Span::styled(format!("  {}", state_of(name)), dim),
+      ]));
+    }
+    lines.push(Line::from(""));
 
-    let log_lines = self
-      .logs
-      .iter()
-      .rev()
-      .map(|l| {
-        if let Some((timestamp, rest)) = l.split_once(' ') {
-          if let Some(formatted_date) = parse_journalctl_timestamp(timestamp) {
-            return Line::from(vec![
-              Span::styled(formatted_date, Style::default().add_modifier(Modifier::DIM)),
-              Span::raw(" "),
-              Span::raw(rest),
-            ]);
-          }
-        }
+    lines.push(Line::from(Span::styled("Running now, but nothing starts it at boot", bold)));
+    lines
+      .push(Line::from(Span::styled("  (started by hand, or activated by a socket, timer, D-Bus or a device)", dim)));
+    if plan.not_at_boot.is_empty() {
+      lines.push(Line::from(Span::styled("  None", dim)));
+    }
+    for name in &plan.not_at_boot {
+      lines.push(Line::from(Span::styled(format!("  {name}"), Style::default().fg(Color::Cyan))));
+    }
+    lines.push(Line::from(""));
 
-        Line::from(l.as_str())
-      })
-      .collect_vec();
+    lines.push(Line::from(Span::styled("All services started at boot", bold)));
+    lines.extend(services.iter().map(|name| Line::from(format!("  {name}"))));
+    lines.push(Line::from(""));
+  }
+  lines
+}
 
-    let paragraph = Paragraph::new(log_lines)
-      .block(Block::default().title("─Service Logs").borders(Borders::ALL).border_type(BorderType::Rounded))
-      .style(Style::default())
-      .wrap(Wrap { trim: true })
-      .scroll((self.logs_scroll_offset, 0));
-    f.render_widget(paragraph, logs_panel);
+/// Ordering cycles in the current configuration, and the ones systemd had to break during this boot
+fn ordering_cycle_lines(scopes: &[UnitScope]) -> Vec<Line<'static>> {
+  let bold = Style::default().add_modifier(Modifier::BOLD);
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let mut lines = vec![];
 
-    let width = search_panel.width.max(3) - 3; // keep 2 for borders and 1 for cursor
-    let scroll = self.input.visual_scroll(width as usize);
-    let input = Paragraph::new(self.input.value())
-      .style(match self.mode {
-        Mode::Search => Style::default().fg(theme.accent),
-        _ => Style::default(),
-      })
-      .scroll((0, scroll as u16))
-      .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded).title(Line::from(vec![
-        Span::raw("─Search "),
-        Span::styled("(", Style::default().fg(theme.muted_alt)),
-        Span::styled("ctrl+f", Style::default().add_modifier(Modifier::BOLD).fg(theme.kbd)),
-        Span::styled(" or ", Style::default().fg(theme.muted_alt)),
-        Span::styled("/", Style::default().add_modifier(Modifier::BOLD).fg(theme.kbd)),
-        Span::styled(")", Style::default().fg(theme.muted_alt)),
-      ])));
-    f.render_widget(input, search_panel);
-    // clear top right of search panel so we can put help instructions there
-    let help_width = 24;
-    let help_area = Rect::new(search_panel.x + search_panel.width - help_width - 2, search_panel.y, help_width, 1);
-    f.render_widget(Clear, help_area);
-    let help_text = Paragraph::new(Line::from(vec![
-      Span::raw(" Press "),
-      Span::styled("?", Style::default().add_modifier(Modifier::BOLD).fg(theme.kbd)),
-      Span::raw(" or "),
-      Span::styled("F1", Style::default().add_modifier(Modifier::BOLD).fg(theme.kbd)),
-      Span::raw(" for help "),
-    ]))
-    .style(Style::default().fg(theme.muted_alt));
-    f.render_widget(help_text, help_area);
+  for scope in scopes {
+    let heading = match scope {
+      UnitScope::Global => "System units",
+      UnitScope::User => "User units",
+    };
+    lines.push(Line::from(Span::styled(heading, bold.add_modifier(Modifier::UNDERLINED))));
+    let cycles = match systemd::get_all_unit_properties(*scope, &["After", "Before"]) {
+      Ok(units) => ordering::OrderingGraph::from_properties(&units).cycles(),
+      Err(e) => {
+        lines.push(Line::from(Span::styled(e.to_string(), Style::default().fg(Color::Red))));
+        lines.push(Line::from(""));
+        continue;
+      },
+    };
+    if cycles.is_empty() {
+      lines.push(Line::from(Span::styled("  No ordering cycles", Style::default().fg(Color::Green))));
+    }
+    for (i, cycle) in cycles.iter().enumerate() {
+      lines.push(Line::from(Span::styled(format!("  Cycle {} ({} units)", i + 1, cycle.len()), bold)));
+      for edge in cycle {
+        lines.push(Line::from(format!("    {}", edge.from)));
+        lines.push(Line::from(vec![
+          Span::styled("      ↓ starts after ", dim),
+          Span::raw(edge.to.clone()),
+          Span::styled(format!("  ({})", edge.origin), dim),
+        ]));
+      }
+      if let Some(first) = cycle.first() {
+        lines.push(Line::from(format!("    {}", first.from)));
+      }
+    }
+    lines.push(Line::from(""));
+  }
+
+  if scopes.contains(&UnitScope::Global) {
+    lines.push(Line::from(Span::styled("Cycles broken during this boot", bold.add_modifier(Modifier::UNDERLINED))));
+    match systemd::get_manager_messages() {
+      Ok(messages) => {
+        let broken = ordering::parse_cycle_messages(messages.iter().map(String::as_str));
+        if broken.is_empty() {
+          lines.push(Line::from(Span::styled("  None", Style::default().fg(Color::Green))));
+        }
+        for cycle in broken {
+          lines.push(Line::from(format!("  {}", cycle.jobs.join(" → "))));
+          lines.push(match cycle.dropped {
+            Some(job) => Line::from(vec![
+              Span::raw("    systemd dropped "),
+              Span::styled(job, Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+            ]),
+            None => Line::from(Span::styled("    systemd could not break this cycle", Style::default().fg(Color::Red))),
+          });
+        }
+      },
+      Err(e) => lines.push(Line::from(Span::styled(e.to_string(), Style::default().fg(Color::Red)))),
+    }
+  }
+  lines
+}
+
+/// Every preset rule, in evaluation order, with the units each one decides. The selected unit's rule goes on top
+fn preset_lines(units: &[UnitId], selected: Option<&UnitId>) -> Vec<Line<'static>> {
+  let bold = Style::default().add_modifier(Modifier::BOLD);
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let mut lines = vec![];
+
+  let mut scopes: Vec<UnitScope> = units.iter().map(|u| u.scope).collect();
+  scopes.sort_by_key(|s| *s == UnitScope::User);
+  scopes.dedup();
+  let rules: Vec<(UnitScope, Vec<preset::PresetRule>)> = scopes.iter().map(|s| (*s, preset::load_rules(*s))).collect();
+  let rules_for = |scope: UnitScope| rules.iter().find(|(s, _)| *s == scope).map(|(_, r)| r.as_slice()).unwrap_or(&[]);
+
+  if let Some(selected) = selected {
+    let decision = match preset::decide(rules_for(selected.scope), &selected.name) {
+      Some(rule) => {
+        format!(
+          "{}: {}, by {}:{} `{}`",
+          selected.name,
+          rule.action.label(),
+          rule.file.display(),
+          rule.line,
+          rule.text()
+        )
+      },
+      None => format!("{}: enable, since no rule matches", selected.name),
+    };
+    lines.push(Line::from(Span::styled(decision, bold)));
+    lines.push(Line::from(""));
+  }
+
+  for (scope, rules) in &rules {
+    lines.push(Line::from(Span::styled(
+      match scope {
+        UnitScope::Global => "System presets",
+        UnitScope::User => "User presets",
+      },
+      bold.add_modifier(Modifier::UNDERLINED),
+    )));
+    if rules.is_empty() {
+      lines.push(Line::from(Span::styled("  No preset files", dim)));
+    }
 
-    if self.mode == Mode::Search {
-      f.set_cursor_position((
-        (search_panel.x + 1 + self.input.cursor() as u16).min(search_panel.x + search_panel.width - 2),
-        search_panel.y + 1,
-      ));
+    // which loaded units each rule decides
+    let mut decided: Vec<Vec<&str>> = vec![vec![]; rules.len()];
+    let mut undecided = vec![];
+    for unit in units.iter().filter(|u| u.scope == *scope) {
+      match rules.iter().position(|r| r.matches(&unit.name)) {
+        Some(i) => decided[i].push(unit.name.as_str()),
+        None => undecided.push(unit.name.as_str()),
+      }
     }
 
-    if self.mode == Mode::Help {
-      let popup = centered_rect_abs(50, 18, f.area());
-
-      let primary = |s| Span::styled(s, Style::default().fg(theme.primary));
-      let help_lines = vec![
-        Line::from(""),
-        Line::from(Span::styled("Shortcuts", Style::default().add_modifier(Modifier::UNDERLINED))),
-        Line::from(""),
-        Line::from(vec![primary("ctrl+C"), Span::raw(" or "), primary("ctrl+Q"), Span::raw(" to quit")]),
-        Line::from(vec![primary("ctrl+L"), Span::raw(" toggles the logger pane")]),
-        Line::from(vec![primary("PageUp"), Span::raw(" / "), primary("PageDown"), Span::raw(" scroll the logs")]),
-        Line::from(vec![primary("Home"), Span::raw(" / "), primary("End"), Span::raw(" scroll to top/bottom")]),
-        Line::from(vec![primary("Enter"), Span::raw(" or "), primary("Space"), Span::raw(" open the action menu")]),
-        Line::from(vec![primary("?"), Span::raw(" / "), primary("F1"), Span::raw(" open this help pane")]),
-        Line::from(""),
-        Line::from(Span::styled("Vim Style Shortcuts", Style::default().add_modifier(Modifier::UNDERLINED))),
-        Line::from(""),
-        Line::from(vec![primary("j"), Span::raw(" navigate down")]),
-        Line::from(vec![primary("k"), Span::raw(" navigate up")]),
-        Line::from(vec![primary("ctrl+U"), Span::raw(" / "), primary("ctrl+D"), Span::raw(" scroll the logs")]),
-      ];
+    let mut file = None;
+    for (rule, units) in rules.iter().zip(decided) {
+      if file != Some(&rule.file) {
+        file = Some(&rule.file);
+        lines.push(Line::from(Span::styled(format!("  {}", rule.file.display()), bold)));
+      }
+      let color = match rule.action {
+        preset::PresetAction::Enable => Color::Green,
+        preset::PresetAction::Disable => Color::Red,
+        preset::PresetAction::Ignore => Color::Yellow,
+      };
+      let mut spans =
+        vec![Span::styled(format!("  {:>4}  ", rule.line), dim), Span::styled(rule.text(), Style::default().fg(color))];
+      if !units.is_empty() {
+        spans.push(Span::styled(format!("  {}", summarize_names(&units)), dim));
+      }
+      lines.push(Line::from(spans));
+    }
+    if !undecided.is_empty() {
+      lines.push(Line::from(vec![
+        Span::raw("  No matching rule, so enabled: "),
+        Span::styled(summarize_names(&undecided), dim),
+      ]));
+    }
+    lines.push(Line::from(""));
+  }
+  lines
+}
 
-      let name = env!("CARGO_PKG_NAME");
-      let version = env!("CARGO_PKG_VERSION");
-      let title = format!("─Help for {name} v{version}");
+/// `a, b, c and 12 more`
+fn summarize_names(names: &[&str]) -> String {
+  const SHOWN: usize = 4;
+  let mut summary = names.iter().take(SHOWN).copied().collect::<Vec<_>>().join(", ");
+  if names.len() > SHOWN {
+    summary.push_str(&format!(" and {} more", names.len() - SHOWN));
+  }
+  summary
+}
 
-      let paragraph = Paragraph::new(help_lines)
-        .block(Block::default().title(title).borders(Borders::ALL).border_type(BorderType::Rounded))
-        .style(Style::default())
-        .wrap(Wrap { trim: true });
+fn manager_environment_lines(scope: UnitScope) -> Vec<Line<'static>> {
+  let environment = match systemd::get_manager_environment(scope) {
+    Ok(environment) => environment,
+    Err(e) => return vec![Line::from(Span::styled(e.to_string(), Style::default().fg(Color::Red)))],
+  };
+
+  let mut lines = vec![];
+  let missing = systemd::missing_session_variables(&environment, |name| std::env::var(name).ok());
+  if !missing.is_empty() {
+    let names: Vec<&str> = missing.iter().filter_map(|a| a.split_once('=')).map(|(name, _)| name).collect();
+    lines.push(Line::from(Span::styled(
+      format!("Set differently in this session: {} (import from session to copy them)", names.join(", ")),
+      Style::default().fg(Color::Yellow),
+    )));
+    lines.push(Line::from(""));
+  }
+  lines.extend(environment.into_iter().map(|assignment| {
+    let is_session = assignment.split_once('=').is_some_and(|(name, _)| systemd::SESSION_VARIABLES.contains(&name));
+    let style = if is_session { Style::default().fg(Color::Cyan) } else { Style::default() };
+    Line::from(Span::styled(assignment, style))
+  }));
+  lines
+}
 
-      f.render_widget(Clear, popup);
-      f.render_widget(paragraph, popup);
+/// The preview of restoring a running set: what gets started and stopped
+fn restore_lines(plan: &running_set::Plan) -> Vec<Line<'static>> {
+  const SHOWN: usize = 15;
+  if plan.is_empty() {
+    return vec![Line::from("The same units are running now. Nothing to do")];
+  }
+  let mut lines = vec![];
+  for (units, verb, color) in [(&plan.stop, "stop ", Color::Red), (&plan.start, "start", Color::Green)] {
+    lines.extend(
+      units
+        .iter()
+        .take(SHOWN)
+        .map(|u| Line::from(vec![Span::styled(verb, Style::default().fg(color)), Span::raw(format!(" {}", u.name))])),
+    );
+    if units.len() > SHOWN {
+      lines.push(Line::from(Span::styled(
+        format!("...and {} more to {}", units.len() - SHOWN, verb.trim()),
+        Style::default().add_modifier(Modifier::DIM),
+      )));
     }
+  }
+  lines
+}
 
-    if self.mode == Mode::Error {
-      let popup = centered_rect_abs(50, 12, f.area());
-      let error_lines = self.error_message.split('\n').map(Line::from).collect_vec();
-      let paragraph = Paragraph::new(error_lines)
-        .block(
-          Block::default()
-            .title("─Error")
-            .borders(Borders::ALL)
-            .border_type(BorderType::Rounded)
-            .border_style(Style::default().fg(Color::Red)),
-        )
-        .wrap(Wrap { trim: true });
+fn batch_action(verb: BatchVerb, unit: &UnitId) -> Action {
+  match verb {
+    BatchVerb::Start => Action::StartService(unit.clone()),
+    BatchVerb::Stop => Action::StopService(unit.clone()),
+    BatchVerb::Restart => Action::RestartService(unit.clone()),
+  }
+}
 
-      f.render_widget(Clear, popup);
-      f.render_widget(paragraph, popup);
+/// The units a reset-failed would clear
+fn failed_lines(failed: &[&UnitWithStatus]) -> Vec<Line<'static>> {
+  const SHOWN: usize = 15;
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let mut lines = failed
+    .iter()
+    .take(SHOWN)
+    .map(|unit| Line::from(vec![Span::raw(format!("{} ", unit.name)), Span::styled(unit.description.clone(), dim)]))
+    .collect_vec();
+  if failed.len() > SHOWN {
+    lines.push(Line::from(Span::styled(format!("...and {} more", failed.len() - SHOWN), dim)));
+  }
+  lines
+}
+
+/// What confirming a batch will do: each unit's state now and the command it amounts to
+fn batch_lines(verb: BatchVerb, units: &[UnitId], all_units: &IndexMap<UnitId, UnitWithStatus>) -> Vec<Line<'static>> {
+  const SHOWN: usize = 15;
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let mut lines = units
+    .iter()
+    .take(SHOWN)
+    .map(|unit| {
+      let state = all_units.get(unit).map(|u| u.activation_state.clone()).unwrap_or_else(|| "unknown".into());
+      let command = equivalent::for_action(&batch_action(verb, unit)).map(|e| e.shell()).unwrap_or_default();
+      Line::from(vec![Span::raw(format!("{} ", unit.name)), Span::styled(format!("({state})  {command}"), dim)])
+    })
+    .collect_vec();
+  if units.len() > SHOWN {
+    lines.push(Line::from(Span::styled(format!("...and {} more", units.len() - SHOWN), dim)));
+  }
+  lines
+}
+
+/// Each unit's current unit file state and [Install] section (including drop-ins), turned into what `verb` would
+/// change
+fn enablement_changes(units: &[UnitId], verb: EnablementVerb) -> Vec<enablement::Change> {
+  let mut rules: HashMap<UnitScope, Vec<preset::PresetRule>> = HashMap::new();
+  units
+    .iter()
+    .map(|unit| {
+      let preset = match verb {
+        EnablementVerb::Preset(_) => {
+          preset::action_for(rules.entry(unit.scope).or_insert_with(|| preset::load_rules(unit.scope)), &unit.name)
+        },
+        _ => preset::PresetAction::Enable,
+      };
+      let properties =
+        systemd::get_unit_properties(unit, &["UnitFileState", "FragmentPath", "DropInPaths"]).unwrap_or_default();
+      let contents = properties
+        .get("FragmentPath")
+        .into_iter()
+        .chain(properties.get("DropInPaths"))
+        .flat_map(|paths| paths.split_whitespace())
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .join("\n");
+      let current = properties.get("UnitFileState").filter(|s| !s.is_empty()).map_or("unknown", |s| s.as_str());
+      enablement::plan(&unit.name, current, &enablement::parse_install(&contents), verb, preset)
+    })
+    .collect()
+}
+
+/// What `preset-all` would change: the enabled or disabled unit files whose presets say otherwise. Templates are left
+/// out, since only their listed instances are preset
+fn preset_all_lines(scope: UnitScope, files: &[(String, String)], mode: preset::PresetMode) -> Vec<Line<'static>> {
+  let rules = preset::load_rules(scope);
+  let candidates = files
+    .iter()
+    .filter(|(name, state)| {
+      let action = preset::action_for(&rules, name);
+      !name.contains("@.")
+        && mode.allows(action)
+        && match action {
+          preset::PresetAction::Enable => state == "disabled",
+          _ => state == "enabled",
+        }
+    })
+    .map(|(name, _)| UnitId { name: name.clone(), scope })
+    .collect_vec();
+  let changes = enablement_changes(&candidates, EnablementVerb::Preset(mode));
+  let changing = changes.iter().filter(|c| !c.is_noop()).cloned().collect_vec();
+  let summary = format!("{} of {} unit files would change", changing.len(), files.len());
+  let mut lines =
+    vec![Line::from(Span::styled(summary, Style::default().add_modifier(Modifier::BOLD))), Line::from("")];
+  if !changing.is_empty() {
+    lines.extend(enablement_preview_lines(&changing));
+  }
+  lines
+}
+
+fn enablement_preview_lines(changes: &[enablement::Change]) -> Vec<Line<'static>> {
+  let bold = Style::default().add_modifier(Modifier::BOLD);
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let mut lines = vec![Line::from(Span::styled(format!("{:<36} {:<10}    {}", "unit", "now", "after"), bold))];
+  for change in changes {
+    if change.is_noop() {
+      lines.push(Line::from(vec![
+        Span::raw(format!("{:<36} {:<10}    ", change.unit, change.current)),
+        Span::styled(format!("no change, {}", enablement::noop_reason(change)), dim),
+      ]));
+      continue;
     }
+    lines.push(Line::from(vec![
+      Span::raw(format!("{:<36} {:<10} → ", change.unit, change.current)),
+      Span::styled(change.target.clone(), Style::default().fg(Color::Yellow)),
+    ]));
+    lines.extend(change.effects.iter().map(|e| Line::from(Span::styled(format!("    {e}"), dim))));
+  }
+  lines
+}
 
-    let selected_item = match self.filtered_units.selected() {
-      Some(s) => s,
-      None => return,
-    };
+/// Where a failed mount's source comes from, whether its device is present, what it last logged, and a suggested fix
+/// The journal namespace a unit logs to, if it has its own
+fn log_namespace(unit: &UnitId) -> Option<String> {
+  systemd::get_unit_properties(unit, &["LogNamespace"]).ok()?.remove("LogNamespace").filter(|n| !n.is_empty())
+}
 
-    // Help line at the bottom
+/// A unit's journal usage, the size of the journal it's in, and that journal's namespace
+fn measure_journal_usage(
+  unit: &UnitId,
+  retention_days: Option<u32>,
+) -> anyhow::Result<(journal_usage::Usage, Option<u64>, Option<String>)> {
+  let namespace = log_namespace(unit);
+  let cutoff =
+    retention_days.map(|days| (Local::now() - chrono::Duration::days(days as i64)).timestamp_micros().max(0) as u64);
+  let usage = systemd::get_journal_usage(unit, namespace.as_deref(), cutoff)?;
+  let journal_bytes = systemd::get_journal_disk_usage(namespace.as_deref()).ok();
+  Ok((usage, journal_bytes, namespace))
+}
 
-    let version = format!("v{}", env!("CARGO_PKG_VERSION"));
+fn journal_usage_lines(
+  usage: &journal_usage::Usage,
+  journal_bytes: Option<u64>,
+  namespace: Option<&str>,
+  retention_days: Option<u32>,
+) -> Vec<Line<'static>> {
+  let bold = Style::default().add_modifier(Modifier::BOLD);
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  if usage.entries == 0 {
+    return vec![Line::from("Nothing in the journal")];
+  }
+  let date = |usec: Option<u64>| {
+    usec
+      .and_then(|u| DateTime::from_timestamp_micros(u as i64))
+      .map(|t| t.with_timezone(&Local).format("%Y-%m-%d").to_string())
+      .unwrap_or_default()
+  };
+  let mut lines = vec![Line::from(format!(
+    "~{} in {} entries, from {} to {}",
+    humanize::size(usage.bytes),
+    usage.entries,
+    date(usage.first_usec),
+    date(usage.last_usec)
+  ))];
+  if let Some(per_day) = usage.bytes_per_day() {
+    lines.push(Line::from(format!("About {}", humanize::rate(per_day, "day"))));
+  }
+  if let Some(total) = journal_bytes.filter(|b| *b > 0) {
+    let share = usage.bytes as f64 / total as f64 * 100.0;
+    let color = if share >= 25.0 { Color::Yellow } else { Color::Reset };
+    let journal = namespace.map_or("the journal".to_string(), |n| format!("the {n} namespace"));
+    lines.push(Line::from(Span::styled(
+      format!("{share:.0}% of {journal} ({})", humanize::size(total)),
+      Style::default().fg(color),
+    )));
+  }
+  match retention_days {
+    Some(days) => lines.push(Line::from(format!("~{} is older than {days} days", humanize::size(usage.expired_bytes)))),
+    None => lines.push(Line::from(Span::styled(
+      "Set journal-retention-days in the config to see how much is older than that",
+      dim,
+    ))),
+  }
+  lines.push(Line::from(Span::styled("Sizes are estimated from the entries as journalctl prints them", dim)));
+
+  lines.push(Line::from(""));
+  lines.push(Line::from(Span::styled("Keeping it in check", bold)));
+  match namespace {
+    Some(namespace) => lines.push(Line::from(format!(
+      "  Logs to its own namespace: vacuuming (v in the action menu) only touches {namespace}, and \
+       MaxRetentionSec= in journald@{namespace}.conf sets its retention"
+    ))),
+    None => {
+      lines.push(Line::from("  It logs to the main journal, which can only be vacuumed for all units at once."));
+      lines.push(Line::from("  LogNamespace= in [Service] gives it a journal of its own with its own retention"));
+    },
+  }
+  lines.push(Line::from("  LogLevelMax=info in [Service] drops its debug messages"));
+  lines.push(Line::from("  LogRateLimitIntervalSec= and LogRateLimitBurst= cap how fast it can log"));
+  lines
+}
 
-    let help_line_rects =
-      Layout::new(Direction::Horizontal, [Constraint::Fill(1), Constraint::Length(version.len() as u16)])
-        .split(help_line_rect);
-    let help_rect = help_line_rects[0];
-    let version_rect = help_line_rects[1];
+/// How long a poked socket's service gets to come up
+const POKE_WAIT: Duration = Duration::from_secs(5);
+
+/// How often a service has been restarted and under what policy, how its last run ended and its watchdog, warning
+/// when it's crash-looping
+/// The latest OOM kill or core dump, whichever came last, e.g. `core dump 2m ago: /usr/bin/app, SIGSEGV`
+fn crash_line(evidence: &crash::Evidence, hint: Option<String>, theme: &Theme) -> Line<'static> {
+  let red = Style::default().fg(UnitState::Failed.color(theme));
+  let now = Local::now().timestamp();
+  let oom = evidence.oom_kills.last().map(|line| {
+    let at = line.split_once(' ').and_then(|(t, _)| parse_journalctl_datetime(t)).map(|t| t.timestamp());
+    let message = line.split_once("]: ").or(line.split_once(": ")).map_or(line.as_str(), |(_, m)| m);
+    (
+      at.unwrap_or_default(),
+      format!("OOM kill {}: {message}", at.map_or(String::new(), |t| humanize::relative(t, now))),
+    )
+  });
+  let dump = evidence
+    .core_dumps
+    .first()
+    .map(|d| (d.at, format!("core dump {}: {}, {}", humanize::relative(d.at, now), d.exe, d.signal)));
+  let text = [oom, dump].into_iter().flatten().max_by_key(|(at, _)| *at).map(|(_, text)| text).unwrap_or_default();
+  let mut spans = vec![Span::styled(text, red)];
+  spans.extend(hint.map(|hint| Span::styled(hint, Style::default().fg(theme.muted_alt))));
+  Line::from(spans)
+}
 
-    let help_line = match self.mode {
-      Mode::Search => Line::from(span("Show actions: <enter>", theme.primary)),
-      Mode::ServiceList => Line::from(span("Show actions: <enter> | Open unit file: e | Quit: q", theme.primary)),
-      Mode::Help => Line::from(span("Close menu: <esc>", theme.primary)),
-      Mode::ActionMenu => Line::from(span("Execute action: <enter> | Close menu: <esc>", theme.primary)),
-      Mode::Processing => Line::from(span("Cancel task: <esc>", theme.primary)),
-      Mode::Error => Line::from(span("Close menu: <esc>", theme.primary)),
-      Mode::SignalMenu => Line::from(span("Send signal: <enter> | Close menu: <esc>", theme.primary)),
-    };
+/// `3 restarts, last for src/main.py 2m ago`, or what it's watching before anything's changed
+/// The health column's cell: `None` for units without checks, `Some(None)` until the first check finishes
+fn health_cell(outcome: Option<Option<&health::Outcome>>, theme: &Theme) -> Span<'static> {
+  let width = LIST_COLUMN_WIDTH as usize;
+  match outcome {
+    None => Span::styled(format!("{:>width$}", "-"), Style::default().fg(theme.muted_alt)),
+    Some(None) => Span::styled(format!("{:>width$}", "checking"), Style::default().fg(theme.muted_alt)),
+    Some(Some(o)) if o.healthy => Span::styled(format!("{:>width$}", "● healthy"), Style::default().fg(theme.active)),
+    Some(Some(_)) => Span::styled(format!("{:>width$}", "✗ unhealthy"), Style::default().fg(theme.failed)),
+  }
+}
 
-    f.render_widget(help_line, help_rect);
-    f.render_widget(Line::from(version), version_rect);
+/// CPU and memory use over the refreshes in `history`, as graphs, or spelled out for a screen reader
+fn trend_line(
+  history: &VecDeque<ResourceSample>,
+  style: config::GraphStyle,
+  accessible: bool,
+  theme: &Theme,
+) -> Line<'static> {
+  let minutes = history.front().map_or(0, |s| s.at.elapsed().as_secs()).div_ceil(60).max(1);
+  let cpu = history.iter().map(|s| (s.cpu_percent.unwrap_or(0.0) * 10.0).round() as u64).collect_vec();
+  let memory = history.iter().map(|s| s.memory.unwrap_or(0)).collect_vec();
+  let last = history.back().copied();
+  let last_cpu = last.and_then(|s| s.cpu_percent).map_or("-".into(), |p| format!("{p:.1}%"));
+  let last_memory = format_bytes_or_dash(last.and_then(|s| s.memory));
+  if accessible {
+    let peak_cpu = cpu.iter().max().map_or(0.0, |p| *p as f64 / 10.0);
+    let peak_memory = humanize::size(memory.iter().max().copied().unwrap_or(0));
+    return Line::from(format!(
+      "cpu {last_cpu} (peak {peak_cpu:.1}%), memory {last_memory} (peak {peak_memory}) over the last {minutes} minutes"
+    ));
+  }
+  let graph =
+    |values: &[u64]| Span::styled(graph::sparkline(values, TREND_CELLS, style), Style::default().fg(theme.accent));
+  Line::from(vec![
+    Span::raw("cpu "),
+    graph(&cpu),
+    Span::raw(format!(" {last_cpu}, memory ")),
+    graph(&memory),
+    Span::raw(format!(" {last_memory}")),
+    Span::styled(format!(" (last {minutes}m)"), Style::default().fg(theme.muted_alt)),
+  ])
+}
 
-    let title = format!("Actions for {}", selected_item.name);
-    let mut min_width = title.len() as u16 + 2; // title plus corners
-    min_width = min_width.max(24); // hack: the width of the longest action name + 2
+fn health_line(outcome: &health::Outcome, theme: &Theme) -> Line<'static> {
+  let ago = humanize::relative(outcome.at.timestamp(), Local::now().timestamp());
+  let (state, color) = if outcome.healthy { ("healthy", theme.active) } else { ("unhealthy", theme.failed) };
+  let mut spans = vec![Span::styled(state, Style::default().fg(color))];
+  if !outcome.healthy {
+    spans.push(Span::raw(format!(", {}", outcome.detail)));
+  }
+  spans.push(Span::styled(format!(" (checked {ago})"), Style::default().fg(theme.muted_alt)));
+  Line::from(spans)
+}
 
-    let popup_width = min_width.min(f.area().width);
+fn watch_line(watch: &Watch, theme: &Theme) -> Line<'static> {
+  let Some((path, at, error)) = &watch.last else {
+    return Line::from(format!("restarts when {} change", watch.patterns.join(" ")));
+  };
+  let restarts = if watch.restarts == 1 { "1 restart".to_string() } else { format!("{} restarts", watch.restarts) };
+  let ago = humanize::relative(at.timestamp(), Local::now().timestamp());
+  let file = path.file_name().map_or(path.to_string_lossy(), |f| f.to_string_lossy());
+  let mut spans = vec![Span::raw(format!("{restarts}, last for {file} {ago}"))];
+  if let Some(error) = error {
+    spans.push(Span::styled(format!(", failed: {error}"), Style::default().fg(UnitState::Failed.color(theme))));
+  }
+  Line::from(spans)
+}
 
-    if self.mode == Mode::ActionMenu || self.mode == Mode::SignalMenu {
-      let title_prefix = if self.mode == Mode::ActionMenu { "Actions" } else { "Signals" };
-      let title = format!("{} for {}", title_prefix, selected_item.name);
-      let height = self.menu_items.items.len() as u16 + 2;
-      let popup = centered_rect_abs(popup_width, height, f.area());
+fn restart_line(accounting: &Accounting, sub_state: &str, theme: &Theme) -> Line<'static> {
+  let muted = Style::default().fg(theme.muted_alt);
+  let restarts = accounting.restarts.unwrap_or(0);
+  let mut spans = vec![
+    Span::styled(restarts.to_string(), Style::default().fg(if restarts > 0 { Color::Yellow } else { Color::Reset })),
+    Span::styled(format!(" (Restart={})", accounting.restart_policy.as_deref().unwrap_or("no")), muted),
+  ];
+  if let Some(result) = &accounting.result {
+    let color = if result == "success" { Color::Reset } else { Color::Red };
+    spans.extend([Span::raw(", last result "), Span::styled(result.clone(), Style::default().fg(color))]);
+  }
+  if let Some(watchdog) = accounting.watchdog_usec {
+    spans.push(Span::raw(format!(", watchdog {}", humanize::duration(Duration::from_micros(watchdog)))));
+  }
+  if accounting.crash_looping(sub_state) {
+    spans.push(Span::styled(" crash-looping", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)));
+  }
+  Line::from(spans)
+}
 
-      let items: Vec<ListItem> = self
-        .menu_items
-        .items
-        .iter()
-        .map(|i| {
-          let key_string = Span::styled(format!(" {:1} ", i.key_string()), Style::default().fg(theme.primary));
-          let line = Line::from(vec![key_string, Span::raw(&i.name)]);
-          ListItem::new(line)
-        })
-        .collect();
-      let items = List::new(items)
-        .block(
-          Block::default()
-            .borders(Borders::ALL)
-            .border_type(BorderType::Rounded)
-            .border_style(Style::default().fg(theme.accent))
-            .title(title),
-        )
-        .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD));
+/// What a unit's socket units are set to listen on, then what its processes actually listen on
+fn listening_lines(unit: &UnitId) -> Vec<Line<'static>> {
+  let bold = Style::default().add_modifier(Modifier::BOLD);
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let red = Style::default().fg(Color::Red);
+  let mut lines = vec![];
+
+  let sockets = if unit.name.ends_with(".socket") {
+    vec![unit.name.clone()]
+  } else {
+    let properties = systemd::get_unit_properties(unit, &["TriggeredBy"]).unwrap_or_default();
+    let triggered_by = properties.get("TriggeredBy").map(String::as_str).unwrap_or_default();
+    triggered_by.split_whitespace().filter(|u| u.ends_with(".socket")).map(String::from).collect()
+  };
+  if sockets.is_empty() {
+    lines.push(Line::from(Span::styled(format!("No socket unit starts {}", unit.name), dim)));
+  } else {
+    lines.push(Line::from(Span::styled("Socket units", bold)));
+    for socket in sockets {
+      let socket = UnitId { name: socket, scope: unit.scope };
+      match systemd::get_property_values(&socket, "Listen") {
+        Ok(listens) if listens.is_empty() => lines.push(Line::from(format!("  {} listens nowhere", socket.name))),
+        Ok(listens) => lines.extend(listens.into_iter().map(|l| Line::from(format!("  {}  {l}", socket.name)))),
+        Err(e) => lines.push(Line::from(Span::styled(format!("  {}: {e:#}", socket.name), red))),
+      }
+    }
+  }
+  lines.push(Line::from(""));
 
-      f.render_widget(Clear, popup);
-      f.render_stateful_widget(items, popup, &mut self.menu_items.state);
+  if remote::elsewhere().is_some() {
+    lines.push(Line::from(Span::styled("What the processes have open can only be read on this machine", dim)));
+    return lines;
+  }
+  let listening = systemd::get_unit_properties(unit, &["ControlGroup"]).and_then(|properties| {
+    match properties.get("ControlGroup").filter(|c| !c.is_empty()) {
+      Some(control_group) => sockets::listening(&process_tree::read(control_group)?),
+      None => anyhow::bail!("{} isn't running, so nothing of it is listening", unit.name),
     }
+  });
+  match listening {
+    Ok(listening) if listening.is_empty() => {
+      lines.push(Line::from(Span::styled("Its processes aren't listening on anything", dim)))
+    },
+    Ok(listening) => {
+      lines.push(Line::from(Span::styled("Listening now", bold)));
+      let width = listening.iter().map(|l| l.address.chars().count()).max().unwrap_or(0);
+      lines.extend(listening.into_iter().map(|l| {
+        Line::from(vec![
+          Span::raw(format!("  {:<4} {:width$}  ", l.protocol, l.address)),
+          Span::styled(format!("{} ({})", l.comm, l.pid), dim),
+        ])
+      }));
+    },
+    Err(e) => lines.push(Line::from(Span::styled(format!("{e:#}"), red))),
+  }
+  lines
+}
 
-    if self.mode == Mode::Processing {
-      let height = self.menu_items.items.len() as u16 + 2;
-      let popup = centered_rect_abs(popup_width, height, f.area());
+fn socket_poke_lines(socket: &UnitId) -> Vec<Line<'static>> {
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let good = |text: String| Line::from(vec![Span::styled("✓ ", Style::default().fg(Color::Green)), Span::raw(text)]);
+  let bad = |text: String| Line::from(vec![Span::styled("✗ ", Style::default().fg(Color::Red)), Span::raw(text)]);
+  let listens = match systemd::get_property_values(socket, "Listen") {
+    Ok(listens) if listens.is_empty() => return vec![Line::from(format!("{} doesn't listen anywhere", socket.name))],
+    Ok(listens) => listens,
+    Err(e) => return vec![bad(e.to_string())],
+  };
+  let socket_properties =
+    |s: &UnitId| systemd::get_unit_properties(s, &["Accept", "NAccepted", "Triggers"]).unwrap_or_default();
+  let properties = socket_properties(socket);
+  let accept = properties.get("Accept").is_some_and(|a| a == "yes");
+  let accepted = |p: &HashMap<String, String>| p.get("NAccepted").and_then(|n| n.parse::<u64>().ok()).unwrap_or(0);
+  // with Accept=yes every connection gets its own instance of the template
+  let prefix = socket.name.trim_end_matches(".socket");
+  let service = match properties.get("Triggers").and_then(|t| t.split_whitespace().next()) {
+    Some(service) => service.to_string(),
+    None if accept => format!("{prefix}@.service"),
+    None => format!("{prefix}.service"),
+  };
+  let service_id = UnitId { name: service.clone(), scope: socket.scope };
+  let service_state =
+    || systemd::get_unit_properties(&service_id, &["ActiveState", "InvocationID"]).unwrap_or_default();
+
+  let mut lines = vec![];
+  let mut target = None;
+  for listen in &listens {
+    match sockets::target(listen) {
+      Ok(t) => {
+        lines.push(Line::from(format!("Listens on {listen}")));
+        target.get_or_insert(t);
+      },
+      Err(e) => {
+        lines.push(Line::from(vec![Span::raw(format!("Listens on {listen} ")), Span::styled(e.to_string(), dim)]))
+      },
+    }
+  }
+  let Some(target) = target else {
+    lines.push(bad("None of these can be poked".into()));
+    return lines;
+  };
+
+  let since = Local::now();
+  let before = service_state();
+  lines.push(Line::from(""));
+  if let Err(e) = sockets::poke(&target, POKE_WAIT) {
+    lines.push(bad(format!("Couldn't connect to {target}: {e}")));
+    return lines;
+  }
+  lines.push(good(format!("Connected to {target}")));
+
+  let started = Instant::now();
+  let outcome = loop {
+    std::thread::sleep(Duration::from_millis(250));
+    let waited = started.elapsed() >= POKE_WAIT;
+    if accept {
+      if accepted(&socket_properties(socket)) > accepted(&properties) {
+        break good(format!("{} accepted the connection and spawned an instance of {service}", socket.name));
+      }
+      if waited {
+        break bad(format!("{} didn't accept the connection", socket.name));
+      }
+      continue;
+    }
+    let after = service_state();
+    let state = after.get("ActiveState").cloned().unwrap_or_default();
+    let restarted = after.get("InvocationID") != before.get("InvocationID");
+    match state.as_str() {
+      "active" if restarted => break good(format!("{service} activated")),
+      "active" if waited => {
+        break Line::from(format!("{service} was already running, so the connection went straight to it"))
+      },
+      "failed" => break bad(format!("{service} failed to start")),
+      _ if waited => break bad(format!("{service} didn't come up within {}s, it's {state}", POKE_WAIT.as_secs())),
+      _ => {},
+    }
+  };
+  lines.push(outcome);
+
+  // give the journal a moment to catch up with what the service logged
+  std::thread::sleep(Duration::from_millis(500));
+  let mut command = systemd::journalctl();
+  command
+    .args(["--quiet", "--no-pager", "--output=short-iso"])
+    .arg(format!("--since=@{}", since.timestamp()))
+    .args(["-u", &socket.name, "-u"])
+    .arg(if accept { format!("{prefix}@*.service") } else { service.clone() });
+  systemd::in_scope(&mut command, socket.scope);
+  lines.push(Line::from(""));
+  match remote::on_host(&mut command).output() {
+    Ok(output) if output.status.success() => {
+      let logged = String::from_utf8_lossy(&output.stdout).lines().map(String::from).collect_vec();
+      if logged.is_empty() {
+        lines.push(Line::from(Span::styled("Nothing was logged", dim)));
+      }
+      lines.extend(logged.into_iter().map(Line::from));
+    },
+    Ok(output) => lines.push(bad(String::from_utf8_lossy(&output.stderr).trim().to_string())),
+    Err(e) => lines.push(bad(e.to_string())),
+  }
+  lines
+}
 
-      static SPINNER_CHARS: &[char] = &['⣷', '⣯', '⣟', '⡿', '⢿', '⣻', '⣽', '⣾'];
+/// 0-based slot in `pinned` for the keys 1 to 9
+fn pinned_slot(code: KeyCode) -> Option<usize> {
+  match code {
+    KeyCode::Char(c @ '1'..='9') => c.to_digit(10).map(|d| d as usize - 1),
+    _ => None,
+  }
+}
 
-      let spinner_char = SPINNER_CHARS[self.spinner_tick as usize % SPINNER_CHARS.len()];
-      // TODO: make this a spinner
-      let paragraph = Paragraph::new(vec![Line::from(format!("{spinner_char}"))])
-        .block(
-          Block::default()
-            .title("Processing")
-            .border_type(BorderType::Rounded)
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(theme.accent)),
-        )
-        .style(Style::default())
-        .wrap(Wrap { trim: true });
+/// Cells in the timeline strip
+const TIMELINE_WIDTH: usize = 72;
+
+fn timeline_lines(unit: &UnitId, theme: &Theme) -> Vec<Line<'static>> {
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let runs = match systemd::get_invocations(unit) {
+    Ok(runs) if runs.is_empty() => {
+      return vec![Line::from(format!("The journal has no record of {} running", unit.name))]
+    },
+    Ok(runs) => runs,
+    Err(e) => return vec![Line::from(Span::styled(e.to_string(), Style::default().fg(Color::Red)))],
+  };
+  let from = runs[0].started;
+  let to = Local::now().timestamp_micros().max(from + 1);
+
+  // actions taken from here are the closest thing to a deploy marker the TUI knows about
+  let actions = action_log::load()
+    .unwrap_or_default()
+    .into_iter()
+    .filter(|e| e.is_for(unit))
+    .map(|e| (e.timestamp.timestamp_micros(), '▼'))
+    .collect_vec();
+  let reloads = runs.iter().flat_map(|r| r.reloads.iter().map(|at| (*at, '↻'))).collect_vec();
+  let events = reloads.iter().chain(&actions).copied().sorted().collect_vec();
+
+  let active = UnitState::Active.color(theme);
+  let failed = UnitState::Failed.color(theme);
+  let strip = timeline::lane(&runs, from, to, TIMELINE_WIDTH).into_iter().map(|slot| {
+    let style = match slot {
+      Slot::Idle => dim,
+      Slot::Running | Slot::Started => Style::default().fg(active),
+      Slot::Failed => Style::default().fg(failed).add_modifier(Modifier::BOLD),
+    };
+    Span::styled(slot.symbol().to_string(), style)
+  });
+  let markers = timeline::markers(&events, from, to, TIMELINE_WIDTH).into_iter().map(|marker| match marker {
+    Some('↻') => Span::styled("↻", Style::default().fg(Color::Yellow)),
+    Some(symbol) => Span::styled(symbol.to_string(), Style::default().fg(Color::Cyan)),
+    None => Span::raw(" "),
+  });
+
+  let local = |micros: i64| {
+    DateTime::from_timestamp_micros(micros).map(|t| t.with_timezone(&Local).format("%m-%d %H:%M").to_string())
+  };
+  let (start, end) = (local(from).unwrap_or_default(), local(to).unwrap_or_default());
+  let failures = runs.iter().filter(|r| timeline::is_failure(r)).collect_vec();
+  let label = |text: &str| Span::styled(format!("{text:<8}"), dim);
+
+  let mut lines = vec![
+    Line::from(format!(
+      "{} runs, {} failed, {} reloads, {} actions over {}",
+      runs.len(),
+      failures.len(),
+      reloads.len(),
+      actions.len(),
+      humanize::duration(Duration::from_micros((to - from) as u64))
+    )),
+    Line::from(""),
+    Line::from(std::iter::once(label("state")).chain(strip).collect_vec()),
+    Line::from(std::iter::once(label("events")).chain(markers).collect_vec()),
+    Line::from(vec![
+      label(""),
+      Span::styled(format!("{start:<width$}{end}", width = TIMELINE_WIDTH.saturating_sub(end.len())), dim),
+    ]),
+    Line::from(""),
+    Line::from(Span::styled(
+      format!(
+        "{} started  {} running  {} failed  {} idle  ↻ reloaded  ▼ action taken here",
+        Slot::Started.symbol(),
+        Slot::Running.symbol(),
+        Slot::Failed.symbol(),
+        Slot::Idle.symbol()
+      ),
+      dim,
+    )),
+  ];
+  if !failures.is_empty() {
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("Recent failures", Style::default().add_modifier(Modifier::BOLD))));
+    lines.extend(failures.iter().rev().take(10).map(|run| Line::from(format!("  {}", run_label(run)))));
+  }
+  lines
+}
 
-      f.render_widget(Clear, popup);
-      f.render_widget(paragraph, popup);
+fn mount_diagnostics_lines(unit: &UnitId) -> Vec<Line<'static>> {
+  let bold = Style::default().add_modifier(Modifier::BOLD);
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let error = |e: String| Line::from(Span::styled(e, Style::default().fg(Color::Red)));
+  // an automount hands the actual mounting to the .mount unit of the same name
+  let mount = UnitId { name: mounts::mount_unit_for(&unit.name), scope: unit.scope };
+  let properties = match systemd::get_unit_properties(&mount, mounts::MOUNT_PROPERTIES) {
+    Ok(properties) => properties,
+    Err(e) => return vec![error(e.to_string())],
+  };
+  let property = |name: &str| properties.get(name).cloned().unwrap_or_default();
+
+  let field = |label: &str, value: Vec<Span<'static>>| {
+    let mut spans = vec![Span::styled(format!("{label:>14}  "), dim)];
+    spans.extend(value);
+    Line::from(spans)
+  };
+  let mut lines = vec![
+    field("Source", vec![Span::raw(property("What"))]),
+    field("Mount point", vec![Span::raw(property("Where"))]),
+    field("Type", vec![Span::raw(property("Type"))]),
+    field("Options", vec![Span::raw(property("Options"))]),
+    field("Result", vec![Span::raw(property("Result"))]),
+  ];
+  if mount != *unit {
+    lines.push(field("Mounted by", vec![Span::raw(mount.name.clone())]));
+  }
+
+  let source_path = property("SourcePath");
+  let device = mounts::device_path(&property("What"));
+  let device_exists = device.as_ref().map(|d| Path::new(d).exists());
+  if let Some(device) = &device {
+    let state = if device_exists == Some(true) {
+      Span::styled("present", Style::default().fg(Color::Green))
+    } else {
+      Span::styled("missing", Style::default().fg(Color::Red))
+    };
+    lines.push(field("Device", vec![Span::raw(format!("{device} ")), state]));
+  }
+  lines.push(Line::from(""));
+
+  match source_path.as_str() {
+    "" => lines.push(Line::from(Span::styled("Defined by a unit file, not fstab", dim))),
+    path => {
+      let entry = remote::read_file(path).ok().and_then(|fstab| mounts::fstab_entry(&fstab, &property("Where")));
+      match entry {
+        Some((number, text)) => {
+          lines.push(Line::from(Span::styled(format!("Generated from {path}, line {number}"), bold)));
+          lines.push(Line::from(format!("  {text}")));
+        },
+        None => lines.push(Line::from(Span::styled(format!("Generated from {path}"), bold))),
+      }
+    },
+  }
+  lines.push(Line::from(""));
+  lines.push(Line::from(Span::styled(mounts::hint(&properties, device_exists), Style::default().fg(Color::Yellow))));
+  lines.push(Line::from(""));
+
+  lines.push(Line::from(Span::styled("Last log lines this boot", bold)));
+  let mut command = systemd::journalctl();
+  command.args(["--quiet", "--output=short-iso", "--boot=0", "--lines=15", "--no-pager", "-u", &mount.name]);
+  systemd::in_scope(&mut command, mount.scope);
+  match remote::on_host(&mut command).output() {
+    Ok(output) if output.status.success() => {
+      let logs = String::from_utf8_lossy(&output.stdout).lines().map(|l| Line::from(format!("  {l}"))).collect_vec();
+      if logs.is_empty() {
+        lines.push(Line::from(Span::styled("  Nothing logged this boot", dim)));
+      }
+      lines.extend(logs);
+    },
+    Ok(output) => lines.push(error(String::from_utf8_lossy(&output.stderr).trim().to_string())),
+    Err(e) => lines.push(error(e.to_string())),
+  }
+  lines.push(Line::from(""));
+  lines.push(Line::from(Span::styled(
+    "Reload and retry mount (M in the action menu) runs daemon-reload first, so fixes to fstab are picked up",
+    dim,
+  )));
+  lines
+}
+
+fn sandboxing_lines(unit: &UnitId, accessible: bool) -> Vec<Line<'static>> {
+  let properties = match systemd::get_unit_properties(unit, &hardening::properties()) {
+    Ok(properties) => properties,
+    Err(e) => return vec![Line::from(Span::styled(e.to_string(), Style::default().fg(Color::Red)))],
+  };
+
+  let results = hardening::evaluate(&properties);
+  let hardened = results.iter().filter(|r| r.hardened).count();
+  let mut lines = vec![
+    Line::from(format!("{hardened} of {} hardening settings in place", results.len())),
+    Line::from(Span::styled(
+      format!("For the full report run `systemd-analyze security {}`", unit.name),
+      Style::default().add_modifier(Modifier::DIM),
+    )),
+    Line::from(""),
+  ];
+
+  for result in results {
+    let (marker, color) = match (result.hardened, accessible) {
+      (true, false) => ("✓", Color::Green),
+      (false, false) => ("✗", Color::Yellow),
+      (true, true) => ("ok", Color::Green),
+      (false, true) => ("missing", Color::Yellow),
+    };
+    let mut value = result.value;
+    if value.chars().count() > 40 {
+      value = format!("{}…", value.chars().take(40).collect::<String>());
     }
+    let mut spans = vec![
+      Span::styled(format!("{marker} "), Style::default().fg(color)),
+      Span::raw(format!("{}={value}", result.directive)),
+    ];
+    if !result.hardened {
+      spans.push(Span::styled(format!("  {}", result.hint), Style::default().add_modifier(Modifier::DIM)));
+    }
+    lines.push(Line::from(spans));
+  }
+  lines
+}
+
+/// Every overridden/extended/masked unit file for the given scopes, grouped by the kind of change
+fn delta_audit_lines(scopes: &[UnitScope]) -> Vec<Line<'static>> {
+  let mut lines = vec![];
+  for scope in scopes {
+    let scope_name = match scope {
+      UnitScope::Global => "System",
+      UnitScope::User => "User",
+    };
+    lines.push(Line::from(Span::styled(
+      format!("{scope_name} units"),
+      Style::default().add_modifier(Modifier::UNDERLINED),
+    )));
+
+    let entries = match systemd::get_delta(*scope) {
+      Ok(entries) => entries,
+      Err(e) => {
+        lines
+          .push(Line::from(Span::styled(format!("Failed to run systemd-delta: {e}"), Style::default().fg(Color::Red))));
+        continue;
+      },
+    };
+
+    if entries.is_empty() {
+      lines.push(Line::from("  No local changes"));
+    }
+
+    let groups = entries.into_iter().into_group_map_by(|e| e.kind);
+    for (kind, entries) in groups.into_iter().sorted_by_key(|(kind, _)| *kind) {
+      lines.push(Line::from(Span::styled(
+        format!("{} ({})", kind.label(), entries.len()),
+        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+      )));
+      for entry in entries {
+        lines.push(Line::from(vec![
+          Span::raw(format!("  {}", entry.path)),
+          Span::styled(" → ", Style::default().add_modifier(Modifier::DIM)),
+          Span::raw(entry.target),
+        ]));
+      }
+    }
+    lines.push(Line::from(""));
   }
+  lines
 }
 
 /// helper function to create a centered rect using up certain percentage of the available rect `r`
@@ -1211,9 +9405,84 @@ fn centered_rect_abs(width: u16, height: u16, r: Rect) -> Rect {
 ///
 /// systemd v255 changed the timestamp format from `-0700` to `-07:00` (RFC 3339).
 /// See: https://github.com/systemd/systemd/pull/29134
-fn parse_journalctl_timestamp(timestamp: &str) -> Option<String> {
+/// Columns Left and Right scroll unwrapped logs by
+const LOG_HSCROLL_STEP: u16 = 8;
+
+pub fn parse_journalctl_timestamp(timestamp: &str) -> Option<String> {
+  format_log_timestamp(timestamp, LogTimestamps::Short, Local::now())
+}
+
+/// A journal timestamp shown as `format`: `None` when it isn't one, empty when timestamps are hidden
+pub fn format_log_timestamp(timestamp: &str, format: LogTimestamps, now: DateTime<Local>) -> Option<String> {
+  let at = parse_journalctl_datetime(timestamp)?;
+  Some(match format {
+    LogTimestamps::Short => at.format("%Y-%m-%d %H:%M").to_string(),
+    LogTimestamps::Relative => humanize::relative(at.timestamp(), now.timestamp()),
+    LogTimestamps::Iso => at.with_timezone(&Local).format("%Y-%m-%dT%H:%M:%S%:z").to_string(),
+    LogTimestamps::Utc => at.with_timezone(&Utc).format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+    LogTimestamps::Hidden => String::new(),
+  })
+}
+
+fn parse_journalctl_datetime(timestamp: &str) -> Option<DateTime<FixedOffset>> {
   // %z accepts both "-0700" (systemd <v255) and "-07:00" (systemd >=v255)
-  DateTime::parse_from_str(timestamp, "%Y-%m-%dT%H:%M:%S%z").ok().map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+  DateTime::parse_from_str(timestamp, "%Y-%m-%dT%H:%M:%S%z").ok()
+}
+
+/// The part of a log line that's searched: the message, without the timestamp
+fn log_message(line: &str) -> &str {
+  match line.split_once(' ') {
+    Some((timestamp, message)) if parse_journalctl_datetime(timestamp).is_some() => message,
+    _ => line,
+  }
+}
+
+/// `text` in `style`, with the search matches at `ranges` reversed, and in bold on the line jumped to
+fn highlight_matches<'a>(text: &'a str, style: Style, ranges: &[(usize, usize)], current: bool) -> Vec<Span<'a>> {
+  let mut matched = style.add_modifier(Modifier::REVERSED);
+  if current {
+    matched = matched.add_modifier(Modifier::BOLD);
+  }
+  let mut spans = vec![];
+  let mut at = 0;
+  for (start, end) in ranges {
+    spans.push(Span::styled(&text[at..*start], style));
+    spans.push(Span::styled(&text[*start..*end], matched));
+    at = *end;
+  }
+  spans.push(Span::styled(&text[at..], style));
+  spans
+}
+
+/// How many of `logs` were logged in each minute, by minutes since the epoch
+fn count_log_minutes(logs: &[Entry]) -> BTreeMap<i64, u64> {
+  let mut minutes = BTreeMap::new();
+  tally_log_minutes(&mut minutes, logs, true);
+  minutes
+}
+
+/// Count `lines` into `minutes`, or take them back out
+fn tally_log_minutes(minutes: &mut BTreeMap<i64, u64>, lines: &[Entry], add: bool) {
+  for line in lines {
+    let Some(dt) = line.text.split_once(' ').and_then(|(timestamp, _)| parse_journalctl_datetime(timestamp)) else {
+      continue;
+    };
+    let minute = dt.timestamp().div_euclid(60);
+    if add {
+      *minutes.entry(minute).or_default() += 1;
+    } else if let Some(count) = minutes.get_mut(&minute) {
+      *count = count.saturating_sub(1);
+      if *count == 0 {
+        minutes.remove(&minute);
+      }
+    }
+  }
+}
+
+/// Count of log lines for each of the last `minutes` minutes up to `now` (in seconds since the epoch), oldest first
+fn log_rate(log_minutes: &BTreeMap<i64, u64>, now: i64, minutes: usize) -> Vec<u64> {
+  let current = now.div_euclid(60);
+  (0..minutes as i64).rev().map(|age| log_minutes.get(&(current - age)).copied().unwrap_or(0)).collect()
 }
 
 #[cfg(test)]
@@ -1229,6 +9498,369 @@ mod tests {
     assert_eq!(result, Some("2025-04-26 06:04".to_string()));
   }
 
+  #[test]
+  fn test_boot_filter() {
+    assert_eq!(BootFilter::at(-1), BootFilter::Previous);
+    let earlier = BootFilter::at(-3);
+    assert_eq!(earlier.journalctl_arg().as_deref(), Some("--boot=-3"));
+    assert_eq!(earlier.label(), "boot -3");
+    assert!(!earlier.can_follow());
+    assert_eq!(earlier.next(), BootFilter::All);
+  }
+
+  #[test]
+  fn test_resume_point() {
+    let logs = vec![
+      Entry::plain("2025-04-26T06:04:44-07:00 host nginx[812]: one"),
+      Entry::plain("2025-04-26T06:04:45-07:00 host nginx[812]: two"),
+      Entry::plain("2025-04-26T06:04:45-07:00 host nginx[812]: three"),
+    ];
+    let resume = ResumePoint::after(&logs).unwrap();
+    assert_eq!(resume.since, 1745672685);
+    assert_eq!(resume.seen, logs[1..]);
+    assert_eq!(ResumePoint::after(&[Entry::plain("No logs found/available")]), None);
+
+    let logs = vec![
+      Entry::plain("2025-04-26T06:04:44-07:00 host nginx[812]: one"),
+      Entry::plain("                                           continued"),
+      Entry::plain("2025-04-26T06:04:44-07:00 host nginx[812]: two"),
+      Entry::plain("2025-04-26T06:04:45-07:00 host nginx[812]: three"),
+    ];
+    let older = ResumePoint::before(&logs).unwrap();
+    assert_eq!(older.since, 1745672684);
+    assert_eq!(older.seen, logs[..3]);
+    // a page read up to the end of that second sends them again after the lines before them
+    let page = vec![Entry::plain("2025-04-26T06:04:44-07:00 host nginx[812]: zero"), logs[0].clone(), logs[1].clone()];
+    assert_eq!(older.unseen(page), [Entry::plain("2025-04-26T06:04:44-07:00 host nginx[812]: zero")]);
+  }
+
+  #[test]
+  fn test_log_line_bounds() {
+    let lines = |range: std::ops::Range<usize>| range.map(|i| Entry::plain(i.to_string())).collect_vec();
+    let mut logs = lines(0..MAX_LOG_LINES - 1);
+    assert_eq!(append_log_lines(&mut logs, lines(MAX_LOG_LINES - 1..MAX_LOG_LINES + 2)), 2);
+    assert_eq!(logs.len(), MAX_LOG_LINES);
+    assert_eq!(logs[0].text, "2");
+    // older lines push out the newest
+    assert_eq!(prepend_log_lines(&mut logs, lines(0..2)), 2);
+    assert_eq!(logs.len(), MAX_LOG_LINES);
+    assert_eq!(logs[0].text, "0");
+    assert_eq!(logs.last().unwrap().text, (MAX_LOG_LINES - 1).to_string());
+  }
+
+  #[test]
+  fn test_is_related_denial() {
+    let avc =
+      r#"2025-04-26T06:04:45-07:00 host audit[812]: AVC avc:  denied  { read } for  pid=812 comm="nginx" name="x" "#;
+    let apparmor = r#"2025-04-26T06:04:45-07:00 host kernel: audit: apparmor="DENIED" operation="open" profile="foo" pid=99 comm="other""#;
+    let exes = vec!["/usr/sbin/nginx".to_string()];
+    assert!(is_related_denial(avc, &[], &exes));
+    assert!(!is_related_denial(apparmor, &[], &exes));
+    assert!(!is_related_denial("2025-04-26T06:04:45-07:00 host nginx[812]: pid=812 started", &["812".into()], &exes));
+    assert_eq!(
+      audit_matches(&["812".into()], &exes),
+      ["_TRANSPORT=audit", "_PID=812", "+", "_TRANSPORT=audit", "_COMM=nginx", "+", "_TRANSPORT=kernel"]
+    );
+    assert_eq!(comm("/usr/lib/systemd/systemd-resolved"), "systemd-resolve");
+  }
+
+  #[test]
+  fn test_is_boot_context_message() {
+    assert!(is_boot_context_message("host systemd[1]: Dependency failed for Web Server.", "nginx.service"));
+    assert!(is_boot_context_message("host systemd[1]: nginx.service: Scheduled restart job", "nginx.service"));
+    assert!(is_boot_context_message("host systemd[1]: foo.mount: Mounting timed out. Terminating.", "nginx.service"));
+    assert!(!is_boot_context_message("host systemd[1]: Started Session 1 of User root.", "nginx.service"));
+  }
+
+  #[test]
+  fn test_run_label() {
+    let run = Invocation {
+      id: "aaa".into(),
+      started: 0,
+      ended: Some(250_000_000),
+      result: Some("exit-code".into()),
+      exit_status: Some("1".into()),
+      reloads: vec![],
+    };
+    assert!(run_label(&run).ends_with(" (4m 10s) failed: exit-code, status 1"));
+    let run = Invocation { ended: None, result: None, exit_status: None, ..run };
+    assert!(run_label(&run).ends_with(" (no end recorded)"));
+  }
+
+  #[test]
+  fn test_moved() {
+    assert_eq!(moved(Some(3), 10, 1, true), Some(4));
+    assert_eq!(moved(Some(9), 10, 1, true), Some(0));
+    assert_eq!(moved(Some(9), 10, 1, false), Some(9));
+    assert_eq!(moved(Some(0), 10, -1, true), Some(9));
+    // a page past the end stops at it first
+    assert_eq!(moved(Some(5), 10, 20, true), Some(9));
+    assert_eq!(moved(Some(5), 10, -20, true), Some(0));
+    assert_eq!(moved(None, 10, 5, true), Some(0));
+    assert_eq!(moved(None, 0, 1, true), None);
+
+    let failed = [false, true, false, true];
+    assert_eq!(next_match(Some(1), 4, true, |i| failed[i]), Some(3));
+    assert_eq!(next_match(Some(3), 4, true, |i| failed[i]), Some(1));
+    assert_eq!(next_match(Some(3), 4, false, |i| failed[i]), None);
+    assert_eq!(next_match(None, 4, false, |i| failed[i]), Some(1));
+  }
+
+  #[test]
+  fn test_list_column_cell() {
+    let accounting =
+      Accounting { io_accounting: true, io_read_bytes: Some(4096), io_write_bytes: None, ..Default::default() };
+    assert_eq!(
+      list_column_cell(ListColumn::Io, Some(&accounting), None, None, None, UnitScope::Global, 0).trim(),
+      "4 KiB/-"
+    );
+    assert_eq!(
+      list_column_cell(ListColumn::Network, Some(&accounting), None, None, None, UnitScope::Global, 0).trim(),
+      "-"
+    );
+    let accounting = Accounting { cpu_usage_nsec: Some(250_000_000_000), ..Default::default() };
+    assert_eq!(
+      list_column_cell(ListColumn::Cpu, Some(&accounting), None, None, None, UnitScope::Global, 0).trim(),
+      "4m 10s"
+    );
+    assert_eq!(
+      list_column_cell(ListColumn::Uptime, Some(&accounting), None, None, None, UnitScope::Global, 0).trim(),
+      "-"
+    );
+    let timer = TimerTimes { next_elapse: Some(4000), last_trigger: Some(1000) };
+    assert_eq!(
+      list_column_cell(ListColumn::NextElapse, None, None, Some(&timer), None, UnitScope::Global, 1300).trim(),
+      "in 45m 0s"
+    );
+    assert_eq!(
+      list_column_cell(ListColumn::LastTrigger, None, None, Some(&timer), None, UnitScope::Global, 1300).trim(),
+      "5m 0s ago"
+    );
+    let accounting = Accounting { cpu_usage_nsec: Some(42_000_000), ..Default::default() };
+    assert_eq!(
+      list_column_cell(ListColumn::Cpu, Some(&accounting), None, None, None, UnitScope::Global, 0).trim(),
+      "42ms"
+    );
+    let accounting = Accounting { memory_current: Some(20 << 20), tasks_current: Some(7), ..Default::default() };
+    let rates = Rates { cpu_percent: Some(12.5), io_read: Some(1536.0), ..Default::default() };
+    assert_eq!(
+      list_column_cell(ListColumn::Memory, Some(&accounting), None, None, None, UnitScope::Global, 0).trim(),
+      "20 MiB"
+    );
+    assert_eq!(
+      list_column_cell(ListColumn::Tasks, Some(&accounting), None, None, None, UnitScope::Global, 0).trim(),
+      "7"
+    );
+    assert_eq!(
+      list_column_cell(ListColumn::CpuPercent, Some(&accounting), Some(&rates), None, None, UnitScope::Global, 0)
+        .trim(),
+      "12.5%"
+    );
+    assert_eq!(
+      list_column_cell(ListColumn::FileState, None, None, None, Some("masked"), UnitScope::Global, 0).trim(),
+      "masked"
+    );
+    assert_eq!(list_column_cell(ListColumn::FileState, None, None, None, Some(""), UnitScope::Global, 0).trim(), "-");
+    assert_eq!(list_column_cell(ListColumn::Scope, None, None, None, None, UnitScope::User, 0).trim(), "user");
+    assert_eq!(sort_value(ListColumn::Memory, Some(&accounting), None), Some(20971520.0));
+    assert_eq!(sort_value(ListColumn::CpuPercent, None, None), None);
+    let accounting = Accounting { state_changed_usec: Some(1_000_000), ..Default::default() };
+    assert_eq!(sort_value(ListColumn::StateChanged, Some(&accounting), None), Some(1_000_000.0));
+    assert_eq!(sort_value(ListColumn::IoRate, None, Some(&rates)), Some(1536.0));
+    let accounting = Accounting { io_accounting: true, ..Default::default() };
+    assert_eq!(
+      list_column_cell(ListColumn::IoRate, Some(&accounting), Some(&rates), None, None, UnitScope::Global, 0).trim(),
+      "1.5 KiB/-/s"
+    );
+    assert_eq!(
+      list_column_cell(ListColumn::NetworkRate, Some(&accounting), Some(&rates), None, None, UnitScope::Global, 0)
+        .trim(),
+      "-"
+    );
+  }
+
+  #[test]
+  fn test_state_rank() {
+    let unit = |load_state: &str, activation_state: &str| UnitWithStatus {
+      name: "nginx.service".into(),
+      scope: UnitScope::Global,
+      description: String::new(),
+      file_path: None,
+      load_state: load_state.into(),
+      activation_state: activation_state.into(),
+      sub_state: String::new(),
+      enablement_state: None,
+    };
+    let mut units = [unit("loaded", "inactive"), unit("loaded", "active"), unit("masked", "failed")];
+    units.sort_by_key(|u| state_rank(SortOrder::ActiveState, u));
+    assert_eq!(units.iter().map(|u| u.activation_state.as_str()).collect_vec(), ["failed", "active", "inactive"]);
+    units.sort_by_key(|u| state_rank(SortOrder::LoadState, u));
+    assert_eq!(units[0].load_state, "masked");
+  }
+
+  #[test]
+  fn test_pipe_through() {
+    assert_eq!(pipe_through("grep b", "a\nb\nc\n".into()).unwrap(), "b\n");
+    assert_eq!(pipe_through("grep z", "a\n".into()).unwrap(), "");
+    assert!(pipe_through("exit 3", String::new()).is_err());
+  }
+
+  #[test]
+  fn test_destructive_verb() {
+    let unit = UnitId { name: "sshd.service".into(), scope: UnitScope::Global };
+    assert_eq!(destructive_verb(&Action::StopService(unit.clone())), Some(("stop".into(), vec!["sshd.service"])));
+    let kill = Action::KillService { unit: unit.clone(), signal: "SIGHUP".into(), whom: KillWhom::All };
+    assert_eq!(destructive_verb(&kill), Some(("send SIGHUP to".into(), vec!["sshd.service"])));
+    let args = ["mask", "--now", "sshd.service", "cron.service"].map(String::from).to_vec();
+    assert_eq!(
+      destructive_verb(&Action::RunSystemctl(unit.clone(), args)),
+      Some(("mask".into(), vec!["sshd.service", "cron.service"]))
+    );
+    assert_eq!(destructive_verb(&Action::RunSystemctl(unit.clone(), vec!["unmask".into()])), None);
+    assert_eq!(destructive_verb(&Action::StartService(unit)), None);
+  }
+
+  #[test]
+  fn test_read_only() {
+    let unit = UnitId { name: "sshd.service".into(), scope: UnitScope::Global };
+    let mut home = Home { read_only: true, ..Default::default() };
+    let refused = |action: Option<Action>| matches!(action, Some(Action::EnterError(e)) if e == READ_ONLY_REFUSAL);
+    assert!(refused(home.dispatch(Action::RestartService(unit.clone()))));
+    assert!(refused(home.dispatch(Action::Confirmed(Box::new(Action::StopService(unit.clone()))))));
+    assert!(refused(home.run_command(Command::Kill).pop()));
+    assert!(!refused(home.run_command(Command::ToggleSplit).pop()));
+
+    let systemctl = |args: &[&str]| Action::RunSystemctl(unit.clone(), args.iter().map(|a| a.to_string()).collect());
+    assert!(!systemctl(&["status", "sshd.service"]).changes_system());
+    assert!(!systemctl(&["list-dependencies", "sshd.service"]).changes_system());
+    assert!(!systemctl(&["get-default"]).changes_system());
+    assert!(systemctl(&["mask", "sshd.service"]).changes_system());
+    assert!(!Action::ShowJobs.changes_system() && !Command::FollowLogsInPane.changes_system());
+    assert!(Action::OpenPipeLogs.changes_system() && Command::PipeLogs.changes_system());
+
+    // plugins run only when they say they just look
+    let plugin = |read_only| config::Plugin { name: "p".into(), command: vec!["true".into()], read_only };
+    home.config.plugins = vec![plugin(false), plugin(true)];
+    assert!(home.refuses(&Action::RunPlugin { unit: unit.clone(), plugin: 0 }));
+    assert!(!home.refuses(&Action::RunPlugin { unit: unit.clone(), plugin: 1 }));
+
+    // the action menu's keys don't get around it for what app.rs runs itself
+    let edit = Action::EditDropIn { unit, fragment_path: None };
+    home.mode = Mode::ActionMenu;
+    home.menu_items = StatefulList::with_items(vec![MenuItem::new("Edit", edit, Some(KeyCode::Char('E')))]);
+    home.menu_items.state.select(Some(0));
+    assert!(refused(home.handle_key_events(KeyEvent::from(KeyCode::Char('E'))).pop()));
+    assert!(refused(home.handle_key_events(KeyEvent::from(KeyCode::Enter)).pop()));
+  }
+
+  #[test]
+  fn test_log_rate() {
+    let entry = |timestamp: &str| Entry::plain(format!("{timestamp} host app[1]: hello"));
+    let mut logs = vec![entry("2025-04-26T06:04:45-07:00"), entry("2025-04-26T06:04:59-07:00")];
+    logs.push(entry("2025-04-26T06:06:01-07:00"));
+    let mut minutes = count_log_minutes(&logs);
+    let now = DateTime::parse_from_rfc3339("2025-04-26T06:06:30-07:00").unwrap().timestamp();
+    assert_eq!(log_rate(&minutes, now, 3), [2, 0, 1]);
+    tally_log_minutes(&mut minutes, &logs[..1], false);
+    assert_eq!(log_rate(&minutes, now, 2), [0, 1]);
+    assert_eq!(log_rate(&minutes, now, 3), [1, 0, 1]);
+  }
+
+  #[test]
+  fn test_units_removed() {
+    let unit = |name: &str| UnitWithStatus {
+      name: name.into(),
+      scope: UnitScope::Global,
+      description: String::new(),
+      file_path: None,
+      load_state: "loaded".into(),
+      activation_state: "active".into(),
+      sub_state: "running".into(),
+      enablement_state: None,
+    };
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let (journalctl_tx, _journalctl_rx) = std::sync::mpsc::channel();
+    let mut home = Home { action_tx: Some(tx), journalctl_tx: Some(journalctl_tx), ..Default::default() };
+    home.dispatch(Action::SetServices(vec![unit("nginx.service"), unit("run-r1.service")]));
+
+    // a transient unit that finished goes away without a full fetch to notice
+    let gone = unit("run-r1.service").id();
+    home.dispatch(Action::UnitsUpdated(systemd::UnitChanges {
+      updated: vec![unit("cups.service")],
+      removed: vec![gone.clone()],
+    }));
+    assert!(!home.all_units.contains_key(&gone));
+    assert_eq!(home.all_units.len(), 2);
+    assert!(home.search_index.search("run-r1").is_empty());
+  }
+
+  #[test]
+  fn test_refresh_coalescing() {
+    let mut home = Home { refreshing_since: Some(Instant::now()), ..Default::default() };
+    assert!(home.dispatch(Action::RefreshServices).is_none());
+    assert!(home.dispatch(Action::RefreshServices).is_none());
+    assert!(home.refresh_again);
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    home.action_tx = Some(tx);
+    home.dispatch(Action::SetServices(vec![]));
+    assert!(home.refreshing_since.is_none() && !home.refresh_again);
+    assert!(matches!(rx.try_recv(), Ok(Action::RefreshServices)));
+    assert!(rx.try_recv().is_err());
+
+    home.refreshing_since = Some(Instant::now());
+    home.refresh_again = true;
+    assert!(matches!(home.dispatch(Action::RefreshFailed("gone".into())), Some(Action::EnterError(e)) if e == "gone"));
+    assert!(home.refreshing_since.is_none() && !home.refresh_again);
+  }
+
+  #[test]
+  fn test_format_log_timestamp() {
+    let timestamp = "2025-04-26T06:04:45-07:00";
+    let now = DateTime::parse_from_rfc3339("2025-04-26T06:07:00-07:00").unwrap().with_timezone(&Local);
+    let format = |format| format_log_timestamp(timestamp, format, now).unwrap();
+    assert_eq!(format(LogTimestamps::Short), "2025-04-26 06:04");
+    assert_eq!(format(LogTimestamps::Relative), "2m 15s ago");
+    assert_eq!(format(LogTimestamps::Utc), "2025-04-26T13:04:45Z");
+    // whatever the local time zone, it's the same moment
+    let iso = DateTime::parse_from_rfc3339(&format(LogTimestamps::Iso)).unwrap();
+    assert_eq!(iso, DateTime::parse_from_rfc3339(timestamp).unwrap());
+    assert_eq!(format(LogTimestamps::Hidden), "");
+    assert_eq!(format_log_timestamp("not-a-time", LogTimestamps::Utc, now), None);
+    assert_eq!(LogTimestamps::Hidden.next(), LogTimestamps::Short);
+  }
+
+  #[test]
+  fn test_scroll_to_log_match() {
+    let mut home = Home { log_page_width: 30, ..Default::default() };
+    let padding = "x".repeat(60);
+    home.logs = vec![
+      Entry::plain(format!("2025-04-26T06:04:45-07:00 host app[1]: {padding} needle")),
+      Entry::plain("not a journal line, needle"),
+    ];
+    home.log_search.set_query("needle", false);
+    // "2025-04-26 06:04 host app[1]: " is 30 columns, then the padding and a space
+    home.scroll_to_log_line(Some(0));
+    assert_eq!(home.log_hscroll, 91 - LOG_HSCROLL_STEP);
+    assert_eq!(home.logs_scroll_offset, 1);
+    // already in view, so it stays put
+    home.log_hscroll = 5;
+    home.scroll_to_log_line(Some(1));
+    assert_eq!(home.log_hscroll, 5);
+    // wrapped lines don't scroll sideways at all
+    home.log_wrap = true;
+    home.log_hscroll = 0;
+    home.scroll_to_log_line(Some(0));
+    assert_eq!(home.log_hscroll, 0);
+  }
+
+  #[test]
+  fn test_merge_log_lines() {
+    let a = vec!["2025-04-26T06:00:00-07:00 a1".to_string(), "2025-04-26T06:02:00-07:00 a2".to_string()];
+    let b = vec!["2025-04-26T06:01:00-07:00 b1".to_string()];
+    let merged = merge_log_lines(a, b);
+    assert_eq!(merged[1], "2025-04-26T06:01:00-07:00 b1");
+  }
+
   #[test]
   fn test_parse_timestamp_systemd_before_v255() {
     // systemd <v255 uses ISO 8601 format without colon in timezone offset
@@ -0,0 +1,159 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+  layout::{Margin, Rect},
+  style::{Color, Modifier, Style},
+  text::{Line, Span},
+  widgets::{Clear, List, ListItem, ListState},
+};
+
+use super::{home::Mode, pane_block, pane_title, Component, Frame};
+use crate::{action::Action, systemd::Job, theme::Theme};
+
+/// The managers' job queues, like `systemctl list-jobs`, kept current while open. A job that's waiting says which
+/// units it's waiting for, and c cancels the selected one
+#[derive(Default)]
+pub struct JobQueue {
+  jobs: Vec<Job>,
+  loading: bool,
+  error: Option<String>,
+  state: ListState,
+  pub theme: Theme,
+  pub accessible: bool,
+}
+
+impl JobQueue {
+  pub fn new(theme: Theme, accessible: bool) -> Self {
+    Self { loading: true, theme, accessible, ..Default::default() }
+  }
+
+  /// The latest queue, keeping the same job selected while it's still there
+  pub fn set_jobs(&mut self, jobs: Result<Vec<Job>, String>) {
+    self.loading = false;
+    let selected = self.selected().map(|j| (j.id, j.unit.scope));
+    match jobs {
+      Ok(jobs) => {
+        self.jobs = jobs;
+        self.error = None;
+      },
+      Err(e) => self.error = Some(e),
+    }
+    let index = selected
+      .and_then(|(id, scope)| self.jobs.iter().position(|j| j.id == id && j.unit.scope == scope))
+      .or_else(|| self.state.selected().map(|i| i.min(self.jobs.len().saturating_sub(1))));
+    self.state.select(if self.jobs.is_empty() { None } else { Some(index.unwrap_or(0)) });
+  }
+
+  fn selected(&self) -> Option<&Job> {
+    self.jobs.get(self.state.selected()?)
+  }
+
+  /// What's selected, for screen readers
+  pub fn selected_description(&self) -> String {
+    match self.selected() {
+      Some(job) => format!(
+        "{} for {}, {} of {}",
+        job.describe(),
+        job.unit.name,
+        self.state.selected().unwrap_or(0) + 1,
+        self.jobs.len()
+      ),
+      None => "No jobs queued".into(),
+    }
+  }
+
+  fn move_by(&mut self, delta: isize) {
+    if self.jobs.is_empty() {
+      return;
+    }
+    let current = self.state.selected().unwrap_or(0) as isize;
+    self.state.select(Some((current + delta).clamp(0, self.jobs.len() as isize - 1) as usize));
+  }
+}
+
+impl Component for JobQueue {
+  fn handle_key_events(&mut self, key: KeyEvent) -> Vec<Action> {
+    match key.code {
+      KeyCode::Esc | KeyCode::Char('q') => return vec![Action::EnterMode(Mode::ServiceList)],
+      KeyCode::Down | KeyCode::Char('j') => self.move_by(1),
+      KeyCode::Up | KeyCode::Char('k') => self.move_by(-1),
+      KeyCode::Char('c') | KeyCode::Delete => {
+        let Some(job) = self.selected() else { return vec![] };
+        return vec![Action::CancelJob { unit: job.unit.clone(), id: job.id }];
+      },
+      _ => return vec![],
+    }
+    vec![Action::Render]
+  }
+
+  fn render(&mut self, f: &mut Frame<'_>, rect: Rect) {
+    let popup = rect.inner(Margin::new(rect.width / 8, rect.height / 6));
+    let dim = Style::default().add_modifier(Modifier::DIM);
+
+    let items = if let Some(e) = &self.error {
+      vec![ListItem::new(Line::from(Span::styled(e.clone(), Style::default().fg(self.theme.failed))))]
+    } else if self.loading {
+      vec![ListItem::new(Line::from("Loading..."))]
+    } else if self.jobs.is_empty() {
+      vec![ListItem::new(Line::from(Span::styled("No jobs queued, everything's settled", dim)))]
+    } else {
+      let width = self.jobs.iter().map(|j| j.unit.name.chars().count()).max().unwrap_or(0);
+      self
+        .jobs
+        .iter()
+        .map(|job| {
+          let state_color = if job.state == "running" { self.theme.primary } else { Color::Yellow };
+          let mut spans = vec![
+            Span::styled(format!("{:>7} ", job.id), dim),
+            Span::raw(format!("{:width$} ", job.unit.name)),
+            Span::raw(format!("{:<13} ", job.job_type)),
+            Span::styled(job.state.clone(), Style::default().fg(state_color)),
+          ];
+          if !job.waiting_for.is_empty() {
+            spans.push(Span::styled(format!(" for {}", job.waiting_for.join(", ")), dim));
+          }
+          ListItem::new(Line::from(spans))
+        })
+        .collect()
+    };
+    let mut list = List::new(items).highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    if self.accessible {
+      list = list.highlight_symbol("> ");
+    }
+
+    let title = format!("Job queue ({} jobs)", self.jobs.len());
+    let block = pane_block(self.accessible)
+      .title(pane_title(&title, self.accessible))
+      .title_bottom(Line::from(" j/k move, c cancel job, Esc close ").right_aligned().style(dim));
+    f.render_widget(Clear, popup);
+    f.render_stateful_widget(list.block(block), popup, &mut self.state);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::systemd::{UnitId, UnitScope};
+
+  #[test]
+  fn test_set_jobs() {
+    let job = |id: u32, name: &str| Job {
+      id,
+      unit: UnitId { name: name.into(), scope: UnitScope::Global },
+      job_type: "start".into(),
+      state: "waiting".into(),
+      waiting_for: vec![],
+    };
+    let mut queue = JobQueue::default();
+    queue.set_jobs(Ok(vec![job(1, "a.service"), job(2, "b.service"), job(3, "c.service")]));
+    queue.move_by(1);
+    assert_eq!(queue.selected().map(|j| j.id), Some(2));
+    // the selected job stays selected as the ones before it finish
+    queue.set_jobs(Ok(vec![job(2, "b.service"), job(3, "c.service")]));
+    assert_eq!(queue.selected().map(|j| j.id), Some(2));
+    // and once it's gone, whatever took its place is
+    queue.set_jobs(Ok(vec![job(3, "c.service")]));
+    assert_eq!(queue.selected().map(|j| j.id), Some(3));
+    queue.set_jobs(Ok(vec![]));
+    assert!(queue.selected().is_none());
+  }
+}
@@ -1,18 +1,16 @@
 use anyhow::Result;
 use log::LevelFilter;
-use ratatui::{
-  layout::Rect,
-  style::{Color, Style},
-  widgets::{Block, BorderType, Borders},
-};
+use ratatui::{layout::Rect, style::Style};
 use tokio::sync::mpsc::UnboundedSender;
 use tui_logger::{TuiLoggerLevelOutput, TuiLoggerWidget, TuiWidgetState};
 
-use super::{Component, Frame};
-use crate::action::Action;
+use super::{pane_block, pane_title, Component, Frame};
+use crate::{action::Action, theme::Theme};
 
 #[derive(Default)]
 pub struct Logger {
+  pub accessible: bool,
+  pub theme: Theme,
   state: TuiWidgetState,
 }
 
@@ -24,12 +22,12 @@ impl Component for Logger {
 
   fn render(&mut self, f: &mut Frame<'_>, rect: Rect) {
     let w = TuiLoggerWidget::default()
-      .block(Block::default().title("─systemctl-tui logs").borders(Borders::ALL).border_type(BorderType::Rounded))
-      .style_error(Style::default().fg(Color::Red))
-      .style_debug(Style::default().fg(Color::Green))
-      .style_warn(Style::default().fg(Color::Yellow))
-      .style_trace(Style::default().fg(Color::Magenta))
-      .style_info(Style::default().fg(Color::Cyan))
+      .block(pane_block(self.accessible).title(pane_title("systemctl-tui logs", self.accessible)))
+      .style_error(Style::default().fg(self.theme.log_error))
+      .style_warn(Style::default().fg(self.theme.log_warning))
+      .style_info(Style::default().fg(self.theme.log_info))
+      .style_debug(Style::default().fg(self.theme.log_debug))
+      .style_trace(Style::default().fg(self.theme.log_debug))
       .output_separator(':')
       .output_timestamp(Some("%H:%M:%S".to_string()))
       .output_level(Some(TuiLoggerLevelOutput::Long))
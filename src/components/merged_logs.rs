@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+  layout::Rect,
+  style::{Color, Modifier, Style},
+  text::{Line, Span},
+  widgets::{Clear, Paragraph, Wrap},
+};
+
+use super::{
+  home::{format_log_timestamp, Mode},
+  pane_block, pane_title, Component, Frame,
+};
+use crate::{
+  action::Action,
+  config::LogTimestamps,
+  journal::{self, Entry},
+  systemd::{UnitId, UnitScope},
+  theme::Theme,
+};
+
+/// Each unit's prefix color, in the order the units were picked
+const UNIT_COLORS: &[Color] = &[Color::Cyan, Color::Magenta, Color::Yellow, Color::Green, Color::Blue, Color::LightRed];
+
+/// Older lines are dropped past this many, so a chatty unit left streaming doesn't grow without bound
+const MAX_LINES: usize = 10_000;
+
+/// The logs of several units as one stream, like `journalctl -u a -u b -f`, with each line prefixed by the unit it's
+/// from. Newest lines are at the top, like the log pane
+#[derive(Default)]
+pub struct MergedLogs {
+  pub units: Vec<UnitId>,
+  /// Each line with the index in `units` of the unit it's from
+  lines: Vec<(usize, Entry)>,
+  loading: bool,
+  /// Lines scrolled down from the newest
+  scroll: u16,
+  /// Like the log pane's, and cycled with the same key
+  pub timestamps: LogTimestamps,
+  pub theme: Theme,
+  pub accessible: bool,
+}
+
+impl MergedLogs {
+  pub fn new(units: Vec<UnitId>, theme: Theme, accessible: bool) -> Self {
+    Self { units, loading: true, theme, accessible, ..Default::default() }
+  }
+
+  pub fn append(&mut self, lines: Vec<(usize, Entry)>) {
+    self.loading = false;
+    self.lines.extend(lines);
+    let excess = self.lines.len().saturating_sub(MAX_LINES);
+    self.lines.drain(..excess);
+  }
+
+  /// What's on screen, for screen readers
+  pub fn description(&self) -> String {
+    let names = self.units.iter().map(|u| u.name.as_str()).collect::<Vec<_>>().join(", ");
+    match self.lines.iter().rev().nth(self.scroll as usize) {
+      Some((unit, entry)) => format!("Logs of {names}. {}: {}", self.units[*unit].name, entry.text),
+      None => format!("Logs of {names}. No lines yet"),
+    }
+  }
+
+  fn label(&self, index: usize) -> String {
+    let unit = &self.units[index];
+    let name = unit.name.strip_suffix(".service").unwrap_or(&unit.name);
+    let mixed_scopes = self.units.iter().any(|u| u.scope != unit.scope);
+    if mixed_scopes && unit.scope == UnitScope::User {
+      format!("{name} (user)")
+    } else {
+      name.to_string()
+    }
+  }
+}
+
+/// Which of `units` a journal entry read from the `scope` manager is from, going by the fields naming its unit
+pub fn source(units: &[UnitId], scope: UnitScope, fields: &HashMap<String, String>) -> Option<usize> {
+  journal::units_of(fields).find_map(|name| units.iter().position(|u| u.scope == scope && u.name == name))
+}
+
+/// The lines of `entries` read from the `scope` manager, each with the unit it's from. Entries from none of `units`
+/// are left out
+pub fn attribute(units: &[UnitId], scope: UnitScope, entries: &[HashMap<String, String>]) -> Vec<(usize, Entry)> {
+  entries
+    .iter()
+    .filter_map(|fields| Some((source(units, scope, fields)?, fields)))
+    .flat_map(|(unit, fields)| Entry::from_fields(fields).into_iter().map(move |line| (unit, line)))
+    .collect()
+}
+
+impl Component for MergedLogs {
+  fn handle_key_events(&mut self, key: KeyEvent) -> Vec<Action> {
+    match key.code {
+      KeyCode::Esc | KeyCode::Char('q') => return vec![Action::EnterMode(Mode::ServiceList)],
+      KeyCode::Down | KeyCode::Char('j') => self.scroll = self.scroll.saturating_add(1),
+      KeyCode::Up | KeyCode::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+      KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+      KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+      KeyCode::Home | KeyCode::Char('g') => self.scroll = 0,
+      KeyCode::Char('t') => self.timestamps = self.timestamps.next(),
+      _ => return vec![],
+    }
+    vec![Action::Render]
+  }
+
+  fn render(&mut self, f: &mut Frame<'_>, rect: Rect) {
+    let dim = Style::default().add_modifier(Modifier::DIM);
+    let labels = (0..self.units.len()).map(|i| self.label(i)).collect::<Vec<_>>();
+    let width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let now = chrono::Local::now();
+
+    let lines = if self.loading {
+      vec![Line::from("Loading...")]
+    } else if self.lines.is_empty() {
+      vec![Line::from(Span::styled("No logs yet, new lines show up here as they're written", dim))]
+    } else {
+      self
+        .lines
+        .iter()
+        .rev()
+        .map(|(unit, entry)| {
+          let color = UNIT_COLORS[unit % UNIT_COLORS.len()];
+          let style = entry.priority.map_or(Style::default(), |p| Style::default().fg(self.theme.log_priority(p)));
+          let mut spans = vec![Span::styled(format!("{:width$} ", labels[*unit]), Style::default().fg(color))];
+          let (timestamp, rest) = entry.text.split_once(' ').unwrap_or_default();
+          match format_log_timestamp(timestamp, self.timestamps, now) {
+            Some(date) if date.is_empty() => spans.push(Span::styled(rest.to_string(), style)),
+            Some(date) => {
+              spans.extend([Span::styled(date, dim), Span::raw(" "), Span::styled(rest.to_string(), style)])
+            },
+            None => spans.push(Span::styled(entry.text.clone(), style)),
+          }
+          Line::from(spans)
+        })
+        .collect()
+    };
+
+    let title = format!("Following the logs of {} units", self.units.len());
+    let block = pane_block(self.accessible)
+      .title(pane_title(&title, self.accessible))
+      .title_bottom(Line::from(" j/k scroll, g newest, t times, Esc close ").right_aligned().style(dim));
+    let paragraph = Paragraph::new(lines).block(block).wrap(Wrap { trim: false }).scroll((self.scroll, 0));
+    f.render_widget(Clear, rect);
+    f.render_widget(paragraph, rect);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_attribute() {
+    let units = [
+      UnitId { name: "nginx.service".into(), scope: UnitScope::Global },
+      UnitId { name: "postgresql.service".into(), scope: UnitScope::Global },
+    ];
+    let entry = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+      pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    };
+    let entries = [
+      entry(&[("_SYSTEMD_UNIT", "postgresql.service"), ("MESSAGE", "ready\nto accept connections")]),
+      entry(&[("_SYSTEMD_UNIT", "init.scope"), ("UNIT", "nginx.service"), ("MESSAGE", "Started nginx.service")]),
+      entry(&[("_SYSTEMD_UNIT", "cron.service"), ("MESSAGE", "unrelated")]),
+    ];
+    let lines = attribute(&units, UnitScope::Global, &entries);
+    assert_eq!(lines.iter().map(|(unit, _)| *unit).collect::<Vec<_>>(), [1, 1, 0]);
+    assert!(lines[2].1.text.ends_with("Started nginx.service"));
+    // the same name under the other manager is a different unit
+    assert!(attribute(&units, UnitScope::User, &entries).is_empty());
+  }
+}
@@ -1,12 +1,22 @@
 use anyhow::Result;
 use crossterm::event::{KeyEvent, MouseEvent};
-use ratatui::{layout::Rect, Frame};
+use ratatui::{
+  layout::Rect,
+  widgets::{Block, BorderType, Borders},
+  Frame,
+};
 use tokio::sync::mpsc::UnboundedSender;
 
 use crate::{action::Action, event::Event};
 
+pub mod deptree;
+pub mod form;
 pub mod home;
+pub mod jobs;
 pub mod logger;
+pub mod merged_logs;
+pub mod properties;
+pub mod viewer;
 
 pub trait Component {
   #[allow(unused_variables)]
@@ -20,7 +30,7 @@ pub trait Component {
       Some(Event::Key(key_event)) => self.handle_key_events(key_event),
       Some(Event::Mouse(mouse_event)) => self.handle_mouse_events(mouse_event),
       Some(Event::Resize(x, y)) => vec![Action::Resize(x, y)],
-      Some(Event::RefreshTick) => vec![Action::RefreshServices],
+      Some(Event::RefreshTick) => vec![Action::RefreshTick],
       Some(_) => vec![],
       None => vec![],
     }
@@ -39,3 +49,22 @@ pub trait Component {
   }
   fn render(&mut self, f: &mut Frame<'_>, rect: Rect);
 }
+
+/// Base block for a pane or popup. Accessible mode drops the box-drawing borders because screen readers announce them
+/// character by character
+pub fn pane_block<'a>(accessible: bool) -> Block<'a> {
+  if accessible {
+    Block::default()
+  } else {
+    Block::default().borders(Borders::ALL).border_type(BorderType::Rounded)
+  }
+}
+
+/// Pane titles normally lead with a `─` so they blend into the top border; skip it when there's no border
+pub fn pane_title(title: &str, accessible: bool) -> String {
+  if accessible {
+    title.to_string()
+  } else {
+    format!("─{title}")
+  }
+}
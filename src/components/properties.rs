@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+  layout::{Constraint, Direction, Layout, Margin, Rect},
+  style::{Modifier, Style},
+  text::{Line, Span},
+  widgets::{Clear, List, ListItem, ListState, Paragraph},
+};
+use tui_input::{backend::crossterm::EventHandler, Input};
+
+use super::{home::Mode, pane_block, pane_title, Component, Frame};
+use crate::{action::Action, log_filter::Filter, search::is_subsequence, systemd::UnitId, theme::Theme};
+
+/// Every property of a unit, like `systemctl show`, narrowed down by typing part of a property name. Enter copies the
+/// selected property's value. It also shows the fields of a journal entry, where Enter instead filters the log pane by
+/// the selected field
+#[derive(Default)]
+pub struct PropertyInspector {
+  pub unit: Option<UnitId>,
+  /// The start of the entry's first line, like `2025-04-26T06:04:45-07:00 host nginx[812]`, when it's showing a
+  /// journal entry
+  entry: Option<String>,
+  /// Empty while they're being read
+  properties: Vec<(String, String)>,
+  error: Option<String>,
+  filter: Input,
+  /// Indexes into `properties` of the ones matching the filter
+  matches: Vec<usize>,
+  state: ListState,
+  pub theme: Theme,
+  pub accessible: bool,
+}
+
+impl PropertyInspector {
+  pub fn new(unit: &UnitId, theme: Theme, accessible: bool) -> Self {
+    Self { unit: Some(unit.clone()), theme, accessible, ..Default::default() }
+  }
+
+  pub fn journal_entry(line: &str, fields: &BTreeMap<String, String>, theme: Theme, accessible: bool) -> Self {
+    let header = line.split_once(": ").map_or(line, |(header, _)| header);
+    let mut inspector = Self { entry: Some(header.to_string()), theme, accessible, ..Default::default() };
+    inspector.set_properties(Ok(fields.iter().map(|(k, v)| (k.clone(), v.clone())).collect()));
+    inspector
+  }
+
+  pub fn set_properties(&mut self, properties: Result<Vec<(String, String)>, String>) {
+    match properties {
+      Ok(properties) => self.properties = properties,
+      Err(e) => self.error = Some(e),
+    }
+    self.refilter();
+  }
+
+  fn refilter(&mut self) {
+    self.matches = matching(&self.properties, self.filter.value());
+    self.state.select(if self.matches.is_empty() { None } else { Some(0) });
+  }
+
+  fn selected(&self) -> Option<&(String, String)> {
+    self.properties.get(*self.matches.get(self.state.selected()?)?)
+  }
+
+  /// What's selected, for screen readers
+  pub fn selected_description(&self) -> String {
+    match self.selected() {
+      Some((name, value)) => format!(
+        "{name} is {}, {} of {}",
+        if value.is_empty() { "empty" } else { value },
+        self.state.selected().unwrap_or(0) + 1,
+        self.matches.len()
+      ),
+      None => "No matching properties".into(),
+    }
+  }
+
+  /// What the keys do, for the bottom of the popup
+  fn keys(&self) -> &'static str {
+    match self.entry {
+      Some(_) => " type to filter, ↑/↓ move, Enter only entries with it, ctrl-x hide them, ctrl-y copy, Esc close ",
+      None => " type to filter, ↑/↓ move, Enter copy value, Esc close ",
+    }
+  }
+
+  fn move_by(&mut self, delta: isize) {
+    if self.matches.is_empty() {
+      return;
+    }
+    let current = self.state.selected().unwrap_or(0) as isize;
+    self.state.select(Some((current + delta).clamp(0, self.matches.len() as isize - 1) as usize));
+  }
+}
+
+/// The properties whose names match `filter`: those containing it first, then those with its characters in order, each
+/// in the order `systemctl show` lists them
+fn matching(properties: &[(String, String)], filter: &str) -> Vec<usize> {
+  let filter = filter.to_lowercase();
+  let mut tiers: [Vec<usize>; 2] = Default::default();
+  for (i, (name, _)) in properties.iter().enumerate() {
+    let name = name.to_lowercase();
+    if name.contains(&filter) {
+      tiers[0].push(i);
+    } else if is_subsequence(&filter, &name) {
+      tiers[1].push(i);
+    }
+  }
+  tiers.concat()
+}
+
+impl Component for PropertyInspector {
+  fn handle_key_events(&mut self, key: KeyEvent) -> Vec<Action> {
+    let filter = |exclude| match self.selected() {
+      Some((name, value)) => vec![Action::AddLogFilter(Filter::field(name, value, exclude))],
+      None => vec![],
+    };
+    match key.code {
+      KeyCode::Esc if self.entry.is_some() => return vec![Action::EnterMode(Mode::Logs)],
+      KeyCode::Esc => return vec![Action::EnterMode(Mode::ServiceList)],
+      KeyCode::Enter if self.entry.is_some() => return filter(false),
+      KeyCode::Char('x') if self.entry.is_some() && key.modifiers.contains(KeyModifiers::CONTROL) => {
+        return filter(true)
+      },
+      KeyCode::Char('y') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+        let Some((name, value)) = self.selected() else { return vec![] };
+        return vec![Action::CopyText { what: name.clone(), text: value.clone() }];
+      },
+      KeyCode::Down => self.move_by(1),
+      KeyCode::Up => self.move_by(-1),
+      KeyCode::PageDown => self.move_by(10),
+      KeyCode::PageUp => self.move_by(-10),
+      KeyCode::Enter => {
+        let Some((name, value)) = self.selected() else { return vec![] };
+        return vec![Action::CopyText { what: name.clone(), text: value.clone() }];
+      },
+      _ => {
+        self.filter.handle_event(&crossterm::event::Event::Key(key));
+        self.refilter();
+      },
+    }
+    vec![Action::Render]
+  }
+
+  fn render(&mut self, f: &mut Frame<'_>, rect: Rect) {
+    let popup = rect.inner(Margin::new(rect.width / 10, rect.height / 10));
+    let dim = Style::default().add_modifier(Modifier::DIM);
+
+    let name = self.unit.as_ref().map_or("", |u| u.name.as_str());
+    let mut title = match &self.entry {
+      Some(entry) => format!("Fields of {entry}"),
+      None => format!("Properties of {name}"),
+    };
+    if self.properties.is_empty() && self.error.is_none() {
+      title.push_str(" (loading...)");
+    } else if !self.filter.value().is_empty() {
+      title.push_str(&format!(" ({} of {})", self.matches.len(), self.properties.len()));
+    }
+    let block = pane_block(self.accessible)
+      .title(pane_title(&title, self.accessible))
+      .title_bottom(Line::from(self.keys()).right_aligned().style(dim));
+    let inner = block.inner(popup);
+    let rows = Layout::new(Direction::Vertical, [Constraint::Length(2), Constraint::Min(0)]).split(inner);
+
+    let prompt = "Filter: ";
+    let filter = Paragraph::new(Line::from(vec![
+      Span::styled(prompt, Style::default().fg(self.theme.primary)),
+      Span::raw(self.filter.value().to_string()),
+    ]));
+
+    let items = match &self.error {
+      Some(e) => vec![ListItem::new(Line::from(Span::styled(e.clone(), Style::default().fg(self.theme.failed))))],
+      None => self
+        .matches
+        .iter()
+        .map(|i| {
+          let (name, value) = &self.properties[*i];
+          ListItem::new(Line::from(vec![
+            Span::styled(name.clone(), Style::default().fg(self.theme.primary)),
+            Span::styled("=", dim),
+            Span::raw(value.clone()),
+          ]))
+        })
+        .collect(),
+    };
+    let mut list = List::new(items).highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    if self.accessible {
+      list = list.highlight_symbol("> ");
+    }
+
+    f.render_widget(Clear, popup);
+    f.render_widget(block, popup);
+    f.render_widget(filter, rows[0]);
+    f.set_cursor_position((rows[0].x + (prompt.len() + self.filter.visual_cursor()) as u16, rows[0].y));
+    f.render_stateful_widget(list, rows[1], &mut self.state);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_matching() {
+    let properties = ["ExecStart", "Restart", "RestartUSec", "RemainAfterExit", "Environment"]
+      .map(|name| (name.to_string(), String::new()));
+    assert_eq!(matching(&properties, "restart"), [1, 2]);
+    // containing it beats having its letters spread out
+    assert_eq!(matching(&properties, "ent"), [4, 3]);
+    assert_eq!(matching(&properties, "").len(), properties.len());
+    assert!(matching(&properties, "xyz").is_empty());
+  }
+}
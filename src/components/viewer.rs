@@ -0,0 +1,154 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+  layout::{Margin, Rect},
+  style::{Color, Modifier, Style},
+  text::{Line, Span},
+  widgets::{Clear, Paragraph},
+};
+
+use super::{home::Mode, pane_block, pane_title, Component, Frame};
+use crate::{action::Action, monochrome, systemd::UnitFile};
+
+/// A scrollable, read-only popup for showing command output and reports over the main UI
+#[derive(Default)]
+pub struct Viewer {
+  pub title: String,
+  pub lines: Vec<Line<'static>>,
+  pub scroll: u16,
+  pub accessible: bool,
+  /// Height of the text area at the last render, used for paging
+  page_height: u16,
+}
+
+impl Viewer {
+  pub fn new(title: String, lines: Vec<Line<'static>>, accessible: bool) -> Self {
+    Self { title, lines, scroll: 0, accessible, page_height: 0 }
+  }
+
+  fn max_scroll(&self) -> u16 {
+    (self.lines.len() as u16).saturating_sub(self.page_height)
+  }
+
+  fn scroll_down(&mut self, n: u16) {
+    self.scroll = self.scroll.saturating_add(n).min(self.max_scroll());
+  }
+}
+
+impl Component for Viewer {
+  fn handle_key_events(&mut self, key: KeyEvent) -> Vec<Action> {
+    let page = self.page_height.max(1);
+    match key.code {
+      KeyCode::Esc | KeyCode::Char('q') => return vec![Action::EnterMode(Mode::ServiceList)],
+      KeyCode::Down | KeyCode::Char('j') => self.scroll_down(1),
+      KeyCode::Up | KeyCode::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+      KeyCode::PageDown | KeyCode::Char(' ') => self.scroll_down(page),
+      KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(page),
+      KeyCode::Home | KeyCode::Char('g') => self.scroll = 0,
+      KeyCode::End | KeyCode::Char('G') => self.scroll = self.max_scroll(),
+      KeyCode::Char('y') => {
+        return vec![Action::CopyText { what: self.title.clone(), text: monochrome::plain_text(&self.lines) }]
+      },
+      _ => return vec![],
+    }
+    vec![Action::Render]
+  }
+
+  fn render(&mut self, f: &mut Frame<'_>, rect: Rect) {
+    let popup = rect.inner(Margin::new(rect.width / 10, rect.height / 10));
+    let block = pane_block(self.accessible).title(pane_title(&self.title, self.accessible)).title_bottom(
+      Line::from(" j/k scroll, y copy as plain text, Esc close ")
+        .right_aligned()
+        .style(Style::default().add_modifier(Modifier::DIM)),
+    );
+    self.page_height = block.inner(popup).height;
+    self.scroll = self.scroll.min(self.max_scroll());
+
+    let paragraph = Paragraph::new(self.lines.clone()).block(block).scroll((self.scroll, 0));
+    f.render_widget(Clear, popup);
+    f.render_widget(paragraph, popup);
+  }
+}
+
+/// Color a unified diff the usual way: additions green, removals red, hunk headers cyan
+pub fn diff_lines(diff: &[String]) -> Vec<Line<'static>> {
+  diff
+    .iter()
+    .map(|l| {
+      let style = if l.starts_with("+++") || l.starts_with("---") {
+        Style::default().add_modifier(Modifier::BOLD)
+      } else if l.starts_with('+') {
+        Style::default().fg(Color::Green)
+      } else if l.starts_with('-') {
+        Style::default().fg(Color::Red)
+      } else if l.starts_with("@@") {
+        Style::default().fg(Color::Cyan)
+      } else {
+        Style::default()
+      };
+      Line::from(Span::styled(l.clone(), style))
+    })
+    .collect()
+}
+
+/// A unit file and its drop-ins, each under a heading with its path like `systemctl cat` prints, colored as INI:
+/// section headers bold, setting names cyan, comments dim. Sections in drop-ins are marked with the file they're from,
+/// since the same section usually appears in several
+pub fn unit_file_lines(files: &[UnitFile]) -> Vec<Line<'static>> {
+  let dim = Style::default().add_modifier(Modifier::DIM);
+  let mut lines = vec![];
+  for file in files {
+    if !lines.is_empty() {
+      lines.push(Line::from(""));
+    }
+    let kind = if file.drop_in { "drop-in" } else { "unit file" };
+    lines.push(Line::from(vec![
+      Span::styled(format!("# {}", file.path), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+      Span::styled(format!("  ({kind})"), dim),
+    ]));
+    let file_name = file.path.rsplit('/').next().unwrap_or(&file.path).to_string();
+    for line in file.contents.lines() {
+      let trimmed = line.trim_start();
+      lines.push(if trimmed.starts_with('#') || trimmed.starts_with(';') {
+        Line::from(Span::styled(line.to_string(), dim))
+      } else if trimmed.starts_with('[') {
+        let mut spans = vec![Span::styled(line.to_string(), Style::default().add_modifier(Modifier::BOLD))];
+        if file.drop_in {
+          spans.push(Span::styled(format!("  ← {file_name}"), dim));
+        }
+        Line::from(spans)
+      } else if let Some((key, value)) = line.split_once('=') {
+        Line::from(vec![
+          Span::styled(key.to_string(), Style::default().fg(Color::Cyan)),
+          Span::styled("=", dim),
+          Span::raw(value.to_string()),
+        ])
+      } else {
+        Line::from(line.to_string())
+      });
+    }
+  }
+  lines
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_unit_file_lines() {
+    let files = [
+      UnitFile { path: "/usr/lib/systemd/system/a.service".into(), drop_in: false, contents: "[Service]\n".into() },
+      UnitFile {
+        path: "/etc/systemd/system/a.service.d/override.conf".into(),
+        drop_in: true,
+        contents: "# tuned\n[Service]\nRestart=always\n".into(),
+      },
+    ];
+    let text = monochrome::plain_text(&unit_file_lines(&files));
+    assert_eq!(
+      text,
+      "# /usr/lib/systemd/system/a.service  (unit file)\n[Service]\n\n\
+       # /etc/systemd/system/a.service.d/override.conf  (drop-in)\n# tuned\n[Service]  ← override.conf\nRestart=always"
+    );
+  }
+}
@@ -0,0 +1,574 @@
+use std::{collections::HashMap, net::SocketAddr, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use tracing::info;
+
+use crate::{
+  keybindings::{self, Keymap},
+  pane_layout::Split,
+  preset,
+  saved_view::SavedView,
+  theme::{self, CustomThemes, Theme},
+  utils::get_config_dir,
+};
+
+/// User configuration, read from `config.toml` in the config directory. Every field is optional
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+  /// Screen-reader friendly rendering (also available as `--accessible`)
+  pub accessible: bool,
+  /// How to draw graphs like the log rate sparkline
+  pub graph_style: GraphStyle,
+  /// Colors for the whole UI, one of the built-in themes or one from `themes`. By default `dark` or `light` to suit
+  /// the terminal's background
+  pub theme: Option<String>,
+  /// Themes of your own, by name, see `theme`
+  pub themes: CustomThemes,
+  /// Colors for unit states, in place of the theme's
+  pub palette: Palette,
+  /// Which decimal separator numbers get, e.g. `de_DE`. From the environment (`LC_NUMERIC` or `LANG`) by default
+  pub locale: Option<String>,
+  /// Decimals shown for sizes and short durations, 1 by default
+  pub precision: Option<u8>,
+  /// Mark unit states and log priorities with a symbol as well as a color. Always on with the color-blind palette or
+  /// without color
+  pub state_symbols: bool,
+  /// Whether the unit list sits beside the details and logs or above them
+  pub split: Split,
+  /// Percent of the width (side by side) or height (stacked) the unit list takes, instead of fitting its columns
+  pub list_size: Option<u16>,
+  /// Extra columns in the unit list
+  pub list_columns: Vec<ListColumn>,
+  /// What the unit list is sorted by at launch. Left out, it's sorted the way it was when last closed
+  pub sort: Option<SortOrder>,
+  /// How log timestamps are shown at launch
+  pub log_timestamps: LogTimestamps,
+  /// Serve Prometheus metrics for the listed units on this address, e.g. `127.0.0.1:9558` (also `--metrics-address`)
+  pub metrics_address: Option<SocketAddr>,
+  /// Start with dry-run on, so actions show what they'd do instead of doing it (also `--dry-run`)
+  pub dry_run: bool,
+  /// Refuse everything that changes units or the machine, for shared or production sessions (also `--read-only`)
+  pub read_only: bool,
+  /// Show the `systemctl` or `journalctl` command equivalent to what the TUI is doing, below the unit list
+  pub command_echo: bool,
+  /// Accept requests from scripts on this Unix socket, see `control` (also `--control-socket`)
+  pub control_socket: Option<PathBuf>,
+  /// Days of logs worth keeping: the journal usage view shows how much of a unit's logs are older, and offers to
+  /// vacuum them
+  pub journal_retention_days: Option<u32>,
+  /// Where the log of actions taken is kept instead of the data directory, see `action_log`
+  pub action_log: Option<PathBuf>,
+  /// Units whose stop, restart, kill or mask asks first, by name or fnmatch-style pattern like `network*.service`
+  pub confirm_units: Vec<String>,
+  /// Moving down from the last unit (or up from the first) stays put rather than wrapping around to the other end
+  pub stop_at_ends: bool,
+  /// Leave the mouse to the terminal (e.g. for selecting text in tmux) instead of clicking and scrolling in the TUI
+  pub no_mouse: bool,
+  /// Units to jump to with the number keys, in order: the first is on 1. A unit running under both managers is
+  /// taken from the system one
+  pub pinned: Vec<String>,
+  /// What to show on launch, see `StartupView` (also `--view`)
+  pub startup_view: Option<StartupView>,
+  /// How the unit list starts out narrowed, from `--filter`, `--state` and `--type`
+  #[serde(skip)]
+  pub startup_filter: StartupFilter,
+  /// Save what's shown on exit (search, selected unit, scope, sort, scroll positions) and pick up there next launch
+  pub restore_session: bool,
+  /// The view saved by the last session, when `restore-session` is on and the command line doesn't ask for another
+  #[serde(skip)]
+  pub restored_view: Option<SavedView>,
+  /// External commands offered in the action menu, see `plugin`
+  pub plugins: Vec<Plugin>,
+  /// What to do when units fail while the TUI is open, see `failure_hooks`
+  pub failure_hooks: Vec<FailureHook>,
+  /// Checks of whether units actually work, shown in the health column, see `health`
+  pub health_checks: Vec<HealthCheck>,
+  /// Keys for the unit list, by command, see `keybindings`
+  pub keybindings: HashMap<keybindings::Command, keybindings::Keys>,
+}
+
+/// An external executable that reports on the selected unit
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Plugin {
+  /// Shown in the action menu and as its tab in the details pane
+  pub name: String,
+  /// Program and arguments; the unit name is passed as one more argument
+  pub command: Vec<String>,
+  /// It only looks, so it still runs in read-only sessions
+  #[serde(default)]
+  pub read_only: bool,
+}
+
+/// Notifying about, or running a command for, matching units that fail
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct FailureHook {
+  /// Units it's for, by name or fnmatch-style pattern. All of them by default
+  pub units: Vec<String>,
+  /// Pop up a desktop notification with notify-send. By default only when there's no command to run
+  pub notify: Option<bool>,
+  /// Program and arguments to run; the unit name is passed as one more argument
+  pub command: Vec<String>,
+}
+
+impl Default for FailureHook {
+  fn default() -> Self {
+    Self { units: vec!["*".into()], notify: None, command: vec![] }
+  }
+}
+
+impl FailureHook {
+  /// Whether to notify when the command is, or isn't, going to run
+  pub fn notifies(&self, run_command: bool) -> bool {
+    self.notify.unwrap_or(self.command.is_empty() || !run_command)
+  }
+}
+
+/// Fetching a URL or running a command now and then to see whether matching units work
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct HealthCheck {
+  /// Units it's for, by name or fnmatch-style pattern
+  pub units: Vec<String>,
+  /// Fetched with curl, which fails on an error status
+  pub url: Option<String>,
+  /// Program and arguments to run when there's no URL; the unit name is passed as one more argument
+  pub command: Vec<String>,
+  /// The exit code of a passing check, 0 by default
+  pub expect_exit: i32,
+  /// Seconds between checks, 30 by default
+  pub interval: u64,
+  /// Seconds a check gets before it counts as failed, 5 by default
+  pub timeout: u64,
+}
+
+impl Default for HealthCheck {
+  fn default() -> Self {
+    Self { units: vec![], url: None, command: vec![], expect_exit: 0, interval: 30, timeout: 5 }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ListColumn {
+  /// Bytes read and written by the current run (needs IOAccounting=)
+  Io,
+  /// Bytes received and sent by the current run (needs IPAccounting=)
+  Network,
+  /// Bytes read and written per second since the last refresh
+  IoRate,
+  /// Bytes received and sent per second since the last refresh
+  NetworkRate,
+  /// CPU time used by the current run
+  Cpu,
+  /// CPU use since the last refresh, as a percentage of one CPU
+  CpuPercent,
+  /// Memory in use, page cache included
+  Memory,
+  /// Processes and threads
+  Tasks,
+  /// Whether the unit starts on its own: enabled, disabled, static, masked and so on
+  FileState,
+  /// Time since the unit became active
+  Uptime,
+  /// When a timer fires next
+  NextElapse,
+  /// When a timer last fired
+  LastTrigger,
+  /// Whether the unit's health checks pass. Shown whenever there are any
+  Health,
+  /// Whether it's a system or a user unit, for telling them apart when both are shown
+  Scope,
+  /// Time since the unit last started, stopped, failed and so on
+  StateChanged,
+}
+
+/// What the unit list is sorted by. The sort key cycles through these in order
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortOrder {
+  #[default]
+  Name,
+  /// Failed first, then starting and stopping, then active, then inactive
+  ActiveState,
+  /// Not found and broken first, then masked, then loaded
+  LoadState,
+  Memory,
+  CpuPercent,
+  Tasks,
+  /// Busiest disk I/O since the last refresh first
+  Io,
+  /// Busiest network traffic since the last refresh first
+  Network,
+  /// Whichever changed state last first, for finding the unit that just flapped
+  StateChanged,
+}
+
+impl SortOrder {
+  const ALL: &[SortOrder] = &[
+    SortOrder::Name,
+    SortOrder::ActiveState,
+    SortOrder::LoadState,
+    SortOrder::Memory,
+    SortOrder::CpuPercent,
+    SortOrder::Tasks,
+    SortOrder::Io,
+    SortOrder::Network,
+    SortOrder::StateChanged,
+  ];
+
+  pub fn next(self) -> Self {
+    let index = Self::ALL.iter().position(|s| *s == self).unwrap_or(0);
+    Self::ALL[(index + 1) % Self::ALL.len()]
+  }
+
+  /// The list column showing what's sorted by, if there is one
+  pub fn column(self) -> Option<ListColumn> {
+    match self {
+      SortOrder::Memory => Some(ListColumn::Memory),
+      SortOrder::CpuPercent => Some(ListColumn::CpuPercent),
+      SortOrder::Tasks => Some(ListColumn::Tasks),
+      SortOrder::Io => Some(ListColumn::IoRate),
+      SortOrder::Network => Some(ListColumn::NetworkRate),
+      SortOrder::StateChanged => Some(ListColumn::StateChanged),
+      SortOrder::Name | SortOrder::ActiveState | SortOrder::LoadState => None,
+    }
+  }
+
+  pub fn from_label(label: &str) -> Option<Self> {
+    Self::ALL.iter().copied().find(|s| s.label() == label)
+  }
+
+  /// `memory`, for the unit list title
+  pub fn label(self) -> &'static str {
+    match self {
+      SortOrder::Name => "name",
+      SortOrder::ActiveState => "state",
+      SortOrder::LoadState => "load state",
+      SortOrder::Memory => "memory",
+      SortOrder::CpuPercent => "CPU",
+      SortOrder::Tasks => "tasks",
+      SortOrder::Io => "I/O",
+      SortOrder::Network => "network",
+      SortOrder::StateChanged => "last change",
+    }
+  }
+}
+
+/// How log timestamps are shown. The timestamp key cycles through these in order
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogTimestamps {
+  /// `2025-04-26 06:04`, in the time zone the journal wrote them in
+  #[default]
+  Short,
+  /// `2m 5s ago`
+  Relative,
+  /// `2025-04-26T15:04:45+02:00`, in this machine's time zone
+  Iso,
+  /// `2025-04-26T13:04:45Z`
+  Utc,
+  Hidden,
+}
+
+impl LogTimestamps {
+  const ALL: &[LogTimestamps] =
+    &[LogTimestamps::Short, LogTimestamps::Relative, LogTimestamps::Iso, LogTimestamps::Utc, LogTimestamps::Hidden];
+
+  pub fn next(self) -> Self {
+    let index = Self::ALL.iter().position(|t| *t == self).unwrap_or(0);
+    Self::ALL[(index + 1) % Self::ALL.len()]
+  }
+
+  /// `relative times`, for the log pane title
+  pub fn label(self) -> &'static str {
+    match self {
+      LogTimestamps::Short => "short times",
+      LogTimestamps::Relative => "relative times",
+      LogTimestamps::Iso => "ISO 8601 times",
+      LogTimestamps::Utc => "UTC times",
+      LogTimestamps::Hidden => "no times",
+    }
+  }
+}
+
+/// The screen to start on, for monitors that should boot straight into the view that matters. Written as `list`,
+/// `failed`, `top`, `search:<query>` or `logs:<unit>`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum StartupView {
+  /// The unit list, as usual
+  List,
+  /// Only the units that have failed
+  Failed,
+  /// Units by CPU use, like systemd-cgtop
+  Top,
+  /// The list searched for this, property queries included
+  Search(String),
+  /// This unit's logs, `.service` assumed without a suffix
+  Logs(String),
+}
+
+impl std::str::FromStr for StartupView {
+  type Err = anyhow::Error;
+
+  fn from_str(view: &str) -> Result<Self> {
+    Ok(match view.split_once(':') {
+      Some(("search", query)) => StartupView::Search(query.to_string()),
+      Some(("logs", unit)) if unit.contains('.') => StartupView::Logs(unit.to_string()),
+      Some(("logs", unit)) if !unit.is_empty() => StartupView::Logs(format!("{unit}.service")),
+      _ => match view {
+        "list" => StartupView::List,
+        "failed" => StartupView::Failed,
+        "top" => StartupView::Top,
+        _ => bail!("Unknown view `{view}`: use list, failed, top, search:<query> or logs:<unit>"),
+      },
+    })
+  }
+}
+
+impl TryFrom<String> for StartupView {
+  type Error = anyhow::Error;
+
+  fn try_from(view: String) -> Result<Self> {
+    view.parse()
+  }
+}
+
+/// Narrowing the unit list from the command line. The name and state filters show in the list title until cleared
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StartupFilter {
+  /// Only units whose names match, see `search::name_matches`
+  pub pattern: Option<String>,
+  /// Only units in one of these load, active or sub states, like `systemctl --state`
+  pub states: Vec<String>,
+  /// Only units of this type
+  pub unit_type: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Palette {
+  /// Green for active, red for failed, yellow for not found
+  #[default]
+  Default,
+  /// Blue for active, orange for failed, yellow for not found; readable with red-green color blindness
+  ColorBlind,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GraphStyle {
+  /// Pick the best style the terminal can probably display
+  #[default]
+  Auto,
+  /// 2 samples per cell, 4 levels each
+  Braille,
+  /// 1 sample per cell, 8 levels
+  Block,
+  /// For terminals without Unicode support
+  Ascii,
+}
+
+impl GraphStyle {
+  /// Resolve `Auto` into a concrete style based on the locale and terminal
+  pub fn resolve(self) -> Self {
+    if self != GraphStyle::Auto {
+      return self;
+    }
+
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"].iter().find_map(|v| std::env::var(v).ok().filter(|s| !s.is_empty()));
+    let utf8 = locale.is_some_and(|l| {
+      let l = l.to_lowercase();
+      l.contains("utf-8") || l.contains("utf8")
+    });
+    // the Linux virtual console can do block elements but has no braille glyphs
+    let linux_console = std::env::var("TERM").is_ok_and(|t| t == "linux");
+
+    match (utf8, linux_console) {
+      (true, false) => GraphStyle::Braille,
+      (true, true) => GraphStyle::Block,
+      (false, _) => GraphStyle::Ascii,
+    }
+  }
+}
+
+pub fn config_file_path() -> Result<PathBuf> {
+  Ok(get_config_dir()?.join("config.toml"))
+}
+
+impl Config {
+  pub fn show_state_symbols(&self) -> bool {
+    self.state_symbols || self.palette == Palette::ColorBlind
+  }
+
+  /// The `confirm-units` pattern `unit` matches, if it's one to ask about before stopping it
+  pub fn confirm_pattern(&self, unit: &str) -> Option<&str> {
+    self.confirm_units.iter().find(|p| preset::glob_match(p, unit)).map(String::as_str)
+  }
+
+  /// The `failure-hooks` to fire when `unit` fails
+  pub fn failure_hooks_for<'a>(&'a self, unit: &'a str) -> impl Iterator<Item = &'a FailureHook> {
+    self.failure_hooks.iter().filter(move |h| h.units.iter().any(|p| preset::glob_match(p, unit)))
+  }
+
+  /// The `health-checks` covering `unit`, with their index
+  pub fn health_checks_for<'a>(&'a self, unit: &'a str) -> impl Iterator<Item = (usize, &'a HealthCheck)> {
+    self.health_checks.iter().enumerate().filter(move |(_, c)| c.units.iter().any(|p| preset::glob_match(p, unit)))
+  }
+
+  /// The configured theme, or the one suiting the terminal
+  pub fn theme(&self) -> Result<Theme> {
+    let theme = match &self.theme {
+      Some(name) => theme::resolve(name, &self.themes)?,
+      None => Theme::detect(),
+    };
+    Ok(theme.with_palette(self.palette))
+  }
+
+  /// Carry over what's only read at launch or can be given on the command line, for a config that's been reloaded
+  pub fn keep_launch_settings(&mut self, launched: &Config) {
+    self.accessible = launched.accessible;
+    self.dry_run = launched.dry_run;
+    self.read_only = launched.read_only;
+    self.metrics_address = launched.metrics_address;
+    self.control_socket = launched.control_socket.clone();
+    self.startup_view = launched.startup_view.clone();
+    self.startup_filter = launched.startup_filter.clone();
+    self.restored_view = launched.restored_view.clone();
+  }
+
+  /// Load the config file, falling back to defaults if it doesn't exist
+  pub fn load() -> Result<Self> {
+    let path = config_file_path()?;
+    if !path.exists() {
+      return Ok(Self::default());
+    }
+
+    let contents = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let config: Self = toml::from_str(&contents).with_context(|| format!("Failed to parse {}", path.display()))?;
+    Keymap::new(&config.keybindings).with_context(|| format!("Failed to parse {}", path.display()))?;
+    if let Some(check) = config.health_checks.iter().find(|c| c.url.is_none() && c.command.is_empty()) {
+      bail!(
+        "Failed to parse {}: the health check for {} needs a url or a command",
+        path.display(),
+        check.units.join(", ")
+      );
+    }
+    for name in config.theme.iter().chain(config.themes.keys()) {
+      theme::resolve(name, &config.themes).with_context(|| format!("Failed to parse {}", path.display()))?;
+    }
+    info!("Loaded config from {}", path.display());
+    Ok(config)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_config() {
+    let config: Config =
+      toml::from_str("graph-style = \"ascii\"\nlist-columns = [\"io\", \"network\", \"cpu\"]").unwrap();
+    assert_eq!(config.graph_style, GraphStyle::Ascii);
+    assert_eq!(config.list_columns, vec![ListColumn::Io, ListColumn::Network, ListColumn::Cpu]);
+    assert_eq!(config.metrics_address, None);
+    assert!(!config.accessible);
+    assert_eq!(config.startup_view, None);
+
+    let config: Config = toml::from_str("startup-view = \"logs:nginx\"").unwrap();
+    assert_eq!(config.startup_view, Some(StartupView::Logs("nginx.service".into())));
+    assert_eq!("search:Restart=always".parse::<StartupView>().unwrap(), StartupView::Search("Restart=always".into()));
+    assert_eq!("top".parse::<StartupView>().unwrap(), StartupView::Top);
+    assert!("logs:".parse::<StartupView>().is_err());
+    assert!(toml::from_str::<Config>("startup-view = \"dashboard\"").is_err());
+  }
+
+  #[test]
+  fn test_sort_order() {
+    let config: Config = toml::from_str("sort = \"state-changed\"").unwrap();
+    assert_eq!(config.sort, Some(SortOrder::StateChanged));
+    assert_eq!(SortOrder::StateChanged.column(), Some(ListColumn::StateChanged));
+    // and around again
+    assert_eq!(SortOrder::StateChanged.next(), SortOrder::Name);
+    assert_eq!(SortOrder::Name.next(), SortOrder::ActiveState);
+  }
+
+  #[test]
+  fn test_empty_config_is_default() {
+    let config: Config = toml::from_str("").unwrap();
+    assert_eq!(config.graph_style, GraphStyle::Auto);
+    assert!(!config.show_state_symbols());
+  }
+
+  #[test]
+  fn test_parse_plugins() {
+    let config: Config = toml::from_str(
+      "[[plugins]]\nname = \"Health\"\ncommand = [\"/usr/local/bin/health\", \"--brief\"]\nread-only = true",
+    )
+    .unwrap();
+    assert_eq!(
+      config.plugins,
+      vec![Plugin {
+        name: "Health".into(),
+        command: vec!["/usr/local/bin/health".into(), "--brief".into()],
+        read_only: true
+      }]
+    );
+  }
+
+  #[test]
+  fn test_parse_palette() {
+    let config: Config = toml::from_str("palette = \"color-blind\"").unwrap();
+    assert_eq!(config.palette, Palette::ColorBlind);
+    assert!(config.show_state_symbols());
+  }
+
+  #[test]
+  fn test_parse_theme() {
+    let config: Config =
+      toml::from_str("theme = \"paper\"\n\n[themes.paper]\nbase = \"light\"\nactive = \"#2aa198\"").unwrap();
+    assert_eq!(config.theme().unwrap().active, ratatui::style::Color::Rgb(0x2a, 0xa1, 0x98));
+  }
+
+  #[test]
+  fn test_parse_formatting() {
+    let config: Config = toml::from_str("locale = \"fr_FR.UTF-8\"\nprecision = 2").unwrap();
+    assert_eq!((config.locale.as_deref(), config.precision), (Some("fr_FR.UTF-8"), Some(2)));
+  }
+
+  #[test]
+  fn test_confirm_pattern() {
+    let config: Config = toml::from_str("confirm-units = [\"sshd.service\", \"network*.service\"]").unwrap();
+    assert_eq!(config.confirm_pattern("sshd.service"), Some("sshd.service"));
+    assert_eq!(config.confirm_pattern("networkd-dispatcher.service"), Some("network*.service"));
+    assert_eq!(config.confirm_pattern("nginx.service"), None);
+  }
+
+  #[test]
+  fn test_failure_hooks_for() {
+    let config: Config = toml::from_str(
+      "[[failure-hooks]]\n\n[[failure-hooks]]\nunits = [\"postgresql*\"]\ncommand = [\"page\", \"--db\"]\n\n\
+       [[failure-hooks]]\nunits = [\"nginx*\"]\nnotify = false",
+    )
+    .unwrap();
+    assert_eq!(config.failure_hooks_for("nginx.service").count(), 2);
+    let hooks = config.failure_hooks_for("postgresql@16.service").collect::<Vec<_>>();
+    assert_eq!(hooks.len(), 2);
+    assert!(hooks[0].notifies(true) && hooks[0].command.is_empty());
+    // a command replaces the notification, unless it's not going to run
+    assert!(!hooks[1].notifies(true) && hooks[1].notifies(false));
+    assert_eq!(hooks[1].command, ["page", "--db"]);
+    assert!(!config.failure_hooks[2].notifies(false));
+  }
+
+  #[test]
+  fn test_parse_metrics_address() {
+    let config: Config = toml::from_str("metrics-address = \"127.0.0.1:9558\"").unwrap();
+    assert_eq!(config.metrics_address, Some("127.0.0.1:9558".parse().unwrap()));
+  }
+}
@@ -0,0 +1,233 @@
+// An opt-in Unix socket that lets scripts drive a running TUI, e.g. an alerting tool jumping straight to the unit that
+// paged. Requests are one line each and every response ends with a line that's either `ok` or `error: <reason>`:
+//
+//   select <unit>    select the unit in the list, clearing the search if it hides the unit
+//   search <text>    search the list as if <text> was typed
+//   view <name>      open a view: list, presets, ordering, boot, slices or delta
+//   command <name>   do what a key in the unit list does, by its name in `[keybindings]`, e.g. `command restart`
+//   refresh          reload the unit list
+//   reload-config    read the config file again, e.g. after changing keybindings or the theme
+//   selected         the selected unit's name
+//   status           one tab-separated line per unit: name, scope, load state, active state, sub state
+//
+// `systemctl-tui --send '<request>'` sends one and prints the answer, for tmux bindings and editor plugins that
+// would rather not bother with socat.
+
+use std::{
+  io::{BufRead, Write},
+  path::{Path, PathBuf},
+  sync::Arc,
+};
+
+use anyhow::{bail, Context, Result};
+use tokio::{
+  io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+  net::{UnixListener, UnixStream},
+  sync::{mpsc::UnboundedSender, Mutex},
+};
+use tracing::{info, warn};
+
+use crate::{
+  action::Action,
+  components::home::{Home, Mode},
+  keybindings::Command,
+  systemd::UnitScope,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request {
+  Select(String),
+  Search(String),
+  View(String),
+  Command(Command),
+  Refresh,
+  ReloadConfig,
+  Selected,
+  Status,
+}
+
+pub fn parse_request(line: &str) -> Result<Request> {
+  let line = line.trim();
+  let (command, argument) = line.split_once(' ').map(|(c, a)| (c, a.trim())).unwrap_or((line, ""));
+  let needs_argument = |request: fn(String) -> Request| {
+    if argument.is_empty() {
+      bail!("`{command}` needs an argument");
+    }
+    Ok(request(argument.to_string()))
+  };
+  match command {
+    "select" => needs_argument(Request::Select),
+    "search" => Ok(Request::Search(argument.to_string())),
+    "view" => needs_argument(Request::View),
+    "command" if argument.is_empty() => bail!("`command` needs an argument"),
+    "command" => Ok(Request::Command(Command::try_from(argument.to_string()).map_err(anyhow::Error::msg)?)),
+    "refresh" => Ok(Request::Refresh),
+    "reload-config" => Ok(Request::ReloadConfig),
+    "selected" => Ok(Request::Selected),
+    "status" => Ok(Request::Status),
+    "" => bail!("Empty request"),
+    other => bail!("Unknown request `{other}`"),
+  }
+}
+
+/// The action that opens a view, by the name `view` requests use
+fn view_action(name: &str) -> Option<Action> {
+  Some(match name {
+    "list" => Action::EnterMode(Mode::ServiceList),
+    "presets" => Action::ShowPresets,
+    "ordering" => Action::ShowOrderingCycles,
+    "boot" => Action::ShowBootPlan,
+    "boots" => Action::ShowBootFailures,
+    "slices" => Action::ShowSliceTree,
+    "delta" => Action::ShowDeltaAudit,
+    _ => return None,
+  })
+}
+
+/// The socket being listened on, for removing it however we exit
+static LISTENING: std::sync::Mutex<Option<PathBuf>> = std::sync::Mutex::new(None);
+
+/// Remove the socket being listened on, if there is one. Quitting, returning an error, panicking and SIGTERM all do
+pub fn remove_socket() {
+  if let Some(path) = LISTENING.lock().ok().and_then(|mut listening| listening.take()) {
+    let _ = std::fs::remove_file(path);
+  }
+}
+
+/// Listen on `path`, then answer requests in the background. A socket left behind by an instance that's gone is
+/// replaced, one that's still being listened on isn't
+pub async fn serve(path: &Path, home: Arc<Mutex<Home>>, action_tx: UnboundedSender<Action>) -> Result<()> {
+  if path.exists() {
+    if std::os::unix::net::UnixStream::connect(path).is_ok() {
+      bail!("Another instance is already listening on {}", path.display());
+    }
+    std::fs::remove_file(path).with_context(|| format!("Failed to remove stale socket {}", path.display()))?;
+  }
+  // it drives a session that may be running as root, so keep it to the user that started us. The umask makes it so
+  // from the start, instead of leaving a moment between creating it and narrowing it when anyone could connect
+  let umask = unsafe { libc::umask(0o177) };
+  let bound = UnixListener::bind(path);
+  unsafe { libc::umask(umask) };
+  let listener = bound.with_context(|| format!("Failed to listen on {}", path.display()))?;
+  *LISTENING.lock().unwrap() = Some(path.to_path_buf());
+  info!("Listening for control requests on {}", path.display());
+
+  tokio::spawn(async move {
+    loop {
+      let stream = match listener.accept().await {
+        Ok((stream, _)) => stream,
+        Err(e) => {
+          warn!("Error accepting a control connection: {}", e);
+          continue;
+        },
+      };
+      let (home, action_tx) = (home.clone(), action_tx.clone());
+      tokio::spawn(async move {
+        if let Err(e) = handle_connection(stream, &home, &action_tx).await {
+          warn!("Error answering control requests: {}", e);
+        }
+      });
+    }
+  });
+  Ok(())
+}
+
+/// Send `request` to the instance listening on `path`, and return what it answered before `ok`
+pub fn send(path: &Path, request: &str) -> Result<String> {
+  let mut stream = std::os::unix::net::UnixStream::connect(path)
+    .with_context(|| format!("Nothing is listening on {}; is systemctl-tui running with it?", path.display()))?;
+  writeln!(stream, "{}", request.trim())?;
+  let mut output = String::new();
+  for line in std::io::BufReader::new(stream).lines() {
+    let line = line?;
+    if line == "ok" {
+      return Ok(output);
+    }
+    if let Some(error) = line.strip_prefix("error: ") {
+      bail!("{error}");
+    }
+    output.push_str(&line);
+    output.push('\n');
+  }
+  bail!("The connection closed before an answer")
+}
+
+async fn handle_connection(stream: UnixStream, home: &Mutex<Home>, action_tx: &UnboundedSender<Action>) -> Result<()> {
+  let (reader, mut writer) = stream.into_split();
+  let mut lines = BufReader::new(reader).lines();
+  while let Some(line) = lines.next_line().await? {
+    let response = match parse_request(&line) {
+      Ok(request) => answer(request, home, action_tx).await,
+      Err(e) => Err(e),
+    };
+    let response = match response {
+      Ok(output) => output.into_iter().chain(["ok".to_string()]).map(|l| l + "\n").collect::<String>(),
+      Err(e) => format!("error: {e}\n"),
+    };
+    writer.write_all(response.as_bytes()).await?;
+  }
+  Ok(())
+}
+
+async fn answer(request: Request, home: &Mutex<Home>, action_tx: &UnboundedSender<Action>) -> Result<Vec<String>> {
+  let mut output = vec![];
+  match request {
+    Request::Select(name) => {
+      let home = home.lock().await;
+      // prefer the system unit when both managers have one by this name
+      let Some(unit) =
+        home.all_units.keys().filter(|id| id.name == name).min_by_key(|id| id.scope != UnitScope::Global).cloned()
+      else {
+        bail!("No unit named {name}");
+      };
+      action_tx.send(Action::SelectUnit(unit))?;
+    },
+    Request::Search(text) => action_tx.send(Action::SetSearch(text))?,
+    Request::View(name) => {
+      let Some(action) = view_action(&name) else { bail!("Unknown view `{name}`") };
+      action_tx.send(action)?;
+    },
+    Request::Command(command) => {
+      for action in home.lock().await.run_command(command) {
+        action_tx.send(action)?;
+      }
+    },
+    Request::Refresh => action_tx.send(Action::RefreshServices)?,
+    Request::ReloadConfig => {
+      home.lock().await.reload_config()?;
+      action_tx.send(Action::Render)?;
+    },
+    Request::Selected => output.extend(home.lock().await.selected_service().map(|u| u.name)),
+    Request::Status => {
+      for unit in home.lock().await.all_units.values() {
+        let scope = match unit.scope {
+          UnitScope::Global => "system",
+          UnitScope::User => "user",
+        };
+        output
+          .push(format!("{}\t{scope}\t{}\t{}\t{}", unit.name, unit.load_state, unit.activation_state, unit.sub_state));
+      }
+    },
+  }
+  Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_request() {
+    assert_eq!(parse_request("select nginx.service\n").unwrap(), Request::Select("nginx.service".into()));
+    assert_eq!(parse_request("search ").unwrap(), Request::Search("".into()));
+    assert_eq!(parse_request("status").unwrap(), Request::Status);
+    assert!(parse_request("select").is_err());
+    assert!(parse_request("restart nginx.service").is_err());
+    assert_eq!(parse_request("command focus-logs").unwrap(), Request::Command(Command::FocusLogs));
+    assert!(parse_request("command").is_err());
+    assert!(parse_request("command fly").is_err());
+    assert_eq!(parse_request("reload-config").unwrap(), Request::ReloadConfig);
+    assert!(view_action("slices").is_some());
+    assert!(view_action("kernel").is_none());
+  }
+}
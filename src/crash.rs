@@ -0,0 +1,125 @@
+// Evidence of how a unit's processes died, for units whose last run ended in an OOM kill, a core dump or a signal:
+// the kernel's (and systemd-oomd's) OOM kills in its cgroup, and the core dumps systemd-coredump caught. Both come from
+// the journal. Kernel lines name the unit by its cgroup path; core dumps by `COREDUMP_UNIT=` (`COREDUMP_USER_UNIT=` for
+// user units), with systemd-coredump's message carrying the stack trace.
+
+use std::collections::HashMap;
+
+use crate::systemd::UnitScope;
+
+/// Core dumps listed, newest first
+pub const MAX_CORE_DUMPS: usize = 5;
+
+/// Whether a unit's `Result=` is worth looking for evidence of
+pub fn is_crash(result: &str) -> bool {
+  matches!(result, "oom-kill" | "core-dump" | "signal")
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Evidence {
+  /// Lines about the unit's processes being killed for memory this boot, oldest first
+  pub oom_kills: Vec<String>,
+  /// Newest first
+  pub core_dumps: Vec<CoreDump>,
+}
+
+impl Evidence {
+  pub fn is_empty(&self) -> bool {
+    self.oom_kills.is_empty() && self.core_dumps.is_empty()
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreDump {
+  pub pid: String,
+  /// `SIGSEGV`, or the number when there's no name
+  pub signal: String,
+  pub exe: String,
+  /// In seconds since the epoch
+  pub at: i64,
+  /// systemd-coredump's message, the stack trace included when it could make one
+  pub message: String,
+}
+
+impl CoreDump {
+  /// From a systemd-coredump journal entry
+  pub fn from_fields(fields: &HashMap<String, String>) -> Option<Self> {
+    let pid = fields.get("COREDUMP_PID")?.clone();
+    let signal = fields.get("COREDUMP_SIGNAL_NAME").or(fields.get("COREDUMP_SIGNAL")).cloned().unwrap_or_default();
+    let exe = fields.get("COREDUMP_EXE").or(fields.get("COREDUMP_COMM")).cloned().unwrap_or_default();
+    let micros = fields.get("COREDUMP_TIMESTAMP").or(fields.get("__REALTIME_TIMESTAMP"));
+    let at = micros.and_then(|us| us.parse::<i64>().ok()).unwrap_or_default() / 1_000_000;
+    let message = fields.get("MESSAGE").cloned().unwrap_or_default();
+    Some(Self { pid, signal, exe, at, message })
+  }
+}
+
+/// The journal field core dumps of the unit are matched on
+pub fn unit_field(scope: UnitScope) -> &'static str {
+  match scope {
+    UnitScope::Global => "COREDUMP_UNIT",
+    UnitScope::User => "COREDUMP_USER_UNIT",
+  }
+}
+
+/// The kernel's and systemd-oomd's lines (in journalctl's `short-iso` form) about killing `unit`'s processes: the
+/// `oom-kill:` summary naming its cgroup, the `Killed process` line for the same PID after it, and oomd's kills
+pub fn oom_kills(lines: &[String], unit: &str) -> Vec<String> {
+  let in_unit = |cgroup: &str| cgroup.ends_with(&format!("/{unit}"));
+  let field = |line: &str, name: &str| {
+    let start = line.find(&format!("{name}="))? + name.len() + 1;
+    line[start..].split(',').next().map(str::to_string)
+  };
+  let mut pids = vec![];
+  let mut kills = vec![];
+  for line in lines {
+    if line.contains("oom-kill:") {
+      if field(line, "task_memcg").is_some_and(|c| in_unit(&c)) {
+        pids.extend(field(line, "pid"));
+        kills.push(line.clone());
+      }
+    } else if let Some(pid) = line.split_once("Killed process ").and_then(|(_, rest)| rest.split(' ').next()) {
+      if pids.iter().any(|p| p == pid) {
+        kills.push(line.clone());
+      }
+    } else if line.contains("systemd-oomd") && line.split_whitespace().any(in_unit) {
+      kills.push(line.clone());
+    }
+  }
+  kills
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_oom_kills() {
+    let lines = [
+      "2025-04-26T06:04:44-07:00 host kernel: app invoked oom-killer: gfp_mask=0xcc0(GFP_KERNEL), order=0",
+      "2025-04-26T06:04:44-07:00 host kernel: oom-kill:constraint=CONSTRAINT_MEMCG,nodemask=(null),cpuset=/,mems_allowed=0,oom_memcg=/system.slice/app.service,task_memcg=/system.slice/app.service,task=app,pid=812,uid=0",
+      "2025-04-26T06:04:44-07:00 host kernel: Memory cgroup out of memory: Killed process 812 (app) total-vm:1048576kB, anon-rss:524288kB",
+      "2025-04-26T06:05:10-07:00 host kernel: oom-kill:constraint=CONSTRAINT_NONE,task_memcg=/system.slice/other-app.service,task=other,pid=900,uid=0",
+      "2025-04-26T06:05:10-07:00 host kernel: Out of memory: Killed process 900 (other) total-vm:2048kB",
+      "2025-04-26T06:06:00-07:00 host systemd-oomd[401]: Killed /system.slice/app.service due to memory pressure for /system.slice being 81.2% > 80.0% for > 20s with reclaim activity",
+    ]
+    .map(String::from);
+    let kills = oom_kills(&lines, "app.service");
+    assert_eq!(kills.len(), 3);
+    assert!(kills[1].contains("Killed process 812"));
+    assert!(kills[2].contains("systemd-oomd"));
+    assert!(oom_kills(&lines, "db.service").is_empty());
+
+    let fields = [
+      ("COREDUMP_PID", "812"),
+      ("COREDUMP_SIGNAL_NAME", "SIGSEGV"),
+      ("COREDUMP_EXE", "/usr/bin/app"),
+      ("COREDUMP_TIMESTAMP", "1745672684000000"),
+      ("MESSAGE", "Process 812 (app) of user 0 dumped core.\n\nStack trace of thread 812:\n#0  0x0000 main (app)"),
+    ];
+    let dump = CoreDump::from_fields(&fields.map(|(k, v)| (k.to_string(), v.to_string())).into()).unwrap();
+    assert_eq!((dump.pid.as_str(), dump.signal.as_str(), dump.at), ("812", "SIGSEGV", 1745672684));
+    assert!(dump.message.contains("Stack trace"));
+    assert_eq!(CoreDump::from_fields(&HashMap::new()), None);
+  }
+}
@@ -0,0 +1,340 @@
+// Translating crontab entries into OnCalendar expressions, for migrating cron jobs to timers.
+
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+
+use crate::{scaffold::NewTimer, systemd::UnitScope};
+
+/// One schedule line from a crontab
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronJob {
+  /// The line as written, for showing next to the translation
+  pub line: String,
+  /// Only present in system crontabs (/etc/crontab, /etc/cron.d)
+  pub user: Option<String>,
+  pub command: String,
+  /// Variable assignments that preceded the job, as `NAME=value`
+  pub environment: Vec<String>,
+  /// Cron runs a job when either day-of-month or day-of-week matches, so this can take two expressions
+  pub on_calendar: Result<Vec<String>, String>,
+}
+
+/// Read a crontab from `source`: empty for the current user's crontab, a path to a crontab file, or a single crontab
+/// line. Returns the text and whether it's a system crontab with a user column
+pub fn load_crontab(source: &str) -> Result<(String, bool)> {
+  let source = source.trim();
+  if source.is_empty() {
+    let output = Command::new("crontab").arg("-l").output().context("Failed to run crontab -l")?;
+    if !output.status.success() {
+      bail!("crontab -l failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    return Ok((String::from_utf8_lossy(&output.stdout).to_string(), false));
+  }
+  if source.starts_with('/') {
+    let text = std::fs::read_to_string(source).with_context(|| format!("Failed to read {source}"))?;
+    let system = source == "/etc/crontab" || source.starts_with("/etc/cron.d/");
+    return Ok((text, system));
+  }
+  Ok((source.to_string(), false))
+}
+
+/// Parse a whole crontab (or a single line). `system` crontabs have a user column before the command
+pub fn parse_crontab(text: &str, system: bool) -> Vec<CronJob> {
+  let mut environment = vec![];
+  let mut jobs = vec![];
+  for line in text.lines().map(str::trim) {
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+    if is_assignment(line) {
+      let (name, value) = line.split_once('=').unwrap();
+      environment.push(format!("{}={}", name.trim(), value.trim().trim_matches(|c| c == '"' || c == '\'')));
+      continue;
+    }
+
+    let schedule_fields = if line.starts_with('@') { 1 } else { 5 };
+    let (mut fields, command) = split_fields(line, schedule_fields + system as usize);
+    let user = if system && fields.len() > schedule_fields { fields.pop().map(str::to_string) } else { None };
+    let schedule = fields;
+    let command = command.to_string();
+
+    let on_calendar = if schedule.len() < schedule_fields || command.is_empty() {
+      Err("not a complete crontab entry".to_string())
+    } else if command_uses_stdin(&command) {
+      Err("uses % to pass stdin, which has no timer equivalent".to_string())
+    } else {
+      to_on_calendar(&schedule).map_err(|e| e.to_string())
+    };
+    jobs.push(CronJob { line: line.to_string(), user, command, environment: environment.clone(), on_calendar });
+  }
+  jobs
+}
+
+/// The first `n` whitespace-separated fields, and the rest of the line as-is
+fn split_fields(line: &str, n: usize) -> (Vec<&str>, &str) {
+  let mut fields = vec![];
+  let mut rest = line.trim_start();
+  while fields.len() < n && !rest.is_empty() {
+    let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    fields.push(&rest[..end]);
+    rest = rest[end..].trim_start();
+  }
+  (fields, rest)
+}
+
+fn is_assignment(line: &str) -> bool {
+  match line.split_once('=') {
+    Some((name, _)) => {
+      let name = name.trim();
+      !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    },
+    None => false,
+  }
+}
+
+/// An unescaped `%` in a cron command ends the command and starts its stdin
+fn command_uses_stdin(command: &str) -> bool {
+  let mut escaped = false;
+  for c in command.chars() {
+    match c {
+      '\\' => escaped = !escaped,
+      '%' if !escaped => return true,
+      _ => escaped = false,
+    }
+  }
+  false
+}
+
+/// The command as an ExecStart= value. Cron hands the command to /bin/sh, and systemd expands `%` and `$` itself
+pub fn exec_start(command: &str) -> String {
+  let command = command.replace("\\%", "%");
+  let mut escaped = String::new();
+  for c in command.chars() {
+    match c {
+      '\\' => escaped.push_str("\\\\"),
+      '"' => escaped.push_str("\\\""),
+      '%' => escaped.push_str("%%"),
+      '$' => escaped.push_str("$$"),
+      c => escaped.push(c),
+    }
+  }
+  format!("/bin/sh -c \"{escaped}\"")
+}
+
+/// A unit name for the job, from the executable it runs
+pub fn job_name(command: &str) -> String {
+  let program = command.split_whitespace().next().unwrap_or("");
+  let program = program.rsplit('/').next().unwrap_or(program);
+  let program = program.split('.').next().unwrap_or(program);
+  let name: String = program.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_').collect();
+  if name.is_empty() {
+    "job".into()
+  } else {
+    name
+  }
+}
+
+/// A job's timer, or why it couldn't be converted
+pub type Translation = Result<NewTimer, String>;
+
+/// The timer each job translates to, named `{prefix}-{program}` and numbered when programs repeat
+pub fn to_timers(jobs: &[CronJob], prefix: &str, persistent: bool, scope: UnitScope) -> Vec<Translation> {
+  let mut names: Vec<String> = vec![];
+  jobs
+    .iter()
+    .map(|job| {
+      let on_calendar = job.on_calendar.clone()?;
+      let base = format!("{prefix}-{}", job_name(&job.command));
+      let mut name = base.clone();
+      let mut n = 1;
+      while names.contains(&name) {
+        n += 1;
+        name = format!("{base}-{n}");
+      }
+      names.push(name.clone());
+      // systemd expands specifiers in these too, and the cron line and variables are meant literally
+      Ok(NewTimer {
+        name,
+        description: format!("Converted from crontab: {}", job.line),
+        command: exec_start(&job.command),
+        user: job.user.clone().unwrap_or_default(),
+        environment: job.environment.iter().map(|v| v.replace('%', "%%")).collect(),
+        on_calendar,
+        persistent,
+        scope,
+      })
+    })
+    .collect()
+}
+
+pub fn to_on_calendar(schedule: &[&str]) -> Result<Vec<String>> {
+  if let [special] = schedule {
+    let expression = match *special {
+      "@yearly" | "@annually" => "yearly",
+      "@monthly" => "monthly",
+      "@weekly" => "weekly",
+      "@daily" | "@midnight" => "daily",
+      "@hourly" => "hourly",
+      "@reboot" => bail!("@reboot runs at boot, which is a job for WantedBy=multi-user.target rather than a timer"),
+      other => bail!("unknown schedule {other}"),
+    };
+    return Ok(vec![expression.to_string()]);
+  }
+  let [minute, hour, dom, month, dow] = schedule else { bail!("expected 5 schedule fields") };
+
+  let minute = field(minute, 0, 59, &[]).context("minute")?;
+  let hour = field(hour, 0, 23, &[]).context("hour")?;
+  let dom_restricted = *dom != "*";
+  let dow_restricted = *dow != "*";
+  let dom = field(dom, 1, 31, &[]).context("day of month")?;
+  let month = field(month, 1, 12, &MONTHS).context("month")?;
+  let dow = weekdays(dow).context("day of week")?;
+
+  let time = format!("{}:{}:00", hour.format(2), minute.format(2));
+  let date = |dom: &str| format!("*-{}-{dom}", month.format(2));
+  Ok(match (dom_restricted, dow_restricted) {
+    (true, true) => vec![format!("{} {time}", date(&dom.format(2))), format!("{dow} {} {time}", date("*"))],
+    (false, true) => vec![format!("{dow} {} {time}", date("*"))],
+    _ => vec![format!("{} {time}", date(&dom.format(2)))],
+  })
+}
+
+const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+enum Field {
+  Any,
+  /// `*/n`
+  Every(u32, u32),
+  Values(Vec<u32>),
+}
+
+impl Field {
+  fn format(&self, width: usize) -> String {
+    match self {
+      Field::Any => "*".into(),
+      Field::Every(start, step) => format!("{start:0width$}/{step}"),
+      Field::Values(values) => values.iter().map(|v| format!("{v:0width$}")).collect::<Vec<_>>().join(","),
+    }
+  }
+}
+
+/// Expand a cron field into explicit values; `names` maps e.g. `jan` to `min`
+fn field(spec: &str, min: u32, max: u32, names: &[&str]) -> Result<Field> {
+  if spec == "*" {
+    return Ok(Field::Any);
+  }
+  if let Some(step) = spec.strip_prefix("*/") {
+    match step.parse() {
+      Ok(step) if step > 0 => return Ok(Field::Every(min, step)),
+      _ => bail!("bad step in `{spec}`"),
+    }
+  }
+
+  let value = |s: &str| -> Result<u32> {
+    if let Some(i) = names.iter().position(|n| n.eq_ignore_ascii_case(s)) {
+      return Ok(min + i as u32);
+    }
+    let v: u32 = s.parse().with_context(|| format!("bad value `{s}`"))?;
+    if v < min || v > max {
+      bail!("{v} is out of range");
+    }
+    Ok(v)
+  };
+
+  let mut values = vec![];
+  for part in spec.split(',') {
+    let (range, step) = match part.split_once('/') {
+      Some((range, step)) => match step.parse::<usize>() {
+        Ok(step) if step > 0 => (range, step),
+        _ => bail!("bad step in `{part}`"),
+      },
+      None => (part, 1),
+    };
+    let (start, end) = match range {
+      "*" => (min, max),
+      r => match r.split_once('-') {
+        Some((a, b)) => (value(a)?, value(b)?),
+        None if step > 1 => (value(r)?, max),
+        None => (value(r)?, value(r)?),
+      },
+    };
+    values.extend((start..=end).step_by(step));
+  }
+  values.sort_unstable();
+  values.dedup();
+  Ok(Field::Values(values))
+}
+
+fn weekdays(spec: &str) -> Result<String> {
+  // cron accepts both 0 and 7 for Sunday
+  let days = match field(spec, 0, 7, &WEEKDAYS)? {
+    Field::Any => return Ok(String::new()),
+    Field::Every(start, step) => (start..=6).step_by(step as usize).collect(),
+    Field::Values(days) => days,
+  };
+  // OnCalendar weeks start on Monday
+  let mut days: Vec<u32> = days.into_iter().map(|d| (d + 6) % 7).collect();
+  days.sort_unstable();
+  days.dedup();
+  Ok(days.iter().map(|d| WEEKDAYS[(*d as usize + 1) % 7]).collect::<Vec<_>>().join(","))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn convert(schedule: &str) -> Vec<String> {
+    to_on_calendar(&schedule.split_whitespace().collect::<Vec<_>>()).unwrap()
+  }
+
+  #[test]
+  fn test_to_on_calendar() {
+    assert_eq!(convert("30 2 * * *"), vec!["*-*-* 02:30:00"]);
+    assert_eq!(convert("*/15 * * * *"), vec!["*-*-* *:00/15:00"]);
+    assert_eq!(convert("0 9-17/4 * * mon-fri"), vec!["Mon,Tue,Wed,Thu,Fri *-*-* 09,13,17:00:00"]);
+    assert_eq!(convert("0 0 1 jan,jul *"), vec!["*-01,07-01 00:00:00"]);
+    assert_eq!(convert("0 0 * * 0,7"), vec!["Sun *-*-* 00:00:00"]);
+    assert_eq!(convert("@weekly"), vec!["weekly"]);
+    // cron ORs day-of-month and day-of-week
+    assert_eq!(convert("0 12 1 * 5"), vec!["*-*-01 12:00:00", "Fri *-*-* 12:00:00"]);
+    assert!(to_on_calendar(&["61", "*", "*", "*", "*"]).is_err());
+    assert!(to_on_calendar(&["@reboot"]).is_err());
+  }
+
+  #[test]
+  fn test_parse_crontab() {
+    let crontab = "# m h dom mon dow user command
+MAILTO=root
+PATH=/usr/bin:/bin
+17 * * * * root cd / && run-parts --report /etc/cron.hourly
+0 0 * * * backup /usr/local/bin/backup.sh > /dev/null 2>&1
+5 4 * * * root echo hi%there
+";
+    let jobs = parse_crontab(crontab, true);
+    assert_eq!(jobs.len(), 3);
+    assert_eq!(jobs[0].user.as_deref(), Some("root"));
+    assert_eq!(jobs[0].command, "cd / && run-parts --report /etc/cron.hourly");
+    assert_eq!(jobs[0].environment, vec!["MAILTO=root", "PATH=/usr/bin:/bin"]);
+    assert_eq!(jobs[1].on_calendar, Ok(vec!["*-*-* 00:00:00".to_string()]));
+    assert_eq!(job_name(&jobs[1].command), "backup");
+    assert!(jobs[2].on_calendar.is_err());
+
+    let timers = to_timers(&[jobs[1].clone(), jobs[1].clone()], "cron", true, UnitScope::Global);
+    assert_eq!(timers[0].as_ref().unwrap().name, "cron-backup");
+    assert_eq!(timers[1].as_ref().unwrap().name, "cron-backup-2");
+    assert_eq!(timers[1].as_ref().unwrap().user, "backup");
+
+    let dated = parse_crontab("FORMAT=%F\n0 0 * * * date +\\%F\n", false);
+    let timer = to_timers(&dated, "cron", false, UnitScope::Global).remove(0).unwrap();
+    assert_eq!(timer.description, r"Converted from crontab: 0 0 * * * date +\%F");
+    assert!(timer.render_service().starts_with("[Unit]\nDescription=Converted from crontab: 0 0 * * * date +\\%%F\n"));
+    assert_eq!(timer.environment, vec!["FORMAT=%%F"]);
+  }
+
+  #[test]
+  fn test_exec_start() {
+    assert_eq!(exec_start(r#"echo "$HOME" \% done"#), r#"/bin/sh -c "echo \"$$HOME\" %% done""#);
+  }
+}
@@ -0,0 +1,175 @@
+// Exporting a unit's dependency graph as Graphviz DOT, or as SVG when Graphviz is installed, for architecture docs and
+// postmortems. Edges are styled by how strong the dependency is, the same way `systemd-analyze dot` does.
+
+use std::{
+  collections::{BTreeSet, HashMap, VecDeque},
+  fmt::Write as _,
+  io::Write as _,
+  path::Path,
+  process::{Command, Stdio},
+};
+
+use anyhow::{bail, Context, Result};
+
+/// Forward properties, each paired with the property that points the other way
+const DEPENDENCIES: &[(&str, &str)] = &[
+  ("Requires", "RequiredBy"),
+  ("Requisite", "RequisiteOf"),
+  ("BindsTo", "BoundBy"),
+  ("PartOf", "ConsistsOf"),
+  ("Upholds", "UpheldBy"),
+  ("Wants", "WantedBy"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+  /// What the unit pulls in
+  Dependencies,
+  /// What pulls the unit in
+  Dependents,
+}
+
+impl Direction {
+  pub fn label(self) -> &'static str {
+    match self {
+      Direction::Dependencies => "dependencies",
+      Direction::Dependents => "dependents",
+    }
+  }
+
+  /// The properties `collect` reads for each unit
+  pub fn properties(self) -> Vec<&'static str> {
+    DEPENDENCIES
+      .iter()
+      .map(|(forward, reverse)| if self == Direction::Dependencies { *forward } else { *reverse })
+      .collect()
+  }
+}
+
+/// "`from` Requires= `to`", always pointing from the depending unit to its dependency whichever way the graph was walked
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Edge {
+  pub from: String,
+  pub to: String,
+  /// The forward property, e.g. `Wants`
+  pub kind: &'static str,
+}
+
+/// Walk the graph from `root` up to `depth` hops, given each unit's `Direction::properties` keyed by unit name
+pub fn collect(
+  units: &HashMap<String, HashMap<String, String>>,
+  root: &str,
+  direction: Direction,
+  depth: usize,
+) -> Vec<Edge> {
+  let mut edges = BTreeSet::new();
+  let mut seen = BTreeSet::from([root.to_string()]);
+  let mut queue = VecDeque::from([(root.to_string(), 0)]);
+  while let Some((unit, hops)) = queue.pop_front() {
+    if hops >= depth {
+      continue;
+    }
+    let Some(properties) = units.get(&unit) else { continue };
+    for ((forward, _), property) in DEPENDENCIES.iter().zip(direction.properties()) {
+      for other in properties.get(property).map(|v| v.split_whitespace()).into_iter().flatten() {
+        let (from, to) = match direction {
+          Direction::Dependencies => (unit.clone(), other.to_string()),
+          Direction::Dependents => (other.to_string(), unit.clone()),
+        };
+        edges.insert(Edge { from, to, kind: forward });
+        if seen.insert(other.to_string()) {
+          queue.push_back((other.to_string(), hops + 1));
+        }
+      }
+    }
+  }
+  edges.into_iter().collect()
+}
+
+/// The graph as a DOT digraph, with `root` highlighted
+pub fn to_dot(root: &str, edges: &[Edge]) -> String {
+  let mut dot = String::from("digraph systemd {\n  rankdir=LR;\n  node [shape=box];\n");
+  let _ = writeln!(dot, "  {} [style=bold];", quote(root));
+  for edge in edges {
+    let style = match edge.kind {
+      "Requires" | "Requisite" | "BindsTo" => "color=black",
+      "PartOf" | "Upholds" => "color=blue",
+      _ => "color=grey66, style=dashed",
+    };
+    let _ = writeln!(dot, "  {} -> {} [{style}, tooltip={}];", quote(&edge.from), quote(&edge.to), quote(edge.kind));
+  }
+  dot.push_str("}\n");
+  dot
+}
+
+fn quote(id: &str) -> String {
+  format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Write `dot` to `path`, rendered through Graphviz when the file name ends in `.svg`
+pub fn write(dot: &str, path: &Path) -> Result<()> {
+  if path.extension().is_none_or(|e| e != "svg") {
+    return std::fs::write(path, dot).with_context(|| format!("Failed to write {}", path.display()));
+  }
+  let mut child = Command::new("dot")
+    .arg("-Tsvg")
+    .arg("-o")
+    .arg(path)
+    .stdin(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .context("Failed to run `dot`, is Graphviz installed?")?;
+  child.stdin.take().unwrap().write_all(dot.as_bytes())?;
+  let output = child.wait_with_output()?;
+  if !output.status.success() {
+    bail!("Graphviz failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn units(blocks: &[(&str, &[(&str, &str)])]) -> HashMap<String, HashMap<String, String>> {
+    blocks
+      .iter()
+      .map(|(id, props)| (id.to_string(), props.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()))
+      .collect()
+  }
+
+  #[test]
+  fn test_collect() {
+    let forward = units(&[
+      ("web.service", &[("Requires", "db.service"), ("Wants", "cache.service network.target")]),
+      ("db.service", &[("Requires", "network.target")]),
+      ("network.target", &[("Wants", "web.service")]),
+    ]);
+    let edge = |from: &str, to: &str, kind| Edge { from: from.into(), to: to.into(), kind };
+    assert_eq!(
+      collect(&forward, "web.service", Direction::Dependencies, 1),
+      vec![
+        edge("web.service", "cache.service", "Wants"),
+        edge("web.service", "db.service", "Requires"),
+        edge("web.service", "network.target", "Wants"),
+      ]
+    );
+    // the cycle back to web.service is drawn but not walked again
+    assert_eq!(collect(&forward, "web.service", Direction::Dependencies, 5).len(), 5);
+
+    let reverse = units(&[("db.service", &[("RequiredBy", "web.service")])]);
+    assert_eq!(
+      collect(&reverse, "db.service", Direction::Dependents, 3),
+      vec![edge("web.service", "db.service", "Requires")]
+    );
+  }
+
+  #[test]
+  fn test_to_dot() {
+    let edges = vec![Edge { from: "a.service".into(), to: "b\"c.service".into(), kind: "Wants" }];
+    let dot = to_dot("a.service", &edges);
+    assert!(dot.starts_with("digraph systemd {\n"));
+    assert!(dot.contains("  \"a.service\" [style=bold];\n"));
+    assert!(dot.contains("  \"a.service\" -> \"b\\\"c.service\" [color=grey66, style=dashed, tooltip=\"Wants\"];\n"));
+  }
+}
@@ -0,0 +1,92 @@
+// A unit's `Documentation=` URIs, and opening them: man and info pages in the terminal, everything else with
+// xdg-open.
+
+use std::process::{Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Link {
+  /// `man:systemd.service(5)`
+  Man { page: String, section: Option<String> },
+  /// `info:coreutils`
+  Info(String),
+  /// `https:`, `http:` and `file:` URIs
+  External(String),
+}
+
+impl Link {
+  pub fn parse(uri: &str) -> Option<Link> {
+    let (scheme, rest) = uri.split_once(':')?;
+    match scheme {
+      "man" => {
+        let (page, section) = match rest.strip_suffix(')').and_then(|r| r.split_once('(')) {
+          Some((page, section)) => (page, Some(section.to_string())),
+          None => (rest, None),
+        };
+        (!page.is_empty()).then(|| Link::Man { page: page.to_string(), section })
+      },
+      "info" if !rest.is_empty() => Some(Link::Info(rest.to_string())),
+      "http" | "https" | "file" => Some(Link::External(uri.to_string())),
+      _ => None,
+    }
+  }
+
+  /// Whether it's read in the terminal, which has to be handed over while it's open
+  pub fn in_terminal(&self) -> bool {
+    !matches!(self, Link::External(_))
+  }
+
+  pub fn command(&self) -> Vec<String> {
+    match self {
+      Link::Man { page, section: Some(section) } => vec!["man".into(), section.clone(), page.clone()],
+      Link::Man { page, section: None } => vec!["man".into(), page.clone()],
+      Link::Info(page) => vec!["info".into(), page.clone()],
+      Link::External(uri) => vec!["xdg-open".into(), uri.clone()],
+    }
+  }
+}
+
+/// `Documentation=` as `systemctl show` prints it: URIs separated by spaces
+pub fn parse_documentation(value: &str) -> Vec<String> {
+  value.split_whitespace().map(str::to_string).collect()
+}
+
+/// Open a link. Pages in the terminal are waited for; a browser is left to run on its own
+pub fn open(link: &Link) -> Result<()> {
+  let command = link.command();
+  let mut child = Command::new(&command[0]);
+  child.args(&command[1..]);
+  if link.in_terminal() {
+    let status = child.status().with_context(|| format!("Failed to run {}", command[0]))?;
+    if !status.success() {
+      bail!("`{}` failed ({status})", command.join(" "));
+    }
+    return Ok(());
+  }
+  let mut child =
+    child.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn().context("Failed to run xdg-open")?;
+  std::thread::spawn(move || child.wait());
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse() {
+    assert_eq!(
+      Link::parse("man:systemd.service(5)"),
+      Some(Link::Man { page: "systemd.service".into(), section: Some("5".into()) })
+    );
+    assert_eq!(Link::parse("man:sshd").unwrap().command(), ["man", "sshd"]);
+    assert_eq!(Link::parse("info:coreutils"), Some(Link::Info("coreutils".into())));
+    assert_eq!(
+      Link::parse("https://nginx.org/en/docs/").unwrap().command(),
+      ["xdg-open", "https://nginx.org/en/docs/"]
+    );
+    assert_eq!(Link::parse("gopher://example"), None);
+    assert_eq!(parse_documentation("man:sshd(8) man:sshd_config(5)"), ["man:sshd(8)", "man:sshd_config(5)"]);
+  }
+}
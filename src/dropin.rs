@@ -0,0 +1,96 @@
+// Drop-in overrides the way `systemctl edit` makes them: an `override.conf` next to the unit in the admin directory,
+// seeded with the unit's current settings as comments, and removed again if it's saved without any settings in it.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::{scaffold, systemd::UnitId};
+
+pub fn override_path(unit: &UnitId) -> Result<PathBuf> {
+  Ok(scaffold::unit_directory(unit.scope)?.join(format!("{}.d", unit.name)).join("override.conf"))
+}
+
+/// What a new override starts out as: how it works, then the unit file commented out for reference
+pub fn template(unit: &str, fragment_path: Option<&str>, fragment: Option<&str>) -> String {
+  let mut template = format!(
+    "### Override for {unit}\n\
+     ### Settings here are applied on top of the unit file. Uncomment or add lines under the section they belong\n\
+     ### to, e.g. [Service]. To clear a list setting like ExecStart= before setting it, assign it empty first.\n\
+     ### Saving this file with nothing but comments in it discards the override.\n"
+  );
+  if let (Some(path), Some(fragment)) = (fragment_path, fragment) {
+    template.push_str(&format!("\n### {path}\n"));
+    for line in fragment.lines() {
+      template.push_str(if line.is_empty() { "#" } else { "# " });
+      template.push_str(line);
+      template.push('\n');
+    }
+  }
+  template
+}
+
+/// Whether a drop-in sets anything, as opposed to being only comments and blank lines
+pub fn has_settings(contents: &str) -> bool {
+  contents.lines().map(str::trim).any(|l| !l.is_empty() && !l.starts_with('#') && !l.starts_with(';'))
+}
+
+/// Make sure the override exists so the editor can open it. Returns what it contained before, empty if it's new
+pub fn prepare(path: &Path, unit: &str, fragment_path: Option<&str>) -> Result<String> {
+  if path.exists() {
+    return std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()));
+  }
+  let directory = path.parent().context("Drop-in path has no directory")?;
+  std::fs::create_dir_all(directory).with_context(|| format!("Failed to create {}", directory.display()))?;
+  let fragment = fragment_path.and_then(|p| std::fs::read_to_string(p).ok());
+  std::fs::write(path, template(unit, fragment_path, fragment.as_deref()))
+    .with_context(|| format!("Failed to write {}", path.display()))?;
+  Ok(String::new())
+}
+
+/// After editing: drop an override left without settings (and its directory if that's now empty). Returns whether
+/// the unit's configuration changed, i.e. whether a daemon-reload is needed
+pub fn finish(path: &Path, before: &str) -> Result<bool> {
+  let after = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+  if has_settings(&after) {
+    return Ok(after != before);
+  }
+  std::fs::remove_file(path).with_context(|| format!("Failed to remove {}", path.display()))?;
+  if let Some(directory) = path.parent() {
+    // fails if anything else is in there, which is fine
+    let _ = std::fs::remove_dir(directory);
+  }
+  Ok(has_settings(before))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_template() {
+    let template =
+      template("nginx.service", Some("/usr/lib/systemd/system/nginx.service"), Some("[Service]\n\nType=forking"));
+    assert!(template.starts_with("### Override for nginx.service\n"));
+    assert!(template.ends_with("### /usr/lib/systemd/system/nginx.service\n# [Service]\n#\n# Type=forking\n"));
+    assert!(!has_settings(&template));
+    assert!(has_settings(&format!("{template}[Service]\nMemoryMax=1G\n")));
+  }
+
+  #[test]
+  fn test_finish() {
+    let directory = std::env::temp_dir().join(format!("systemctl-tui-dropin-{}", std::process::id()));
+    let path = directory.join("nginx.service.d/override.conf");
+    let before = prepare(&path, "nginx.service", None).unwrap();
+    assert_eq!(before, "");
+    // saved untouched: nothing changed and nothing is left behind
+    assert!(!finish(&path, &before).unwrap());
+    assert!(!path.parent().unwrap().exists());
+
+    prepare(&path, "nginx.service", None).unwrap();
+    std::fs::write(&path, "[Service]\nMemoryMax=1G\n").unwrap();
+    assert!(finish(&path, "").unwrap());
+    assert!(path.exists());
+    std::fs::remove_dir_all(&directory).unwrap();
+  }
+}
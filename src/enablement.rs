@@ -0,0 +1,191 @@
+// Previewing what enabling or disabling units will do before doing it. `systemctl enable` acts on the [Install]
+// section of the unit file, so that's where the effects come from: WantedBy=/RequiredBy= symlinks, aliases, and the
+// other units Also= drags along. `reenable` puts those symlinks back even when the unit is already enabled, and
+// `preset` enables or disables each unit as the preset files say, or leaves it be.
+
+use crate::{
+  preset::{PresetAction, PresetMode},
+  systemd::EnablementVerb,
+};
+
+/// What a unit file's [Install] section asks for
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstallSection {
+  pub wanted_by: Vec<String>,
+  pub required_by: Vec<String>,
+  pub alias: Vec<String>,
+  pub also: Vec<String>,
+}
+
+impl InstallSection {
+  pub fn is_empty(&self) -> bool {
+    self.wanted_by.is_empty() && self.required_by.is_empty() && self.alias.is_empty() && self.also.is_empty()
+  }
+}
+
+/// Read the [Install] section out of unit file contents. Drop-ins can add to it too, so pass them concatenated after
+/// the main file
+pub fn parse_install(contents: &str) -> InstallSection {
+  let mut install = InstallSection::default();
+  let mut in_install = false;
+  for line in contents.lines().map(str::trim) {
+    if line.starts_with('[') {
+      in_install = line == "[Install]";
+      continue;
+    }
+    let Some((key, value)) = line.split_once('=').filter(|_| in_install) else { continue };
+    let list = match key.trim() {
+      "WantedBy" => &mut install.wanted_by,
+      "RequiredBy" => &mut install.required_by,
+      "Alias" => &mut install.alias,
+      "Also" => &mut install.also,
+      _ => continue,
+    };
+    // an empty assignment resets the list
+    if value.trim().is_empty() {
+      list.clear();
+    }
+    list.extend(value.split_whitespace().map(String::from));
+  }
+  install
+}
+
+/// One row of the preview: where a unit is now, where it'll end up, and what changes on disk to get there
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+  pub unit: String,
+  pub current: String,
+  pub target: String,
+  pub effects: Vec<String>,
+  /// Why the presets leave it be, for `preset`
+  pub skipped: Option<&'static str>,
+}
+
+impl Change {
+  pub fn is_noop(&self) -> bool {
+    self.effects.is_empty()
+  }
+}
+
+/// What `verb` does to `unit`, currently in unit file state `current`. `preset` is what the preset files say to do
+/// with it, which only matters to `Preset`
+pub fn plan(unit: &str, current: &str, install: &InstallSection, verb: EnablementVerb, preset: PresetAction) -> Change {
+  let mut change = Change {
+    unit: unit.to_string(),
+    current: current.to_string(),
+    target: current.to_string(),
+    effects: vec![],
+    skipped: None,
+  };
+  let enable = match verb {
+    EnablementVerb::Enable | EnablementVerb::Reenable => true,
+    EnablementVerb::Disable => false,
+    EnablementVerb::Preset(mode) if !mode.allows(preset) => {
+      change.skipped = Some(match (preset, mode) {
+        (PresetAction::Ignore, _) => "the presets say to ignore it",
+        (_, PresetMode::EnableOnly) => "the presets would disable it, but only enabling",
+        _ => "the presets would enable it, but only disabling",
+      });
+      return change;
+    },
+    EnablementVerb::Preset(_) => preset == PresetAction::Enable,
+  };
+  let installable = !install.is_empty()
+    && !matches!(current, "masked" | "masked-runtime" | "static" | "indirect" | "generated" | "transient");
+  if !installable {
+    return change;
+  }
+  let enabled = matches!(current, "enabled" | "enabled-runtime" | "alias");
+  let already = match verb {
+    EnablementVerb::Reenable => false,
+    _ if enable => enabled,
+    _ => current == "disabled",
+  };
+  change.target = if enable { "enabled" } else { "disabled" }.to_string();
+  if already {
+    return change;
+  }
+
+  let word = match (enable, enabled) {
+    (false, _) => "remove",
+    (true, true) => "recreate",
+    (true, false) => "add",
+  };
+  let also = match verb {
+    EnablementVerb::Reenable => "reenable",
+    _ if enable => "enable",
+    _ => "disable",
+  };
+  change.effects.extend(install.wanted_by.iter().map(|t| format!("{word} {t}.wants/{unit}")));
+  change.effects.extend(install.required_by.iter().map(|t| format!("{word} {t}.requires/{unit}")));
+  change.effects.extend(install.alias.iter().map(|a| format!("{word} alias {a}")));
+  change.effects.extend(install.also.iter().map(|a| format!("also {also} {a}")));
+  change
+}
+
+/// Why nothing will happen to a unit, for the preview
+pub fn noop_reason(change: &Change) -> &'static str {
+  if let Some(skipped) = change.skipped {
+    return skipped;
+  }
+  match change.current.as_str() {
+    "masked" | "masked-runtime" => "masked, unmask it first",
+    "static" => "static: no [Install] section, other units pull it in",
+    "indirect" => "indirect: enabled through Also= of another unit",
+    "generated" | "transient" => "created at runtime, can't be enabled",
+    _ if change.current == change.target => "already there",
+    _ => "no [Install] section",
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_install() {
+    let contents = "[Unit]\nDescription=x\nWantedBy=ignored.target\n[Install]\nWantedBy=multi-user.target \
+                    graphical.target\nAlias=web.service\n";
+    let install = parse_install(contents);
+    assert_eq!(install.wanted_by, vec!["multi-user.target", "graphical.target"]);
+    assert_eq!(install.alias, vec!["web.service"]);
+    assert!(install.also.is_empty());
+    assert!(parse_install("[Install]\nWantedBy=a.target\n[Install]\nWantedBy=\n").is_empty());
+  }
+
+  #[test]
+  fn test_plan() {
+    let install = parse_install("[Install]\nWantedBy=multi-user.target\nAlias=web.service\n");
+    let plan =
+      |current, install: &InstallSection, verb| plan("nginx.service", current, install, verb, PresetAction::Enable);
+    let change = plan("disabled", &install, EnablementVerb::Enable);
+    assert_eq!(change.target, "enabled");
+    assert_eq!(change.effects, vec!["add multi-user.target.wants/nginx.service", "add alias web.service"]);
+    assert!(plan("enabled", &install, EnablementVerb::Enable).is_noop());
+    let masked = plan("masked", &install, EnablementVerb::Enable);
+    assert!(masked.is_noop());
+    assert_eq!(noop_reason(&masked), "masked, unmask it first");
+    assert!(plan("static", &InstallSection::default(), EnablementVerb::Disable).is_noop());
+
+    // reenabling puts the symlinks back even when they're there
+    let reenable = plan("enabled", &install, EnablementVerb::Reenable);
+    assert_eq!(reenable.effects[0], "recreate multi-user.target.wants/nginx.service");
+    assert!(plan("static", &install, EnablementVerb::Reenable).is_noop());
+  }
+
+  #[test]
+  fn test_plan_preset() {
+    let install = parse_install("[Install]\nWantedBy=multi-user.target\n");
+    let preset = |current, mode, action| plan("nginx.service", current, &install, EnablementVerb::Preset(mode), action);
+    let disable = preset("enabled", PresetMode::Full, PresetAction::Disable);
+    assert_eq!(disable.target, "disabled");
+    assert_eq!(disable.effects, vec!["remove multi-user.target.wants/nginx.service"]);
+    assert!(preset("enabled", PresetMode::Full, PresetAction::Enable).is_noop());
+    let ignored = preset("disabled", PresetMode::Full, PresetAction::Ignore);
+    assert_eq!(noop_reason(&ignored), "the presets say to ignore it");
+    let enable_only = preset("enabled", PresetMode::EnableOnly, PresetAction::Disable);
+    assert!(enable_only.is_noop());
+    assert_eq!(enable_only.target, "enabled");
+    assert!(!preset("disabled", PresetMode::EnableOnly, PresetAction::Enable).is_noop());
+  }
+}
@@ -0,0 +1,316 @@
+// What each state-changing action does under the hood: the `systemctl` command that does the same thing, the D-Bus
+// call we actually make, and which other units systemd will drag along. Used by dry-run mode and the command echo.
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use crate::{
+  action::Action,
+  machine, remote,
+  systemd::{self, KillWhom, UnitId, UnitScope},
+  user_manager,
+  utils::shell_quote,
+};
+
+/// Dependencies that starting a unit pulls in
+pub const START_PROPERTIES: &[&str] = &["Requires", "Requisite", "BindsTo", "Wants", "Upholds"];
+/// Dependents that get stopped, or restarted, along with a unit
+pub const STOP_PROPERTIES: &[&str] = &["RequiredBy", "BoundBy", "ConsistsOf"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Equivalent {
+  pub unit: UnitId,
+  pub command: Vec<String>,
+  /// The D-Bus method called, when the action doesn't shell out to `systemctl` anyway
+  pub dbus: Option<String>,
+  /// How the properties in `propagates_through` affect other units, e.g. "Also starts"
+  pub propagation: Option<&'static str>,
+  pub propagates_through: &'static [&'static str],
+}
+
+impl Equivalent {
+  pub fn shell(&self) -> String {
+    self.command.iter().map(|a| shell_quote(a)).collect::<Vec<_>>().join(" ")
+  }
+}
+
+fn systemctl(scope: UnitScope, args: &[&str]) -> Vec<String> {
+  manager_tool("systemctl", scope, args)
+}
+
+/// `program`, one of systemd's tools taking `-H`, `-M` and `--user`, pointed at `scope`'s manager
+fn manager_tool(program: &str, scope: UnitScope, args: &[&str]) -> Vec<String> {
+  let mut command = vec![program.to_string()];
+  if let Some(host) = remote::host() {
+    command.extend(["-H".to_string(), host]);
+  }
+  command.extend(machine::args());
+  if scope == UnitScope::User {
+    command.push("--user".into());
+    if let Some(user) = user_manager::user() {
+      command.push(format!("--machine={}@", user.name));
+    }
+  }
+  command.extend(args.iter().map(|a| a.to_string()));
+  command
+}
+
+/// `systemctl reset-failed` for every failed unit of `scope`'s manager
+pub fn reset_all_failed(scope: UnitScope) -> String {
+  systemctl(scope, &["reset-failed"]).iter().map(|a| shell_quote(a)).collect::<Vec<_>>().join(" ")
+}
+
+/// The equivalent of `action`, or `None` for actions that don't change anything
+pub fn for_action(action: &Action) -> Option<Equivalent> {
+  let manager_call = |method: &str, unit: &UnitId| {
+    Some(format!("org.freedesktop.systemd1.Manager.{method}(\"{}\", \"replace\")", unit.name))
+  };
+  let equivalent = match action {
+    Action::StartService(unit) => Equivalent {
+      unit: unit.clone(),
+      command: systemctl(unit.scope, &["start", &unit.name]),
+      dbus: manager_call("StartUnit", unit),
+      propagation: Some("Also starts"),
+      propagates_through: START_PROPERTIES,
+    },
+    Action::StopService(unit) => Equivalent {
+      unit: unit.clone(),
+      command: systemctl(unit.scope, &["stop", &unit.name]),
+      dbus: manager_call("StopUnit", unit),
+      propagation: Some("Also stops"),
+      propagates_through: STOP_PROPERTIES,
+    },
+    Action::RestartService(unit) => Equivalent {
+      unit: unit.clone(),
+      command: systemctl(unit.scope, &["restart", &unit.name]),
+      dbus: manager_call("RestartUnit", unit),
+      propagation: Some("Also restarts"),
+      propagates_through: STOP_PROPERTIES,
+    },
+    // "Reload" reloads the manager's configuration, not the unit
+    Action::ReloadService(unit) => Equivalent {
+      unit: unit.clone(),
+      command: systemctl(unit.scope, &["daemon-reload"]),
+      dbus: Some("org.freedesktop.systemd1.Manager.Reload()".into()),
+      propagation: None,
+      propagates_through: &[],
+    },
+    Action::KillService { unit, signal, whom } => {
+      let kill_whom = format!("--kill-whom={}", whom.as_str());
+      let mut args = vec!["kill"];
+      if *whom != KillWhom::All {
+        args.push(&kill_whom);
+      }
+      args.extend(["--signal", signal, &unit.name]);
+      Equivalent {
+        unit: unit.clone(),
+        command: systemctl(unit.scope, &args),
+        dbus: systemd::signal_number(signal).ok().map(|number| {
+          format!("org.freedesktop.systemd1.Manager.KillUnit(\"{}\", \"{}\", {number})", unit.name, whom.as_str())
+        }),
+        propagation: None,
+        propagates_through: &[],
+      }
+    },
+    Action::ResetFailed(unit) => Equivalent {
+      unit: unit.clone(),
+      command: systemctl(unit.scope, &["reset-failed", &unit.name]),
+      dbus: Some(format!("org.freedesktop.systemd1.Manager.ResetFailedUnit(\"{}\")", unit.name)),
+      propagation: None,
+      propagates_through: &[],
+    },
+    Action::CancelJob { unit, id } => Equivalent {
+      unit: unit.clone(),
+      command: systemctl(unit.scope, &["cancel", &id.to_string()]),
+      dbus: Some(format!("org.freedesktop.systemd1.Manager.CancelJob({id})")),
+      propagation: None,
+      propagates_through: &[],
+    },
+    Action::FreezeUnit { unit, thaw } => {
+      let (verb, method) = if *thaw { ("thaw", "ThawUnit") } else { ("freeze", "FreezeUnit") };
+      Equivalent {
+        unit: unit.clone(),
+        command: systemctl(unit.scope, &[verb, &unit.name]),
+        dbus: Some(format!("org.freedesktop.systemd1.Manager.{method}(\"{}\")", unit.name)),
+        propagation: None,
+        propagates_through: &[],
+      }
+    },
+    Action::RunSystemctl(unit, args) => Equivalent {
+      unit: unit.clone(),
+      command: systemctl(unit.scope, &args.iter().map(String::as_str).collect::<Vec<_>>()),
+      dbus: None,
+      propagation: None,
+      propagates_through: &[],
+    },
+    Action::EnableAccounting(unit) => Equivalent {
+      unit: unit.clone(),
+      command: systemctl(
+        unit.scope,
+        &["set-property", "--runtime", &unit.name, "IOAccounting=yes", "IPAccounting=yes"],
+      ),
+      dbus: None,
+      propagation: None,
+      propagates_through: &[],
+    },
+    Action::SetProperties { unit, assignments, runtime } => {
+      let mut args = vec!["set-property"];
+      if *runtime {
+        args.push("--runtime");
+      }
+      args.push(&unit.name);
+      args.extend(assignments.iter().map(String::as_str));
+      Equivalent {
+        unit: unit.clone(),
+        command: systemctl(unit.scope, &args),
+        dbus: None,
+        propagation: None,
+        propagates_through: &[],
+      }
+    },
+    Action::RunTransient(transient) => {
+      let unit = format!("--unit={}", transient.unit.name);
+      let description = format!("--description={}", transient.description);
+      let user = transient.user.as_ref().map(|user| format!("--uid={user}"));
+      let directory = transient.working_directory.as_ref().map(|d| format!("--working-directory={d}"));
+      let memory_max = transient.memory_max.map(|bytes| format!("--property=MemoryMax={bytes}"));
+      let mut args = vec![];
+      if transient.is_scope() {
+        args.push("--scope");
+      }
+      args.extend([unit.as_str(), description.as_str()]);
+      args.extend([&user, &directory, &memory_max].into_iter().flatten().map(String::as_str));
+      args.push("--");
+      args.extend(transient.command.iter().map(String::as_str));
+      Equivalent {
+        unit: transient.unit.clone(),
+        command: manager_tool("systemd-run", transient.unit.scope, &args),
+        dbus: Some(format!(
+          "org.freedesktop.systemd1.Manager.StartTransientUnit(\"{}\", \"fail\", …)",
+          transient.unit.name
+        )),
+        propagation: None,
+        propagates_through: &[],
+      }
+    },
+    _ => return None,
+  };
+  Some(equivalent)
+}
+
+/// The `journalctl` command that shows the same logs as the log pane. `boot_arg` is e.g. `--boot=0`
+pub fn journalctl(unit: &UnitId, boot_arg: Option<&str>, priority: Option<u8>, current_run: bool) -> String {
+  let mut command = vec!["journalctl".to_string()];
+  if unit.scope == UnitScope::User {
+    if let Some(user) = user_manager::user() {
+      command.splice(0..0, ["runuser".into(), "-u".into(), user.name, "--".into()]);
+    }
+    command.push("--user".into());
+  }
+  command.extend(machine::args());
+  command.extend(boot_arg.map(String::from));
+  command.extend(priority.map(|p| format!("--priority=0..{p}")));
+  if !current_run {
+    command.push("-u".into());
+    command.push(unit.name.clone());
+  }
+  let mut command = command.iter().map(|a| shell_quote(a)).collect::<Vec<_>>().join(" ");
+  // the invocation ID isn't known up front, so look it up the way a shell user would
+  if current_run {
+    let show = systemctl(unit.scope, &["show", "--value", "--property=InvocationID", &unit.name]);
+    let show = show.iter().map(|a| shell_quote(a)).collect::<Vec<_>>().join(" ");
+    command.push_str(&format!(" _SYSTEMD_INVOCATION_ID=\"$({show})\""));
+  }
+  // journalctl can't reach another machine by itself
+  match remote::host() {
+    Some(host) => format!("ssh {} {}", remote::ssh_args(&host).join(" "), shell_quote(&command)),
+    None => command,
+  }
+}
+
+/// Units reached from `root` through `properties`, transitively, given each unit's properties keyed by unit name
+pub fn affected_units(
+  units: &HashMap<String, HashMap<String, String>>,
+  root: &str,
+  properties: &[&str],
+) -> Vec<String> {
+  let mut seen = BTreeSet::new();
+  let mut queue = VecDeque::from([root.to_string()]);
+  while let Some(unit) = queue.pop_front() {
+    let Some(unit_properties) = units.get(&unit) else { continue };
+    for other in properties.iter().filter_map(|p| unit_properties.get(*p)).flat_map(|v| v.split_whitespace()) {
+      if other != root && seen.insert(other.to_string()) {
+        queue.push_back(other.to_string());
+      }
+    }
+  }
+  seen.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_for_action() {
+    let unit = UnitId { name: "nginx.service".into(), scope: UnitScope::User };
+    let restart = for_action(&Action::RestartService(unit.clone())).unwrap();
+    assert_eq!(restart.shell(), "systemctl --user restart nginx.service");
+    assert_eq!(
+      restart.dbus.as_deref(),
+      Some("org.freedesktop.systemd1.Manager.RestartUnit(\"nginx.service\", \"replace\")")
+    );
+    let kill = for_action(&Action::KillService { unit: unit.clone(), signal: "SIGTERM".into(), whom: KillWhom::All });
+    assert_eq!(kill.unwrap().shell(), "systemctl --user kill --signal SIGTERM nginx.service");
+    let reset = for_action(&Action::ResetFailed(unit.clone())).unwrap();
+    assert_eq!(reset.shell(), "systemctl --user reset-failed nginx.service");
+    let cancel = for_action(&Action::CancelJob { unit: unit.clone(), id: 42 }).unwrap();
+    assert_eq!(cancel.shell(), "systemctl --user cancel 42");
+    let thaw = for_action(&Action::FreezeUnit { unit: unit.clone(), thaw: true }).unwrap();
+    assert_eq!(thaw.shell(), "systemctl --user thaw nginx.service");
+    let kill = for_action(&Action::KillService { unit, signal: "SIGHUP".into(), whom: KillWhom::Main }).unwrap();
+    assert_eq!(kill.shell(), "systemctl --user kill --kill-whom=main --signal SIGHUP nginx.service");
+    assert_eq!(kill.dbus.as_deref(), Some("org.freedesktop.systemd1.Manager.KillUnit(\"nginx.service\", \"main\", 1)"));
+    assert_eq!(for_action(&Action::Render), None);
+  }
+
+  #[test]
+  fn test_systemd_run() {
+    let transient = systemd::Transient {
+      unit: UnitId { name: "run-backup.scope".into(), scope: UnitScope::User },
+      command: ["rsync", "-a", "/srv", "/backup"].map(String::from).to_vec(),
+      description: "nightly backup".into(),
+      user: None,
+      working_directory: Some("/srv".into()),
+      memory_max: Some(1 << 30),
+    };
+    assert_eq!(
+      for_action(&Action::RunTransient(transient)).unwrap().shell(),
+      "systemd-run --user --scope --unit=run-backup.scope '--description=nightly backup' --working-directory=/srv \
+       --property=MemoryMax=1073741824 -- rsync -a /srv /backup"
+    );
+  }
+
+  #[test]
+  fn test_journalctl() {
+    let unit = UnitId { name: "nginx.service".into(), scope: UnitScope::Global };
+    assert_eq!(
+      journalctl(&unit, Some("--boot=0"), Some(4), false),
+      "journalctl --boot=0 --priority=0..4 -u nginx.service"
+    );
+    assert_eq!(
+      journalctl(&unit, None, None, true),
+      "journalctl _SYSTEMD_INVOCATION_ID=\"$(systemctl show --value --property=InvocationID nginx.service)\""
+    );
+  }
+
+  #[test]
+  fn test_affected_units() {
+    let props = |pairs: &[(&str, &str)]| pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    let units = HashMap::from([
+      ("db.service".to_string(), props(&[("RequiredBy", "web.service"), ("WantedBy", "multi-user.target")])),
+      ("web.service".to_string(), props(&[("BoundBy", "proxy.service db.service")])),
+    ]);
+    assert_eq!(affected_units(&units, "db.service", STOP_PROPERTIES), vec!["proxy.service", "web.service"]);
+    assert!(affected_units(&units, "proxy.service", STOP_PROPERTIES).is_empty());
+  }
+}
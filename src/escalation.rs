@@ -0,0 +1,87 @@
+// Retrying an action the manager refused for lack of privileges. Our D-Bus calls don't allow interactive
+// authorization, so polkit turns them down outright instead of asking for a password. Running the equivalent
+// systemctl command in the terminal lets polkit's text agent ask instead, or sudo can run it as root. Either way the
+// TUI steps aside while the password is typed.
+
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+
+use crate::{
+  remote,
+  systemd::{self, UnitScope},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+  /// systemctl itself, which starts polkit's text agent to ask for a password
+  Polkit,
+  Sudo,
+}
+
+impl Method {
+  pub fn label(self) -> &'static str {
+    match self {
+      Method::Polkit => "Retry, authenticating with polkit",
+      Method::Sudo => "Retry with sudo",
+    }
+  }
+
+  /// `command`, a systemctl command line, run this way
+  pub fn command(self, command: &[String]) -> Vec<String> {
+    match self {
+      Method::Polkit => command.to_vec(),
+      Method::Sudo => [&["sudo".to_string()], command].concat(),
+    }
+  }
+}
+
+/// Whether an action's error says it wasn't allowed, rather than that it went wrong
+pub fn is_permission_error(message: &str) -> bool {
+  ["AccessDenied", "Access denied", "Permission denied", "Interactive authentication required"]
+    .iter()
+    .any(|m| message.contains(m))
+}
+
+/// The ways a refused action on a unit of `scope` could be retried here. Neither helps with another machine's manager,
+/// a container's or a user manager, whose refusals aren't about being root
+pub fn methods(scope: UnitScope) -> Vec<Method> {
+  if remote::elsewhere().is_some() || scope == UnitScope::User {
+    return vec![];
+  }
+  let mut methods = vec![];
+  if systemd::find_on_path("pkttyagent").is_some() {
+    methods.push(Method::Polkit);
+  }
+  if systemd::find_on_path("sudo").is_some() {
+    methods.push(Method::Sudo);
+  }
+  methods
+}
+
+/// Run `command` in the terminal, so it can ask for a password
+pub fn run(command: &[String]) -> Result<()> {
+  let Some((program, args)) = command.split_first() else { bail!("Nothing to run") };
+  println!("Running {}", command.join(" "));
+  let status = Command::new(program).args(args).status().with_context(|| format!("Failed to run {program}"))?;
+  if !status.success() {
+    bail!("`{}` failed ({status})", command.join(" "));
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_command() {
+    let command = ["systemctl", "restart", "nginx.service"].map(String::from);
+    assert_eq!(Method::Polkit.command(&command), command);
+    assert_eq!(Method::Sudo.command(&command), ["sudo", "systemctl", "restart", "nginx.service"]);
+    assert!(is_permission_error("org.freedesktop.DBus.Error.AccessDenied: Access denied"));
+    assert!(is_permission_error("Failed to start nginx.service: Interactive authentication required."));
+    assert!(!is_permission_error("Unit nginx.service not found."));
+    assert!(methods(UnitScope::User).is_empty());
+  }
+}
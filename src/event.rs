@@ -1,6 +1,6 @@
 use std::{sync::Arc, time::Duration};
 
-use crossterm::event::{Event as CrosstermEvent, KeyEvent, KeyEventKind, MouseEvent};
+use crossterm::event::{Event as CrosstermEvent, KeyEvent, KeyEventKind, MouseEvent, MouseEventKind};
 use futures::{FutureExt, StreamExt};
 use tokio::{
   sync::{mpsc, Mutex},
@@ -32,6 +32,10 @@ pub struct EventHandler {
 
 const SERVICE_REFRESH_INTERVAL_MS: u64 = 5000;
 
+/// Resizes are only passed on once they've stopped for this long. Dragging a window or a tmux reattach can fire dozens
+/// of them, and laying out (and fully redrawing) for each one is what leaves half-drawn frames behind
+const RESIZE_SETTLE: Duration = Duration::from_millis(50);
+
 impl EventHandler {
   pub fn new(home: Arc<Mutex<Home>>, action_tx: mpsc::UnboundedSender<Action>) -> Self {
     let (event_tx, mut event_rx) = mpsc::unbounded_channel();
@@ -41,6 +45,9 @@ impl EventHandler {
       let mut reader = crossterm::event::EventStream::new();
       let mut refresh_services_interval = tokio::time::interval(Duration::from_millis(SERVICE_REFRESH_INTERVAL_MS));
       refresh_services_interval.tick().await;
+      let mut pending_resize: Option<(u16, u16)> = None;
+      let resize_settle = tokio::time::sleep(RESIZE_SETTLE);
+      tokio::pin!(resize_settle);
       loop {
         let refresh_delay = refresh_services_interval.tick();
         let crossterm_event = reader.next().fuse();
@@ -52,14 +59,25 @@ impl EventHandler {
             match maybe_event {
               Some(Ok(evt)) => {
                 match evt {
-                  CrosstermEvent::Key(key) => {
-                    if key.kind == KeyEventKind::Press {
-                      event_tx.send(Event::Key(key)).unwrap();
-                    }
+                  CrosstermEvent::Key(key) if key.kind == KeyEventKind::Press => {
+                    event_tx.send(Event::Key(key)).unwrap();
+                  },
+                  // the pointer merely moving is of no interest, and there are lots of those
+                  CrosstermEvent::Mouse(mouse) if mouse.kind != MouseEventKind::Moved => {
+                    event_tx.send(Event::Mouse(mouse)).unwrap();
                   },
                   // interestingly, we never get these if running in dev mode with watchexec
                   CrosstermEvent::Resize(x, y) => {
-                    event_tx.send(Event::Resize(x, y)).unwrap();
+                    pending_resize = Some((x, y));
+                    resize_settle.as_mut().reset(tokio::time::Instant::now() + RESIZE_SETTLE);
+                  },
+                  // tmux reports focus when a client attaches; the screen may not match what was last drawn even if
+                  // the size didn't change, so treat it like a resize and redraw everything
+                  CrosstermEvent::FocusGained => {
+                    if let Ok(size) = crossterm::terminal::size() {
+                      pending_resize = Some(size);
+                      resize_settle.as_mut().reset(tokio::time::Instant::now() + RESIZE_SETTLE);
+                    }
                   },
                   _ => {},
                 }
@@ -70,6 +88,10 @@ impl EventHandler {
               None => {},
             }
           },
+          _ = &mut resize_settle, if pending_resize.is_some() => {
+            let (x, y) = pending_resize.take().unwrap();
+            event_tx.send(Event::Resize(x, y)).unwrap();
+          },
           _ = refresh_delay => {
             event_tx.send(Event::RefreshTick).unwrap();
           },
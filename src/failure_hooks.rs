@@ -0,0 +1,63 @@
+// Acting on units failing while the TUI is open, so it can be left running as a small monitoring dashboard. Each
+// configured hook covers the units matching its patterns, and can pop up a desktop notification, run a command, or
+// both. Failures are noticed when the unit list is refreshed, so a unit failing and restarting between two refreshes
+// goes unnoticed, and units already failed at launch don't fire.
+//
+// A hook's command gets the unit name as its last argument, plus `SYSTEMCTL_TUI_UNIT`, `SYSTEMCTL_TUI_SCOPE`
+// (`system` or `user`) and `SYSTEMCTL_TUI_HOST` (the host or container, empty for this machine) in its environment,
+// like plugins do.
+
+use std::process::{Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+use tracing::error;
+
+use crate::{
+  config::FailureHook,
+  remote,
+  systemd::{UnitId, UnitScope},
+};
+
+/// Fire `hook` for `unit` having just failed. Nothing waits for the notification or the command, which are left to
+/// finish on their own. Read-only sessions pass `run_command` false, since the command could change anything. The
+/// command runs even if the notification can't be shown, which is logged rather than returned
+pub fn fire(hook: &FailureHook, unit: &UnitId, description: &str, run_command: bool) -> Result<()> {
+  if hook.notifies(run_command) {
+    let summary = match remote::elsewhere() {
+      Some(host) => format!("{} failed on {host}", unit.name),
+      None => format!("{} failed", unit.name),
+    };
+    if let Err(e) =
+      spawn(&["notify-send", "--urgency=critical", "--app-name=systemctl-tui", &summary, description], unit)
+    {
+      error!("Failed to show a notification for {} failing: {e:#}", unit.name);
+    }
+  }
+  if run_command && !hook.command.is_empty() {
+    let mut command = hook.command.iter().map(String::as_str).collect::<Vec<_>>();
+    command.push(&unit.name);
+    spawn(&command, unit)?;
+  }
+  Ok(())
+}
+
+fn spawn(command: &[&str], unit: &UnitId) -> Result<()> {
+  let Some((program, args)) = command.split_first() else { bail!("Nothing to run") };
+  let scope = match unit.scope {
+    UnitScope::Global => "system",
+    UnitScope::User => "user",
+  };
+  let mut child = Command::new(program)
+    .args(args)
+    .env("SYSTEMCTL_TUI_UNIT", &unit.name)
+    .env("SYSTEMCTL_TUI_SCOPE", scope)
+    .env("SYSTEMCTL_TUI_HOST", remote::elsewhere().unwrap_or_default())
+    .stdin(Stdio::null())
+    .stdout(Stdio::null())
+    .stderr(Stdio::null())
+    .spawn()
+    .with_context(|| format!("Failed to run {program}"))?;
+  // reaped once it's done, so it doesn't linger as a zombie
+  std::thread::spawn(move || child.wait());
+  Ok(())
+}
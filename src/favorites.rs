@@ -0,0 +1,77 @@
+// Starred units, listed above everything else. They're kept in the data directory as one `scope name` line per unit,
+// e.g. `system nginx.service`, so they're still there next launch. Units that aren't loaded keep their star for when
+// they are.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use indexmap::IndexSet;
+
+use crate::{
+  systemd::{UnitId, UnitScope},
+  utils::get_data_dir,
+};
+
+fn path() -> Result<PathBuf> {
+  Ok(get_data_dir()?.join("favorites"))
+}
+
+/// The favorites saved by earlier sessions, if any
+pub fn load() -> IndexSet<UnitId> {
+  path().and_then(|path| Ok(std::fs::read_to_string(path)?)).map(|text| parse(&text)).unwrap_or_default()
+}
+
+pub fn save(favorites: &IndexSet<UnitId>) -> Result<()> {
+  let path = path()?;
+  if let Some(directory) = path.parent() {
+    std::fs::create_dir_all(directory)?;
+  }
+  std::fs::write(path, to_text(favorites))?;
+  Ok(())
+}
+
+fn parse(text: &str) -> IndexSet<UnitId> {
+  text
+    .lines()
+    .filter_map(|line| {
+      let (scope, name) = line.trim().split_once(char::is_whitespace)?;
+      let scope = match scope {
+        "system" => UnitScope::Global,
+        "user" => UnitScope::User,
+        _ => return None,
+      };
+      Some(UnitId { name: name.trim().to_string(), scope })
+    })
+    .collect()
+}
+
+fn to_text(favorites: &IndexSet<UnitId>) -> String {
+  favorites
+    .iter()
+    .map(|unit| {
+      let scope = match unit.scope {
+        UnitScope::Global => "system",
+        UnitScope::User => "user",
+      };
+      format!("{scope} {}\n", unit.name)
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse() {
+    let favorites = parse("system nginx.service\nuser  pipewire.service\n\nbogus line\n");
+    assert_eq!(
+      favorites.iter().cloned().collect::<Vec<_>>(),
+      [
+        UnitId { name: "nginx.service".into(), scope: UnitScope::Global },
+        UnitId { name: "pipewire.service".into(), scope: UnitScope::User },
+      ]
+    );
+    assert_eq!(to_text(&favorites), "system nginx.service\nuser pipewire.service\n");
+  }
+}
@@ -0,0 +1,133 @@
+// Restarting a unit whenever files it runs from change, like `cargo watch` for a service being worked on. A watch
+// is a list of paths or globs, e.g. `~/src/app/**/*.py`; `*` matches across directories, so `~/src/app/*.py` covers
+// the whole tree too, and a path without wildcards covers everything under it. There's no inotify here: the files'
+// modification times are polled every second, which is plenty for edit-and-save and doesn't need any setup. Hidden
+// directories like `.git` are skipped, and so is anything past `MAX_FILES`.
+
+use std::{
+  collections::HashMap,
+  path::{Path, PathBuf},
+  time::{Duration, SystemTime},
+};
+
+use chrono::{DateTime, Local};
+use tokio_util::sync::CancellationToken;
+
+use crate::preset::glob_match;
+
+/// How often the files are checked
+pub const POLL: Duration = Duration::from_secs(1);
+/// How long files have to stay unchanged after a change before restarting, so saving several restarts once
+pub const SETTLE: Duration = Duration::from_millis(300);
+/// Files looked at per watch, at most
+const MAX_FILES: usize = 20_000;
+
+/// The files a watch covers and when each was last modified
+pub type Snapshot = HashMap<PathBuf, SystemTime>;
+
+/// A unit being restarted on changes
+#[derive(Debug)]
+pub struct Watch {
+  /// As typed, `~` and all
+  pub patterns: Vec<String>,
+  pub restarts: u32,
+  /// What set off the last restart: the file, when, and why the restart failed if it did
+  pub last: Option<(PathBuf, DateTime<Local>, Option<String>)>,
+  /// Stops the polling
+  pub cancel: CancellationToken,
+}
+
+impl Watch {
+  pub fn new(patterns: Vec<String>) -> Self {
+    Self { patterns, restarts: 0, last: None, cancel: CancellationToken::new() }
+  }
+}
+
+impl Drop for Watch {
+  fn drop(&mut self) {
+    self.cancel.cancel();
+  }
+}
+
+/// `~/src` with the home directory filled in
+fn expand_home(pattern: &str) -> String {
+  match (pattern.strip_prefix("~/"), std::env::var("HOME")) {
+    (Some(rest), Ok(home)) => format!("{home}/{rest}"),
+    _ => pattern.to_string(),
+  }
+}
+
+fn has_wildcard(s: &str) -> bool {
+  s.contains(['*', '?', '['])
+}
+
+/// Whether `path` is covered by `pattern`, both absolute
+fn matches(pattern: &str, path: &Path) -> bool {
+  if has_wildcard(pattern) {
+    path.to_str().is_some_and(|p| glob_match(pattern, p))
+  } else {
+    path.starts_with(pattern)
+  }
+}
+
+/// The directory to look in for `pattern`: its path up to the first wildcard
+fn base(pattern: &str) -> PathBuf {
+  Path::new(pattern).components().take_while(|c| !has_wildcard(&c.as_os_str().to_string_lossy())).collect()
+}
+
+/// The files `patterns` cover now. Files that can't be read are left out, as if they weren't there
+pub fn scan(patterns: &[String]) -> Snapshot {
+  let mut snapshot = Snapshot::new();
+  for pattern in patterns.iter().map(|p| expand_home(p)) {
+    let mut pending = vec![base(&pattern)];
+    let mut seen = 0;
+    while let Some(path) = pending.pop() {
+      seen += 1;
+      if seen > MAX_FILES {
+        break;
+      }
+      let Ok(metadata) = std::fs::metadata(&path) else { continue };
+      if metadata.is_dir() {
+        let Ok(entries) = std::fs::read_dir(&path) else { continue };
+        pending
+          .extend(entries.flatten().filter(|e| !e.file_name().to_string_lossy().starts_with('.')).map(|e| e.path()));
+      } else if matches(&pattern, &path) {
+        snapshot.insert(path, metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH));
+      }
+    }
+  }
+  snapshot
+}
+
+/// A file added, removed or modified between the two snapshots, if any
+pub fn changed(before: &Snapshot, after: &Snapshot) -> Option<PathBuf> {
+  let modified = after.iter().find(|(path, time)| before.get(*path) != Some(*time)).map(|(path, _)| path);
+  let removed = || before.keys().find(|path| !after.contains_key(*path));
+  modified.or_else(removed).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_watch() {
+    assert_eq!(base("/home/me/src/app/**/*.py"), PathBuf::from("/home/me/src/app"));
+    assert_eq!(base("/home/me/app.conf"), PathBuf::from("/home/me/app.conf"));
+    assert!(matches("/srv/app/*.py", Path::new("/srv/app/lib/models.py")));
+    assert!(!matches("/srv/app/*.py", Path::new("/srv/app/README.md")));
+    assert!(matches("/srv/app", Path::new("/srv/app/config/app.toml")));
+    assert!(!matches("/srv/app", Path::new("/srv/apple")));
+
+    let at = |seconds| SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+    let before = Snapshot::from([("/srv/app/main.py".into(), at(1)), ("/srv/app/util.py".into(), at(1))]);
+    assert_eq!(changed(&before, &before.clone()), None);
+    let mut after = before.clone();
+    after.insert("/srv/app/util.py".into(), at(2));
+    assert_eq!(changed(&before, &after), Some("/srv/app/util.py".into()));
+    after.remove(&PathBuf::from("/srv/app/util.py"));
+    assert_eq!(changed(&before, &after), Some("/srv/app/util.py".into()));
+    after.insert("/srv/app/new.py".into(), at(3));
+    assert_eq!(changed(&before, &after), Some("/srv/app/new.py".into()));
+  }
+}
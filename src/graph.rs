@@ -0,0 +1,77 @@
+// Tiny single-row sparkline renderer. Ratatui has a Sparkline widget, but it only does block characters and we want
+// braille (twice the horizontal resolution) with a fallback for terminals that can't display either.
+
+use crate::config::GraphStyle;
+
+const BLOCKS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const ASCII: [char; 5] = [' ', '.', '-', '=', '#'];
+
+// braille dots from the bottom up, for the left and right column of a cell
+const BRAILLE_LEFT: [u32; 4] = [0x40, 0x04, 0x02, 0x01];
+const BRAILLE_RIGHT: [u32; 4] = [0x80, 0x20, 0x10, 0x08];
+
+/// How many samples fit in one terminal cell for a given style
+pub fn samples_per_cell(style: GraphStyle) -> usize {
+  match style.resolve() {
+    GraphStyle::Braille => 2,
+    _ => 1,
+  }
+}
+
+/// Render `values` as a sparkline scaled to the largest value. Values beyond what fits in `width` cells are dropped
+/// from the front, so the most recent samples are always shown
+pub fn sparkline(values: &[u64], width: usize, style: GraphStyle) -> String {
+  let style = style.resolve();
+  let per_cell = samples_per_cell(style);
+  let values = &values[values.len().saturating_sub(width * per_cell)..];
+  let max = values.iter().copied().max().unwrap_or(0);
+
+  // scale to 0..=levels, but make sure any non-zero value is visible
+  let scale = |v: u64, levels: usize| -> usize {
+    if v == 0 || max == 0 {
+      0
+    } else {
+      ((v as f64 / max as f64 * levels as f64).round() as usize).clamp(1, levels)
+    }
+  };
+
+  match style {
+    GraphStyle::Braille => values
+      .chunks(2)
+      .map(|pair| {
+        let left = scale(pair[0], 4);
+        let right = pair.get(1).map(|v| scale(*v, 4)).unwrap_or(0);
+        let bits: u32 = BRAILLE_LEFT[..left].iter().sum::<u32>() + BRAILLE_RIGHT[..right].iter().sum::<u32>();
+        char::from_u32(0x2800 + bits).unwrap_or(' ')
+      })
+      .collect(),
+    GraphStyle::Block => values.iter().map(|v| BLOCKS[scale(*v, BLOCKS.len() - 1)]).collect(),
+    GraphStyle::Ascii | GraphStyle::Auto => values.iter().map(|v| ASCII[scale(*v, ASCII.len() - 1)]).collect(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_block_sparkline() {
+    assert_eq!(sparkline(&[0, 4, 8], 3, GraphStyle::Block), " ▄█");
+  }
+
+  #[test]
+  fn test_ascii_sparkline() {
+    assert_eq!(sparkline(&[0, 1, 4], 3, GraphStyle::Ascii), " .#");
+  }
+
+  #[test]
+  fn test_braille_sparkline_packs_two_samples() {
+    // full left column + empty right column, then empty left + bottom dot on the right
+    assert_eq!(sparkline(&[4, 0, 0, 1], 2, GraphStyle::Braille), "\u{2847}\u{2880}");
+  }
+
+  #[test]
+  fn test_sparkline_keeps_most_recent() {
+    assert_eq!(sparkline(&[8, 8, 0, 8], 2, GraphStyle::Block), " █");
+  }
+}
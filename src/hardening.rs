@@ -0,0 +1,150 @@
+// A glanceable summary of a unit's sandboxing directives. `systemd-analyze security` is the thorough version of this,
+// but it's long and slow; this covers the settings that matter for most services.
+
+use std::collections::HashMap;
+
+pub struct Check {
+  pub directive: &'static str,
+  is_hardened: fn(&str) -> bool,
+  pub hint: &'static str,
+}
+
+fn is_yes(v: &str) -> bool {
+  v == "yes"
+}
+
+pub const CHECKS: &[Check] = &[
+  Check {
+    directive: "ProtectSystem",
+    is_hardened: |v| v == "strict" || v == "full",
+    hint: "ProtectSystem=strict makes the whole filesystem read-only",
+  },
+  Check {
+    directive: "ProtectHome",
+    is_hardened: |v| v == "yes" || v == "read-only" || v == "tmpfs",
+    hint: "ProtectHome=yes hides /home, /root and /run/user",
+  },
+  Check { directive: "PrivateTmp", is_hardened: is_yes, hint: "PrivateTmp=yes gives the unit its own /tmp" },
+  Check {
+    directive: "PrivateDevices",
+    is_hardened: is_yes,
+    hint: "PrivateDevices=yes hides physical devices from /dev",
+  },
+  Check {
+    directive: "NoNewPrivileges",
+    is_hardened: is_yes,
+    hint: "NoNewPrivileges=yes stops setuid binaries from gaining privileges",
+  },
+  Check {
+    directive: "ProtectKernelTunables",
+    is_hardened: is_yes,
+    hint: "ProtectKernelTunables=yes makes /proc/sys and /sys read-only",
+  },
+  Check {
+    directive: "ProtectKernelModules",
+    is_hardened: is_yes,
+    hint: "ProtectKernelModules=yes denies module loading",
+  },
+  Check { directive: "ProtectKernelLogs", is_hardened: is_yes, hint: "ProtectKernelLogs=yes denies access to dmesg" },
+  Check {
+    directive: "ProtectControlGroups",
+    is_hardened: is_yes,
+    hint: "ProtectControlGroups=yes makes the cgroup tree read-only",
+  },
+  Check { directive: "ProtectClock", is_hardened: is_yes, hint: "ProtectClock=yes denies changing the system clock" },
+  Check { directive: "ProtectHostname", is_hardened: is_yes, hint: "ProtectHostname=yes denies changing the hostname" },
+  Check {
+    directive: "RestrictNamespaces",
+    is_hardened: is_yes,
+    hint: "RestrictNamespaces=yes denies creating namespaces",
+  },
+  Check {
+    directive: "RestrictSUIDSGID",
+    is_hardened: is_yes,
+    hint: "RestrictSUIDSGID=yes denies creating setuid/setgid files",
+  },
+  Check { directive: "RestrictRealtime", is_hardened: is_yes, hint: "RestrictRealtime=yes denies realtime scheduling" },
+  Check { directive: "LockPersonality", is_hardened: is_yes, hint: "LockPersonality=yes locks the execution domain" },
+  Check {
+    directive: "MemoryDenyWriteExecute",
+    is_hardened: is_yes,
+    hint: "MemoryDenyWriteExecute=yes denies writable+executable memory (breaks JITs)",
+  },
+  Check {
+    directive: "SystemCallFilter",
+    is_hardened: |v| !v.is_empty(),
+    hint: "SystemCallFilter=@system-service allows only common syscalls",
+  },
+  Check {
+    // unrestricted, this lists every capability the kernel knows about
+    directive: "CapabilityBoundingSet",
+    is_hardened: |v| !v.contains("cap_sys_admin"),
+    hint: "CapabilityBoundingSet= should list only the capabilities the service needs",
+  },
+];
+
+/// The properties to ask systemd for, including the ones that decide which user the unit runs as
+pub fn properties() -> Vec<&'static str> {
+  let mut properties = CHECKS.iter().map(|c| c.directive).collect::<Vec<_>>();
+  properties.extend(["User", "DynamicUser"]);
+  properties
+}
+
+pub struct CheckResult {
+  pub directive: &'static str,
+  pub value: String,
+  pub hardened: bool,
+  pub hint: &'static str,
+}
+
+pub fn evaluate(properties: &HashMap<String, String>) -> Vec<CheckResult> {
+  let mut results: Vec<CheckResult> = CHECKS
+    .iter()
+    .map(|c| {
+      let value = properties.get(c.directive).cloned().unwrap_or_default();
+      CheckResult { directive: c.directive, hardened: (c.is_hardened)(&value), value, hint: c.hint }
+    })
+    .collect();
+
+  let user = properties.get("User").cloned().unwrap_or_default();
+  let dynamic_user = properties.get("DynamicUser").is_some_and(|v| v == "yes");
+  results.push(CheckResult {
+    directive: "User",
+    hardened: dynamic_user || !(user.is_empty() || user == "root" || user == "0"),
+    value: if dynamic_user {
+      "(dynamic)".into()
+    } else if user.is_empty() {
+      "root".into()
+    } else {
+      user
+    },
+    hint: "User= or DynamicUser=yes avoids running as root",
+  });
+  results
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_evaluate() {
+    let properties: HashMap<String, String> = [
+      ("ProtectSystem", "strict"),
+      ("PrivateTmp", "no"),
+      ("CapabilityBoundingSet", "cap_chown cap_sys_admin"),
+      ("DynamicUser", "yes"),
+    ]
+    .iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+
+    let results = evaluate(&properties);
+    let find = |d: &str| results.iter().find(|r| r.directive == d).unwrap();
+    assert!(find("ProtectSystem").hardened);
+    assert!(!find("PrivateTmp").hardened);
+    assert!(!find("CapabilityBoundingSet").hardened);
+    assert!(!find("SystemCallFilter").hardened);
+    assert!(find("User").hardened);
+  }
+}
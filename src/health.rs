@@ -0,0 +1,104 @@
+// Checking that units actually work, beyond systemd calling them active: a service can be up and running while it
+// refuses connections or serves errors. Each check in the config covers the units matching its patterns and either
+// fetches a URL with curl or runs a command, every `interval` seconds while the TUI is open. It passes when curl or
+// the command exits with the expected code (0 unless set), within the timeout. Checks run on this machine, also when
+// managing another host or a container.
+//
+// A check's command gets the unit name as its last argument, like failure hooks' commands do.
+
+use std::{process::Command, time::Duration};
+
+use chrono::{DateTime, Local};
+
+use crate::{config::HealthCheck, subprocess::run_with_timeout, systemd::UnitId};
+
+/// How a unit's last check went
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+  pub healthy: bool,
+  /// `exit status 7: Failed to connect to localhost port 8080`, or `ok` when healthy
+  pub detail: String,
+  pub at: DateTime<Local>,
+}
+
+/// What `check` runs for `unit`
+pub fn command(check: &HealthCheck, unit: &UnitId) -> Vec<String> {
+  match &check.url {
+    Some(url) => {
+      let timeout = check.timeout.to_string();
+      ["curl", "--silent", "--show-error", "--fail", "--output", "/dev/null", "--max-time", &timeout, url]
+        .map(String::from)
+        .to_vec()
+    },
+    None => check.command.iter().cloned().chain([unit.name.clone()]).collect(),
+  }
+}
+
+/// Run `check` for `unit`, waiting for it to finish or time out
+pub fn probe(check: &HealthCheck, unit: &UnitId) -> Outcome {
+  let (healthy, detail) = match run(&command(check, unit), Duration::from_secs(check.timeout)) {
+    Ok((code, output)) => judge(check.expect_exit, code, &output),
+    Err(e) => (false, e),
+  };
+  Outcome { healthy, detail, at: Local::now() }
+}
+
+/// The exit code (`None` if killed by a signal) and what it printed, or why it didn't finish
+fn run(command: &[String], timeout: Duration) -> Result<(Option<i32>, String), String> {
+  let (program, args) = command.split_first().ok_or("nothing to run")?;
+  match run_with_timeout(Command::new(program).args(args), None, timeout) {
+    Ok(Some(finished)) => Ok((finished.status.code(), finished.stderr)),
+    Ok(None) => Err(format!("no answer within {}s", timeout.as_secs())),
+    Err(e) => Err(format!("couldn't run {program}: {e}")),
+  }
+}
+
+/// Whether exiting with `code` is what `expected` asks for, and why not if it isn't, from the last thing printed
+fn judge(expected: i32, code: Option<i32>, output: &str) -> (bool, String) {
+  if code == Some(expected) {
+    return (true, "ok".into());
+  }
+  let status = code.map_or("killed by a signal".into(), |c| format!("exit status {c}"));
+  match output.lines().map(str::trim).rfind(|l| !l.is_empty()) {
+    Some(last) => (false, format!("{status}: {last}")),
+    None => (false, status),
+  }
+}
+
+/// The outcome that says most about a unit with several checks: a failing one, else the latest
+pub fn worst<'a>(outcomes: impl Iterator<Item = &'a Outcome>) -> Option<&'a Outcome> {
+  outcomes.max_by_key(|o| (!o.healthy, o.at))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::systemd::UnitScope;
+
+  #[test]
+  fn test_health() {
+    let unit = UnitId { name: "app.service".into(), scope: UnitScope::Global };
+    let check = HealthCheck { command: vec!["/usr/local/bin/ping-app".into(), "-q".into()], ..Default::default() };
+    assert_eq!(command(&check, &unit), ["/usr/local/bin/ping-app", "-q", "app.service"]);
+    let check = HealthCheck { url: Some("http://localhost:8080/health".into()), timeout: 3, ..Default::default() };
+    let curl = command(&check, &unit);
+    assert_eq!((curl[0].as_str(), curl.last().unwrap().as_str()), ("curl", "http://localhost:8080/health"));
+    assert!(curl.windows(2).any(|w| w == ["--max-time", "3"]));
+
+    assert_eq!(judge(0, Some(0), "ignored"), (true, "ok".into()));
+    assert_eq!(judge(2, Some(2), ""), (true, "ok".into()));
+    assert_eq!(
+      judge(0, Some(7), "curl: (7) Failed to connect to localhost port 8080\n\n"),
+      (false, "exit status 7: curl: (7) Failed to connect to localhost port 8080".into())
+    );
+    assert_eq!(judge(0, None, ""), (false, "killed by a signal".into()));
+
+    let at = |seconds| DateTime::from_timestamp(seconds, 0).unwrap().with_timezone(&Local);
+    let outcomes = [
+      Outcome { healthy: true, detail: "ok".into(), at: at(20) },
+      Outcome { healthy: false, detail: "exit status 1".into(), at: at(10) },
+    ];
+    assert_eq!(worst(outcomes.iter()), Some(&outcomes[1]));
+    assert_eq!(worst(outcomes[..1].iter()), Some(&outcomes[0]));
+  }
+}
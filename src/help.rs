@@ -0,0 +1,281 @@
+// Every key, for the `?` overlay. The keys of commands come from the keymap, so they show as remapped in the config,
+// and are grouped by what they're about; every command is in one group, so none can go missing from the overlay.
+// Keys elsewhere (the search box, menus and popups) can't be remapped and are listed as they are.
+
+use crate::keybindings::{Command, Keymap};
+
+/// Every command, by group
+const GROUPS: &[(&str, &[Command])] = &[
+  (
+    "Unit list",
+    &[
+      Command::Next,
+      Command::Previous,
+      Command::HalfPageDown,
+      Command::HalfPageUp,
+      Command::PageDown,
+      Command::PageUp,
+      Command::NextFailed,
+      Command::NextInstance,
+      Command::GoToTrigger,
+      Command::GoToMount,
+      Command::Search,
+      Command::FailedView,
+      Command::CycleUnitType,
+      Command::CycleSort,
+      Command::CycleScope,
+      Command::ToggleFavorite,
+      Command::Mark,
+      Command::ClearMarks,
+      Command::CommandBar,
+      Command::ToggleSplit,
+      Command::GrowList,
+      Command::ShrinkList,
+      Command::DryRun,
+      Command::RecordMacro,
+      Command::ReplayMacro,
+      Command::Quit,
+    ],
+  ),
+  (
+    "The selected unit",
+    &[
+      Command::ActionMenu,
+      Command::Start,
+      Command::Stop,
+      Command::Restart,
+      Command::Kill,
+      Command::Enable,
+      Command::Disable,
+      Command::Reenable,
+      Command::ApplyPreset,
+      Command::ToggleMask,
+      Command::ToggleFreeze,
+      Command::ResetFailed,
+      Command::Clean,
+      Command::Instantiate,
+      Command::EditUnitFile,
+      Command::EditDropIn,
+      Command::VerifyUnitFile,
+      Command::CopyUnitFilePath,
+      Command::SetProperties,
+      Command::SetEnvironment,
+      Command::WatchFiles,
+      Command::EnableAccounting,
+      Command::ShowUnitFiles,
+      Command::ShowUnitDelta,
+      Command::ShowEnvironment,
+      Command::ShowSandboxing,
+      Command::ShowProcesses,
+      Command::ShowListening,
+      Command::ShowProperties,
+      Command::ShowRunHistory,
+      Command::ShowTimeline,
+      Command::ShowBootWait,
+      Command::ShowBootContext,
+      Command::ShowCrash,
+      Command::ShowJournalUsage,
+      Command::ShowDocumentation,
+      Command::PluginTab,
+      Command::DependencyTree,
+      Command::ExportGraph,
+    ],
+  ),
+  (
+    "Logs",
+    &[
+      Command::FocusLogs,
+      Command::MaximizeLogs,
+      Command::CycleLogBoot,
+      Command::PickLogBoot,
+      Command::ToggleLogAudit,
+      Command::ToggleCurrentRun,
+      Command::CycleLogPriority,
+      Command::CycleLogTimestamps,
+      Command::LogsInPager,
+      Command::CopyLogs,
+      Command::PipeLogs,
+      Command::FollowMergedLogs,
+      Command::FollowLogsInPane,
+    ],
+  ),
+  (
+    "Log pane",
+    &[
+      Command::LogDown,
+      Command::LogUp,
+      Command::LogSearch,
+      Command::LogNextMatch,
+      Command::LogPreviousMatch,
+      Command::LogFilters,
+      Command::LogInspect,
+      Command::LogCopy,
+      Command::LogCopyLine,
+      Command::LogSave,
+      Command::LogWrap,
+      Command::LogLeft,
+      Command::LogRight,
+      Command::LogLineStart,
+      Command::LogTimestamps,
+      Command::LogMaximize,
+      Command::LeaveLogs,
+    ],
+  ),
+  (
+    "The whole system",
+    &[
+      Command::Reload,
+      Command::ResetAllFailed,
+      Command::ShowJobs,
+      Command::SessionStats,
+      Command::WhatChanged,
+      Command::ActionLog,
+      Command::DeltaAudit,
+      Command::Presets,
+      Command::PresetAll,
+      Command::OrderingCycles,
+      Command::BootPlan,
+      Command::BootFailures,
+      Command::BootTimes,
+      Command::BootTargets,
+      Command::SliceTree,
+      Command::ManagerEnvironment,
+      Command::NewService,
+      Command::NewTimer,
+      Command::RunTransient,
+      Command::CrontabImport,
+      Command::RunningSets,
+      Command::PowerMenu,
+      Command::SelfCheck,
+      Command::SwitchHost,
+      Command::SwitchUser,
+      Command::SwitchMachine,
+      Command::Themes,
+    ],
+  ),
+  (
+    "Anywhere",
+    &[
+      Command::Help,
+      Command::ForceQuit,
+      Command::Suspend,
+      Command::JumpToSearch,
+      Command::ToggleDebugLog,
+      Command::ScrollLogsDown,
+      Command::ScrollLogsUp,
+      Command::LogsTop,
+      Command::LogsBottom,
+    ],
+  ),
+];
+
+/// Keys that can't be remapped, by where they work
+const FIXED: &[(&str, &[(&str, &str)])] = &[
+  ("Pinned units", &[("1-9", "go to a pinned unit (in the unit list)"), ("alt-1-9", "go to a pinned unit's logs")]),
+  (
+    "Search box",
+    &[
+      ("Enter", "open the action menu for the selected unit"),
+      ("Down / Tab", "go to the unit list"),
+      ("Esc", "leave the search box"),
+    ],
+  ),
+  ("Log search", &[("ctrl-r", "toggle regex while searching"), ("Esc", "clear the search")]),
+  (
+    "Menus and prompts",
+    &[
+      ("j / k", "move"),
+      ("Enter / Space", "choose"),
+      ("letter keys", "choose the item marked with it"),
+      ("Esc", "close"),
+    ],
+  ),
+  (
+    "Forms",
+    &[
+      ("Tab / Shift-Tab", "next/previous field, or complete a unit name"),
+      ("← / →", "change a choice"),
+      ("Enter", "submit"),
+      ("Esc", "cancel"),
+    ],
+  ),
+  (
+    "Viewers",
+    &[
+      ("j / k", "scroll"),
+      ("Space / PageUp", "a page down/up"),
+      ("g / G", "top/bottom"),
+      ("y", "copy as plain text"),
+      ("Esc / q", "close"),
+    ],
+  ),
+  (
+    "Dependency tree",
+    &[
+      ("l / h", "expand/collapse"),
+      ("g", "go to the unit in the list"),
+      ("Space", "more actions"),
+      ("s / t / r", "start/stop/restart the unit"),
+      ("Esc / q", "close"),
+    ],
+  ),
+  ("Job queue", &[("j / k", "move"), ("c", "cancel the job"), ("Esc / q", "close")]),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+  /// Each key or sequence that does it. Empty for unbound commands
+  pub keys: Vec<String>,
+  pub description: &'static str,
+  /// The command's name in `[keybindings]`, for those that can be remapped
+  pub command: Option<&'static str>,
+}
+
+pub struct Section {
+  pub title: &'static str,
+  pub entries: Vec<Entry>,
+}
+
+/// Every section of the overlay, those of commands first
+pub fn sections(keymap: &Keymap) -> Vec<Section> {
+  let commands = GROUPS.iter().map(|(title, commands)| Section {
+    title,
+    entries: commands
+      .iter()
+      .map(|c| Entry { keys: keymap.sequences(*c), description: c.description(), command: Some(c.name()) })
+      .collect(),
+  });
+  let fixed = FIXED.iter().map(|(title, keys)| Section {
+    title,
+    entries: keys
+      .iter()
+      .map(|(keys, description)| Entry { keys: vec![keys.to_string()], description, command: None })
+      .collect(),
+  });
+  commands.chain(fixed).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use super::*;
+  use crate::keybindings::{Keys, COMMANDS};
+
+  #[test]
+  fn test_sections() {
+    // every command shows up, once
+    let listed = GROUPS.iter().flat_map(|(_, commands)| commands.iter()).collect::<Vec<_>>();
+    for (command, name, _) in COMMANDS {
+      assert_eq!(listed.iter().filter(|c| **c == command).count(), 1, "{name}");
+    }
+
+    let overrides = HashMap::from([(Command::Restart, Keys::One("o".into())), (Command::Quit, Keys::Many(vec![]))]);
+    let sections = sections(&Keymap::new(&overrides).unwrap());
+    let entry = |name| sections.iter().flat_map(|s| &s.entries).find(|e| e.command == Some(name)).unwrap();
+    assert_eq!(entry("restart").keys, ["o"]);
+    assert_eq!(entry("action-menu").keys, ["enter", "space"]);
+    assert!(entry("quit").keys.is_empty());
+    assert_eq!(sections.last().unwrap().title, "Job queue");
+  }
+}
@@ -0,0 +1,143 @@
+// Durations, sizes and rates as people read them, the same way in every pane: `2h 13m ago`, `4.5s`, `1.4 GiB`,
+// `12 MiB/day`. How many decimals to show and which decimal separator to use are set once at startup, from
+// `precision` and `locale` in config.toml; the locale otherwise comes from the environment like other programs'.
+
+use std::{sync::RwLock, time::Duration};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+  pub decimal_separator: char,
+  /// Decimals for sizes and for durations under a minute. Trailing zeros are left off
+  pub precision: usize,
+}
+
+impl Style {
+  pub const DEFAULT: Style = Style { decimal_separator: '.', precision: 1 };
+
+  /// `locale` like `de_DE.UTF-8`, falling back to `LC_ALL`, `LC_NUMERIC` and `LANG`
+  pub fn new(locale: Option<&str>, precision: Option<u8>) -> Self {
+    let locale = locale.map(str::to_string).or_else(|| {
+      ["LC_ALL", "LC_NUMERIC", "LANG"].iter().find_map(|v| std::env::var(v).ok().filter(|s| !s.is_empty()))
+    });
+    Self {
+      decimal_separator: locale.as_deref().map_or('.', decimal_separator),
+      precision: precision.map_or(Self::DEFAULT.precision, usize::from),
+    }
+  }
+}
+
+static STYLE: RwLock<Style> = RwLock::new(Style::DEFAULT);
+
+pub fn set_style(style: Style) {
+  *STYLE.write().unwrap() = style;
+}
+
+fn style() -> Style {
+  *STYLE.read().unwrap()
+}
+
+/// Languages that write `1,5` rather than `1.5`
+const DECIMAL_COMMA: &[&str] = &[
+  "bg", "ca", "cs", "da", "de", "el", "es", "et", "eu", "fi", "fr", "gl", "hr", "hu", "id", "is", "it", "lt", "lv",
+  "nb", "nl", "nn", "no", "pl", "pt", "ro", "ru", "sk", "sl", "sr", "sv", "tr", "uk", "vi",
+];
+
+fn decimal_separator(locale: &str) -> char {
+  let language = locale.split(['_', '.', '@', '-']).next().unwrap_or_default();
+  if DECIMAL_COMMA.contains(&language) {
+    ','
+  } else {
+    '.'
+  }
+}
+
+/// `value` with up to `precision` decimals
+fn number(value: f64, style: Style) -> String {
+  let text = format!("{value:.*}", style.precision);
+  let text = if text.contains('.') { text.trim_end_matches('0').trim_end_matches('.') } else { &text };
+  text.replace('.', &style.decimal_separator.to_string())
+}
+
+/// `512 B`, `1.5 KiB`, `20 MiB`
+pub fn size(bytes: u64) -> String {
+  size_with(bytes, style())
+}
+
+fn size_with(bytes: u64, style: Style) -> String {
+  const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+  let mut value = bytes as f64;
+  let mut unit = 0;
+  while value >= 1024.0 && unit < UNITS.len() - 1 {
+    value /= 1024.0;
+    unit += 1;
+  }
+  format!("{} {}", number(value, style), UNITS[unit])
+}
+
+/// Bytes per `period`, e.g. `12 MiB/day`
+pub fn rate(bytes: u64, period: &str) -> String {
+  format!("{}/{period}", size(bytes))
+}
+
+/// `12.5%`
+pub fn percent(value: f64) -> String {
+  format!("{}%", number(value, style()))
+}
+
+/// `288ms`, `4.5s`, `4m 10s`, `2h 13m`, `3d 4h`
+pub fn duration(duration: Duration) -> String {
+  duration_with(duration, style())
+}
+
+fn duration_with(duration: Duration, style: Style) -> String {
+  let seconds = duration.as_secs();
+  match seconds {
+    0 => format!("{}ms", duration.as_millis()),
+    1..60 => format!("{}s", number(duration.as_secs_f64(), style)),
+    60..3600 => format!("{}m {}s", seconds / 60, seconds % 60),
+    3600..86400 => format!("{}h {}m", seconds / 3600, seconds % 3600 / 60),
+    _ => format!("{}d {}h", seconds / 86400, seconds % 86400 / 3600),
+  }
+}
+
+/// `in 3h 12m` or `5m 2s ago`, for unix timestamps in seconds
+pub fn relative(at: i64, now: i64) -> String {
+  let apart = Duration::from_secs(at.abs_diff(now));
+  if at >= now {
+    format!("in {}", duration(apart))
+  } else {
+    format!("{} ago", duration(apart))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_size() {
+    assert_eq!(size_with(512, Style::DEFAULT), "512 B");
+    assert_eq!(size_with(1536, Style::DEFAULT), "1.5 KiB");
+    assert_eq!(size_with(20 << 20, Style::DEFAULT), "20 MiB");
+    assert_eq!(size_with(1_503_238_554, Style::DEFAULT), "1.4 GiB");
+    let german = Style::new(Some("de_DE.UTF-8"), Some(2));
+    assert_eq!(german, Style { decimal_separator: ',', precision: 2 });
+    assert_eq!(size_with(1_503_238_554, german), "1,4 GiB");
+    assert_eq!(size_with(1_288_490_189, german), "1,2 GiB");
+    assert_eq!(size_with(1_300_000_000, german), "1,21 GiB");
+    assert_eq!(Style::new(Some("en_US.UTF-8"), None), Style::DEFAULT);
+  }
+
+  #[test]
+  fn test_duration() {
+    assert_eq!(duration_with(Duration::from_micros(288_000), Style::DEFAULT), "288ms");
+    assert_eq!(duration_with(Duration::from_micros(4_512_000), Style::DEFAULT), "4.5s");
+    assert_eq!(duration_with(Duration::from_micros(4_512_000), Style { precision: 3, ..Style::DEFAULT }), "4.512s");
+    assert_eq!(duration_with(Duration::from_secs(45), Style::DEFAULT), "45s");
+    assert_eq!(duration_with(Duration::from_secs(250), Style::DEFAULT), "4m 10s");
+    assert_eq!(duration_with(Duration::from_secs(7260), Style::DEFAULT), "2h 1m");
+    assert_eq!(duration_with(Duration::from_secs(273_600), Style::DEFAULT), "3d 4h");
+    assert_eq!(relative(4000, 1300), "in 45m 0s");
+    assert_eq!(relative(1000, 1300), "5m 0s ago");
+  }
+}
@@ -0,0 +1,803 @@
+// Keys for the unit list, the log pane and those that work anywhere, remappable in the `[keybindings]` table of
+// config.toml. Each command is bound to one or more key sequences, written as chords separated by spaces: `"q"`,
+// `"ctrl-r"`, `"g p"`. Binding a command replaces its default keys; an empty list leaves it unbound. Menus, text boxes
+// and the full-screen viewers, forms and trees keep keys of their own.
+
+use std::{collections::HashMap, fmt};
+
+use anyhow::{bail, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use itertools::Itertools;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Command {
+  Quit,
+  Next,
+  Previous,
+  Search,
+  HalfPageDown,
+  HalfPageUp,
+  PageDown,
+  PageUp,
+  NextFailed,
+  NextInstance,
+  Instantiate,
+  FailedView,
+  ToggleFavorite,
+  ResetFailed,
+  ResetAllFailed,
+  CycleUnitType,
+  CycleSort,
+  ActionMenu,
+  Start,
+  Stop,
+  Restart,
+  Reload,
+  Kill,
+  Enable,
+  Disable,
+  Reenable,
+  ApplyPreset,
+  PresetAll,
+  ToggleMask,
+  ToggleFreeze,
+  Clean,
+  EditUnitFile,
+  EditDropIn,
+  CopyUnitFilePath,
+  SetProperties,
+  ShowUnitDelta,
+  VerifyUnitFile,
+  ShowEnvironment,
+  SetEnvironment,
+  ShowSandboxing,
+  ShowProcesses,
+  ShowListening,
+  ShowProperties,
+  ShowJobs,
+  ShowUnitFiles,
+  FollowMergedLogs,
+  ShowRunHistory,
+  ShowTimeline,
+  ShowBootWait,
+  ShowBootContext,
+  ShowJournalUsage,
+  ExportGraph,
+  FollowLogsInPane,
+  ShowDocumentation,
+  EnableAccounting,
+  DependencyTree,
+  DeltaAudit,
+  Presets,
+  OrderingCycles,
+  BootPlan,
+  BootFailures,
+  BootTimes,
+  SliceTree,
+  SessionStats,
+  WhatChanged,
+  ActionLog,
+  SelfCheck,
+  PowerMenu,
+  BootTargets,
+  NewService,
+  RunTransient,
+  NewTimer,
+  CrontabImport,
+  ManagerEnvironment,
+  SwitchHost,
+  SwitchUser,
+  SwitchMachine,
+  CycleScope,
+  CycleLogBoot,
+  PickLogBoot,
+  ToggleLogAudit,
+  ToggleCurrentRun,
+  CycleLogPriority,
+  CycleLogTimestamps,
+  LogsInPager,
+  CopyLogs,
+  PipeLogs,
+  RecordMacro,
+  ReplayMacro,
+  Mark,
+  ClearMarks,
+  DryRun,
+  RunningSets,
+  Themes,
+  FocusLogs,
+  CommandBar,
+  GoToTrigger,
+  GoToMount,
+  ToggleSplit,
+  GrowList,
+  ShrinkList,
+  MaximizeLogs,
+  WatchFiles,
+  ShowCrash,
+  PluginTab,
+  ForceQuit,
+  Suspend,
+  JumpToSearch,
+  ToggleDebugLog,
+  Help,
+  ScrollLogsDown,
+  ScrollLogsUp,
+  LogsTop,
+  LogsBottom,
+  LogDown,
+  LogUp,
+  LeaveLogs,
+  LogMaximize,
+  LogSearch,
+  LogNextMatch,
+  LogPreviousMatch,
+  LogCopy,
+  LogCopyLine,
+  LogSave,
+  LogFilters,
+  LogInspect,
+  LogWrap,
+  LogTimestamps,
+  LogLeft,
+  LogRight,
+  LogLineStart,
+}
+
+/// Where a command's keys work
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+  /// Every screen, even while typing
+  Anywhere,
+  /// Everywhere but the full-screen viewers, forms and trees
+  MainScreen,
+  UnitList,
+  LogPane,
+}
+
+impl Context {
+  /// Whether the same key could mean commands from both
+  fn overlaps(self, other: Context) -> bool {
+    match (self, other) {
+      (Context::UnitList, Context::LogPane) | (Context::LogPane, Context::UnitList) => false,
+      (Context::Anywhere | Context::MainScreen, _) | (_, Context::Anywhere | Context::MainScreen) => true,
+      _ => self == other,
+    }
+  }
+}
+
+/// Every command, its name in the config file, and its default keys
+pub const COMMANDS: &[(Command, &str, &[&str])] = &[
+  (Command::Quit, "quit", &["q"]),
+  (Command::Next, "next", &["j", "down"]),
+  (Command::Previous, "previous", &["k", "up"]),
+  (Command::Search, "search", &["/"]),
+  (Command::HalfPageDown, "half-page-down", &["J"]),
+  (Command::HalfPageUp, "half-page-up", &["K"]),
+  (Command::PageDown, "page-down", &["alt-j"]),
+  (Command::PageUp, "page-up", &["alt-k"]),
+  (Command::NextFailed, "next-failed", &["!"]),
+  (Command::NextInstance, "next-instance", &["alt-n"]),
+  (Command::Instantiate, "instantiate", &["I"]),
+  (Command::FailedView, "failed-view", &["x"]),
+  (Command::ToggleFavorite, "toggle-favorite", &["*"]),
+  (Command::ResetFailed, "reset-failed", &[]),
+  (Command::ResetAllFailed, "reset-all-failed", &["X"]),
+  (Command::CycleUnitType, "cycle-unit-type", &["f"]),
+  (Command::CycleSort, "cycle-sort", &["s"]),
+  (Command::ActionMenu, "action-menu", &["enter", "space"]),
+  (Command::Start, "start", &[]),
+  (Command::Stop, "stop", &[]),
+  (Command::Restart, "restart", &[]),
+  (Command::Reload, "reload", &["r"]),
+  (Command::Kill, "kill", &[]),
+  (Command::Enable, "enable", &[]),
+  (Command::Disable, "disable", &[]),
+  (Command::Reenable, "reenable", &[]),
+  (Command::ApplyPreset, "apply-preset", &[]),
+  (Command::PresetAll, "preset-all", &[]),
+  (Command::ToggleMask, "toggle-mask", &[]),
+  (Command::ToggleFreeze, "toggle-freeze", &[]),
+  (Command::Clean, "clean", &[]),
+  (Command::EditUnitFile, "edit-unit-file", &["e"]),
+  (Command::EditDropIn, "edit-drop-in", &[]),
+  (Command::CopyUnitFilePath, "copy-unit-file-path", &[]),
+  (Command::SetProperties, "set-properties", &[]),
+  (Command::ShowUnitDelta, "show-unit-delta", &[]),
+  (Command::VerifyUnitFile, "verify-unit-file", &[]),
+  (Command::ShowEnvironment, "show-environment", &[]),
+  (Command::SetEnvironment, "set-environment", &[]),
+  (Command::ShowSandboxing, "show-sandboxing", &[]),
+  (Command::ShowProcesses, "show-processes", &[]),
+  (Command::ShowListening, "show-listening", &[]),
+  (Command::ShowProperties, "show-properties", &[]),
+  (Command::ShowJobs, "show-jobs", &["Q"]),
+  (Command::ShowUnitFiles, "show-unit-files", &[]),
+  (Command::FollowMergedLogs, "follow-merged-logs", &[]),
+  (Command::ShowRunHistory, "show-run-history", &[]),
+  (Command::ShowTimeline, "show-timeline", &[]),
+  (Command::ShowBootWait, "show-boot-wait", &[]),
+  (Command::ShowBootContext, "show-boot-context", &[]),
+  (Command::ShowJournalUsage, "show-journal-usage", &[]),
+  (Command::ExportGraph, "export-graph", &[]),
+  (Command::FollowLogsInPane, "follow-logs-in-pane", &[]),
+  (Command::ShowDocumentation, "show-documentation", &["M"]),
+  (Command::EnableAccounting, "enable-accounting", &["A"]),
+  (Command::DependencyTree, "dependency-tree", &["g"]),
+  (Command::DeltaAudit, "delta-audit", &["D"]),
+  (Command::Presets, "presets", &["P"]),
+  (Command::OrderingCycles, "ordering-cycles", &["O"]),
+  (Command::BootPlan, "boot-plan", &["B"]),
+  (Command::BootFailures, "boot-failures", &["F"]),
+  (Command::BootTimes, "boot-times", &["w"]),
+  (Command::SliceTree, "slice-tree", &["S"]),
+  (Command::SessionStats, "session-stats", &["H"]),
+  (Command::WhatChanged, "what-changed", &["alt-w"]),
+  (Command::ActionLog, "action-log", &["L"]),
+  (Command::SelfCheck, "self-check", &["alt-c"]),
+  (Command::PowerMenu, "power-menu", &["alt-p"]),
+  (Command::BootTargets, "boot-targets", &["alt-d"]),
+  (Command::NewService, "new-service", &["n"]),
+  (Command::RunTransient, "run-transient", &["alt-r"]),
+  (Command::NewTimer, "new-timer", &["T"]),
+  (Command::CrontabImport, "crontab-import", &["C"]),
+  (Command::ManagerEnvironment, "manager-environment", &["E"]),
+  (Command::SwitchHost, "switch-host", &["R"]),
+  (Command::SwitchUser, "switch-user", &["U"]),
+  (Command::SwitchMachine, "switch-machine", &["N"]),
+  (Command::CycleScope, "cycle-scope", &["u"]),
+  (Command::CycleLogBoot, "cycle-log-boot", &["b"]),
+  (Command::PickLogBoot, "pick-log-boot", &["alt-b"]),
+  (Command::ToggleLogAudit, "toggle-log-audit", &["a"]),
+  (Command::ToggleCurrentRun, "toggle-current-run", &["i"]),
+  (Command::CycleLogPriority, "cycle-log-priority", &["p"]),
+  (Command::CycleLogTimestamps, "cycle-log-timestamps", &["alt-t"]),
+  (Command::LogsInPager, "logs-in-pager", &["o"]),
+  (Command::CopyLogs, "copy-logs", &["y"]),
+  (Command::PipeLogs, "pipe-logs", &["|"]),
+  (Command::RecordMacro, "record-macro", &["m"]),
+  (Command::ReplayMacro, "replay-macro", &["@"]),
+  (Command::Mark, "mark", &["v"]),
+  (Command::ClearMarks, "clear-marks", &["V"]),
+  (Command::DryRun, "dry-run", &["d"]),
+  (Command::RunningSets, "running-sets", &["W"]),
+  (Command::Themes, "themes", &["c"]),
+  (Command::FocusLogs, "focus-logs", &["l"]),
+  (Command::CommandBar, "command-bar", &[":"]),
+  (Command::GoToTrigger, "go-to-trigger", &["t"]),
+  (Command::GoToMount, "go-to-mount", &["alt-m"]),
+  (Command::ToggleSplit, "toggle-split", &["alt-s"]),
+  (Command::GrowList, "grow-list", &[">"]),
+  (Command::ShrinkList, "shrink-list", &["<"]),
+  (Command::MaximizeLogs, "maximize-logs", &["z"]),
+  (Command::WatchFiles, "watch-files", &[]),
+  (Command::ShowCrash, "show-crash", &["alt-x"]),
+  (Command::PluginTab, "plugin-tab", &["tab"]),
+  (Command::ForceQuit, "force-quit", &["ctrl-c", "ctrl-q"]),
+  (Command::Suspend, "suspend", &["ctrl-z"]),
+  (Command::JumpToSearch, "jump-to-search", &["ctrl-f"]),
+  (Command::ToggleDebugLog, "toggle-debug-log", &["ctrl-l"]),
+  (Command::Help, "help", &["?", "f1"]),
+  (Command::ScrollLogsDown, "scroll-logs-down", &["ctrl-d", "pagedown"]),
+  (Command::ScrollLogsUp, "scroll-logs-up", &["ctrl-u", "pageup"]),
+  (Command::LogsTop, "logs-top", &["home"]),
+  (Command::LogsBottom, "logs-bottom", &["end"]),
+  (Command::LogDown, "log-down", &["j", "down"]),
+  (Command::LogUp, "log-up", &["k", "up"]),
+  (Command::LeaveLogs, "leave-logs", &["esc", "tab", "h"]),
+  (Command::LogMaximize, "log-maximize", &["z"]),
+  (Command::LogSearch, "log-search", &["/"]),
+  (Command::LogNextMatch, "log-next-match", &["n"]),
+  (Command::LogPreviousMatch, "log-previous-match", &["N"]),
+  (Command::LogCopy, "log-copy", &["y"]),
+  (Command::LogCopyLine, "log-copy-line", &["Y"]),
+  (Command::LogSave, "log-save", &["w"]),
+  (Command::LogFilters, "log-filters", &["f"]),
+  (Command::LogInspect, "log-inspect", &["enter"]),
+  (Command::LogWrap, "log-wrap", &["W"]),
+  (Command::LogTimestamps, "log-timestamps", &["t"]),
+  (Command::LogLeft, "log-left", &["left"]),
+  (Command::LogRight, "log-right", &["right"]),
+  (Command::LogLineStart, "log-line-start", &["0"]),
+];
+
+impl Command {
+  pub fn name(self) -> &'static str {
+    COMMANDS.iter().find(|(c, ..)| *c == self).map(|(_, name, _)| *name).unwrap()
+  }
+
+  /// What it does, for the help overlay
+  pub fn description(self) -> &'static str {
+    match self {
+      Command::Quit => "quit",
+      Command::Next => "next unit",
+      Command::Previous => "previous unit, or the search box from the top",
+      Command::Search => "search units, or filter by property like Restart=always",
+      Command::HalfPageDown => "half a page down",
+      Command::HalfPageUp => "half a page up",
+      Command::PageDown => "a page down",
+      Command::PageUp => "a page up",
+      Command::NextFailed => "next failed unit",
+      Command::NextInstance => "next instance of the same template",
+      Command::Instantiate => "start or enable a new instance of a template unit",
+      Command::FailedView => "list only failed units",
+      Command::ToggleFavorite => "star the unit, to list it first",
+      Command::ResetFailed => "reset the unit's failed state",
+      Command::ResetAllFailed => "reset every failed unit",
+      Command::CycleUnitType => "show one type of unit at a time",
+      Command::CycleSort => "sort by name/state/load state/memory/CPU/tasks/I/O/network/last change",
+      Command::ActionMenu => "open the action menu",
+      Command::Start => "start the unit",
+      Command::Stop => "stop the unit",
+      Command::Restart => "restart the unit",
+      Command::Reload => "daemon-reload, after unit files change on disk",
+      Command::Kill => "send the unit's processes a signal",
+      Command::Enable => "enable the unit",
+      Command::Disable => "disable the unit",
+      Command::Reenable => "reenable the unit, putting its symlinks back",
+      Command::ApplyPreset => "enable or disable the unit as the preset files say",
+      Command::PresetAll => "apply the presets to every unit file, like systemctl preset-all",
+      Command::ToggleMask => "mask or unmask the unit",
+      Command::ToggleFreeze => "freeze or thaw the unit's processes",
+      Command::Clean => "clean the unit's cache, state or logs",
+      Command::EditUnitFile => "edit the unit file",
+      Command::EditDropIn => "edit a drop-in for the unit, like systemctl edit",
+      Command::CopyUnitFilePath => "copy the unit file's path",
+      Command::SetProperties => "set resource limits and other properties",
+      Command::ShowUnitDelta => "show local changes to the unit file",
+      Command::VerifyUnitFile => "check the unit file with systemd-analyze verify",
+      Command::ShowEnvironment => "show the service's environment",
+      Command::SetEnvironment => "override the service's environment",
+      Command::ShowSandboxing => "show how sandboxed the service is",
+      Command::ShowProcesses => "show the unit's processes",
+      Command::ShowListening => "show the ports and sockets it listens on",
+      Command::ShowProperties => "browse all of the unit's properties",
+      Command::ShowJobs => "show queued jobs and what they're waiting for",
+      Command::ShowUnitFiles => "show the unit file and drop-ins",
+      Command::FollowMergedLogs => "follow the logs of the marked units together",
+      Command::ShowRunHistory => "show the unit's past runs",
+      Command::ShowTimeline => "show the unit's activity timeline",
+      Command::ShowBootWait => "show what the unit waited on at boot",
+      Command::ShowBootContext => "show what else failed around the unit at boot",
+      Command::ShowJournalUsage => "show how much journal space the unit's logs use",
+      Command::ExportGraph => "export the unit's dependency graph",
+      Command::FollowLogsInPane => "follow the unit's logs in a tmux or zellij pane",
+      Command::ShowDocumentation => "open the unit's documentation (man page or website)",
+      Command::EnableAccounting => "turn on I/O and network accounting",
+      Command::DependencyTree => "browse the unit's dependency tree",
+      Command::DeltaAudit => "audit local changes to unit files",
+      Command::Presets => "show which preset rules decide what",
+      Command::OrderingCycles => "find ordering cycles",
+      Command::BootPlan => "show what starts at next boot",
+      Command::BootFailures => "show failures and startup time of past boots",
+      Command::BootTimes => "show what slowed this boot down",
+      Command::SliceTree => "show resource use by slice",
+      Command::SessionStats => "show state changes seen this session",
+      Command::WhatChanged => "show what changed between refreshes lately",
+      Command::ActionLog => "show the log of actions taken",
+      Command::SelfCheck => "check what works on this machine",
+      Command::PowerMenu => "reboot, power off or suspend the machine",
+      Command::BootTargets => "change the default target, or isolate one",
+      Command::NewService => "create a new service",
+      Command::RunTransient => "run a command as a transient unit (systemd-run)",
+      Command::NewTimer => "create a new timer",
+      Command::CrontabImport => "convert a crontab to timers",
+      Command::ManagerEnvironment => "view/edit the manager environment",
+      Command::SwitchHost => "manage another host over ssh",
+      Command::SwitchUser => "as root, show another user's user units",
+      Command::SwitchMachine => "manage a container's units (machinectl)",
+      Command::CycleScope => "switch between system and user units, or both",
+      Command::CycleLogBoot => "cycle logs between all/current/previous boot",
+      Command::PickLogBoot => "pick an earlier boot to show logs from",
+      Command::ToggleLogAudit => "include SELinux/AppArmor denials in logs",
+      Command::ToggleCurrentRun => "only show logs from the unit's current run",
+      Command::CycleLogPriority => "cycle logs between all/info/warning/err and up",
+      Command::CycleLogTimestamps => "cycle log times: short/relative/ISO/UTC/hidden",
+      Command::LogsInPager => "open the logs in $PAGER",
+      Command::CopyLogs => "copy the logs",
+      Command::PipeLogs => "pipe the logs through a shell command",
+      Command::RecordMacro => "start/stop recording a macro",
+      Command::ReplayMacro => "replay a macro",
+      Command::Mark => "mark the unit for bulk start/stop/restart/enable",
+      Command::ClearMarks => "clear marks",
+      Command::DryRun => "toggle dry run: show what actions would do",
+      Command::RunningSets => "save what's running, or restore a saved set",
+      Command::Themes => "switch to another color theme",
+      Command::FocusLogs => "go to the log pane",
+      Command::CommandBar => "type a systemctl command, e.g. mask foo.timer",
+      Command::GoToTrigger => "go to the timer/socket/path that starts the unit",
+      Command::GoToMount => "go to a mount or device the unit needs",
+      Command::ToggleSplit => "put the unit list beside the details and logs, or above them",
+      Command::GrowList => "give the unit list more of the screen",
+      Command::ShrinkList => "give the unit list less of the screen",
+      Command::MaximizeLogs => "show only the logs, full screen",
+      Command::WatchFiles => "restart the unit whenever files change, or stop",
+      Command::ShowCrash => "show the unit's OOM kills and core dumps, with a backtrace",
+      Command::PluginTab => "run the next plugin, showing its output in the details pane, or go back to the details",
+      Command::ForceQuit => "quit, from any screen",
+      Command::Suspend => "suspend to the shell",
+      Command::JumpToSearch => "search units, from any screen",
+      Command::ToggleDebugLog => "show or hide the debug logger pane",
+      Command::Help => "open or close this help",
+      Command::ScrollLogsDown => "scroll the logs down",
+      Command::ScrollLogsUp => "scroll the logs up",
+      Command::LogsTop => "scroll the logs to the top",
+      Command::LogsBottom => "scroll the logs to the bottom",
+      Command::LogDown => "scroll down",
+      Command::LogUp => "scroll up",
+      Command::LeaveLogs => "back to the unit list",
+      Command::LogMaximize => "maximize the log pane, or restore the layout",
+      Command::LogSearch => "search the logs",
+      Command::LogNextMatch => "next match",
+      Command::LogPreviousMatch => "previous match",
+      Command::LogCopy => "copy the logs",
+      Command::LogCopyLine => "copy the line at the top",
+      Command::LogSave => "save the logs to a file",
+      Command::LogFilters => "filter the logs: keep the lines matching a pattern, or drop them with !pattern",
+      Command::LogInspect => "show every field of the entry at the top, and filter by one",
+      Command::LogWrap => "wrap or unwrap long lines",
+      Command::LogTimestamps => "cycle log times",
+      Command::LogLeft => "scroll sideways to the left, when unwrapped",
+      Command::LogRight => "scroll sideways to the right, when unwrapped",
+      Command::LogLineStart => "back to the start of the lines, when unwrapped",
+    }
+  }
+
+  pub fn context(self) -> Context {
+    match self {
+      Command::ForceQuit | Command::Suspend | Command::JumpToSearch | Command::ToggleDebugLog => Context::Anywhere,
+      Command::Help | Command::ScrollLogsDown | Command::ScrollLogsUp | Command::LogsTop | Command::LogsBottom => {
+        Context::MainScreen
+      },
+      Command::LogDown
+      | Command::LogUp
+      | Command::LeaveLogs
+      | Command::LogMaximize
+      | Command::LogSearch
+      | Command::LogNextMatch
+      | Command::LogPreviousMatch
+      | Command::LogCopy
+      | Command::LogCopyLine
+      | Command::LogSave
+      | Command::LogFilters
+      | Command::LogInspect
+      | Command::LogWrap
+      | Command::LogTimestamps
+      | Command::LogLeft
+      | Command::LogRight
+      | Command::LogLineStart => Context::LogPane,
+      _ => Context::UnitList,
+    }
+  }
+
+  /// Whether it changes units, unit files or the machine, or runs a command that could, so read-only mode turns it off
+  pub fn changes_system(self) -> bool {
+    matches!(
+      self,
+      Command::Instantiate
+        | Command::ResetFailed
+        | Command::ResetAllFailed
+        | Command::Start
+        | Command::Stop
+        | Command::Restart
+        | Command::Reload
+        | Command::Kill
+        | Command::Enable
+        | Command::Disable
+        | Command::Reenable
+        | Command::ApplyPreset
+        | Command::PresetAll
+        | Command::ToggleMask
+        | Command::ToggleFreeze
+        | Command::Clean
+        | Command::EditUnitFile
+        | Command::EditDropIn
+        | Command::SetProperties
+        | Command::SetEnvironment
+        | Command::EnableAccounting
+        | Command::PowerMenu
+        | Command::BootTargets
+        | Command::NewService
+        | Command::RunTransient
+        | Command::NewTimer
+        | Command::CrontabImport
+        | Command::ManagerEnvironment
+        | Command::WatchFiles
+        | Command::PipeLogs
+    )
+  }
+}
+
+impl TryFrom<String> for Command {
+  type Error = String;
+
+  fn try_from(name: String) -> Result<Self, Self::Error> {
+    COMMANDS.iter().find(|(_, n, _)| *n == name).map(|(c, ..)| *c).ok_or_else(|| format!("unknown command `{name}`"))
+  }
+}
+
+impl<'de> Deserialize<'de> for Command {
+  fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    Command::try_from(String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+  }
+}
+
+/// One or more key sequences, as written in the config file
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum Keys {
+  One(String),
+  Many(Vec<String>),
+}
+
+impl Keys {
+  fn sequences(&self) -> Vec<&str> {
+    match self {
+      Keys::One(keys) => vec![keys.as_str()],
+      Keys::Many(keys) => keys.iter().map(String::as_str).collect(),
+    }
+  }
+}
+
+/// A key with its modifiers. Shift is part of the character for letters and symbols, so it's only kept for other keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chord {
+  pub code: KeyCode,
+  pub modifiers: KeyModifiers,
+}
+
+impl Chord {
+  pub fn parse(chord: &str) -> Result<Chord> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = chord;
+    // the key itself may be `-`, so only split off prefixes that are known modifiers
+    while let Some((prefix, after)) = rest.split_once('-').filter(|(_, after)| !after.is_empty()) {
+      modifiers |= match prefix.to_ascii_lowercase().as_str() {
+        "ctrl" => KeyModifiers::CONTROL,
+        "alt" | "meta" => KeyModifiers::ALT,
+        "shift" => KeyModifiers::SHIFT,
+        _ => break,
+      };
+      rest = after;
+    }
+    let code = match rest.to_ascii_lowercase().as_str() {
+      "enter" | "return" => KeyCode::Enter,
+      "space" => KeyCode::Char(' '),
+      "tab" => KeyCode::Tab,
+      "backtab" => KeyCode::BackTab,
+      "esc" | "escape" => KeyCode::Esc,
+      "backspace" => KeyCode::Backspace,
+      "delete" | "del" => KeyCode::Delete,
+      "insert" => KeyCode::Insert,
+      "up" => KeyCode::Up,
+      "down" => KeyCode::Down,
+      "left" => KeyCode::Left,
+      "right" => KeyCode::Right,
+      "home" => KeyCode::Home,
+      "end" => KeyCode::End,
+      "pageup" => KeyCode::PageUp,
+      "pagedown" => KeyCode::PageDown,
+      lower if lower.len() > 1 && lower.starts_with('f') && lower[1..].parse::<u8>().is_ok() => {
+        KeyCode::F(lower[1..].parse().unwrap())
+      },
+      _ => {
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+          (Some(c), None) => KeyCode::Char(c),
+          _ => bail!("`{chord}` isn't a key"),
+        }
+      },
+    };
+    Ok(Chord::new(code, modifiers))
+  }
+
+  fn new(code: KeyCode, modifiers: KeyModifiers) -> Chord {
+    let modifiers = match code {
+      KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+      _ => modifiers,
+    };
+    Chord { code, modifiers: modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT) }
+  }
+}
+
+impl From<KeyEvent> for Chord {
+  fn from(key: KeyEvent) -> Chord {
+    Chord::new(key.code, key.modifiers)
+  }
+}
+
+impl fmt::Display for Chord {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for (modifier, name) in
+      [(KeyModifiers::CONTROL, "ctrl-"), (KeyModifiers::ALT, "alt-"), (KeyModifiers::SHIFT, "shift-")]
+    {
+      if self.modifiers.contains(modifier) {
+        f.write_str(name)?;
+      }
+    }
+    match self.code {
+      KeyCode::Char(' ') => f.write_str("space"),
+      KeyCode::Char(c) => write!(f, "{c}"),
+      KeyCode::F(n) => write!(f, "f{n}"),
+      // Enter, PageUp, BackTab...
+      code => f.write_str(&format!("{code:?}").to_lowercase()),
+    }
+  }
+}
+
+pub fn parse_sequence(sequence: &str) -> Result<Vec<Chord>> {
+  let chords = sequence.split_whitespace().map(Chord::parse).collect::<Result<Vec<_>>>()?;
+  if chords.is_empty() {
+    bail!("empty key sequence");
+  }
+  Ok(chords)
+}
+
+pub fn format_sequence(sequence: &[Chord]) -> String {
+  sequence.iter().map(Chord::to_string).collect::<Vec<_>>().join(" ")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lookup {
+  Found(Command),
+  /// The keys so far start one or more sequences; wait for the next
+  Prefix,
+  None,
+}
+
+#[derive(Debug, Clone)]
+pub struct Keymap {
+  bindings: Vec<(Vec<Chord>, Command)>,
+}
+
+impl Keymap {
+  /// The defaults with `overrides` from the config file applied. A key the user bound is taken away from whatever
+  /// command had it by default where both work. Commands that work anywhere take single keys, not sequences
+  pub fn new(overrides: &HashMap<Command, Keys>) -> Result<Keymap> {
+    let mut bindings: Vec<(Vec<Chord>, Command)> = vec![];
+    // in a fixed order, so a clash is reported the same way every time
+    for (command, keys) in overrides.iter().sorted_by_key(|(command, _)| command.name()) {
+      for sequence in keys.sequences() {
+        let sequence = parse_sequence(sequence).map_err(|e| anyhow::anyhow!("keybindings.{}: {e}", command.name()))?;
+        if sequence.len() > 1 && matches!(command.context(), Context::Anywhere | Context::MainScreen) {
+          bail!("keybindings.{}: only the unit list's and log pane's commands take key sequences", command.name());
+        }
+        let clash = bindings.iter().find(|(s, c)| {
+          c != command
+            && c.context().overlaps(command.context())
+            && (s.starts_with(&sequence) || sequence.starts_with(s))
+        });
+        if let Some((taken, other)) = clash {
+          bail!(
+            "keybindings.{} and keybindings.{} clash: `{}` and `{}` can't both work",
+            other.name(),
+            command.name(),
+            format_sequence(taken),
+            format_sequence(&sequence)
+          );
+        }
+        bindings.push((sequence, *command));
+      }
+    }
+    let user_bindings = bindings.len();
+    for (command, _, defaults) in COMMANDS {
+      if overrides.contains_key(command) {
+        continue;
+      }
+      for sequence in *defaults {
+        let sequence = parse_sequence(sequence).unwrap();
+        let taken = bindings[..user_bindings].iter().any(|(s, c)| {
+          c.context().overlaps(command.context()) && (s.starts_with(&sequence) || sequence.starts_with(s))
+        });
+        if !taken {
+          bindings.push((sequence, *command));
+        }
+      }
+    }
+    Ok(Keymap { bindings })
+  }
+
+  /// The command `keys` are bound to among those working in `context`
+  pub fn lookup(&self, context: Context, keys: &[Chord]) -> Lookup {
+    let mut bindings = self.bindings.iter().filter(|(_, command)| command.context() == context);
+    if let Some((_, command)) = bindings.clone().find(|(sequence, _)| sequence == keys) {
+      return Lookup::Found(*command);
+    }
+    if bindings.any(|(sequence, _)| sequence.starts_with(keys)) {
+      return Lookup::Prefix;
+    }
+    Lookup::None
+  }
+
+  /// The first key sequence for `command`, for hints
+  pub fn describe(&self, command: Command) -> Option<String> {
+    self.sequences(command).into_iter().next()
+  }
+
+  /// Every key sequence bound to `command`, for the help overlay
+  pub fn sequences(&self, command: Command) -> Vec<String> {
+    self.bindings.iter().filter(|(_, c)| *c == command).map(|(sequence, _)| format_sequence(sequence)).collect()
+  }
+}
+
+impl Default for Keymap {
+  fn default() -> Self {
+    Keymap::new(&HashMap::new()).unwrap()
+  }
+}
+
+/// A config file with every keybinding at its default, for `--print-default-config`
+pub fn default_config() -> String {
+  let mut config = String::from(
+    "# systemctl-tui keybindings. Keys are chords separated by spaces, e.g. \"ctrl-r\" or \"g p\"; a list binds\n\
+     # several. Binding a command replaces its default keys, and [] unbinds it. Those named log-* work in the log\n\
+     # pane; force-quit through logs-bottom work anywhere and take single keys. See the README for other settings.\n\
+     [keybindings]\n",
+  );
+  for (_, name, defaults) in COMMANDS {
+    let keys = defaults.iter().map(|k| format!("\"{k}\"")).collect::<Vec<_>>();
+    match keys.as_slice() {
+      [] => config.push_str(&format!("# {name} = []\n")),
+      [key] => config.push_str(&format!("{name} = {key}\n")),
+      keys => config.push_str(&format!("{name} = [{}]\n", keys.join(", "))),
+    }
+  }
+  config
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_chord() {
+    assert_eq!(Chord::parse("ctrl-r").unwrap(), Chord { code: KeyCode::Char('r'), modifiers: KeyModifiers::CONTROL });
+    assert_eq!(Chord::parse("-").unwrap().code, KeyCode::Char('-'));
+    assert_eq!(Chord::parse("alt--").unwrap(), Chord { code: KeyCode::Char('-'), modifiers: KeyModifiers::ALT });
+    assert_eq!(Chord::parse("F5").unwrap().code, KeyCode::F(5));
+    assert!(Chord::parse("ctrl-nope").is_err());
+    // a shifted letter arrives as the capital with SHIFT set
+    assert_eq!(Chord::from(KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT)), Chord::parse("G").unwrap());
+    assert_eq!(Chord::parse("ctrl-space").unwrap().to_string(), "ctrl-space");
+  }
+
+  #[test]
+  fn test_keymap() {
+    let config: HashMap<Command, Keys> =
+      toml::from_str("restart = \"o\"\nlogs-in-pager = [\"g p\", \"ctrl-o\"]\nquit = []").unwrap();
+    let keymap = Keymap::new(&config).unwrap();
+    let keys = |s: &str| parse_sequence(s).unwrap();
+    let list = |s: &str| keymap.lookup(Context::UnitList, &keys(s));
+    assert_eq!(list("o"), Lookup::Found(Command::Restart));
+    assert_eq!(list("g p"), Lookup::Found(Command::LogsInPager));
+    // `g` on its own would cut off `g p`, so the dependency tree loses it
+    assert_eq!(list("g"), Lookup::Prefix);
+    assert_eq!(list("q"), Lookup::None);
+    assert_eq!(list("j"), Lookup::Found(Command::Next));
+    // the log pane has keys of its own
+    assert_eq!(keymap.lookup(Context::LogPane, &keys("j")), Lookup::Found(Command::LogDown));
+    assert_eq!(keymap.lookup(Context::Anywhere, &keys("ctrl-c")), Lookup::Found(Command::ForceQuit));
+    assert_eq!(keymap.describe(Command::LogsInPager).unwrap(), "g p");
+    assert_eq!(keymap.describe(Command::DependencyTree), None);
+
+    assert!(toml::from_str::<HashMap<Command, Keys>>("explode = \"x\"").is_err());
+    // a key bound to work anywhere is taken from the unit list too, but a log pane key isn't
+    let config: HashMap<Command, Keys> = toml::from_str("force-quit = \"x\"\nlog-copy = \"o\"").unwrap();
+    let keymap = Keymap::new(&config).unwrap();
+    assert_eq!(keymap.describe(Command::FailedView), None);
+    assert_eq!(keymap.describe(Command::LogsInPager).unwrap(), "o");
+    assert!(Keymap::new(&toml::from_str("help = \"g h\"").unwrap()).is_err());
+    // two overrides on the same keys, or one cutting off the other, are an error rather than a coin toss
+    assert!(Keymap::new(&toml::from_str("start = \"x\"\nstop = \"x\"").unwrap()).is_err());
+    assert!(Keymap::new(&toml::from_str("start = \"x\"\nstop = \"x y\"").unwrap()).is_err());
+    assert!(Keymap::new(&toml::from_str("start = \"x\"\nlog-copy = \"x\"").unwrap()).is_ok());
+    let defaults: toml::Table = toml::from_str(&default_config()).unwrap();
+    let defaults: HashMap<Command, Keys> = defaults["keybindings"].clone().try_into().unwrap();
+    assert_eq!(defaults[&Command::ActionMenu], Keys::Many(vec!["enter".into(), "space".into()]));
+    // unbound commands are listed commented out, and uncommenting them keeps them unbound
+    let uncommented = default_config().replace("# ", "").lines().filter(|l| l.ends_with("= []")).join("\n");
+    assert!(!uncommented.is_empty());
+    assert!(Keymap::new(&toml::from_str(&uncommented).unwrap()).is_ok());
+  }
+}
@@ -0,0 +1,118 @@
+// Recording a run of keystrokes and playing it back, once or against each listed unit in turn. Keys are replayed one
+// at a time at a steady pace rather than all at once, so whatever a key opens has had time to load before the next
+// key lands on it.
+
+use std::{collections::VecDeque, time::Duration};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::systemd::UnitId;
+
+/// How long replay waits after each key before pressing the next one
+pub const REPLAY_KEY_INTERVAL: Duration = Duration::from_millis(150);
+
+/// A macro being played back
+#[derive(Debug, Clone)]
+pub struct Replay {
+  pub keys: Vec<KeyEvent>,
+  /// Index of the key to press next
+  pub next: usize,
+  /// Units still to replay against, the current one first. Empty when replaying on whatever is selected
+  pub units: VecDeque<UnitId>,
+  /// Whether the current pass has selected its unit yet
+  selected: bool,
+}
+
+/// What a replay step should do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+  /// Select this unit before pressing the first key
+  Select(UnitId),
+  Press(KeyEvent),
+  Done,
+}
+
+impl Replay {
+  pub fn new(keys: Vec<KeyEvent>, units: Vec<UnitId>) -> Self {
+    Self { keys, next: 0, units: units.into(), selected: false }
+  }
+
+  /// Advance by one step. Every pass starts by selecting its unit, then presses the keys in order
+  pub fn step(&mut self) -> Step {
+    if !self.selected {
+      self.selected = true;
+      if let Some(unit) = self.units.front() {
+        return Step::Select(unit.clone());
+      }
+    }
+    if let Some(key) = self.keys.get(self.next) {
+      self.next += 1;
+      return Step::Press(*key);
+    }
+    self.units.pop_front();
+    if self.units.is_empty() {
+      return Step::Done;
+    }
+    self.next = 0;
+    self.selected = false;
+    self.step()
+  }
+}
+
+/// How a key is written in the help pane, e.g. `ctrl+f`, `Enter` or `j`
+pub fn key_label(key: &KeyEvent) -> String {
+  let code = match key.code {
+    KeyCode::Char(' ') => "Space".to_string(),
+    KeyCode::Char(c) => c.to_string(),
+    KeyCode::F(n) => format!("F{n}"),
+    KeyCode::Enter => "Enter".into(),
+    KeyCode::Esc => "Esc".into(),
+    KeyCode::Tab => "Tab".into(),
+    KeyCode::BackTab => "shift+Tab".into(),
+    KeyCode::Backspace => "Backspace".into(),
+    KeyCode::Up => "↑".into(),
+    KeyCode::Down => "↓".into(),
+    KeyCode::Left => "←".into(),
+    KeyCode::Right => "→".into(),
+    other => format!("{other:?}"),
+  };
+  if key.modifiers.contains(KeyModifiers::CONTROL) {
+    format!("ctrl+{code}")
+  } else if key.modifiers.contains(KeyModifiers::ALT) {
+    format!("alt+{code}")
+  } else {
+    code
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::systemd::UnitScope;
+
+  #[test]
+  fn test_replay_steps() {
+    let keys = vec![KeyEvent::from(KeyCode::Enter), KeyEvent::from(KeyCode::Char('l'))];
+    let unit = |name: &str| UnitId { name: name.into(), scope: UnitScope::Global };
+    let mut replay = Replay::new(keys.clone(), vec![unit("a.service"), unit("b.service")]);
+    assert_eq!(replay.step(), Step::Select(unit("a.service")));
+    assert_eq!(replay.step(), Step::Press(keys[0]));
+    assert_eq!(replay.step(), Step::Press(keys[1]));
+    assert_eq!(replay.step(), Step::Select(unit("b.service")));
+    assert_eq!(replay.step(), Step::Press(keys[0]));
+    assert_eq!(replay.step(), Step::Press(keys[1]));
+    assert_eq!(replay.step(), Step::Done);
+
+    let mut once = Replay::new(keys.clone(), vec![]);
+    assert_eq!(once.step(), Step::Press(keys[0]));
+    assert_eq!(once.step(), Step::Press(keys[1]));
+    assert_eq!(once.step(), Step::Done);
+  }
+
+  #[test]
+  fn test_key_label() {
+    assert_eq!(key_label(&KeyEvent::new(KeyCode::Char('f'), KeyModifiers::CONTROL)), "ctrl+f");
+    assert_eq!(key_label(&KeyEvent::from(KeyCode::Char(' '))), "Space");
+    assert_eq!(key_label(&KeyEvent::from(KeyCode::Enter)), "Enter");
+  }
+}
@@ -1,13 +1,122 @@
 pub mod app;
 
+pub mod changes;
+
+pub mod cli;
+
+pub mod clipboard;
+
+pub mod command_bar;
+
 pub mod action;
 
+pub mod action_log;
+
+pub mod boot;
+
 pub mod components;
 
+pub mod config;
+
+pub mod control;
+
+pub mod crash;
+
+pub mod cron;
+
+pub mod depgraph;
+
+pub mod docs;
+
+pub mod dropin;
+
+pub mod enablement;
+
+pub mod equivalent;
+
+pub mod escalation;
+
 pub mod event;
 
+pub mod failure_hooks;
+
+pub mod favorites;
+
+pub mod file_watch;
+
+pub mod graph;
+
+pub mod hardening;
+
+pub mod health;
+
+pub mod help;
+
+pub mod humanize;
+
+pub mod keybindings;
+
+pub mod keymacro;
+
+pub mod log_filter;
+
+pub mod log_search;
+
+pub mod metrics;
+
+pub mod monochrome;
+
+pub mod mounts;
+
+pub mod mouse;
+
+pub mod multiplexer;
+
+pub mod ordering;
+
+pub mod pane_layout;
+
+pub mod plugin;
+
+pub mod pressure;
+
+pub mod process_tree;
+
+pub mod property_diff;
+
+pub mod running_set;
+
+pub mod saved_view;
+
+pub mod scaffold;
+
+pub mod search;
+
+pub mod self_check;
+
+pub mod session;
+
+pub mod slices;
+
+pub mod sockets;
+
+pub mod states;
+
+pub mod subprocess;
+
 pub mod terminal;
 
+pub mod theme;
+
+pub mod timeline;
+
+pub mod unit_environment;
+
+pub mod unit_types;
+
 pub mod utils;
 
-pub mod systemd;
+// the systemd and journal layer lives in its own crate; these keep its modules where the rest of the code expects them
+pub use systemctl_tui_core::{
+  boot_history, boot_wait, journal, journal_usage, machine, power, preset, remote, systemd, timers, user_manager,
+};
@@ -0,0 +1,171 @@
+// Filters for the log pane that stay put, like piping journalctl through grep: `timeout` keeps only the lines
+// matching it, `!healthcheck` drops the lines matching it, and filters stack, so a line has to pass every one. Unlike
+// the search, lines filtered out are gone from the pane (and from what's copied or saved) until the filter is
+// removed, and lines streamed in later are filtered too. Patterns are regexes, where case only matters once they have
+// an uppercase letter, like the search. `_PID=812` instead matches a journal field exactly, keeping only that
+// process's entries (or dropping them, as `!_PID=812`).
+
+use anyhow::{bail, Result};
+use regex::{Regex, RegexBuilder};
+
+use crate::journal::Entry;
+
+#[derive(Debug, Clone)]
+enum Matcher {
+  Text(Regex),
+  /// A field's name and the value it has to have
+  Field(String, String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Filter {
+  /// As typed, `!` and all
+  pub spec: String,
+  exclude: bool,
+  matcher: Matcher,
+}
+
+/// Whether `name` could be a journal field: uppercase letters, digits and underscores, not starting with a digit
+fn is_field_name(name: &str) -> bool {
+  !name.is_empty()
+    && !name.starts_with(|c: char| c.is_ascii_digit())
+    && name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+impl Filter {
+  pub fn parse(spec: &str) -> Result<Self> {
+    let spec = spec.trim();
+    let (exclude, pattern) = match spec.strip_prefix('!') {
+      Some(pattern) => (true, pattern),
+      None => (false, spec),
+    };
+    if pattern.is_empty() {
+      bail!("Type a pattern to keep the lines matching it, or !pattern to drop them");
+    }
+    if let Some((name, value)) = pattern.split_once('=').filter(|(name, _)| is_field_name(name)) {
+      let matcher = Matcher::Field(name.to_string(), value.to_string());
+      return Ok(Self { spec: spec.to_string(), exclude, matcher });
+    }
+    let pattern = match RegexBuilder::new(pattern).case_insensitive(!pattern.chars().any(char::is_uppercase)).build() {
+      Ok(pattern) => pattern,
+      Err(e) => bail!("Not a valid regex: {}", e.to_string().lines().last().unwrap_or_default().trim()),
+    };
+    Ok(Self { spec: spec.to_string(), exclude, matcher: Matcher::Text(pattern) })
+  }
+
+  /// Only entries whose `name` field is `value`, or with `exclude` only the others
+  pub fn field(name: &str, value: &str, exclude: bool) -> Self {
+    let spec = format!("{}{name}={value}", if exclude { "!" } else { "" });
+    Self { spec, exclude, matcher: Matcher::Field(name.to_string(), value.to_string()) }
+  }
+
+  /// Lines that aren't journal entries have no fields, so they never match a field
+  fn passes(&self, line: &Entry) -> bool {
+    let matches = match &self.matcher {
+      Matcher::Text(pattern) => pattern.is_match(&line.text),
+      Matcher::Field(name, value) => line.fields.as_ref().and_then(|f| f.get(name)) == Some(value),
+    };
+    matches != self.exclude
+  }
+}
+
+#[derive(Debug, Default)]
+pub struct LogFilters {
+  pub filters: Vec<Filter>,
+}
+
+impl LogFilters {
+  pub fn is_empty(&self) -> bool {
+    self.filters.is_empty()
+  }
+
+  /// Whether a line gets through every filter
+  pub fn passes(&self, line: &Entry) -> bool {
+    self.filters.iter().all(|f| f.passes(line))
+  }
+
+  /// The lines that get through. The continuation lines of a multi-line message go with its first line
+  pub fn apply(&self, lines: Vec<Entry>) -> Vec<Entry> {
+    if self.is_empty() {
+      return lines;
+    }
+    let mut keep = true;
+    lines
+      .into_iter()
+      .filter(|line| {
+        if !line.text.starts_with(' ') {
+          keep = self.passes(line);
+        }
+        keep
+      })
+      .collect()
+  }
+
+  /// `error, !healthcheck`, for the log pane's title
+  pub fn label(&self) -> String {
+    self.filters.iter().map(|f| f.spec.as_str()).collect::<Vec<_>>().join(", ")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_log_filters() {
+    let lines = [
+      "2025-04-26T06:04:44-07:00 host app[812]: GET /healthcheck 200",
+      "2025-04-26T06:04:45-07:00 host app[812]: request timed out",
+      "                                         while reading the body",
+      "2025-04-26T06:04:46-07:00 host app[812]: ERROR: pool exhausted",
+      "2025-04-26T06:04:47-07:00 host app[812]: GET /healthcheck timed out",
+    ]
+    .map(Entry::plain)
+    .to_vec();
+    let texts = |lines: Vec<Entry>| lines.into_iter().map(|l| l.text).collect::<Vec<_>>();
+
+    let mut filters = LogFilters::default();
+    assert_eq!(filters.apply(lines.clone()).len(), 5);
+    filters.filters.push(Filter::parse("!healthcheck").unwrap());
+    assert_eq!(filters.apply(lines.clone()).len(), 3);
+    filters.filters.push(Filter::parse("timed out|error").unwrap());
+    let kept = texts(filters.apply(lines.clone()));
+    // the continuation stays with its message, and lowercase patterns ignore case
+    assert_eq!(kept.len(), 3);
+    assert!(kept[1].ends_with("while reading the body"));
+    assert!(kept[2].ends_with("pool exhausted"));
+    assert_eq!(filters.label(), "!healthcheck, timed out|error");
+
+    // an uppercase letter makes the pattern case sensitive
+    assert!(!Filter::parse("Error").unwrap().passes(&Entry::plain("ERROR: pool exhausted")));
+    assert!(Filter::parse("!").is_err());
+    assert!(Filter::parse("(unclosed").is_err());
+  }
+
+  #[test]
+  fn test_field_filters() {
+    let entry = |pid: &str, message: &str| {
+      let fields = [
+        ("__REALTIME_TIMESTAMP", "1745672685000000"),
+        ("_PID", pid),
+        ("SYSLOG_IDENTIFIER", "app"),
+        ("MESSAGE", message),
+      ];
+      Entry::from_fields(&fields.map(|(k, v)| (k.to_string(), v.to_string())).into())
+    };
+    let mut lines = entry("812", "started\nwith two workers");
+    lines.extend(entry("813", "started"));
+    lines.push(Entry::plain("-- Boot 5e0f... --"));
+
+    let only = LogFilters { filters: vec![Filter::parse("_PID=812").unwrap()] };
+    assert_eq!(only.apply(lines.clone()).len(), 2);
+    let without = LogFilters { filters: vec![Filter::field("_PID", "812", true)] };
+    let kept = without.apply(lines.clone());
+    assert_eq!(kept.len(), 2);
+    assert!(kept[0].text.ends_with("app[813]: started"));
+    assert_eq!(without.label(), "!_PID=812");
+    // lowercase or empty names are patterns, not fields
+    assert!(matches!(Filter::parse("key=value").unwrap().matcher, Matcher::Text(_)));
+    assert!(matches!(Filter::parse("=x").unwrap().matcher, Matcher::Text(_)));
+  }
+}
@@ -0,0 +1,173 @@
+// Searching the log pane: which lines match, where in them, and stepping from one match to the next. The query is
+// plain text unless regex mode is on, and case only matters once it has an uppercase letter. Matching is kept up to
+// date as lines stream in, only looking at the new ones.
+
+use regex::{Regex, RegexBuilder};
+
+#[derive(Debug, Default)]
+pub struct LogSearch {
+  pub query: String,
+  pub regex: bool,
+  /// `None` when there's no query, or it isn't a valid regex
+  pattern: Option<Regex>,
+  pub error: Option<String>,
+  /// Indexes of the matching lines, in order
+  matches: Vec<usize>,
+  /// How many lines have been looked at
+  scanned: usize,
+  /// The line jumped to last
+  pub current: Option<usize>,
+}
+
+impl LogSearch {
+  pub fn is_active(&self) -> bool {
+    self.pattern.is_some()
+  }
+
+  pub fn set_query(&mut self, query: &str, regex: bool) {
+    self.query = query.to_string();
+    self.regex = regex;
+    self.error = None;
+    self.pattern = None;
+    if !query.is_empty() {
+      let source = if regex { query.to_string() } else { regex::escape(query) };
+      match RegexBuilder::new(&source).case_insensitive(!query.chars().any(char::is_uppercase)).build() {
+        Ok(pattern) => self.pattern = Some(pattern),
+        Err(e) => self.error = Some(e.to_string().lines().last().unwrap_or_default().trim().to_string()),
+      }
+    }
+    self.matches.clear();
+    self.scanned = 0;
+    self.current = None;
+  }
+
+  pub fn clear(&mut self) {
+    self.set_query("", self.regex);
+  }
+
+  /// Look at lines added since the last call. `text` gives the part of a line that's searched. Starts over if the
+  /// lines were replaced by fewer
+  pub fn scan<T: AsRef<str>>(&mut self, lines: &[T], text: fn(&str) -> &str) {
+    if lines.len() < self.scanned {
+      self.rescan(lines, text);
+      return;
+    }
+    if let Some(pattern) = &self.pattern {
+      let new = lines[self.scanned..].iter().enumerate().filter(|(_, line)| pattern.is_match(text(line.as_ref())));
+      self.matches.extend(new.map(|(i, _)| self.scanned + i));
+    }
+    self.scanned = lines.len();
+  }
+
+  /// Start over, e.g. for the logs of another unit
+  pub fn rescan<T: AsRef<str>>(&mut self, lines: &[T], text: fn(&str) -> &str) {
+    self.matches.clear();
+    self.scanned = 0;
+    self.current = None;
+    self.scan(lines, text);
+  }
+
+  /// The first `count` lines were dropped, e.g. to keep the logs from growing without bound
+  pub fn drop_front(&mut self, count: usize) {
+    self.matches.retain(|i| *i >= count);
+    self.matches.iter_mut().for_each(|i| *i -= count);
+    self.scanned = self.scanned.saturating_sub(count);
+    self.current = self.current.and_then(|c| c.checked_sub(count));
+  }
+
+  /// `count` older lines were put before the others. Starts over, but stays on the same match
+  pub fn prepended<T: AsRef<str>>(&mut self, lines: &[T], count: usize, text: fn(&str) -> &str) {
+    let current = self.current.map(|c| c + count).filter(|c| *c < lines.len());
+    self.rescan(lines, text);
+    self.current = current;
+  }
+
+  pub fn match_count(&self) -> usize {
+    self.matches.len()
+  }
+
+  /// Where the current match is among all of them, counting from 1 at the highest index
+  pub fn position(&self) -> Option<usize> {
+    let current = self.current?;
+    Some(self.matches.len() - self.matches.binary_search(&current).ok()?)
+  }
+
+  /// Byte ranges of the matches in `text`
+  pub fn ranges(&self, text: &str) -> Vec<(usize, usize)> {
+    match &self.pattern {
+      Some(pattern) => pattern.find_iter(text).filter(|m| !m.is_empty()).map(|m| (m.start(), m.end())).collect(),
+      None => vec![],
+    }
+  }
+
+  /// The next matching line below the current one, wrapping around. The log pane shows the newest line first, so
+  /// that's the next lower index
+  pub fn next_match(&mut self) -> Option<usize> {
+    let below = self.current.and_then(|current| self.matches.iter().rev().find(|i| **i < current));
+    self.current = below.or(self.matches.last()).copied();
+    self.current
+  }
+
+  /// The next matching line above the current one, wrapping around
+  pub fn previous_match(&mut self) -> Option<usize> {
+    let above = self.current.and_then(|current| self.matches.iter().find(|i| **i > current));
+    self.current = above.or(self.matches.first()).copied();
+    self.current
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_search() {
+    let mut lines = ["ts Started nginx", "ts connection reset", "ts Stopped nginx"].map(String::from).to_vec();
+    let message: fn(&str) -> &str = |line| line.split_once(' ').map_or(line, |(_, rest)| rest);
+    let mut search = LogSearch::default();
+    search.set_query("nginx", false);
+    search.scan(&lines, message);
+    assert_eq!(search.match_count(), 2);
+    assert_eq!(search.ranges("Started nginx"), [(8, 13)]);
+
+    // newest first, and around again
+    assert_eq!(search.next_match(), Some(2));
+    assert_eq!(search.position(), Some(1));
+    assert_eq!(search.next_match(), Some(0));
+    assert_eq!(search.next_match(), Some(2));
+    assert_eq!(search.previous_match(), Some(0));
+
+    lines.push("ts Reloading NGINX".into());
+    search.scan(&lines, message);
+    assert_eq!(search.match_count(), 3);
+    search.set_query("NGINX", false);
+    search.scan(&lines, message);
+    assert_eq!(search.match_count(), 1);
+
+    search.set_query(r"(Start|Stopp)ed \w+", true);
+    search.scan(&lines, message);
+    assert_eq!(search.match_count(), 2);
+    search.scan(&lines[..1], message);
+    assert_eq!(search.match_count(), 1);
+
+    search.set_query("(", true);
+    assert!(!search.is_active());
+    assert!(search.error.is_some());
+    search.set_query("ts", false);
+    search.scan(&lines, message);
+    assert_eq!(search.match_count(), 0);
+
+    // dropping lines from the front and putting older ones before them keeps the current match
+    search.set_query("nginx", false);
+    search.scan(&lines, message);
+    assert_eq!(search.next_match(), Some(3));
+    lines.remove(0);
+    search.drop_front(1);
+    assert_eq!(search.match_count(), 2);
+    assert_eq!(search.current, Some(2));
+    lines.insert(0, "ts Started nginx".into());
+    search.prepended(&lines, 1, message);
+    assert_eq!(search.match_count(), 3);
+    assert_eq!(search.current, Some(3));
+  }
+}
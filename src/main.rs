@@ -1,9 +1,13 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use clap::{Parser, Subcommand, ValueEnum};
 use systemctl_tui::{
   app::App,
-  systemd,
-  utils::{get_data_dir, initialize_logging, initialize_panic_handler, version},
+  cli,
+  config::{Config, StartupFilter},
+  control, keybindings, machine, remote, saved_view, self_check,
+  systemd::{self, UnitId, UnitScope},
+  unit_types, user_manager,
+  utils::{get_data_dir, initialize_logging, initialize_panic_handler, version, StartupProfile},
 };
 
 // Define the command line arguments structure
@@ -23,8 +27,65 @@ struct Args {
       action = clap::ArgAction::SetTrue
     )]
   no_log: bool,
+  /// Screen-reader friendly output: no box-drawing borders, unit state spelled out as text, focus announced on the
+  /// bottom line
+  #[arg(long, env = "SYSTEMCTL_TUI_ACCESSIBLE", default_value_t = false, action = clap::ArgAction::SetTrue)]
+  accessible: bool,
+  /// Time startup (init, first unit fetch, first render), print a summary on exit and write a Perfetto trace
+  #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
+  profile_startup: bool,
+  /// Serve Prometheus metrics (unit states, restart counts, memory) for the listed units on this address while the
+  /// TUI runs, e.g. 127.0.0.1:9558
+  #[arg(long, env = "SYSTEMCTL_TUI_METRICS_ADDRESS")]
+  metrics_address: Option<std::net::SocketAddr>,
+  /// Start in dry-run mode: actions show the command they'd run and the units they'd affect instead of running
+  #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
+  dry_run: bool,
+  /// Start in read-only mode, for shared or production sessions: browsing and logs work, but nothing that changes a
+  /// unit or the machine does
+  #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
+  read_only: bool,
+  /// Let scripts drive this instance (select a unit, open a view, refresh, read unit states) through a Unix socket at
+  /// this path
+  #[arg(long, env = "SYSTEMCTL_TUI_CONTROL_SOCKET")]
+  control_socket: Option<std::path::PathBuf>,
+  /// Send a request to the instance listening on the control socket, print its answer and exit, e.g.
+  /// 'select nginx.service' or 'reload-config'
+  #[arg(long, value_name = "REQUEST")]
+  send: Option<String>,
+  /// Manage the system units of another machine over ssh, like `systemctl -H`: [user@]machine[:port]. Needs
+  /// systemd-stdio-bridge on the host
+  #[arg(short = 'H', long, env = "SYSTEMCTL_TUI_HOST")]
+  host: Option<String>,
+  /// Manage the system units of a local container registered with machined (e.g. systemd-nspawn), like
+  /// `systemctl -M`
+  #[arg(short = 'M', long, env = "SYSTEMCTL_TUI_MACHINE")]
+  machine: Option<String>,
+  /// As root, show this user's user units (from their `systemd --user`) instead of your own. The user has to be logged
+  /// in or have lingering enabled
+  #[arg(long, value_name = "USER", env = "SYSTEMCTL_TUI_USER_MANAGER")]
+  user_manager: Option<String>,
+  /// What to show on launch: list, failed, top (units by CPU use), search:<query> or logs:<unit>
+  #[arg(long, env = "SYSTEMCTL_TUI_VIEW")]
+  view: Option<String>,
+  /// Open with the list narrowed to units whose names match this, e.g. 'docker*'. Without wildcards, names
+  /// containing it
+  #[arg(long, value_name = "PATTERN")]
+  filter: Option<String>,
+  /// Open with the list narrowed to units in this load, active or sub state, e.g. failed or running. Can be repeated
+  #[arg(long)]
+  state: Vec<String>,
+  /// Open with the list narrowed to units of this type, e.g. timer
+  #[arg(long = "type", value_name = "TYPE")]
+  unit_type: Option<String>,
+  /// Open straight to this unit's logs, taken to be a service if it has no type suffix
+  #[arg(conflicts_with = "view")]
+  unit: Option<String>,
+  /// Print a config file with the default keybindings, to start from when remapping keys
+  #[arg(long, default_value_t = false, action = clap::ArgAction::SetTrue)]
+  print_default_config: bool,
   /// Limit view to only these unit files
-  #[clap(short, long, default_value="*.service", num_args=1..)]
+  #[clap(short, long, default_values=unit_types::DEFAULT_PATTERNS, num_args=1..)]
   limit_units: Vec<String>,
 }
 
@@ -32,6 +93,25 @@ struct Args {
 enum Commands {
   /// Show the path to the logs directory
   ShowLogsPath,
+  /// Check what works on this machine: managing units, reading logs, user units
+  Check,
+  /// Print the units matching --limit-units and exit
+  List {
+    /// Only units in this load, active or sub state, e.g. failed or running. Can be repeated
+    #[arg(long)]
+    state: Vec<String>,
+    #[arg(long, value_enum, default_value_t)]
+    output: cli::Output,
+  },
+  /// Print a unit's latest logs and exit
+  Logs {
+    /// The unit, taken to be a service if it has no type suffix
+    unit: String,
+    #[arg(short = 'n', long, default_value_t = 200)]
+    lines: usize,
+    #[arg(long, value_enum, default_value_t)]
+    output: cli::Output,
+  },
 }
 
 #[derive(Parser, Debug, ValueEnum, Clone)]
@@ -50,6 +130,11 @@ async fn main() -> Result<()> {
 
   let args = Args::parse();
 
+  if args.print_default_config {
+    print!("{}", keybindings::default_config());
+    return Ok(());
+  }
+
   // Handle subcommands
   match args.command {
     Some(Commands::ShowLogsPath) => {
@@ -57,12 +142,25 @@ async fn main() -> Result<()> {
       println!("{}", logs_path.display());
       return Ok(());
     },
-    None => {
-      // Default behavior - run the TUI
+    Some(Commands::Check) => {
+      print!("{}", self_check::report(&self_check::run().await));
+      return Ok(());
     },
+    // these need the host and user manager set up first
+    Some(Commands::List { .. } | Commands::Logs { .. }) | None => {},
   }
 
-  let _guard = initialize_logging(!args.no_log)?;
+  if let Some(request) = &args.send {
+    let path = match args.control_socket.clone() {
+      Some(path) => path,
+      None => Config::load()?.control_socket.context("Set control-socket in the config, or pass --control-socket")?,
+    };
+    print!("{}", control::send(&path, request)?);
+    return Ok(());
+  }
+
+  let startup_profile = args.profile_startup.then(StartupProfile::default);
+  let _guard = initialize_logging(!args.no_log, startup_profile.clone())?;
   initialize_panic_handler();
 
   // There's probably a nicer way to do this than defining the scope enum twice, but this is fine for now
@@ -81,8 +179,88 @@ async fn main() -> Result<()> {
     },
   };
 
-  let mut app = App::new(scope, args.limit_units)?;
-  app.run().await?;
+  if let Some(host) = &args.host {
+    if matches!(args.scope, Some(Scope::User)) {
+      bail!("User units can't be managed on a remote host");
+    }
+    remote::set_host(remote::parse_host(host)?);
+  }
+
+  if let Some(name) = &args.machine {
+    if args.host.is_some() || matches!(args.scope, Some(Scope::User)) {
+      bail!("--machine is for the system units of a container on this machine");
+    }
+    machine::set_machine(Some(name.clone()));
+  }
+
+  if let Some(user) = &args.user_manager {
+    if args.host.is_some() || args.machine.is_some() || matches!(args.scope, Some(Scope::Global)) {
+      bail!("--user-manager is for user units on this machine");
+    }
+    user_manager::set_user(Some(user_manager::find(user)?));
+  }
+
+  match args.command {
+    Some(Commands::List { state, output }) => {
+      print!("{}", cli::list(scope, &args.limit_units, &state, output).await?);
+      return Ok(());
+    },
+    Some(Commands::Logs { unit, lines, output }) => {
+      let name = if unit.contains('.') { unit } else { format!("{unit}.service") };
+      let scope = if matches!(args.scope, Some(Scope::User)) { UnitScope::User } else { UnitScope::Global };
+      print!("{}", cli::logs(&UnitId { name, scope }, lines, output)?);
+      return Ok(());
+    },
+    _ => {},
+  }
+
+  let mut config = Config::load()?;
+  config.accessible |= args.accessible;
+  config.dry_run |= args.dry_run;
+  config.read_only |= args.read_only;
+  config.metrics_address = args.metrics_address.or(config.metrics_address);
+  config.control_socket = args.control_socket.or(config.control_socket);
+  if let Some(view) = &args.view {
+    config.startup_view = Some(view.parse()?);
+  }
+  if let Some(unit) = &args.unit {
+    config.startup_view = Some(format!("logs:{unit}").parse()?);
+  }
+  let unit_type = match &args.unit_type {
+    Some(name) => Some(unit_types::known(name).with_context(|| format!("Unknown unit type `{name}`"))?),
+    None => None,
+  };
+  let mut limit_units = args.limit_units;
+  // the types left out by default have to be asked for to be listed at all
+  if let Some(unit_type) = unit_type.filter(|_| limit_units == unit_types::DEFAULT_PATTERNS) {
+    let pattern = format!("*.{unit_type}");
+    if !limit_units.contains(&pattern) {
+      limit_units.push(pattern);
+    }
+  }
+  config.startup_filter = StartupFilter { pattern: args.filter, states: args.state, unit_type };
+  // a view asked for on the command line or with startup-view wins over where the last session left off
+  if config.restore_session && config.startup_view.is_none() && config.startup_filter == StartupFilter::default() {
+    config.restored_view = saved_view::load();
+  }
+  // so does a scope that's given, or that's all there is on another machine or WSL
+  let scope = match config.restored_view.as_ref().and_then(|v| v.scope) {
+    Some(saved) if args.scope.is_none() && args.host.is_none() && args.machine.is_none() && !is_wsl::is_wsl() => saved,
+    _ => scope,
+  };
+
+  let mut app = App::new(scope, limit_units, config)?;
+  let result = app.run().await;
+  control::remove_socket();
+  result?;
+
+  if let Some(profile) = startup_profile {
+    println!("{}", profile.summary());
+    match profile.write_trace() {
+      Ok(path) => println!("Trace written to {} (open it at https://ui.perfetto.dev)", path.display()),
+      Err(e) => eprintln!("Failed to write the startup trace: {e:#}"),
+    }
+  }
 
   Ok(())
 }
@@ -0,0 +1,179 @@
+// An opt-in Prometheus endpoint served while the TUI runs, so a long-running session can double as a small exporter
+// for the units it's showing. It speaks just enough HTTP for a scraper: every request gets the metrics back.
+
+use std::{collections::HashMap, fmt::Write, net::SocketAddr, sync::Arc, time::Duration};
+
+use anyhow::{Context, Result};
+use tokio::{
+  io::{AsyncReadExt, AsyncWriteExt},
+  net::TcpListener,
+  sync::{Mutex, Semaphore},
+};
+use tracing::{info, warn};
+
+use crate::{
+  components::home::Home,
+  systemd::{self, parse_counter, UnitId, UnitScope},
+};
+
+/// How long a client gets to send its request before it's answered anyway, and to take the answer
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+/// Connections served at once; more are turned away, so a stuck scraper can't pile them up
+const MAX_CONNECTIONS: usize = 16;
+
+/// Every active state gets a series, 1 for the current one, so alerts can match on `state="failed"`
+const ACTIVE_STATES: &[&str] = &["active", "reloading", "inactive", "failed", "activating", "deactivating"];
+
+/// Bind `address`, then serve metrics for the units `home` is showing in the background
+pub async fn serve(address: SocketAddr, home: Arc<Mutex<Home>>) -> Result<()> {
+  let listener = TcpListener::bind(address).await.with_context(|| format!("Failed to serve metrics on {address}"))?;
+  info!("Serving metrics on http://{address}/metrics");
+  let connections = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+  tokio::spawn(async move {
+    loop {
+      let (mut stream, peer) = match listener.accept().await {
+        Ok(connection) => connection,
+        Err(e) => {
+          warn!("Error accepting a metrics connection: {}", e);
+          continue;
+        },
+      };
+      let Ok(permit) = connections.clone().try_acquire_owned() else {
+        warn!("Turning away metrics request from {}: {} connections open already", peer, MAX_CONNECTIONS);
+        continue;
+      };
+      let home = home.clone();
+      tokio::spawn(async move {
+        let _permit = permit;
+        // the request itself doesn't matter, but read it so the client isn't reset mid-send. One that never sends
+        // anything gets its answer after the timeout rather than holding the connection open
+        let mut request = [0; 1024];
+        let _ = tokio::time::timeout(IO_TIMEOUT, stream.read(&mut request)).await;
+        let body = scrape(&home).await;
+        let response = format!(
+          "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+          body.len()
+        );
+        match tokio::time::timeout(IO_TIMEOUT, stream.write_all(response.as_bytes())).await {
+          Ok(Ok(())) => {},
+          Ok(Err(e)) => warn!("Error answering metrics request from {}: {}", peer, e),
+          Err(_) => warn!("Gave up answering metrics request from {}: it isn't reading", peer),
+        }
+      });
+    }
+  });
+  Ok(())
+}
+
+/// One unit's worth of metrics
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitSample {
+  pub id: UnitId,
+  pub active_state: String,
+  /// `NRestarts` and `MemoryCurrent`, when systemd reports them
+  pub restarts: Option<u64>,
+  pub memory_bytes: Option<u64>,
+}
+
+async fn scrape(home: &Mutex<Home>) -> String {
+  let units: Vec<(UnitId, String)> =
+    home.lock().await.all_units.values().map(|u| (u.id(), u.activation_state.clone())).collect();
+  let samples = tokio::task::spawn_blocking(move || {
+    let mut properties: HashMap<UnitId, HashMap<String, String>> = HashMap::new();
+    for scope in [UnitScope::Global, UnitScope::User] {
+      if !units.iter().any(|(id, _)| id.scope == scope) {
+        continue;
+      }
+      match systemd::get_all_unit_properties(scope, &["NRestarts", "MemoryCurrent"]) {
+        Ok(all) => {
+          properties.extend(all.into_iter().filter_map(|p| Some((UnitId { name: p.get("Id")?.clone(), scope }, p))))
+        },
+        Err(e) => warn!("Error getting unit properties for metrics: {}", e),
+      }
+    }
+    let counter = |id: &UnitId, name: &str| parse_counter(properties.get(id)?, name);
+    units
+      .iter()
+      .map(|(id, active_state)| UnitSample {
+        id: id.clone(),
+        active_state: active_state.clone(),
+        restarts: counter(id, "NRestarts"),
+        memory_bytes: counter(id, "MemoryCurrent"),
+      })
+      .collect::<Vec<_>>()
+  })
+  .await
+  .unwrap_or_default();
+  render(&samples)
+}
+
+/// The Prometheus text exposition format for `samples`
+pub fn render(samples: &[UnitSample]) -> String {
+  let mut out = String::new();
+  let labels = |id: &UnitId| {
+    let scope = match id.scope {
+      UnitScope::Global => "system",
+      UnitScope::User => "user",
+    };
+    format!("name=\"{}\",scope=\"{scope}\"", escape(&id.name))
+  };
+
+  out.push_str("# HELP systemd_unit_state Whether the unit is in each active state.\n");
+  out.push_str("# TYPE systemd_unit_state gauge\n");
+  for sample in samples {
+    for state in ACTIVE_STATES {
+      let value = u8::from(sample.active_state == *state);
+      let _ = writeln!(out, "systemd_unit_state{{{},state=\"{state}\"}} {value}", labels(&sample.id));
+    }
+  }
+
+  out.push_str("# HELP systemd_unit_restarts_total How many times systemd has restarted the unit automatically.\n");
+  out.push_str("# TYPE systemd_unit_restarts_total counter\n");
+  for sample in samples {
+    if let Some(restarts) = sample.restarts {
+      let _ = writeln!(out, "systemd_unit_restarts_total{{{}}} {restarts}", labels(&sample.id));
+    }
+  }
+
+  out.push_str("# HELP systemd_unit_memory_bytes Memory used by the unit's cgroup.\n");
+  out.push_str("# TYPE systemd_unit_memory_bytes gauge\n");
+  for sample in samples {
+    if let Some(memory) = sample.memory_bytes {
+      let _ = writeln!(out, "systemd_unit_memory_bytes{{{}}} {memory}", labels(&sample.id));
+    }
+  }
+  out
+}
+
+fn escape(value: &str) -> String {
+  value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_render() {
+    let samples = vec![
+      UnitSample {
+        id: UnitId { name: "nginx.service".into(), scope: UnitScope::Global },
+        active_state: "failed".into(),
+        restarts: Some(3),
+        memory_bytes: None,
+      },
+      UnitSample {
+        id: UnitId { name: "a\"b.service".into(), scope: UnitScope::User },
+        active_state: "active".into(),
+        restarts: None,
+        memory_bytes: Some(4096),
+      },
+    ];
+    let text = render(&samples);
+    assert!(text.contains("systemd_unit_state{name=\"nginx.service\",scope=\"system\",state=\"failed\"} 1\n"));
+    assert!(text.contains("systemd_unit_state{name=\"nginx.service\",scope=\"system\",state=\"active\"} 0\n"));
+    assert!(text.contains("systemd_unit_restarts_total{name=\"nginx.service\",scope=\"system\"} 3\n"));
+    assert!(text.contains("systemd_unit_memory_bytes{name=\"a\\\"b.service\",scope=\"user\"} 4096\n"));
+    assert!(!text.contains("systemd_unit_memory_bytes{name=\"nginx.service\""));
+  }
+}
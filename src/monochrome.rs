@@ -0,0 +1,102 @@
+// Output without color: the NO_COLOR / CLICOLOR conventions for the screen, and plain text for anything copied out of
+// the TUI so it pastes cleanly into tickets and chat.
+
+use ratatui::{
+  buffer::Buffer,
+  style::{Color, Modifier},
+  text::Line,
+};
+
+/// Whether to draw in color. `CLICOLOR_FORCE` wins; otherwise a non-empty `NO_COLOR` or `CLICOLOR=0` turns color off
+pub fn color_enabled() -> bool {
+  color_enabled_with(|name| std::env::var(name).ok())
+}
+
+fn color_enabled_with(var: impl Fn(&str) -> Option<String>) -> bool {
+  if var("CLICOLOR_FORCE").is_some_and(|v| !v.is_empty() && v != "0") {
+    return true;
+  }
+  !(var("NO_COLOR").is_some_and(|v| !v.is_empty()) || var("CLICOLOR").is_some_and(|v| v == "0"))
+}
+
+/// Drop every color from a rendered frame. Anything highlighted with a background (like the selected unit) is shown
+/// reversed instead, so it stays visible
+pub fn strip_colors(buffer: &mut Buffer) {
+  for cell in buffer.content.iter_mut() {
+    if cell.bg != Color::Reset {
+      cell.modifier |= Modifier::REVERSED;
+    }
+    cell.fg = Color::Reset;
+    cell.bg = Color::Reset;
+    cell.underline_color = Color::Reset;
+  }
+}
+
+/// Lines as plain text, one per line, without styling or any escape sequences that came along in the content
+pub fn plain_text(lines: &[Line]) -> String {
+  lines
+    .iter()
+    .map(|line| strip_ansi(&line.spans.iter().map(|s| s.content.as_ref()).collect::<String>()).trim_end().to_string())
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// Remove ANSI escape sequences, e.g. the colors a service put in its own log lines
+pub fn strip_ansi(text: &str) -> String {
+  let mut plain = String::with_capacity(text.len());
+  let mut chars = text.chars().peekable();
+  while let Some(c) = chars.next() {
+    if c != '\x1b' {
+      plain.push(c);
+      continue;
+    }
+    match chars.next() {
+      // CSI: parameters and intermediates, ended by a byte in @..~
+      Some('[') => {
+        for c in chars.by_ref() {
+          if ('@'..='~').contains(&c) {
+            break;
+          }
+        }
+      },
+      // OSC: ended by BEL or ESC \
+      Some(']') => {
+        while let Some(c) = chars.next() {
+          if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+            break;
+          }
+        }
+      },
+      _ => {},
+    }
+  }
+  plain
+}
+
+#[cfg(test)]
+mod tests {
+  use ratatui::text::Span;
+
+  use super::*;
+
+  #[test]
+  fn test_color_enabled() {
+    let env = |vars: &'static [(&'static str, &'static str)]| {
+      move |name: &str| vars.iter().find(|(n, _)| *n == name).map(|(_, v)| v.to_string())
+    };
+    assert!(color_enabled_with(env(&[])));
+    assert!(!color_enabled_with(env(&[("NO_COLOR", "1")])));
+    assert!(color_enabled_with(env(&[("NO_COLOR", "")])));
+    assert!(!color_enabled_with(env(&[("CLICOLOR", "0")])));
+    assert!(color_enabled_with(env(&[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")])));
+  }
+
+  #[test]
+  fn test_plain_text() {
+    let lines = vec![
+      Line::from(vec![Span::raw("✓ "), Span::raw("nginx.service  ")]),
+      Line::from("\x1b[1;31merror\x1b[0m: \x1b]8;;http://x\x1b\\link\x1b]8;;\x1b\\"),
+    ];
+    assert_eq!(plain_text(&lines), "✓ nginx.service\nerror: link");
+  }
+}
@@ -0,0 +1,189 @@
+// Working out why a mount or automount unit failed: where its source comes from, whether the device is there, and
+// what to try next. Boot-time mount failures otherwise surface as little more than "Dependency failed". And the other
+// way round, which mounts and devices a service can't start without, so a missing data disk shows up on the service.
+
+use std::collections::HashMap;
+
+/// What a mount unit's diagnostics are built from
+pub const MOUNT_PROPERTIES: &[&str] = &["What", "Where", "Type", "Options", "SourcePath", "Result", "TimeoutUSec"];
+
+pub fn is_mount(unit_name: &str) -> bool {
+  unit_name.ends_with(".mount") || unit_name.ends_with(".automount")
+}
+
+/// `home.automount` -> `home.mount`, the unit that does the mounting
+pub fn mount_unit_for(unit_name: &str) -> String {
+  match unit_name.strip_suffix(".automount") {
+    Some(prefix) => format!("{prefix}.mount"),
+    None => unit_name.to_string(),
+  }
+}
+
+/// The fstab line (1-based number and text) that mounts something on `mount_point`
+pub fn fstab_entry(fstab: &str, mount_point: &str) -> Option<(usize, String)> {
+  fstab.lines().enumerate().find_map(|(i, line)| {
+    let trimmed = line.trim();
+    if trimmed.starts_with('#') {
+      return None;
+    }
+    let target = trimmed.split_whitespace().nth(1)?;
+    // fstab escapes spaces and tabs in paths as octal
+    let target = target.replace("\\040", " ").replace("\\011", "\t");
+    (target.trim_end_matches('/') == mount_point.trim_end_matches('/') || target == mount_point)
+      .then(|| (i + 1, trimmed.to_string()))
+  })
+}
+
+/// The device node a mount source refers to, when it names one: `/dev/sdb1`, `UUID=...` or `LABEL=...`
+pub fn device_path(what: &str) -> Option<String> {
+  if what.starts_with("/dev/") {
+    return Some(what.to_string());
+  }
+  let (tag, value) = what.split_once('=')?;
+  let directory = match tag {
+    "UUID" => "by-uuid",
+    "LABEL" => "by-label",
+    "PARTUUID" => "by-partuuid",
+    "PARTLABEL" => "by-partlabel",
+    _ => return None,
+  };
+  Some(format!("/dev/disk/{directory}/{}", value.trim_matches('"')))
+}
+
+/// What a unit's mount and device requirements are read from
+pub const REQUIREMENT_PROPERTIES: &[&str] = &["RequiresMountsFor", "Requires", "BindsTo"];
+
+/// A mount or device unit that a unit can't run without
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Requirement {
+  pub unit: String,
+  /// The `RequiresMountsFor=` path it's there for, when that's where the dependency comes from
+  pub path: Option<String>,
+  /// Filled in once the unit's state has been read
+  pub active_state: String,
+}
+
+impl Requirement {
+  pub fn is_available(&self) -> bool {
+    self.active_state == "active"
+  }
+}
+
+/// The mount and device units among a unit's `REQUIREMENT_PROPERTIES`, the ones backing its `RequiresMountsFor=`
+/// paths first. The root file system is left out unless a path is on it, as every service needs it anyway
+pub fn requirements(properties: &HashMap<String, String>) -> Vec<Requirement> {
+  let words = |property: &str| properties.get(property).map_or(vec![], |v| v.split_whitespace().collect::<Vec<_>>());
+  let dependencies = [words("Requires"), words("BindsTo")]
+    .concat()
+    .into_iter()
+    .filter(|u| u.ends_with(".mount") || u.ends_with(".device"))
+    .collect::<Vec<_>>();
+
+  let mut requirements: Vec<Requirement> = vec![];
+  for path in words("RequiresMountsFor") {
+    // the innermost mount the path is on; systemd only adds those that have a unit
+    let unit = ancestors(path).map(|a| mount_unit_name(&a)).find(|u| dependencies.contains(&u.as_str()));
+    if let Some(unit) = unit {
+      requirements.push(Requirement { unit, path: Some(path.to_string()), active_state: String::new() });
+    }
+  }
+  for unit in dependencies {
+    if unit != "-.mount" && !requirements.iter().any(|r| r.unit == unit) {
+      requirements.push(Requirement { unit: unit.to_string(), path: None, active_state: String::new() });
+    }
+  }
+  requirements
+}
+
+/// `/srv/data/x`, `/srv/data`, `/srv`, `/`
+fn ancestors(path: &str) -> impl Iterator<Item = String> + '_ {
+  let path = path.trim_end_matches('/');
+  std::iter::successors(Some(path.to_string()), |p| {
+    if p.is_empty() {
+      return None;
+    }
+    Some(p.rsplit_once('/').map_or(String::new(), |(parent, _)| parent.to_string()))
+  })
+  .map(|p| if p.is_empty() { "/".to_string() } else { p })
+}
+
+/// The mount unit for a mount point, as `systemd-escape --path --suffix=mount` names it: `/srv/my-data` ->
+/// `srv-my\x2ddata.mount`
+pub fn mount_unit_name(path: &str) -> String {
+  let parts = path.split('/').filter(|p| !p.is_empty()).collect::<Vec<_>>();
+  if parts.is_empty() {
+    return "-.mount".into();
+  }
+  let mut name = String::new();
+  for (i, part) in parts.iter().enumerate() {
+    if i > 0 {
+      name.push('-');
+    }
+    for (j, byte) in part.bytes().enumerate() {
+      match byte {
+        b'.' if i == 0 && j == 0 => name.push_str("\\x2e"),
+        b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b':' | b'_' | b'.' => name.push(byte as char),
+        _ => name.push_str(&format!("\\x{byte:02x}")),
+      }
+    }
+  }
+  name + ".mount"
+}
+
+/// A suggestion for fixing the failure, from the unit's `Result` and whether its device exists
+pub fn hint(properties: &HashMap<String, String>, device_exists: Option<bool>) -> &'static str {
+  if device_exists == Some(false) {
+    return "The device isn't there. Check it's attached and that the UUID/LABEL in fstab matches `lsblk -f`";
+  }
+  match properties.get("Result").map(String::as_str) {
+    Some("timeout") => "Mounting timed out. For network or slow disks, add x-systemd.mount-timeout= or nofail in fstab",
+    Some("exit-code") => "mount itself failed; its error is in the log lines below. Often a wrong Type= or option",
+    Some("resources") => "systemd couldn't set up the mount. Check the mount point exists and isn't already in use",
+    Some("success") => "The last attempt succeeded",
+    _ => "After fixing fstab or the unit file, retry: it reloads systemd first so the fix is picked up",
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_fstab_entry() {
+    let fstab = "# /etc/fstab\nUUID=abc / ext4 defaults 0 1\n#UUID=old /data ext4 defaults 0 2\n\
+                 LABEL=backup /mnt/my\\040disk ext4 nofail 0 2\n";
+    assert_eq!(fstab_entry(fstab, "/"), Some((2, "UUID=abc / ext4 defaults 0 1".into())));
+    assert_eq!(fstab_entry(fstab, "/mnt/my disk").map(|(line, _)| line), Some(4));
+    assert_eq!(fstab_entry(fstab, "/data"), None);
+  }
+
+  #[test]
+  fn test_device_path() {
+    assert_eq!(device_path("/dev/sdb1").as_deref(), Some("/dev/sdb1"));
+    assert_eq!(device_path("UUID=1234-ABCD").as_deref(), Some("/dev/disk/by-uuid/1234-ABCD"));
+    assert_eq!(device_path("server:/export"), None);
+    assert_eq!(mount_unit_for("home.automount"), "home.mount");
+  }
+
+  #[test]
+  fn test_requirements() {
+    assert_eq!(mount_unit_name("/"), "-.mount");
+    assert_eq!(mount_unit_name("/srv/my-data/"), "srv-my\\x2ddata.mount");
+    assert_eq!(mount_unit_name("/.snapshots"), "\\x2esnapshots.mount");
+
+    let properties = HashMap::from([
+      ("RequiresMountsFor".to_string(), "/srv/data/db /var/tmp".to_string()),
+      ("Requires".to_string(), "system.slice -.mount srv-data.mount sysinit.target".to_string()),
+      ("BindsTo".to_string(), "dev-sdb1.device".to_string()),
+    ]);
+    let found = requirements(&properties).into_iter().map(|r| (r.unit, r.path)).collect::<Vec<_>>();
+    assert_eq!(
+      found,
+      [
+        ("srv-data.mount".to_string(), Some("/srv/data/db".to_string())),
+        ("-.mount".to_string(), Some("/var/tmp".to_string())),
+        ("dev-sdb1.device".to_string(), None),
+      ]
+    );
+  }
+}
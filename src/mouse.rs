@@ -0,0 +1,97 @@
+// Clicking and scrolling. Each render leaves behind where the unit list, the log pane and the footer's key hints were
+// drawn, so a click can be mapped back to the unit or hint under it, and a hint clicked works like pressing its key.
+// The keyboard does everything the mouse does; `no-mouse` in the config leaves the mouse to the terminal instead, e.g.
+// for selecting text in tmux.
+
+use crossterm::event::KeyCode;
+use ratatui::layout::{Position, Rect};
+
+/// Lines or units moved per notch of the wheel
+pub const WHEEL_LINES: u16 = 3;
+
+/// Where things were drawn by the last render, inside their borders
+#[derive(Debug, Default, Clone)]
+pub struct Areas {
+  pub list: Rect,
+  pub logs: Rect,
+  pub hints: Rect,
+  /// The footer's hints, like `Show actions: <enter> | Quit: q`
+  pub hints_text: String,
+}
+
+impl Areas {
+  pub fn in_list(&self, column: u16, row: u16) -> bool {
+    self.list.contains(Position::new(column, row))
+  }
+
+  pub fn in_logs(&self, column: u16, row: u16) -> bool {
+    self.logs.contains(Position::new(column, row))
+  }
+
+  /// The index of the unit drawn at `row`, with the list scrolled `offset` units down
+  pub fn unit_at(&self, column: u16, row: u16, offset: usize, units: usize) -> Option<usize> {
+    let index = offset + row.checked_sub(self.list.y)? as usize;
+    (self.in_list(column, row) && index < units).then_some(index)
+  }
+
+  /// The key of the footer hint at this position
+  pub fn hint_at(&self, column: u16, row: u16) -> Option<KeyCode> {
+    if !self.hints.contains(Position::new(column, row)) {
+      return None;
+    }
+    hint_at(&self.hints_text, (column - self.hints.x) as usize)
+  }
+}
+
+/// The key a hint's `<enter>` or `q` stands for. Hints for several keys, like `j/k`, aren't clickable
+pub fn hint_key(key: &str) -> Option<KeyCode> {
+  match key {
+    "<enter>" => Some(KeyCode::Enter),
+    "<esc>" => Some(KeyCode::Esc),
+    "<tab>" => Some(KeyCode::Tab),
+    _ => {
+      let mut chars = key.chars();
+      match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(KeyCode::Char(c)),
+        _ => None,
+      }
+    },
+  }
+}
+
+/// The key of the hint `column` characters into `text`, hints like `Quit: q` separated by ` | `
+pub fn hint_at(text: &str, column: usize) -> Option<KeyCode> {
+  let mut start = 0;
+  for hint in text.split(" | ") {
+    let end = start + hint.chars().count();
+    if (start..end).contains(&column) {
+      return hint_key(hint.rsplit_once(": ")?.1);
+    }
+    start = end + " | ".len();
+  }
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_hint_at() {
+    let text = "Show actions: <enter> | Open unit file: e | Scroll: j/k | Quit: q";
+    assert_eq!(hint_at(text, 0), Some(KeyCode::Enter));
+    assert_eq!(hint_at(text, 20), Some(KeyCode::Enter));
+    // the separator between hints
+    assert_eq!(hint_at(text, 22), None);
+    assert_eq!(hint_at(text, 24), Some(KeyCode::Char('e')));
+    assert_eq!(hint_at(text, 50), None);
+    assert_eq!(hint_at(text, text.len() - 1), Some(KeyCode::Char('q')));
+    assert_eq!(hint_at(text, text.len()), None);
+
+    let areas = Areas { list: Rect::new(1, 4, 30, 10), ..Default::default() };
+    assert_eq!(areas.unit_at(5, 4, 0, 20), Some(0));
+    assert_eq!(areas.unit_at(5, 6, 12, 20), Some(14));
+    assert_eq!(areas.unit_at(5, 6, 0, 2), None);
+    assert_eq!(areas.unit_at(40, 6, 0, 20), None);
+  }
+}
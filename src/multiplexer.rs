@@ -0,0 +1,88 @@
+// Opening a unit's log stream next to the TUI when it's running inside tmux or zellij, so logs can stay on screen
+// while the TUI moves on to other units.
+
+use std::process::Command;
+
+use anyhow::{bail, Result};
+
+use crate::{
+  systemd::{UnitId, UnitScope},
+  user_manager,
+  utils::shell_quote,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multiplexer {
+  Tmux,
+  Zellij,
+}
+
+impl Multiplexer {
+  /// The multiplexer this process is running inside, going by the variables each one sets for its panes
+  pub fn detect() -> Option<Self> {
+    if std::env::var_os("TMUX").is_some() {
+      Some(Multiplexer::Tmux)
+    } else if std::env::var_os("ZELLIJ").is_some() {
+      Some(Multiplexer::Zellij)
+    } else {
+      None
+    }
+  }
+
+  pub fn label(self) -> &'static str {
+    match self {
+      Multiplexer::Tmux => "tmux",
+      Multiplexer::Zellij => "zellij",
+    }
+  }
+
+  /// The command that opens a new pane running `journalctl --follow` for `unit`
+  pub fn follow_command(self, unit: &UnitId) -> Vec<String> {
+    let mut journalctl = match user_manager::user() {
+      Some(user) if unit.scope == UnitScope::User => user.argv("journalctl"),
+      _ => vec!["journalctl".to_string()],
+    };
+    journalctl.extend(["--follow".into(), "--lines=100".into(), "-u".into(), unit.name.clone()]);
+    if unit.scope == UnitScope::User {
+      journalctl.push("--user".into());
+    }
+
+    match self {
+      // tmux runs its command through a shell, zellij takes an argv
+      Multiplexer::Tmux => {
+        let shell_command = journalctl.iter().map(|a| shell_quote(a)).collect::<Vec<_>>().join(" ");
+        vec!["tmux".into(), "split-window".into(), "-h".into(), shell_command]
+      },
+      Multiplexer::Zellij => {
+        let mut command = vec!["zellij".to_string(), "run".into(), "--name".into(), unit.name.clone(), "--".into()];
+        command.extend(journalctl);
+        command
+      },
+    }
+  }
+
+  pub fn follow_logs(self, unit: &UnitId) -> Result<()> {
+    let command = self.follow_command(unit);
+    let output = Command::new(&command[0]).args(&command[1..]).output()?;
+    if !output.status.success() {
+      bail!("Failed to open a {} pane: {}", self.label(), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_follow_command() {
+    let unit = UnitId { name: "getty@tty1.service".into(), scope: UnitScope::User };
+    assert_eq!(
+      Multiplexer::Tmux.follow_command(&unit),
+      vec!["tmux", "split-window", "-h", "journalctl --follow --lines=100 -u getty@tty1.service --user"]
+    );
+    assert_eq!(Multiplexer::Zellij.follow_command(&unit)[..5], ["zellij", "run", "--name", "getty@tty1.service", "--"]);
+    assert_eq!(shell_quote("it's"), r"'it'\''s'");
+  }
+}
@@ -0,0 +1,216 @@
+// Finding ordering cycles (After=/Before= loops) among loaded units, and the cycles systemd reported breaking at boot.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+/// "`from` is ordered after `to`", and which directive said so
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edge {
+  pub from: String,
+  pub to: String,
+  /// e.g. `b.service: After=a.service` or `a.service: Before=b.service`
+  pub origin: String,
+}
+
+#[derive(Debug, Default)]
+pub struct OrderingGraph {
+  /// unit -> units it's ordered after, with the origin of each edge
+  after: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl OrderingGraph {
+  /// Build from each unit's `Id`, `After` and `Before` properties
+  pub fn from_properties(units: &[HashMap<String, String>]) -> Self {
+    let mut graph = Self::default();
+    for properties in units {
+      let Some(id) = properties.get("Id").filter(|id| !id.is_empty()) else { continue };
+      for other in properties.get("After").map(|v| v.split_whitespace()).into_iter().flatten() {
+        graph.add(id, other, format!("{id}: After={other}"));
+      }
+      for other in properties.get("Before").map(|v| v.split_whitespace()).into_iter().flatten() {
+        graph.add(other, id, format!("{id}: Before={other}"));
+      }
+    }
+    graph
+  }
+
+  fn add(&mut self, from: &str, to: &str, origin: String) {
+    self.after.entry(from.to_string()).or_default().entry(to.to_string()).or_insert(origin);
+    self.after.entry(to.to_string()).or_default();
+  }
+
+  fn successors(&self, unit: &str) -> impl Iterator<Item = &String> {
+    self.after.get(unit).into_iter().flat_map(|m| m.keys())
+  }
+
+  /// One cycle per strongly connected component, as the edges going around it
+  pub fn cycles(&self) -> Vec<Vec<Edge>> {
+    self
+      .components()
+      .into_iter()
+      .filter_map(|component| {
+        let start = component.iter().next()?;
+        let path = self.shortest_path_back(start, &component)?;
+        let edges = path
+          .windows(2)
+          .map(|w| Edge { from: w[0].clone(), to: w[1].clone(), origin: self.after[&w[0]][&w[1]].clone() })
+          .collect();
+        Some(edges)
+      })
+      .collect()
+  }
+
+  /// BFS from `start` through `component` back to `start`; returns the units visited, starting and ending with `start`
+  fn shortest_path_back(&self, start: &String, component: &BTreeSet<String>) -> Option<Vec<String>> {
+    let mut previous: HashMap<&String, &String> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(unit) = queue.pop_front() {
+      for next in self.successors(unit).filter(|n| component.contains(*n)) {
+        if next == start {
+          let mut path = vec![start.clone()];
+          let mut current = unit;
+          while current != start {
+            path.push(current.clone());
+            current = previous[current];
+          }
+          path.push(start.clone());
+          path.reverse();
+          return Some(path);
+        }
+        if !previous.contains_key(next) {
+          previous.insert(next, unit);
+          queue.push_back(next);
+        }
+      }
+    }
+    None
+  }
+
+  /// Tarjan's algorithm, keeping only components that contain a cycle
+  fn components(&self) -> Vec<BTreeSet<String>> {
+    struct State<'a> {
+      index: usize,
+      indices: HashMap<&'a String, usize>,
+      lowlink: HashMap<&'a String, usize>,
+      stack: Vec<&'a String>,
+      on_stack: BTreeSet<&'a String>,
+      components: Vec<BTreeSet<String>>,
+    }
+
+    fn connect<'a>(graph: &'a OrderingGraph, unit: &'a String, state: &mut State<'a>) {
+      state.indices.insert(unit, state.index);
+      state.lowlink.insert(unit, state.index);
+      state.index += 1;
+      state.stack.push(unit);
+      state.on_stack.insert(unit);
+
+      for next in graph.successors(unit) {
+        if !state.indices.contains_key(next) {
+          connect(graph, next, state);
+          let low = state.lowlink[unit].min(state.lowlink[next]);
+          state.lowlink.insert(unit, low);
+        } else if state.on_stack.contains(next) {
+          let low = state.lowlink[unit].min(state.indices[next]);
+          state.lowlink.insert(unit, low);
+        }
+      }
+
+      if state.lowlink[unit] == state.indices[unit] {
+        let mut component = BTreeSet::new();
+        while let Some(member) = state.stack.pop() {
+          state.on_stack.remove(member);
+          component.insert(member.clone());
+          if member == unit {
+            break;
+          }
+        }
+        let self_loop = graph.successors(unit).any(|n| n == unit);
+        if component.len() > 1 || self_loop {
+          state.components.push(component);
+        }
+      }
+    }
+
+    let mut state = State {
+      index: 0,
+      indices: HashMap::new(),
+      lowlink: HashMap::new(),
+      stack: vec![],
+      on_stack: BTreeSet::new(),
+      components: vec![],
+    };
+    for unit in self.after.keys() {
+      if !state.indices.contains_key(unit) {
+        connect(self, unit, &mut state);
+      }
+    }
+    state.components
+  }
+}
+
+/// A cycle systemd ran into while building a transaction, from its "Found ordering cycle" log messages
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BrokenCycle {
+  /// Jobs around the cycle, e.g. `foo.service/start`
+  pub jobs: Vec<String>,
+  /// The job systemd deleted to break it, if it managed to
+  pub dropped: Option<String>,
+}
+
+/// Pick the cycle reports out of PID 1's messages (as printed by `journalctl -o cat`)
+pub fn parse_cycle_messages<'a>(lines: impl IntoIterator<Item = &'a str>) -> Vec<BrokenCycle> {
+  let mut cycles: Vec<BrokenCycle> = vec![];
+  for line in lines {
+    if let Some((_, job)) = line.split_once("Found ordering cycle on ") {
+      cycles.push(BrokenCycle { jobs: vec![job.trim().to_string()], dropped: None });
+    } else if let Some((_, job)) = line.split_once("Found dependency on ") {
+      if let Some(cycle) = cycles.last_mut() {
+        cycle.jobs.push(job.trim().to_string());
+      }
+    } else if let Some((_, rest)) = line.split_once("Job ") {
+      if let (Some((job, _)), Some(cycle)) = (rest.split_once(" deleted to break ordering cycle"), cycles.last_mut()) {
+        cycle.dropped = Some(job.trim().to_string());
+      }
+    }
+  }
+  cycles
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::systemd::parse_property_blocks;
+
+  #[test]
+  fn test_cycles() {
+    let output = "Id=a.service\nAfter=b.service basic.target\nBefore=\n\n\
+                  Id=b.service\nAfter=\nBefore=\n\n\
+                  Id=c.service\nAfter=\nBefore=b.service\n\n\
+                  Id=d.service\nAfter=c.service\nBefore=c.service\n";
+    // a after b after c is fine, but d after c (After=) and c after d (d's Before=) loop
+    let graph = OrderingGraph::from_properties(&parse_property_blocks(output));
+    let cycles = graph.cycles();
+    assert_eq!(cycles.len(), 1);
+    let units: BTreeSet<&str> = cycles[0].iter().map(|e| e.from.as_str()).collect();
+    assert_eq!(units, BTreeSet::from(["c.service", "d.service"]));
+    assert!(cycles[0].iter().any(|e| e.origin == "d.service: Before=c.service"));
+
+    let looped =
+      OrderingGraph::from_properties(&parse_property_blocks(&format!("{output}\nId=b.service\nAfter=a.service\n")));
+    assert_eq!(looped.cycles().len(), 2);
+  }
+
+  #[test]
+  fn test_parse_cycle_messages() {
+    let messages = [
+      "Reached target basic.target - Basic System.",
+      "multi-user.target: Found ordering cycle on foo.service/start",
+      "multi-user.target: Found dependency on bar.service/start",
+      "multi-user.target: Found dependency on foo.service/start",
+      "multi-user.target: Job bar.service/start deleted to break ordering cycle starting with foo.service/start",
+    ];
+    let cycles = parse_cycle_messages(messages);
+    assert_eq!(cycles.len(), 1);
+    assert_eq!(cycles[0].jobs, vec!["foo.service/start", "bar.service/start", "foo.service/start"]);
+    assert_eq!(cycles[0].dropped.as_deref(), Some("bar.service/start"));
+  }
+}
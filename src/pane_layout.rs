@@ -0,0 +1,154 @@
+// How the unit list, the details and the logs share the screen. Side by side, the default, puts the list on the left
+// and the details above the logs on the right, which suits a wide monitor; stacked puts all three one above another,
+// for a narrow SSH window. The list's share can be grown and shrunk from the keyboard, and the log pane maximized to
+// take the whole screen while reading.
+
+use ratatui::layout::{Constraint, Layout, Rect};
+use serde::Deserialize;
+
+/// Percentage points the list grows or shrinks by per key press
+const STEP: u16 = 5;
+const MIN_SHARE: u16 = 10;
+const MAX_SHARE: u16 = 90;
+/// The list's share of the height when stacked, unless set
+const STACKED_SHARE: u16 = 30;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Split {
+  /// The unit list on the left, the details above the logs on the right
+  #[default]
+  SideBySide,
+  /// The unit list, the details and the logs one above another
+  Stacked,
+}
+
+impl Split {
+  pub fn toggled(self) -> Self {
+    match self {
+      Split::SideBySide => Split::Stacked,
+      Split::Stacked => Split::SideBySide,
+    }
+  }
+}
+
+/// Where each pane goes. Panes that aren't shown get an empty area
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Panes {
+  pub list: Rect,
+  pub details: Rect,
+  pub logs: Rect,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PaneLayout {
+  pub split: Split,
+  /// Percent of the width (side by side) or height (stacked) the list takes. Unset, the list is as wide as its
+  /// columns need, or takes a third of the height
+  pub list_share: Option<u16>,
+  /// Only the log pane is shown
+  pub logs_maximized: bool,
+  /// The list's share when last laid out, for resizing from when it isn't set
+  shown_share: u16,
+}
+
+impl PaneLayout {
+  pub fn new(split: Split, list_share: Option<u16>) -> Self {
+    let list_share = list_share.map(|s| s.clamp(MIN_SHARE, MAX_SHARE));
+    Self { split, list_share, ..Default::default() }
+  }
+
+  /// Split `area` between the panes, the list at least `list_width` wide side by side and the details
+  /// `details_height` high
+  pub fn panes(&mut self, area: Rect, list_width: u16, details_height: u16) -> Panes {
+    if self.logs_maximized {
+      return Panes { list: Rect::default(), details: Rect::default(), logs: area };
+    }
+    let panes = match self.split {
+      Split::SideBySide => {
+        let list = match self.list_share {
+          Some(share) => Constraint::Percentage(share),
+          None => Constraint::Min(list_width),
+        };
+        let [list, right] = Layout::horizontal([list, Constraint::Percentage(100)]).areas(area);
+        let [details, logs] =
+          Layout::vertical([Constraint::Min(details_height), Constraint::Percentage(100)]).areas(right);
+        Panes { list, details, logs }
+      },
+      Split::Stacked => {
+        let list = Constraint::Percentage(self.list_share.unwrap_or(STACKED_SHARE));
+        let [list, details, logs] =
+          Layout::vertical([list, Constraint::Length(details_height), Constraint::Fill(1)]).areas(area);
+        Panes { list, details, logs }
+      },
+    };
+    self.shown_share = match self.split {
+      Split::SideBySide => share(panes.list.width, area.width),
+      Split::Stacked => share(panes.list.height, area.height),
+    };
+    panes
+  }
+
+  /// Give the list a bigger share of the screen, or with `grow` false a smaller one
+  pub fn resize(&mut self, grow: bool) {
+    let share = self.list_share.unwrap_or(self.shown_share);
+    let share = if grow { share.saturating_add(STEP) } else { share.saturating_sub(STEP) };
+    self.list_share = Some(share.clamp(MIN_SHARE, MAX_SHARE));
+  }
+
+  /// `stacked, list 40%`, for the status message after a change
+  pub fn describe(&self) -> String {
+    let split = match self.split {
+      Split::SideBySide => "side by side",
+      Split::Stacked => "stacked",
+    };
+    match self.list_share {
+      Some(share) => format!("{split}, list {share}%"),
+      None => split.to_string(),
+    }
+  }
+}
+
+fn share(part: u16, whole: u16) -> u16 {
+  if whole == 0 {
+    return 0;
+  }
+  (part as u32 * 100 / whole as u32) as u16
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_panes() {
+    let area = Rect::new(0, 0, 200, 50);
+    let mut layout = PaneLayout::default();
+    let panes = layout.panes(area, 40, 15);
+    assert_eq!(panes.list, Rect::new(0, 0, 40, 50));
+    assert_eq!(panes.details, Rect::new(40, 0, 160, 15));
+    assert_eq!(panes.logs, Rect::new(40, 15, 160, 35));
+
+    // resizing starts from where the list was, a fifth of the width
+    layout.resize(true);
+    assert_eq!(layout.list_share, Some(25));
+    assert_eq!(layout.panes(area, 40, 15).list.width, 50);
+    for _ in 0..20 {
+      layout.resize(false);
+    }
+    assert_eq!(layout.list_share, Some(MIN_SHARE));
+
+    let mut layout = PaneLayout::new(Split::SideBySide.toggled(), Some(40));
+    let panes = layout.panes(area, 40, 15);
+    assert_eq!(panes.list, Rect::new(0, 0, 200, 20));
+    assert_eq!(panes.details, Rect::new(0, 20, 200, 15));
+    assert_eq!(panes.logs, Rect::new(0, 35, 200, 15));
+    assert_eq!(layout.describe(), "stacked, list 40%");
+
+    layout.logs_maximized = true;
+    let panes = layout.panes(area, 40, 15);
+    assert_eq!(panes.logs, area);
+    assert!(panes.list.is_empty() && panes.details.is_empty());
+    assert_eq!(PaneLayout::new(Split::Stacked, Some(100)).list_share, Some(MAX_SHARE));
+  }
+}
@@ -0,0 +1,97 @@
+// Running configured plugins: external executables that report on the selected unit, for checks this tool can't know
+// about (a company health endpoint, the Kubernetes pod a unit backs, ...).
+//
+// The protocol is deliberately small. The plugin gets the unit name as its last argument, plus `SYSTEMCTL_TUI_UNIT`
+// and `SYSTEMCTL_TUI_SCOPE` (`system` or `user`) in its environment, and prints lines of text. A line starting with
+// `ok:`, `warn:` or `error:` is shown with a matching status marker, and a line starting with `# ` as a heading.
+
+use std::{process::Command, time::Duration};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{
+  config::Plugin,
+  subprocess::run_with_timeout,
+  systemd::{UnitId, UnitScope},
+};
+
+/// How long a plugin gets before it's killed
+const PLUGIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+  Ok,
+  Warn,
+  Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputLine {
+  Heading(String),
+  Status(Status, String),
+  Text(String),
+}
+
+pub fn parse_line(line: &str) -> OutputLine {
+  if let Some(heading) = line.strip_prefix("# ") {
+    return OutputLine::Heading(heading.to_string());
+  }
+  for (prefix, status) in [("ok:", Status::Ok), ("warn:", Status::Warn), ("error:", Status::Error)] {
+    if let Some(text) = line.strip_prefix(prefix) {
+      return OutputLine::Status(status, text.trim_start().to_string());
+    }
+  }
+  OutputLine::Text(line.to_string())
+}
+
+/// Run `plugin` for `unit`. Output from a plugin that exits unsuccessfully is still returned, after a status line
+/// saying so, since that's often where it explains what's wrong
+pub fn run(plugin: &Plugin, unit: &UnitId) -> Result<Vec<OutputLine>> {
+  let Some((program, args)) = plugin.command.split_first() else { bail!("Plugin `{}` has no command", plugin.name) };
+  let scope = match unit.scope {
+    UnitScope::Global => "system",
+    UnitScope::User => "user",
+  };
+  let mut command = Command::new(program);
+  command.args(args).arg(&unit.name).env("SYSTEMCTL_TUI_UNIT", &unit.name).env("SYSTEMCTL_TUI_SCOPE", scope);
+  let finished = run_with_timeout(&mut command, None, PLUGIN_TIMEOUT)
+    .with_context(|| format!("Failed to run plugin `{}` ({program})", plugin.name))?;
+  let Some(finished) = finished else {
+    bail!("Plugin `{}` was still running after {}s, so it was stopped", plugin.name, PLUGIN_TIMEOUT.as_secs());
+  };
+
+  let mut lines = vec![];
+  if !finished.status.success() {
+    let status = finished.status;
+    lines.push(OutputLine::Status(Status::Error, format!("{} exited unsuccessfully ({status})", plugin.name)));
+  }
+  lines.extend(finished.stdout.lines().map(parse_line));
+  lines.extend(finished.stderr.lines().map(|l| OutputLine::Text(l.to_string())));
+  Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_line() {
+    assert_eq!(parse_line("# Pods"), OutputLine::Heading("Pods".into()));
+    assert_eq!(parse_line("ok: healthy"), OutputLine::Status(Status::Ok, "healthy".into()));
+    assert_eq!(parse_line("error:timeout"), OutputLine::Status(Status::Error, "timeout".into()));
+    assert_eq!(parse_line("okay"), OutputLine::Text("okay".into()));
+  }
+
+  #[test]
+  fn test_run() {
+    let plugin = Plugin {
+      name: "echo".into(),
+      command: vec!["sh".into(), "-c".into(), "echo \"ok: $1 $SYSTEMCTL_TUI_SCOPE\"; exit 3".into(), "sh".into()],
+      read_only: true,
+    };
+    let unit = UnitId { name: "nginx.service".into(), scope: UnitScope::User };
+    let lines = run(&plugin, &unit).unwrap();
+    assert!(matches!(lines[0], OutputLine::Status(Status::Error, _)));
+    assert_eq!(lines[1], OutputLine::Status(Status::Ok, "nginx.service user".into()));
+  }
+}
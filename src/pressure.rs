@@ -0,0 +1,86 @@
+// Pressure stall information (PSI) for a unit's cgroup: the share of time its tasks spent waiting on CPU, memory or
+// I/O. Unlike CPU usage this rises when a service is starved, which is usually what "is it struggling?" means.
+// See https://docs.kernel.org/accounting/psi.html
+
+use std::path::{Path, PathBuf};
+
+/// Percent of the last minute spent stalled above which a unit counts as under sustained pressure
+pub const SUSTAINED_PRESSURE: f64 = 10.0;
+
+/// The `some` line of one pressure file: the share of time at least one task was stalled, in percent
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Stall {
+  pub avg10: f64,
+  pub avg60: f64,
+  pub avg300: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Pressure {
+  pub cpu: Stall,
+  pub memory: Stall,
+  pub io: Stall,
+}
+
+impl Pressure {
+  pub fn is_sustained(&self) -> bool {
+    [self.cpu, self.memory, self.io].iter().any(|s| s.avg60 >= SUSTAINED_PRESSURE)
+  }
+}
+
+/// Parse the `some` line of a `cpu.pressure`, `memory.pressure` or `io.pressure` file
+pub fn parse_stall(text: &str) -> Option<Stall> {
+  let line = text.lines().find(|l| l.starts_with("some "))?;
+  let mut stall = Stall::default();
+  for (key, value) in line.split_whitespace().skip(1).filter_map(|f| f.split_once('=')) {
+    let value = value.parse().ok()?;
+    match key {
+      "avg10" => stall.avg10 = value,
+      "avg60" => stall.avg60 = value,
+      "avg300" => stall.avg300 = value,
+      _ => {},
+    }
+  }
+  Some(stall)
+}
+
+/// Where the unified (v2) hierarchy is mounted, including on hybrid setups
+fn cgroup_root() -> PathBuf {
+  let root = Path::new("/sys/fs/cgroup");
+  if root.join("cgroup.controllers").exists() {
+    root.to_path_buf()
+  } else {
+    root.join("unified")
+  }
+}
+
+/// The directory of a cgroup, given as a unit's `ControlGroup` property
+pub fn cgroup_path(control_group: &str) -> PathBuf {
+  cgroup_root().join(control_group.trim_start_matches('/'))
+}
+
+/// Read the pressure files of a cgroup, given as the unit's `ControlGroup` property. `None` when the unit has no
+/// cgroup or the kernel doesn't expose PSI
+pub fn read_pressure(control_group: &str) -> Option<Pressure> {
+  if control_group.is_empty() {
+    return None;
+  }
+  let dir = cgroup_path(control_group);
+  let read = |file: &str| std::fs::read_to_string(dir.join(file)).ok().and_then(|t| parse_stall(&t));
+  Some(Pressure { cpu: read("cpu.pressure")?, memory: read("memory.pressure")?, io: read("io.pressure")? })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_stall() {
+    let text = "some avg10=1.50 avg60=12.25 avg300=3.00 total=123456\nfull avg10=0.00 avg60=0.00 avg300=0.00 total=0\n";
+    let stall = parse_stall(text).unwrap();
+    assert_eq!(stall, Stall { avg10: 1.5, avg60: 12.25, avg300: 3.0 });
+    assert!(Pressure { io: stall, ..Default::default() }.is_sustained());
+    assert!(!Pressure::default().is_sustained());
+    assert_eq!(parse_stall(""), None);
+  }
+}
@@ -0,0 +1,201 @@
+// The processes in a unit's cgroup as a tree, like the `CGroup:` section of `systemctl status` but with CPU and
+// memory per process and kept up to date. Processes come from the `cgroup.procs` files of the unit's cgroup and the
+// cgroups below it, and everything else about them from /proc, so this only works on the local machine.
+
+use std::{
+  collections::{HashMap, HashSet},
+  path::{Path, PathBuf},
+  time::Instant,
+};
+
+use anyhow::{Context, Result};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Process {
+  pub pid: u32,
+  pub ppid: u32,
+  pub comm: String,
+  /// The command line with its arguments, empty for kernel threads
+  pub cmdline: String,
+  /// User and system CPU time, in clock ticks
+  pub cpu_ticks: u64,
+  pub rss_bytes: u64,
+}
+
+/// CPU time of each process at one point, to work out CPU use from the next reading
+#[derive(Debug, Clone)]
+pub struct Sample {
+  pub at: Instant,
+  pub cpu_ticks: HashMap<u32, u64>,
+}
+
+impl Sample {
+  pub fn of(processes: &[Process]) -> Self {
+    Self { at: Instant::now(), cpu_ticks: processes.iter().map(|p| (p.pid, p.cpu_ticks)).collect() }
+  }
+
+  /// CPU use of `process` since this sample, as a percentage of one CPU. `None` for processes that weren't around
+  pub fn cpu_percent(&self, process: &Process, now: Instant) -> Option<f64> {
+    let earlier = self.cpu_ticks.get(&process.pid)?;
+    let elapsed = now.duration_since(self.at).as_secs_f64();
+    if elapsed <= 0.0 {
+      return None;
+    }
+    let seconds = process.cpu_ticks.saturating_sub(*earlier) as f64 / clock_ticks_per_second() as f64;
+    Some(seconds / elapsed * 100.0)
+  }
+}
+
+fn clock_ticks_per_second() -> u64 {
+  // SAFETY: sysconf has no preconditions
+  let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+  if ticks > 0 {
+    ticks as u64
+  } else {
+    100
+  }
+}
+
+fn page_size() -> u64 {
+  // SAFETY: sysconf has no preconditions
+  let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+  if size > 0 {
+    size as u64
+  } else {
+    4096
+  }
+}
+
+/// The processes in a cgroup, given as the unit's `ControlGroup` property, and in the cgroups below it
+pub fn read(control_group: &str) -> Result<Vec<Process>> {
+  let directory = crate::pressure::cgroup_path(control_group);
+  let mut pids = vec![];
+  collect_pids(&directory, &mut pids).with_context(|| format!("Failed to read {}", directory.display()))?;
+  // processes can exit between listing and reading them
+  Ok(pids.into_iter().filter_map(read_process).collect())
+}
+
+fn collect_pids(directory: &Path, pids: &mut Vec<u32>) -> Result<()> {
+  let procs = std::fs::read_to_string(directory.join("cgroup.procs"))?;
+  pids.extend(procs.lines().filter_map(|l| l.trim().parse::<u32>().ok()));
+  for entry in std::fs::read_dir(directory)?.flatten() {
+    if entry.file_type().is_ok_and(|t| t.is_dir()) {
+      // a cgroup that goes away while we look is no loss
+      let _ = collect_pids(&entry.path(), pids);
+    }
+  }
+  Ok(())
+}
+
+fn read_process(pid: u32) -> Option<Process> {
+  let proc = PathBuf::from(format!("/proc/{pid}"));
+  let stat = parse_stat(&std::fs::read_to_string(proc.join("stat")).ok()?)?;
+  let cmdline = std::fs::read(proc.join("cmdline")).unwrap_or_default();
+  let cmdline = String::from_utf8_lossy(&cmdline).split('\0').filter(|a| !a.is_empty()).collect::<Vec<_>>().join(" ");
+  Some(Process {
+    pid,
+    ppid: stat.ppid,
+    comm: stat.comm,
+    cmdline,
+    cpu_ticks: stat.cpu_ticks,
+    rss_bytes: stat.rss_pages * page_size(),
+  })
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Stat {
+  comm: String,
+  ppid: u32,
+  cpu_ticks: u64,
+  rss_pages: u64,
+}
+
+/// Parse `/proc/<pid>/stat`. The command name is in parentheses and may contain spaces and parentheses itself, so
+/// the fields after it are counted from the last `)`
+fn parse_stat(text: &str) -> Option<Stat> {
+  let open = text.find('(')?;
+  let close = text.rfind(')')?;
+  let comm = text.get(open + 1..close)?.to_string();
+  // fields from the state on, which is field 3
+  let fields: Vec<&str> = text.get(close + 1..)?.split_whitespace().collect();
+  let field = |number: usize| fields.get(number - 3).and_then(|f| f.parse::<u64>().ok());
+  Some(Stat { comm, ppid: field(4)? as u32, cpu_ticks: field(14)? + field(15)?, rss_pages: field(24)? })
+}
+
+/// The processes in tree order, each with its depth: parents before their children, siblings by PID. Processes
+/// whose parent is outside the cgroup are at the top
+pub fn tree(processes: &[Process]) -> Vec<(usize, &Process)> {
+  let pids: HashSet<u32> = processes.iter().map(|p| p.pid).collect();
+  let mut children: HashMap<u32, Vec<&Process>> = HashMap::new();
+  let mut roots = vec![];
+  for process in processes {
+    if pids.contains(&process.ppid) && process.ppid != process.pid {
+      children.entry(process.ppid).or_default().push(process);
+    } else {
+      roots.push(process);
+    }
+  }
+  roots.sort_by_key(|p| p.pid);
+  children.values_mut().for_each(|c| c.sort_by_key(|p| p.pid));
+
+  let mut ordered = vec![];
+  let mut stack: Vec<(usize, &Process)> = roots.into_iter().rev().map(|p| (0, p)).collect();
+  while let Some((depth, process)) = stack.pop() {
+    ordered.push((depth, process));
+    if let Some(children) = children.get(&process.pid) {
+      stack.extend(children.iter().rev().map(|c| (depth + 1, *c)));
+    }
+  }
+  ordered
+}
+
+/// The `├─`, `└─` and `│ ` drawing before each process of a `tree`
+pub fn branches(tree: &[(usize, &Process)]) -> Vec<String> {
+  let mut prefixes = vec![];
+  for (i, (depth, _)) in tree.iter().enumerate() {
+    if *depth == 0 {
+      prefixes.push(String::new());
+      continue;
+    }
+    let mut prefix = String::new();
+    // for each ancestor level, whether more siblings follow further down
+    for level in 1..=*depth {
+      let more_below = tree[i + 1..].iter().take_while(|(d, _)| *d >= level).any(|(d, _)| *d == level);
+      prefix.push_str(match (level == *depth, more_below) {
+        (true, true) => "├─",
+        (true, false) => "└─",
+        (false, true) => "│ ",
+        (false, false) => "  ",
+      });
+    }
+    prefixes.push(prefix);
+  }
+  prefixes
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn process(pid: u32, ppid: u32) -> Process {
+    Process { pid, ppid, comm: format!("p{pid}"), cmdline: String::new(), cpu_ticks: 0, rss_bytes: 0 }
+  }
+
+  #[test]
+  fn test_parse_stat() {
+    let text = "812 (nginx: worker (1)) S 810 810 810 0 -1 4194624 2077 0 0 0 120 30 0 0 20 0 1 0 3517 \
+                91234304 1530 18446744073709551615";
+    let stat = parse_stat(text).unwrap();
+    assert_eq!(stat, Stat { comm: "nginx: worker (1)".into(), ppid: 810, cpu_ticks: 150, rss_pages: 1530 });
+    assert_eq!(parse_stat("812 (nginx"), None);
+  }
+
+  #[test]
+  fn test_tree() {
+    let processes = [process(812, 810), process(810, 1), process(900, 810), process(950, 812), process(700, 1)];
+    let tree = tree(&processes);
+    let order: Vec<(usize, u32)> = tree.iter().map(|(d, p)| (*d, p.pid)).collect();
+    assert_eq!(order, [(0, 700), (0, 810), (1, 812), (2, 950), (1, 900)]);
+    assert_eq!(branches(&tree), ["", "", "├─", "│ └─", "└─"]);
+  }
+}
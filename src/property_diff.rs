@@ -0,0 +1,247 @@
+// Before/after comparisons of resource and timing settings, so a new limit can be checked against what's in effect
+// before it's applied. Settings are compared as values (`1G` and `1073741824` are the same limit) and shown with what
+// they work out to, e.g. `CPUQuota=150%` is 1.5 CPUs.
+
+use std::{collections::HashMap, time::Duration};
+
+use crate::humanize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+  /// `CPUQuota=`: a percentage of one CPU, read back as the time allowed per second
+  CpuQuota,
+  /// Sizes like `MemoryMax=`: bytes with K/M/G/T suffixes, a percentage of RAM, or `infinity`
+  Bytes,
+  /// Time spans like `TimeoutStartSec=`, read back in microseconds
+  Timespan,
+  Plain,
+}
+
+/// Settings that can be compared with what's in effect: the setting, the property `systemctl show` reports it as, and
+/// how its values read
+const SETTINGS: &[(&str, &str, Kind)] = &[
+  ("CPUQuota", "CPUQuotaPerSecUSec", Kind::CpuQuota),
+  ("CPUQuotaPeriodSec", "CPUQuotaPeriodUSec", Kind::Timespan),
+  ("CPUWeight", "CPUWeight", Kind::Plain),
+  ("StartupCPUWeight", "StartupCPUWeight", Kind::Plain),
+  ("AllowedCPUs", "AllowedCPUs", Kind::Plain),
+  ("MemoryMin", "MemoryMin", Kind::Bytes),
+  ("MemoryLow", "MemoryLow", Kind::Bytes),
+  ("MemoryHigh", "MemoryHigh", Kind::Bytes),
+  ("MemoryMax", "MemoryMax", Kind::Bytes),
+  ("MemorySwapMax", "MemorySwapMax", Kind::Bytes),
+  ("TasksMax", "TasksMax", Kind::Plain),
+  ("IOWeight", "IOWeight", Kind::Plain),
+  ("Nice", "Nice", Kind::Plain),
+  ("LimitNOFILE", "LimitNOFILE", Kind::Plain),
+  ("LimitNPROC", "LimitNPROC", Kind::Plain),
+  ("LimitCORE", "LimitCORE", Kind::Bytes),
+  ("TimeoutStartSec", "TimeoutStartUSec", Kind::Timespan),
+  ("TimeoutStopSec", "TimeoutStopUSec", Kind::Timespan),
+  ("RuntimeMaxSec", "RuntimeMaxUSec", Kind::Timespan),
+  ("RestartSec", "RestartUSec", Kind::Timespan),
+  ("WatchdogSec", "WatchdogUSec", Kind::Timespan),
+  ("Restart", "Restart", Kind::Plain),
+  ("User", "User", Kind::Plain),
+  ("Group", "Group", Kind::Plain),
+  ("CPUAccounting", "CPUAccounting", Kind::Plain),
+  ("MemoryAccounting", "MemoryAccounting", Kind::Plain),
+  ("IOAccounting", "IOAccounting", Kind::Plain),
+  ("IPAccounting", "IPAccounting", Kind::Plain),
+  ("TasksAccounting", "TasksAccounting", Kind::Plain),
+];
+
+/// The properties to read to compare `settings` with what's in effect
+pub fn properties(settings: &[(String, String)]) -> Vec<&'static str> {
+  let mut properties = vec![];
+  for (_, property, _) in settings.iter().filter_map(|(name, _)| lookup(name)) {
+    if !properties.contains(property) {
+      properties.push(*property);
+    }
+  }
+  properties
+}
+
+fn lookup(setting: &str) -> Option<&'static (&'static str, &'static str, Kind)> {
+  SETTINGS.iter().find(|(name, _, _)| *name == setting)
+}
+
+/// `NAME=value` pairs of a `systemctl set-property` command line, e.g. `CPUQuota=50% MemoryMax=1G`
+pub fn parse_arguments(text: &str) -> Vec<(String, String)> {
+  text.split_whitespace().filter_map(|a| a.split_once('=')).map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+/// The settings in a unit file or drop-in. A setting given more than once keeps its last value
+pub fn parse_unit_file(text: &str) -> Vec<(String, String)> {
+  let mut settings: Vec<(String, String)> = vec![];
+  for line in text.lines().map(str::trim) {
+    if line.is_empty() || line.starts_with(['#', ';', '[']) {
+      continue;
+    }
+    let Some((name, value)) = line.split_once('=') else { continue };
+    let (name, value) = (name.trim().to_string(), value.trim().to_string());
+    match settings.iter_mut().find(|(n, _)| *n == name) {
+      Some(existing) => existing.1 = value,
+      None => settings.push((name, value)),
+    }
+  }
+  settings
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+  pub setting: String,
+  /// What's in effect, as it reads; `None` for settings that aren't compared
+  pub before: Option<String>,
+  pub after: String,
+  pub changed: bool,
+}
+
+/// Compare each setting with the property values in effect. `ram_bytes` is for percentages of memory
+pub fn diff(settings: &[(String, String)], live: &HashMap<String, String>, ram_bytes: Option<u64>) -> Vec<Change> {
+  settings
+    .iter()
+    .map(|(setting, value)| {
+      let Some((_, property, kind)) = lookup(setting) else {
+        return Change { setting: setting.clone(), before: None, after: value.clone(), changed: true };
+      };
+      let current = live.get(*property).map(String::as_str).unwrap_or("");
+      let (before, after, changed) = match kind {
+        Kind::CpuQuota => {
+          let before = parse_timespan(current).map(|usec| if usec == u64::MAX { usec } else { usec / 10_000 });
+          let after = value.strip_suffix('%').and_then(|p| p.parse().ok()).or(empty_as_unlimited(value));
+          (describe_cpu_quota(before), describe_cpu_quota(after), before != after)
+        },
+        Kind::Bytes => {
+          let (before, after) = (parse_bytes(current, ram_bytes), parse_bytes(value, ram_bytes));
+          (describe_bytes(before), describe_bytes(after), before != after)
+        },
+        Kind::Timespan => {
+          // a bare number in a unit file means seconds
+          let after = value.parse::<u64>().ok().map(|s| s * 1_000_000).or_else(|| parse_timespan(value));
+          let before = parse_timespan(current);
+          (describe_timespan(before), describe_timespan(after), before != after)
+        },
+        Kind::Plain => (current.to_string(), value.clone(), current != value),
+      };
+      Change { setting: setting.clone(), before: Some(before), after, changed }
+    })
+    .collect()
+}
+
+/// An empty assignment resets a limit, which for these means no limit
+fn empty_as_unlimited(value: &str) -> Option<u64> {
+  value.is_empty().then_some(u64::MAX)
+}
+
+fn describe_cpu_quota(percent: Option<u64>) -> String {
+  match percent {
+    Some(u64::MAX) => "no limit".into(),
+    Some(percent) => format!("{percent}% ({} CPUs)", percent as f64 / 100.0),
+    None => "?".into(),
+  }
+}
+
+fn describe_bytes(bytes: Option<u64>) -> String {
+  match bytes {
+    Some(u64::MAX) => "no limit".into(),
+    Some(bytes) => humanize::size(bytes),
+    None => "?".into(),
+  }
+}
+
+fn describe_timespan(usec: Option<u64>) -> String {
+  match usec {
+    Some(u64::MAX) => "infinity".into(),
+    Some(usec) => humanize::duration(Duration::from_micros(usec)),
+    None => "?".into(),
+  }
+}
+
+/// `1G`, `512M`, `1073741824`, `50%` (of RAM) or `infinity`. Suffixes are powers of 1024, as systemd reads them
+pub fn parse_bytes(value: &str, ram_bytes: Option<u64>) -> Option<u64> {
+  let value = value.trim();
+  if value.is_empty() || value == "infinity" {
+    return Some(u64::MAX);
+  }
+  if let Some(percent) = value.strip_suffix('%') {
+    return Some((percent.parse::<f64>().ok()? / 100.0 * ram_bytes? as f64) as u64);
+  }
+  let (number, suffix) = value.split_at(value.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(value.len()));
+  let exponent = ["", "K", "M", "G", "T", "P", "E"].iter().position(|s| *s == suffix)?;
+  Some((number.parse::<f64>().ok()? * 1024f64.powi(exponent as i32)) as u64)
+}
+
+/// A systemd time span like `1min 30s`, `500ms` or `infinity`, in microseconds
+pub fn parse_timespan(value: &str) -> Option<u64> {
+  let value = value.trim();
+  if value == "infinity" {
+    return Some(u64::MAX);
+  }
+  let mut total = 0;
+  let mut rest = value;
+  while !rest.is_empty() {
+    let number_end = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+    let number: f64 = rest[..number_end].parse().ok()?;
+    rest = rest[number_end..].trim_start();
+    let unit_end = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+    let multiplier: u64 = match &rest[..unit_end] {
+      "us" | "usec" | "µs" => 1,
+      "ms" | "msec" => 1_000,
+      "" | "s" | "sec" | "second" | "seconds" => 1_000_000,
+      "m" | "min" | "minute" | "minutes" => 60_000_000,
+      "h" | "hr" | "hour" | "hours" => 3_600_000_000,
+      "d" | "day" | "days" => 86_400_000_000,
+      "w" | "week" | "weeks" => 604_800_000_000,
+      _ => return None,
+    };
+    total += (number * multiplier as f64) as u64;
+    rest = rest[unit_end..].trim_start();
+  }
+  (!value.is_empty()).then_some(total)
+}
+
+/// Total RAM, for settings given as a percentage of it
+pub fn ram_bytes() -> Option<u64> {
+  let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+  let kib = meminfo.lines().find_map(|l| l.strip_prefix("MemTotal:"))?.trim().strip_suffix("kB")?.trim();
+  Some(kib.parse::<u64>().ok()? * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse() {
+    assert_eq!(parse_timespan("1min 30s"), Some(90_000_000));
+    assert_eq!(parse_timespan("500ms"), Some(500_000));
+    assert_eq!(parse_timespan("2h"), Some(7_200_000_000));
+    assert_eq!(parse_timespan("soon"), None);
+    assert_eq!(parse_bytes("1G", None), Some(1 << 30));
+    assert_eq!(parse_bytes("1073741824", None), Some(1 << 30));
+    assert_eq!(parse_bytes("50%", Some(8 << 30)), Some(4 << 30));
+    assert_eq!(parse_bytes("50%", None), None);
+    assert_eq!(
+      parse_unit_file("[Service]\n# MemoryMax=1G\nMemoryMax=2G\nExecStart=\nExecStart=/bin/true\nMemoryMax=3G\n"),
+      vec![("MemoryMax".to_string(), "3G".to_string()), ("ExecStart".into(), "/bin/true".into())]
+    );
+  }
+
+  #[test]
+  fn test_diff() {
+    let live = HashMap::from([
+      ("CPUQuotaPerSecUSec".to_string(), "infinity".to_string()),
+      ("MemoryMax".to_string(), "1073741824".to_string()),
+      ("TimeoutStartUSec".to_string(), "1min 30s".to_string()),
+    ]);
+    let settings = parse_arguments("CPUQuota=150% MemoryMax=1G TimeoutStartSec=30 Environment=A=1");
+    assert_eq!(properties(&settings), ["CPUQuotaPerSecUSec", "MemoryMax", "TimeoutStartUSec"]);
+    let changes = diff(&settings, &live, None);
+    assert_eq!(changes[0].before.as_deref(), Some("no limit"));
+    assert_eq!(changes[0].after, "150% (1.5 CPUs)");
+    assert!(!changes[1].changed);
+    assert_eq!((changes[2].before.as_deref(), changes[2].after.as_str()), (Some("1m 30s"), "30s"));
+    assert_eq!((changes[3].before.clone(), changes[3].changed), (None, true));
+  }
+}
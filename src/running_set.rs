@@ -0,0 +1,179 @@
+// Snapshots of which units are running, to put things back the way they were after risky maintenance. Each one is a
+// text file in the data directory, one unit per line after a header:
+//
+//   # taken 2026-10-15T09:30:00+02:00
+//   # host web1
+//   # scopes system user
+//   system  nginx.service
+//
+// Restoring starts what was running then and isn't now, and stops what's running now and wasn't then, within the
+// scopes the snapshot covered.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, Local};
+
+use crate::{
+  systemd::{UnitId, UnitScope, UnitWithStatus},
+  utils::get_data_dir,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunningSet {
+  pub taken: DateTime<FixedOffset>,
+  /// The remote host it was taken on, `None` for this machine
+  pub host: Option<String>,
+  pub scopes: Vec<UnitScope>,
+  pub units: Vec<UnitId>,
+}
+
+/// What restoring a snapshot does
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Plan {
+  pub start: Vec<UnitId>,
+  pub stop: Vec<UnitId>,
+}
+
+impl Plan {
+  pub fn is_empty(&self) -> bool {
+    self.start.is_empty() && self.stop.is_empty()
+  }
+}
+
+/// Whether a unit counts as running: up, or on its way up
+pub fn is_running(unit: &UnitWithStatus) -> bool {
+  matches!(unit.activation_state.as_str(), "active" | "activating" | "reloading")
+}
+
+fn scope_name(scope: UnitScope) -> &'static str {
+  match scope {
+    UnitScope::Global => "system",
+    UnitScope::User => "user",
+  }
+}
+
+fn parse_scope(scope: &str) -> Option<UnitScope> {
+  match scope {
+    "system" => Some(UnitScope::Global),
+    "user" => Some(UnitScope::User),
+    _ => None,
+  }
+}
+
+impl RunningSet {
+  pub fn capture<'a>(
+    units: impl IntoIterator<Item = &'a UnitWithStatus>,
+    scopes: &[UnitScope],
+    host: Option<String>,
+  ) -> Self {
+    let units = units.into_iter().filter(|u| is_running(u) && scopes.contains(&u.scope)).map(|u| u.id()).collect();
+    Self { taken: Local::now().fixed_offset(), host, scopes: scopes.to_vec(), units }
+  }
+
+  pub fn to_text(&self) -> String {
+    let mut text = format!("# taken {}\n", self.taken.to_rfc3339());
+    if let Some(host) = &self.host {
+      text.push_str(&format!("# host {host}\n"));
+    }
+    let scopes = self.scopes.iter().map(|s| scope_name(*s)).collect::<Vec<_>>();
+    text.push_str(&format!("# scopes {}\n", scopes.join(" ")));
+    for unit in &self.units {
+      text.push_str(&format!("{}\t{}\n", scope_name(unit.scope), unit.name));
+    }
+    text
+  }
+
+  pub fn parse(text: &str) -> Option<Self> {
+    let mut taken = None;
+    let (mut host, mut scopes, mut units) = (None, vec![], vec![]);
+    for line in text.lines() {
+      if let Some(header) = line.strip_prefix("# ") {
+        match header.split_once(' ') {
+          Some(("taken", at)) => taken = DateTime::parse_from_rfc3339(at).ok(),
+          Some(("host", name)) => host = Some(name.to_string()),
+          Some(("scopes", names)) => scopes = names.split_whitespace().filter_map(parse_scope).collect(),
+          _ => {},
+        }
+      } else if let Some((scope, name)) = line.split_once('\t') {
+        units.push(UnitId { name: name.to_string(), scope: parse_scope(scope)? });
+      }
+    }
+    Some(Self { taken: taken?, host, scopes, units })
+  }
+
+  /// Get from `current` back to this snapshot
+  pub fn plan(&self, current: &[UnitWithStatus]) -> Plan {
+    let running = current.iter().filter(|u| is_running(u) && self.scopes.contains(&u.scope)).map(|u| u.id());
+    let running = running.collect::<Vec<_>>();
+    Plan {
+      start: self.units.iter().filter(|u| !running.contains(u)).cloned().collect(),
+      stop: running.into_iter().filter(|u| !self.units.contains(u)).collect(),
+    }
+  }
+
+  pub fn save(&self) -> Result<PathBuf> {
+    let directory = directory()?;
+    std::fs::create_dir_all(&directory).with_context(|| format!("Failed to create {}", directory.display()))?;
+    let path = directory.join(self.taken.format("%Y-%m-%dT%H-%M-%S").to_string());
+    std::fs::write(&path, self.to_text()).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+  }
+}
+
+fn directory() -> Result<PathBuf> {
+  Ok(get_data_dir()?.join("running-sets"))
+}
+
+/// Saved snapshots taken on `host`, newest first
+pub fn list(host: Option<&str>) -> Result<Vec<RunningSet>> {
+  let directory = directory()?;
+  if !directory.exists() {
+    return Ok(vec![]);
+  }
+  let mut sets = std::fs::read_dir(&directory)
+    .with_context(|| format!("Failed to read {}", directory.display()))?
+    .filter_map(|entry| read(&entry.ok()?.path()))
+    .filter(|set| set.host.as_deref() == host)
+    .collect::<Vec<_>>();
+  sets.sort_by_key(|set| std::cmp::Reverse(set.taken));
+  Ok(sets)
+}
+
+fn read(path: &Path) -> Option<RunningSet> {
+  RunningSet::parse(&std::fs::read_to_string(path).ok()?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn unit(name: &str, state: &str) -> UnitWithStatus {
+    UnitWithStatus {
+      name: name.into(),
+      scope: UnitScope::Global,
+      description: String::new(),
+      file_path: None,
+      load_state: "loaded".into(),
+      activation_state: state.into(),
+      sub_state: String::new(),
+      enablement_state: None,
+    }
+  }
+
+  #[test]
+  fn test_plan() {
+    let then = [unit("nginx.service", "active"), unit("redis.service", "active"), unit("cron.service", "inactive")];
+    let set = RunningSet::capture(&then, &[UnitScope::Global], Some("web1".into()));
+    let set = RunningSet::parse(&set.to_text()).unwrap();
+    assert_eq!(set.host.as_deref(), Some("web1"));
+    assert_eq!(set.units.len(), 2);
+
+    let now = [unit("nginx.service", "failed"), unit("redis.service", "active"), unit("cron.service", "activating")];
+    let plan = set.plan(&now);
+    let names = |units: &[UnitId]| units.iter().map(|u| u.name.clone()).collect::<Vec<_>>();
+    assert_eq!(names(&plan.start), ["nginx.service"]);
+    assert_eq!(names(&plan.stop), ["cron.service"]);
+    assert!(set.plan(&then).is_empty());
+  }
+}
@@ -0,0 +1,142 @@
+// What the TUI was showing when it was closed: the search text, the selected unit, which units were listed and how
+// they were sorted, and how far the list and logs were scrolled. With `restore-session` in the config it's saved on
+// exit and picked up on the next launch, so keeping an eye on a deployment doesn't start from scratch each time. Kept
+// in the data directory as one `key value` line each; anything missing or unreadable just isn't restored.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use crate::{
+  config::SortOrder,
+  systemd::{Scope, UnitId, UnitScope},
+  unit_types,
+  utils::get_data_dir,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SavedView {
+  /// What was typed in the search box
+  pub search: String,
+  pub selected: Option<UnitId>,
+  pub scope: Option<Scope>,
+  pub sort: Option<SortOrder>,
+  pub unit_type: Option<&'static str>,
+  pub failed_only: bool,
+  /// Units scrolled past at the top of the list
+  pub list_offset: usize,
+  /// Lines scrolled up from the newest in the log pane
+  pub logs_scroll: u16,
+  /// Whether the log pane had the focus
+  pub logs_focused: bool,
+}
+
+fn path() -> Result<PathBuf> {
+  Ok(get_data_dir()?.join("last-view"))
+}
+
+/// The view the last session was closed on, if it was saved
+pub fn load() -> Option<SavedView> {
+  let text = std::fs::read_to_string(path().ok()?).ok()?;
+  Some(parse(&text))
+}
+
+pub fn save(view: &SavedView) -> Result<()> {
+  let path = path()?;
+  if let Some(directory) = path.parent() {
+    std::fs::create_dir_all(directory)?;
+  }
+  std::fs::write(path, to_text(view))?;
+  Ok(())
+}
+
+fn scope_name(scope: UnitScope) -> &'static str {
+  match scope {
+    UnitScope::Global => "system",
+    UnitScope::User => "user",
+  }
+}
+
+fn parse(text: &str) -> SavedView {
+  let mut view = SavedView::default();
+  for line in text.lines() {
+    let Some((key, value)) = line.split_once(' ') else { continue };
+    match key {
+      "search" => view.search = value.to_string(),
+      "selected" => {
+        view.selected = value.split_once(' ').and_then(|(scope, name)| {
+          let scope = [UnitScope::Global, UnitScope::User].into_iter().find(|s| scope_name(*s) == scope)?;
+          Some(UnitId { name: name.to_string(), scope })
+        })
+      },
+      "scope" => {
+        view.scope = match value {
+          "system" => Some(Scope::Global),
+          "user" => Some(Scope::User),
+          "all" => Some(Scope::All),
+          _ => None,
+        }
+      },
+      "sort" => view.sort = SortOrder::from_label(value),
+      "type" => view.unit_type = unit_types::known(value),
+      "failed-only" => view.failed_only = value == "true",
+      "list-offset" => view.list_offset = value.parse().unwrap_or_default(),
+      "logs-scroll" => view.logs_scroll = value.parse().unwrap_or_default(),
+      "logs-focused" => view.logs_focused = value == "true",
+      _ => {},
+    }
+  }
+  view
+}
+
+fn to_text(view: &SavedView) -> String {
+  let mut text = format!("search {}\n", view.search);
+  if let Some(unit) = &view.selected {
+    text.push_str(&format!("selected {} {}\n", scope_name(unit.scope), unit.name));
+  }
+  if let Some(scope) = view.scope {
+    let scope = match scope {
+      Scope::Global => "system",
+      Scope::User => "user",
+      Scope::All => "all",
+    };
+    text.push_str(&format!("scope {scope}\n"));
+  }
+  if let Some(sort) = view.sort {
+    text.push_str(&format!("sort {}\n", sort.label()));
+  }
+  if let Some(unit_type) = view.unit_type {
+    text.push_str(&format!("type {unit_type}\n"));
+  }
+  text.push_str(&format!("failed-only {}\n", view.failed_only));
+  text.push_str(&format!("list-offset {}\n", view.list_offset));
+  text.push_str(&format!("logs-scroll {}\n", view.logs_scroll));
+  text.push_str(&format!("logs-focused {}\n", view.logs_focused));
+  text
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_round_trip() {
+    let view = SavedView {
+      search: "nginx worker".into(),
+      selected: Some(UnitId { name: "nginx.service".into(), scope: UnitScope::Global }),
+      scope: Some(Scope::Global),
+      sort: Some(SortOrder::LoadState),
+      unit_type: Some("timer"),
+      failed_only: true,
+      list_offset: 12,
+      logs_scroll: 40,
+      logs_focused: true,
+    };
+    assert_eq!(parse(&to_text(&view)), view);
+    assert_eq!(parse(&to_text(&SavedView::default())), SavedView::default());
+
+    // what can't be read is left out
+    let view = parse("selected nowhere x.service\nsort sideways\ntype bogus\nlist-offset -3\nunknown key\n");
+    assert_eq!(view, SavedView::default());
+  }
+}
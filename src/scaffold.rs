@@ -0,0 +1,414 @@
+// Generating new unit files from a handful of answers, for the creation wizards.
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+
+use crate::systemd::{self, UnitScope};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HardeningPreset {
+  None,
+  Basic,
+  Strict,
+}
+
+impl HardeningPreset {
+  pub const NAMES: &'static [&'static str] = &["none", "basic", "strict"];
+
+  pub fn parse(s: &str) -> Self {
+    match s {
+      "basic" => HardeningPreset::Basic,
+      "strict" => HardeningPreset::Strict,
+      _ => HardeningPreset::None,
+    }
+  }
+
+  fn directives(self) -> &'static [&'static str] {
+    const BASIC: &[&str] = &["NoNewPrivileges=yes", "PrivateTmp=yes", "ProtectSystem=full", "ProtectHome=read-only"];
+    const STRICT: &[&str] = &[
+      "NoNewPrivileges=yes",
+      "PrivateTmp=yes",
+      "PrivateDevices=yes",
+      "ProtectSystem=strict",
+      "ProtectHome=yes",
+      "ProtectKernelTunables=yes",
+      "ProtectKernelModules=yes",
+      "ProtectKernelLogs=yes",
+      "ProtectControlGroups=yes",
+      "RestrictSUIDSGID=yes",
+      "RestrictNamespaces=yes",
+      "LockPersonality=yes",
+      "SystemCallFilter=@system-service",
+    ];
+    match self {
+      HardeningPreset::None => &[],
+      HardeningPreset::Basic => BASIC,
+      HardeningPreset::Strict => STRICT,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct NewService {
+  /// Without the `.service` suffix
+  pub name: String,
+  pub description: String,
+  pub exec_start: String,
+  pub user: String,
+  pub working_directory: String,
+  pub restart: String,
+  pub hardening: HardeningPreset,
+  pub scope: UnitScope,
+  /// Units to start along with it and wait for, e.g. `postgresql.service`
+  pub after: Vec<String>,
+}
+
+impl NewService {
+  pub fn unit_name(&self) -> String {
+    format!("{}.service", self.name)
+  }
+
+  pub fn validate(&self) -> Result<()> {
+    check_unit_name(&self.unit_name())?;
+    if self.exec_start.trim().is_empty() {
+      bail!("ExecStart is required");
+    }
+    if !self.exec_start.trim_start().starts_with('/') {
+      bail!("ExecStart must start with an absolute path to the executable");
+    }
+    Ok(())
+  }
+
+  pub fn render(&self) -> String {
+    let description = literal(if self.description.is_empty() { &self.name } else { &self.description });
+    let mut unit = format!("[Unit]\nDescription={description}\n");
+    if !self.after.is_empty() {
+      let after = self.after.join(" ");
+      unit.push_str(&format!("Wants={after}\nAfter={after}\n"));
+    }
+    unit.push_str(&format!("\n[Service]\nExecStart={}\n", self.exec_start.trim()));
+    // the user manager runs everything as its own user
+    if !self.user.is_empty() && self.scope == UnitScope::Global {
+      unit.push_str(&format!("User={}\n", self.user));
+    }
+    if !self.working_directory.is_empty() {
+      unit.push_str(&format!("WorkingDirectory={}\n", self.working_directory));
+    }
+    unit.push_str(&format!("Restart={}\n", self.restart));
+    for directive in self.hardening.directives() {
+      unit.push_str(directive);
+      unit.push('\n');
+    }
+
+    let wanted_by = match self.scope {
+      UnitScope::Global => "multi-user.target",
+      UnitScope::User => "default.target",
+    };
+    unit.push_str(&format!("\n[Install]\nWantedBy={wanted_by}\n"));
+    unit
+  }
+}
+
+/// A oneshot service plus the timer that triggers it
+#[derive(Debug, Clone)]
+pub struct NewTimer {
+  /// Shared by the service and the timer, without suffix
+  pub name: String,
+  pub description: String,
+  pub command: String,
+  /// Run as this user (system units only); empty for the default
+  pub user: String,
+  /// `NAME=value` pairs for `Environment=`
+  pub environment: Vec<String>,
+  /// One or more expressions; the timer elapses when any of them does
+  pub on_calendar: Vec<String>,
+  /// Run a missed trigger on the next boot
+  pub persistent: bool,
+  pub scope: UnitScope,
+}
+
+impl NewTimer {
+  pub fn service_name(&self) -> String {
+    format!("{}.service", self.name)
+  }
+
+  pub fn timer_name(&self) -> String {
+    format!("{}.timer", self.name)
+  }
+
+  pub fn validate(&self) -> Result<()> {
+    check_unit_name(&self.service_name())?;
+    if !self.command.trim_start().starts_with('/') {
+      bail!("The command must start with an absolute path to the executable");
+    }
+    if self.on_calendar.iter().all(|c| c.trim().is_empty()) {
+      bail!("OnCalendar is required");
+    }
+    Ok(())
+  }
+
+  pub fn render_service(&self) -> String {
+    let description = literal(if self.description.is_empty() { &self.name } else { &self.description });
+    let mut unit =
+      format!("[Unit]\nDescription={description}\n\n[Service]\nType=oneshot\nExecStart={}\n", self.command.trim());
+    // the user manager runs everything as its own user
+    if !self.user.is_empty() && self.scope == UnitScope::Global {
+      unit.push_str(&format!("User={}\n", self.user));
+    }
+    for variable in &self.environment {
+      unit.push_str(&format!("Environment={}\n", quote_assignment(variable)));
+    }
+    unit
+  }
+
+  pub fn render_timer(&self) -> String {
+    let description = literal(if self.description.is_empty() { &self.name } else { &self.description });
+    let mut timer = format!("[Unit]\nDescription=Timer for {description}\n\n[Timer]\n");
+    for on_calendar in self.on_calendar.iter().filter(|c| !c.trim().is_empty()) {
+      timer.push_str(&format!("OnCalendar={}\n", on_calendar.trim()));
+    }
+    if self.persistent {
+      timer.push_str("Persistent=true\n");
+    }
+    timer.push_str("\n[Install]\nWantedBy=timers.target\n");
+    timer
+  }
+}
+
+/// The file name for a copy of `source` called `new_name`. The suffix defaults to the source's, and because unit files
+/// for instances are really templates, an instance name like `foo@bar.service` becomes the template `foo@.service`
+pub fn duplicate_file_name(source: &str, new_name: &str) -> Result<String> {
+  let new_name = new_name.trim();
+  if new_name.is_empty() || new_name.contains('/') || new_name.contains(char::is_whitespace) {
+    bail!("Invalid unit name `{new_name}`");
+  }
+  let source_suffix = source.rsplit_once('.').map(|(_, s)| s).unwrap_or("service");
+  let (stem, suffix) = match new_name.rsplit_once('.') {
+    Some((stem, suffix)) if suffix == source_suffix => (stem, suffix),
+    _ => (new_name, source_suffix),
+  };
+
+  let stem = match stem.split_once('@') {
+    Some((prefix, _instance)) => format!("{prefix}@"),
+    // a copy of a template has to stay a template for %i to mean anything
+    None if source.contains('@') => format!("{stem}@"),
+    None => stem.to_string(),
+  };
+  if stem == "@" || stem.is_empty() {
+    bail!("Invalid unit name `{new_name}`");
+  }
+  let file_name = format!("{stem}.{suffix}");
+  check_unit_name(&file_name)?;
+  if file_name == source || source.split_once('@').is_some_and(|(p, _)| file_name == format!("{p}@.{source_suffix}")) {
+    bail!("The copy needs a different name");
+  }
+  Ok(file_name)
+}
+
+/// Copy a unit file to a new name in the local unit directory, refusing to clobber an existing one. Not verified,
+/// since the copy is about to be edited anyway
+pub fn copy_unit_file(scope: UnitScope, source_path: &str, file_name: &str) -> Result<PathBuf> {
+  let contents = std::fs::read_to_string(source_path).with_context(|| format!("Failed to read {source_path}"))?;
+  let directory = unit_directory(scope)?;
+  std::fs::create_dir_all(&directory).with_context(|| format!("Failed to create {}", directory.display()))?;
+  let path = directory.join(file_name);
+  if path.exists() {
+    bail!("{} already exists", path.display());
+  }
+  std::fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))?;
+  Ok(path)
+}
+
+/// Refuse a name systemd wouldn't load a unit by: only ASCII letters, digits and `:_.-\@`, at most 255 bytes, and an
+/// `@` only between a non-empty prefix and the suffix, once
+fn check_unit_name(unit_name: &str) -> Result<()> {
+  let allowed = |c: char| c.is_ascii_alphanumeric() || ":_.-\\@".contains(c);
+  if let Some(c) = unit_name.chars().find(|c| !allowed(*c)) {
+    bail!("Invalid unit name `{unit_name}`: `{c}` isn't allowed, only letters, digits and :_.-\\@ are");
+  }
+  if unit_name.len() > 255 {
+    bail!("Invalid unit name `{unit_name}`: longer than 255 characters");
+  }
+  let stem = unit_name.rsplit_once('.').map(|(stem, _)| stem).unwrap_or(unit_name);
+  let prefix = match stem.split_once('@') {
+    Some((_, instance)) if instance.contains('@') => bail!("Invalid unit name `{unit_name}`: more than one @"),
+    Some((prefix, _)) => prefix,
+    None if unit_name[stem.len()..].contains('@') => bail!("Invalid unit name `{unit_name}`: @ in the suffix"),
+    None => stem,
+  };
+  if prefix.is_empty() {
+    bail!("Invalid unit name `{unit_name}`: nothing before the {}", if stem.contains('@') { "@" } else { "suffix" });
+  }
+  Ok(())
+}
+
+/// `text` as it's meant in a unit file setting, rather than with systemd expanding `%` specifiers in it
+fn literal(text: &str) -> String {
+  text.replace('%', "%%")
+}
+
+/// A `NAME=value` pair quoted for `Environment=`, which would otherwise split it at spaces and take quotes and
+/// backslashes in it as its own
+fn quote_assignment(variable: &str) -> String {
+  format!("\"{}\"", variable.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Install the service and then the timer, removing the service again if the timer can't be installed
+pub fn install_timer(timer: &NewTimer) -> Result<[PathBuf; 2]> {
+  let service_path = install_unit_file(timer.scope, &timer.service_name(), &timer.render_service())?;
+  match install_unit_file(timer.scope, &timer.timer_name(), &timer.render_timer()) {
+    Ok(timer_path) => Ok([service_path, timer_path]),
+    Err(e) => {
+      let _ = std::fs::remove_file(service_path);
+      Err(e)
+    },
+  }
+}
+
+/// Install each timer in turn, removing the ones already installed if one can't be, so a failed import can simply
+/// be tried again
+pub fn install_timers(timers: &[NewTimer]) -> Result<()> {
+  let mut installed = vec![];
+  for timer in timers {
+    match install_timer(timer) {
+      Ok(paths) => installed.extend(paths),
+      Err(e) => {
+        for path in installed {
+          let _ = std::fs::remove_file(path);
+        }
+        return Err(e).with_context(|| format!("Failed to install {}, so none were", timer.timer_name()));
+      },
+    }
+  }
+  Ok(())
+}
+
+/// Where locally-created units go: the admin directory for system units, the user's config dir for user units
+pub fn unit_directory(scope: UnitScope) -> Result<PathBuf> {
+  match scope {
+    UnitScope::Global => Ok(PathBuf::from("/etc/systemd/system")),
+    UnitScope::User => {
+      let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))
+        .context("Could not determine the user config directory")?;
+      Ok(config.join("systemd/user"))
+    },
+  }
+}
+
+/// Write a new unit file, refusing to clobber an existing one, and check it with `systemd-analyze verify`.
+/// A unit that fails verification is removed again so it can't be loaded by a later daemon-reload
+pub fn install_unit_file(scope: UnitScope, unit_name: &str, contents: &str) -> Result<PathBuf> {
+  let directory = unit_directory(scope)?;
+  std::fs::create_dir_all(&directory).with_context(|| format!("Failed to create {}", directory.display()))?;
+
+  let path = directory.join(unit_name);
+  if path.exists() {
+    bail!("{} already exists", path.display());
+  }
+  std::fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))?;
+
+  if let Err(e) = systemd::verify_unit_file(&path.to_string_lossy(), scope) {
+    let _ = std::fs::remove_file(&path);
+    return Err(e);
+  }
+  Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn service() -> NewService {
+    NewService {
+      name: "myapp".into(),
+      description: "".into(),
+      exec_start: "/usr/bin/myapp --serve".into(),
+      user: "www-data".into(),
+      working_directory: "".into(),
+      restart: "on-failure".into(),
+      hardening: HardeningPreset::Basic,
+      scope: UnitScope::Global,
+      after: vec![],
+    }
+  }
+
+  #[test]
+  fn test_render_service() {
+    let unit = service().render();
+    assert!(unit.starts_with("[Unit]\nDescription=myapp\n"));
+    assert!(unit.contains("ExecStart=/usr/bin/myapp --serve\nUser=www-data\nRestart=on-failure\n"));
+    assert!(!unit.contains("WorkingDirectory"));
+    assert!(unit.contains("PrivateTmp=yes"));
+    assert!(unit.ends_with("[Install]\nWantedBy=multi-user.target\n"));
+
+    assert!(!NewService { scope: UnitScope::User, ..service() }.render().contains("User="));
+
+    let unit = NewService { description: "Runs at 50% load".into(), ..service() }.render();
+    assert!(unit.starts_with("[Unit]\nDescription=Runs at 50%% load\n"));
+
+    let unit = NewService { after: vec!["postgresql.service".into()], ..service() }.render();
+    assert!(unit.starts_with("[Unit]\nDescription=myapp\nWants=postgresql.service\nAfter=postgresql.service\n\n"));
+  }
+
+  #[test]
+  fn test_check_unit_name() {
+    for valid in ["myapp.service", "getty@.service", "getty@tty1.service", "a:b_c-d.e\\x2d.service"] {
+      assert!(check_unit_name(valid).is_ok(), "{valid}");
+    }
+    let too_long = format!("{}.service", "a".repeat(248));
+    for invalid in ["my app.service", "café.service", ".service", "@tty1.service", "a@b@c.service", "a.ser@vice"] {
+      assert!(check_unit_name(invalid).is_err(), "{invalid}");
+    }
+    assert!(check_unit_name(&too_long).is_err());
+    assert!(check_unit_name(&too_long[1..]).is_ok());
+  }
+
+  #[test]
+  fn test_render_timer() {
+    let timer = NewTimer {
+      name: "backup".into(),
+      description: "Nightly backup, 100%".into(),
+      command: "/usr/local/bin/backup".into(),
+      user: "".into(),
+      environment: vec!["PATH=/usr/bin".into(), r#"GREETING=say "hi" \o/"#.into()],
+      on_calendar: vec!["daily".into()],
+      persistent: true,
+      scope: UnitScope::User,
+    };
+    assert!(timer.render_service().ends_with(
+      r#"Type=oneshot
+ExecStart=/usr/local/bin/backup
+Environment="PATH=/usr/bin"
+Environment="GREETING=say \"hi\" \\o/"
+"#
+    ));
+    let as_backup = NewTimer { user: "backup".into(), ..timer.clone() };
+    assert!(!as_backup.render_service().contains("User="));
+    assert!(NewTimer { scope: UnitScope::Global, ..as_backup }.render_service().contains("User=backup\n"));
+    assert_eq!(
+      timer.render_timer(),
+      "[Unit]\nDescription=Timer for Nightly backup, 100%%\n\n[Timer]\nOnCalendar=daily\nPersistent=true\n\n[Install]\nWantedBy=timers.target\n"
+    );
+  }
+
+  #[test]
+  fn test_duplicate_file_name() {
+    assert_eq!(duplicate_file_name("nginx.service", "nginx-staging").unwrap(), "nginx-staging.service");
+    assert_eq!(duplicate_file_name("nginx.service", "web.service").unwrap(), "web.service");
+    assert_eq!(duplicate_file_name("getty@tty1.service", "mygetty").unwrap(), "mygetty@.service");
+    assert_eq!(duplicate_file_name("getty@tty1.service", "mygetty@tty2.service").unwrap(), "mygetty@.service");
+    assert_eq!(duplicate_file_name("backup.timer", "backup-weekly").unwrap(), "backup-weekly.timer");
+    assert!(duplicate_file_name("nginx.service", "nginx").is_err());
+    assert!(duplicate_file_name("getty@tty1.service", "getty@tty2").is_err());
+  }
+
+  #[test]
+  fn test_validate_service() {
+    assert!(service().validate().is_ok());
+    assert!(NewService { exec_start: "myapp".into(), ..service() }.validate().is_err());
+    assert!(NewService { name: "my app".into(), ..service() }.validate().is_err());
+  }
+}
@@ -0,0 +1,435 @@
+// Search over unit names and descriptions. Haystacks are lowercased once when units are added rather than on every
+// keystroke, and typing more of a query only re-checks the units that matched the shorter one, so search stays quick
+// with tens of thousands of template instances loaded. Past `BACKGROUND_UNITS` units a search runs as a `SearchJob`
+// off the UI thread, which gives up as soon as another query or a changed unit makes it stale.
+//
+// Queries made of `Property=value` terms search unit properties instead, see `parse_property_query`.
+
+use std::{
+  collections::HashMap,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+  },
+};
+
+use indexmap::IndexMap;
+
+use crate::{preset, systemd::UnitId};
+
+/// With this many units or more, searches are worth running in the background
+pub const BACKGROUND_UNITS: usize = 2_000;
+
+/// A background search checks whether it's still wanted after this many units
+const CHECK_EVERY: usize = 1_024;
+
+#[derive(Clone)]
+struct Haystack {
+  name: String,
+  description: String,
+}
+
+#[derive(Default)]
+pub struct SearchIndex {
+  /// Shared with background searches; copied on write if one is still reading it
+  entries: Arc<IndexMap<UnitId, Haystack>>,
+  /// The last query and its matches, in entry order
+  last: Option<Matches>,
+  /// Bumped for every search started and every change to the entries, so a background search can tell it's stale
+  generation: Arc<AtomicU64>,
+  /// The query being searched in the background
+  pending: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Matches {
+  query: String,
+  /// In entry order
+  sorted: Vec<usize>,
+  /// Best first
+  ranked: Vec<usize>,
+}
+
+/// A search to run away from the index, e.g. on a blocking task
+pub struct SearchJob {
+  entries: Arc<IndexMap<UnitId, Haystack>>,
+  query: String,
+  candidates: Vec<usize>,
+  generation: u64,
+  latest: Arc<AtomicU64>,
+}
+
+/// The outcome of a `SearchJob`, to hand back to the index it came from
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+  generation: u64,
+  matches: Matches,
+}
+
+impl SearchIndex {
+  pub fn clear(&mut self) {
+    self.entries = Arc::default();
+    self.changed();
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /// Add a unit, or update it if its description changed. An unchanged one is compared as it is, without lowercasing
+  /// it again, since a refresh goes through every unit
+  pub fn insert(&mut self, id: UnitId, short_name: &str, description: &str) {
+    if let Some(existing) = self.entries.get(&id) {
+      if existing.description.chars().eq(description.chars().flat_map(char::to_lowercase)) {
+        return;
+      }
+    }
+    let haystack = Haystack { name: short_name.to_lowercase(), description: description.to_lowercase() };
+    Arc::make_mut(&mut self.entries).insert(id, haystack);
+    self.changed();
+  }
+
+  /// Drop the units `keep` says no to, e.g. the ones a refresh no longer lists
+  pub fn retain(&mut self, keep: impl Fn(&UnitId) -> bool) {
+    if self.entries.keys().all(&keep) {
+      return;
+    }
+    Arc::make_mut(&mut self.entries).retain(|id, _| keep(id));
+    self.changed();
+  }
+
+  fn changed(&mut self) {
+    self.last = None;
+    self.pending = None;
+    self.generation.fetch_add(1, Ordering::Relaxed);
+  }
+
+  /// Units matching `query`: names containing it first, then descriptions containing it, then names that contain its
+  /// characters in order (so `ngx` finds nginx). Within each group, units keep the order they were added in
+  pub fn search(&mut self, query: &str) -> Vec<&UnitId> {
+    if self.cached(query).is_none() {
+      // this one supersedes any running in the background
+      self.pending = None;
+      if let Some(result) = self.job(query).and_then(SearchJob::run) {
+        self.finish(result);
+      }
+    }
+    self.cached(query).unwrap_or_default()
+  }
+
+  /// The results of the last search, if it was for `query`
+  pub fn cached(&self, query: &str) -> Option<Vec<&UnitId>> {
+    let last = self.last.as_ref().filter(|last| last.query == query.to_lowercase())?;
+    Some(last.ranked.iter().map(|i| self.entries.get_index(*i).unwrap().0).collect())
+  }
+
+  /// A search for `query`, or `None` if one is already running. Any other search running goes stale
+  pub fn job(&mut self, query: &str) -> Option<SearchJob> {
+    let query = query.to_lowercase();
+    if self.pending.as_ref() == Some(&query) {
+      return None;
+    }
+    let candidates: Vec<usize> = match &self.last {
+      // anything matching the longer query also matched the shorter one
+      Some(last) if query.starts_with(last.query.as_str()) => last.sorted.clone(),
+      _ => (0..self.entries.len()).collect(),
+    };
+    self.pending = Some(query.clone());
+    Some(SearchJob {
+      entries: self.entries.clone(),
+      query,
+      candidates,
+      generation: self.generation.fetch_add(1, Ordering::Relaxed) + 1,
+      latest: self.generation.clone(),
+    })
+  }
+
+  /// Keep a search's results for `cached`, unless they're stale. Whether they were kept
+  pub fn finish(&mut self, result: SearchResult) -> bool {
+    if result.generation != self.generation.load(Ordering::Relaxed) {
+      return false;
+    }
+    self.pending = None;
+    self.last = Some(result.matches);
+    true
+  }
+}
+
+impl SearchJob {
+  /// `None` if it went stale before finishing
+  pub fn run(self) -> Option<SearchResult> {
+    let mut tiers: [Vec<usize>; 3] = Default::default();
+    for (n, i) in self.candidates.into_iter().enumerate() {
+      if n % CHECK_EVERY == 0 && self.latest.load(Ordering::Relaxed) != self.generation {
+        return None;
+      }
+      let (_, haystack) = self.entries.get_index(i).unwrap();
+      if haystack.name.contains(&self.query) {
+        tiers[0].push(i);
+      } else if haystack.description.contains(&self.query) {
+        tiers[1].push(i);
+      } else if is_subsequence(&self.query, &haystack.name) {
+        tiers[2].push(i);
+      }
+    }
+
+    let ranked = tiers.concat();
+    let mut sorted = ranked.clone();
+    sorted.sort_unstable();
+    Some(SearchResult { generation: self.generation, matches: Matches { query: self.query, sorted, ranked } })
+  }
+}
+
+/// One `Name=value` or `Name!=value` term of a property query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertyFilter {
+  pub property: String,
+  pub value: String,
+  pub negate: bool,
+}
+
+impl PropertyFilter {
+  pub fn matches(&self, value: &str) -> bool {
+    (value == self.value) != self.negate
+  }
+}
+
+/// A query like `Restart=always User=www-data` or `MemoryMax!=infinity`, where every term has to match. Property
+/// names are CamelCase, as `systemctl show` prints them, which keeps ordinary searches like `a=b` as text searches
+pub fn parse_property_query(query: &str) -> Option<Vec<PropertyFilter>> {
+  let filters = query
+    .split_whitespace()
+    .map(|term| {
+      let (property, value, negate) = match term.split_once('=') {
+        Some((property, value)) if property.ends_with('!') => (&property[..property.len() - 1], value, true),
+        Some((property, value)) => (property, value, false),
+        None => return None,
+      };
+      let is_property =
+        property.starts_with(|c: char| c.is_ascii_uppercase()) && property.chars().all(|c| c.is_ascii_alphanumeric());
+      is_property.then(|| PropertyFilter { property: property.to_string(), value: value.to_string(), negate })
+    })
+    .collect::<Option<Vec<_>>>()?;
+  (!filters.is_empty()).then_some(filters)
+}
+
+/// The properties to fetch to answer a search on `property`. `Origin` and `Drift` aren't real properties: `Origin=admin`
+/// matches units by where their unit file lives, `Drift=yes` units enabled differently from their vendor preset
+pub fn source_properties(property: &str) -> Vec<&str> {
+  match property {
+    "Origin" => vec!["FragmentPath"],
+    "Drift" => vec!["UnitFileState", "UnitFilePreset"],
+    _ => vec![property],
+  }
+}
+
+/// The value a search on `property` compares against, from a unit's `source_properties`
+pub fn property_value(property: &str, unit: &HashMap<String, String>) -> String {
+  let get = |name: &str| unit.get(name).map(String::as_str).unwrap_or_default();
+  match property {
+    "Origin" => unit_origin(get("FragmentPath")).to_string(),
+    "Drift" => if preset_drift(get("UnitFileState"), get("UnitFilePreset")) { "yes" } else { "no" }.to_string(),
+    _ => get(property).to_string(),
+  }
+}
+
+/// Where a unit's file comes from, named by who put it there
+pub fn unit_origin(fragment_path: &str) -> &'static str {
+  let generated = ["/systemd/generator/", "/systemd/generator.early/", "/systemd/generator.late/"];
+  if fragment_path.is_empty() {
+    "none"
+  } else if fragment_path.starts_with("/run/") && generated.iter().any(|g| fragment_path.contains(g)) {
+    "generated"
+  } else if fragment_path.starts_with("/run/") {
+    "runtime"
+  } else if fragment_path.starts_with("/etc/")
+    || fragment_path.starts_with("/usr/local/")
+    || fragment_path.contains("/.config/systemd/")
+  {
+    "admin"
+  } else {
+    "vendor"
+  }
+}
+
+/// Whether a unit is enabled or disabled against its vendor preset. Units the preset doesn't decide (static, aliased,
+/// generated, ...) don't count; masking a unit does
+pub fn preset_drift(unit_file_state: &str, preset: &str) -> bool {
+  let enabled = match unit_file_state {
+    "enabled" | "enabled-runtime" => true,
+    "disabled" => false,
+    "masked" | "masked-runtime" => return matches!(preset, "enabled" | "disabled"),
+    _ => return false,
+  };
+  match preset {
+    "enabled" => !enabled,
+    "disabled" => enabled,
+    _ => false,
+  }
+}
+
+/// Unit names to offer for a partly typed one: those starting with it, then those containing it, then those containing
+/// its characters in order, shortest first within each. Nothing once it's a whole unit name
+pub fn complete<'a>(word: &str, names: &'a [String], limit: usize) -> Vec<&'a str> {
+  let word = word.to_lowercase();
+  if word.is_empty() || names.iter().any(|name| name.to_lowercase() == word) {
+    return vec![];
+  }
+  let mut tiers: [Vec<&str>; 3] = Default::default();
+  for name in names {
+    let lower = name.to_lowercase();
+    if lower.starts_with(&word) {
+      tiers[0].push(name);
+    } else if lower.contains(&word) {
+      tiers[1].push(name);
+    } else if is_subsequence(&word, &lower) {
+      tiers[2].push(name);
+    }
+  }
+  for tier in &mut tiers {
+    tier.sort_by_key(|name| (name.len(), *name));
+  }
+  tiers.concat().into_iter().take(limit).collect()
+}
+
+/// Whether `needle`'s characters all appear in `haystack`, in order
+pub fn is_subsequence(needle: &str, haystack: &str) -> bool {
+  let mut haystack = haystack.chars();
+  needle.chars().all(|c| haystack.any(|h| h == c))
+}
+
+/// Whether a unit name matches `--filter`: an fnmatch-style pattern like `docker*`, or without wildcards any name
+/// containing it
+pub fn name_matches(pattern: &str, name: &str) -> bool {
+  if pattern.contains(['*', '?', '[']) {
+    preset::glob_match(pattern, name)
+  } else {
+    name.contains(pattern)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::systemd::UnitScope;
+
+  fn id(name: &str) -> UnitId {
+    UnitId { name: format!("{name}.service"), scope: UnitScope::Global }
+  }
+
+  fn index() -> SearchIndex {
+    let mut index = SearchIndex::default();
+    index.insert(id("cron"), "cron", "Regular background program processing daemon");
+    index.insert(id("nginx"), "nginx", "A high performance web server");
+    index.insert(id("webhook"), "webhook", "Small server for running commands");
+    index
+  }
+
+  fn names(results: Vec<&UnitId>) -> Vec<&str> {
+    results.into_iter().map(|u| u.name.as_str()).collect()
+  }
+
+  #[test]
+  fn test_search_tiers() {
+    let mut index = index();
+    assert_eq!(names(index.search("web")), vec!["webhook.service", "nginx.service"]);
+    assert_eq!(names(index.search("ngx")), vec!["nginx.service"]);
+    assert_eq!(index.search("").len(), 3);
+  }
+
+  #[test]
+  fn test_search_narrows_and_widens() {
+    let mut index = index();
+    assert_eq!(names(index.search("ser")), vec!["nginx.service", "webhook.service"]);
+    assert_eq!(names(index.search("serv")), vec!["nginx.service", "webhook.service"]);
+    assert_eq!(names(index.search("c")), vec!["cron.service", "nginx.service", "webhook.service"]);
+    index.insert(id("cups"), "cups", "CUPS Scheduler");
+    assert_eq!(names(index.search("cu")), vec!["cups.service"]);
+    index.retain(|unit| *unit != id("cups"));
+    assert!(index.search("cu").is_empty());
+    assert_eq!(index.len(), 3);
+  }
+
+  #[test]
+  fn test_background_search() {
+    let mut index = index();
+    let job = index.job("server").unwrap();
+    // already running
+    assert!(index.job("server").is_none());
+    let result = job.run().unwrap();
+    assert!(index.finish(result));
+    assert_eq!(names(index.cached("server").unwrap()), vec!["nginx.service", "webhook.service"]);
+
+    // another query, or a changed unit, makes a running search stale
+    let stale = index.job("web").unwrap();
+    let current = index.job("webh").unwrap();
+    assert!(stale.run().is_none());
+    let result = current.run().unwrap();
+    index.insert(id("cups"), "cups", "CUPS Scheduler");
+    assert!(!index.finish(result));
+    assert!(index.cached("webh").is_none());
+  }
+
+  #[test]
+  fn test_parse_property_query() {
+    let filters = parse_property_query("Restart=always  MemoryMax!=infinity").unwrap();
+    assert_eq!(filters[0], PropertyFilter { property: "Restart".into(), value: "always".into(), negate: false });
+    assert!(filters[1].negate);
+    assert!(filters[1].matches("1G"));
+    assert!(!filters[1].matches("infinity"));
+    assert_eq!(
+      parse_property_query("User="),
+      Some(vec![PropertyFilter { property: "User".into(), value: "".into(), negate: false }])
+    );
+    assert_eq!(parse_property_query("nginx"), None);
+    assert_eq!(parse_property_query("a=b"), None);
+    assert_eq!(parse_property_query("Restart=always nginx"), None);
+    assert_eq!(parse_property_query(""), None);
+  }
+
+  #[test]
+  fn test_preset_drift() {
+    assert!(preset_drift("enabled", "disabled"));
+    assert!(preset_drift("disabled", "enabled"));
+    assert!(preset_drift("masked", "enabled"));
+    assert!(!preset_drift("enabled-runtime", "enabled"));
+    assert!(!preset_drift("static", "disabled"));
+    assert!(!preset_drift("enabled", "ignored"));
+    assert!(!preset_drift("enabled", ""));
+  }
+
+  #[test]
+  fn test_unit_origin() {
+    assert_eq!(unit_origin("/usr/lib/systemd/system/nginx.service"), "vendor");
+    assert_eq!(unit_origin("/lib/systemd/system/cron.service"), "vendor");
+    assert_eq!(unit_origin("/etc/systemd/system/backup.service"), "admin");
+    assert_eq!(unit_origin("/home/me/.config/systemd/user/sync.service"), "admin");
+    assert_eq!(unit_origin("/run/systemd/generator/-.mount"), "generated");
+    assert_eq!(unit_origin("/run/user/1000/systemd/generator.late/app.service"), "generated");
+    assert_eq!(unit_origin("/run/systemd/transient/run-u1.service"), "runtime");
+    assert_eq!(unit_origin(""), "none");
+  }
+
+  #[test]
+  fn test_complete() {
+    let names = ["nginx.service", "nginx-debug.service", "php-fpm.service", "ssh.service"].map(String::from);
+    assert_eq!(complete("ngi", &names, 5), ["nginx.service", "nginx-debug.service"]);
+    assert_eq!(complete("fpm", &names, 5), ["php-fpm.service"]);
+    assert_eq!(complete("ss", &names, 1), ["ssh.service"]);
+    assert_eq!(complete("nginx.service", &names, 5), Vec::<&str>::new());
+    assert!(complete("", &names, 5).is_empty());
+  }
+
+  #[test]
+  fn test_name_matches() {
+    assert!(name_matches("docker*", "docker.service"));
+    assert!(name_matches("docker*", "docker.socket"));
+    assert!(!name_matches("docker*", "containerd.service"));
+    assert!(name_matches("*.timer", "logrotate.timer"));
+    assert!(name_matches("ssh", "sshd.service"));
+  }
+}
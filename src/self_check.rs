@@ -0,0 +1,184 @@
+// What will and won't work on this machine, checked once on the first launch (and on request afterwards) so that
+// missing permissions show up as one summary with what to do about them, rather than as features failing one at a
+// time later on: starting units needs root or polkit, reading other units' logs needs the right group, and user units
+// need a user bus, which `su` and plain ssh sessions often don't have.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use zbus::{fdo::DBusProxy, names::BusName, Connection};
+
+use crate::utils::get_data_dir;
+
+/// Groups whose members can read the whole journal
+const JOURNAL_GROUPS: &[&str] = &["systemd-journal", "adm", "wheel"];
+
+const POLKIT: &str = "org.freedesktop.PolicyKit1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+  Works,
+  /// Works, up to a point
+  Limited,
+  Missing,
+}
+
+impl Status {
+  pub fn symbol(self) -> &'static str {
+    match self {
+      Status::Works => "✓",
+      Status::Limited => "~",
+      Status::Missing => "✗",
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+  pub name: &'static str,
+  pub status: Status,
+  pub detail: String,
+  /// What would fix it, when it doesn't fully work
+  pub hint: Option<String>,
+}
+
+impl Check {
+  fn new(name: &'static str, status: Status, detail: impl Into<String>, hint: Option<&str>) -> Self {
+    Self { name, status, detail: detail.into(), hint: hint.map(String::from) }
+  }
+}
+
+fn marker() -> Result<PathBuf> {
+  Ok(get_data_dir()?.join("first-run-checked"))
+}
+
+/// Whether the checks haven't been shown yet on this machine
+pub fn is_first_run() -> bool {
+  marker().is_ok_and(|path| !path.exists())
+}
+
+/// Don't show the checks on launch again
+pub fn mark_done() -> Result<()> {
+  let path = marker()?;
+  if let Some(directory) = path.parent() {
+    std::fs::create_dir_all(directory)?;
+  }
+  std::fs::write(path, "")?;
+  Ok(())
+}
+
+pub async fn run() -> Vec<Check> {
+  let is_root = nix::unistd::geteuid().is_root();
+  let system_bus = Connection::system().await;
+  let polkit = match &system_bus {
+    Ok(connection) => has_polkit(connection).await,
+    Err(_) => false,
+  };
+
+  let mut checks = vec![match &system_bus {
+    Ok(_) => Check::new("System manager", Status::Works, "connected over D-Bus", None),
+    Err(e) => Check::new(
+      "System manager",
+      Status::Missing,
+      format!("can't reach the system bus: {e}"),
+      Some("Check that systemd and dbus are running; in a container, bind-mount /run/dbus/system_bus_socket"),
+    ),
+  }];
+
+  checks.push(match (is_root, polkit) {
+    (true, _) => Check::new("Starting and stopping units", Status::Works, "running as root", None),
+    (false, true) => Check::new(
+      "Starting and stopping units",
+      Status::Limited,
+      "polkit decides, which usually means a password prompt or a denial outside a desktop session",
+      Some("Run with sudo to manage system units without asking polkit"),
+    ),
+    (false, false) => Check::new(
+      "Starting and stopping units",
+      Status::Missing,
+      "not root, and polkit isn't running to authorize anyone else",
+      Some("Run with sudo"),
+    ),
+  });
+
+  let groups = group_names();
+  checks.push(match journal_access(is_root, &groups) {
+    Status::Works => Check::new("Reading logs", Status::Works, "all units' logs are readable", None),
+    status => Check::new(
+      "Reading logs",
+      status,
+      "only your own user's logs; system units will show no lines",
+      Some("sudo usermod -aG systemd-journal $USER, then log in again"),
+    ),
+  });
+
+  checks.push(match Connection::session().await {
+    Ok(_) => Check::new("User units", Status::Works, "connected to your user manager", None),
+    Err(_) if is_root => Check::new(
+      "User units",
+      Status::Limited,
+      "root has no user manager of its own",
+      Some("Switch to a logged-in user's manager (U) to see their units"),
+    ),
+    Err(_) => Check::new(
+      "User units",
+      Status::Missing,
+      "no user bus, as happens after su or in an ssh session without lingering",
+      Some("Log in directly, or use `machinectl shell $USER@` to get a full session"),
+    ),
+  });
+  checks
+}
+
+/// The checks as plain text, for the terminal
+pub fn report(checks: &[Check]) -> String {
+  let mut text = String::new();
+  for check in checks {
+    text.push_str(&format!("{} {}: {}\n", check.status.symbol(), check.name, check.detail));
+    if let Some(hint) = &check.hint {
+      text.push_str(&format!("  {hint}\n"));
+    }
+  }
+  text
+}
+
+async fn has_polkit(connection: &Connection) -> bool {
+  let Ok(proxy) = DBusProxy::new(connection).await else { return false };
+  let Ok(name) = BusName::try_from(POLKIT) else { return false };
+  if proxy.name_has_owner(name).await.unwrap_or(false) {
+    return true;
+  }
+  // it may not have been needed yet, and start on the first request
+  proxy.list_activatable_names().await.is_ok_and(|names| names.iter().any(|n| n.as_str() == POLKIT))
+}
+
+fn group_names() -> Vec<String> {
+  let gids = nix::unistd::getgroups().unwrap_or_default();
+  let primary = nix::unistd::getegid();
+  std::iter::once(primary)
+    .chain(gids)
+    .filter_map(|gid| nix::unistd::Group::from_gid(gid).ok().flatten())
+    .map(|group| group.name)
+    .collect()
+}
+
+/// Whether the whole journal is readable, going by who we are
+pub fn journal_access(is_root: bool, groups: &[String]) -> Status {
+  if is_root || groups.iter().any(|g| JOURNAL_GROUPS.contains(&g.as_str())) {
+    Status::Works
+  } else {
+    Status::Limited
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_journal_access() {
+    assert_eq!(journal_access(true, &[]), Status::Works);
+    assert_eq!(journal_access(false, &["users".into(), "adm".into()]), Status::Works);
+    assert_eq!(journal_access(false, &["users".into()]), Status::Limited);
+  }
+}
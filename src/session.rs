@@ -0,0 +1,133 @@
+// Unit state changes seen while the TUI has been running, so a service that keeps falling over shows up as a pattern
+// instead of something that happened to be noticed once. Units are polled, so a restart that completes between two
+// refreshes isn't seen.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Local};
+
+use crate::systemd::UnitId;
+
+/// A unit changing state at least this many times within `FLAP_WINDOW` is flapping
+pub const FLAP_TRANSITIONS: usize = 4;
+pub const FLAP_WINDOW: Duration = Duration::minutes(10);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+  pub at: DateTime<Local>,
+  pub from: String,
+  pub to: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitStats {
+  pub unit: UnitId,
+  pub transitions: usize,
+  /// Times the unit came back to active after leaving it
+  pub restarts: usize,
+  pub failures: usize,
+  pub flapping: bool,
+  pub last: Transition,
+}
+
+#[derive(Debug, Clone)]
+pub struct SessionStats {
+  pub started: DateTime<Local>,
+  transitions: HashMap<UnitId, Vec<Transition>>,
+}
+
+impl Default for SessionStats {
+  fn default() -> Self {
+    Self { started: Local::now(), transitions: HashMap::new() }
+  }
+}
+
+impl SessionStats {
+  pub fn record(&mut self, unit: UnitId, from: &str, to: &str, at: DateTime<Local>) {
+    if from != to {
+      self.transitions.entry(unit).or_default().push(Transition { at, from: from.into(), to: to.into() });
+    }
+  }
+
+  /// Every unit that changed state, flapping ones first, then the busiest
+  pub fn units(&self, now: DateTime<Local>) -> Vec<UnitStats> {
+    let mut units: Vec<UnitStats> = self
+      .transitions
+      .iter()
+      .filter_map(|(unit, transitions)| {
+        let recent = transitions.iter().filter(|t| now - t.at <= FLAP_WINDOW).count();
+        Some(UnitStats {
+          unit: unit.clone(),
+          transitions: transitions.len(),
+          restarts: restarts(transitions),
+          failures: transitions.iter().filter(|t| t.to == "failed").count(),
+          flapping: recent >= FLAP_TRANSITIONS,
+          last: transitions.last()?.clone(),
+        })
+      })
+      .collect();
+    units.sort_by(|a, b| {
+      b.flapping.cmp(&a.flapping).then(b.transitions.cmp(&a.transitions)).then(a.unit.name.cmp(&b.unit.name))
+    });
+    units
+  }
+
+  /// Failures in each `bucket` since the session started, oldest first
+  pub fn failures_over_time(&self, bucket: Duration, now: DateTime<Local>) -> Vec<u64> {
+    let index = |at: DateTime<Local>| ((at - self.started).num_seconds().max(0) / bucket.num_seconds().max(1)) as usize;
+    let mut buckets = vec![0; index(now) + 1];
+    for transition in self.transitions.values().flatten().filter(|t| t.to == "failed") {
+      if let Some(count) = buckets.get_mut(index(transition.at)) {
+        *count += 1;
+      }
+    }
+    buckets
+  }
+}
+
+/// Returns to active after the unit was seen leaving it. Reloads don't count
+fn restarts(transitions: &[Transition]) -> usize {
+  let mut left_active = false;
+  let mut restarts = 0;
+  for transition in transitions {
+    if transition.to == "active" && left_active {
+      restarts += 1;
+    }
+    left_active |= transition.from == "active" && transition.to != "reloading";
+  }
+  restarts
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::systemd::UnitScope;
+
+  #[test]
+  fn test_session_stats() {
+    let started = Local::now();
+    let mut stats = SessionStats { started, transitions: HashMap::new() };
+    let unit = |name: &str| UnitId { name: name.into(), scope: UnitScope::Global };
+    let at = |minutes| started + Duration::minutes(minutes);
+
+    stats.record(unit("steady.service"), "inactive", "active", at(1));
+    stats.record(unit("steady.service"), "active", "active", at(2));
+    for (i, (from, to)) in
+      [("active", "failed"), ("failed", "activating"), ("activating", "active"), ("active", "failed")]
+        .iter()
+        .enumerate()
+    {
+      stats.record(unit("flappy.service"), from, to, at(20 + i as i64));
+    }
+
+    let units = stats.units(at(25));
+    assert_eq!(units.len(), 2);
+    assert_eq!(units[0].unit, unit("flappy.service"));
+    assert!(units[0].flapping);
+    assert_eq!((units[0].failures, units[0].restarts), (2, 1));
+    assert!(!units[1].flapping);
+    assert_eq!(units[1].transitions, 1);
+
+    assert_eq!(stats.failures_over_time(Duration::minutes(10), at(25)), vec![0, 0, 2]);
+  }
+}
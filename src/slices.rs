@@ -0,0 +1,116 @@
+// The slice hierarchy with resource use rolled up from the units in each slice, so it's obvious which slice is using
+// the machine. Totals are summed from the running units rather than read off the slice itself, so they work without
+// accounting being enabled on the slices.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::systemd::parse_counter;
+
+/// The properties `build` reads for each unit, besides the `Id` that `get_all_unit_properties` always asks for
+pub const SLICE_PROPERTIES: &[&str] = &["Slice", "MemoryCurrent", "CPUUsageNSec", "TasksCurrent"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+  pub memory_bytes: u64,
+  pub cpu_nsec: u64,
+  pub tasks: u64,
+}
+
+impl Usage {
+  fn add(&mut self, other: Usage) {
+    self.memory_bytes += other.memory_bytes;
+    self.cpu_nsec += other.cpu_nsec;
+    self.tasks += other.tasks;
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SliceNode {
+  pub name: String,
+  /// Everything the slice's units (and the units of its child slices) use
+  pub total: Usage,
+  pub slices: Vec<SliceNode>,
+  /// Running units directly in this slice, with what each uses
+  pub units: Vec<(String, Usage)>,
+}
+
+impl SliceNode {
+  /// Child slices and units, biggest memory users first
+  fn sort(&mut self) {
+    self.slices.iter_mut().for_each(SliceNode::sort);
+    self.slices.sort_by(|a, b| b.total.memory_bytes.cmp(&a.total.memory_bytes).then(a.name.cmp(&b.name)));
+    self.units.sort_by(|a, b| b.1.memory_bytes.cmp(&a.1.memory_bytes).then(a.0.cmp(&b.0)));
+  }
+}
+
+/// Build the tree rooted at `-.slice` from each unit's `SLICE_PROPERTIES`
+pub fn build(units: &[HashMap<String, String>]) -> SliceNode {
+  let mut parents: BTreeMap<String, String> = BTreeMap::new();
+  let mut leaves: BTreeMap<String, Vec<(String, Usage)>> = BTreeMap::new();
+  for unit in units {
+    let (Some(id), Some(slice)) = (unit.get("Id"), unit.get("Slice")) else { continue };
+    if id.ends_with(".slice") {
+      if !slice.is_empty() {
+        parents.insert(id.clone(), slice.clone());
+      }
+      continue;
+    }
+    let counter = |name| parse_counter(unit, name);
+    let (memory, cpu, tasks) = (counter("MemoryCurrent"), counter("CPUUsageNSec"), counter("TasksCurrent"));
+    // units that aren't running have no counters
+    if slice.is_empty() || (memory.is_none() && cpu.is_none() && tasks.is_none()) {
+      continue;
+    }
+    let usage = Usage {
+      memory_bytes: memory.unwrap_or_default(),
+      cpu_nsec: cpu.unwrap_or_default(),
+      tasks: tasks.unwrap_or_default(),
+    };
+    leaves.entry(slice.clone()).or_default().push((id.clone(), usage));
+  }
+
+  fn node(
+    name: &str,
+    parents: &BTreeMap<String, String>,
+    leaves: &BTreeMap<String, Vec<(String, Usage)>>,
+  ) -> SliceNode {
+    let slices: Vec<SliceNode> =
+      parents.iter().filter(|(_, parent)| *parent == name).map(|(child, _)| node(child, parents, leaves)).collect();
+    let units = leaves.get(name).cloned().unwrap_or_default();
+    let mut total = Usage::default();
+    slices.iter().for_each(|s| total.add(s.total));
+    units.iter().for_each(|(_, u)| total.add(*u));
+    SliceNode { name: name.to_string(), total, slices, units }
+  }
+
+  let mut root = node("-.slice", &parents, &leaves);
+  root.sort();
+  root
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::systemd::parse_property_blocks;
+
+  #[test]
+  fn test_build() {
+    let output = "Id=-.slice\nSlice=\n\n\
+                  Id=system.slice\nSlice=-.slice\nMemoryCurrent=[not set]\n\n\
+                  Id=user.slice\nSlice=-.slice\n\n\
+                  Id=user-1000.slice\nSlice=user.slice\n\n\
+                  Id=nginx.service\nSlice=system.slice\nMemoryCurrent=100\nCPUUsageNSec=5\nTasksCurrent=2\n\n\
+                  Id=cron.service\nSlice=system.slice\nMemoryCurrent=[not set]\nCPUUsageNSec=[not set]\n\
+                  TasksCurrent=18446744073709551615\n\n\
+                  Id=user@1000.service\nSlice=user-1000.slice\nMemoryCurrent=300\nCPUUsageNSec=1\nTasksCurrent=7\n";
+    let root = build(&parse_property_blocks(output));
+    assert_eq!(root.total, Usage { memory_bytes: 400, cpu_nsec: 6, tasks: 9 });
+    // user.slice uses more memory, so it comes first
+    assert_eq!(root.slices.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), vec!["user.slice", "system.slice"]);
+    assert_eq!(root.slices[0].slices[0].total.tasks, 7);
+    assert_eq!(
+      root.slices[1].units,
+      vec![("nginx.service".to_string(), Usage { memory_bytes: 100, cpu_nsec: 5, tasks: 2 })]
+    );
+  }
+}
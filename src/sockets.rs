@@ -0,0 +1,247 @@
+// Poking a socket unit: connecting to what it listens on, the way a client would, to check that socket activation
+// actually brings up the service behind it. And finding what a unit's processes listen on, like `ss -tlnp` narrowed
+// to the unit, by matching the sockets in their fd tables against /proc/net. That only works on the local machine.
+
+use std::{
+  collections::{HashMap, HashSet},
+  io::Write,
+  net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket},
+  os::unix::net::{UnixDatagram, UnixStream},
+  path::PathBuf,
+  time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::process_tree::Process;
+
+/// Somewhere a socket unit listens that we know how to connect to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+  Tcp(SocketAddr),
+  Udp(SocketAddr),
+  UnixStream(PathBuf),
+  UnixDatagram(PathBuf),
+}
+
+impl std::fmt::Display for Target {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Target::Tcp(address) => write!(f, "tcp {address}"),
+      Target::Udp(address) => write!(f, "udp {address}"),
+      Target::UnixStream(path) => write!(f, "unix {}", path.display()),
+      Target::UnixDatagram(path) => write!(f, "unix datagram {}", path.display()),
+    }
+  }
+}
+
+/// Parse a `Listen=` value as printed by `systemctl show`, e.g. `[::]:22 (Stream)` or `/run/foo.sock (Stream)`.
+/// Fails for the kinds of socket there's no sensible test connection to, like FIFOs and netlink
+pub fn target(listen: &str) -> Result<Target> {
+  let Some((address, kind)) = listen.trim().rsplit_once(' ') else { bail!("Can't make sense of `{listen}`") };
+  let kind = kind.trim_matches(|c| c == '(' || c == ')');
+  if address.starts_with('@') {
+    bail!("{address} is an abstract socket, which can't be poked from here");
+  }
+  if address.starts_with('/') {
+    return match kind {
+      "Stream" | "SequentialPacket" => Ok(Target::UnixStream(address.into())),
+      "Datagram" => Ok(Target::UnixDatagram(address.into())),
+      _ => bail!("{address} is a {kind} socket, only stream and datagram sockets can be poked"),
+    };
+  }
+  let address = loopback(address)?;
+  match kind {
+    "Stream" => Ok(Target::Tcp(address)),
+    "Datagram" => Ok(Target::Udp(address)),
+    _ => bail!("{address} is a {kind} socket, only stream and datagram sockets can be poked"),
+  }
+}
+
+/// A socket listening on every interface is reached through loopback. A bare port listens on all of them too
+fn loopback(address: &str) -> Result<SocketAddr> {
+  let address = if address.chars().all(|c| c.is_ascii_digit()) { format!("[::]:{address}") } else { address.into() };
+  let mut address: SocketAddr =
+    address.to_socket_addrs()?.next().with_context(|| format!("{address} doesn't resolve to anything"))?;
+  match address {
+    SocketAddr::V4(ref mut v4) if v4.ip().is_unspecified() => v4.set_ip(std::net::Ipv4Addr::LOCALHOST),
+    SocketAddr::V6(ref mut v6) if v6.ip().is_unspecified() => v6.set_ip(std::net::Ipv6Addr::LOCALHOST),
+    _ => {},
+  }
+  Ok(address)
+}
+
+/// Make a connection (or send an empty datagram), then hang up. The service sees a client that says nothing
+pub fn poke(target: &Target, timeout: Duration) -> Result<()> {
+  match target {
+    Target::Tcp(address) => {
+      TcpStream::connect_timeout(address, timeout)?;
+    },
+    Target::Udp(address) => {
+      let local = if address.is_ipv4() { "127.0.0.1:0" } else { "[::1]:0" };
+      UdpSocket::bind(local)?.send_to(&[], address)?;
+    },
+    Target::UnixStream(path) => {
+      let mut stream = UnixStream::connect(path)?;
+      stream.set_write_timeout(Some(timeout))?;
+      stream.flush()?;
+    },
+    Target::UnixDatagram(path) => {
+      UnixDatagram::unbound()?.send_to(&[], path)?;
+    },
+  }
+  Ok(())
+}
+
+/// A socket one of a unit's processes listens on, or for datagram sockets is just bound to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Listening {
+  /// tcp, udp or unix, as `ss` puts it
+  pub protocol: &'static str,
+  pub address: String,
+  /// The first of the unit's processes with it open
+  pub pid: u32,
+  pub comm: String,
+}
+
+/// The sockets `processes` listen on. They're looked up in the first process's network namespace, which is the
+/// unit's unless it has processes in several
+pub fn listening(processes: &[Process]) -> Result<Vec<Listening>> {
+  let Some(first) = processes.first() else { return Ok(vec![]) };
+  let net = format!("/proc/{}/net", first.pid);
+  let mut sockets = HashMap::new();
+  for (file, protocol) in [("tcp", "tcp"), ("tcp6", "tcp"), ("udp", "udp"), ("udp6", "udp")] {
+    // no tcp6 or udp6 without IPv6
+    if let Ok(text) = std::fs::read_to_string(format!("{net}/{file}")) {
+      sockets.extend(parse_inet(&text, protocol));
+    }
+  }
+  let unix = std::fs::read_to_string(format!("{net}/unix")).with_context(|| format!("Failed to read {net}/unix"))?;
+  sockets.extend(parse_unix(&unix));
+
+  let mut listening = vec![];
+  let mut seen = HashSet::new();
+  let mut readable = false;
+  for process in processes {
+    // processes can exit while we look, and other users' fd tables need root
+    let Ok(inodes) = socket_inodes(process.pid) else { continue };
+    readable = true;
+    for inode in inodes {
+      let Some((protocol, address)) = sockets.get(&inode) else { continue };
+      if seen.insert(inode) {
+        listening.push(Listening { protocol, address: address.clone(), pid: process.pid, comm: process.comm.clone() });
+      }
+    }
+  }
+  if !readable {
+    bail!("Not allowed to see the files the processes have open, which takes root");
+  }
+  listening.sort_by(|a, b| (a.protocol, &a.address).cmp(&(b.protocol, &b.address)));
+  Ok(listening)
+}
+
+/// The inodes of the sockets in a process's fd table
+fn socket_inodes(pid: u32) -> Result<Vec<u64>> {
+  let mut inodes = vec![];
+  for entry in std::fs::read_dir(format!("/proc/{pid}/fd"))?.flatten() {
+    let Ok(target) = std::fs::read_link(entry.path()) else { continue };
+    let target = target.to_string_lossy();
+    if let Some(inode) = target.strip_prefix("socket:[").and_then(|t| t.strip_suffix(']')) {
+      inodes.extend(inode.parse::<u64>().ok());
+    }
+  }
+  Ok(inodes)
+}
+
+/// The listening TCP sockets, or bound but unconnected UDP ones, in /proc/net/tcp, tcp6, udp or udp6, by inode
+fn parse_inet(text: &str, protocol: &'static str) -> HashMap<u64, (&'static str, String)> {
+  // TCP_LISTEN for TCP, TCP_CLOSE (not connected) for UDP
+  let listening_state = if protocol == "tcp" { "0A" } else { "07" };
+  text
+    .lines()
+    .skip(1)
+    .filter_map(|line| {
+      let fields = line.split_whitespace().collect::<Vec<_>>();
+      if fields.get(3) != Some(&listening_state) {
+        return None;
+      }
+      let address = parse_inet_address(fields.get(1)?)?;
+      Some((fields.get(9)?.parse().ok()?, (protocol, address.to_string())))
+    })
+    .collect()
+}
+
+/// An address from /proc/net, like `0100007F:0016`. The address is printed as the native-endian u32s it's stored
+/// in, so their bytes are back in network order once printed the same way
+fn parse_inet_address(text: &str) -> Option<SocketAddr> {
+  let (address, port) = text.split_once(':')?;
+  let port = u16::from_str_radix(port, 16).ok()?;
+  let words = (0..address.len() / 8)
+    .map(|i| Some(u32::from_str_radix(address.get(i * 8..i * 8 + 8)?, 16).ok()?.to_ne_bytes()))
+    .collect::<Option<Vec<_>>>()?;
+  let ip = match words.concat()[..] {
+    [a, b, c, d] => IpAddr::V4(Ipv4Addr::new(a, b, c, d)),
+    ref bytes if bytes.len() == 16 => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?)),
+    _ => return None,
+  };
+  Some(SocketAddr::new(ip, port))
+}
+
+/// The listening stream sockets and bound datagram sockets in /proc/net/unix, by inode
+fn parse_unix(text: &str) -> HashMap<u64, (&'static str, String)> {
+  /// __SO_ACCEPTCON, set on sockets that called listen()
+  const ACCEPTING: u32 = 0x10000;
+  const DATAGRAM: &str = "0002";
+  text
+    .lines()
+    .skip(1)
+    .filter_map(|line| {
+      let fields = line.split_whitespace().collect::<Vec<_>>();
+      let path = fields.get(7)?;
+      let flags = u32::from_str_radix(fields.get(3)?, 16).ok()?;
+      if flags & ACCEPTING == 0 && fields.get(4) != Some(&DATAGRAM) {
+        return None;
+      }
+      Some((fields.get(6)?.parse().ok()?, ("unix", path.to_string())))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_target() {
+    assert_eq!(target("[::]:22 (Stream)").unwrap(), Target::Tcp("[::1]:22".parse().unwrap()));
+    assert_eq!(target("0.0.0.0:53 (Datagram)").unwrap(), Target::Udp("127.0.0.1:53".parse().unwrap()));
+    assert_eq!(target("/run/cups/cups.sock (Stream)").unwrap(), Target::UnixStream("/run/cups/cups.sock".into()));
+    assert_eq!(target("631 (Stream)").unwrap(), Target::Tcp("[::1]:631".parse().unwrap()));
+    assert!(target("/run/initctl (FIFO)").is_err());
+    assert!(target("@/org/kernel/udev (SequentialPacket)").is_err());
+  }
+
+  #[test]
+  fn test_parse_proc_net() {
+    let tcp = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 21345 1 0
+   1: 0100007F:1F90 0100007F:D431 01 00000000:00000000 00:00000000 00000000  1000        0 55555 1 0";
+    let sockets = parse_inet(tcp, "tcp");
+    // the established connection isn't listening
+    assert_eq!(sockets.len(), 1);
+    assert_eq!(sockets[&21345], ("tcp", "0.0.0.0:22".to_string()));
+    if cfg!(target_endian = "little") {
+      assert_eq!(parse_inet_address("0100007F:0035").unwrap().to_string(), "127.0.0.1:53");
+      assert_eq!(parse_inet_address("00000000000000000000000001000000:0050").unwrap().to_string(), "[::1]:80");
+    }
+    let unix = "Num       RefCount Protocol Flags    Type St Inode Path
+0000000000000000: 00000002 00000000 00010000 0001 01 18092 /run/systemd/journal/stdout
+0000000000000000: 00000002 00000000 00000000 0002 01 18094 /run/systemd/journal/socket
+0000000000000000: 00000003 00000000 00000000 0001 03 40114 /run/dbus/system_bus_socket
+0000000000000000: 00000003 00000000 00000000 0001 03 40115";
+    let sockets = parse_unix(unix);
+    assert_eq!(sockets.len(), 2);
+    assert_eq!(sockets[&18092].1, "/run/systemd/journal/stdout");
+    assert_eq!(sockets[&18094].1, "/run/systemd/journal/socket");
+  }
+}
@@ -0,0 +1,73 @@
+// How a unit's state is shown: a color from the theme, and a symbol so that telling states apart doesn't
+// rely on color alone. Log lines get the same treatment for their priority.
+
+use ratatui::style::Color;
+
+use crate::theme::Theme;
+
+/// The handful of states the unit list distinguishes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnitState {
+  Active,
+  Failed,
+  /// activating, deactivating, reloading and the like
+  Changing,
+  Inactive,
+  NotFound,
+}
+
+impl UnitState {
+  pub fn of(load_state: &str, activation_state: &str) -> Self {
+    if load_state == "not-found" {
+      return UnitState::NotFound;
+    }
+    match activation_state {
+      "active" => UnitState::Active,
+      "failed" => UnitState::Failed,
+      "inactive" | "" => UnitState::Inactive,
+      _ => UnitState::Changing,
+    }
+  }
+
+  pub fn symbol(self) -> &'static str {
+    match self {
+      UnitState::Active => "●",
+      UnitState::Failed => "✗",
+      UnitState::Changing => "◐",
+      UnitState::Inactive => "○",
+      UnitState::NotFound => "?",
+    }
+  }
+
+  pub fn color(self, theme: &Theme) -> Color {
+    match self {
+      UnitState::Active => theme.active,
+      UnitState::Failed => theme.failed,
+      UnitState::NotFound => theme.warning,
+      UnitState::Changing | UnitState::Inactive => Color::Reset,
+    }
+  }
+}
+
+/// Marks errors and warnings in the log pane when the symbols are on; other lines get a space, to keep them lined up
+pub fn log_priority_symbol(priority: u8) -> &'static str {
+  match priority {
+    0..=3 => "✗",
+    4 => "!",
+    _ => " ",
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_unit_state() {
+    assert_eq!(UnitState::of("loaded", "active"), UnitState::Active);
+    assert_eq!(UnitState::of("loaded", "deactivating"), UnitState::Changing);
+    assert_eq!(UnitState::of("not-found", "inactive"), UnitState::NotFound);
+    assert_eq!(UnitState::Failed.color(&Theme::SOLARIZED), Theme::SOLARIZED.failed);
+    assert_eq!((log_priority_symbol(2), log_priority_symbol(4), log_priority_symbol(6)), ("✗", "!", " "));
+  }
+}
@@ -0,0 +1,106 @@
+// Running a helper program to completion without letting it hang whoever asked: logs piped through a command, plugins
+// and health checks all do this. Its output is read on threads while it runs, so a program that prints more than a
+// pipe holds doesn't block on writing it and look like it hung. It runs in a process group of its own, so whatever it
+// started in the background goes when it's killed, and its output stops being waited for at the deadline either way.
+
+use std::{
+  io::{Read, Write},
+  os::unix::process::CommandExt,
+  process::{Command, ExitStatus, Stdio},
+  sync::mpsc::{self, Receiver},
+  time::{Duration, Instant},
+};
+
+/// How often a running program is checked for having exited
+const WAIT_POLL: Duration = Duration::from_millis(20);
+
+/// How a program that exited went
+#[derive(Debug)]
+pub struct Finished {
+  pub status: ExitStatus,
+  pub stdout: String,
+  pub stderr: String,
+}
+
+/// Run `command` with `input` on its stdin (nothing if `None`), and wait for it to exit. `None` if it was still running
+/// after `timeout`, in which case it's killed
+pub fn run_with_timeout(
+  command: &mut Command,
+  input: Option<String>,
+  timeout: Duration,
+) -> std::io::Result<Option<Finished>> {
+  let stdin = if input.is_some() { Stdio::piped() } else { Stdio::null() };
+  let mut child = command.stdin(stdin).stdout(Stdio::piped()).stderr(Stdio::piped()).process_group(0).spawn()?;
+
+  if let (Some(mut stdin), Some(input)) = (child.stdin.take(), input) {
+    // a program that stops reading early (e.g. head) isn't an error
+    std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+  }
+  let stdout = drain(child.stdout.take());
+  let stderr = drain(child.stderr.take());
+
+  let deadline = Instant::now() + timeout;
+  let status = loop {
+    if let Some(status) = child.try_wait()? {
+      break status;
+    }
+    if Instant::now() > deadline {
+      // the whole group, so nothing it started keeps the pipes open
+      // SAFETY: kill has no memory safety requirements; the group is the child's own, as it hasn't been reaped yet
+      unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) };
+      let _ = child.wait();
+      return Ok(None);
+    }
+    std::thread::sleep(WAIT_POLL);
+  };
+  // something it left running in the background can hold the pipes open past its exit
+  let collect = |reader: Receiver<String>| reader.recv_timeout(deadline.saturating_duration_since(Instant::now()));
+  Ok(Some(Finished {
+    status,
+    stdout: collect(stdout).unwrap_or_default(),
+    stderr: collect(stderr).unwrap_or_default(),
+  }))
+}
+
+/// Read everything from `pipe` on a thread, sending it once the pipe closes
+fn drain(pipe: Option<impl Read + Send + 'static>) -> Receiver<String> {
+  let (tx, rx) = mpsc::channel();
+  std::thread::spawn(move || {
+    let mut output = vec![];
+    if let Some(mut pipe) = pipe {
+      let _ = pipe.read_to_end(&mut output);
+    }
+    let _ = tx.send(String::from_utf8_lossy(&output).into_owned());
+  });
+  rx
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_run_with_timeout() {
+    // more than a pipe buffer's worth on stderr mustn't look like a hang
+    let mut chatty = Command::new("sh");
+    chatty.args(["-c", "head -c 200000 /dev/zero >&2; cat"]);
+    let finished = run_with_timeout(&mut chatty, Some("fed".into()), Duration::from_secs(5)).unwrap().unwrap();
+    assert!(finished.status.success());
+    assert_eq!((finished.stdout.as_str(), finished.stderr.len()), ("fed", 200_000));
+
+    let mut slow = Command::new("sleep");
+    slow.arg("5");
+    assert!(run_with_timeout(&mut slow, None, Duration::from_millis(100)).unwrap().is_none());
+
+    // a background process holding the pipes doesn't keep the timeout from working, before or after the exit
+    let started = Instant::now();
+    let mut forking = Command::new("sh");
+    forking.args(["-c", "sleep 5 & sleep 5"]);
+    assert!(run_with_timeout(&mut forking, None, Duration::from_millis(100)).unwrap().is_none());
+    let mut leaving = Command::new("sh");
+    leaving.args(["-c", "sleep 5 & echo done"]);
+    let finished = run_with_timeout(&mut leaving, None, Duration::from_millis(300)).unwrap().unwrap();
+    assert!(finished.status.success());
+    assert!(started.elapsed() < Duration::from_secs(2));
+  }
+}
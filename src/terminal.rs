@@ -1,47 +1,59 @@
 use std::{
   ops::{Deref, DerefMut},
   sync::Arc,
+  time::Instant,
 };
 
 use anyhow::{anyhow, Context, Result};
 use crossterm::{
   cursor,
-  event::{DisableMouseCapture, EnableMouseCapture},
+  event::{DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture},
   terminal::{EnterAlternateScreen, LeaveAlternateScreen},
 };
-use ratatui::backend::CrosstermBackend as Backend;
+use ratatui::{backend::CrosstermBackend as Backend, layout::Rect};
 use signal_hook::{iterator::Signals, low_level};
 use tokio::{
   sync::{mpsc, Mutex},
   task::JoinHandle,
 };
+use tracing::warn;
 
-use crate::components::{home::Home, Component};
+use crate::{
+  components::{home::Home, Component},
+  monochrome,
+  utils::log_perf_event,
+};
 
 // A struct that mostly exists to be a catch-all for terminal operations that should be synchronized
 pub struct Tui {
   pub terminal: ratatui::Terminal<Backend<std::io::Stderr>>,
+  /// Whether to capture the mouse, which takes it away from the terminal
+  pub mouse: bool,
 }
 
 impl Tui {
-  pub fn new() -> Result<Self> {
+  pub fn new(mouse: bool) -> Result<Self> {
     let terminal = ratatui::Terminal::new(Backend::new(std::io::stderr()))?;
 
-    // spin up a signal handler to catch SIGTERM and exit gracefully
+    // spin up a signal handler to catch SIGTERM, or SIGHUP when the terminal goes away, and exit gracefully
     let _ = std::thread::spawn(move || {
-      const SIGNALS: &[libc::c_int] = &[signal_hook::consts::signal::SIGTERM];
+      const SIGNALS: &[libc::c_int] = &[signal_hook::consts::signal::SIGTERM, signal_hook::consts::signal::SIGHUP];
       let mut sigs = Signals::new(SIGNALS).unwrap();
       let signal = sigs.into_iter().next().unwrap();
       let _ = exit();
+      crate::control::remove_socket();
       low_level::emulate_default_handler(signal).unwrap();
     });
 
-    Ok(Self { terminal })
+    Ok(Self { terminal, mouse })
   }
 
   pub fn enter(&self) -> Result<()> {
     crossterm::terminal::enable_raw_mode()?;
-    crossterm::execute!(std::io::stderr(), EnterAlternateScreen, EnableMouseCapture, cursor::Hide)?;
+    crossterm::execute!(std::io::stderr(), EnterAlternateScreen, EnableFocusChange, cursor::Hide)?;
+    if self.mouse {
+      crossterm::execute!(std::io::stderr(), EnableMouseCapture)?;
+    }
     Ok(())
   }
 
@@ -64,7 +76,7 @@ impl Tui {
 
 // This one's public because we want to expose it to the panic handler
 pub fn exit() -> Result<()> {
-  crossterm::execute!(std::io::stderr(), LeaveAlternateScreen, DisableMouseCapture, cursor::Show)?;
+  crossterm::execute!(std::io::stderr(), LeaveAlternateScreen, DisableMouseCapture, DisableFocusChange, cursor::Show)?;
   crossterm::terminal::disable_raw_mode()?;
   Ok(())
 }
@@ -104,10 +116,10 @@ pub struct TerminalHandler {
 }
 
 impl TerminalHandler {
-  pub fn new(home: Arc<Mutex<Home>>) -> Self {
+  pub fn new(home: Arc<Mutex<Home>>, mouse: bool) -> Self {
     let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
     let cloned_home = home.clone();
-    let tui = Tui::new().context(anyhow!("Unable to create terminal")).unwrap();
+    let tui = Tui::new(mouse).context(anyhow!("Unable to create terminal")).unwrap();
     tui.enter().unwrap();
     let tui = Arc::new(Mutex::new(tui));
     let cloned_tui = tui.clone();
@@ -151,6 +163,28 @@ impl TerminalHandler {
     render(&mut tui, &mut home);
   }
 
+  /// Lay out for the new size and draw every cell again, rather than only what changed since the last frame: after a
+  /// resize or a reattach the screen can't be trusted to still hold that frame
+  pub async fn resize(&self, width: u16, height: u16) {
+    let mut home = self.home.lock().await;
+    let mut tui = self.tui.lock().await;
+    if let Err(e) = tui.resize(Rect::new(0, 0, width, height)) {
+      warn!("Unable to resize the terminal: {e}");
+    }
+    render(&mut tui, &mut home);
+  }
+
+  /// Hand the terminal to something else, like an editor or pager, for the duration of `f`, then take it back and
+  /// redraw from scratch. Stop the event handler first so it doesn't eat the other program's input
+  pub async fn run_outside<T>(&self, f: impl FnOnce() -> T) -> Result<T> {
+    let mut tui = self.tui.lock().await;
+    tui.exit()?;
+    let result = f();
+    tui.enter()?;
+    tui.clear()?;
+    Ok(result)
+  }
+
   // little more performant in situations where we don't need to wait for the render to complete
   pub fn enqueue_render(&self) -> Result<()> {
     self.tx.send(Message::Render)?;
@@ -159,9 +193,16 @@ impl TerminalHandler {
 }
 
 fn render(tui: &mut Tui, home: &mut Home) {
-  tui
-    .draw(|f| {
-      home.render(f, f.area());
-    })
-    .expect("Unable to draw");
+  let started = Instant::now();
+  let result = tui.draw(|f| {
+    home.render(f, f.area());
+    if home.monochrome {
+      monochrome::strip_colors(f.buffer_mut());
+    }
+  });
+  // the terminal can briefly go away (e.g. while detached); skip the frame rather than crash, the next one catches up
+  if let Err(e) = result {
+    warn!("Unable to draw: {e}");
+  }
+  log_perf_event("frame", started.elapsed());
 }
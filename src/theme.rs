@@ -0,0 +1,255 @@
+// Colors for the whole UI. `dark` and `light` are built in, one of them picked by the terminal's background unless
+// the config file names a theme, and so is `solarized` for terminals set up with the Solarized palette. `monochrome`
+// draws without color, like NO_COLOR, with unit states and log priorities marked by symbols instead. More can be
+// defined in the `[themes]` table of config.toml, each starting from a built-in one and changing some of its colors:
+//
+//   theme = "mine"
+//
+//   [themes.mine]
+//   base = "light"
+//   selection = "#eee8d5"
+//   failed = "magenta"
+//
+// Colors are names (`red`, `light-green`), `#rrggbb`, or 256-color indexes.
+
+use std::{collections::HashMap, str::FromStr};
+
+use anyhow::{bail, Context, Result};
+use ratatui::{buffer::Buffer, style::Color};
+
+use crate::config::Palette;
+
+pub const BUILT_IN: &[&str] = &["dark", "light", "solarized", "monochrome"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+  /// Text that isn't colored otherwise
+  pub foreground: Color,
+  /// The help popup and menu keys
+  pub primary: Color,
+  /// Borders of the focused pane and forms
+  pub accent: Color,
+  /// Keyboard shortcuts in the help line
+  pub kbd: Color,
+  pub muted: Color,
+  pub muted_alt: Color,
+  /// Background of the selected row
+  pub selection: Color,
+  /// Unit states
+  pub active: Color,
+  pub failed: Color,
+  /// Units that aren't found, and things worth a look
+  pub warning: Color,
+  /// Log lines by priority
+  pub log_error: Color,
+  pub log_warning: Color,
+  pub log_notice: Color,
+  pub log_info: Color,
+  pub log_debug: Color,
+}
+
+impl Default for Theme {
+  fn default() -> Self {
+    Self::DARK
+  }
+}
+
+impl Theme {
+  pub const DARK: Theme = Theme {
+    foreground: Color::Reset,
+    primary: Color::Cyan,
+    accent: Color::LightGreen,
+    kbd: Color::Gray, // appears white-ish when bold on dark terminals
+    muted: Color::Gray,
+    muted_alt: Color::DarkGray,
+    selection: Color::DarkGray,
+    active: Color::Green,
+    failed: Color::Red,
+    warning: Color::Yellow,
+    log_error: Color::Red,
+    log_warning: Color::Yellow,
+    log_notice: Color::Cyan,
+    log_info: Color::Reset,
+    log_debug: Color::DarkGray,
+  };
+
+  pub const LIGHT: Theme = Theme {
+    foreground: Color::Reset,
+    primary: Color::Blue,
+    accent: Color::Green,
+    kbd: Color::Blue,
+    muted: Color::DarkGray,
+    muted_alt: Color::Reset,
+    selection: Color::Gray,
+    active: Color::Green,
+    failed: Color::Red,
+    // yellow is barely visible on white
+    warning: Color::Indexed(130),
+    log_error: Color::Red,
+    log_warning: Color::Indexed(130),
+    log_notice: Color::Blue,
+    log_info: Color::Reset,
+    log_debug: Color::DarkGray,
+  };
+
+  pub const SOLARIZED: Theme = Theme {
+    foreground: Color::Rgb(0x83, 0x94, 0x96),  // base0
+    primary: Color::Rgb(0x26, 0x8b, 0xd2),     // blue
+    accent: Color::Rgb(0x2a, 0xa1, 0x98),      // cyan
+    kbd: Color::Rgb(0x93, 0xa1, 0xa1),         // base1
+    muted: Color::Rgb(0x93, 0xa1, 0xa1),       // base1
+    muted_alt: Color::Rgb(0x58, 0x6e, 0x75),   // base01
+    selection: Color::Rgb(0x07, 0x36, 0x42),   // base02
+    active: Color::Rgb(0x85, 0x99, 0x00),      // green
+    failed: Color::Rgb(0xdc, 0x32, 0x2f),      // red
+    warning: Color::Rgb(0xb5, 0x89, 0x00),     // yellow
+    log_error: Color::Rgb(0xdc, 0x32, 0x2f),   // red
+    log_warning: Color::Rgb(0xcb, 0x4b, 0x16), // orange
+    log_notice: Color::Rgb(0x6c, 0x71, 0xc4),  // violet
+    log_info: Color::Rgb(0x83, 0x94, 0x96),    // base0
+    log_debug: Color::Rgb(0x58, 0x6e, 0x75),   // base01
+  };
+
+  /// No colors at all. The selection's background is drawn reversed
+  pub const MONOCHROME: Theme = Theme {
+    foreground: Color::Reset,
+    primary: Color::Reset,
+    accent: Color::Reset,
+    kbd: Color::Reset,
+    muted: Color::Reset,
+    muted_alt: Color::Reset,
+    selection: Color::DarkGray,
+    active: Color::Reset,
+    failed: Color::Reset,
+    warning: Color::Reset,
+    log_error: Color::Reset,
+    log_warning: Color::Reset,
+    log_notice: Color::Reset,
+    log_info: Color::Reset,
+    log_debug: Color::Reset,
+  };
+
+  /// `dark` or `light`, whichever suits the terminal's background
+  pub fn detect() -> Self {
+    if terminal_light::luma().is_ok_and(|luma| luma > 0.5) {
+      Self::LIGHT
+    } else {
+      Self::DARK
+    }
+  }
+
+  pub fn built_in(name: &str) -> Option<Self> {
+    match name {
+      "dark" => Some(Self::DARK),
+      "light" => Some(Self::LIGHT),
+      "solarized" => Some(Self::SOLARIZED),
+      "monochrome" => Some(Self::MONOCHROME),
+      _ => None,
+    }
+  }
+
+  /// The unit state colors from a palette other than the default, which take the theme's place
+  pub fn with_palette(mut self, palette: Palette) -> Self {
+    if palette == Palette::ColorBlind && self != Self::MONOCHROME {
+      // blue/orange from the Okabe-Ito palette, which stays apart under the common kinds of color blindness
+      self.active = Color::Indexed(39);
+      self.failed = Color::Indexed(208);
+      self.warning = Color::Indexed(220);
+    }
+    self
+  }
+
+  /// The color of a log line at a syslog priority, 0 (emerg) to 7 (debug)
+  pub fn log_priority(&self, priority: u8) -> Color {
+    match priority {
+      0..=3 => self.log_error,
+      4 => self.log_warning,
+      5 => self.log_notice,
+      6 => self.log_info,
+      _ => self.log_debug,
+    }
+  }
+
+  fn color_mut(&mut self, name: &str) -> Option<&mut Color> {
+    Some(match name {
+      "foreground" => &mut self.foreground,
+      "primary" => &mut self.primary,
+      "accent" => &mut self.accent,
+      "kbd" => &mut self.kbd,
+      "muted" => &mut self.muted,
+      "muted-alt" => &mut self.muted_alt,
+      "selection" => &mut self.selection,
+      "active" => &mut self.active,
+      "failed" => &mut self.failed,
+      "warning" => &mut self.warning,
+      "log-error" => &mut self.log_error,
+      "log-warning" => &mut self.log_warning,
+      "log-notice" => &mut self.log_notice,
+      "log-info" => &mut self.log_info,
+      "log-debug" => &mut self.log_debug,
+      _ => return None,
+    })
+  }
+}
+
+/// Themes from the config file: colors by name, plus the built-in theme they start from as `base`
+pub type CustomThemes = HashMap<String, HashMap<String, String>>;
+
+/// The built-in themes, then the ones from the config file
+pub fn names(custom: &CustomThemes) -> Vec<String> {
+  let mut names = custom.keys().filter(|name| !BUILT_IN.contains(&name.as_str())).cloned().collect::<Vec<_>>();
+  names.sort();
+  BUILT_IN.iter().map(|name| name.to_string()).chain(names).collect()
+}
+
+/// A theme by name. A theme in the config file takes precedence over a built-in one of the same name
+pub fn resolve(name: &str, custom: &CustomThemes) -> Result<Theme> {
+  let Some(colors) = custom.get(name) else {
+    return Theme::built_in(name).with_context(|| format!("No theme named {name}"));
+  };
+  let base = colors.get("base").map(String::as_str).unwrap_or("dark");
+  let mut theme = Theme::built_in(base)
+    .with_context(|| format!("Theme {name} is based on {base}, which isn't one of {}", BUILT_IN.join(", ")))?;
+  for (key, value) in colors.iter().filter(|(key, _)| *key != "base") {
+    let Some(color) = theme.color_mut(key) else {
+      bail!("Theme {name} has an unknown color {key}");
+    };
+    *color = Color::from_str(value).ok().with_context(|| format!("Theme {name}: {value} isn't a color"))?;
+  }
+  Ok(theme)
+}
+
+/// Give text the theme's foreground where nothing else colored it
+pub fn fill_foreground(buffer: &mut Buffer, foreground: Color) {
+  if foreground == Color::Reset {
+    return;
+  }
+  for cell in buffer.content.iter_mut().filter(|cell| cell.fg == Color::Reset) {
+    cell.fg = foreground;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_resolve() {
+    let custom: CustomThemes = toml::from_str(
+      "[mine]\nbase = \"light\"\nselection = \"#eee8d5\"\nfailed = \"magenta\"\nlog-debug = \"244\"\n\n[broken]\nborder = \
+       \"red\"",
+    )
+    .unwrap();
+    let theme = resolve("mine", &custom).unwrap();
+    assert_eq!(theme.selection, Color::Rgb(0xee, 0xe8, 0xd5));
+    assert_eq!(theme.failed, Color::Magenta);
+    assert_eq!(theme.log_debug, Color::Indexed(244));
+    assert_eq!(theme.primary, Theme::LIGHT.primary);
+
+    assert_eq!(resolve("solarized", &custom).unwrap(), Theme::SOLARIZED);
+    assert!(resolve("broken", &custom).is_err());
+    assert!(resolve("nope", &custom).is_err());
+    assert_eq!(names(&custom), ["dark", "light", "solarized", "monochrome", "broken", "mine"]);
+    assert_eq!(Theme::DARK.with_palette(Palette::ColorBlind).active, Color::Indexed(39));
+  }
+}
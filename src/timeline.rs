@@ -0,0 +1,107 @@
+// A unit's history drawn as a horizontal strip: one cell per slice of time, showing whether it was running, when it
+// started and failed, and markers for reloads and actions taken from here. Flapping shows up as a run of short
+// segments, and a failure that lines up with a deploy is easy to spot.
+
+use crate::systemd::Invocation;
+
+/// What a single cell of the strip shows. When several things happen within one cell, the most notable wins
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Slot {
+  Idle,
+  Running,
+  Started,
+  Failed,
+}
+
+impl Slot {
+  pub fn symbol(self) -> char {
+    match self {
+      Slot::Idle => '·',
+      Slot::Running => '━',
+      Slot::Started => '┣',
+      Slot::Failed => '✗',
+    }
+  }
+}
+
+/// The cell `at` (microseconds since the epoch) falls into, if it's within `from..=to`
+pub fn column(at: i64, from: i64, to: i64, width: usize) -> Option<usize> {
+  if width == 0 || at < from || at > to {
+    return None;
+  }
+  let span = (to - from).max(1) as f64;
+  Some((((at - from) as f64 / span) * width as f64) as usize).map(|c| c.min(width - 1))
+}
+
+pub fn is_failure(run: &Invocation) -> bool {
+  run.result.as_deref().is_some_and(|r| r != "success")
+}
+
+/// The strip for `runs` (oldest first) between `from` and `to`. A run with no recorded end lasts until the next run
+/// starts, or until `to` when it's the latest
+pub fn lane(runs: &[Invocation], from: i64, to: i64, width: usize) -> Vec<Slot> {
+  let mut slots = vec![Slot::Idle; width];
+  for (i, run) in runs.iter().enumerate() {
+    let end = run.ended.or_else(|| runs.get(i + 1).map(|next| next.started)).unwrap_or(to);
+    let (Some(first), Some(last)) =
+      (column(run.started.max(from), from, to, width), column(end.min(to), from, to, width))
+    else {
+      continue;
+    };
+    for slot in &mut slots[first..=last] {
+      *slot = (*slot).max(Slot::Running);
+    }
+    if run.started >= from {
+      slots[first] = slots[first].max(Slot::Started);
+    }
+    if is_failure(run) && run.ended.is_some_and(|e| e <= to) {
+      slots[last] = Slot::Failed;
+    }
+  }
+  slots
+}
+
+/// A row of markers: each `(time, symbol)` lands in its cell, later ones overwriting earlier ones
+pub fn markers(events: &[(i64, char)], from: i64, to: i64, width: usize) -> Vec<Option<char>> {
+  let mut row = vec![None; width];
+  for (at, symbol) in events {
+    if let Some(c) = column(*at, from, to, width) {
+      row[c] = Some(*symbol);
+    }
+  }
+  row
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn run(started: i64, ended: Option<i64>, result: Option<&str>) -> Invocation {
+    Invocation {
+      id: started.to_string(),
+      started,
+      ended,
+      result: result.map(String::from),
+      exit_status: None,
+      reloads: vec![],
+    }
+  }
+
+  #[test]
+  fn test_lane() {
+    let runs = vec![run(0, Some(30), Some("exit-code")), run(50, None, None)];
+    let lane = lane(&runs, 0, 100, 10);
+    let strip: String = lane.iter().map(|s| s.symbol()).collect();
+    assert_eq!(strip, "┣━━✗·┣━━━━");
+    assert_eq!(column(100, 0, 100, 10), Some(9));
+    assert_eq!(column(-1, 0, 100, 10), None);
+  }
+
+  #[test]
+  fn test_markers() {
+    let row = markers(&[(15, '↻'), (99, '▼'), (500, '▼')], 0, 100, 10);
+    assert_eq!(row[1], Some('↻'));
+    assert_eq!(row[9], Some('▼'));
+    assert_eq!(row.iter().flatten().count(), 2);
+  }
+}
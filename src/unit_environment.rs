@@ -0,0 +1,250 @@
+// The environment a service's processes get, and overriding it. Processes start from the manager's environment (as
+// in `systemctl show-environment`), then `Environment=` settings, then `EnvironmentFile=` files, which win over
+// `Environment=`; `UnsetEnvironment=` removes names last. Overrides go in a drop-in of their own next to the unit,
+// so they're easy to find and take back out.
+
+use std::{
+  collections::{BTreeMap, HashMap},
+  path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+
+use crate::{
+  command_bar, remote, scaffold,
+  systemd::{self, UnitId},
+  user_manager,
+};
+
+/// Where a variable's value comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+  /// The manager's own environment
+  Manager,
+  /// An `Environment=` setting
+  Setting,
+  /// An `EnvironmentFile=`
+  File(String),
+}
+
+impl Source {
+  pub fn label(&self) -> String {
+    match self {
+      Source::Manager => "manager environment".into(),
+      Source::Setting => "Environment=".into(),
+      Source::File(path) => path.clone(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variable {
+  pub name: String,
+  pub value: String,
+  pub source: Source,
+  /// Sources it's also set in, whose values this one overrides
+  pub overrides: Vec<Source>,
+}
+
+#[derive(Debug, Default)]
+pub struct Effective {
+  /// By name
+  pub variables: Vec<Variable>,
+  /// What the running main process actually has, when it can be read
+  pub process: Option<HashMap<String, String>>,
+  /// Environment files that couldn't be read
+  pub errors: Vec<String>,
+}
+
+/// `NAME=value` as a pair
+fn assignment(line: &str) -> Option<(String, String)> {
+  let (name, value) = line.split_once('=')?;
+  Some((name.to_string(), value.to_string()))
+}
+
+/// The assignments of an `EnvironmentFile=`: `NAME=value` lines, where `#` and `;` start comments and values may
+/// be quoted
+pub fn parse_environment_file(contents: &str) -> Vec<(String, String)> {
+  contents
+    .lines()
+    .map(str::trim)
+    .filter(|l| !l.is_empty() && !l.starts_with('#') && !l.starts_with(';'))
+    .filter_map(|line| {
+      let (name, value) = line.trim_start_matches("export ").split_once('=')?;
+      let value = value.trim();
+      let unquoted = ['"', '\''].iter().find_map(|q| value.strip_prefix(*q)?.strip_suffix(*q)).unwrap_or(value);
+      Some((name.trim().to_string(), unquoted.to_string()))
+    })
+    .collect()
+}
+
+/// Layer the sources the way the manager does. `unset` entries are names, or `NAME=value` to only unset that value
+pub fn merge(
+  manager: &[String],
+  settings: &[String],
+  files: &[(String, Vec<(String, String)>)],
+  unset: &[String],
+) -> Vec<Variable> {
+  let mut variables: BTreeMap<String, Variable> = BTreeMap::new();
+  let mut set = |name: String, value: String, source: Source| match variables.get_mut(&name) {
+    Some(existing) => {
+      let overridden = std::mem::replace(&mut existing.source, source);
+      existing.overrides.push(overridden);
+      existing.value = value;
+    },
+    None => {
+      variables.insert(name.clone(), Variable { name, value, source, overrides: vec![] });
+    },
+  };
+  for (name, value) in manager.iter().filter_map(|l| assignment(l)) {
+    set(name, value, Source::Manager);
+  }
+  for (name, value) in settings.iter().filter_map(|l| assignment(l)) {
+    set(name, value, Source::Setting);
+  }
+  for (path, assignments) in files {
+    for (name, value) in assignments {
+      set(name.clone(), value.clone(), Source::File(path.clone()));
+    }
+  }
+  for entry in unset {
+    match assignment(entry) {
+      Some((name, value)) if variables.get(&name).is_some_and(|v| v.value == value) => variables.remove(&name),
+      Some(_) => None,
+      None => variables.remove(entry),
+    };
+  }
+  variables.into_values().collect()
+}
+
+/// The environment `unit`'s processes start with, and what its main process has if it's running here
+pub fn effective(unit: &UnitId) -> Result<Effective> {
+  let properties =
+    systemd::get_unit_property_lists(unit, &["Environment", "EnvironmentFiles", "UnsetEnvironment", "MainPID"])?;
+  let list = |name: &str| properties.get(name).cloned().unwrap_or_default();
+  // each is a space separated list, quoted where values have spaces
+  let words =
+    |name: &str| list(name).iter().flat_map(|v| command_bar::split(v).unwrap_or_default()).collect::<Vec<_>>();
+  let manager = systemd::get_manager_environment(unit.scope)?;
+
+  let mut effective = Effective::default();
+  let mut files = vec![];
+  // e.g. `/etc/default/ssh (ignore_errors=yes)`
+  for entry in list("EnvironmentFiles") {
+    let path = entry.split(" (").next().unwrap_or_default().to_string();
+    match remote::read_file(&path) {
+      Ok(contents) => files.push((path, parse_environment_file(&contents))),
+      Err(e) => effective.errors.push(format!("{e:#}")),
+    }
+  }
+  effective.variables = merge(&manager, &words("Environment"), &files, &words("UnsetEnvironment"));
+
+  let main_pid = list("MainPID").first().and_then(|p| p.parse::<u32>().ok()).unwrap_or(0);
+  if main_pid != 0 && remote::elsewhere().is_none() {
+    // only readable by root or the process's own user
+    if let Ok(environ) = std::fs::read(format!("/proc/{main_pid}/environ")) {
+      let environ = String::from_utf8_lossy(&environ);
+      effective.process = Some(environ.split('\0').filter_map(assignment).collect());
+    }
+  }
+  Ok(effective)
+}
+
+/// The drop-in overrides are written to
+pub fn drop_in_path(unit: &UnitId) -> Result<PathBuf> {
+  Ok(scaffold::unit_directory(unit.scope)?.join(format!("{}.d", unit.name)).join("environment.conf"))
+}
+
+/// The `Environment=` assignments in a drop-in written by `render_drop_in`
+pub fn parse_drop_in(contents: &str) -> IndexMap<String, String> {
+  contents
+    .lines()
+    .filter_map(|l| l.trim().strip_prefix("Environment=\"")?.strip_suffix('"'))
+    .filter_map(|quoted| {
+      let mut unescaped = String::new();
+      let mut chars = quoted.chars();
+      while let Some(c) = chars.next() {
+        unescaped.push(if c == '\\' { chars.next()? } else { c });
+      }
+      assignment(&unescaped.replace("%%", "%"))
+    })
+    .collect()
+}
+
+/// A drop-in setting `variables`, one quoted `Environment=` line each. `%` is doubled so systemd doesn't read
+/// specifiers
+pub fn render_drop_in(unit: &str, variables: &IndexMap<String, String>) -> String {
+  let mut contents = format!("# Environment overrides for {unit}, set from systemctl-tui\n[Service]\n");
+  for (name, value) in variables {
+    let escaped = format!("{name}={value}").replace('%', "%%").replace('\\', "\\\\").replace('"', "\\\"");
+    contents.push_str(&format!("Environment=\"{escaped}\"\n"));
+  }
+  contents
+}
+
+/// Apply `changes` to the overrides drop-in: `NAME=value` sets a variable, a bare `NAME` drops the override. The
+/// drop-in is removed once it overrides nothing. Returns the path, for the action log
+pub fn set_overrides(unit: &UnitId, changes: &[String]) -> Result<PathBuf> {
+  remote::ensure_local("Overriding the environment")?;
+  user_manager::ensure_own("Overriding the environment", unit.scope)?;
+  let path = drop_in_path(unit)?;
+  let existing = std::fs::read_to_string(&path).unwrap_or_default();
+  let mut variables = parse_drop_in(&existing);
+  for change in changes {
+    match assignment(change) {
+      Some((name, value)) => {
+        variables.insert(name, value);
+      },
+      None => {
+        variables.shift_remove(change);
+      },
+    }
+  }
+  if variables.is_empty() {
+    if path.exists() {
+      std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+    }
+    return Ok(path);
+  }
+  let directory = path.parent().context("Drop-in path has no directory")?;
+  std::fs::create_dir_all(directory).with_context(|| format!("Failed to create {}", directory.display()))?;
+  std::fs::write(&path, render_drop_in(&unit.name, &variables))
+    .with_context(|| format!("Failed to write {}", path.display()))?;
+  Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_merge() {
+    let manager = ["PATH=/usr/bin".to_string(), "LANG=C".to_string()];
+    let settings = ["LANG=en_US.UTF-8".to_string(), "PORT=8080".to_string(), "DEBUG=1".to_string()];
+    let file = parse_environment_file("# defaults\nPORT=\"9090\"\n\nexport OPTS='-v -x'\n");
+    let files = [("/etc/default/app".to_string(), file)];
+    let variables = merge(&manager, &settings, &files, &["DEBUG".to_string(), "PATH=/bin".to_string()]);
+
+    let names = variables.iter().map(|v| v.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["LANG", "OPTS", "PATH", "PORT"]);
+    assert_eq!(variables[0].source, Source::Setting);
+    assert_eq!(variables[0].overrides, [Source::Manager]);
+    assert_eq!(variables[1].value, "-v -x");
+    // an environment file wins over Environment=
+    assert_eq!(variables[3].value, "9090");
+    assert_eq!(variables[3].source, Source::File("/etc/default/app".into()));
+  }
+
+  #[test]
+  fn test_drop_in() {
+    let variables = IndexMap::from([
+      ("GREETING".to_string(), "hello \"world\"".to_string()),
+      ("RATE".to_string(), "50%".to_string()),
+      ("SEPARATOR".to_string(), "\\t".to_string()),
+    ]);
+    let contents = render_drop_in("app.service", &variables);
+    assert!(contents.contains("[Service]\nEnvironment=\"GREETING=hello \\\"world\\\"\"\nEnvironment=\"RATE=50%%\"\n"));
+    assert_eq!(parse_drop_in(&contents), variables);
+  }
+}
@@ -0,0 +1,233 @@
+// Units other than services and timers: sockets, mounts, paths, slices and scopes are listed too, and the unit list
+// can be narrowed to one type at a time. Types that aren't about running a process get details rows of their own in
+// place of resource use, like the addresses a socket listens on or what a mount mounts where.
+
+use std::collections::HashMap;
+
+/// The unit types, in the order the type filter cycles through them, with what to call a list of them
+pub const UNIT_TYPES: &[(&str, &str)] = &[
+  ("service", "Services"),
+  ("timer", "Timers"),
+  ("socket", "Sockets"),
+  ("path", "Paths"),
+  ("target", "Targets"),
+  ("mount", "Mounts"),
+  ("automount", "Automounts"),
+  ("swap", "Swaps"),
+  ("slice", "Slices"),
+  ("scope", "Scopes"),
+  ("device", "Devices"),
+];
+
+/// The units listed unless `--limit-units` says otherwise. Devices and targets are left out as too many to be useful
+pub const DEFAULT_PATTERNS: &[&str] =
+  &["*.service", "*.timer", "*.socket", "*.path", "*.mount", "*.automount", "*.slice", "*.scope"];
+
+/// `nginx.service` -> `service`
+pub fn unit_type(unit_name: &str) -> &str {
+  unit_name.rsplit_once('.').map_or("", |(_, suffix)| suffix)
+}
+
+/// `getty@tty1.service` -> `getty@.service`; `None` for units that aren't instances of a template
+pub fn template(unit_name: &str) -> Option<String> {
+  let (prefix, instance) = unit_name.split_once('@')?;
+  let (_, suffix) = instance.rsplit_once('.')?;
+  Some(format!("{prefix}@.{suffix}"))
+}
+
+/// `getty@.service` and `tty1` -> `getty@tty1.service`, with the instance escaped like `systemd-escape` does: `/`
+/// becomes `-`, and anything but letters, digits, `:`, `_` and (except first) `.` becomes `\xNN`. `None` for names that
+/// aren't templates
+pub fn instance(template: &str, instance: &str) -> Option<String> {
+  let (prefix, suffix) = template.split_once("@.")?;
+  let mut escaped = String::new();
+  for (i, byte) in instance.bytes().enumerate() {
+    match byte {
+      b'/' => escaped.push('-'),
+      b'.' if i > 0 => escaped.push('.'),
+      b if b.is_ascii_alphanumeric() || b == b':' || b == b'_' => escaped.push(b as char),
+      b => escaped.push_str(&format!("\\x{b:02x}")),
+    }
+  }
+  Some(format!("{prefix}@{escaped}.{suffix}"))
+}
+
+/// `timer` (or `.timer`) as one of `UNIT_TYPES`, `None` if it isn't one
+pub fn known(unit_type: &str) -> Option<&'static str> {
+  let unit_type = unit_type.trim_start_matches('.');
+  UNIT_TYPES.iter().map(|(t, _)| *t).find(|t| *t == unit_type)
+}
+
+/// `Sockets`, for the unit list title
+pub fn plural(unit_type: &str) -> Option<&'static str> {
+  UNIT_TYPES.iter().find(|(t, _)| *t == unit_type).map(|(_, plural)| *plural)
+}
+
+/// The type to show after `current`, out of those `loaded` has: every type, then each of them in turn
+pub fn next_type_filter<'a>(current: Option<&str>, loaded: impl IntoIterator<Item = &'a str>) -> Option<&'static str> {
+  let loaded: Vec<&str> = loaded.into_iter().collect();
+  let start = current.and_then(|c| UNIT_TYPES.iter().position(|(t, _)| *t == c)).map_or(0, |i| i + 1);
+  UNIT_TYPES[start..].iter().map(|(t, _)| *t).find(|t| loaded.contains(t))
+}
+
+/// A details row shown for some types of unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+  Listen,
+  Connections,
+  Source,
+  Where,
+  Filesystem,
+  Options,
+  IdleTimeout,
+  Priority,
+  Watches,
+  Sysfs,
+}
+
+impl Field {
+  pub fn label(self) -> &'static str {
+    match self {
+      Field::Listen => "Listen: ",
+      Field::Connections => "Connections: ",
+      Field::Source => "Source: ",
+      Field::Where => "Where: ",
+      Field::Filesystem => "Filesystem: ",
+      Field::Options => "Options: ",
+      Field::IdleTimeout => "Idle timeout: ",
+      Field::Priority => "Priority: ",
+      Field::Watches => "Watches: ",
+      Field::Sysfs => "Sysfs path: ",
+    }
+  }
+
+  fn properties(self) -> &'static [&'static str] {
+    match self {
+      Field::Listen => &["Listen"],
+      Field::Connections => &["NConnections", "NAccepted", "NRefused"],
+      Field::Source => &["What"],
+      Field::Where => &["Where"],
+      Field::Filesystem => &["Type"],
+      Field::Options => &["Options"],
+      Field::IdleTimeout => &["TimeoutIdleUSec"],
+      Field::Priority => &["Priority"],
+      Field::Watches => &["Paths"],
+      Field::Sysfs => &["SysFSPath"],
+    }
+  }
+
+  /// The row's text, from every value of each property as `systemctl show` prints them
+  fn value(self, values: &HashMap<String, Vec<String>>) -> String {
+    let get = |property: &str| values.get(property).map(|v| v.join(", ")).unwrap_or_default();
+    match self {
+      Field::Connections => {
+        let mut text = format!("{} open, {} accepted", get("NConnections"), get("NAccepted"));
+        let refused = get("NRefused");
+        if !refused.is_empty() && refused != "0" {
+          text.push_str(&format!(", {refused} refused"));
+        }
+        text
+      },
+      Field::IdleTimeout => match get("TimeoutIdleUSec").as_str() {
+        "0" | "infinity" => "never unmounts".into(),
+        timeout => format!("unmounts after {timeout}"),
+      },
+      field => get(field.properties()[0]),
+    }
+  }
+}
+
+/// The details rows shown for units of a type in place of resource use. Empty for types that run processes
+pub fn fields(unit_type: &str) -> &'static [Field] {
+  match unit_type {
+    "socket" => &[Field::Listen, Field::Connections],
+    "mount" => &[Field::Source, Field::Where, Field::Filesystem, Field::Options],
+    "automount" => &[Field::Where, Field::IdleTimeout],
+    "swap" => &[Field::Source, Field::Priority, Field::Options],
+    "path" => &[Field::Watches],
+    "device" => &[Field::Sysfs],
+    _ => &[],
+  }
+}
+
+/// The properties to fetch for a type's details rows
+pub fn properties(unit_type: &str) -> Vec<&'static str> {
+  fields(unit_type).iter().flat_map(|f| f.properties()).copied().collect()
+}
+
+/// The text of each of a type's details rows, in order
+pub fn values(unit_type: &str, values: &HashMap<String, Vec<String>>) -> Vec<String> {
+  fields(unit_type).iter().map(|f| f.value(values)).collect()
+}
+
+/// What `systemctl clean --what=` calls each kind of directory a unit can have, the property listing them, and the key
+/// picking it in the clean prompt
+pub const CLEAN_KINDS: &[(&str, &str, char)] = &[
+  ("cache", "CacheDirectory", 'c'),
+  ("state", "StateDirectory", 's'),
+  ("logs", "LogsDirectory", 'l'),
+  ("runtime", "RuntimeDirectory", 'r'),
+  ("configuration", "ConfigurationDirectory", 'o'),
+];
+
+/// Whether `systemctl clean` works on units like this: the types that run processes and so can have these directories
+pub fn can_clean(unit_name: &str) -> bool {
+  matches!(unit_type(unit_name), "service" | "socket" | "mount" | "swap")
+}
+
+pub fn clean_properties() -> Vec<&'static str> {
+  CLEAN_KINDS.iter().map(|(_, property, _)| *property).collect()
+}
+
+/// Each kind of directory the unit has, with its directories, e.g. `("state", "mysql mysql-files")`
+pub fn clean_targets(properties: &HashMap<String, String>) -> Vec<(&'static str, String)> {
+  CLEAN_KINDS
+    .iter()
+    .filter_map(|(what, property, _)| {
+      let directories = properties.get(*property)?.trim();
+      (!directories.is_empty()).then(|| (*what, directories.to_string()))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_next_type_filter() {
+    let loaded = ["scope", "service", "socket", "service"];
+    assert_eq!(next_type_filter(None, loaded), Some("service"));
+    assert_eq!(next_type_filter(Some("service"), loaded), Some("socket"));
+    assert_eq!(next_type_filter(Some("socket"), loaded), Some("scope"));
+    assert_eq!(next_type_filter(Some("scope"), loaded), None);
+    // a filter whose units have all gone
+    assert_eq!(next_type_filter(Some("timer"), loaded), Some("socket"));
+    assert_eq!(unit_type("getty@tty1.service"), "service");
+    assert_eq!(plural("automount"), Some("Automounts"));
+    assert_eq!(template("getty@tty1.service").as_deref(), Some("getty@.service"));
+    assert_eq!(template("systemd-fsck@dev-disk-by\\x2duuid-1234.service").as_deref(), Some("systemd-fsck@.service"));
+    assert_eq!(template("nginx.service"), None);
+    assert_eq!(instance("getty@.service", "tty1").as_deref(), Some("getty@tty1.service"));
+    assert_eq!(instance("container@.service", "web-1/a b").as_deref(), Some("container@web\\x2d1-a\\x20b.service"));
+    assert_eq!(instance("foo@.service", ".hidden").as_deref(), Some("foo@\\x2ehidden.service"));
+    assert_eq!(instance("nginx.service", "x"), None);
+  }
+
+  #[test]
+  fn test_values() {
+    let output = "Listen=[::]:22 (Stream)\nListen=/run/ssh.sock (Stream)\nNConnections=2\nNAccepted=40\nNRefused=0\n";
+    let lists = crate::systemd::parse_property_lists(output);
+    assert_eq!(properties("socket"), ["Listen", "NConnections", "NAccepted", "NRefused"]);
+    assert_eq!(values("socket", &lists), ["[::]:22 (Stream), /run/ssh.sock (Stream)", "2 open, 40 accepted"]);
+    assert!(fields("slice").is_empty());
+  }
+
+  #[test]
+  fn test_clean_targets() {
+    let properties =
+      crate::systemd::parse_properties("CacheDirectory=\nStateDirectory=mysql mysql-files\nLogsDirectory=mysql\n");
+    assert_eq!(clean_targets(&properties), [("state", "mysql mysql-files".to_string()), ("logs", "mysql".to_string())]);
+    assert!(can_clean("mysql.service") && !can_clean("mysql.timer"));
+  }
+}
@@ -1,22 +1,31 @@
-use std::path::PathBuf;
+use std::{
+  fmt::Write,
+  path::PathBuf,
+  sync::{Arc, Mutex},
+  time::{Duration, Instant},
+};
 
 use anyhow::{anyhow, Context, Result};
 use better_panic::Settings;
 use directories::ProjectDirs;
-use tracing::{error, level_filters::LevelFilter};
+use tracing::{debug, error, level_filters::LevelFilter, span, warn, Subscriber};
 use tracing_appender::{
   non_blocking::WorkerGuard,
   rolling::{RollingFileAppender, Rotation},
 };
 use tracing_subscriber::{
-  self, filter::EnvFilter, prelude::__tracing_subscriber_SubscriberExt, util::SubscriberInitExt, Layer,
+  self, filter::EnvFilter, layer::Context as LayerContext, prelude::__tracing_subscriber_SubscriberExt,
+  registry::LookupSpan, util::SubscriberInitExt, Layer,
 };
 
+pub use crate::remote::shell_quote;
+
 pub fn initialize_panic_handler() {
   std::panic::set_hook(Box::new(|panic_info| {
     if let Err(r) = crate::terminal::exit() {
       error!("Unable to exit Terminal: {r:?}");
     }
+    crate::control::remove_socket();
 
     Settings::auto().most_recent_first(false).lineno_suffix(true).create_panic_handler()(panic_info);
     std::process::exit(libc::EXIT_FAILURE);
@@ -45,7 +54,125 @@ pub fn get_config_dir() -> Result<PathBuf> {
   Ok(directory)
 }
 
-pub fn initialize_logging(enable_file_logging: bool) -> Result<Option<WorkerGuard>> {
+/// Target for the spans `--profile-startup` records, e.g. `info_span!(target: STARTUP_TARGET, "first_render")`
+pub const STARTUP_TARGET: &str = "startup";
+
+/// Target for how long frames and actions take, e.g. `RUST_LOG=perf=debug` to see every one
+pub const PERF_TARGET: &str = "perf";
+
+/// A frame at 60 per second. Drawing or handling an action that takes longer is visible as a stall
+pub const FRAME_BUDGET: Duration = Duration::from_millis(16);
+
+/// Log how long `event` (like `frame` or `dispatch`) took, warning when it went over the frame budget
+pub fn log_perf_event(event: &str, took: Duration) {
+  if took > FRAME_BUDGET {
+    warn!(target: PERF_TARGET, "{event} took {took:?}, over the {FRAME_BUDGET:?} frame budget");
+  } else {
+    debug!(target: PERF_TARGET, "{event} took {took:?}");
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProfiledSpan {
+  pub name: &'static str,
+  /// Since the profile was created
+  pub start: Duration,
+  pub duration: Duration,
+}
+
+/// Timings of the startup spans, collected by a tracing layer and reported when the app exits
+#[derive(Clone)]
+pub struct StartupProfile {
+  origin: Instant,
+  spans: Arc<Mutex<Vec<ProfiledSpan>>>,
+}
+
+impl Default for StartupProfile {
+  fn default() -> Self {
+    Self { origin: Instant::now(), spans: Default::default() }
+  }
+}
+
+impl StartupProfile {
+  pub fn spans(&self) -> Vec<ProfiledSpan> {
+    self.spans.lock().unwrap().clone()
+  }
+
+  /// A table of span timings, for printing after the terminal has been restored
+  pub fn summary(&self) -> String {
+    let mut summary = format!("{:<20} {:>10} {:>10}\n", "span", "start", "duration");
+    for span in self.spans() {
+      let _ = writeln!(
+        summary,
+        "{:<20} {:>7.1} ms {:>7.1} ms",
+        span.name,
+        span.start.as_secs_f64() * 1000.0,
+        span.duration.as_secs_f64() * 1000.0
+      );
+    }
+    summary
+  }
+
+  /// The spans in Chrome's trace event format, which Perfetto (https://ui.perfetto.dev) opens directly
+  pub fn trace_json(&self) -> String {
+    let events = self
+      .spans()
+      .iter()
+      .map(|s| {
+        format!(
+          r#"{{"name":"{}","ph":"X","ts":{},"dur":{},"pid":1,"tid":1}}"#,
+          s.name,
+          s.start.as_micros(),
+          s.duration.as_micros()
+        )
+      })
+      .collect::<Vec<_>>();
+    format!(r#"{{"traceEvents":[{}]}}"#, events.join(","))
+  }
+
+  /// Write the trace to the data directory and return its path
+  pub fn write_trace(&self) -> Result<PathBuf> {
+    let directory = get_data_dir()?;
+    std::fs::create_dir_all(&directory).context(format!("{directory:?} could not be created"))?;
+    let path = directory.join("startup-trace.json");
+    std::fs::write(&path, self.trace_json()).context(format!("{path:?} could not be written"))?;
+    Ok(path)
+  }
+}
+
+struct SpanStart(Instant);
+
+impl<S> Layer<S> for StartupProfile
+where
+  S: Subscriber + for<'a> LookupSpan<'a>,
+{
+  fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: LayerContext<'_, S>) {
+    if attrs.metadata().target() == STARTUP_TARGET {
+      if let Some(span) = ctx.span(id) {
+        span.extensions_mut().insert(SpanStart(Instant::now()));
+      }
+    }
+  }
+
+  fn on_close(&self, id: span::Id, ctx: LayerContext<'_, S>) {
+    let Some(span) = ctx.span(&id) else { return };
+    let Some(SpanStart(start)) = span.extensions().get::<SpanStart>().map(|s| SpanStart(s.0)) else { return };
+    let mut spans = self.spans.lock().unwrap();
+    // only the first of each (e.g. the first render) is interesting
+    if !spans.iter().any(|s| s.name == span.name()) {
+      spans.push(ProfiledSpan {
+        name: span.name(),
+        start: start.duration_since(self.origin),
+        duration: start.elapsed(),
+      });
+    }
+  }
+}
+
+pub fn initialize_logging(
+  enable_file_logging: bool,
+  startup_profile: Option<StartupProfile>,
+) -> Result<Option<WorkerGuard>> {
   let mut guard = None;
 
   let file_layer = if enable_file_logging {
@@ -83,7 +210,7 @@ pub fn initialize_logging(enable_file_logging: bool) -> Result<Option<WorkerGuar
   let tui_layer = tui_logger::TuiTracingSubscriberLayer
     .with_filter(EnvFilter::builder().with_default_directive(LevelFilter::INFO.into()).from_env_lossy());
 
-  tracing_subscriber::registry().with(file_layer).with(tui_layer).init();
+  tracing_subscriber::registry().with(file_layer).with(tui_layer).with(startup_profile).init();
 
   Ok(guard)
 }